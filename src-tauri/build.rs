@@ -1,3 +1,46 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Collect `relative/path -> sha256` for every file under `dir`.
+fn hash_dir(root: &Path, dir: &Path, out: &mut BTreeMap<String, String>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            hash_dir(root, &path, out);
+        } else {
+            let bytes = fs::read(&path)
+                .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+            let hash: String = Sha256::digest(&bytes)
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            let rel: Vec<String> = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            out.insert(rel.join("/"), hash);
+        }
+    }
+}
+
 fn main() {
+    // Embed a checksum manifest of the bundled templates so extracted copies can be verified
+    let templates = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates");
+    println!("cargo:rerun-if-changed=templates");
+    let mut manifest = BTreeMap::new();
+    hash_dir(&templates, &templates, &mut manifest);
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("templates_manifest.json");
+    fs::write(out, serde_json::to_string(&manifest).unwrap()).unwrap();
+
     tauri_build::build()
 }
@@ -0,0 +1,438 @@
+//! AWS CLI shared config (`~/.aws/config`) and credentials
+//! (`~/.aws/credentials`) parsing.
+//!
+//! Both files are INI: `[section]` headers followed by `key = value` lines,
+//! with `#` / `;` comment lines. In the config file, profiles are
+//! `[default]` and `[profile <name>]`, and SSO settings shared between
+//! profiles live in `[sso-session <name>]`. In the credentials file every
+//! section is a profile. Indented lines under a key with an empty value
+//! (e.g. `s3 =` followed by `  max_concurrent_requests = 20`) are nested
+//! settings and are ignored here. `AWS_CONFIG_FILE` and
+//! `AWS_SHARED_CREDENTIALS_FILE` override the default locations, as in the CLI.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+type Section = BTreeMap<String, String>;
+
+/// How a profile obtains credentials, in the order the CLI tries them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AwsProfileKind {
+    /// `role_arn` with `web_identity_token_file`.
+    WebIdentity,
+    /// `role_arn` with `source_profile` or `credential_source`.
+    AssumeRole,
+    /// IAM Identity Center, via `sso_session` or legacy `sso_start_url`.
+    Sso,
+    /// Access keys in the credentials or config file.
+    Static,
+    CredentialProcess,
+    /// Nothing configured: the CLI falls back to environment or instance credentials.
+    Other,
+}
+
+/// A profile with what it will do when used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AwsProfile {
+    pub name: String,
+    pub is_sso: bool,
+    pub kind: AwsProfileKind,
+    pub region: Option<String>,
+    pub sso_session: Option<String>,
+    pub sso_start_url: Option<String>,
+    pub sso_account_id: Option<String>,
+    pub sso_role_name: Option<String>,
+    pub role_arn: Option<String>,
+    pub source_profile: Option<String>,
+    pub credential_source: Option<String>,
+    pub credential_process: Option<String>,
+    /// One-line explanation for the UI.
+    pub description: String,
+    /// Configuration problems that will make the profile fail.
+    pub warnings: Vec<String>,
+}
+
+/// An `[sso-session]` block and the profiles that sign in through it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AwsSsoSession {
+    pub name: String,
+    pub sso_start_url: Option<String>,
+    pub sso_region: Option<String>,
+    pub sso_registration_scopes: Option<String>,
+    pub profiles: Vec<String>,
+}
+
+/// Parsed config and credentials files.
+#[derive(Debug, Default)]
+pub struct AwsConfig {
+    profiles: BTreeMap<String, Section>,
+    sso_sessions: BTreeMap<String, Section>,
+    credentials: BTreeMap<String, Section>,
+}
+
+// ─── Parsing ────────────────────────────────────────────────────────────────
+
+/// Sections of an INI file, keyed by their header with whitespace collapsed.
+/// Repeated sections are merged; later keys win.
+fn parse_ini(content: &str) -> BTreeMap<String, Section> {
+    let mut sections: BTreeMap<String, Section> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    let mut nested = false;
+    for raw in content.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+            sections.entry(header.clone()).or_default();
+            current = Some(header);
+            nested = false;
+            continue;
+        }
+        if nested && raw.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let (Some(section), Some((key, value))) = (&current, line.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim();
+        nested = value.is_empty();
+        if !nested {
+            if let Some(entries) = sections.get_mut(section) {
+                entries.insert(key.trim().to_string(), value.to_string());
+            }
+        }
+    }
+    sections
+}
+
+impl AwsConfig {
+    pub fn parse(config: &str, credentials: &str) -> Self {
+        let mut parsed = AwsConfig::default();
+        for (header, section) in parse_ini(config) {
+            if header == "default" {
+                parsed.profiles.insert(header, section);
+            } else if let Some(name) = header.strip_prefix("profile ") {
+                parsed.profiles.insert(name.to_string(), section);
+            } else if let Some(name) = header.strip_prefix("sso-session ") {
+                parsed.sso_sessions.insert(name.to_string(), section);
+            }
+        }
+        parsed.credentials = parse_ini(credentials);
+        parsed
+    }
+
+    /// Read the files the AWS CLI would. Missing files are treated as empty.
+    pub fn load() -> Self {
+        let read = |path: Option<PathBuf>| path.and_then(|p| fs::read_to_string(p).ok()).unwrap_or_default();
+        Self::parse(&read(config_path()), &read(credentials_path()))
+    }
+
+    /// Profile names from both files, `default` first.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().chain(self.credentials.keys()).cloned().collect();
+        names.sort_by(|a, b| (a != "default", a).cmp(&(b != "default", b)));
+        names.dedup();
+        names
+    }
+
+    fn get(&self, profile: &str, key: &str) -> Option<String> {
+        self.profiles
+            .get(profile)
+            .and_then(|s| s.get(key))
+            .filter(|v| !v.is_empty())
+            .cloned()
+    }
+
+    /// SSO start URL for a profile, directly or through its `sso_session`.
+    pub fn sso_start_url(&self, profile: &str) -> Option<String> {
+        self.get(profile, "sso_start_url").or_else(|| {
+            let session = self.get(profile, "sso_session")?;
+            self.sso_sessions.get(&session)?.get("sso_start_url").cloned()
+        })
+    }
+
+    fn has_static_keys(&self, profile: &str) -> bool {
+        let in_credentials = self
+            .credentials
+            .get(profile)
+            .is_some_and(|s| s.get("aws_access_key_id").is_some_and(|v| !v.is_empty()));
+        in_credentials || self.get(profile, "aws_access_key_id").is_some()
+    }
+
+    /// Metadata for one profile, or `None` if neither file defines it.
+    pub fn profile(&self, name: &str) -> Option<AwsProfile> {
+        if !self.profiles.contains_key(name) && !self.credentials.contains_key(name) {
+            return None;
+        }
+        let role_arn = self.get(name, "role_arn");
+        let source_profile = self.get(name, "source_profile");
+        let credential_source = self.get(name, "credential_source");
+        let credential_process = self.get(name, "credential_process");
+        let sso_session = self.get(name, "sso_session");
+        let sso_start_url = self.sso_start_url(name);
+        let mut warnings = Vec::new();
+
+        if let Some(session) = &sso_session {
+            if !self.sso_sessions.contains_key(session) {
+                warnings.push(format!("sso-session '{}' is not defined", session));
+            }
+        }
+
+        let kind = if role_arn.is_some() && self.get(name, "web_identity_token_file").is_some() {
+            AwsProfileKind::WebIdentity
+        } else if role_arn.is_some() {
+            AwsProfileKind::AssumeRole
+        } else if sso_session.is_some() || sso_start_url.is_some() {
+            AwsProfileKind::Sso
+        } else if self.has_static_keys(name) {
+            AwsProfileKind::Static
+        } else if credential_process.is_some() {
+            AwsProfileKind::CredentialProcess
+        } else {
+            AwsProfileKind::Other
+        };
+
+        let description = match kind {
+            AwsProfileKind::WebIdentity => {
+                format!("Assumes {} with a web identity token", role_arn.as_deref().unwrap_or_default())
+            }
+            AwsProfileKind::AssumeRole => {
+                let role = role_arn.as_deref().unwrap_or_default();
+                match (&source_profile, &credential_source) {
+                    (Some(source), _) => {
+                        if let Some(problem) = self.source_chain_problem(name) {
+                            warnings.push(problem);
+                        }
+                        format!("Assumes {} using credentials from profile '{}'", role, source)
+                    }
+                    (None, Some(source)) => format!("Assumes {} using {} credentials", role, source),
+                    (None, None) => {
+                        warnings.push("role_arn needs source_profile or credential_source".to_string());
+                        format!("Assumes {}", role)
+                    }
+                }
+            }
+            AwsProfileKind::Sso => match (&sso_start_url, self.get(name, "sso_account_id")) {
+                (Some(url), Some(account)) => format!("Signs in through IAM Identity Center ({}) to account {}", url, account),
+                (Some(url), None) => format!("Signs in through IAM Identity Center ({})", url),
+                (None, _) => "Signs in through IAM Identity Center".to_string(),
+            },
+            AwsProfileKind::Static => "Uses access keys stored in the AWS credentials file".to_string(),
+            AwsProfileKind::CredentialProcess => {
+                let program = credential_process.as_deref().unwrap_or_default().split_whitespace().next().unwrap_or_default();
+                format!("Runs '{}' to obtain credentials", program)
+            }
+            AwsProfileKind::Other => "No credentials configured; falls back to environment or instance credentials".to_string(),
+        };
+
+        Some(AwsProfile {
+            name: name.to_string(),
+            is_sso: kind == AwsProfileKind::Sso,
+            kind,
+            region: self.get(name, "region"),
+            sso_session,
+            sso_start_url,
+            sso_account_id: self.get(name, "sso_account_id"),
+            sso_role_name: self.get(name, "sso_role_name"),
+            role_arn,
+            source_profile,
+            credential_source,
+            credential_process,
+            description,
+            warnings,
+        })
+    }
+
+    /// Why a `source_profile` chain can't provide credentials, if it can't.
+    fn source_chain_problem(&self, name: &str) -> Option<String> {
+        let mut seen = vec![name.to_string()];
+        let mut current = name.to_string();
+        while let Some(source) = self.get(&current, "source_profile") {
+            if !self.profiles.contains_key(&source) && !self.credentials.contains_key(&source) {
+                return Some(format!("source_profile '{}' is not defined", source));
+            }
+            // A profile may use its own static keys as the source
+            if source == current && self.has_static_keys(&source) {
+                return None;
+            }
+            if seen.contains(&source) {
+                return Some(format!("source_profile chain loops back to '{}'", source));
+            }
+            seen.push(source.clone());
+            current = source;
+        }
+        None
+    }
+
+    /// `sso-session` a profile signs in through, if any.
+    pub fn sso_session_of(&self, profile: &str) -> Option<String> {
+        self.get(profile, "sso_session")
+    }
+
+    /// All `[sso-session]` blocks, by name.
+    pub fn sso_sessions(&self) -> Vec<AwsSsoSession> {
+        self.sso_sessions
+            .iter()
+            .map(|(name, section)| AwsSsoSession {
+                name: name.clone(),
+                sso_start_url: section.get("sso_start_url").cloned(),
+                sso_region: section.get("sso_region").cloned(),
+                sso_registration_scopes: section.get("sso_registration_scopes").cloned(),
+                profiles: self
+                    .profile_names()
+                    .into_iter()
+                    .filter(|profile| self.get(profile, "sso_session").as_ref() == Some(name))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// All profiles with metadata, `default` first.
+    pub fn profiles(&self) -> Vec<AwsProfile> {
+        self.profile_names().iter().filter_map(|name| self.profile(name)).collect()
+    }
+}
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    env_path("AWS_CONFIG_FILE").or_else(|| Some(dirs::home_dir()?.join(".aws").join("config")))
+}
+
+pub fn credentials_path() -> Option<PathBuf> {
+    env_path("AWS_SHARED_CREDENTIALS_FILE").or_else(|| Some(dirs::home_dir()?.join(".aws").join("credentials")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# comment
+[default]
+region = us-east-1
+
+[profile dev]
+sso_session = corp
+sso_account_id = 111122223333
+sso_role_name = Admin
+region = eu-west-1
+s3 =
+  max_concurrent_requests = 20
+
+[profile prod]
+sso_start_url = https://legacy.awsapps.com/start
+
+[sso-session corp]
+sso_start_url = https://corp.awsapps.com/start/
+sso_region = us-east-1
+
+[profile deployer]
+role_arn = arn:aws:iam::222233334444:role/Deployer
+source_profile = base
+
+[profile broken]
+role_arn = arn:aws:iam::222233334444:role/Deployer
+source_profile = nowhere
+
+[profile ec2]
+role_arn = arn:aws:iam::222233334444:role/Deployer
+credential_source = Ec2InstanceMetadata
+
+[profile vault]
+credential_process = /usr/local/bin/vault-aws --role x
+
+[services local]
+s3 =
+  endpoint_url = http://localhost:4566
+
+[not-a-profile]
+region = us-west-2
+";
+
+    const CREDENTIALS: &str = "\
+[default]
+aws_access_key_id = AKIAEXAMPLE
+aws_secret_access_key = secret
+
+[base]
+aws_access_key_id = AKIABASE
+aws_secret_access_key = secret
+";
+
+    #[test]
+    fn lists_profiles_from_both_files() {
+        let config = AwsConfig::parse(CONFIG, CREDENTIALS);
+        assert_eq!(
+            config.profile_names(),
+            ["default", "base", "broken", "deployer", "dev", "ec2", "prod", "vault"]
+        );
+    }
+
+    #[test]
+    fn describes_each_profile_kind() {
+        let config = AwsConfig::parse(CONFIG, CREDENTIALS);
+        let profile = |name: &str| config.profile(name).unwrap();
+
+        let default = profile("default");
+        assert_eq!((default.kind, default.region.as_deref()), (AwsProfileKind::Static, Some("us-east-1")));
+
+        // Each SSO profile resolves its own start URL
+        let dev = profile("dev");
+        assert!(dev.is_sso);
+        assert_eq!(dev.sso_start_url.as_deref(), Some("https://corp.awsapps.com/start/"));
+        assert_eq!(dev.region.as_deref(), Some("eu-west-1"));
+        assert!(dev.description.contains("111122223333"));
+        assert_eq!(profile("prod").sso_start_url.as_deref(), Some("https://legacy.awsapps.com/start"));
+
+        let deployer = profile("deployer");
+        assert_eq!(deployer.kind, AwsProfileKind::AssumeRole);
+        assert!(!deployer.is_sso && deployer.warnings.is_empty());
+        assert!(deployer.description.contains("profile 'base'"));
+        assert_eq!(profile("broken").warnings, vec!["source_profile 'nowhere' is not defined".to_string()]);
+        assert!(profile("ec2").description.contains("Ec2InstanceMetadata"));
+
+        let vault = profile("vault");
+        assert_eq!(vault.kind, AwsProfileKind::CredentialProcess);
+        assert_eq!(vault.description, "Runs '/usr/local/bin/vault-aws' to obtain credentials");
+
+        assert!(config.profile("local").is_none());
+        assert!(config.profile("not-a-profile").is_none());
+    }
+
+    #[test]
+    fn groups_profiles_by_sso_session() {
+        let config = AwsConfig::parse(
+            &format!("{}\n[profile dev-readonly]\nsso_session = corp\nsso_role_name = ReadOnly\n", CONFIG),
+            CREDENTIALS,
+        );
+        let sessions = config.sso_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "corp");
+        assert_eq!(sessions[0].sso_region.as_deref(), Some("us-east-1"));
+        assert_eq!(sessions[0].profiles, ["dev", "dev-readonly"]);
+        assert_eq!(config.sso_session_of("dev-readonly").as_deref(), Some("corp"));
+        assert_eq!(config.sso_session_of("prod"), None);
+
+        let missing = AwsConfig::parse("[profile x]\nsso_session = gone\nsso_account_id = 1\n", "");
+        let profile = missing.profile("x").unwrap();
+        assert!(profile.is_sso);
+        assert_eq!(profile.warnings, vec!["sso-session 'gone' is not defined".to_string()]);
+    }
+
+    #[test]
+    fn detects_source_profile_loops() {
+        let config = AwsConfig::parse(
+            "[profile a]\nrole_arn = arn:aws:iam::1:role/A\nsource_profile = b\n[profile b]\nrole_arn = arn:aws:iam::1:role/B\nsource_profile = a\n",
+            "",
+        );
+        assert!(config.profile("a").unwrap().warnings[0].contains("loops"));
+    }
+}
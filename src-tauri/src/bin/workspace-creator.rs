@@ -0,0 +1,6 @@
+//! Headless CLI for running deployments without the desktop UI.
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(databricks_deployer_lib::cli::run(args));
+}
@@ -0,0 +1,423 @@
+//! Headless command-line front end (`workspace-creator` binary).
+//!
+//! Runs the same save/plan/apply/destroy logic as the desktop app without
+//! Tauri, so deployments can be driven from CI or over SSH. Storage defaults to
+//! the desktop app's data directory, so both share templates and deployments.
+
+use crate::commands::rollback::RollbackConfirmation;
+use crate::commands::{self, CloudCredentials};
+use crate::storage::{Environment, StoragePaths};
+use crate::terraform::{self, DeploymentStatus};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const USAGE: &str = "\
+Usage: workspace-creator [--data-dir <dir>] <command> [options]
+
+Commands:
+  templates                      List available templates
+  save <deployment>              Copy a template and write terraform.tfvars
+      --template <id>              Template to deploy (required)
+      --values <file.json>         JSON object of variable values
+      --var <name=value>           Variable value (repeatable; JSON or plain string)
+      --credentials <file.json>    Credentials (same fields as the desktop app)
+  init|plan|apply|destroy <deployment>
+      --cloud <aws|azure|gcp>      Read credentials from the environment
+      --credentials <file.json>    Credentials file (overrides --cloud)
+      --confirm-delete <kind>      Let destroy delete resources holding data
+                                   (storage, metastore or encryption-keys; repeatable)
+  status <deployment>            Show engine, state and expiry for a deployment
+
+The data directory defaults to the desktop app's (override with --data-dir or
+WORKSPACE_CREATOR_DATA_DIR).
+";
+
+/// Parsed command line.
+#[derive(Debug, Default, PartialEq)]
+struct CliArgs {
+    command: String,
+    deployment: Option<String>,
+    data_dir: Option<PathBuf>,
+    template: Option<String>,
+    values_file: Option<PathBuf>,
+    vars: Vec<(String, String)>,
+    credentials_file: Option<PathBuf>,
+    cloud: Option<String>,
+    confirm_delete: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} requires a value", flag))
+        };
+        match arg.as_str() {
+            "--data-dir" => parsed.data_dir = Some(PathBuf::from(value(arg)?)),
+            "--template" => parsed.template = Some(value(arg)?),
+            "--values" => parsed.values_file = Some(PathBuf::from(value(arg)?)),
+            "--credentials" => parsed.credentials_file = Some(PathBuf::from(value(arg)?)),
+            "--cloud" => parsed.cloud = Some(value(arg)?),
+            "--confirm-delete" => parsed.confirm_delete.push(value(arg)?),
+            "--var" => {
+                let raw = value(arg)?;
+                let (name, val) = raw
+                    .split_once('=')
+                    .ok_or_else(|| format!("--var expects name=value, got '{}'", raw))?;
+                parsed.vars.push((name.trim().to_string(), val.to_string()));
+            }
+            "-h" | "--help" => positional.insert(0, "help".to_string()),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    parsed.command = positional.next().unwrap_or_else(|| "help".to_string());
+    parsed.deployment = positional.next();
+    if let Some(extra) = positional.next() {
+        return Err(format!("Unexpected argument: {}", extra));
+    }
+    Ok(parsed)
+}
+
+/// `--var` values are JSON when they parse as JSON (`true`, `3`, `{"a":1}`),
+/// otherwise plain strings.
+fn parse_var_value(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
+fn read_json_file<T: serde::de::DeserializeOwned>(path: &Path, what: &str) -> Result<T, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {} {}: {}", what, path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {} {}: {}", what, path.display(), e))
+}
+
+fn collect_values(args: &CliArgs) -> Result<HashMap<String, serde_json::Value>, String> {
+    let mut values: HashMap<String, serde_json::Value> = match &args.values_file {
+        Some(path) => read_json_file(path, "values file")?,
+        None => HashMap::new(),
+    };
+    for (name, raw) in &args.vars {
+        values.insert(name.clone(), parse_var_value(raw));
+    }
+    Ok(values)
+}
+
+fn load_credentials(args: &CliArgs) -> Result<Option<CloudCredentials>, String> {
+    if let Some(path) = &args.credentials_file {
+        return read_json_file(path, "credentials file").map(Some);
+    }
+    match &args.cloud {
+        Some(cloud) => commands::get_cloud_credentials(cloud.clone()).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Data-bearing resource kinds `destroy` may delete, from `--confirm-delete`.
+fn delete_confirmation(args: &CliArgs) -> Result<RollbackConfirmation, String> {
+    let mut confirmation = RollbackConfirmation::default();
+    for kind in &args.confirm_delete {
+        match kind.as_str() {
+            "storage" => confirmation.storage = true,
+            "metastore" => confirmation.metastore = true,
+            "encryption-keys" => confirmation.encryption_keys = true,
+            other => {
+                return Err(format!(
+                    "Unknown --confirm-delete kind '{}': expected storage, metastore or encryption-keys",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(confirmation)
+}
+
+fn require_deployment(args: &CliArgs) -> Result<&str, String> {
+    args.deployment
+        .as_deref()
+        .ok_or_else(|| format!("'{}' requires a deployment name", args.command))
+}
+
+fn deployment_dir(paths: &StoragePaths, name: &str) -> Result<PathBuf, String> {
+    let dir = paths
+        .deployments_dir()?
+        .join(commands::sanitize_deployment_name(name)?);
+    if !dir.exists() {
+        return Err(format!("Deployment '{}' not found. Run 'save' first.", name));
+    }
+    Ok(dir)
+}
+
+/// Run one Terraform command, streaming its output to this process'
+/// stdout/stderr and appending it to `output` for the audit record.
+fn run_streaming(
+    command: &str,
+    dir: &Path,
+    env_vars: HashMap<String, String>,
+    output: &mut String,
+) -> Result<bool, String> {
+    let mut child = terraform::run_terraform(command, dir, env_vars)?;
+
+    let out = child.stdout.take().map(|out| {
+        std::thread::spawn(move || {
+            let mut collected = String::new();
+            for line in BufReader::new(out).lines().map_while(Result::ok) {
+                println!("{}", line);
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+            collected
+        })
+    });
+    let err = child.stderr.take().map(|err| {
+        std::thread::spawn(move || {
+            let mut collected = String::new();
+            for line in BufReader::new(err).lines().map_while(Result::ok) {
+                eprintln!("{}", line);
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+            collected
+        })
+    });
+    for handle in [out, err].into_iter().flatten() {
+        output.push_str(&handle.join().unwrap_or_default());
+    }
+
+    child
+        .wait()
+        .map(|s| s.success())
+        .map_err(|e| format!("Failed to wait for {}: {}", command, e))
+}
+
+/// Plan, apply or destroy through the same checks, lock, state handling and
+/// audit record as the desktop app's runs.
+fn run_engine_command(paths: &StoragePaths, args: &CliArgs) -> Result<bool, String> {
+    let name = commands::sanitize_deployment_name(require_deployment(args)?)?;
+    let dir = deployment_dir(paths, &name)?;
+    let credentials = load_credentials(args)?.ok_or_else(|| {
+        "Pass --cloud <aws|azure|gcp> or --credentials <file.json>".to_string()
+    })?;
+    let confirmation = delete_confirmation(args)?;
+    let run = commands::prepare_terraform_run(paths, &name, &dir, &args.command, &credentials, &confirmation)?;
+    for warning in &run.credential_warnings {
+        eprintln!("warning: {}", warning);
+    }
+    let env_vars = &run.env_vars;
+    let mut output = String::new();
+
+    // First plan/apply/destroy on a fresh deployment needs providers installed
+    let needs_init = args.command != "init" && !dir.join(".terraform").exists();
+    let mut success = !needs_init || run_streaming("init", &dir, env_vars.clone(), &mut output)?;
+    if success {
+        success = run_streaming(&args.command, &dir, env_vars.clone(), &mut output)?;
+        if !success && args.command == "apply" {
+            // The retry reads the failed apply's errors from the status output
+            let status = Arc::new(Mutex::new(DeploymentStatus { output: output.clone(), ..Default::default() }));
+            let process = Arc::new(Mutex::new(None));
+            let log_path = commands::run_recovery::log_path(&dir);
+            let (ok, _) =
+                terraform::import_and_retry_apply(&dir, env_vars, &[], &log_path, status.clone(), process, &|_| {});
+            let _ = fs::remove_file(&log_path);
+            let retry_output = commands::lock_or_recover(&status).output[output.len()..].to_string();
+            print!("{}", retry_output);
+            output.push_str(&retry_output);
+            success = ok;
+        }
+    }
+    if success && args.command == "apply" {
+        let result = tauri::async_runtime::block_on(commands::post_deploy::run_for_deployment(
+            &dir,
+            &credentials,
+            run.state.key(),
+        ));
+        print!("{}", commands::post_deploy::format_result(&result));
+    }
+    commands::record_finished_run(&dir, &run.run_record, success, &output, credentials.cloud.as_deref());
+    drop(run);
+    if success && args.command == "destroy" {
+        commands::expiry::clear_expiry(&dir);
+    }
+    Ok(success)
+}
+
+fn print_status(paths: &StoragePaths, args: &CliArgs) -> Result<(), String> {
+    let dir = deployment_dir(paths, require_deployment(args)?)?;
+    println!("Deployment: {}", dir.display());
+    println!("Engine:     {}", terraform::deployment_engine(&dir).binary_name());
+    println!(
+        "Resources:  {}",
+        if terraform::check_state_exists(&dir) { "deployed" } else { "none" }
+    );
+    if let Some(expiry) = commands::expiry::read_expiry(&dir) {
+        println!(
+            "Expires at: {} (unix){}",
+            expiry.expires_at,
+            if expiry.unattended { ", auto-destroy" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// Extract templates, warning about any local edits set aside by a refresh.
+fn setup_templates(paths: &StoragePaths) -> Result<(), String> {
+    let report = commands::setup_templates_in(paths)?;
+    if let Some(dir) = report.backup_dir {
+        eprintln!(
+            "Templates updated to {}; preserved {} modified file(s) in {}",
+            report.version,
+            report.preserved_files.len(),
+            dir
+        );
+    }
+    Ok(())
+}
+
+fn dispatch(args: &CliArgs) -> Result<bool, String> {
+    let paths = match &args.data_dir {
+        Some(dir) => StoragePaths::from_data_dir(dir),
+        None => StoragePaths::user_default()?,
+    };
+
+    match args.command.as_str() {
+        "help" => {
+            print!("{}", USAGE);
+            Ok(true)
+        }
+        "templates" => {
+            setup_templates(&paths)?;
+            for template in commands::list_templates(&paths.templates_dir()?) {
+                println!("{:<14} {:<6} {}", template.id, template.cloud, template.name);
+            }
+            Ok(true)
+        }
+        "save" => {
+            setup_templates(&paths)?;
+            let template = args.template.as_deref().ok_or("'save' requires --template <id>")?;
+            let dir = commands::save_configuration_in(
+                &paths,
+                template,
+                require_deployment(args)?,
+                collect_values(args)?,
+                load_credentials(args)?,
+                None,
+                None,
+            )?;
+            println!("Saved configuration to {}", dir);
+            Ok(true)
+        }
+        "init" | "plan" | "apply" | "destroy" => run_engine_command(&paths, args),
+        "status" => print_status(&paths, args).map(|_| true),
+        other => Err(format!("Unknown command: {}\n\n{}", other, USAGE)),
+    }
+}
+
+/// Entry point for the `workspace-creator` binary. Returns the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let parsed = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let code = match dispatch(&parsed) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    };
+    let _ = std::io::stdout().flush();
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_save_command() {
+        let parsed = parse_args(&args(&[
+            "save", "sandbox", "--template", "aws-simple", "--var", "region=us-east-1", "--var",
+            "tags={\"team\":\"data\"}",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.command, "save");
+        assert_eq!(parsed.deployment.as_deref(), Some("sandbox"));
+        assert_eq!(parsed.template.as_deref(), Some("aws-simple"));
+        assert_eq!(parsed.vars[0], ("region".to_string(), "us-east-1".to_string()));
+        assert_eq!(parsed.vars.len(), 2);
+    }
+
+    #[test]
+    fn parse_args_defaults_to_help() {
+        assert_eq!(parse_args(&[]).unwrap().command, "help");
+        assert_eq!(parse_args(&args(&["plan", "--help"])).unwrap().command, "help");
+    }
+
+    #[test]
+    fn parse_args_rejects_bad_input() {
+        assert!(parse_args(&args(&["plan", "a", "b"])).is_err());
+        assert!(parse_args(&args(&["plan", "--bogus"])).is_err());
+        assert!(parse_args(&args(&["save", "a", "--var", "novalue"])).is_err());
+        assert!(parse_args(&args(&["save", "a", "--template"])).is_err());
+    }
+
+    #[test]
+    fn confirm_delete_kinds() {
+        let parsed = parse_args(&args(&["destroy", "a", "--confirm-delete", "storage", "--confirm-delete", "encryption-keys"]))
+            .unwrap();
+        let confirmation = delete_confirmation(&parsed).unwrap();
+        assert!(confirmation.storage && confirmation.encryption_keys && !confirmation.metastore);
+        let bad = parse_args(&args(&["destroy", "a", "--confirm-delete", "everything"])).unwrap();
+        assert!(delete_confirmation(&bad).is_err());
+    }
+
+    #[test]
+    fn var_values_parse_json_or_string() {
+        assert_eq!(parse_var_value("true"), serde_json::json!(true));
+        assert_eq!(parse_var_value("3"), serde_json::json!(3));
+        assert_eq!(parse_var_value("us-east-1"), serde_json::json!("us-east-1"));
+        assert_eq!(parse_var_value("{\"a\":1}"), serde_json::json!({"a": 1}));
+    }
+
+    #[test]
+    fn save_and_status_without_tauri() {
+        let data = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(data.path());
+
+        let parsed = parse_args(&args(&[
+            "--data-dir",
+            data.path().to_str().unwrap(),
+            "save",
+            "ci-test",
+            "--template",
+            "aws-simple",
+            "--var",
+            "prefix=ci",
+        ]))
+        .unwrap();
+        assert!(dispatch(&parsed).unwrap());
+
+        let dir = deployment_dir(&paths, "ci-test").unwrap();
+        assert!(fs::read_to_string(dir.join("terraform.tfvars")).unwrap().contains("prefix"));
+        assert!(deployment_dir(&paths, "missing").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Account admins of a Databricks account.
+//!
+//! When a validation step fails for lack of account admin access, the UI
+//! can name the users and service principals that hold the `account_admin`
+//! role instead of a generic "contact your admin".
+
+use super::CloudCredentials;
+use crate::databricks_api::{AccountClient, ApiError, ScimRole, ScimServicePrincipal, ScimUser};
+use serde::Serialize;
+
+const ACCOUNT_ADMIN_ROLE: &str = "account_admin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminKind {
+    User,
+    ServicePrincipal,
+}
+
+/// A user or service principal with the account admin role.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountAdmin {
+    pub kind: AdminKind,
+    pub id: String,
+    /// User name (email) or service principal application ID.
+    pub name: String,
+    pub display_name: Option<String>,
+}
+
+fn is_account_admin(roles: &[ScimRole]) -> bool {
+    roles.iter().any(|r| r.value == ACCOUNT_ADMIN_ROLE)
+}
+
+/// Users then service principals holding the role directly, each sorted by name.
+fn admins(users: Vec<ScimUser>, service_principals: Vec<ScimServicePrincipal>) -> Vec<AccountAdmin> {
+    let mut users: Vec<AccountAdmin> = users
+        .into_iter()
+        .filter(|u| is_account_admin(&u.roles))
+        .map(|u| AccountAdmin { kind: AdminKind::User, id: u.id, name: u.user_name, display_name: u.display_name })
+        .collect();
+    let mut service_principals: Vec<AccountAdmin> = service_principals
+        .into_iter()
+        .filter(|sp| is_account_admin(&sp.roles))
+        .map(|sp| AccountAdmin {
+            kind: AdminKind::ServicePrincipal,
+            id: sp.id,
+            name: sp.application_id,
+            display_name: sp.display_name,
+        })
+        .collect();
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    service_principals.sort_by(|a, b| a.name.cmp(&b.name));
+    users.extend(service_principals);
+    users
+}
+
+pub(crate) async fn account_admins(credentials: CloudCredentials) -> Result<Vec<AccountAdmin>, String> {
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks Account ID is required")?;
+
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    let to_error = |e: ApiError| match e {
+        ApiError::Forbidden(_) => {
+            "Only account admins can list account admins; ask the Databricks account owner for access".to_string()
+        }
+        e => format!("Failed to list account admins: {}", e),
+    };
+    let users = client.list_users_with_roles().await.map_err(to_error)?;
+    let service_principals = client.list_service_principals().await.map_err(to_error)?;
+    Ok(admins(users, service_principals))
+}
+
+/// Users and service principals assigned the account admin role, using the
+/// active Databricks auth method. Admin rights inherited through a group are
+/// not listed.
+#[tauri::command]
+pub async fn get_account_admins(credentials: CloudCredentials) -> Result<Vec<AccountAdmin>, String> {
+    account_admins(credentials).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(value: &str) -> ScimRole {
+        ScimRole { value: value.to_string() }
+    }
+
+    #[test]
+    fn only_direct_account_admins_are_listed() {
+        let users = vec![
+            ScimUser { id: "2".into(), user_name: "zoe@example.com".into(), roles: vec![role(ACCOUNT_ADMIN_ROLE)], ..Default::default() },
+            ScimUser { id: "1".into(), user_name: "amy@example.com".into(), roles: vec![role(ACCOUNT_ADMIN_ROLE)], ..Default::default() },
+            ScimUser { id: "3".into(), user_name: "bob@example.com".into(), roles: vec![role("marketplace.admin")], ..Default::default() },
+        ];
+        let sps = vec![
+            ScimServicePrincipal { id: "9".into(), application_id: "app-1".into(), roles: vec![role(ACCOUNT_ADMIN_ROLE)], ..Default::default() },
+            ScimServicePrincipal { id: "8".into(), application_id: "app-2".into(), ..Default::default() },
+        ];
+
+        let listed = admins(users, sps);
+        let names: Vec<&str> = listed.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["amy@example.com", "zoe@example.com", "app-1"]);
+        assert_eq!(listed[2].kind, AdminKind::ServicePrincipal);
+    }
+}
@@ -0,0 +1,278 @@
+//! Account-level objects left behind by deleted workspaces.
+//!
+//! Destroying an AWS workspace outside the app, or a destroy that fails
+//! halfway, can leave its network, storage, and credential configurations
+//! registered in the Databricks account. The bundled templates name those
+//! objects `<prefix>-network`, `<prefix>-storage`, and so on, so
+//! [`deep_cleanup_workspace`] looks up the deployment's prefix, lists the
+//! account objects carrying it that no workspace references any more, and
+//! deletes the ones the user confirms. Objects of other deployments whose
+//! prefix extends this one (`prod-eu` next to `prod`) are left alone, and the
+//! deployment is locked so an apply can't create objects mid-cleanup.
+
+use super::{get_deployments_dir, sanitize_deployment_name, CloudCredentials};
+use crate::databricks_api::{AccountClient, ApiError, Workspace};
+use crate::storage::Environment;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// tfvars variables the templates use as the account object name prefix.
+pub(super) const PREFIX_VARIABLES: &[&str] = &["prefix", "resource_prefix"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountObjectKind {
+    Network,
+    StorageConfiguration,
+    Credentials,
+}
+
+/// A network, storage, or credential configuration in the account.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountObject {
+    pub kind: AccountObjectKind,
+    pub id: String,
+    pub name: String,
+}
+
+/// Result of a deep cleanup run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccountCleanup {
+    /// Name prefix read from the deployment.
+    pub prefix: String,
+    pub removed: Vec<String>,
+    /// `(id, error)` for objects that could not be deleted.
+    pub failed: Vec<(String, String)>,
+    /// Orphaned objects still in the account after the run.
+    pub orphans: Vec<AccountObject>,
+}
+
+/// The first of `variables` set in `deployment_dir`'s `terraform.tfvars`.
+fn tfvars_prefix(deployment_dir: &Path, variables: &[&str]) -> Option<String> {
+    let tfvars = fs::read_to_string(deployment_dir.join("terraform.tfvars")).ok()?;
+    super::audit::parse_tfvars(&tfvars)
+        .into_iter()
+        .find(|(name, _)| variables.contains(&name.as_str()))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|prefix| !prefix.is_empty())
+}
+
+/// The name prefix from the deployment's `terraform.tfvars`: the first of
+/// `variables` that is set.
+pub(super) fn deployment_prefix(env: &dyn Environment, deployment_name: &str, variables: &[&str]) -> Result<String, String> {
+    let dir = get_deployments_dir(env)?.join(sanitize_deployment_name(deployment_name)?);
+    if !dir.join("terraform.tfvars").is_file() {
+        return Err("Deployment not found".to_string());
+    }
+    tfvars_prefix(&dir, variables).ok_or_else(|| "This deployment has no name prefix to match resources by".to_string())
+}
+
+/// Prefixes of the other deployments that extend `prefix` (`prod-eu` for
+/// `prod`), whose objects must not be taken for this deployment's. Fails when
+/// another deployment uses the same prefix, as their objects can't be told
+/// apart.
+pub(super) fn other_prefixes(
+    env: &dyn Environment,
+    deployment_name: &str,
+    prefix: &str,
+    variables: &[&str],
+) -> Result<Vec<String>, String> {
+    let own = sanitize_deployment_name(deployment_name)?;
+    let entries = fs::read_dir(get_deployments_dir(env)?).map_err(|e| e.to_string())?;
+    let mut others = Vec::new();
+    for entry in entries.flatten().filter(|e| e.path().is_dir() && e.file_name() != own.as_str()) {
+        let Some(other) = tfvars_prefix(&entry.path(), variables) else {
+            continue;
+        };
+        if other == prefix {
+            return Err(format!(
+                "Deployment '{}' uses the same prefix '{}', so their resources can't be told apart",
+                entry.file_name().to_string_lossy(),
+                prefix
+            ));
+        }
+        if has_prefix(&other, prefix) {
+            others.push(other);
+        }
+    }
+    Ok(others)
+}
+
+/// Whether `name` was created with `prefix` (`<prefix>-...`), so that
+/// `prod` does not match `production-network`.
+pub(super) fn has_prefix(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// Whether `name` carries `prefix` and none of the longer `other_prefixes`.
+pub(super) fn owns_name(name: &str, prefix: &str, other_prefixes: &[String]) -> bool {
+    has_prefix(name, prefix) && !other_prefixes.iter().any(|other| name == other || has_prefix(name, other))
+}
+
+/// Objects named with `prefix`, and none of `other_prefixes`, that none of
+/// `workspaces` references.
+fn orphans(prefix: &str, other_prefixes: &[String], objects: Vec<AccountObject>, workspaces: &[Workspace]) -> Vec<AccountObject> {
+    let in_use = |object: &AccountObject| {
+        workspaces.iter().any(|ws| {
+            let referenced = match object.kind {
+                AccountObjectKind::Network => &ws.network_id,
+                AccountObjectKind::StorageConfiguration => &ws.storage_configuration_id,
+                AccountObjectKind::Credentials => &ws.credentials_id,
+            };
+            referenced.as_deref() == Some(object.id.as_str())
+        })
+    };
+    objects
+        .into_iter()
+        .filter(|object| owns_name(&object.name, prefix, other_prefixes) && !in_use(object))
+        .collect()
+}
+
+/// Orphaned account objects named with `prefix`.
+async fn find_orphans(client: &AccountClient, prefix: &str, other_prefixes: &[String]) -> Result<Vec<AccountObject>, ApiError> {
+    let workspaces = client.list_workspaces().await?;
+    let mut objects: Vec<AccountObject> = client
+        .list_networks()
+        .await?
+        .into_iter()
+        .map(|n| AccountObject { kind: AccountObjectKind::Network, id: n.network_id, name: n.network_name })
+        .collect();
+    objects.extend(client.list_storage_configurations().await?.into_iter().map(|s| AccountObject {
+        kind: AccountObjectKind::StorageConfiguration,
+        id: s.storage_configuration_id,
+        name: s.storage_configuration_name,
+    }));
+    objects.extend(client.list_credentials().await?.into_iter().map(|c| AccountObject {
+        kind: AccountObjectKind::Credentials,
+        id: c.credentials_id,
+        name: c.credentials_name,
+    }));
+    Ok(orphans(prefix, other_prefixes, objects, &workspaces))
+}
+
+async fn delete_object(client: &AccountClient, object: &AccountObject) -> Result<(), ApiError> {
+    match object.kind {
+        AccountObjectKind::Network => client.delete_network(&object.id).await,
+        AccountObjectKind::StorageConfiguration => client.delete_storage_configuration(&object.id).await,
+        AccountObjectKind::Credentials => client.delete_credentials(&object.id).await,
+    }
+}
+
+/// Delete the orphans among `ids`, then list what is left. Orphans are
+/// looked up again first, so an object a workspace started using since the
+/// user confirmed is never deleted.
+async fn cleanup_orphans(
+    client: &AccountClient,
+    prefix: &str,
+    other_prefixes: &[String],
+    ids: &[String],
+) -> Result<AccountCleanup, ApiError> {
+    let mut cleanup = AccountCleanup { prefix: prefix.to_string(), ..Default::default() };
+    if ids.is_empty() {
+        cleanup.orphans = find_orphans(client, prefix, other_prefixes).await?;
+        return Ok(cleanup);
+    }
+    for object in find_orphans(client, prefix, other_prefixes).await?.into_iter().filter(|o| ids.contains(&o.id)) {
+        match delete_object(client, &object).await {
+            Ok(()) | Err(ApiError::NotFound(_)) => cleanup.removed.push(object.id),
+            Err(e) => cleanup.failed.push((object.id, e.to_string())),
+        }
+    }
+    cleanup.orphans = find_orphans(client, prefix, other_prefixes).await?;
+    Ok(cleanup)
+}
+
+pub(crate) async fn deep_cleanup(
+    prefix: String,
+    other_prefixes: Vec<String>,
+    credentials: CloudCredentials,
+    ids: Option<Vec<String>>,
+) -> Result<AccountCleanup, String> {
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks Account ID is required")?;
+
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    cleanup_orphans(&client, &prefix, &other_prefixes, &ids.unwrap_or_default())
+        .await
+        .map_err(|e| match e {
+            ApiError::Forbidden(_) => "Cleaning up account objects requires account admin access".to_string(),
+            e => format!("Failed to clean up account objects: {}", e),
+        })
+}
+
+/// List account-level network, storage, and credential configurations named
+/// with the deployment's prefix that no workspace uses, and delete the
+/// selected ones. With no `ids` nothing is deleted and the orphans are
+/// returned for confirmation. The deployment is locked meanwhile.
+#[tauri::command]
+pub async fn deep_cleanup_workspace(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+    ids: Option<Vec<String>>,
+) -> Result<AccountCleanup, String> {
+    let prefix = deployment_prefix(&app, &deployment_name, PREFIX_VARIABLES)?;
+    let other_prefixes = other_prefixes(&app, &deployment_name, &prefix, PREFIX_VARIABLES)?;
+    let _lock = super::deployment_lock::acquire(&app, &deployment_name, "account_cleanup")?;
+    deep_cleanup(prefix, other_prefixes, credentials, ids).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+
+    fn object(kind: AccountObjectKind, id: &str, name: &str) -> AccountObject {
+        AccountObject { kind, id: id.to_string(), name: name.to_string() }
+    }
+
+    #[test]
+    fn orphans_skip_other_prefixes_and_objects_in_use() {
+        let objects = vec![
+            object(AccountObjectKind::Network, "n-1", "prod-network"),
+            object(AccountObjectKind::Network, "n-2", "production-network"),
+            object(AccountObjectKind::StorageConfiguration, "s-1", "prod-storage"),
+            object(AccountObjectKind::Credentials, "c-1", "prod-creds"),
+            object(AccountObjectKind::Network, "n-3", "prod-eu-network"),
+        ];
+        let workspaces = vec![Workspace { storage_configuration_id: Some("s-1".to_string()), ..Default::default() }];
+
+        let ids: Vec<String> = orphans("prod", &["prod-eu".to_string()], objects, &workspaces).into_iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec!["n-1", "c-1"]);
+    }
+
+    #[test]
+    fn prefix_is_read_from_tfvars() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        let deployments = paths.deployments_dir().unwrap();
+        fs::create_dir_all(deployments.join("sra")).unwrap();
+        fs::write(deployments.join("sra/terraform.tfvars"), "region = \"us-east-1\"\nresource_prefix = \"sra-dev\"\n").unwrap();
+        fs::create_dir_all(deployments.join("azure")).unwrap();
+        fs::write(deployments.join("azure/terraform.tfvars"), "location = \"eastus\"\n").unwrap();
+
+        assert_eq!(deployment_prefix(&paths, "sra", PREFIX_VARIABLES).unwrap(), "sra-dev");
+        assert!(deployment_prefix(&paths, "azure", PREFIX_VARIABLES).is_err());
+        assert!(deployment_prefix(&paths, "missing", PREFIX_VARIABLES).is_err());
+    }
+
+    #[test]
+    fn other_deployments_extending_the_prefix_are_excluded() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        let deployments = paths.deployments_dir().unwrap();
+        for (name, prefix) in [("prod", "prod"), ("prod-eu", "prod-eu"), ("production", "production")] {
+            fs::create_dir_all(deployments.join(name)).unwrap();
+            fs::write(deployments.join(name).join("terraform.tfvars"), format!("prefix = \"{}\"\n", prefix)).unwrap();
+        }
+        assert_eq!(other_prefixes(&paths, "prod", "prod", PREFIX_VARIABLES).unwrap(), vec!["prod-eu"]);
+        assert!(other_prefixes(&paths, "production", "prod", PREFIX_VARIABLES).unwrap_err().contains("same prefix"));
+    }
+}
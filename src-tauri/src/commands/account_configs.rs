@@ -0,0 +1,169 @@
+//! Existing Databricks account configurations that a new workspace can reuse.
+//!
+//! AWS and GCP workspaces reference account-level network, storage, and
+//! credential configurations. The bundled templates create new ones unless an
+//! `existing_*_id` variable is set; these commands list what the account
+//! already has so users can pick one instead of registering duplicates.
+
+use super::CloudCredentials;
+use crate::databricks_api::{AccountClient, ApiError, CredentialConfig, NetworkConfig, StorageConfig};
+use serde::Serialize;
+
+/// An existing configuration, reduced to what the picker shows.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountConfiguration {
+    pub id: String,
+    pub name: String,
+    /// What the configuration points at (VPC, bucket, role), for the label.
+    pub detail: String,
+}
+
+/// Reusable configurations in the account. Storage and credential
+/// configurations only exist on AWS and are empty for GCP.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccountConfigurations {
+    pub networks: Vec<AccountConfiguration>,
+    pub storage_configurations: Vec<AccountConfiguration>,
+    pub credentials: Vec<AccountConfiguration>,
+}
+
+fn join_detail(parts: &[&str]) -> String {
+    parts.iter().filter(|p| !p.is_empty()).cloned().collect::<Vec<_>>().join(", ")
+}
+
+/// Networks registered for `cloud`; AWS networks carry a VPC ID and GCP
+/// networks a `gcp_network_info` block, so the other cloud's entries drop out.
+fn network_option(cloud: &str, network: NetworkConfig) -> Option<AccountConfiguration> {
+    let detail = match cloud {
+        "aws" => {
+            let vpc_id = network.vpc_id.as_deref().filter(|v| !v.is_empty())?;
+            let subnets = match network.subnet_ids.len() {
+                0 => String::new(),
+                1 => "1 subnet".to_string(),
+                n => format!("{} subnets", n),
+            };
+            join_detail(&[vpc_id, &subnets, network.vpc_status.as_deref().unwrap_or("")])
+        }
+        "gcp" => {
+            let info = network.gcp_network_info.as_ref()?;
+            let vpc = format!("{}/{}", info.network_project_id, info.vpc_id);
+            join_detail(&[&vpc, &info.subnet_id, &info.subnet_region])
+        }
+        _ => return None,
+    };
+    Some(AccountConfiguration { id: network.network_id, name: network.network_name, detail })
+}
+
+fn storage_option(storage: StorageConfig) -> AccountConfiguration {
+    AccountConfiguration {
+        detail: storage.root_bucket_info.map(|b| b.bucket_name).unwrap_or_default(),
+        id: storage.storage_configuration_id,
+        name: storage.storage_configuration_name,
+    }
+}
+
+fn credential_option(credential: CredentialConfig) -> AccountConfiguration {
+    AccountConfiguration {
+        detail: credential.role_arn().unwrap_or_default().to_string(),
+        id: credential.credentials_id,
+        name: credential.credentials_name,
+    }
+}
+
+fn sorted(mut options: Vec<AccountConfiguration>) -> Vec<AccountConfiguration> {
+    options.sort_by_key(|o| o.name.to_lowercase());
+    options
+}
+
+/// Query the account for configurations usable by a `cloud` workspace.
+pub(crate) async fn fetch_account_configurations(
+    client: &AccountClient,
+    cloud: &str,
+) -> Result<AccountConfigurations, ApiError> {
+    let networks = client.list_networks().await?;
+    let mut configs = AccountConfigurations {
+        networks: sorted(networks.into_iter().filter_map(|n| network_option(cloud, n)).collect()),
+        ..Default::default()
+    };
+    if cloud == "aws" {
+        let storage = client.list_storage_configurations().await?;
+        configs.storage_configurations = sorted(storage.into_iter().map(storage_option).collect());
+        let credentials = client.list_credentials().await?;
+        configs.credentials = sorted(credentials.into_iter().map(credential_option).collect());
+    }
+    Ok(configs)
+}
+
+/// List the account's network, storage, and credential configurations that a
+/// new AWS or GCP workspace could reuse via the template's `existing_*_id` variables.
+#[tauri::command]
+pub async fn list_account_configurations(credentials: CloudCredentials) -> Result<AccountConfigurations, String> {
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    if cloud != "aws" && cloud != "gcp" {
+        return Err("Reusing account configurations is only supported for AWS and GCP workspaces".to_string());
+    }
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks Account ID is required")?;
+
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    fetch_account_configurations(&client, &cloud).await.map_err(|e| match e {
+        ApiError::Forbidden(_) => "Listing account configurations requires account admin access".to_string(),
+        e => format!("Failed to list account configurations: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::databricks_api::GcpNetworkInfo;
+
+    #[test]
+    fn network_options_filter_by_cloud() {
+        let aws = NetworkConfig {
+            network_id: "n-1".to_string(),
+            network_name: "shared".to_string(),
+            vpc_id: Some("vpc-123".to_string()),
+            vpc_status: Some("VALID".to_string()),
+            subnet_ids: vec!["subnet-a".to_string(), "subnet-b".to_string()],
+            ..Default::default()
+        };
+        let gcp = NetworkConfig {
+            network_id: "n-2".to_string(),
+            network_name: "gcp-net".to_string(),
+            gcp_network_info: Some(GcpNetworkInfo {
+                network_project_id: "proj".to_string(),
+                vpc_id: "vpc".to_string(),
+                subnet_id: "subnet".to_string(),
+                subnet_region: "us-central1".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let option = network_option("aws", aws.clone()).unwrap();
+        assert_eq!(option.id, "n-1");
+        assert_eq!(option.detail, "vpc-123, 2 subnets, VALID");
+        assert!(network_option("gcp", aws).is_none());
+
+        assert_eq!(network_option("gcp", gcp.clone()).unwrap().detail, "proj/vpc, subnet, us-central1");
+        assert!(network_option("aws", gcp).is_none());
+    }
+
+    #[test]
+    fn credential_option_uses_role_arn() {
+        let credential: CredentialConfig = serde_json::from_value(serde_json::json!({
+            "credentials_id": "c-1",
+            "credentials_name": "creds",
+            "aws_credentials": { "sts_role": { "role_arn": "arn:aws:iam::123:role/x" } }
+        }))
+        .unwrap();
+        let option = credential_option(credential);
+        assert_eq!(option.detail, "arn:aws:iam::123:role/x");
+
+        let bare: CredentialConfig = serde_json::from_value(serde_json::json!({ "credentials_id": "c-2" })).unwrap();
+        assert_eq!(credential_option(bare).detail, "");
+    }
+}
@@ -0,0 +1,534 @@
+//! Deployment audit trail.
+//!
+//! Each deployment records the template it was created from
+//! (`.deployer-meta.json`) and a history of Terraform runs
+//! (`.deployer-runs.json`). `export_deployment_report` combines these with the
+//! redacted variable values and state outputs into a single JSON document for
+//! compliance record-keeping.
+
+use super::environment_snapshot::EnvironmentSnapshot;
+use super::run_settings::RunSettings;
+use super::{debug_log, get_deployments_dir, opt_non_empty, sanitize_deployment_name, CloudCredentials, TEMPLATES_VERSION};
+use crate::terraform;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Template metadata file in a deployment directory.
+const META_FILE: &str = ".deployer-meta.json";
+
+/// Run history file in a deployment directory.
+const RUNS_FILE: &str = ".deployer-runs.json";
+
+/// Oldest runs are dropped beyond this many.
+const MAX_RUN_RECORDS: usize = 200;
+
+/// Shown in place of secret values.
+pub(super) const REDACTED: &str = "(redacted)";
+
+/// Variable name fragments treated as secret even when not marked `sensitive`.
+const SECRET_NAME_HINTS: &[&str] = &["secret", "password", "token", "credentials", "private_key"];
+
+/// Template a deployment was created from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeploymentMeta {
+    pub template_id: String,
+    pub templates_version: String,
+    /// Unix timestamps (seconds).
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Resolved identities of the last successful apply.
+    #[serde(default)]
+    pub last_applied_by: Vec<String>,
+    #[serde(default)]
+    pub last_applied_at: Option<u64>,
+    /// Advanced Terraform arguments and environment for this deployment.
+    #[serde(default)]
+    pub run_settings: RunSettings,
+    /// Tool versions used by the last successful apply.
+    #[serde(default)]
+    pub last_apply_environment: Option<EnvironmentSnapshot>,
+}
+
+/// One Terraform run against a deployment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunRecord {
+    pub command: String,
+    pub engine: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub success: bool,
+    /// Cloud identities the run authenticated as (no secrets).
+    pub identities: Vec<String>,
+    /// Principals those credentials resolved to when the run started (e.g. the
+    /// AWS caller ARN) and the OS user. Only recorded for apply and destroy.
+    #[serde(default)]
+    pub resolved_identities: Vec<String>,
+    /// e.g. "Plan: 3 to add, 0 to change, 0 to destroy."
+    #[serde(default)]
+    pub plan_summary: Option<String>,
+    /// e.g. "Apply complete! Resources: 3 added, 0 changed, 0 destroyed."
+    #[serde(default)]
+    pub result_summary: Option<String>,
+}
+
+/// A `terraform.tfvars` entry in a report.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReportVariable {
+    pub name: String,
+    pub value: String,
+    pub redacted: bool,
+}
+
+/// A state output in a report.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReportOutput {
+    pub name: String,
+    pub value: serde_json::Value,
+    pub sensitive: bool,
+}
+
+/// Audit report for one deployment.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentReport {
+    pub deployment_name: String,
+    pub generated_at: u64,
+    pub app_version: String,
+    pub engine: String,
+    pub template: Option<DeploymentMeta>,
+    /// Distinct identities across all recorded runs.
+    pub identities: Vec<String>,
+    pub variables: Vec<ReportVariable>,
+    pub runs: Vec<RunRecord>,
+    pub outputs: Vec<ReportOutput>,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn read_meta(deployment_dir: &Path) -> Option<DeploymentMeta> {
+    try_read_meta(deployment_dir).ok().flatten()
+}
+
+/// [`read_meta`], failing when the file exists but can't be read or parsed.
+/// `None` when the deployment has no metadata yet.
+pub(crate) fn try_read_meta(deployment_dir: &Path) -> Result<Option<DeploymentMeta>, String> {
+    let path = deployment_dir.join(META_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Record (or update) the template a deployment was saved from.
+pub(crate) fn record_template(deployment_dir: &Path, template_id: &str) -> Result<(), String> {
+    let now = now_secs();
+    let previous = read_meta(deployment_dir);
+    let meta = DeploymentMeta {
+        template_id: template_id.to_string(),
+        templates_version: TEMPLATES_VERSION.to_string(),
+        created_at: previous.as_ref().map(|m| m.created_at).unwrap_or(now),
+        updated_at: now,
+        last_applied_by: previous.as_ref().map(|m| m.last_applied_by.clone()).unwrap_or_default(),
+        last_applied_at: previous.as_ref().and_then(|m| m.last_applied_at),
+        run_settings: previous.as_ref().map(|m| m.run_settings.clone()).unwrap_or_default(),
+        last_apply_environment: previous.and_then(|m| m.last_apply_environment),
+    };
+    write_meta(deployment_dir, &meta)
+}
+
+pub(super) fn write_meta(deployment_dir: &Path, meta: &DeploymentMeta) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+    fs::write(deployment_dir.join(META_FILE), json).map_err(|e| format!("Failed to write deployment metadata: {}", e))
+}
+
+/// Record who performed the latest successful apply. Logged, not raised.
+pub(crate) fn record_applied_by(deployment_dir: &Path, identities: &[String]) {
+    let Some(mut meta) = read_meta(deployment_dir) else {
+        return;
+    };
+    meta.last_applied_by = identities.to_vec();
+    meta.last_applied_at = Some(now_secs());
+    if let Err(_e) = write_meta(deployment_dir, &meta) {
+        debug_log!("[audit] Failed to record applying identity: {}", _e);
+    }
+}
+
+pub(crate) fn read_runs(deployment_dir: &Path) -> Vec<RunRecord> {
+    fs::read_to_string(deployment_dir.join(RUNS_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Append a run to the deployment's history. Failures are logged, not raised,
+/// so bookkeeping never fails a deployment.
+pub(crate) fn record_run(deployment_dir: &Path, mut record: RunRecord, output: &str) {
+    let (plan, result) = summarize_output(output);
+    record.plan_summary = plan;
+    record.result_summary = result;
+
+    let mut runs = read_runs(deployment_dir);
+    runs.push(record);
+    if runs.len() > MAX_RUN_RECORDS {
+        runs.drain(..runs.len() - MAX_RUN_RECORDS);
+    }
+    let written = serde_json::to_string_pretty(&runs)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(deployment_dir.join(RUNS_FILE), json).map_err(|e| e.to_string()));
+    if let Err(_e) = written {
+        debug_log!("[audit] Failed to record run: {}", _e);
+    }
+}
+
+/// Last plan summary and last completion line in Terraform output.
+pub(crate) fn summarize_output(output: &str) -> (Option<String>, Option<String>) {
+    let last_line = |prefixes: &[&str]| {
+        output
+            .lines()
+            .map(str::trim)
+            .rev()
+            .find(|l| prefixes.iter().any(|p| l.starts_with(p)))
+            .map(str::to_string)
+    };
+    (
+        last_line(&["Plan:", "No changes."]),
+        last_line(&["Apply complete!", "Destroy complete!", "Terraform has been successfully initialized", "OpenTofu has been successfully initialized", "Error:"]),
+    )
+}
+
+/// Describe the identities in `creds` without secrets.
+pub(crate) fn identities_used(creds: &CloudCredentials) -> Vec<String> {
+    let mut identities = Vec::new();
+    let mut push = |label: &str, value: &Option<String>| {
+        if let Some(v) = value.as_deref().filter(|v| !v.is_empty()) {
+            identities.push(format!("{}: {}", label, v));
+        }
+    };
+
+    match creds.cloud.as_deref() {
+        Some("aws") => {
+            push("AWS profile", &creds.aws_profile);
+            if !opt_non_empty(&creds.aws_profile) {
+                let masked = creds.aws_access_key_id.as_deref().map(mask_key_id);
+                push("AWS access key", &masked);
+            }
+        }
+        Some("azure") => {
+            push("Azure tenant", &creds.azure_tenant_id);
+            push("Azure subscription", &creds.azure_subscription_id);
+            if opt_non_empty(&creds.azure_client_secret) {
+                push("Azure service principal", &creds.azure_client_id);
+            } else {
+                push("Azure CLI account", &creds.azure_account_email);
+            }
+        }
+        Some("gcp") => {
+            push("GCP project", &creds.gcp_project_id);
+            push("GCP service account", &creds.gcp_service_account_email);
+        }
+        _ => {}
+    }
+
+    push("Databricks account", &creds.databricks_account_id);
+    if opt_non_empty(&creds.databricks_client_secret) {
+        push("Databricks service principal", &creds.databricks_client_id);
+    } else {
+        push("Databricks profile", &creds.databricks_profile);
+    }
+    identities
+}
+
+/// `AKIA…WXYZ` — enough to identify a key without exposing it.
+fn mask_key_id(key: &str) -> String {
+    if key.len() <= 8 || !key.is_ascii() {
+        return REDACTED.to_string();
+    }
+    format!("{}…{}", &key[..4], &key[key.len() - 4..])
+}
+
+/// Top-level `name = value` entries in a tfvars file. Multi-line list and map
+/// values are kept whole.
+pub(super) fn parse_tfvars(content: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut depth: i32 = 0;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if depth > 0 {
+            if let Some((_, value)) = entries.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim_end());
+            }
+        } else if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") {
+            continue;
+        } else if let Some((name, value)) = trimmed.split_once('=') {
+            entries.push((name.trim().to_string(), value.trim().to_string()));
+        } else {
+            continue;
+        }
+        depth += trimmed.matches(['[', '{']).count() as i32 - trimmed.matches([']', '}']).count() as i32;
+        depth = depth.max(0);
+    }
+    entries
+}
+
+pub(crate) fn is_secret_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    SECRET_NAME_HINTS.iter().any(|hint| lower.contains(hint))
+}
+
+/// `terraform.tfvars` entries as `(name, value, secret)`, where `secret`
+/// marks sensitive variables and secret-looking names. Values are not redacted.
+pub(super) fn tfvars_entries(deployment_dir: &Path) -> Vec<(String, String, bool)> {
+    let Ok(tfvars) = fs::read_to_string(deployment_dir.join("terraform.tfvars")) else {
+        return Vec::new();
+    };
+    // A variables.tf that doesn't parse can't tell what is sensitive: redact everything
+    let variables = fs::read_to_string(deployment_dir.join("variables.tf")).unwrap_or_default();
+    let sensitive: Option<Vec<String>> = terraform::parse_variables_tf(&variables)
+        .ok()
+        .map(|vars| vars.into_iter().filter(|v| v.sensitive).map(|v| v.name).collect());
+
+    parse_tfvars(&tfvars)
+        .into_iter()
+        .map(|(name, value)| {
+            let secret = sensitive.as_ref().is_none_or(|s| s.contains(&name)) || is_secret_name(&name);
+            (name, value, secret)
+        })
+        .collect()
+}
+
+/// `terraform.tfvars` values with sensitive variables redacted.
+fn report_variables(deployment_dir: &Path) -> Vec<ReportVariable> {
+    tfvars_entries(deployment_dir)
+        .into_iter()
+        .map(|(name, value, redacted)| ReportVariable {
+            value: if redacted { REDACTED.to_string() } else { value },
+            name,
+            redacted,
+        })
+        .collect()
+}
+
+/// Outputs from the local state with sensitive values redacted.
+fn report_outputs(state: &serde_json::Value) -> Vec<ReportOutput> {
+    let Some(outputs) = state["outputs"].as_object() else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .map(|(name, output)| {
+            let sensitive = output["sensitive"].as_bool().unwrap_or(false);
+            ReportOutput {
+                name: name.clone(),
+                value: if sensitive { serde_json::json!(REDACTED) } else { output["value"].clone() },
+                sensitive,
+            }
+        })
+        .collect()
+}
+
+/// `key` decrypts state that is encrypted at rest; without it outputs are omitted.
+pub(crate) fn build_report(deployment_name: &str, deployment_dir: &Path, key: Option<&[u8; 32]>) -> DeploymentReport {
+    let runs = read_runs(deployment_dir);
+    let mut identities: Vec<String> = Vec::new();
+    for identity in runs.iter().flat_map(|r| r.identities.iter().chain(&r.resolved_identities)) {
+        if !identities.contains(identity) {
+            identities.push(identity.clone());
+        }
+    }
+    let state = super::state_encryption::read_state(deployment_dir, key)
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or(serde_json::Value::Null);
+
+    DeploymentReport {
+        deployment_name: deployment_name.to_string(),
+        generated_at: now_secs(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        engine: terraform::deployment_engine(deployment_dir).binary_name().to_string(),
+        template: read_meta(deployment_dir),
+        identities,
+        variables: report_variables(deployment_dir),
+        runs,
+        outputs: report_outputs(&state),
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Build the audit report for a deployment, writing it as JSON to
+/// `destination` when given.
+#[tauri::command]
+pub fn export_deployment_report(
+    app: AppHandle,
+    deployment_name: String,
+    destination: Option<String>,
+) -> Result<DeploymentReport, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+
+    let key = if super::state_encryption::is_enabled(&deployment_dir) {
+        Some(super::state_encryption::load_key(&app)?)
+    } else {
+        None
+    };
+    let report = build_report(&safe_name, &deployment_dir, key.as_ref());
+    if let Some(path) = destination.filter(|p| !p.is_empty()) {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write report: {}", e))?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, identities: &[&str]) -> RunRecord {
+        RunRecord {
+            command: command.to_string(),
+            engine: "terraform".to_string(),
+            started_at: 1,
+            finished_at: 2,
+            success: true,
+            identities: identities.iter().map(|s| s.to_string()).collect(),
+            resolved_identities: Vec::new(),
+            plan_summary: None,
+            result_summary: None,
+        }
+    }
+
+    #[test]
+    fn summarizes_plan_and_result_lines() {
+        let output = "Initializing...\nPlan: 12 to add, 0 to change, 0 to destroy.\naws_vpc.this: Creating...\nApply complete! Resources: 12 added, 0 changed, 0 destroyed.\n";
+        let (plan, result) = summarize_output(output);
+        assert_eq!(plan.as_deref(), Some("Plan: 12 to add, 0 to change, 0 to destroy."));
+        assert_eq!(result.as_deref(), Some("Apply complete! Resources: 12 added, 0 changed, 0 destroyed."));
+        assert_eq!(summarize_output("nothing here"), (None, None));
+    }
+
+    #[test]
+    fn identities_exclude_secrets() {
+        let creds = CloudCredentials {
+            cloud: Some("aws".to_string()),
+            aws_access_key_id: Some("AKIAABCDEFGHWXYZ".to_string()),
+            aws_secret_access_key: Some("very-secret".to_string()),
+            databricks_account_id: Some("acct".to_string()),
+            databricks_client_id: Some("sp-id".to_string()),
+            databricks_client_secret: Some("sp-secret".to_string()),
+            ..Default::default()
+        };
+        let identities = identities_used(&creds);
+        assert_eq!(
+            identities,
+            vec![
+                "AWS access key: AKIA…WXYZ",
+                "Databricks account: acct",
+                "Databricks service principal: sp-id",
+            ]
+        );
+        assert!(!identities.join(" ").contains("secret"));
+    }
+
+    #[test]
+    fn parses_multiline_tfvars() {
+        let content = "# generated\nregion = \"us-east-1\"\ntags = {\n  team = \"data\"\n}\nsubnets = [\"a\", \"b\"]\n";
+        let entries = parse_tfvars(content);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], ("region".to_string(), "\"us-east-1\"".to_string()));
+        assert_eq!(entries[1].1, "{\n  team = \"data\"\n}");
+        assert_eq!(entries[2].0, "subnets");
+    }
+
+    #[test]
+    fn report_redacts_secrets_and_sensitive_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("variables.tf"),
+            "variable \"admin_user\" {\n  type = string\n  sensitive = true\n}\nvariable \"region\" {\n  type = string\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("terraform.tfvars"),
+            "admin_user = \"alice@example.com\"\nregion = \"eastus\"\ndatabricks_client_secret = \"s3cr3t\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("terraform.tfstate"),
+            r#"{"outputs": {"workspace_url": {"value": "https://adb-1.azuredatabricks.net", "type": "string"}, "token": {"value": "dapi", "type": "string", "sensitive": true}}}"#,
+        )
+        .unwrap();
+        record_template(dir.path(), "azure-simple").unwrap();
+        record_run(dir.path(), record("apply", &["Azure tenant: t1"]), "Plan: 1 to add, 0 to change, 0 to destroy.");
+        record_run(dir.path(), record("apply", &["Azure tenant: t1", "Databricks profile: p"]), "");
+
+        let report = build_report("demo", dir.path(), None);
+        assert_eq!(report.template.as_ref().map(|t| t.template_id.as_str()), Some("azure-simple"));
+        assert_eq!(report.identities, vec!["Azure tenant: t1", "Databricks profile: p"]);
+        assert_eq!(report.runs.len(), 2);
+        assert_eq!(report.runs[0].plan_summary.as_deref(), Some("Plan: 1 to add, 0 to change, 0 to destroy."));
+
+        let value = |name: &str| report.variables.iter().find(|v| v.name == name).unwrap().value.clone();
+        assert_eq!(value("admin_user"), REDACTED);
+        assert_eq!(value("databricks_client_secret"), REDACTED);
+        assert_eq!(value("region"), "\"eastus\"");
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("s3cr3t") && !json.contains("dapi") && !json.contains("alice@"));
+        assert!(json.contains("https://adb-1.azuredatabricks.net"));
+    }
+
+    #[test]
+    fn record_template_keeps_created_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = DeploymentMeta {
+            template_id: "aws-simple".to_string(),
+            templates_version: "0.0.1".to_string(),
+            created_at: 42,
+            updated_at: 42,
+            last_applied_by: Vec::new(),
+            last_applied_at: None,
+            run_settings: RunSettings::default(),
+            last_apply_environment: None,
+        };
+        fs::write(dir.path().join(META_FILE), serde_json::to_string(&meta).unwrap()).unwrap();
+        record_template(dir.path(), "aws-simple").unwrap();
+        let updated = read_meta(dir.path()).unwrap();
+        assert_eq!(updated.created_at, 42);
+        assert_eq!(updated.templates_version, TEMPLATES_VERSION);
+        assert!(updated.updated_at > 42);
+    }
+
+    #[test]
+    fn applying_identity_survives_template_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        record_applied_by(dir.path(), &["AWS principal: arn:aws:iam::1:user/a".to_string()]);
+        assert!(read_meta(dir.path()).is_none());
+
+        record_template(dir.path(), "aws-simple").unwrap();
+        record_applied_by(dir.path(), &["AWS principal: arn:aws:iam::1:user/a".to_string()]);
+        record_template(dir.path(), "aws-simple").unwrap();
+        let meta = read_meta(dir.path()).unwrap();
+        assert_eq!(meta.last_applied_by, vec!["AWS principal: arn:aws:iam::1:user/a"]);
+        assert!(meta.last_applied_at.is_some());
+
+        let mut run = record("apply", &["AWS profile: dev"]);
+        run.resolved_identities = meta.last_applied_by.clone();
+        record_run(dir.path(), run, "");
+        let report = build_report("demo", dir.path(), None);
+        assert_eq!(report.identities, vec!["AWS profile: dev", "AWS principal: arn:aws:iam::1:user/a"]);
+    }
+}
@@ -1,16 +1,19 @@
 //! AWS authentication and permission checking commands.
 
-use super::{CloudCredentials, CloudPermissionCheck};
+use super::preflight::{BLOCKER, WARNING};
+use super::cancellation::CancelToken;
+use super::cli_runner::{CliCommand, CliTool};
+use super::{CloudCredentials, CloudPermissionCheck, PreflightEntry, PreflightReport};
 use crate::dependencies;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use tauri::AppHandle;
 
-/// AWS CLI profile entry.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AwsProfile {
-    pub name: String,
-    pub is_sso: bool,
-}
+/// Guide to granting IAM permissions, linked from permission check remediation.
+const AWS_IAM_DOCS_URL: &str =
+    "https://docs.aws.amazon.com/IAM/latest/UserGuide/access_policies_manage-attach-detach.html";
+
+pub use crate::aws_config::{AwsProfile, AwsSsoSession};
+use crate::aws_config::{AwsConfig, AwsProfileKind};
 
 /// AWS STS caller identity.
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,122 +32,79 @@ fn validate_aws_profile_name(name: &str) -> bool {
             .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
 }
 
-/// List AWS profiles from `~/.aws/config` and `~/.aws/credentials`.
+/// List AWS profiles from `~/.aws/config` and `~/.aws/credentials`, with
+/// how each one obtains credentials.
 #[tauri::command]
 pub fn get_aws_profiles() -> Vec<AwsProfile> {
-    let mut profiles = Vec::new();
-    let mut seen = std::collections::HashSet::new();
-
-    if let Some(home) = dirs::home_dir() {
-        // Parse ~/.aws/config
-        let config_path = home.join(".aws").join("config");
-        if config_path.exists() {
-            if let Ok(content) = fs::read_to_string(&config_path) {
-                let mut current_name: Option<String> = None;
-                let mut profile_sso: std::collections::HashMap<String, bool> =
-                    std::collections::HashMap::new();
-
-                for line in content.lines() {
-                    let line = line.trim();
-                    if line.starts_with('[') && line.ends_with(']') {
-                        let section = &line[1..line.len() - 1];
-                        if section.starts_with("sso-session ") {
-                            current_name = None;
-                            continue;
-                        }
-                        let name = if section.starts_with("profile ") {
-                            section.strip_prefix("profile ").unwrap().to_string()
-                        } else {
-                            section.to_string()
-                        };
-                        profile_sso.entry(name.clone()).or_insert(false);
-                        current_name = Some(name);
-                    } else if let Some(ref name) = current_name {
-                        if line.starts_with("sso_start_url") || line.starts_with("sso_session") {
-                            profile_sso.insert(name.clone(), true);
-                        }
-                    }
-                }
-
-                for (name, is_sso) in &profile_sso {
-                    if !seen.contains(name) {
-                        profiles.push(AwsProfile {
-                            name: name.clone(),
-                            is_sso: *is_sso,
-                        });
-                        seen.insert(name.clone());
-                    }
-                }
+    AwsConfig::load().profiles()
+}
+
+/// List `[sso-session]` blocks from `~/.aws/config` with the profiles using each.
+#[tauri::command]
+pub fn get_aws_sso_sessions() -> Vec<AwsSsoSession> {
+    AwsConfig::load().sso_sessions()
+}
+
+/// `aws sso login` arguments for a profile or an explicit `sso-session`.
+/// Profiles that use an `sso-session` log in through the session, which
+/// signs in every profile sharing it.
+fn sso_login_target(config: &AwsConfig, profile: &str, sso_session: Option<&str>) -> Result<Vec<String>, String> {
+    let session = match sso_session.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(session) => Some(session.to_string()),
+        None if profile.is_empty() => None,
+        None => {
+            let Some(info) = config.profile(profile) else {
+                return Err(format!("AWS profile '{}' was not found in the AWS config", profile));
+            };
+            if info.kind != AwsProfileKind::Sso {
+                return Err(format!("AWS profile '{}' is not configured for SSO: {}", profile, info.description));
+            }
+            if let Some(warning) = info.warnings.first() {
+                return Err(format!("AWS profile '{}' can't sign in: {}", profile, warning));
             }
+            config.sso_session_of(profile)
         }
-
-        // Parse ~/.aws/credentials for additional profiles
-        let creds_path = home.join(".aws").join("credentials");
-        if creds_path.exists() {
-            if let Ok(content) = fs::read_to_string(&creds_path) {
-                for line in content.lines() {
-                    let line = line.trim();
-                    if line.starts_with('[') && line.ends_with(']') {
-                        let name = line[1..line.len() - 1].to_string();
-                        if !seen.contains(&name) {
-                            profiles.push(AwsProfile {
-                                name: name.clone(),
-                                is_sso: false,
-                            });
-                            seen.insert(name);
-                        }
-                    }
-                }
+    };
+    match session {
+        Some(session) => {
+            if !validate_aws_profile_name(&session) {
+                return Err("Invalid SSO session name".to_string());
             }
+            Ok(vec!["--sso-session".to_string(), session])
         }
+        None if profile.is_empty() => Ok(Vec::new()),
+        None => Ok(vec!["--profile".to_string(), profile.to_string()]),
     }
-
-    // Ensure "default" is first if it exists
-    profiles.sort_by(|a, b| {
-        if a.name == "default" {
-            std::cmp::Ordering::Less
-        } else if b.name == "default" {
-            std::cmp::Ordering::Greater
-        } else {
-            a.name.cmp(&b.name)
-        }
-    });
-
-    profiles
 }
 
 /// Get AWS identity for a profile using `aws sts get-caller-identity`.
 #[tauri::command]
 pub async fn get_aws_identity(profile: String) -> Result<AwsIdentity, String> {
+    super::run_blocking(move || get_aws_identity_blocking(profile)).await
+}
+
+fn get_aws_identity_blocking(profile: String) -> Result<AwsIdentity, String> {
     if !profile.is_empty() && !validate_aws_profile_name(&profile) {
         return Err("Invalid AWS profile name".to_string());
     }
 
-    let aws_path =
-        dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
-
-    let mut cmd = super::silent_cmd(&aws_path);
+    let mut cmd = CliCommand::new(CliTool::Aws)?;
     cmd.args(["sts", "get-caller-identity", "--output", "json"]);
 
     if !profile.is_empty() {
         cmd.args(["--profile", &profile]);
     }
 
-    let output = cmd
-        .output()
-        .map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    let output = cmd.run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("expired") || stderr.contains("Token") {
+    if !output.success {
+        if output.stderr.contains("expired") || output.stderr.contains("Token") {
             return Err(crate::errors::auth_expired("AWS"));
         }
-        return Err(format!("Not authenticated: {}", stderr.trim()));
+        return Err(format!("Not authenticated: {}", output.stderr.trim()));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse response: {}", e))?;
+    let json: serde_json::Value = output.json()?;
 
     Ok(AwsIdentity {
         account: json["Account"].as_str().unwrap_or("").to_string(),
@@ -154,99 +114,83 @@ pub async fn get_aws_identity(profile: String) -> Result<AwsIdentity, String> {
 }
 
 /// Trigger AWS SSO login for a profile. Supports cancellation via `cancel_cli_login`.
+///
+/// Runs with `--no-browser` so the verification URL and user code are
+/// captured and forwarded to the UI; the app opens the browser itself.
+/// Profiles that reference an `sso-session` (or an explicit `sso_session`)
+/// log in with `--sso-session`.
 #[tauri::command]
-pub async fn aws_sso_login(profile: String) -> Result<String, String> {
-    use super::CLI_LOGIN_PROCESS;
-    use std::time::{Duration, Instant};
-
+pub async fn aws_sso_login(app: AppHandle, profile: String, sso_session: Option<String>) -> Result<String, String> {
     if !profile.is_empty() && !validate_aws_profile_name(&profile) {
         return Err("Invalid AWS profile name".to_string());
     }
+    let target = sso_login_target(&AwsConfig::load(), &profile, sso_session.as_deref())?;
 
     let aws_path =
         dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
 
     let mut cmd = super::silent_cmd(&aws_path);
-    cmd.args(["sso", "login"]);
+    cmd.args(["sso", "login", "--no-browser"]).args(&target);
 
-    if !profile.is_empty() {
-        cmd.args(["--profile", &profile]);
-    }
-
-    let mut child = cmd
+    let child = cmd
+        .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
         .map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
 
-    super::acquire_login_slot(child.id()).map_err(|e| {
-        let _ = child.kill();
-        e
-    })?;
-
-    let timeout = Duration::from_secs(300);
-    let start = Instant::now();
-
-    let result = loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                let output = child.wait_with_output().map_err(|e| format!("Failed to read output: {}", e))?;
-                if !status.success() {
-                    let was_cancelled = super::lock_or_recover(&CLI_LOGIN_PROCESS).is_none();
-                    if was_cancelled {
-                        break Err("LOGIN_CANCELLED".to_string());
-                    }
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stderr_str = stderr.trim();
-                    if stderr_str.is_empty() || stderr_str.contains("Killed") || stderr_str.contains("terminated") {
-                        break Err("LOGIN_CANCELLED".to_string());
-                    }
-                    break Err(format!("SSO login failed: {}", stderr_str));
-                }
-                break Ok("SSO login completed successfully.".to_string());
-            }
-            Ok(None) => {
-                if start.elapsed() >= timeout {
-                    let _ = child.kill();
-                    break Err("SSO login timed out after 5 minutes. Please try again.".to_string());
-                }
-                tokio::time::sleep(Duration::from_millis(500)).await;
-            }
-            Err(e) => break Err(format!("Error waiting for AWS CLI: {}", e)),
-        }
-    };
-
-    super::release_login_slot();
-
-    result
+    let exit = super::login_flow::run_login_process(
+        &app,
+        "aws",
+        child,
+        std::time::Duration::from_secs(300),
+        "SSO login timed out after 5 minutes. Please try again.",
+    )
+    .await?;
+
+    if exit.cancelled {
+        return Err("LOGIN_CANCELLED".to_string());
+    }
+    if !exit.success {
+        return Err(format!("SSO login failed: {}", exit.stderr));
+    }
+    Ok("SSO login completed successfully.".to_string())
 }
 
-/// Apply AWS credentials from a `CloudCredentials` struct to a `Command` as env vars.
+/// AWS credentials from a `CloudCredentials` struct as env vars.
 /// Validates the profile name if present.
-fn apply_aws_credentials(cmd: &mut std::process::Command, credentials: &CloudCredentials) -> Result<(), String> {
+pub(super) fn aws_credential_env(credentials: &CloudCredentials) -> Result<Vec<(&'static str, String)>, String> {
+    let mut env = Vec::new();
     if let Some(profile) = &credentials.aws_profile {
         if !profile.is_empty() {
             if !validate_aws_profile_name(profile) {
                 return Err("Invalid AWS profile name".to_string());
             }
-            cmd.env("AWS_PROFILE", profile);
+            env.push(("AWS_PROFILE", profile.clone()));
         }
     }
     if let Some(key) = &credentials.aws_access_key_id {
         if !key.is_empty() {
-            cmd.env("AWS_ACCESS_KEY_ID", key);
+            env.push(("AWS_ACCESS_KEY_ID", key.clone()));
         }
     }
     if let Some(secret) = &credentials.aws_secret_access_key {
         if !secret.is_empty() {
-            cmd.env("AWS_SECRET_ACCESS_KEY", secret);
+            env.push(("AWS_SECRET_ACCESS_KEY", secret.clone()));
         }
     }
     if let Some(token) = &credentials.aws_session_token {
         if !token.is_empty() {
-            cmd.env("AWS_SESSION_TOKEN", token);
+            env.push(("AWS_SESSION_TOKEN", token.clone()));
         }
     }
+    Ok(env)
+}
+
+/// Apply AWS credentials from a `CloudCredentials` struct to a `Command` as env vars.
+/// Validates the profile name if present.
+pub(super) fn apply_aws_credentials(cmd: &mut std::process::Command, credentials: &CloudCredentials) -> Result<(), String> {
+    cmd.envs(aws_credential_env(credentials)?);
     Ok(())
 }
 
@@ -261,9 +205,13 @@ pub struct AwsVpc {
 /// List AWS VPCs in a region. Supports both profile and access-key auth via CloudCredentials.
 #[tauri::command]
 pub async fn get_aws_vpcs(credentials: CloudCredentials) -> Result<Vec<AwsVpc>, String> {
-    let aws_cli = match dependencies::find_aws_cli_path() {
-        Some(path) => path,
-        None => return Ok(vec![]),
+    super::run_blocking(move || get_aws_vpcs_blocking(credentials)).await
+}
+
+fn get_aws_vpcs_blocking(credentials: CloudCredentials) -> Result<Vec<AwsVpc>, String> {
+    let mut cmd = match CliCommand::new(CliTool::Aws) {
+        Ok(cmd) => cmd,
+        Err(_) => return Ok(vec![]),
     };
 
     let region = credentials
@@ -273,21 +221,17 @@ pub async fn get_aws_vpcs(credentials: CloudCredentials) -> Result<Vec<AwsVpc>,
         .cloned()
         .unwrap_or_else(|| "us-east-1".to_string());
 
-    let mut cmd = super::silent_cmd(&aws_cli);
-    cmd.args(["ec2", "describe-vpcs", "--region", &region, "--output", "json"]);
-    apply_aws_credentials(&mut cmd, &credentials)?;
+    cmd.args(["ec2", "describe-vpcs", "--region", &region, "--output", "json"])
+        .credentials(&credentials)?;
 
-    let output = cmd
-        .output()
-        .map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    let output = cmd.run()?;
 
-    if !output.status.success() {
+    if !output.success {
         return Ok(vec![]);
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let json: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse VPCs: {}", e))?;
+        serde_json::from_str(&output.stdout).map_err(|e| format!("Failed to parse VPCs: {}", e))?;
 
     let empty = vec![];
     let vpcs: Vec<AwsVpc> = json["Vpcs"]
@@ -318,6 +262,17 @@ pub async fn get_aws_vpcs(credentials: CloudCredentials) -> Result<Vec<AwsVpc>,
 #[tauri::command]
 pub async fn check_aws_permissions(
     credentials: CloudCredentials,
+    request_id: Option<String>,
+) -> Result<CloudPermissionCheck, String> {
+    super::cancellation::cancellable(request_id, |token| {
+        super::run_blocking(move || check_aws_permissions_blocking(credentials, token))
+    })
+    .await
+}
+
+fn check_aws_permissions_blocking(
+    credentials: CloudCredentials,
+    token: CancelToken,
 ) -> Result<CloudPermissionCheck, String> {
     let required_actions = vec![
         "ec2:CreateVpc",
@@ -345,37 +300,37 @@ pub async fn check_aws_permissions(
         "iam:PassRole",
     ];
 
-    let aws_cli = match dependencies::find_aws_cli_path() {
-        Some(path) => path,
-        None => {
-            return Ok(CloudPermissionCheck {
-                has_all_permissions: true,
-                checked_permissions: vec![],
-                missing_permissions: vec![],
-                message: "AWS CLI not installed. Permission check skipped.".to_string(),
-                is_warning: true,
-            });
+    let mut aws_cli = match CliCommand::new(CliTool::Aws) {
+        Ok(cmd) => cmd,
+        Err(_) => {
+            return Ok(CloudPermissionCheck::skipped(
+                PreflightEntry::skipped(
+                    "IAM policy simulation",
+                    "AWS CLI not installed. Permission check skipped.",
+                )
+                .remediation("Install the AWS CLI to verify IAM permissions before deploying."),
+            ));
         }
     };
 
+    aws_cli.cancel_token(&token);
+
     // Get caller identity to obtain the ARN
-    let mut identity_cmd = super::silent_cmd(&aws_cli);
-    identity_cmd.args(["sts", "get-caller-identity", "--output", "json"]);
-    apply_aws_credentials(&mut identity_cmd, &credentials)?;
+    let mut identity_cmd = aws_cli.clone();
+    identity_cmd
+        .args(["sts", "get-caller-identity", "--output", "json"])
+        .credentials(&credentials)?;
 
-    let identity_output = identity_cmd
-        .output()
-        .map_err(|e| format!("Failed to get AWS identity: {}", e))?;
+    let identity_output = identity_cmd.run()?;
 
-    if !identity_output.status.success() {
-        let stderr = String::from_utf8_lossy(&identity_output.stderr);
+    if !identity_output.success {
         return Err(format!(
             "Invalid AWS credentials: {}",
-            stderr.trim()
+            identity_output.stderr.trim()
         ));
     }
 
-    let identity_json: serde_json::Value = serde_json::from_slice(&identity_output.stdout)
+    let identity_json: serde_json::Value = serde_json::from_str(&identity_output.stdout)
         .map_err(|e| format!("Failed to parse identity: {}", e))?;
 
     let caller_arn = identity_json["Arn"]
@@ -383,7 +338,7 @@ pub async fn check_aws_permissions(
         .ok_or("No ARN in identity response")?;
 
     // Build the simulate-principal-policy command
-    let mut simulate_cmd = super::silent_cmd(&aws_cli);
+    let mut simulate_cmd = aws_cli;
     simulate_cmd.args([
         "iam",
         "simulate-principal-policy",
@@ -395,60 +350,40 @@ pub async fn check_aws_permissions(
     for action in &required_actions {
         simulate_cmd.arg(action);
     }
-    simulate_cmd.args(["--output", "json"]);
-    apply_aws_credentials(&mut simulate_cmd, &credentials)?;
+    simulate_cmd.args(["--output", "json"]).credentials(&credentials)?;
 
-    let simulate_output = simulate_cmd
-        .output()
-        .map_err(|e| format!("Failed to simulate policy: {}", e))?;
+    let simulate_output = simulate_cmd.run()?;
 
-    if !simulate_output.status.success() {
-        let stderr = String::from_utf8_lossy(&simulate_output.stderr);
+    if !simulate_output.success {
+        let stderr = &simulate_output.stderr;
 
         if stderr.contains("AccessDenied") || stderr.contains("not authorized") {
-            return Ok(CloudPermissionCheck {
-                has_all_permissions: true,
-                checked_permissions: vec![],
-                missing_permissions: vec![],
-                message: "Unable to check permissions (missing iam:SimulatePrincipalPolicy). Proceeding without verification.".to_string(),
-                is_warning: true,
-            });
+            return Ok(CloudPermissionCheck::skipped(
+                PreflightEntry::skipped(
+                    "IAM policy simulation",
+                    "Unable to check permissions (missing iam:SimulatePrincipalPolicy). Proceeding without verification.",
+                )
+                .remediation(format!("Allow iam:SimulatePrincipalPolicy for {}.", caller_arn))
+                .docs(AWS_IAM_DOCS_URL),
+            ));
         }
 
-        return Ok(CloudPermissionCheck {
-            has_all_permissions: true,
-            checked_permissions: vec![],
-            missing_permissions: vec![],
-            message: format!(
+        return Ok(CloudPermissionCheck::skipped(PreflightEntry::skipped(
+            "IAM policy simulation",
+            &format!(
                 "Permission check failed: {}. Proceeding without verification.",
                 stderr.trim()
             ),
-            is_warning: true,
-        });
+        )));
     }
 
     // Parse simulation results
-    let results_json: serde_json::Value = serde_json::from_slice(&simulate_output.stdout)
+    let results_json: serde_json::Value = serde_json::from_str(&simulate_output.stdout)
         .map_err(|e| format!("Failed to parse simulation results: {}", e))?;
 
-    let mut checked_permissions = Vec::new();
-    let mut missing_permissions = Vec::new();
-
-    if let Some(evaluations) = results_json["EvaluationResults"].as_array() {
-        for eval in evaluations {
-            let action = eval["EvalActionName"].as_str().unwrap_or("unknown");
-            let decision = eval["EvalDecision"].as_str().unwrap_or("unknown");
-
-            checked_permissions.push(action.to_string());
-
-            if decision != "allowed" {
-                missing_permissions.push(action.to_string());
-            }
-        }
-    }
-
-    let has_all = missing_permissions.is_empty();
-    let message = if has_all {
+    let report = aws_permission_report(&results_json, caller_arn);
+    let missing_permissions = report.failed();
+    let message = if missing_permissions.is_empty() {
         "All required AWS permissions verified.".to_string()
     } else {
         format!(
@@ -458,13 +393,27 @@ pub async fn check_aws_permissions(
         )
     };
 
-    Ok(CloudPermissionCheck {
-        has_all_permissions: has_all,
-        checked_permissions,
-        missing_permissions,
-        message,
-        is_warning: true,
-    })
+    Ok(CloudPermissionCheck::from_report(report, message))
+}
+
+/// Checklist from `simulate-principal-policy` output. An explicit deny is a
+/// blocker since no other policy can override it; implicit denials are
+/// warnings, as the simulator can't see permission boundaries or SCP exceptions.
+fn aws_permission_report(results: &serde_json::Value, caller_arn: &str) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    for eval in results["EvaluationResults"].as_array().into_iter().flatten() {
+        let action = eval["EvalActionName"].as_str().unwrap_or("unknown");
+        let decision = eval["EvalDecision"].as_str().unwrap_or("unknown");
+        let severity = if decision == "explicitDeny" { BLOCKER } else { WARNING };
+        report.push(if decision == "allowed" {
+            PreflightEntry::pass(action, "Allowed")
+        } else {
+            PreflightEntry::fail(action, severity, &format!("Simulated decision: {}", decision))
+                .remediation(format!("Grant {} to {} in an IAM policy.", action, caller_arn))
+                .docs(AWS_IAM_DOCS_URL)
+        });
+    }
+    report
 }
 
 #[cfg(test)]
@@ -524,4 +473,61 @@ mod tests {
     fn invalid_profile_name_path_traversal() {
         assert!(!validate_aws_profile_name("../etc/passwd"));
     }
+
+    // ── aws_permission_report ───────────────────────────────────────────
+
+    #[test]
+    fn permission_report_flags_denied_actions_as_warnings() {
+        let results = serde_json::json!({"EvaluationResults": [
+            {"EvalActionName": "ec2:CreateVpc", "EvalDecision": "allowed"},
+            {"EvalActionName": "iam:PassRole", "EvalDecision": "implicitDeny"}
+        ]});
+        let report = aws_permission_report(&results, "arn:aws:iam::123:user/dev");
+
+        assert_eq!(report.failed(), vec!["iam:PassRole"]);
+        assert_eq!(report.status(), "warn");
+        let denied = &report.entries[1];
+        assert!(denied.message.contains("implicitDeny"));
+        assert!(denied.remediation.as_deref().unwrap().contains("arn:aws:iam::123:user/dev"));
+        assert_eq!(denied.docs_url.as_deref(), Some(AWS_IAM_DOCS_URL));
+
+        let check = CloudPermissionCheck::from_report(report, "");
+        assert_eq!(check.checked_permissions, vec!["ec2:CreateVpc", "iam:PassRole"]);
+        assert!(check.is_warning);
+    }
+
+    #[test]
+    fn permission_report_blocks_on_explicit_deny() {
+        let results = serde_json::json!({"EvaluationResults": [
+            {"EvalActionName": "ec2:CreateVpc", "EvalDecision": "explicitDeny"},
+            {"EvalActionName": "iam:PassRole", "EvalDecision": "implicitDeny"}
+        ]});
+        let report = aws_permission_report(&results, "arn:aws:iam::123:user/dev");
+
+        assert_eq!(report.entries[0].severity, BLOCKER);
+        assert_eq!(report.entries[1].severity, WARNING);
+        assert!(!CloudPermissionCheck::from_report(report, "").is_warning);
+    }
+
+    // ── sso_login_target ────────────────────────────────────────────────
+
+    const SSO_CONFIG: &str = "[sso-session corp]\nsso_start_url = https://corp.awsapps.com/start\nsso_region = us-east-1\n\n[profile dev]\nsso_session = corp\nsso_account_id = 111111111111\nsso_role_name = Admin\n\n[profile legacy]\nsso_start_url = https://old.awsapps.com/start\nsso_account_id = 222222222222\nsso_role_name = Admin\n\n[profile broken]\nsso_session = gone\nsso_account_id = 1\n";
+
+    #[test]
+    fn sso_login_uses_session_of_profile() {
+        let config = AwsConfig::parse(SSO_CONFIG, "[static]\naws_access_key_id = AKIA\naws_secret_access_key = x\n");
+        assert_eq!(sso_login_target(&config, "dev", None).unwrap(), vec!["--sso-session", "corp"]);
+        assert_eq!(sso_login_target(&config, "legacy", None).unwrap(), vec!["--profile", "legacy"]);
+        assert_eq!(sso_login_target(&config, "", Some("corp")).unwrap(), vec!["--sso-session", "corp"]);
+        assert!(sso_login_target(&config, "", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sso_login_rejects_non_sso_profiles() {
+        let config = AwsConfig::parse(SSO_CONFIG, "[static]\naws_access_key_id = AKIA\naws_secret_access_key = x\n");
+        assert!(sso_login_target(&config, "static", None).unwrap_err().contains("not configured for SSO"));
+        assert!(sso_login_target(&config, "broken", None).unwrap_err().contains("gone"));
+        assert!(sso_login_target(&config, "missing", None).is_err());
+        assert!(sso_login_target(&config, "dev", Some("bad name;")).is_err());
+    }
 }
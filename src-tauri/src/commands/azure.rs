@@ -1,9 +1,19 @@
 //! Azure authentication and permission checking commands.
 
-use super::{http_client, is_valid_uuid, CLI_LOGIN_PROCESS};
-use super::{CloudCredentials, CloudPermissionCheck};
+use super::cancellation::CancelToken;
+use super::cli_runner::{CliCommand, CliTool};
+use super::metadata_cache;
+use super::{http_client, is_valid_uuid};
+use crate::endpoints::https_url;
+use super::preflight::WARNING;
+use super::{CloudCredentials, CloudPermissionCheck, PreflightEntry, PreflightReport};
 use crate::dependencies;
 use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Guide to assigning Azure roles, linked from permission check remediation.
+const AZURE_RBAC_DOCS_URL: &str =
+    "https://learn.microsoft.com/en-us/azure/role-based-access-control/role-assignments-cli";
 
 /// Azure subscription descriptor.
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,7 +34,7 @@ pub struct AzureAccount {
 }
 
 /// Azure resource group descriptor.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AzureResourceGroup {
     pub name: String,
     pub location: String,
@@ -35,28 +45,104 @@ fn validate_azure_subscription_id(id: &str) -> bool {
     is_valid_uuid(id)
 }
 
+/// Azure Resource Manager token for the service principal in `credentials`
+/// (client credentials grant).
+pub(super) async fn arm_token_sp(http_client: &reqwest::Client, credentials: &CloudCredentials) -> Result<String, String> {
+    let required = |value: &Option<String>, label: &str| {
+        value.clone().filter(|s| !s.is_empty()).ok_or(format!("Azure {} is required", label))
+    };
+    let tenant_id = required(&credentials.azure_tenant_id, "Tenant ID")?;
+    let client_id = required(&credentials.azure_client_id, "Client ID")?;
+    let client_secret = required(&credentials.azure_client_secret, "Client Secret")?;
+
+    let token_url = https_url("login.microsoftonline.com", &format!("/{}/oauth2/v2.0/token", tenant_id));
+    let token_response = http_client
+        .post(&token_url)
+        .form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+            ("scope", "https://management.azure.com/.default"),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to get Azure AD token: {}", e))?;
+
+    if !token_response.status().is_success() {
+        let status = token_response.status();
+        let error_text = token_response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Azure AD authentication failed ({}): {}",
+            status, error_text
+        ));
+    }
+
+    let token_json: serde_json::Value = token_response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Azure AD token response: {}", e))?;
+
+    token_json["access_token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "No access token in Azure AD response".to_string())
+}
+
+/// GET an ARM collection, following `nextLink`.
+pub(super) async fn arm_list(
+    http_client: &reqwest::Client,
+    access_token: &str,
+    url: String,
+    what: &str,
+) -> Result<Vec<serde_json::Value>, String> {
+    let mut items = Vec::new();
+    let mut next = Some(url);
+    while let Some(url) = next {
+        let response = http_client
+            .get(&url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to list {}: {}", what, e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("Failed to list {} ({}): {}", what, status, error_text));
+        }
+
+        let page: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse {} response: {}", what, e))?;
+        if let Some(values) = page["value"].as_array() {
+            items.extend(values.iter().cloned());
+        }
+        next = page["nextLink"].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    }
+    Ok(items)
+}
+
 /// Get Azure CLI login status using `az account show`.
 #[tauri::command]
 pub fn get_azure_account() -> Result<AzureAccount, String> {
     let az_path = dependencies::find_azure_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
 
-    let output = super::silent_cmd(&az_path)
+    let output = CliCommand::with_path(CliTool::Azure, &az_path)
         .args(["account", "show", "--output", "json"])
-        .output()
-        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+        .run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
+        let stderr = &output.stderr;
         if stderr.contains("az login") || stderr.contains("not logged in") {
             return Err(crate::errors::not_logged_in("Azure"));
         }
         return Err(format!("Azure CLI error: {}", stderr.trim()));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let json: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse response: {}", e))?;
+        serde_json::from_str(&output.stdout).map_err(|e| format!("Failed to parse response: {}", e))?;
 
     let user = json["user"]["name"]
         .as_str()
@@ -79,94 +165,101 @@ pub fn get_azure_account() -> Result<AzureAccount, String> {
     })
 }
 
-/// Get list of Azure subscriptions.
+/// Get list of Azure subscriptions. Cached unless `refresh` is set.
 #[tauri::command]
-pub fn get_azure_subscriptions() -> Result<Vec<AzureSubscription>, String> {
-    let az_path = dependencies::find_azure_cli_path()
-        .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
-
-    let output = super::silent_cmd(&az_path)
-        .args(["account", "list", "--output", "json"])
-        .output()
-        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Azure CLI error: {}", stderr.trim()));
-    }
+pub fn get_azure_subscriptions(refresh: Option<bool>) -> Result<Vec<AzureSubscription>, String> {
+    let key = metadata_cache::key("azure_subscriptions", None, &[]);
+    metadata_cache::get_or_fetch(key, refresh.unwrap_or(false), || {
+        let output = CliCommand::new(CliTool::Azure)?
+            .args(["account", "list", "--output", "json"])
+            .run_checked()?;
+        let json: Vec<serde_json::Value> = output.json()?;
+
+        let subscriptions: Vec<AzureSubscription> = json
+            .iter()
+            .map(|sub| AzureSubscription {
+                id: sub["id"].as_str().unwrap_or("").to_string(),
+                name: sub["name"].as_str().unwrap_or("").to_string(),
+                is_default: sub["isDefault"].as_bool().unwrap_or(false),
+                tenant_id: sub["tenantId"].as_str().unwrap_or("").to_string(),
+            })
+            .collect();
+
+        Ok(subscriptions)
+    })
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: Vec<serde_json::Value> =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse response: {}", e))?;
+/// List Azure subscriptions visible to a Service Principal via the ARM REST API,
+/// for users without an Azure CLI login. The subscription in `credentials`,
+/// if any, is reported as the default. Cached unless `refresh` is set.
+#[tauri::command]
+pub async fn get_azure_subscriptions_sp(
+    credentials: CloudCredentials,
+    refresh: Option<bool>,
+) -> Result<Vec<AzureSubscription>, String> {
+    let key = metadata_cache::key("azure_subscriptions", Some(&credentials), &[]);
+    let items = metadata_cache::get_or_fetch_async(key, refresh.unwrap_or(false), async {
+        let http_client = http_client()?;
+        let access_token = arm_token_sp(&http_client, &credentials).await?;
+
+        let url = https_url("management.azure.com", "/subscriptions?api-version=2022-12-01");
+        arm_list(&http_client, &access_token, url, "subscriptions").await
+    })
+    .await?;
 
-    let subscriptions: Vec<AzureSubscription> = json
+    let selected = credentials.azure_subscription_id.as_deref().unwrap_or_default();
+    let mut subscriptions: Vec<AzureSubscription> = items
         .iter()
-        .map(|sub| AzureSubscription {
-            id: sub["id"].as_str().unwrap_or("").to_string(),
-            name: sub["name"].as_str().unwrap_or("").to_string(),
-            is_default: sub["isDefault"].as_bool().unwrap_or(false),
-            tenant_id: sub["tenantId"].as_str().unwrap_or("").to_string(),
+        // Disabled and deleted subscriptions can't be deployed to
+        .filter(|sub| matches!(sub["state"].as_str(), None | Some("Enabled") | Some("PastDue") | Some("Warned")))
+        .map(|sub| {
+            let id = sub["subscriptionId"].as_str().unwrap_or("").to_string();
+            AzureSubscription {
+                is_default: !selected.is_empty() && id.eq_ignore_ascii_case(selected),
+                name: sub["displayName"].as_str().unwrap_or("").to_string(),
+                tenant_id: sub["tenantId"].as_str().unwrap_or("").to_string(),
+                id,
+            }
         })
         .collect();
 
+    subscriptions.sort_by_key(|s| s.name.to_lowercase());
+
     Ok(subscriptions)
 }
 
 /// Trigger Azure CLI login with a 5-minute timeout. Supports cancellation via `cancel_cli_login`.
+/// Device-code prompts are forwarded to the frontend (see [`super::login_flow`]).
 #[tauri::command]
-pub async fn azure_login() -> Result<String, String> {
-    use std::time::{Duration, Instant};
-
+pub async fn azure_login(app: AppHandle) -> Result<String, String> {
     let az_path = dependencies::find_azure_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
 
-    let mut child = super::silent_cmd(&az_path)
+    let child = super::silent_cmd(&az_path)
         .args(["login"])
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
         .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
 
-    super::acquire_login_slot(child.id()).map_err(|e| {
-        let _ = child.kill();
-        e
-    })?;
-
-    let timeout = Duration::from_secs(300);
-    let start = Instant::now();
-
-    let result = loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                let output = child.wait_with_output().map_err(|e| format!("Failed to read output: {}", e))?;
-                if !status.success() {
-                    let was_cancelled = super::lock_or_recover(&CLI_LOGIN_PROCESS).is_none();
-                    if was_cancelled {
-                        break Err("LOGIN_CANCELLED".to_string());
-                    }
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stderr_str = stderr.trim();
-                    if stderr_str.is_empty() || stderr_str.contains("Killed") || stderr_str.contains("terminated") {
-                        break Err("LOGIN_CANCELLED".to_string());
-                    }
-                    break Err(format!("Azure login failed: {}", stderr_str));
-                }
-                break Ok("Azure login completed successfully.".to_string());
-            }
-            Ok(None) => {
-                if start.elapsed() >= timeout {
-                    let _ = child.kill();
-                    break Err("Azure login timed out after 5 minutes. Please try again.".to_string());
-                }
-                tokio::time::sleep(Duration::from_millis(500)).await;
-            }
-            Err(e) => break Err(format!("Error waiting for Azure CLI: {}", e)),
-        }
-    };
-
-    super::release_login_slot();
-
-    result
+    let exit = super::login_flow::run_login_process(
+        &app,
+        "azure",
+        child,
+        std::time::Duration::from_secs(300),
+        "Azure login timed out after 5 minutes. Please try again.",
+    )
+    .await?;
+
+    if exit.cancelled {
+        return Err("LOGIN_CANCELLED".to_string());
+    }
+    if !exit.success {
+        return Err(format!("Azure login failed: {}", exit.stderr));
+    }
+    // Lists cached for the previous CLI user no longer apply
+    metadata_cache::clear();
+    Ok("Azure login completed successfully.".to_string())
 }
 
 /// Set the active Azure subscription.
@@ -179,43 +272,50 @@ pub fn set_azure_subscription(subscription_id: String) -> Result<(), String> {
     let az_path = dependencies::find_azure_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
 
-    let output = super::silent_cmd(&az_path)
+    let output = CliCommand::with_path(CliTool::Azure, &az_path)
         .args(["account", "set", "--subscription", &subscription_id])
-        .output()
-        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+        .run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
+        let stderr = &output.stderr;
         return Err(format!(
             "Failed to set subscription: {}",
             stderr.trim()
         ));
     }
 
+    // The cached subscription list still marks the old default
+    metadata_cache::remove(&metadata_cache::key("azure_subscriptions", None, &[]));
     Ok(())
 }
 
-/// List Azure resource groups using `az group list`.
+/// List Azure resource groups using `az group list`. Cached unless `refresh` is set.
 #[tauri::command]
-pub fn get_azure_resource_groups(subscription_id: String) -> Result<Vec<AzureResourceGroup>, String> {
+pub fn get_azure_resource_groups(
+    subscription_id: String,
+    refresh: Option<bool>,
+) -> Result<Vec<AzureResourceGroup>, String> {
+    let key = metadata_cache::key("azure_resource_groups", None, &[&subscription_id]);
+    metadata_cache::get_or_fetch(key, refresh.unwrap_or(false), || list_azure_resource_groups(&subscription_id))
+}
+
+fn list_azure_resource_groups(subscription_id: &str) -> Result<Vec<AzureResourceGroup>, String> {
     let az_path = dependencies::find_azure_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
 
-    let output = super::silent_cmd(&az_path)
-        .args(["group", "list", "--subscription", &subscription_id, "--output", "json"])
-        .output()
-        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+    let output = CliCommand::with_path(CliTool::Azure, &az_path)
+        .args(["group", "list", "--subscription", subscription_id, "--output", "json"])
+        .run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
+        let stderr = &output.stderr;
         return Err(format!(
             "Failed to list resource groups: {}",
             stderr.trim()
         ));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value = serde_json::from_str(&stdout)
+    let json: serde_json::Value = serde_json::from_str(&output.stdout)
         .map_err(|e| format!("Failed to parse resource groups: {}", e))?;
 
     let empty = vec![];
@@ -235,28 +335,20 @@ pub fn get_azure_resource_groups(subscription_id: String) -> Result<Vec<AzureRes
 }
 
 /// List Azure resource groups using Service Principal credentials via Azure ARM REST API.
+/// Cached unless `refresh` is set.
 #[tauri::command]
 pub async fn get_azure_resource_groups_sp(
     credentials: CloudCredentials,
+    refresh: Option<bool>,
 ) -> Result<Vec<AzureResourceGroup>, String> {
-    let tenant_id = credentials
-        .azure_tenant_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Tenant ID is required")?;
-
-    let client_id = credentials
-        .azure_client_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client ID is required")?;
-
-    let client_secret = credentials
-        .azure_client_secret
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client Secret is required")?;
+    let key = metadata_cache::key("azure_resource_groups", Some(&credentials), &[]);
+    metadata_cache::get_or_fetch_async(key, refresh.unwrap_or(false), list_azure_resource_groups_sp(&credentials))
+        .await
+}
 
+async fn list_azure_resource_groups_sp(
+    credentials: &CloudCredentials,
+) -> Result<Vec<AzureResourceGroup>, String> {
     let subscription_id = credentials
         .azure_subscription_id
         .as_ref()
@@ -264,44 +356,9 @@ pub async fn get_azure_resource_groups_sp(
         .ok_or("Azure Subscription ID is required")?;
 
     let http_client = http_client()?;
+    let access_token = arm_token_sp(&http_client, credentials).await?;
 
-    // Step 1: Get Azure AD token
-    let token_url = format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-        tenant_id
-    );
-
-    let token_response = http_client
-        .post(&token_url)
-        .form(&[
-            ("grant_type", "client_credentials"),
-            ("client_id", client_id.as_str()),
-            ("client_secret", client_secret.as_str()),
-            ("scope", "https://management.azure.com/.default"),
-        ])
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get Azure AD token: {}", e))?;
-
-    if !token_response.status().is_success() {
-        let status = token_response.status();
-        let error_text = token_response.text().await.unwrap_or_default();
-        return Err(format!(
-            "Azure AD authentication failed ({}): {}",
-            status, error_text
-        ));
-    }
-
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Azure AD token response: {}", e))?;
-
-    let access_token = token_json["access_token"]
-        .as_str()
-        .ok_or("No access token in Azure AD response")?;
-
-    // Step 2: List resource groups via ARM API
+    // List resource groups via ARM API
     let rg_url = format!(
         "https://management.azure.com/subscriptions/{}/resourcegroups?api-version=2021-04-01",
         subscription_id
@@ -309,7 +366,7 @@ pub async fn get_azure_resource_groups_sp(
 
     let rg_response = http_client
         .get(&rg_url)
-        .bearer_auth(access_token)
+        .bearer_auth(&access_token)
         .send()
         .await
         .map_err(|e| format!("Failed to list resource groups: {}", e))?;
@@ -344,6 +401,90 @@ pub async fn get_azure_resource_groups_sp(
     Ok(groups)
 }
 
+/// Azure region available to a subscription.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AzureLocation {
+    /// Programmatic name used by Terraform, e.g. `eastus2`.
+    pub name: String,
+    pub display_name: String,
+    /// Geography group, e.g. `US`, `Europe`.
+    pub geography: String,
+}
+
+/// Deployable regions from an `az account list-locations` or ARM
+/// `locations` response, sorted by display name. Logical regions (edge
+/// zones, staging) are skipped.
+fn parse_locations(items: &[serde_json::Value]) -> Vec<AzureLocation> {
+    let mut locations: Vec<AzureLocation> = items
+        .iter()
+        .filter(|loc| matches!(loc["metadata"]["regionType"].as_str(), None | Some("Physical")))
+        .filter_map(|loc| {
+            let name = loc["name"].as_str().filter(|s| !s.is_empty())?.to_string();
+            Some(AzureLocation {
+                display_name: loc["displayName"].as_str().unwrap_or(&name).to_string(),
+                geography: loc["metadata"]["geographyGroup"].as_str().unwrap_or("").to_string(),
+                name,
+            })
+        })
+        .collect();
+    locations.sort_by_key(|l| l.display_name.to_lowercase());
+    locations
+}
+
+/// List regions available to a subscription, for the region dropdown of
+/// Azure templates. Uses the Service Principal in `credentials` when one is
+/// set, otherwise `az account list-locations`. Cached unless `refresh` is set.
+#[tauri::command]
+pub async fn get_azure_locations(
+    subscription_id: String,
+    credentials: Option<CloudCredentials>,
+    refresh: Option<bool>,
+) -> Result<Vec<AzureLocation>, String> {
+    if !validate_azure_subscription_id(&subscription_id) {
+        return Err("Invalid subscription ID format".to_string());
+    }
+
+    let credentials = credentials.filter(|c| c.azure_client_secret.as_ref().is_some_and(|s| !s.is_empty()));
+    let key = metadata_cache::key("azure_locations", credentials.as_ref(), &[&subscription_id]);
+    metadata_cache::get_or_fetch_async(key, refresh.unwrap_or(false), list_azure_locations(subscription_id, credentials))
+        .await
+}
+
+async fn list_azure_locations(
+    subscription_id: String,
+    credentials: Option<CloudCredentials>,
+) -> Result<Vec<AzureLocation>, String> {
+    if let Some(credentials) = credentials {
+        let http_client = http_client()?;
+        let access_token = arm_token_sp(&http_client, &credentials).await?;
+        let url = https_url(
+            "management.azure.com",
+            &format!("/subscriptions/{}/locations?api-version=2022-12-01", subscription_id),
+        );
+        let items = arm_list(&http_client, &access_token, url, "locations").await?;
+        return Ok(parse_locations(&items));
+    }
+
+    super::run_blocking(move || {
+        let az_path = dependencies::find_azure_cli_path()
+            .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
+
+        let output = CliCommand::with_path(CliTool::Azure, &az_path)
+            .args(["account", "list-locations", "--subscription", &subscription_id, "--output", "json"])
+            .run()?;
+
+        if !output.success {
+            let stderr = &output.stderr;
+            return Err(format!("Failed to list locations: {}", stderr.trim()));
+        }
+
+        let json: Vec<serde_json::Value> = serde_json::from_str(&output.stdout)
+            .map_err(|e| format!("Failed to parse locations: {}", e))?;
+        Ok(parse_locations(&json))
+    })
+    .await
+}
+
 /// Azure Virtual Network descriptor.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct AzureVnet {
@@ -359,19 +500,17 @@ pub fn get_azure_vnets(subscription_id: String) -> Result<Vec<AzureVnet>, String
     let az_path = dependencies::find_azure_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
 
-    let output = super::silent_cmd(&az_path)
+    let output = CliCommand::with_path(CliTool::Azure, &az_path)
         .args(["network", "vnet", "list", "--subscription", &subscription_id, "--output", "json"])
-        .output()
-        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+        .run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
+        let stderr = &output.stderr;
         return Err(format!("Failed to list VNets: {}", stderr.trim()));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let json: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse VNets: {}", e))?;
+        serde_json::from_str(&output.stdout).map_err(|e| format!("Failed to parse VNets: {}", e))?;
 
     let empty = vec![];
     let vnets: Vec<AzureVnet> = json
@@ -399,24 +538,6 @@ pub fn get_azure_vnets(subscription_id: String) -> Result<Vec<AzureVnet>, String
 pub async fn get_azure_vnets_sp(
     credentials: CloudCredentials,
 ) -> Result<Vec<AzureVnet>, String> {
-    let tenant_id = credentials
-        .azure_tenant_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Tenant ID is required")?;
-
-    let client_id = credentials
-        .azure_client_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client ID is required")?;
-
-    let client_secret = credentials
-        .azure_client_secret
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client Secret is required")?;
-
     let subscription_id = credentials
         .azure_subscription_id
         .as_ref()
@@ -424,41 +545,7 @@ pub async fn get_azure_vnets_sp(
         .ok_or("Azure Subscription ID is required")?;
 
     let http_client = http_client()?;
-
-    let token_url = format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-        tenant_id
-    );
-
-    let token_response = http_client
-        .post(&token_url)
-        .form(&[
-            ("grant_type", "client_credentials"),
-            ("client_id", client_id.as_str()),
-            ("client_secret", client_secret.as_str()),
-            ("scope", "https://management.azure.com/.default"),
-        ])
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get Azure AD token: {}", e))?;
-
-    if !token_response.status().is_success() {
-        let status = token_response.status();
-        let error_text = token_response.text().await.unwrap_or_default();
-        return Err(format!(
-            "Azure AD authentication failed ({}): {}",
-            status, error_text
-        ));
-    }
-
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Azure AD token response: {}", e))?;
-
-    let access_token = token_json["access_token"]
-        .as_str()
-        .ok_or("No access token in Azure AD response")?;
+    let access_token = arm_token_sp(&http_client, &credentials).await?;
 
     let vnet_url = format!(
         "https://management.azure.com/subscriptions/{}/providers/Microsoft.Network/virtualNetworks?api-version=2023-05-01",
@@ -467,7 +554,7 @@ pub async fn get_azure_vnets_sp(
 
     let vnet_response = http_client
         .get(&vnet_url)
-        .bearer_auth(access_token)
+        .bearer_auth(&access_token)
         .send()
         .await
         .map_err(|e| format!("Failed to list VNets: {}", e))?;
@@ -546,13 +633,11 @@ pub fn check_resource_names_available(
     let mut conflicts = Vec::new();
 
     for name in &names {
-        let output = super::silent_cmd(&az_path)
+        let output = CliCommand::with_path(CliTool::Azure, &az_path)
             .args(["group", "show", "-n", name, "--subscription", &subscription_id, "--output", "json"])
-            .output()
-            .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let json: serde_json::Value = serde_json::from_str(&stdout).unwrap_or_default();
+            .run()?;
+        if output.success {
+            let json: serde_json::Value = serde_json::from_str(&output.stdout).unwrap_or_default();
             let tag_value = rg_deployer_tag_value(&json);
             conflicts.push(ResourceNameConflict {
                 name: name.clone(),
@@ -572,24 +657,6 @@ pub async fn check_resource_names_available_sp(
     credentials: CloudCredentials,
     names: Vec<String>,
 ) -> Result<Vec<ResourceNameConflict>, String> {
-    let tenant_id = credentials
-        .azure_tenant_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Tenant ID is required")?;
-
-    let client_id = credentials
-        .azure_client_id
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client ID is required")?;
-
-    let client_secret = credentials
-        .azure_client_secret
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .ok_or("Azure Client Secret is required")?;
-
     let subscription_id = credentials
         .azure_subscription_id
         .as_ref()
@@ -597,41 +664,7 @@ pub async fn check_resource_names_available_sp(
         .ok_or("Azure Subscription ID is required")?;
 
     let http_client = http_client()?;
-
-    let token_url = format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-        tenant_id
-    );
-
-    let token_response = http_client
-        .post(&token_url)
-        .form(&[
-            ("grant_type", "client_credentials"),
-            ("client_id", client_id.as_str()),
-            ("client_secret", client_secret.as_str()),
-            ("scope", "https://management.azure.com/.default"),
-        ])
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get Azure AD token: {}", e))?;
-
-    if !token_response.status().is_success() {
-        let status = token_response.status();
-        let error_text = token_response.text().await.unwrap_or_default();
-        return Err(format!(
-            "Azure AD authentication failed ({}): {}",
-            status, error_text
-        ));
-    }
-
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Azure AD token response: {}", e))?;
-
-    let access_token = token_json["access_token"]
-        .as_str()
-        .ok_or("No access token in Azure AD response")?;
+    let access_token = arm_token_sp(&http_client, &credentials).await?;
 
     let mut conflicts = Vec::new();
 
@@ -643,7 +676,7 @@ pub async fn check_resource_names_available_sp(
 
         let rg_response = http_client
             .get(&rg_url)
-            .bearer_auth(access_token)
+            .bearer_auth(&access_token)
             .send()
             .await
             .map_err(|e| format!("Failed to check resource group '{}': {}", name, e))?;
@@ -670,6 +703,17 @@ pub async fn check_resource_names_available_sp(
 #[tauri::command]
 pub async fn check_azure_permissions(
     credentials: CloudCredentials,
+    request_id: Option<String>,
+) -> Result<CloudPermissionCheck, String> {
+    super::cancellation::cancellable(request_id, |token| {
+        super::run_blocking(move || check_azure_permissions_blocking(credentials, token))
+    })
+    .await
+}
+
+fn check_azure_permissions_blocking(
+    credentials: CloudCredentials,
+    token: CancelToken,
 ) -> Result<CloudPermissionCheck, String> {
     let required_roles = vec![
         "Contributor".to_string(),
@@ -685,13 +729,13 @@ pub async fn check_azure_permissions(
     let az_cli = match dependencies::find_azure_cli_path() {
         Some(path) => path,
         None => {
-            return Ok(CloudPermissionCheck {
-                has_all_permissions: true,
-                checked_permissions: vec![],
-                missing_permissions: vec![],
-                message: "Azure CLI not installed. Permission check skipped.".to_string(),
-                is_warning: true,
-            });
+            return Ok(CloudPermissionCheck::skipped(
+                PreflightEntry::skipped(
+                    "Role assignments",
+                    "Azure CLI not installed. Permission check skipped.",
+                )
+                .remediation("Install the Azure CLI to verify role assignments before deploying."),
+            ));
         }
     };
 
@@ -702,15 +746,13 @@ pub async fn check_azure_permissions(
         .ok_or("Azure subscription ID is required for permission check")?;
 
     // Get current signed-in principal info
-    let mut account_cmd = super::silent_cmd(&az_cli);
-    account_cmd.args(["account", "show", "--output", "json"]);
+    let mut account_cmd = CliCommand::with_path(CliTool::Azure, &az_cli);
+    account_cmd.args(["account", "show", "--output", "json"]).cancel_token(&token);
 
-    let account_output = account_cmd
-        .output()
-        .map_err(|e| format!("Failed to get Azure account: {}", e))?;
+    let account_output = account_cmd.run()?;
 
-    if !account_output.status.success() {
-        let stderr = String::from_utf8_lossy(&account_output.stderr);
+    if !account_output.success {
+        let stderr = &account_output.stderr;
         return Err(format!(
             "Azure authentication failed: {}",
             stderr.trim()
@@ -722,7 +764,7 @@ pub async fn check_azure_permissions(
             client_id.clone()
         } else {
             let account_json: serde_json::Value =
-                serde_json::from_slice(&account_output.stdout).unwrap_or_default();
+                serde_json::from_str(&account_output.stdout).unwrap_or_default();
             account_json["user"]["name"]
                 .as_str()
                 .unwrap_or("")
@@ -730,7 +772,7 @@ pub async fn check_azure_permissions(
         }
     } else {
         let account_json: serde_json::Value =
-            serde_json::from_slice(&account_output.stdout).unwrap_or_default();
+            serde_json::from_str(&account_output.stdout).unwrap_or_default();
         account_json["user"]["name"]
             .as_str()
             .unwrap_or("")
@@ -738,17 +780,14 @@ pub async fn check_azure_permissions(
     };
 
     if assignee.is_empty() {
-        return Ok(CloudPermissionCheck {
-            has_all_permissions: true,
-            checked_permissions: vec![],
-            missing_permissions: vec![],
-            message: "Unable to determine Azure principal. Permission check skipped.".to_string(),
-            is_warning: true,
-        });
+        return Ok(CloudPermissionCheck::skipped(PreflightEntry::skipped(
+            "Role assignments",
+            "Unable to determine Azure principal. Permission check skipped.",
+        )));
     }
 
     // List role assignments for the principal
-    let mut role_cmd = super::silent_cmd(&az_cli);
+    let mut role_cmd = CliCommand::with_path(CliTool::Azure, &az_cli);
     role_cmd.args([
         "role",
         "assignment",
@@ -762,40 +801,37 @@ pub async fn check_azure_permissions(
         "--output",
         "json",
     ]);
+    role_cmd.cancel_token(&token);
 
-    let role_output = role_cmd
-        .output()
-        .map_err(|e| format!("Failed to list role assignments: {}", e))?;
+    let role_output = role_cmd.run()?;
 
-    if !role_output.status.success() {
-        let stderr = String::from_utf8_lossy(&role_output.stderr);
+    if !role_output.success {
+        let stderr = &role_output.stderr;
 
         if stderr.contains("AuthorizationFailed")
             || stderr.contains("does not have authorization")
         {
-            return Ok(CloudPermissionCheck {
-                has_all_permissions: true,
-                checked_permissions: vec![],
-                missing_permissions: vec![],
-                message: "Unable to check role assignments (insufficient permissions). Proceeding without verification.".to_string(),
-                is_warning: true,
-            });
+            return Ok(CloudPermissionCheck::skipped(
+                PreflightEntry::skipped(
+                    "Role assignments",
+                    "Unable to check role assignments (insufficient permissions). Proceeding without verification.",
+                )
+                .remediation("Grant the Reader role on the subscription so role assignments can be listed.")
+                .docs(AZURE_RBAC_DOCS_URL),
+            ));
         }
 
-        return Ok(CloudPermissionCheck {
-            has_all_permissions: true,
-            checked_permissions: vec![],
-            missing_permissions: vec![],
-            message: format!(
+        return Ok(CloudPermissionCheck::skipped(PreflightEntry::skipped(
+            "Role assignments",
+            &format!(
                 "Permission check failed: {}. Proceeding without verification.",
                 stderr.trim()
             ),
-            is_warning: true,
-        });
+        )));
     }
 
     let assigned_roles: Vec<String> =
-        serde_json::from_slice(&role_output.stdout).unwrap_or_default();
+        serde_json::from_str(&role_output.stdout).unwrap_or_default();
 
     let has_primary_roles = required_roles
         .iter()
@@ -811,17 +847,23 @@ pub async fn check_azure_permissions(
 
     let has_all = has_owner || has_primary_roles || has_alternative_roles;
 
-    let checked_permissions: Vec<String> = required_roles.clone();
-
-    let missing_permissions: Vec<String> = if has_all {
-        vec![]
-    } else {
-        required_roles
-            .iter()
-            .filter(|r| !assigned_roles.iter().any(|a| a.eq_ignore_ascii_case(r)))
-            .cloned()
-            .collect()
-    };
+    let mut report = PreflightReport::default();
+    for role in &required_roles {
+        let assigned = assigned_roles.iter().any(|a| a.eq_ignore_ascii_case(role));
+        report.push(if has_owner {
+            PreflightEntry::pass(role, "Covered by the Owner role")
+        } else if has_all || assigned {
+            PreflightEntry::pass(role, "Assigned")
+        } else {
+            PreflightEntry::fail(role, WARNING, "Not assigned on the subscription")
+                .remediation(format!(
+                    "az role assignment create --assignee {} --role \"{}\" --scope /subscriptions/{}",
+                    assignee, role, subscription_id
+                ))
+                .docs(AZURE_RBAC_DOCS_URL)
+        });
+    }
+    let missing_permissions = report.failed();
 
     let message = if has_all {
         if has_owner {
@@ -836,13 +878,7 @@ pub async fn check_azure_permissions(
         )
     };
 
-    Ok(CloudPermissionCheck {
-        has_all_permissions: has_all,
-        checked_permissions,
-        missing_permissions,
-        message,
-        is_warning: true,
-    })
+    Ok(CloudPermissionCheck::from_report(report, message))
 }
 
 #[cfg(test)]
@@ -870,4 +906,21 @@ mod tests {
     fn invalid_subscription_id_no_dashes() {
         assert!(!validate_azure_subscription_id("550e8400e29b41d4a716446655440000"));
     }
+
+    #[test]
+    fn locations_skip_logical_regions() {
+        let items: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[
+                {"name": "westeurope", "displayName": "West Europe", "metadata": {"regionType": "Physical", "geographyGroup": "Europe"}},
+                {"name": "eastusstg", "displayName": "East US STG", "metadata": {"regionType": "Logical"}},
+                {"name": "eastus2", "displayName": "East US 2", "metadata": {"regionType": "Physical", "geographyGroup": "US"}},
+                {"displayName": "Nameless"}
+            ]"#,
+        )
+        .unwrap();
+        let locations = parse_locations(&items);
+        let names: Vec<&str> = locations.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["eastus2", "westeurope"]);
+        assert_eq!(locations[1].geography, "Europe");
+    }
 }
@@ -0,0 +1,302 @@
+//! Inventory of cloud bootstrap artifacts created by the app, with cleanup.
+//!
+//! The credential wizards create GCP service accounts, custom roles, IAM
+//! bindings and Databricks CLI profiles on the user's behalf. Each one is
+//! recorded in `bootstrap-inventory.json` in the app data directory when it
+//! is created, and [`cleanup_bootstrap_artifacts`] deletes the ones the user
+//! selects so everything the wizard did can be undone. Artifacts that already
+//! existed before the app touched them are never recorded.
+
+use super::audit::now_secs;
+use super::{debug_log, lock_or_recover};
+use crate::dependencies;
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const INVENTORY_FILE: &str = "bootstrap-inventory.json";
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write cycles of the inventory file.
+    static ref INVENTORY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Something the app created outside its own data directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArtifactKind {
+    GcpServiceAccount { project_id: String, email: String },
+    GcpCustomRole { project_id: String, role_id: String },
+    /// Binding on a project (`service_account` unset) or on a service account.
+    GcpIamBinding {
+        project_id: String,
+        service_account: Option<String>,
+        member: String,
+        role: String,
+    },
+    DatabricksProfile { name: String, config_path: PathBuf },
+}
+
+impl ArtifactKind {
+    /// Stable identifier, so recording the same artifact twice keeps one entry.
+    fn id(&self) -> String {
+        match self {
+            Self::GcpServiceAccount { project_id, email } => format!("gcp-sa:{}:{}", project_id, email),
+            Self::GcpCustomRole { project_id, role_id } => format!("gcp-role:{}:{}", project_id, role_id),
+            Self::GcpIamBinding { project_id, service_account, member, role } => format!(
+                "gcp-binding:{}:{}:{}:{}",
+                project_id,
+                service_account.as_deref().unwrap_or("-"),
+                member,
+                role
+            ),
+            Self::DatabricksProfile { name, config_path } => {
+                format!("databricks-profile:{}:{}", config_path.display(), name)
+            }
+        }
+    }
+
+    /// Cleanup order: bindings before the roles and accounts they reference.
+    fn cleanup_rank(&self) -> u8 {
+        match self {
+            Self::GcpIamBinding { .. } => 0,
+            Self::GcpCustomRole { .. } => 1,
+            Self::GcpServiceAccount { .. } => 2,
+            Self::DatabricksProfile { .. } => 3,
+        }
+    }
+}
+
+/// An inventory entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapArtifact {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: ArtifactKind,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+}
+
+/// Result of a cleanup run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BootstrapCleanup {
+    pub removed: Vec<String>,
+    /// `(id, error)` for artifacts that could not be removed; they stay in the inventory.
+    pub failed: Vec<(String, String)>,
+    /// Inventory after the run.
+    pub remaining: Vec<BootstrapArtifact>,
+}
+
+// ─── Inventory ──────────────────────────────────────────────────────────────
+
+fn load(path: &Path) -> Vec<BootstrapArtifact> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, artifacts: &[BootstrapArtifact]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(artifacts).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write bootstrap inventory: {}", e))
+}
+
+/// Record an artifact the app just created.
+pub(crate) fn record(env: &dyn Environment, kind: ArtifactKind) -> Result<(), String> {
+    let path = env.data_file(INVENTORY_FILE)?;
+    let _guard = lock_or_recover(&INVENTORY_LOCK);
+    let mut artifacts = load(&path);
+    let id = kind.id();
+    if artifacts.iter().any(|a| a.id == id) {
+        return Ok(());
+    }
+    artifacts.push(BootstrapArtifact { id, kind, created_at: now_secs() });
+    save(&path, &artifacts)
+}
+
+/// [`record`], logging instead of failing: a missing inventory entry must not
+/// fail the wizard step that created the artifact.
+pub(crate) fn record_quietly(env: &dyn Environment, kind: ArtifactKind) {
+    if let Err(_e) = record(env, kind) {
+        debug_log!("[bootstrap_artifacts] Failed to record artifact: {}", _e);
+    }
+}
+
+// ─── Cleanup ────────────────────────────────────────────────────────────────
+
+/// `content` without the `[name]` section, or `None` if there is no such section.
+fn remove_ini_section(content: &str, name: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut skipping = false;
+    let mut found = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            skipping = trimmed[1..trimmed.len() - 1].trim() == name;
+            found |= skipping;
+        }
+        if !skipping {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    found.then(|| {
+        let trimmed = out.trim_end();
+        if trimmed.is_empty() { String::new() } else { format!("{}\n", trimmed) }
+    })
+}
+
+fn remove_databricks_profile(name: &str, config_path: &Path) -> Result<(), String> {
+    let Ok(content) = fs::read_to_string(config_path) else {
+        return Ok(());
+    };
+    if let Some(updated) = remove_ini_section(&content, name) {
+        fs::write(config_path, updated).map_err(|e| format!("Failed to update {}: {}", config_path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// `gcloud` arguments deleting a GCP artifact.
+fn gcloud_delete_args(kind: &ArtifactKind) -> Option<Vec<String>> {
+    let args: Vec<&str> = match kind {
+        ArtifactKind::GcpServiceAccount { project_id, email } => {
+            vec!["iam", "service-accounts", "delete", email, "--project", project_id, "--quiet"]
+        }
+        ArtifactKind::GcpCustomRole { project_id, role_id } => {
+            vec!["iam", "roles", "delete", role_id, "--project", project_id, "--quiet"]
+        }
+        ArtifactKind::GcpIamBinding { project_id, service_account: None, member, role } => vec![
+            "projects",
+            "remove-iam-policy-binding",
+            project_id,
+            "--member",
+            member,
+            "--role",
+            role,
+            "--condition",
+            "None",
+            "--quiet",
+        ],
+        ArtifactKind::GcpIamBinding { project_id, service_account: Some(sa), member, role } => vec![
+            "iam",
+            "service-accounts",
+            "remove-iam-policy-binding",
+            sa,
+            "--member",
+            member,
+            "--role",
+            role,
+            "--project",
+            project_id,
+            "--quiet",
+        ],
+        ArtifactKind::DatabricksProfile { .. } => return None,
+    };
+    Some(args.into_iter().map(String::from).collect())
+}
+
+/// Errors meaning the artifact is already gone.
+fn already_removed(stderr: &str) -> bool {
+    ["NOT_FOUND", "not found", "does not exist", "is not a member", "already deleted"]
+        .iter()
+        .any(|marker| stderr.contains(marker))
+}
+
+fn remove_artifact(kind: &ArtifactKind) -> Result<(), String> {
+    if let ArtifactKind::DatabricksProfile { name, config_path } = kind {
+        return remove_databricks_profile(name, config_path);
+    }
+    let args = gcloud_delete_args(kind).ok_or("Unsupported artifact")?;
+    let gcloud_cli = dependencies::find_gcloud_cli_path()
+        .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
+    let output = super::gcp::gcloud_as_user(&gcloud_cli).args(&args).run()?;
+    let stderr = &output.stderr;
+    if output.success || already_removed(stderr) {
+        Ok(())
+    } else {
+        Err(stderr.trim().to_string())
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List the bootstrap artifacts the app has created and delete the selected
+/// ones. With no `ids` nothing is deleted and the inventory is returned in
+/// `remaining`.
+#[tauri::command]
+pub async fn cleanup_bootstrap_artifacts(app: AppHandle, ids: Option<Vec<String>>) -> Result<BootstrapCleanup, String> {
+    let path = app.data_file(INVENTORY_FILE)?;
+    super::run_blocking(move || {
+        let ids = ids.unwrap_or_default();
+        let mut selected: Vec<BootstrapArtifact> = load(&path).into_iter().filter(|a| ids.contains(&a.id)).collect();
+        selected.sort_by_key(|a| a.kind.cleanup_rank());
+
+        let mut cleanup = BootstrapCleanup::default();
+        for artifact in selected {
+            match remove_artifact(&artifact.kind) {
+                Ok(()) => cleanup.removed.push(artifact.id),
+                Err(e) => cleanup.failed.push((artifact.id, e)),
+            }
+        }
+
+        let _guard = lock_or_recover(&INVENTORY_LOCK);
+        let mut artifacts = load(&path);
+        if !cleanup.removed.is_empty() {
+            artifacts.retain(|a| !cleanup.removed.contains(&a.id));
+            save(&path, &artifacts)?;
+        }
+        cleanup.remaining = artifacts;
+        Ok(cleanup)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+
+    #[test]
+    fn record_dedupes_and_serializes_kind() {
+        let data = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(data.path());
+        let sa = ArtifactKind::GcpServiceAccount { project_id: "proj-123".into(), email: "dep@proj-123.iam.gserviceaccount.com".into() };
+
+        record(&paths, sa.clone()).unwrap();
+        record(&paths, sa).unwrap();
+        record(&paths, ArtifactKind::GcpCustomRole { project_id: "proj-123".into(), role_id: "Deployer".into() }).unwrap();
+
+        let path = paths.data_file(INVENTORY_FILE).unwrap();
+        let artifacts = load(&path);
+        assert_eq!(artifacts.len(), 2);
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"kind\": \"gcp_service_account\""));
+    }
+
+    #[test]
+    fn removes_only_the_named_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join(".databrickscfg");
+        fs::write(&config, "[DEFAULT]\nhost = a\n\n[deployer-sp-1234]\nhost = b\nclient_secret = x\n\n[other]\nhost = c\n").unwrap();
+
+        remove_databricks_profile("deployer-sp-1234", &config).unwrap();
+        assert_eq!(fs::read_to_string(&config).unwrap(), "[DEFAULT]\nhost = a\n\n[other]\nhost = c\n");
+        assert_eq!(remove_ini_section("[a]\nx = 1\n", "b"), None);
+    }
+
+    #[test]
+    fn bindings_are_removed_before_accounts() {
+        let mut kinds = [
+            ArtifactKind::GcpServiceAccount { project_id: "p".into(), email: "e".into() },
+            ArtifactKind::GcpIamBinding { project_id: "p".into(), service_account: None, member: "m".into(), role: "r".into() },
+            ArtifactKind::GcpCustomRole { project_id: "p".into(), role_id: "r".into() },
+        ];
+        kinds.sort_by_key(|k| k.cleanup_rank());
+        assert!(matches!(kinds[0], ArtifactKind::GcpIamBinding { .. }));
+        assert!(matches!(kinds[2], ArtifactKind::GcpServiceAccount { .. }));
+        assert!(gcloud_delete_args(&kinds[0]).unwrap().contains(&"remove-iam-policy-binding".to_string()));
+    }
+}
@@ -0,0 +1,341 @@
+//! Export a deployment as a standalone Terraform bundle.
+//!
+//! The zip holds the Terraform sources, the provider lock file, a generated
+//! `terraform.tfvars.example` with sensitive values replaced by placeholders,
+//! and a README with run instructions, so a platform team can run the
+//! deployment without the app. State is included only on request.
+
+use super::github::{build_preview_entries, render_tfvars_example, TfVarPreviewEntry};
+use super::{get_deployments_dir, sanitize_deployment_name, state_encryption};
+use crate::secret_scan::{self, SecretFinding};
+use crate::terraform;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Instructions file added to every bundle.
+const BUNDLE_README: &str = "BUNDLE_README.md";
+
+/// Directories never bundled: provider binaries, git metadata, state,
+/// template and tfvars snapshots.
+const EXCLUDED_DIRS: &[&str] = &[
+    ".terraform",
+    ".git",
+    super::state_backups::BACKUP_DIR,
+    super::template_upgrade::TEMPLATE_BACKUP_DIR,
+    super::config_changes::TFVARS_BACKUP_DIR,
+    super::debug_logs::RUNS_DIR,
+];
+
+/// What went into an exported bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleSummary {
+    pub path: String,
+    /// Paths inside the zip.
+    pub files: Vec<String>,
+    pub includes_state: bool,
+    /// Variables replaced by placeholders in `terraform.tfvars.example`.
+    pub redacted_variables: Vec<String>,
+    /// Possible secrets left in bundled sources, for the user to review.
+    pub findings: Vec<SecretFinding>,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Files that hold values, state, or app bookkeeping rather than Terraform sources.
+fn is_excluded(rel_path: &str) -> bool {
+    let first = rel_path.split('/').next().unwrap_or("");
+    let name = rel_path.rsplit('/').next().unwrap_or("");
+    EXCLUDED_DIRS.contains(&first)
+        || name.ends_with(".tfvars")
+        || name.ends_with(".tfvars.json")
+        || name == "terraform.tfvars.example"
+        || name.ends_with(".tfstate")
+        || name.contains(".tfstate.")
+        || name.starts_with(".deployer-")
+        || name == "_auto_import.tf"
+        || name == BUNDLE_README
+}
+
+/// Relative paths (with `/` separators) of the files to bundle, sorted.
+pub(super) fn collect_files(root: &Path) -> Result<Vec<String>, String> {
+    collect_files_except(root, is_excluded)
+}
+
+/// Relative paths (with `/` separators) of the files under `root`, sorted,
+/// skipping every path (and directory) `exclude` matches.
+pub(super) fn collect_files_except(root: &Path, exclude: fn(&str) -> bool) -> Result<Vec<String>, String> {
+    fn walk(root: &Path, dir: &Path, exclude: fn(&str) -> bool, out: &mut Vec<String>) -> Result<(), String> {
+        for entry in fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?.flatten() {
+            let path = entry.path();
+            let rel = path
+                .strip_prefix(root)
+                .map_err(|e| e.to_string())?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            if exclude(&rel) {
+                continue;
+            }
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                walk(root, &path, exclude, out)?;
+            } else if file_type.is_file() {
+                out.push(rel);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(root, root, exclude, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Also redact values whose names or contents look secret even though the
+/// variable isn't declared `sensitive`. Returns the redacted names.
+fn redact_entries(entries: &mut [TfVarPreviewEntry]) -> Vec<String> {
+    let mut redacted = Vec::new();
+    for entry in entries.iter_mut() {
+        let looks_secret = super::audit::is_secret_name(&entry.name)
+            || !secret_scan::scan_text("", &format!("{} = \"{}\"", entry.name, entry.value)).is_empty();
+        if looks_secret && !entry.is_sensitive {
+            entry.is_sensitive = true;
+            entry.placeholder = format!("<SENSITIVE - set via TF_VAR_{}>", entry.name);
+            entry.value = entry.placeholder.clone();
+        }
+        if entry.is_sensitive {
+            redacted.push(entry.name.clone());
+        }
+    }
+    redacted
+}
+
+fn render_readme(deployment_name: &str, deployment_dir: &Path, redacted: &[String], includes_state: bool) -> String {
+    let engine = terraform::deployment_engine(deployment_dir).binary_name();
+    let template = super::audit::read_meta(deployment_dir);
+    let provider = template
+        .as_ref()
+        .and_then(|t| t.template_id.split('-').next())
+        .and_then(super::cloud_provider::provider);
+
+    let mut readme = format!("# {}\n\nTerraform bundle exported by Databricks Deployer {}", deployment_name, env!("CARGO_PKG_VERSION"));
+    if let Some(t) = &template {
+        readme.push_str(&format!(" from template `{}` (templates {})", t.template_id, t.templates_version));
+    }
+    readme.push_str(".\n\n## Running\n\n");
+    readme.push_str(&format!(
+        "1. Install {} and the {} CLI.\n",
+        if engine == "tofu" { "OpenTofu" } else { "Terraform" },
+        provider.map_or("cloud provider", |p| p.display_name())
+    ));
+    readme.push_str("2. Copy `terraform.tfvars.example` to `terraform.tfvars` and replace any `<placeholder>` values.\n");
+    if redacted.is_empty() {
+        readme.push_str("3. No sensitive variables need to be supplied separately.\n");
+    } else {
+        readme.push_str("3. Supply sensitive values through the environment instead of files:\n\n");
+        for name in redacted {
+            readme.push_str(&format!("   - `TF_VAR_{}`\n", name));
+        }
+        readme.push('\n');
+    }
+    readme.push_str(&format!(
+        "4. {}\n",
+        provider.map_or("Authenticate to your cloud provider and Databricks account.", |p| p.auth_hint())
+    ));
+    readme.push_str(&format!("5. Run `{engine} init`, `{engine} plan`, then `{engine} apply`.\n\n", engine = engine));
+    readme.push_str("## State\n\n");
+    readme.push_str(if includes_state {
+        "`terraform.tfstate` is included and reflects the resources already deployed. It contains secrets in plaintext; \
+         move it to a secured remote backend and delete the local copy.\n"
+    } else {
+        "No state is included. Running `apply` creates new resources; import existing ones first if this deployment \
+         has already been applied.\n"
+    });
+    readme
+}
+
+/// Write the bundle zip for `deployment_dir` to `destination`.
+pub(crate) fn write_bundle(
+    deployment_name: &str,
+    deployment_dir: &Path,
+    destination: &Path,
+    state: Option<String>,
+) -> Result<BundleSummary, String> {
+    let files = collect_files(deployment_dir)?;
+
+    let mut entries = build_preview_entries(deployment_dir).unwrap_or_default();
+    let redacted_variables = redact_entries(&mut entries);
+    let tfvars_example = (!entries.is_empty()).then(|| render_tfvars_example(&entries, true));
+    let includes_state = state.is_some();
+    let readme = render_readme(deployment_name, deployment_dir, &redacted_variables, includes_state);
+
+    let file = fs::File::create(destination).map_err(|e| format!("Failed to create bundle: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut written = Vec::new();
+    let mut findings = Vec::new();
+
+    let mut add = |name: &str, data: &[u8]| -> Result<(), String> {
+        let path = format!("{}/{}", deployment_name, name);
+        zip.start_file(path.as_str(), options)
+            .map_err(|e| format!("Failed to write bundle: {}", e))?;
+        zip.write_all(data).map_err(|e| format!("Failed to write bundle: {}", e))?;
+        written.push(path);
+        Ok(())
+    };
+
+    for rel in &files {
+        let data = fs::read(deployment_dir.join(rel)).map_err(|e| format!("Failed to read {}: {}", rel, e))?;
+        if let Ok(text) = std::str::from_utf8(&data) {
+            findings.extend(secret_scan::scan_text(rel, text));
+        }
+        add(rel, &data)?;
+    }
+    if let Some(example) = &tfvars_example {
+        add("terraform.tfvars.example", example.as_bytes())?;
+    }
+    add(BUNDLE_README, readme.as_bytes())?;
+    if let Some(state) = &state {
+        add("terraform.tfstate", state.as_bytes())?;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to write bundle: {}", e))?;
+
+    Ok(BundleSummary {
+        path: destination.to_string_lossy().to_string(),
+        files: written,
+        includes_state,
+        redacted_variables,
+        findings,
+    })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Package a deployment into a zip at `destination`. State (decrypted if it is
+/// encrypted at rest) is added only when `include_state` is set.
+#[tauri::command]
+pub fn export_deployment_bundle(
+    app: AppHandle,
+    deployment_name: String,
+    destination: String,
+    include_state: bool,
+) -> Result<BundleSummary, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+
+    let state = if include_state {
+        let key = if state_encryption::is_enabled(&deployment_dir) {
+            Some(state_encryption::load_key(&app)?)
+        } else {
+            None
+        };
+        state_encryption::read_state(&deployment_dir, key.as_ref())
+    } else {
+        None
+    };
+
+    let summary = write_bundle(&safe_name, &deployment_dir, Path::new(&destination), state)?;
+    super::telemetry::record_feature(&app, super::telemetry::Feature::BundleExport);
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn deployment() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("variables.tf"),
+            "variable \"region\" {\n  type = string\n}\nvariable \"client_secret\" {\n  type = string\n  sensitive = true\n}\nvariable \"admin_token\" {\n  type = string\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("main.tf"), "resource \"null_resource\" \"x\" {}\n").unwrap();
+        fs::write(
+            root.join("terraform.tfvars"),
+            "region = \"us-east-1\"\nclient_secret = \"s3cr3t-value\"\nadmin_token = \"tok-abc\"\n",
+        )
+        .unwrap();
+        fs::write(root.join(".terraform.lock.hcl"), "# lock\n").unwrap();
+        fs::write(root.join("terraform.tfstate"), "{\"serial\": 1}").unwrap();
+        fs::write(root.join(".deployer-engine"), "terraform").unwrap();
+        fs::create_dir_all(root.join(".terraform/providers")).unwrap();
+        fs::write(root.join(".terraform/providers/bin"), "binary").unwrap();
+        fs::create_dir_all(root.join("modules/network")).unwrap();
+        fs::write(root.join("modules/network/main.tf"), "# network\n").unwrap();
+        dir
+    }
+
+    fn read_zip(path: &Path) -> std::collections::BTreeMap<String, String> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut content = String::new();
+                file.read_to_string(&mut content).unwrap();
+                (file.name().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn excludes_values_state_and_app_files() {
+        assert!(is_excluded("terraform.tfvars"));
+        assert!(is_excluded("terraform.tfstate.backup"));
+        assert!(is_excluded("terraform.tfstate.enc"));
+        assert!(is_excluded(".terraform/providers/bin"));
+        assert!(is_excluded(".state-backups/1-apply.tfstate"));
+        assert!(is_excluded(".deployer-meta.json"));
+        assert!(!is_excluded(".terraform.lock.hcl"));
+        assert!(!is_excluded("modules/network/main.tf"));
+    }
+
+    #[test]
+    fn bundle_strips_sensitive_values() {
+        let dir = deployment();
+        let out = tempfile::tempdir().unwrap();
+        let zip_path = out.path().join("bundle.zip");
+        let summary = write_bundle("demo", dir.path(), &zip_path, None).unwrap();
+
+        let files = read_zip(&zip_path);
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            vec![
+                "demo/.terraform.lock.hcl",
+                "demo/BUNDLE_README.md",
+                "demo/main.tf",
+                "demo/modules/network/main.tf",
+                "demo/terraform.tfvars.example",
+                "demo/variables.tf",
+            ]
+        );
+        let example = &files["demo/terraform.tfvars.example"];
+        assert!(example.contains("region = \"us-east-1\""));
+        assert!(!example.contains("s3cr3t-value") && !example.contains("tok-abc"));
+        assert_eq!(summary.redacted_variables, vec!["client_secret", "admin_token"]);
+        assert!(files["demo/BUNDLE_README.md"].contains("TF_VAR_admin_token"));
+        assert!(!summary.includes_state);
+    }
+
+    #[test]
+    fn bundle_includes_state_on_request() {
+        let dir = deployment();
+        let out = tempfile::tempdir().unwrap();
+        let zip_path = out.path().join("bundle.zip");
+        let summary = write_bundle("demo", dir.path(), &zip_path, Some("{\"serial\": 1}".to_string())).unwrap();
+        assert!(summary.includes_state);
+        assert_eq!(read_zip(&zip_path)["demo/terraform.tfstate"], "{\"serial\": 1}");
+    }
+}
@@ -0,0 +1,144 @@
+//! Cancellation of long-running validation commands.
+//!
+//! Permission and Unity Catalog checks take an optional `request_id` from the
+//! frontend. While such a command runs its id is registered here, and
+//! [`cancel_request`] flags it so the command returns [`CANCELLED`] instead of
+//! finishing work nobody is waiting for. CLI calls given the command's
+//! [`CancelToken`] are killed as well.
+
+use super::{debug_log, lock_or_recover};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Error returned by a command that was cancelled.
+pub(crate) const CANCELLED: &str = "Cancelled";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+lazy_static::lazy_static! {
+    /// Tokens of the commands currently running, by request id.
+    static ref REQUESTS: Mutex<HashMap<String, CancelToken>> = Mutex::new(HashMap::new());
+}
+
+/// Shared flag set when a request is cancelled.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// A request id registered for the lifetime of one command.
+struct Registration {
+    id: String,
+    token: CancelToken,
+}
+
+impl Registration {
+    fn new(id: String) -> Self {
+        let token = CancelToken::default();
+        lock_or_recover(&REQUESTS).insert(id.clone(), token.clone());
+        Registration { id, token }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut requests = lock_or_recover(&REQUESTS);
+        // A later command may have reused the id; leave its token alone.
+        if requests.get(&self.id).is_some_and(|t| Arc::ptr_eq(&t.0, &self.token.0)) {
+            requests.remove(&self.id);
+        }
+    }
+}
+
+/// Run `work`, stopping with [`CANCELLED`] if `request_id` is cancelled first.
+///
+/// Without a request id the work runs to completion as before. `work` gets the
+/// token so it can pass it on to blocking CLI calls.
+pub(crate) async fn cancellable<T, F, Fut>(request_id: Option<String>, work: F) -> Result<T, String>
+where
+    F: FnOnce(CancelToken) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let Some(id) = request_id.filter(|id| !id.is_empty()) else {
+        return work(CancelToken::default()).await;
+    };
+    let registration = Registration::new(id);
+    let token = registration.token.clone();
+    tokio::select! {
+        result = work(token.clone()) => result,
+        _ = token.cancelled() => {
+            debug_log!("[cancellation] Request {} cancelled", registration.id);
+            Err(CANCELLED.to_string())
+        }
+    }
+}
+
+/// Cancel a running command started with `request_id`.
+///
+/// Returns `false` if no command with that id is running (it may already have
+/// finished).
+#[tauri::command]
+pub fn cancel_request(request_id: String) -> bool {
+    match lock_or_recover(&REQUESTS).get(&request_id) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_stops_a_running_request() {
+        let id = "test-cancel-running".to_string();
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(cancellable(Some(id.clone()), |token| async move {
+            let _ = started_tx.send(());
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok::<_, String>(token.is_cancelled())
+        }));
+
+        started_rx.await.unwrap();
+        assert!(cancel_request(id.clone()));
+        let result = tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+        assert_eq!(result, Err(CANCELLED.to_string()));
+        assert!(!cancel_request(id), "finished requests are unregistered");
+    }
+
+    #[tokio::test]
+    async fn requests_without_an_id_run_to_completion() {
+        assert_eq!(cancellable(None, |_| async { Ok::<_, String>(1) }).await, Ok(1));
+        assert_eq!(cancellable(Some("test-completes".into()), |_| async { Ok::<_, String>(2) }).await, Ok(2));
+        assert!(!cancel_request("test-completes".into()));
+    }
+
+    #[test]
+    fn reused_id_keeps_the_newer_registration() {
+        let first = Registration::new("test-reused".into());
+        let second = Registration::new("test-reused".into());
+        drop(first);
+        assert!(cancel_request("test-reused".into()));
+        assert!(second.token.is_cancelled());
+    }
+}
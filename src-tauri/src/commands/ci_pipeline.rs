@@ -0,0 +1,496 @@
+//! CI/CD pipeline generation for deployment repositories.
+//!
+//! Writes a ready-to-use workflow (GitHub Actions, GitLab CI, or Azure
+//! Pipelines) into a deployment directory so the pushed repository can be
+//! used for GitOps right away: `terraform fmt`/`validate`/`plan` on pull
+//! requests and `apply` on the default branch. Cloud authentication uses
+//! OIDC / workload identity federation with placeholder secret names that
+//! the user fills in on their CI platform. Sensitive variables, which are
+//! kept out of `terraform.tfvars`, are expected as `TF_VAR_<name>` secrets.
+
+use super::debug_log;
+use super::github::resolve_deployment_dir;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+// ─── Types ──────────────────────────────────────────────────────────────────
+
+/// Result of writing a CI pipeline file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneratedPipeline {
+    pub platform: String,
+    /// Path of the written file, relative to the deployment directory.
+    pub path: String,
+    pub cloud: Option<String>,
+    /// Secrets / variables the user must configure on the CI platform.
+    pub required_secrets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CiPlatform {
+    GithubActions,
+    GitlabCi,
+    AzurePipelines,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn parse_platform(s: &str) -> Result<CiPlatform, String> {
+    match s {
+        "github-actions" | "github" => Ok(CiPlatform::GithubActions),
+        "gitlab-ci" | "gitlab" => Ok(CiPlatform::GitlabCi),
+        "azure-pipelines" | "azure-devops" => Ok(CiPlatform::AzurePipelines),
+        other => Err(format!("Unsupported CI platform: {}", other)),
+    }
+}
+
+/// Path of the pipeline file relative to the repository root.
+fn pipeline_path(platform: CiPlatform) -> &'static str {
+    match platform {
+        CiPlatform::GithubActions => ".github/workflows/terraform.yml",
+        CiPlatform::GitlabCi => ".gitlab-ci.yml",
+        CiPlatform::AzurePipelines => "azure-pipelines.yml",
+    }
+}
+
+/// Whether `file` (relative to the repository root) is a pipeline file this module writes.
+pub(super) fn is_pipeline_file(file: &str) -> bool {
+    [CiPlatform::GithubActions, CiPlatform::GitlabCi, CiPlatform::AzurePipelines]
+        .into_iter()
+        .any(|platform| pipeline_path(platform) == file)
+}
+
+/// Detect which cloud a deployment targets from the providers declared in its `.tf` files.
+fn detect_cloud(dir: &Path) -> Option<&'static str> {
+    let entries = fs::read_dir(dir).ok()?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("tf") {
+            continue;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        if content.contains("provider \"aws\"") {
+            return Some("aws");
+        }
+        if content.contains("provider \"azurerm\"") {
+            return Some("azure");
+        }
+        if content.contains("provider \"google\"") {
+            return Some("gcp");
+        }
+    }
+    None
+}
+
+/// `TF_VAR_<name>` secrets for a deployment's sensitive variables, which
+/// `terraform.tfvars` (and so the TFVARS secret) doesn't contain.
+fn sensitive_var_secrets(dir: &Path) -> Vec<String> {
+    super::secret_vars::stored_names(dir).into_iter().map(|name| format!("TF_VAR_{}", name)).collect()
+}
+
+/// Secret names the generated pipeline references for the given cloud.
+fn required_secrets(cloud: Option<&str>, tf_var_secrets: &[String]) -> Vec<String> {
+    let mut secrets: Vec<&str> = match cloud {
+        Some("aws") => vec!["AWS_ROLE_ARN"],
+        Some("azure") => vec!["AZURE_CLIENT_ID", "AZURE_TENANT_ID", "AZURE_SUBSCRIPTION_ID"],
+        Some("gcp") => vec!["GCP_WORKLOAD_IDENTITY_PROVIDER", "GCP_SERVICE_ACCOUNT"],
+        _ => vec![],
+    };
+    secrets.extend(["DATABRICKS_CLIENT_ID", "DATABRICKS_CLIENT_SECRET", "TFVARS"]);
+    secrets.into_iter().map(String::from).chain(tf_var_secrets.iter().cloned()).collect()
+}
+
+const HEADER: &str = "\
+# Generated by Databricks Deployer.
+#
+# Before enabling apply:
+#   1. Configure a remote Terraform backend (S3 / azurerm / gcs) so state is
+#      shared between runs. The default local state is lost after each job.
+#   2. Add the secrets listed below to your CI platform. TFVARS holds the
+#      contents of terraform.tfvars (it is not committed to the repository);
+#      each TF_VAR_* secret holds the value of a sensitive variable.
+#   3. Set up OIDC / workload identity federation for the cloud account.
+#
+";
+
+fn render_github_actions(cloud: Option<&str>, branch: &str, tf_var_secrets: &[String]) -> String {
+    let auth_step = match cloud {
+        Some("aws") => "\
+      - name: Configure AWS credentials (OIDC)
+        uses: aws-actions/configure-aws-credentials@v4
+        with:
+          role-to-assume: ${{ secrets.AWS_ROLE_ARN }}
+          aws-region: us-east-1 # TODO: set your region
+",
+        Some("azure") => "\
+      - name: Azure login (OIDC)
+        uses: azure/login@v2
+        with:
+          client-id: ${{ secrets.AZURE_CLIENT_ID }}
+          tenant-id: ${{ secrets.AZURE_TENANT_ID }}
+          subscription-id: ${{ secrets.AZURE_SUBSCRIPTION_ID }}
+",
+        Some("gcp") => "\
+      - name: Authenticate to Google Cloud (workload identity)
+        uses: google-github-actions/auth@v2
+        with:
+          workload_identity_provider: ${{ secrets.GCP_WORKLOAD_IDENTITY_PROVIDER }}
+          service_account: ${{ secrets.GCP_SERVICE_ACCOUNT }}
+",
+        _ => "",
+    };
+
+    let azure_env = if cloud == Some("azure") {
+        "\
+  ARM_USE_OIDC: \"true\"
+  ARM_CLIENT_ID: ${{ secrets.AZURE_CLIENT_ID }}
+  ARM_TENANT_ID: ${{ secrets.AZURE_TENANT_ID }}
+  ARM_SUBSCRIPTION_ID: ${{ secrets.AZURE_SUBSCRIPTION_ID }}
+"
+    } else {
+        ""
+    };
+
+    let mut out = String::from(HEADER);
+    out.push_str(&format!(
+        "# Required secrets: {}\n\n",
+        required_secrets(cloud, tf_var_secrets).join(", ")
+    ));
+    out.push_str("name: Terraform\n\n");
+    out.push_str("on:\n  pull_request:\n    branches: [__BRANCH__]\n  push:\n    branches: [__BRANCH__]\n\n");
+    out.push_str("permissions:\n  id-token: write\n  contents: read\n\n");
+    out.push_str("env:\n  TF_IN_AUTOMATION: \"true\"\n  TF_INPUT: \"false\"\n");
+    out.push_str("  DATABRICKS_CLIENT_ID: ${{ secrets.DATABRICKS_CLIENT_ID }}\n");
+    out.push_str("  DATABRICKS_CLIENT_SECRET: ${{ secrets.DATABRICKS_CLIENT_SECRET }}\n");
+    out.push_str(azure_env);
+    for name in tf_var_secrets {
+        out.push_str(&format!("  {}: ${{{{ secrets.{} }}}}\n", name, name));
+    }
+    out.push_str("\njobs:\n  terraform:\n    runs-on: ubuntu-latest\n    steps:\n");
+    out.push_str("      - uses: actions/checkout@v4\n");
+    out.push_str("      - uses: hashicorp/setup-terraform@v3\n");
+    // Before terraform.tfvars exists: the generated file isn't fmt-aligned
+    out.push_str("      - name: Format check\n        run: terraform fmt -check -recursive\n");
+    out.push_str(auth_step);
+    // Through the environment, so quotes or `$` in the values can't break the shell command
+    out.push_str("      - name: Write terraform.tfvars\n        env:\n          TFVARS: ${{ secrets.TFVARS }}\n        run: printf '%s' \"$TFVARS\" > terraform.tfvars\n");
+    out.push_str("      - name: Init\n        run: terraform init\n");
+    out.push_str("      - name: Validate\n        run: terraform validate\n");
+    out.push_str("      - name: Plan\n        run: terraform plan -out=tfplan\n");
+    out.push_str("      - name: Apply\n        if: github.ref == 'refs/heads/__BRANCH__' && github.event_name == 'push'\n        run: terraform apply -auto-approve tfplan\n");
+
+    out.replace("__BRANCH__", branch)
+}
+
+/// GitLab exposes CI/CD variables (including the `TF_VAR_*` ones) to jobs as
+/// environment variables, so they are only listed.
+fn render_gitlab_ci(cloud: Option<&str>, branch: &str, tf_var_secrets: &[String]) -> String {
+    let auth_script = match cloud {
+        Some("aws") => "\
+    - echo \"$CI_JOB_JWT_V2\" > .ci_job_jwt
+    - export AWS_WEB_IDENTITY_TOKEN_FILE=\"$PWD/.ci_job_jwt\"
+",
+        Some("azure") => "\
+    - export ARM_USE_OIDC=true ARM_OIDC_TOKEN=\"$CI_JOB_JWT_V2\"
+    - export ARM_CLIENT_ID=\"$AZURE_CLIENT_ID\" ARM_TENANT_ID=\"$AZURE_TENANT_ID\" ARM_SUBSCRIPTION_ID=\"$AZURE_SUBSCRIPTION_ID\"
+",
+        Some("gcp") => "\
+    - echo \"$CI_JOB_JWT_V2\" > .ci_job_jwt
+    - >
+      printf '{\"type\":\"external_account\",\"audience\":\"//iam.googleapis.com/%s\",
+      \"subject_token_type\":\"urn:ietf:params:oauth:token-type:jwt\",
+      \"token_url\":\"https://sts.googleapis.com/v1/token\",
+      \"service_account_impersonation_url\":\"https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/%s:generateAccessToken\",
+      \"credential_source\":{\"file\":\".ci_job_jwt\"}}'
+      \"$GCP_WORKLOAD_IDENTITY_PROVIDER\" \"$GCP_SERVICE_ACCOUNT\" > .gcp_creds.json
+    - export GOOGLE_APPLICATION_CREDENTIALS=\"$PWD/.gcp_creds.json\"
+",
+        _ => "",
+    };
+
+    let mut out = String::from(HEADER);
+    out.push_str(&format!(
+        "# Required CI/CD variables: {}\n\n",
+        required_secrets(cloud, tf_var_secrets).join(", ")
+    ));
+    out.push_str("image:\n  name: hashicorp/terraform:1.9\n  entrypoint: [\"\"]\n\n");
+    out.push_str("variables:\n  TF_IN_AUTOMATION: \"true\"\n  TF_INPUT: \"false\"\n\n");
+    out.push_str("stages: [validate, plan, apply]\n\n");
+    // Its own job, so it runs before `.terraform` writes terraform.tfvars (which isn't fmt-aligned)
+    out.push_str("fmt:\n  stage: validate\n  script:\n    - terraform fmt -check -recursive\n\n");
+    out.push_str(".terraform:\n  id_tokens:\n    CI_JOB_JWT_V2:\n      aud: https://gitlab.com # TODO: match the audience configured in your cloud trust policy\n  before_script:\n");
+    out.push_str(auth_script);
+    out.push_str("    - printf '%s' \"$TFVARS\" > terraform.tfvars\n    - terraform init\n\n");
+    out.push_str("validate:\n  extends: .terraform\n  stage: validate\n  script:\n    - terraform validate\n\n");
+    out.push_str("plan:\n  extends: .terraform\n  stage: plan\n  script:\n    - terraform plan -out=tfplan\n  artifacts:\n    paths: [tfplan]\n  rules:\n    - if: $CI_PIPELINE_SOURCE == \"merge_request_event\"\n    - if: $CI_COMMIT_BRANCH == \"__BRANCH__\"\n\n");
+    out.push_str("apply:\n  extends: .terraform\n  stage: apply\n  script:\n    - terraform apply -auto-approve tfplan\n  dependencies: [plan]\n  rules:\n    - if: $CI_COMMIT_BRANCH == \"__BRANCH__\"\n");
+
+    out.replace("__BRANCH__", branch)
+}
+
+fn render_azure_pipelines(cloud: Option<&str>, branch: &str, tf_var_secrets: &[String]) -> String {
+    let auth_note = match cloud {
+        Some("azure") => "\
+  # Uses a workload identity federation service connection.
+  azureServiceConnection: 'TODO-service-connection-name'
+",
+        _ => "",
+    };
+
+    let mut out = String::from(HEADER);
+    out.push_str(&format!(
+        "# Required pipeline variables: {}\n\n",
+        required_secrets(cloud, tf_var_secrets).join(", ")
+    ));
+    out.push_str("trigger:\n  branches:\n    include: [__BRANCH__]\n\n");
+    out.push_str("pr:\n  branches:\n    include: [__BRANCH__]\n\n");
+    out.push_str("pool:\n  vmImage: ubuntu-latest\n\n");
+    out.push_str("variables:\n  TF_IN_AUTOMATION: \"true\"\n  TF_INPUT: \"false\"\n");
+    out.push_str(auth_note);
+    out.push_str("\nsteps:\n");
+    // Before terraform.tfvars exists: the generated file isn't fmt-aligned
+    out.push_str("  - script: terraform fmt -check -recursive\n    displayName: Format check\n");
+    // Secret variables only reach scripts through an explicit env mapping
+    out.push_str("  - script: printf '%s' \"$TFVARS\" > terraform.tfvars\n    displayName: Write terraform.tfvars\n    env:\n      TFVARS: $(TFVARS)\n");
+
+    let mut tf_env = "    env:\n      DATABRICKS_CLIENT_ID: $(DATABRICKS_CLIENT_ID)\n      DATABRICKS_CLIENT_SECRET: $(DATABRICKS_CLIENT_SECRET)\n".to_string();
+    for name in tf_var_secrets {
+        tf_env.push_str(&format!("      {}: $({})\n", name, name));
+    }
+    let steps = [
+        ("terraform init", "Init", ""),
+        ("terraform validate", "Validate", ""),
+        ("terraform plan -out=tfplan", "Plan", ""),
+        (
+            "terraform apply -auto-approve tfplan",
+            "Apply",
+            "    condition: and(succeeded(), eq(variables['Build.SourceBranch'], 'refs/heads/__BRANCH__'), ne(variables['Build.Reason'], 'PullRequest'))\n",
+        ),
+    ];
+
+    for (script, name, condition) in steps {
+        if cloud == Some("azure") {
+            out.push_str(&format!(
+                "  - task: AzureCLI@2\n    displayName: {}\n    inputs:\n      azureSubscription: $(azureServiceConnection)\n      scriptType: bash\n      scriptLocation: inlineScript\n      addSpnToEnvironment: true\n      inlineScript: |\n        export ARM_USE_OIDC=true ARM_OIDC_TOKEN=$idToken ARM_CLIENT_ID=$servicePrincipalId ARM_TENANT_ID=$tenantId\n        {}\n",
+                name, script
+            ));
+        } else {
+            out.push_str(&format!("  - script: {}\n    displayName: {}\n", script, name));
+        }
+        out.push_str(&tf_env);
+        out.push_str(condition);
+    }
+
+    out.replace("__BRANCH__", branch)
+}
+
+/// Render the pipeline file content for a platform.
+fn render_pipeline(platform: CiPlatform, cloud: Option<&str>, branch: &str, tf_var_secrets: &[String]) -> String {
+    match platform {
+        CiPlatform::GithubActions => render_github_actions(cloud, branch, tf_var_secrets),
+        CiPlatform::GitlabCi => render_gitlab_ci(cloud, branch, tf_var_secrets),
+        CiPlatform::AzurePipelines => render_azure_pipelines(cloud, branch, tf_var_secrets),
+    }
+}
+
+/// Validate a branch name before interpolating it into YAML.
+fn validate_branch_name(branch: &str) -> Result<(), String> {
+    let valid = !branch.is_empty()
+        && branch.len() <= 100
+        && !branch.starts_with('-')
+        && branch
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err("Invalid branch name".to_string())
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Write a CI/CD pipeline file into the deployment directory.
+///
+/// `platform` is one of `github-actions`, `gitlab-ci`, or `azure-pipelines`.
+/// `default_branch` controls which branch triggers `apply` (defaults to `main`).
+#[tauri::command]
+pub fn generate_ci_pipeline(
+    app: AppHandle,
+    deployment_name: String,
+    platform: String,
+    default_branch: Option<String>,
+) -> Result<GeneratedPipeline, String> {
+    let ci_platform = parse_platform(&platform)?;
+    let branch = default_branch
+        .filter(|b| !b.trim().is_empty())
+        .unwrap_or_else(|| "main".to_string());
+    validate_branch_name(&branch)?;
+
+    let dir = resolve_deployment_dir(&app, &deployment_name)?;
+    let cloud = detect_cloud(&dir);
+    let tf_var_secrets = sensitive_var_secrets(&dir);
+
+    let rel_path = pipeline_path(ci_platform);
+    let full_path = dir.join(rel_path);
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create pipeline directory: {}", e))?;
+    }
+
+    fs::write(&full_path, render_pipeline(ci_platform, cloud, &branch, &tf_var_secrets))
+        .map_err(|e| format!("Failed to write pipeline file: {}", e))?;
+
+    debug_log!("[ci] Wrote {} pipeline for {:?} to {:?}", platform, cloud, full_path);
+    super::telemetry::record_feature(&app, super::telemetry::Feature::CiPipeline);
+
+    Ok(GeneratedPipeline {
+        platform,
+        path: rel_path.to_string(),
+        cloud: cloud.map(String::from),
+        required_secrets: required_secrets(cloud, &tf_var_secrets),
+    })
+}
+
+// ─── Tests ──────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── parse_platform / pipeline_path ──────────────────────────────────
+
+    #[test]
+    fn parse_platform_aliases() {
+        assert_eq!(parse_platform("github-actions").unwrap(), CiPlatform::GithubActions);
+        assert_eq!(parse_platform("gitlab").unwrap(), CiPlatform::GitlabCi);
+        assert_eq!(parse_platform("azure-devops").unwrap(), CiPlatform::AzurePipelines);
+        assert!(parse_platform("jenkins").is_err());
+    }
+
+    #[test]
+    fn pipeline_paths() {
+        assert_eq!(pipeline_path(CiPlatform::GithubActions), ".github/workflows/terraform.yml");
+        assert_eq!(pipeline_path(CiPlatform::GitlabCi), ".gitlab-ci.yml");
+        assert_eq!(pipeline_path(CiPlatform::AzurePipelines), "azure-pipelines.yml");
+    }
+
+    // ── detect_cloud ────────────────────────────────────────────────────
+
+    #[test]
+    fn detect_cloud_from_providers() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("providers.tf"), "provider \"azurerm\" {\n  features {}\n}\n").unwrap();
+        assert_eq!(detect_cloud(dir.path()), Some("azure"));
+    }
+
+    #[test]
+    fn detect_cloud_none_without_tf_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("README.md"), "provider \"aws\"").unwrap();
+        assert_eq!(detect_cloud(dir.path()), None);
+    }
+
+    #[test]
+    fn detect_cloud_real_templates() {
+        let templates = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates");
+        assert_eq!(detect_cloud(&templates.join("aws-simple")), Some("aws"));
+        assert_eq!(detect_cloud(&templates.join("azure-simple")), Some("azure"));
+        assert_eq!(detect_cloud(&templates.join("gcp-simple")), Some("gcp"));
+    }
+
+    // ── render_pipeline ─────────────────────────────────────────────────
+
+    #[test]
+    fn github_actions_aws_uses_oidc() {
+        let yaml = render_pipeline(CiPlatform::GithubActions, Some("aws"), "main", &[]);
+        assert!(yaml.contains("id-token: write"));
+        assert!(yaml.contains("aws-actions/configure-aws-credentials"));
+        assert!(yaml.contains("secrets.AWS_ROLE_ARN"));
+        assert!(yaml.contains("terraform fmt -check"));
+        assert!(yaml.contains("terraform validate"));
+        assert!(yaml.contains("refs/heads/main"));
+        assert!(!yaml.contains("__BRANCH__"));
+    }
+
+    #[test]
+    fn github_actions_azure_sets_arm_oidc() {
+        let yaml = render_pipeline(CiPlatform::GithubActions, Some("azure"), "main", &[]);
+        assert!(yaml.contains("azure/login@v2"));
+        assert!(yaml.contains("ARM_USE_OIDC"));
+    }
+
+    #[test]
+    fn gitlab_ci_applies_only_on_branch() {
+        let yaml = render_pipeline(CiPlatform::GitlabCi, Some("gcp"), "release", &[]);
+        assert!(yaml.contains("$CI_COMMIT_BRANCH == \"release\""));
+        assert!(yaml.contains("merge_request_event"));
+        assert!(yaml.contains("GCP_WORKLOAD_IDENTITY_PROVIDER"));
+    }
+
+    #[test]
+    fn azure_pipelines_uses_service_connection_for_azure() {
+        let yaml = render_pipeline(CiPlatform::AzurePipelines, Some("azure"), "main", &[]);
+        assert!(yaml.contains("AzureCLI@2"));
+        assert!(yaml.contains("ne(variables['Build.Reason'], 'PullRequest')"));
+    }
+
+    #[test]
+    fn tfvars_are_written_from_the_environment() {
+        for platform in [CiPlatform::GithubActions, CiPlatform::GitlabCi, CiPlatform::AzurePipelines] {
+            let yaml = render_pipeline(platform, Some("aws"), "main", &[]);
+            assert!(yaml.contains("printf '%s' \"$TFVARS\" > terraform.tfvars"), "{:?}", platform);
+            assert!(!yaml.contains("echo \"${{ secrets.TFVARS }}\"") && !yaml.contains("\"$(TFVARS)\""), "{:?}", platform);
+        }
+    }
+
+    #[test]
+    fn format_check_runs_before_tfvars_are_written() {
+        for platform in [CiPlatform::GithubActions, CiPlatform::GitlabCi, CiPlatform::AzurePipelines] {
+            let yaml = render_pipeline(platform, Some("azure"), "main", &[]);
+            let fmt = yaml.find("terraform fmt -check").unwrap();
+            let tfvars = yaml.find("> terraform.tfvars").unwrap();
+            assert!(fmt < tfvars, "{:?}", platform);
+            assert_eq!(yaml.matches("terraform fmt -check").count(), 1, "{:?}", platform);
+        }
+        let github = render_pipeline(CiPlatform::GithubActions, Some("aws"), "main", &[]);
+        assert!(!github.contains("pull-requests: write"));
+    }
+
+    #[test]
+    fn required_secrets_per_cloud() {
+        let aws = required_secrets(Some("aws"), &[]);
+        assert!(aws.contains(&"AWS_ROLE_ARN".to_string()));
+        assert!(aws.contains(&"DATABRICKS_CLIENT_SECRET".to_string()));
+        assert_eq!(required_secrets(None, &[]).len(), 3);
+    }
+
+    #[test]
+    fn sensitive_variables_are_passed_as_tf_var_secrets() {
+        let secrets = vec!["TF_VAR_admin_password".to_string()];
+        assert_eq!(required_secrets(Some("aws"), &secrets).last(), Some(&secrets[0]));
+
+        let github = render_pipeline(CiPlatform::GithubActions, Some("aws"), "main", &secrets);
+        assert!(github.contains("  TF_VAR_admin_password: ${{ secrets.TF_VAR_admin_password }}\n"));
+        let azure = render_pipeline(CiPlatform::AzurePipelines, Some("aws"), "main", &secrets);
+        assert!(azure.contains("      TF_VAR_admin_password: $(TF_VAR_admin_password)\n"));
+        let gitlab = render_pipeline(CiPlatform::GitlabCi, Some("aws"), "main", &secrets);
+        assert!(gitlab.contains("TF_VAR_admin_password"));
+    }
+
+    // ── validate_branch_name ────────────────────────────────────────────
+
+    #[test]
+    fn branch_name_validation() {
+        assert!(validate_branch_name("main").is_ok());
+        assert!(validate_branch_name("release/v1.2").is_ok());
+        assert!(validate_branch_name("").is_err());
+        assert!(validate_branch_name("main\"; rm").is_err());
+        assert!(validate_branch_name("-x").is_err());
+    }
+}
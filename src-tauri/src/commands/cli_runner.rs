@@ -0,0 +1,378 @@
+//! One way to run the cloud CLIs (`aws`, `az`, `gcloud`, `databricks`).
+//!
+//! [`CliCommand`] wraps `std::process::Command` for non-interactive calls: it
+//! finds the CLI, injects credentials from [`CloudCredentials`] as
+//! environment variables, closes stdin, enforces a timeout, captures output
+//! as text and logs each call in debug builds with secret arguments masked.
+//! Failures come back as a [`CliError`], which converts to the `String`
+//! errors commands return. Interactive logins keep their own handling in
+//! [`super::login_flow`].
+
+use super::audit::{is_secret_name, REDACTED};
+use super::cancellation::{CancelToken, CANCELLED};
+use super::{debug_log, CloudCredentials};
+use crate::dependencies;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// Timeout for calls that don't set their own.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A cloud CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CliTool {
+    Aws,
+    Azure,
+    Gcloud,
+    Databricks,
+}
+
+impl CliTool {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            CliTool::Aws => "AWS CLI",
+            CliTool::Azure => "Azure CLI",
+            CliTool::Gcloud => "Google Cloud CLI",
+            CliTool::Databricks => "Databricks CLI",
+        }
+    }
+
+    fn find(self) -> Option<PathBuf> {
+        match self {
+            CliTool::Aws => dependencies::find_aws_cli_path(),
+            CliTool::Azure => dependencies::find_azure_cli_path(),
+            CliTool::Gcloud => dependencies::find_gcloud_cli_path(),
+            CliTool::Databricks => dependencies::find_databricks_cli_path(),
+        }
+    }
+}
+
+/// Why a CLI call failed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CliError {
+    NotFound(CliTool),
+    Spawn { tool: CliTool, error: String },
+    Timeout { tool: CliTool, after: Duration },
+    /// Killed because the request it belongs to was cancelled.
+    Cancelled,
+    /// Non-zero exit, from [`CliCommand::run_checked`].
+    Failed { tool: CliTool, stderr: String },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound(tool) => f.write_str(&crate::errors::cli_not_found(tool.display_name())),
+            CliError::Spawn { tool, error } => write!(f, "Failed to run {}: {}", tool.display_name(), error),
+            CliError::Timeout { tool, after } => {
+                write!(f, "{} did not respond within {} seconds", tool.display_name(), after.as_secs())
+            }
+            CliError::Cancelled => f.write_str(CANCELLED),
+            CliError::Failed { tool, stderr } => write!(f, "{} error: {}", tool.display_name(), stderr.trim()),
+        }
+    }
+}
+
+impl From<CliError> for String {
+    fn from(e: CliError) -> String {
+        e.to_string()
+    }
+}
+
+/// Captured result of a CLI call that ran to completion.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CliOutput {
+    pub success: bool,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CliOutput {
+    /// Standard output parsed as JSON.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, String> {
+        serde_json::from_str(&self.stdout).map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Trimmed standard output, `None` when empty or gcloud's `(unset)`.
+    pub fn value(&self) -> Option<String> {
+        Some(self.stdout.trim()).filter(|s| !s.is_empty() && *s != "(unset)").map(str::to_string)
+    }
+}
+
+/// A non-interactive cloud CLI call. Methods mirror `std::process::Command`.
+#[derive(Debug, Clone)]
+pub(crate) struct CliCommand {
+    tool: CliTool,
+    program: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    timeout: Duration,
+    cancel: Option<CancelToken>,
+}
+
+impl CliCommand {
+    /// Call `tool`, found the same way as the dependency checks do.
+    pub fn new(tool: CliTool) -> Result<Self, CliError> {
+        tool.find().map(|path| Self::with_path(tool, path)).ok_or(CliError::NotFound(tool))
+    }
+
+    /// Call `tool` at a path the caller already resolved.
+    pub fn with_path(tool: CliTool, program: impl AsRef<Path>) -> Self {
+        CliCommand {
+            tool,
+            program: program.as_ref().to_path_buf(),
+            args: Vec::new(),
+            env: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            cancel: None,
+        }
+    }
+
+    pub fn arg(&mut self, arg: impl AsRef<str>) -> &mut Self {
+        self.args.push(arg.as_ref().to_string());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args.extend(args.into_iter().map(|a| a.as_ref().to_string()));
+        self
+    }
+
+    /// Environment variable for the call. Values are never logged.
+    pub fn env(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> &mut Self {
+        self.env.push((key.as_ref().to_string(), value.as_ref().to_string()));
+        self
+    }
+
+    /// Kill the process if it hasn't finished after `timeout`.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Kill the process once `token` is cancelled.
+    pub fn cancel_token(&mut self, token: &CancelToken) -> &mut Self {
+        self.cancel = Some(token.clone());
+        self
+    }
+
+    /// Pass the parts of `credentials` this CLI reads from its environment.
+    pub fn credentials(&mut self, credentials: &CloudCredentials) -> Result<&mut Self, String> {
+        let env = match self.tool {
+            CliTool::Aws => super::aws::aws_credential_env(credentials)?,
+            CliTool::Databricks => databricks_credential_env(credentials),
+            CliTool::Gcloud => non_empty([("CLOUDSDK_CORE_PROJECT", &credentials.gcp_project_id)]),
+            // The Azure CLI only uses its own login
+            CliTool::Azure => Vec::new(),
+        };
+        for (key, value) in env {
+            self.env(key, value);
+        }
+        Ok(self)
+    }
+
+    /// Arguments as logged, with the values of secret-named flags masked.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    fn display_args(&self) -> String {
+        let mut shown = Vec::with_capacity(self.args.len());
+        let mut mask_next = false;
+        for arg in &self.args {
+            let masked = if mask_next {
+                REDACTED.to_string()
+            } else {
+                match arg.split_once('=') {
+                    Some((flag, _)) if flag.starts_with('-') && is_secret_name(flag) => format!("{}={}", flag, REDACTED),
+                    _ => arg.clone(),
+                }
+            };
+            mask_next = arg.starts_with('-') && !arg.contains('=') && is_secret_name(arg);
+            shown.push(masked);
+        }
+        shown.join(" ")
+    }
+
+    /// Run to completion. A non-zero exit is returned as output with
+    /// `success: false`, so callers can inspect stderr.
+    pub fn run(&self) -> Result<CliOutput, CliError> {
+        let started = Instant::now();
+        let mut cmd = super::silent_cmd(&self.program);
+        cmd.args(&self.args)
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = cmd.spawn().map_err(|e| {
+            debug_log!("[cli] {} {}: {}", self.tool.display_name(), self.display_args(), e);
+            CliError::Spawn { tool: self.tool, error: e.to_string() }
+        })?;
+
+        let read = |pipe: Option<Box<dyn Read + Send>>| {
+            std::thread::spawn(move || {
+                let mut bytes = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut bytes);
+                }
+                String::from_utf8_lossy(&bytes).to_string()
+            })
+        };
+        let stdout = read(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+        let stderr = read(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    debug_log!("[cli] {} {}: cancelled", self.tool.display_name(), self.display_args());
+                    return Err(CliError::Cancelled);
+                }
+                Ok(None) if started.elapsed() < self.timeout => std::thread::sleep(POLL_INTERVAL),
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    debug_log!("[cli] {} {}: timed out", self.tool.display_name(), self.display_args());
+                    return Err(CliError::Timeout { tool: self.tool, after: self.timeout });
+                }
+                Err(e) => return Err(CliError::Spawn { tool: self.tool, error: e.to_string() }),
+            }
+        };
+
+        let output = CliOutput {
+            success: status.success(),
+            code: status.code(),
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+        debug_log!(
+            "[cli] {} {} -> exit {:?} in {} ms",
+            self.tool.display_name(),
+            self.display_args(),
+            output.code,
+            started.elapsed().as_millis()
+        );
+        Ok(output)
+    }
+
+    /// [`run`](Self::run), treating a non-zero exit as [`CliError::Failed`].
+    pub fn run_checked(&self) -> Result<CliOutput, CliError> {
+        let output = self.run()?;
+        if output.success {
+            Ok(output)
+        } else {
+            Err(CliError::Failed { tool: self.tool, stderr: output.stderr })
+        }
+    }
+
+    /// [`run`](Self::run) on the blocking pool, for async commands.
+    pub async fn run_async(self) -> Result<CliOutput, String> {
+        super::run_blocking(move || self.run().map_err(String::from)).await
+    }
+}
+
+/// `(name, value)` pairs for the values that are set and non-empty.
+fn non_empty<const N: usize>(vars: [(&'static str, &Option<String>); N]) -> Vec<(&'static str, String)> {
+    vars.into_iter()
+        .filter_map(|(name, value)| value.as_ref().filter(|v| !v.is_empty()).map(|v| (name, v.clone())))
+        .collect()
+}
+
+fn databricks_credential_env(credentials: &CloudCredentials) -> Vec<(&'static str, String)> {
+    non_empty([
+        ("DATABRICKS_CONFIG_PROFILE", &credentials.databricks_profile),
+        ("DATABRICKS_ACCOUNT_ID", &credentials.databricks_account_id),
+        ("DATABRICKS_CLIENT_ID", &credentials.databricks_client_id),
+        ("DATABRICKS_CLIENT_SECRET", &credentials.databricks_client_secret),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_arguments_are_masked_in_logs() {
+        let mut cmd = CliCommand::with_path(CliTool::Azure, "az");
+        cmd.args(["login", "--service-principal", "-u", "app-id", "--password", "hunter2", "--tenant", "t"])
+            .arg("--client-secret=abc");
+        assert_eq!(
+            cmd.display_args(),
+            "login --service-principal -u app-id --password (redacted) --tenant t --client-secret=(redacted)"
+        );
+    }
+
+    #[test]
+    fn credentials_become_environment() {
+        let credentials = CloudCredentials {
+            aws_profile: Some("dev".into()),
+            aws_access_key_id: Some(String::new()),
+            databricks_client_secret: Some("s".into()),
+            gcp_project_id: Some("proj".into()),
+            ..Default::default()
+        };
+        let env = |tool| {
+            let mut cmd = CliCommand::with_path(tool, "cli");
+            cmd.credentials(&credentials).unwrap();
+            cmd.env
+        };
+        assert_eq!(env(CliTool::Aws), vec![("AWS_PROFILE".to_string(), "dev".to_string())]);
+        assert_eq!(env(CliTool::Databricks), vec![("DATABRICKS_CLIENT_SECRET".to_string(), "s".to_string())]);
+        assert_eq!(env(CliTool::Gcloud), vec![("CLOUDSDK_CORE_PROJECT".to_string(), "proj".to_string())]);
+        assert!(env(CliTool::Azure).is_empty());
+
+        let bad = CloudCredentials { aws_profile: Some("bad profile!".into()), ..Default::default() };
+        assert!(CliCommand::with_path(CliTool::Aws, "aws").credentials(&bad).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_exit_codes_and_timeouts() {
+        let sh = |script: &str| {
+            let mut cmd = CliCommand::with_path(CliTool::Aws, "sh");
+            cmd.args(["-c", script]).env("GREETING", "hi");
+            cmd
+        };
+
+        let out = sh("echo \"$GREETING\"; echo oops >&2; exit 3").run().unwrap();
+        assert!(!out.success);
+        assert_eq!((out.code, out.stdout.as_str(), out.stderr.as_str()), (Some(3), "hi\n", "oops\n"));
+        assert_eq!(
+            sh("echo nope >&2; exit 1").run_checked().unwrap_err().to_string(),
+            "AWS CLI error: nope"
+        );
+        assert_eq!(sh("echo '{\"a\": 1}'").run_checked().unwrap().json::<serde_json::Value>().unwrap()["a"], 1);
+        assert_eq!(sh("echo '(unset)'").run().unwrap().value(), None);
+
+        let started = Instant::now();
+        let err = sh("sleep 30").timeout(Duration::from_millis(200)).run().unwrap_err();
+        assert!(matches!(err, CliError::Timeout { .. }), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let token = CancelToken::default();
+        let canceller = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                token.cancel();
+            })
+        };
+        let started = Instant::now();
+        assert_eq!(sh("sleep 30").cancel_token(&token).run().unwrap_err(), CliError::Cancelled);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        canceller.join().unwrap();
+
+        let missing = CliCommand::with_path(CliTool::Gcloud, "/nonexistent/gcloud").run().unwrap_err();
+        assert!(missing.to_string().starts_with("Failed to run Google Cloud CLI"));
+    }
+}
@@ -0,0 +1,339 @@
+//! Per-cloud behaviour behind one trait.
+//!
+//! Credential validation, permission checks, the region catalog, Terraform
+//! environment variables, the Databricks accounts host and the names shown
+//! in exported bundles come from [`CloudProvider`], looked up with
+//! [`provider`]. A new cloud (or a variant such as AWS GovCloud) implements
+//! these in [`PROVIDERS`]. Checks that only exist for some clouds (tag
+//! rules, quotas, orphan scans, encryption keys, credential context
+//! switching) still match on the cloud ID in their own modules.
+
+use super::deployment::set_env_if_present;
+use super::regions::{self, SupportedRegion};
+use super::cli_runner::{CliCommand, CliTool};
+use super::{debug_log, opt_non_empty, CloudCredentials, CloudPermissionCheck};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Everything commands need to know about one cloud.
+pub(crate) trait CloudProvider: Send + Sync {
+    /// Identifier used in credentials and templates ("aws", "azure", "gcp").
+    fn id(&self) -> &'static str;
+
+    /// Name shown to users ("AWS", "Azure", "Google Cloud").
+    fn display_name(&self) -> &'static str;
+
+    /// How to authenticate the cloud CLI and Terraform outside the app.
+    fn auth_hint(&self) -> &'static str;
+
+    /// Databricks account-level API hostname.
+    fn databricks_host(&self) -> &'static str;
+
+    /// Regions where Databricks workspaces can be deployed.
+    fn regions(&self) -> &'static [SupportedRegion];
+
+    /// Add the Terraform environment variables for this cloud's credentials.
+    /// Only called for the deployment's own cloud.
+    fn apply_env(&self, credentials: &CloudCredentials, env_vars: &mut HashMap<String, String>);
+
+    /// Confirm the credentials authenticate. Returns who they authenticate
+    /// as, or `None` when the permission check is what verifies them.
+    fn validate_credentials(&self, credentials: CloudCredentials) -> BoxFuture<'static, Result<Option<String>, String>>;
+
+    /// Check the permissions a deployment needs.
+    fn check_permissions(
+        &self,
+        credentials: CloudCredentials,
+        request_id: Option<String>,
+    ) -> BoxFuture<'static, Result<CloudPermissionCheck, String>>;
+}
+
+pub(crate) struct Aws;
+pub(crate) struct Azure;
+pub(crate) struct Gcp;
+
+static PROVIDERS: &[&dyn CloudProvider] = &[&Aws, &Azure, &Gcp];
+
+/// The provider for a cloud identifier, if it is supported.
+pub(crate) fn provider(cloud: &str) -> Option<&'static dyn CloudProvider> {
+    PROVIDERS.iter().copied().find(|p| p.id() == cloud)
+}
+
+/// All supported providers.
+pub(crate) fn providers() -> &'static [&'static dyn CloudProvider] {
+    PROVIDERS
+}
+
+// ─── AWS ────────────────────────────────────────────────────────────────────
+
+/// `aws sts get-caller-identity` with the deployment's credentials.
+fn aws_caller_arn(credentials: &CloudCredentials) -> Result<String, String> {
+    let output = CliCommand::new(CliTool::Aws)?
+        .args(["sts", "get-caller-identity", "--output", "json"])
+        .credentials(credentials)?
+        .run()?;
+    if !output.success {
+        return Err(format!("Invalid AWS credentials: {}", output.stderr.trim()));
+    }
+    let json: serde_json::Value = output.json()?;
+    Ok(json["Arn"].as_str().unwrap_or_default().to_string())
+}
+
+impl CloudProvider for Aws {
+    fn id(&self) -> &'static str {
+        "aws"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "AWS"
+    }
+
+    fn auth_hint(&self) -> &'static str {
+        "Authenticate with `aws sso login` or set `AWS_PROFILE` / access key environment variables."
+    }
+
+    fn databricks_host(&self) -> &'static str {
+        regions::AWS_ACCOUNTS
+    }
+
+    fn regions(&self) -> &'static [SupportedRegion] {
+        regions::AWS_REGIONS
+    }
+
+    fn apply_env(&self, credentials: &CloudCredentials, env_vars: &mut HashMap<String, String>) {
+        // Clear conflicting env vars to prevent inherited shell values from clashing
+        if let Some(profile) = &credentials.aws_profile {
+            if !profile.is_empty() {
+                env_vars.insert("AWS_PROFILE".to_string(), profile.clone());
+                env_vars.insert("AWS_ACCESS_KEY_ID".to_string(), String::new());
+                env_vars.insert("AWS_SECRET_ACCESS_KEY".to_string(), String::new());
+                env_vars.insert("AWS_SESSION_TOKEN".to_string(), String::new());
+            }
+        } else {
+            set_env_if_present(env_vars, "AWS_ACCESS_KEY_ID", &credentials.aws_access_key_id);
+            set_env_if_present(env_vars, "AWS_SECRET_ACCESS_KEY", &credentials.aws_secret_access_key);
+            set_env_if_present(env_vars, "AWS_SESSION_TOKEN", &credentials.aws_session_token);
+            env_vars.insert("AWS_PROFILE".to_string(), String::new());
+        }
+        set_env_if_present(env_vars, "AWS_DEFAULT_REGION", &credentials.aws_region);
+    }
+
+    fn validate_credentials(&self, credentials: CloudCredentials) -> BoxFuture<'static, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let arn = super::run_blocking(move || aws_caller_arn(&credentials)).await?;
+            Ok(Some(format!("Authenticated as {}", arn)))
+        })
+    }
+
+    fn check_permissions(
+        &self,
+        credentials: CloudCredentials,
+        request_id: Option<String>,
+    ) -> BoxFuture<'static, Result<CloudPermissionCheck, String>> {
+        Box::pin(super::check_aws_permissions(credentials, request_id))
+    }
+}
+
+// ─── Azure ──────────────────────────────────────────────────────────────────
+
+impl CloudProvider for Azure {
+    fn id(&self) -> &'static str {
+        "azure"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Azure"
+    }
+
+    fn auth_hint(&self) -> &'static str {
+        "Authenticate with `az login` (or set `ARM_CLIENT_ID` / `ARM_CLIENT_SECRET` / `ARM_TENANT_ID`)."
+    }
+
+    fn databricks_host(&self) -> &'static str {
+        regions::AZURE_ACCOUNTS
+    }
+
+    fn regions(&self) -> &'static [SupportedRegion] {
+        regions::AZURE_REGIONS
+    }
+
+    fn apply_env(&self, credentials: &CloudCredentials, env_vars: &mut HashMap<String, String>) {
+        set_env_if_present(env_vars, "ARM_TENANT_ID", &credentials.azure_tenant_id);
+        set_env_if_present(env_vars, "ARM_SUBSCRIPTION_ID", &credentials.azure_subscription_id);
+        set_env_if_present(env_vars, "ARM_CLIENT_ID", &credentials.azure_client_id);
+        set_env_if_present(env_vars, "ARM_CLIENT_SECRET", &credentials.azure_client_secret);
+    }
+
+    fn validate_credentials(&self, credentials: CloudCredentials) -> BoxFuture<'static, Result<Option<String>, String>> {
+        Box::pin(async move {
+            // A service principal can't be checked with `az account show`
+            if opt_non_empty(&credentials.azure_client_secret) {
+                return Ok(None);
+            }
+            let account = super::run_blocking(super::get_azure_account).await?;
+            Ok(Some(format!("Logged in as {}", account.user)))
+        })
+    }
+
+    fn check_permissions(
+        &self,
+        credentials: CloudCredentials,
+        request_id: Option<String>,
+    ) -> BoxFuture<'static, Result<CloudPermissionCheck, String>> {
+        Box::pin(super::check_azure_permissions(credentials, request_id))
+    }
+}
+
+// ─── GCP ────────────────────────────────────────────────────────────────────
+
+/// Locate a Google Application Default Credentials JSON file.
+///
+/// The Databricks Terraform provider authenticates via Google's ADC chain
+/// when `google_service_account` is set (impersonation mode). We check:
+///   1. The standard ADC path (`gcloud auth application-default login`)
+///   2. The legacy per-account path (`gcloud auth login`)
+fn find_gcp_adc_path() -> Option<String> {
+    let home = dirs::home_dir()?;
+
+    let standard_adc = home.join(".config/gcloud/application_default_credentials.json");
+    if standard_adc.exists() {
+        debug_log!("[find_gcp_adc_path] found standard ADC: {:?}", standard_adc);
+        return Some(standard_adc.to_string_lossy().to_string());
+    }
+
+    let account = CliCommand::new(CliTool::Gcloud)
+        .ok()?
+        .args(["config", "get-value", "account"])
+        .run()
+        .ok()
+        .filter(|o| o.success)
+        .and_then(|o| o.value())?;
+
+    let legacy_adc = home
+        .join(".config/gcloud/legacy_credentials")
+        .join(&account)
+        .join("adc.json");
+    if legacy_adc.exists() {
+        debug_log!("[find_gcp_adc_path] found legacy ADC for {}: {:?}", account, legacy_adc);
+        return Some(legacy_adc.to_string_lossy().to_string());
+    }
+
+    debug_log!("[find_gcp_adc_path] no ADC file found");
+    None
+}
+
+/// Get a fresh GCP user OAuth token via gcloud CLI (for the Google Terraform provider).
+/// Bypasses impersonation so the token belongs to the user, not the SA.
+///
+/// Uses the `CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT` env-var override (set to
+/// empty) instead of mutating the global gcloud config, avoiding race conditions
+/// and leaving the user's config untouched.
+fn refresh_gcp_user_token() -> Option<String> {
+    CliCommand::new(CliTool::Gcloud)
+        .ok()?
+        .args(["auth", "print-access-token"])
+        .env("CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT", "")
+        .run()
+        .ok()
+        .filter(|o| o.success)
+        .and_then(|o| o.value())
+}
+
+impl CloudProvider for Gcp {
+    fn id(&self) -> &'static str {
+        "gcp"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Google Cloud"
+    }
+
+    fn auth_hint(&self) -> &'static str {
+        "Authenticate with `gcloud auth application-default login` or set `GOOGLE_CREDENTIALS`."
+    }
+
+    fn databricks_host(&self) -> &'static str {
+        regions::GCP_ACCOUNTS
+    }
+
+    fn regions(&self) -> &'static [SupportedRegion] {
+        regions::GCP_REGIONS
+    }
+
+    fn apply_env(&self, credentials: &CloudCredentials, env_vars: &mut HashMap<String, String>) {
+        if let Some(project_id) = &credentials.gcp_project_id {
+            if !project_id.is_empty() {
+                env_vars.insert("GOOGLE_PROJECT".to_string(), project_id.clone());
+                env_vars.insert("GCLOUD_PROJECT".to_string(), project_id.clone());
+                env_vars.insert("CLOUDSDK_CORE_PROJECT".to_string(), project_id.clone());
+            }
+        }
+
+        if opt_non_empty(&credentials.gcp_credentials_json) {
+            set_env_if_present(env_vars, "GOOGLE_CREDENTIALS", &credentials.gcp_credentials_json);
+        } else {
+            // Databricks SDK uses Google ADC for impersonation auth — point it at gcloud creds
+            if let Some(adc_path) = find_gcp_adc_path() {
+                env_vars.insert("GOOGLE_APPLICATION_CREDENTIALS".to_string(), adc_path);
+            }
+
+            // Google Terraform provider needs an OAuth token when no GOOGLE_CREDENTIALS is set
+            let token = refresh_gcp_user_token()
+                .or_else(|| credentials.gcp_oauth_token.clone().filter(|s| !s.is_empty()));
+            if let Some(t) = token {
+                env_vars.insert("GOOGLE_OAUTH_ACCESS_TOKEN".to_string(), t);
+            }
+        }
+    }
+
+    fn validate_credentials(&self, credentials: CloudCredentials) -> BoxFuture<'static, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let validation = super::validate_gcp_credentials(credentials).await?;
+            if validation.valid {
+                Ok(Some(validation.message))
+            } else {
+                Err(validation.message)
+            }
+        })
+    }
+
+    fn check_permissions(
+        &self,
+        credentials: CloudCredentials,
+        request_id: Option<String>,
+    ) -> BoxFuture<'static, Result<CloudPermissionCheck, String>> {
+        Box::pin(super::check_gcp_permissions(credentials, request_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_are_looked_up_by_id() {
+        for p in providers() {
+            assert_eq!(provider(p.id()).map(|found| found.id()), Some(p.id()));
+            assert!(!p.regions().is_empty());
+            assert!(p.auth_hint().starts_with("Authenticate"));
+            assert!(p.regions().iter().any(|r| r.accounts_host == p.databricks_host()));
+        }
+        assert!(provider("oracle").is_none());
+    }
+
+    #[test]
+    fn azure_env_only_sets_present_values() {
+        let credentials = CloudCredentials {
+            azure_tenant_id: Some("tenant".to_string()),
+            azure_client_secret: Some(String::new()),
+            ..Default::default()
+        };
+        let mut env_vars = HashMap::new();
+        Azure.apply_env(&credentials, &mut env_vars);
+        assert_eq!(env_vars.len(), 1);
+        assert_eq!(env_vars["ARM_TENANT_ID"], "tenant");
+    }
+}
@@ -0,0 +1,217 @@
+//! Changes to an existing deployment's `terraform.tfvars`.
+//!
+//! Saving a configuration rewrites `terraform.tfvars`. Before that happens the
+//! UI can call [`preview_configuration_change`] for a per-variable diff with
+//! secrets redacted, and every rewrite first copies the previous file to
+//! `<deployment>/.tfvars-backups/<millis>.tfvars`. Only the newest
+//! [`MAX_BACKUPS`] copies are kept.
+
+use super::audit::{is_secret_name, parse_tfvars, REDACTED};
+use super::{debug_log, get_deployments_dir, get_templates_dir, sanitize_deployment_name, sanitize_template_id, CloudCredentials};
+use crate::terraform::TerraformVariable;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Backup directory inside a deployment.
+pub(crate) const TFVARS_BACKUP_DIR: &str = ".tfvars-backups";
+
+const TFVARS_FILE: &str = "terraform.tfvars";
+
+/// Backups kept per deployment.
+const MAX_BACKUPS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One variable that differs between the current and the new `terraform.tfvars`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariableChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    /// Values as written in the file; [`REDACTED`] for secrets.
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub redacted: bool,
+}
+
+/// What saving a configuration would change.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigurationPreview {
+    /// Whether the deployment already has a `terraform.tfvars`.
+    pub exists: bool,
+    pub changes: Vec<VariableChange>,
+    pub unchanged: usize,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Per-variable differences between two tfvars files. Sensitive variables
+/// and secret-looking names are redacted.
+fn diff_tfvars(old: &str, new: &str, variables: &[TerraformVariable]) -> (Vec<VariableChange>, usize) {
+    let old: BTreeMap<String, String> = parse_tfvars(old).into_iter().collect();
+    let new: BTreeMap<String, String> = parse_tfvars(new).into_iter().collect();
+    let redact = |value: Option<&String>, redacted: bool| {
+        value.map(|v| if redacted { REDACTED.to_string() } else { v.clone() })
+    };
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let names: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        let (before, after) = (old.get(name), new.get(name));
+        let kind = match (before, after) {
+            (Some(a), Some(b)) if a == b => {
+                unchanged += 1;
+                continue;
+            }
+            (Some(_), Some(_)) => ChangeKind::Changed,
+            (None, _) => ChangeKind::Added,
+            (_, None) => ChangeKind::Removed,
+        };
+        let redacted = is_secret_name(name) || variables.iter().any(|v| &v.name == name && v.sensitive);
+        changes.push(VariableChange {
+            name: name.clone(),
+            kind,
+            old_value: redact(before, redacted),
+            new_value: redact(after, redacted),
+            redacted,
+        });
+    }
+    (changes, unchanged)
+}
+
+fn list_backups(deployment_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(deployment_dir.join(TFVARS_BACKUP_DIR)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let millis = path.file_name()?.to_str()?.strip_suffix(".tfvars")?.parse().ok()?;
+            Some((millis, path))
+        })
+        .collect();
+    backups.sort_by_key(|(millis, _)| std::cmp::Reverse(*millis));
+    backups.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Copy the current `terraform.tfvars` into `.tfvars-backups/` if it is about
+/// to be replaced with different `new_content`. Returns the backup written.
+pub(crate) fn backup_tfvars(deployment_dir: &Path, new_content: &str) -> Result<Option<PathBuf>, String> {
+    let Ok(current) = fs::read_to_string(deployment_dir.join(TFVARS_FILE)) else {
+        return Ok(None);
+    };
+    if current == new_content {
+        return Ok(None);
+    }
+
+    let backup_dir = deployment_dir.join(TFVARS_BACKUP_DIR);
+    fs::create_dir_all(&backup_dir).map_err(|e| format!("Failed to create tfvars backup directory: {}", e))?;
+    let mut millis = now_millis();
+    let mut target = backup_dir.join(format!("{}.tfvars", millis));
+    while target.exists() {
+        millis += 1;
+        target = backup_dir.join(format!("{}.tfvars", millis));
+    }
+    fs::write(&target, current).map_err(|e| format!("Failed to back up terraform.tfvars: {}", e))?;
+
+    for old in list_backups(deployment_dir).iter().skip(MAX_BACKUPS) {
+        if let Err(_e) = fs::remove_file(old) {
+            debug_log!("[config_changes] Failed to prune {}: {}", old.display(), _e);
+        }
+    }
+    Ok(Some(target))
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Show what `save_configuration` would change in an existing deployment's
+/// `terraform.tfvars`, without writing anything. Takes the same arguments.
+#[tauri::command]
+pub fn preview_configuration_change(
+    app: AppHandle,
+    template_id: String,
+    deployment_name: String,
+    values: HashMap<String, serde_json::Value>,
+    credentials: Option<CloudCredentials>,
+) -> Result<ConfigurationPreview, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let safe_template_id = sanitize_template_id(&template_id)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+
+    // An existing deployment keeps its own copy of the template
+    let variables_path = [deployment_dir.join("variables.tf"), get_templates_dir(&app)?.join(&safe_template_id).join("variables.tf")]
+        .into_iter()
+        .find(|p| p.exists())
+        .ok_or_else(|| "Template not found".to_string())?;
+
+    let rendered =
+        super::deployment::render_tfvars(&app, &safe_template_id, &variables_path, values, credentials.as_ref())?;
+    let current = fs::read_to_string(deployment_dir.join(TFVARS_FILE)).ok();
+    let (changes, unchanged) = diff_tfvars(current.as_deref().unwrap_or(""), &rendered.tfvars, &rendered.variables);
+
+    Ok(ConfigurationPreview { exists: current.is_some(), changes, unchanged })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_reports_changes_and_redacts_secrets() {
+        let variables = crate::terraform::parse_variables_tf(
+            "variable \"admin_password\" {\n  type = string\n}\nvariable \"admin_user\" {\n  type = string\n  sensitive = true\n}\n",
+        ).unwrap();
+        let old = "prefix = \"demo\"\nregion = \"us-east-1\"\nadmin_password = \"hunter2\"\ntags = {\n  \"Owner\" = \"a\"\n}\n";
+        let new = "prefix = \"demo\"\nregion = \"us-west-2\"\nadmin_password = \"hunter3\"\nadmin_user = \"jane@example.com\"\n";
+
+        let (changes, unchanged) = diff_tfvars(old, new, &variables);
+        assert_eq!(unchanged, 1);
+        let find = |name: &str| changes.iter().find(|c| c.name == name).unwrap();
+
+        assert_eq!(find("region").kind, ChangeKind::Changed);
+        assert_eq!(find("region").new_value.as_deref(), Some("\"us-west-2\""));
+        assert_eq!(find("tags").kind, ChangeKind::Removed);
+        assert!(find("tags").new_value.is_none());
+
+        let password = find("admin_password");
+        assert!(password.redacted && password.kind == ChangeKind::Changed);
+        assert_eq!(password.old_value.as_deref(), Some(REDACTED));
+        let user = find("admin_user");
+        assert_eq!((user.kind, user.new_value.as_deref()), (ChangeKind::Added, Some(REDACTED)));
+    }
+
+    #[test]
+    fn backups_only_when_content_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(backup_tfvars(dir.path(), "a = 1\n").unwrap(), None);
+
+        fs::write(dir.path().join(TFVARS_FILE), "a = 1\n").unwrap();
+        assert_eq!(backup_tfvars(dir.path(), "a = 1\n").unwrap(), None);
+
+        let backup = backup_tfvars(dir.path(), "a = 2\n").unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "a = 1\n");
+        assert!(backup.starts_with(dir.path().join(TFVARS_BACKUP_DIR)));
+
+        for _ in 0..MAX_BACKUPS + 3 {
+            backup_tfvars(dir.path(), "a = 2\n").unwrap();
+        }
+        assert_eq!(list_backups(dir.path()).len(), MAX_BACKUPS);
+    }
+}
@@ -0,0 +1,456 @@
+//! Checks that credentials point at the subscription / project / account a
+//! deployment is configured for.
+//!
+//! The UI's selection is written to `terraform.tfvars`, but Azure CLI and
+//! gcloud keep their own active context, which may point elsewhere. Before an
+//! apply, the active context is resolved and compared with the configured
+//! values; a mismatch blocks the run. When the context comes from a CLI, the
+//! report includes the switch that fixes it ([`switch_credential_context`]).
+
+use super::audit::parse_tfvars;
+use super::{get_deployments_dir, non_empty_str, opt_non_empty, sanitize_deployment_name, CloudCredentials};
+use crate::dependencies;
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// tfvars entries holding the target of each field, in order of preference.
+const SUBSCRIPTION_VARS: &[&str] = &["subscription_id", "azure_subscription_id", "az_subscription"];
+const TENANT_VARS: &[&str] = &["tenant_id"];
+const PROJECT_VARS: &[&str] = &["google_project_name", "google_project", "project"];
+const ACCOUNT_VARS: &[&str] = &["aws_account_id"];
+
+/// Outcome of comparing one field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextCheck {
+    /// "subscription", "tenant", "project" or "account".
+    pub field: String,
+    /// Value in `terraform.tfvars` (or the stored sensitive values).
+    pub configured: String,
+    /// Value the credentials resolve to, if it could be determined.
+    pub active: Option<String>,
+    /// "ok", "mismatch" or "unverified".
+    pub status: String,
+    pub message: String,
+}
+
+/// A CLI context change that resolves a mismatch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextSwitch {
+    /// "azure" (`az account set`) or "gcp" (`gcloud config set project`).
+    pub cloud: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialContextReport {
+    pub checks: Vec<ContextCheck>,
+    /// True when any check is a mismatch; apply is refused.
+    pub blocked: bool,
+    pub switch: Option<ContextSwitch>,
+}
+
+/// Where the active context was read from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ContextSource {
+    /// `az` / `gcloud` configuration; can be switched from the app.
+    Cli,
+    /// The credentials themselves (service principal, key file, profile).
+    Credentials,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// First of `names` set to a non-empty string in `terraform.tfvars`.
+fn configured_value(tfvars: &[(String, String)], names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        tfvars
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+pub(super) fn is_valid_gcp_project_id(id: &str) -> bool {
+    (6..=30).contains(&id.len())
+        && id.starts_with(|c: char| c.is_ascii_lowercase())
+        && !id.ends_with('-')
+        && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn compare(field: &str, configured: String, active: Result<String, String>) -> ContextCheck {
+    let (status, message) = match &active {
+        Ok(active) if active.eq_ignore_ascii_case(&configured) => ("ok", format!("Active {} matches the configuration", field)),
+        Ok(active) => (
+            "mismatch",
+            format!("Configured for {} '{}' but the credentials point at '{}'", field, configured, active),
+        ),
+        Err(e) => ("unverified", format!("Could not determine the active {}: {}", field, e)),
+    };
+    ContextCheck {
+        field: field.to_string(),
+        configured,
+        active: active.ok(),
+        status: status.to_string(),
+        message,
+    }
+}
+
+fn azure_cli_account() -> Result<serde_json::Value, String> {
+    let az = dependencies::find_azure_cli_path().ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
+    let output = super::silent_cmd(&az)
+        .args(["account", "show", "--output", "json"])
+        .output()
+        .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+}
+
+fn azure_cli_context() -> Result<(String, String), String> {
+    let json = azure_cli_account()?;
+    Ok((
+        json["id"].as_str().unwrap_or_default().to_string(),
+        json["tenantId"].as_str().unwrap_or_default().to_string(),
+    ))
+}
+
+fn gcloud_project() -> Result<String, String> {
+    let gcloud = dependencies::find_gcloud_cli_path().ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
+    let output = super::silent_cmd(&gcloud)
+        .args(["config", "get-value", "project"])
+        .output()
+        .map_err(|e| format!("Failed to run gcloud: {}", e))?;
+    let project = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || project.is_empty() || project == "(unset)" {
+        return Err("no project is set in gcloud".to_string());
+    }
+    Ok(project)
+}
+
+fn aws_caller_identity(credentials: &CloudCredentials) -> Result<serde_json::Value, String> {
+    let aws = dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
+    let mut cmd = super::silent_cmd(&aws);
+    cmd.args(["sts", "get-caller-identity", "--output", "json"]);
+    super::aws::apply_aws_credentials(&mut cmd, credentials)?;
+    let output = cmd.output().map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+}
+
+fn aws_account(credentials: &CloudCredentials) -> Result<String, String> {
+    let json = aws_caller_identity(credentials)?;
+    json["Account"].as_str().map(String::from).ok_or_else(|| "no account in caller identity".to_string())
+}
+
+fn gcloud_account() -> Option<String> {
+    let gcloud = dependencies::find_gcloud_cli_path()?;
+    let output = super::silent_cmd(&gcloud).args(["config", "get-value", "account"]).output().ok()?;
+    let account = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !account.is_empty() && account != "(unset)").then_some(account)
+}
+
+/// `az account show` principal, e.g. "Azure user: jane@contoso.com".
+fn azure_principal(account: &serde_json::Value) -> Option<String> {
+    let name = account["user"]["name"].as_str().filter(|s| !s.is_empty())?;
+    let kind = match account["user"]["type"].as_str() {
+        Some("user") | None => "user",
+        Some("servicePrincipal") => "service principal",
+        Some("managedServiceIdentity") => "managed identity",
+        Some(other) => other,
+    };
+    Some(format!("Azure {}: {}", kind, name))
+}
+
+/// Principal behind a `.databrickscfg` profile, when the profile names one.
+fn databricks_profile_principal(profile: &str, data: &std::collections::HashMap<String, String>) -> String {
+    match (data.get("client_id"), data.get("username")) {
+        (Some(client_id), _) => format!("Databricks service principal: {} (profile {})", client_id, profile),
+        (None, Some(user)) => format!("Databricks user: {} (profile {})", user, profile),
+        (None, None) => format!("Databricks profile: {}", profile),
+    }
+}
+
+/// Compare the configured targets in `tfvars` with what the credentials
+/// resolve to. Resolution failures are reported as unverified, not blocking.
+fn check_context(cloud: &str, tfvars: &[(String, String)], credentials: &CloudCredentials) -> CredentialContextReport {
+    let mut checks = Vec::new();
+    let mut switch = None;
+
+    match cloud {
+        "azure" => {
+            // With a service principal, Terraform uses ARM_* from the credentials
+            let (source, context) = if opt_non_empty(&credentials.azure_client_secret) {
+                let context = non_empty_str(&credentials.azure_subscription_id)
+                    .zip(non_empty_str(&credentials.azure_tenant_id))
+                    .map(|(sub, tenant)| (sub.to_string(), tenant.to_string()))
+                    .ok_or_else(|| "service principal credentials have no subscription or tenant".to_string());
+                (ContextSource::Credentials, context)
+            } else {
+                (ContextSource::Cli, azure_cli_context())
+            };
+            if let Some(configured) = configured_value(tfvars, SUBSCRIPTION_VARS) {
+                let check = compare("subscription", configured, context.clone().map(|(sub, _)| sub));
+                if check.status == "mismatch" && source == ContextSource::Cli {
+                    switch = Some(ContextSwitch { cloud: "azure".to_string(), target: check.configured.clone() });
+                }
+                checks.push(check);
+            }
+            if let Some(configured) = configured_value(tfvars, TENANT_VARS) {
+                checks.push(compare("tenant", configured, context.map(|(_, tenant)| tenant)));
+            }
+        }
+        "gcp" => {
+            if let Some(configured) = configured_value(tfvars, PROJECT_VARS) {
+                let (source, active) = match non_empty_str(&credentials.gcp_credentials_json) {
+                    Some(_) => (
+                        ContextSource::Credentials,
+                        non_empty_str(&credentials.gcp_project_id)
+                            .map(String::from)
+                            .ok_or_else(|| "no project selected".to_string()),
+                    ),
+                    None => (ContextSource::Cli, gcloud_project()),
+                };
+                let check = compare("project", configured, active);
+                if check.status == "mismatch" && source == ContextSource::Cli {
+                    switch = Some(ContextSwitch { cloud: "gcp".to_string(), target: check.configured.clone() });
+                }
+                checks.push(check);
+            }
+        }
+        "aws" => {
+            if let Some(configured) = configured_value(tfvars, ACCOUNT_VARS) {
+                checks.push(compare("account", configured, aws_account(credentials)));
+            }
+        }
+        _ => {}
+    }
+
+    let blocked = checks.iter().any(|c| c.status == "mismatch");
+    CredentialContextReport { checks, blocked, switch }
+}
+
+fn cloud_of(credentials: &CloudCredentials, deployment_dir: &Path) -> Option<String> {
+    non_empty_str(&credentials.cloud).map(String::from).or_else(|| {
+        let template_id = super::audit::read_meta(deployment_dir)?.template_id;
+        ["aws", "azure", "gcp"]
+            .into_iter()
+            .find(|cloud| template_id.starts_with(&format!("{}-", cloud)))
+            .map(String::from)
+    })
+}
+
+/// Context check for a deployment directory. Sensitive values (such as
+/// `aws_account_id` in some templates) are read from the secret store.
+pub(crate) fn verify_deployment_context(
+    env: &dyn Environment,
+    deployment_dir: &Path,
+    credentials: &CloudCredentials,
+) -> CredentialContextReport {
+    let tfvars = fs::read_to_string(deployment_dir.join("terraform.tfvars")).unwrap_or_default();
+    let mut configured = parse_tfvars(&tfvars);
+    if let Ok(secrets) = super::secret_vars::tf_var_env(env, deployment_dir) {
+        configured.extend(secrets.into_iter().filter_map(|(name, value)| {
+            Some((name.strip_prefix("TF_VAR_")?.to_string(), value))
+        }));
+    }
+    let cloud = cloud_of(credentials, deployment_dir).unwrap_or_default();
+    check_context(&cloud, &configured, credentials)
+}
+
+/// Who a run in `deployment_dir` acts as, resolved through the cloud CLIs:
+/// AWS caller ARN, Azure UPN or service principal app ID, GCP account or
+/// service account, Databricks principal, and the local OS user. Best
+/// effort: anything that can't be resolved is left out.
+pub(crate) fn resolve_identities(deployment_dir: &Path, credentials: &CloudCredentials) -> Vec<String> {
+    let mut identities = Vec::new();
+    match cloud_of(credentials, deployment_dir).as_deref() {
+        Some("aws") => {
+            if let Some(arn) = aws_caller_identity(credentials).ok().and_then(|c| c["Arn"].as_str().map(String::from)) {
+                identities.push(format!("AWS principal: {}", arn));
+            }
+        }
+        Some("azure") => {
+            if opt_non_empty(&credentials.azure_client_secret) {
+                if let Some(app_id) = non_empty_str(&credentials.azure_client_id) {
+                    identities.push(format!("Azure service principal: {}", app_id));
+                }
+            } else if let Some(principal) = azure_cli_account().ok().as_ref().and_then(azure_principal) {
+                identities.push(principal);
+            }
+        }
+        Some("gcp") => {
+            let key_email = non_empty_str(&credentials.gcp_credentials_json)
+                .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+                .and_then(|json| json["client_email"].as_str().map(String::from));
+            if let Some(email) = key_email {
+                identities.push(format!("GCP service account: {}", email));
+            } else {
+                if let Some(account) = gcloud_account() {
+                    identities.push(format!("GCP account: {}", account));
+                }
+                if let Some(sa) = non_empty_str(&credentials.gcp_service_account_email) {
+                    identities.push(format!("GCP service account (impersonated): {}", sa));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if opt_non_empty(&credentials.databricks_client_secret) {
+        if let Some(client_id) = non_empty_str(&credentials.databricks_client_id) {
+            identities.push(format!("Databricks service principal: {}", client_id));
+        }
+    } else if let Some(profile) = non_empty_str(&credentials.databricks_profile) {
+        let sections = dependencies::read_databricks_config_sections();
+        let data = sections.iter().find(|(name, _)| *name == profile).map(|(_, data)| data.clone()).unwrap_or_default();
+        identities.push(databricks_profile_principal(profile, &data));
+    }
+
+    if let Some(user) = ["USER", "USERNAME"].iter().find_map(|var| std::env::var(var).ok().filter(|u| !u.is_empty())) {
+        identities.push(format!("OS user: {}", user));
+    }
+    identities
+}
+
+/// Error to refuse a run with when the context doesn't match.
+pub(crate) fn mismatch_error(report: &CredentialContextReport) -> Option<String> {
+    let mismatches: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|c| c.status == "mismatch")
+        .map(|c| c.message.as_str())
+        .collect();
+    (!mismatches.is_empty()).then(|| {
+        format!(
+            "Credentials point at a different target than this deployment: {}. Switch the active context or update the configuration.",
+            mismatches.join("; ")
+        )
+    })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Compare the subscription / tenant / project / account the credentials
+/// resolve to with the deployment's `terraform.tfvars`.
+#[tauri::command]
+pub async fn verify_credential_context(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+) -> Result<CredentialContextReport, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    super::run_blocking(move || Ok(verify_deployment_context(&app, &deployment_dir, &credentials))).await
+}
+
+/// Point the Azure CLI subscription or gcloud project at `switch.target`.
+#[tauri::command]
+pub async fn switch_credential_context(switch: ContextSwitch) -> Result<(), String> {
+    match switch.cloud.as_str() {
+        "azure" => super::run_blocking(move || super::azure::set_azure_subscription(switch.target)).await,
+        "gcp" => {
+            if !is_valid_gcp_project_id(&switch.target) {
+                return Err("Invalid GCP project ID".to_string());
+            }
+            super::run_blocking(move || {
+                let gcloud = dependencies::find_gcloud_cli_path()
+                    .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
+                let output = super::silent_cmd(&gcloud)
+                    .args(["config", "set", "project", &switch.target])
+                    .output()
+                    .map_err(|e| format!("Failed to run gcloud: {}", e))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "Failed to set project: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                Ok(())
+            })
+            .await
+        }
+        other => Err(format!("Context switching is not supported for '{}'", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tfvars(content: &str) -> Vec<(String, String)> {
+        parse_tfvars(content)
+    }
+
+    #[test]
+    fn azure_service_principal_mismatch_blocks_without_switch() {
+        let credentials = CloudCredentials {
+            cloud: Some("azure".to_string()),
+            azure_client_secret: Some("secret".to_string()),
+            azure_subscription_id: Some("11111111-1111-1111-1111-111111111111".to_string()),
+            azure_tenant_id: Some("tenant-a".to_string()),
+            ..Default::default()
+        };
+        let vars = tfvars(
+            "subscription_id = \"22222222-2222-2222-2222-222222222222\"\ntenant_id = \"TENANT-A\"\n",
+        );
+
+        let report = check_context("azure", &vars, &credentials);
+        assert!(report.blocked);
+        assert_eq!(report.checks[0].status, "mismatch");
+        assert_eq!(report.checks[1].status, "ok");
+        // A service principal's subscription can't be switched from the CLI
+        assert!(report.switch.is_none());
+        assert!(mismatch_error(&report).unwrap().contains("22222222"));
+    }
+
+    #[test]
+    fn gcp_key_file_uses_selected_project() {
+        let credentials = CloudCredentials {
+            gcp_credentials_json: Some("{}".to_string()),
+            gcp_project_id: Some("my-project-1".to_string()),
+            ..Default::default()
+        };
+        let report = check_context("gcp", &tfvars("google_project_name = \"my-project-1\"\n"), &credentials);
+        assert!(!report.blocked);
+        assert_eq!(report.checks[0].status, "ok");
+
+        let unselected = CloudCredentials { gcp_credentials_json: Some("{}".to_string()), ..Default::default() };
+        let report = check_context("gcp", &tfvars("google_project_name = \"my-project-1\"\n"), &unselected);
+        assert_eq!(report.checks[0].status, "unverified");
+        assert!(!report.blocked && mismatch_error(&report).is_none());
+
+        // Nothing configured, nothing to check
+        assert!(check_context("gcp", &[], &credentials).checks.is_empty());
+    }
+
+    #[test]
+    fn principals_are_labelled_by_kind() {
+        let user = serde_json::json!({ "user": { "name": "jane@contoso.com", "type": "user" } });
+        assert_eq!(azure_principal(&user).as_deref(), Some("Azure user: jane@contoso.com"));
+        let sp = serde_json::json!({ "user": { "name": "1111-2222", "type": "servicePrincipal" } });
+        assert_eq!(azure_principal(&sp).as_deref(), Some("Azure service principal: 1111-2222"));
+        assert_eq!(azure_principal(&serde_json::json!({})), None);
+
+        let data = std::collections::HashMap::from([("client_id".to_string(), "abc".to_string())]);
+        assert_eq!(databricks_profile_principal("deployer-sp", &data), "Databricks service principal: abc (profile deployer-sp)");
+        assert_eq!(databricks_profile_principal("dev", &Default::default()), "Databricks profile: dev");
+    }
+
+    #[test]
+    fn gcp_project_id_format() {
+        assert!(is_valid_gcp_project_id("my-project-123"));
+        assert!(!is_valid_gcp_project_id("My-Project"));
+        assert!(!is_valid_gcp_project_id("short"));
+        assert!(!is_valid_gcp_project_id("proj; rm -rf"));
+    }
+}
@@ -0,0 +1,592 @@
+//! Credential freshness checks before Terraform runs.
+//!
+//! SSO and OAuth logins (AWS SSO, Azure CLI, Databricks U2M) can expire in the
+//! middle of a long apply. Before a run each login's token is refreshed where
+//! the CLI can do it silently, and a warning is produced when the token still
+//! won't outlast the expected run time.
+//!
+//! In Azure identity mode both the azurerm and Databricks providers take their
+//! tokens from the Azure CLI login, so the run is refused up front when the CLI
+//! can't issue a token for ARM or for the Azure Databricks resource. While a
+//! run is in progress those tokens are renewed periodically, and any that can
+//! no longer be renewed are reported in the deployment status.
+
+use super::audit::now_secs;
+use super::databricks::DATABRICKS_AZURE_RESOURCE_ID;
+use super::{debug_log, opt_non_empty, uses_azure_identity, CloudCredentials};
+use crate::aws_config::AwsConfig;
+use crate::dependencies;
+use crate::terraform::DeploymentStatus;
+use serde::Serialize;
+use std::fs;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Extra time a token must remain valid beyond the expected run.
+const SAFETY_MARGIN_SECS: u64 = 5 * 60;
+
+/// How often Azure CLI tokens are renewed during a run.
+const KEEP_ALIVE_INTERVAL_SECS: u64 = 10 * 60;
+
+/// Rough upper bound on how long a Terraform command runs for a workspace.
+fn expected_run_secs(command: &str) -> u64 {
+    match command {
+        "apply" => 45 * 60,
+        "destroy" => 30 * 60,
+        _ => 10 * 60,
+    }
+}
+
+/// Login whose token lifetime is checked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenSource {
+    AwsSso,
+    AzureCli,
+    AzureDatabricks,
+    DatabricksOAuth,
+}
+
+impl TokenSource {
+    fn id(self) -> &'static str {
+        match self {
+            TokenSource::AwsSso => "aws_sso",
+            TokenSource::AzureCli => "azure_cli",
+            TokenSource::AzureDatabricks => "azure_databricks",
+            TokenSource::DatabricksOAuth => "databricks_oauth",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TokenSource::AwsSso => "AWS SSO session",
+            TokenSource::AzureCli => "Azure CLI login",
+            TokenSource::AzureDatabricks => "Azure CLI token for Azure Databricks",
+            TokenSource::DatabricksOAuth => "Databricks OAuth login",
+        }
+    }
+
+    fn login_hint(self) -> &'static str {
+        match self {
+            TokenSource::AwsSso => "Sign in again with AWS SSO",
+            TokenSource::AzureCli => "Sign in again with the Azure CLI",
+            TokenSource::AzureDatabricks => {
+                "Run `az login` and make sure your account has consented to Azure Databricks"
+            }
+            TokenSource::DatabricksOAuth => "Sign in again with the Databricks CLI",
+        }
+    }
+}
+
+/// Freshness of one login used by a Terraform run.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CredentialFreshness {
+    /// "aws_sso", "azure_cli", "azure_databricks", or "databricks_oauth".
+    pub source: String,
+    /// Seconds until the current token expires, when known.
+    pub remaining_secs: Option<u64>,
+    pub expected_run_secs: u64,
+    /// The CLI renews the token on its own while the run is in progress.
+    pub auto_renews: bool,
+    /// A refresh was performed before the run.
+    pub refreshed: bool,
+    /// Set when the login may not last through the run.
+    pub warning: Option<String>,
+}
+
+// ─── Timestamps ─────────────────────────────────────────────────────────────
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Parse an RFC 3339-style timestamp (`2026-01-01T12:00:00Z`, fractional
+/// seconds, `UTC` suffix, or a `+01:00` offset) into Unix seconds.
+/// Timestamps without a zone are rejected since they are in local time.
+pub(crate) fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if !value.is_ascii() || value.len() < 19 {
+        return None;
+    }
+    let num = |s: &str| s.parse::<i64>().ok();
+    let (date, rest) = value.split_at(10);
+    let mut date_parts = date.split('-');
+    let (year, month, day) = (num(date_parts.next()?)?, num(date_parts.next()?)?, num(date_parts.next()?)?);
+    let rest = rest.strip_prefix(['T', ' '])?;
+    let (time, zone) = rest.split_at(8);
+    let mut time_parts = time.split(':');
+    let (hour, minute, second) = (num(time_parts.next()?)?, num(time_parts.next()?)?, num(time_parts.next()?)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let zone = match zone.strip_prefix('.') {
+        Some(fraction) => fraction.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => zone,
+    };
+    let offset_secs = match zone.trim() {
+        "Z" | "UTC" | "+00:00" => 0,
+        z if (z.starts_with('+') || z.starts_with('-')) && (z.len() == 6 || z.len() == 5) => {
+            let digits = z[1..].replace(':', "");
+            let hours = num(digits.get(..2)?)?;
+            let minutes = num(digits.get(2..)?)?;
+            let sign = if z.starts_with('-') { -1 } else { 1 };
+            sign * (hours * 3600 + minutes * 60)
+        }
+        _ => return None,
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset_secs;
+    u64::try_from(secs).ok()
+}
+
+// ─── Assessment ─────────────────────────────────────────────────────────────
+
+/// Build the freshness entry for a token expiring at `expires_at`.
+fn assess(
+    source: TokenSource,
+    expires_at: Option<u64>,
+    auto_renews: bool,
+    refreshed: bool,
+    now: u64,
+    expected: u64,
+) -> CredentialFreshness {
+    let remaining = expires_at.map(|at| at.saturating_sub(now));
+    let warning = match remaining {
+        Some(0) => Some(format!("{} has expired. {} before running Terraform.", source.label(), source.login_hint())),
+        Some(secs) if !auto_renews && secs < expected + SAFETY_MARGIN_SECS => Some(format!(
+            "{} expires in about {} min, but this run can take up to {} min. {} to avoid failures mid-run.",
+            source.label(),
+            secs / 60,
+            expected / 60,
+            source.login_hint()
+        )),
+        _ => None,
+    };
+    CredentialFreshness {
+        source: source.id().to_string(),
+        remaining_secs: remaining,
+        expected_run_secs: expected,
+        auto_renews,
+        refreshed,
+        warning,
+    }
+}
+
+/// Entry for a login whose refresh failed.
+fn refresh_failed(source: TokenSource, error: &str, expected: u64) -> CredentialFreshness {
+    CredentialFreshness {
+        source: source.id().to_string(),
+        remaining_secs: None,
+        expected_run_secs: expected,
+        auto_renews: false,
+        refreshed: false,
+        warning: Some(format!(
+            "{} could not be refreshed ({}). {} before running Terraform.",
+            source.label(),
+            error,
+            source.login_hint()
+        )),
+    }
+}
+
+/// Run a CLI and return stdout as JSON, or trimmed stderr on failure.
+fn run_json(program: &std::path::Path, args: &[&str]) -> Result<serde_json::Value, String> {
+    let output = super::silent_cmd(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or("unknown error").trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse CLI output: {}", e))
+}
+
+// ─── AWS SSO ────────────────────────────────────────────────────────────────
+
+/// Cached SSO access token for a start URL.
+#[derive(Debug, PartialEq)]
+struct SsoToken {
+    expires_at: u64,
+    /// `sso-session` logins keep a refresh token the CLI and SDKs renew with.
+    refreshable: bool,
+}
+
+/// Pick the longest-lived cache entry for `start_url`.
+fn find_sso_token(entries: &[serde_json::Value], start_url: &str) -> Option<SsoToken> {
+    let start_url = start_url.trim_end_matches('/');
+    entries
+        .iter()
+        .filter(|e| e["startUrl"].as_str().map(|u| u.trim_end_matches('/')) == Some(start_url))
+        .filter(|e| e["accessToken"].as_str().is_some_and(|t| !t.is_empty()))
+        .filter_map(|e| {
+            Some(SsoToken {
+                expires_at: parse_timestamp(e["expiresAt"].as_str()?)?,
+                refreshable: e["refreshToken"].as_str().is_some_and(|t| !t.is_empty()),
+            })
+        })
+        .max_by_key(|t| t.expires_at)
+}
+
+fn read_sso_cache(start_url: &str) -> Option<SsoToken> {
+    let cache_dir = dirs::home_dir()?.join(".aws").join("sso").join("cache");
+    let entries: Vec<serde_json::Value> = fs::read_dir(cache_dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| serde_json::from_str(&fs::read_to_string(e.path()).ok()?).ok())
+        .collect();
+    find_sso_token(&entries, start_url)
+}
+
+fn check_aws_sso(profile: &str, expected: u64) -> Option<CredentialFreshness> {
+    let start_url = AwsConfig::load().sso_start_url(profile)?;
+    let token = read_sso_cache(&start_url)?;
+    let now = now_secs();
+
+    if !token.refreshable || token.expires_at >= now + expected + SAFETY_MARGIN_SECS {
+        return Some(assess(TokenSource::AwsSso, Some(token.expires_at), token.refreshable, false, now, expected));
+    }
+
+    // Any authenticated call makes the CLI renew a near-expiry sso-session token
+    let aws_path = dependencies::find_aws_cli_path()?;
+    if let Err(e) = run_json(&aws_path, &["sts", "get-caller-identity", "--profile", profile, "--output", "json"]) {
+        return Some(refresh_failed(TokenSource::AwsSso, &e, expected));
+    }
+    let renewed = read_sso_cache(&start_url).unwrap_or(token);
+    Some(assess(TokenSource::AwsSso, Some(renewed.expires_at), true, true, now_secs(), expected))
+}
+
+// ─── Azure CLI ──────────────────────────────────────────────────────────────
+
+/// `expires_on` is Unix seconds (as a number or string) in Azure CLI 2.54+.
+fn azure_token_expiry(token: &serde_json::Value) -> Option<u64> {
+    let expires_on = &token["expires_on"];
+    expires_on
+        .as_u64()
+        .or_else(|| expires_on.as_str()?.parse().ok())
+}
+
+/// `get-access-token` arguments for `source`: the default ARM resource, or
+/// the Azure Databricks resource in the deployment's tenant.
+fn azure_token_args(source: TokenSource, tenant_id: Option<&str>) -> Vec<&str> {
+    let mut args = vec!["account", "get-access-token", "--output", "json"];
+    if source == TokenSource::AzureDatabricks {
+        args.extend(["--resource", DATABRICKS_AZURE_RESOURCE_ID]);
+        if let Some(tenant) = tenant_id.filter(|t| !t.is_empty()) {
+            args.extend(["--tenant", tenant]);
+        }
+    }
+    args
+}
+
+/// Azure CLI tokens the run depends on: ARM unless a service principal is
+/// used, and the Azure Databricks resource in identity mode.
+fn azure_cli_sources(credentials: &CloudCredentials) -> Vec<TokenSource> {
+    let mut sources = Vec::new();
+    if credentials.cloud.as_deref() == Some("azure") && !opt_non_empty(&credentials.azure_client_secret) {
+        sources.push(TokenSource::AzureCli);
+    }
+    if uses_azure_identity(credentials) {
+        sources.push(TokenSource::AzureDatabricks);
+    }
+    sources
+}
+
+fn check_azure_cli(source: TokenSource, tenant_id: Option<&str>, expected: u64) -> Option<CredentialFreshness> {
+    let az_path = dependencies::find_azure_cli_path()?;
+    // get-access-token renews the access token from the CLI's refresh token
+    Some(match run_json(&az_path, &azure_token_args(source, tenant_id)) {
+        Ok(token) => assess(source, azure_token_expiry(&token), true, true, now_secs(), expected),
+        Err(e) => refresh_failed(source, &e, expected),
+    })
+}
+
+// ─── Databricks OAuth ───────────────────────────────────────────────────────
+
+/// Profiles with a client secret use M2M auth, which Terraform renews itself.
+fn is_u2m_profile(profile: &str) -> bool {
+    dependencies::read_databricks_config_sections()
+        .into_iter()
+        .find(|(name, _)| name == profile)
+        .is_some_and(|(_, data)| !data.contains_key("client_secret"))
+}
+
+fn check_databricks_oauth(profile: &str, expected: u64) -> Option<CredentialFreshness> {
+    if !is_u2m_profile(profile) {
+        return None;
+    }
+    let cli_path = dependencies::find_databricks_cli_path()?;
+    // `auth token` renews an expired access token from the cached refresh token
+    Some(match run_json(&cli_path, &["auth", "token", "--profile", profile, "--output", "json"]) {
+        Ok(token) => {
+            let expires_at = token["expiry"].as_str().and_then(parse_timestamp);
+            assess(TokenSource::DatabricksOAuth, expires_at, true, true, now_secs(), expected)
+        }
+        Err(e) => refresh_failed(TokenSource::DatabricksOAuth, &e, expected),
+    })
+}
+
+// ─── Entry points ───────────────────────────────────────────────────────────
+
+/// Refresh and check every SSO/OAuth login `credentials` rely on for `command`.
+pub(crate) fn check_credentials(command: &str, credentials: &CloudCredentials) -> Vec<CredentialFreshness> {
+    let expected = expected_run_secs(command);
+    let cloud = credentials.cloud.as_deref().unwrap_or("");
+    let mut results = Vec::new();
+
+    if cloud == "aws" {
+        if let Some(profile) = credentials.aws_profile.as_deref().filter(|p| !p.is_empty()) {
+            results.extend(check_aws_sso(profile, expected));
+        }
+    }
+    for source in azure_cli_sources(credentials) {
+        results.extend(check_azure_cli(source, credentials.azure_tenant_id.as_deref(), expected));
+    }
+    let databricks_sp = opt_non_empty(&credentials.databricks_client_id)
+        && opt_non_empty(&credentials.databricks_client_secret);
+    if !databricks_sp {
+        if let Some(profile) = credentials.databricks_profile.as_deref().filter(|p| !p.is_empty()) {
+            results.extend(check_databricks_oauth(profile, expected));
+        }
+    }
+
+    for _r in &results {
+        debug_log!("[credential_refresh] {}: remaining={:?} warning={:?}", _r.source, _r.remaining_secs, _r.warning);
+    }
+    results
+}
+
+/// Reason to refuse a run in Azure identity mode: the Azure CLI couldn't issue
+/// an ARM or Azure Databricks token, or the one it has is already expired.
+/// Terraform would otherwise fail partway through the run.
+pub(crate) fn azure_identity_error(credentials: &CloudCredentials, results: &[CredentialFreshness]) -> Option<String> {
+    if !uses_azure_identity(credentials) {
+        return None;
+    }
+    let azure_sources = [TokenSource::AzureCli.id(), TokenSource::AzureDatabricks.id()];
+    results
+        .iter()
+        .filter(|r| azure_sources.contains(&r.source.as_str()))
+        .filter(|r| !r.refreshed || r.remaining_secs == Some(0))
+        .find_map(|r| r.warning.clone())
+}
+
+// ─── Keep-alive during runs ─────────────────────────────────────────────────
+
+/// Warning for a token renewed mid-run (`Ok` holds its expiry) that failed to
+/// renew or won't last until the next renewal.
+fn keep_alive_warning(source: TokenSource, renewed: Result<Option<u64>, String>, now: u64) -> Option<String> {
+    match renewed {
+        Err(e) => Some(format!(
+            "{} could not be renewed during the run ({}). {} or Terraform may fail once it expires.",
+            source.label(),
+            e,
+            source.login_hint()
+        )),
+        Ok(Some(expires_at)) if expires_at < now + KEEP_ALIVE_INTERVAL_SECS + SAFETY_MARGIN_SECS => Some(format!(
+            "{} expires in about {} min and is no longer being extended. {} or Terraform may fail once it expires.",
+            source.label(),
+            expires_at.saturating_sub(now) / 60,
+            source.login_hint()
+        )),
+        Ok(_) => None,
+    }
+}
+
+/// Renews Azure CLI tokens while a run is in progress; stops when dropped.
+pub(crate) struct AzureTokenKeepAlive {
+    _stop: mpsc::Sender<()>,
+}
+
+/// Start renewing the Azure CLI tokens `credentials` rely on every
+/// [`KEEP_ALIVE_INTERVAL_SECS`], replacing `credential_warnings` in `status`
+/// with the outcome. `None` when the run uses no Azure CLI login.
+pub(crate) fn keep_azure_tokens_alive(
+    credentials: &CloudCredentials,
+    status: Arc<Mutex<DeploymentStatus>>,
+) -> Option<AzureTokenKeepAlive> {
+    let sources = azure_cli_sources(credentials);
+    if sources.is_empty() {
+        return None;
+    }
+    let az_path = dependencies::find_azure_cli_path()?;
+    let tenant_id = credentials.azure_tenant_id.clone();
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    std::thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(Duration::from_secs(KEEP_ALIVE_INTERVAL_SECS)) {
+            let warnings: Vec<String> = sources
+                .iter()
+                .filter_map(|&source| {
+                    let renewed = run_json(&az_path, &azure_token_args(source, tenant_id.as_deref()))
+                        .map(|token| azure_token_expiry(&token));
+                    keep_alive_warning(source, renewed, now_secs())
+                })
+                .collect();
+            debug_log!("[credential_refresh] keep-alive renewed {} token(s), {} warning(s)", sources.len(), warnings.len());
+            if let Ok(mut s) = status.lock() {
+                if s.running {
+                    s.credential_warnings = warnings;
+                }
+            }
+        }
+    });
+    Some(AzureTokenKeepAlive { _stop: stop })
+}
+
+/// Check (and refresh where possible) the logins a Terraform command will use.
+#[tauri::command]
+pub async fn check_credential_freshness(
+    command: String,
+    credentials: CloudCredentials,
+) -> Result<Vec<CredentialFreshness>, String> {
+    tokio::task::spawn_blocking(move || check_credentials(&command, &credentials))
+        .await
+        .map_err(|e| format!("Failed to check credentials: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_767_225_600; // 2026-01-01T00:00:00Z
+
+    #[test]
+    fn parses_timestamp_variants() {
+        assert_eq!(parse_timestamp("2026-01-01T00:00:00Z"), Some(NOW));
+        assert_eq!(parse_timestamp("2026-01-01T00:00:00UTC"), Some(NOW));
+        assert_eq!(parse_timestamp("2026-01-01T00:00:00.123456789Z"), Some(NOW));
+        assert_eq!(parse_timestamp("2026-01-01T01:30:00+01:30"), Some(NOW));
+        assert_eq!(parse_timestamp("2025-12-31T19:00:00-0500"), Some(NOW));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    }
+
+    #[test]
+    fn rejects_local_and_malformed_timestamps() {
+        assert_eq!(parse_timestamp("2026-01-01 00:00:00.000000"), None);
+        assert_eq!(parse_timestamp("2026-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("not a timestamp at all"), None);
+        assert_eq!(parse_timestamp(""), None);
+    }
+
+    #[test]
+    fn assess_warns_only_when_token_will_lapse() {
+        let expected = expected_run_secs("apply");
+        let short = assess(TokenSource::AwsSso, Some(NOW + 600), false, false, NOW, expected);
+        assert_eq!(short.remaining_secs, Some(600));
+        assert!(short.warning.unwrap().contains("expires in about 10 min"));
+
+        let long = assess(TokenSource::AwsSso, Some(NOW + 8 * 3600), false, false, NOW, expected);
+        assert_eq!(long.warning, None);
+
+        // Renewable tokens only warn once already expired
+        assert_eq!(assess(TokenSource::AzureCli, Some(NOW + 600), true, true, NOW, expected).warning, None);
+        let expired = assess(TokenSource::AzureCli, Some(NOW - 60), true, true, NOW, expected);
+        assert!(expired.warning.unwrap().contains("has expired"));
+    }
+
+    fn sso_start_url(config: &str, profile: &str) -> Option<String> {
+        AwsConfig::parse(config, "").sso_start_url(profile)
+    }
+
+    #[test]
+    fn resolves_sso_start_url_from_profile_or_session() {
+        let config = "\
+[default]
+sso_start_url = https://legacy.awsapps.com/start
+
+[profile dev]
+sso_session = corp
+sso_account_id = 111122223333
+
+[sso-session corp]
+sso_start_url = https://corp.awsapps.com/start/
+sso_region = us-east-1
+
+[profile static]
+region = us-west-2
+";
+        assert_eq!(sso_start_url(config, "default").as_deref(), Some("https://legacy.awsapps.com/start"));
+        assert_eq!(sso_start_url(config, "dev").as_deref(), Some("https://corp.awsapps.com/start/"));
+        assert_eq!(sso_start_url(config, "static"), None);
+        assert_eq!(sso_start_url(config, "missing"), None);
+    }
+
+    #[test]
+    fn finds_longest_lived_sso_token() {
+        let entries = vec![
+            serde_json::json!({"startUrl": "https://corp.awsapps.com/start", "accessToken": "a", "expiresAt": "2026-01-01T01:00:00Z"}),
+            serde_json::json!({"startUrl": "https://corp.awsapps.com/start", "accessToken": "b", "expiresAt": "2026-01-01T08:00:00Z", "refreshToken": "r"}),
+            serde_json::json!({"startUrl": "https://other.awsapps.com/start", "accessToken": "c", "expiresAt": "2027-01-01T00:00:00Z"}),
+            // Client registration files have no access token
+            serde_json::json!({"clientId": "x", "expiresAt": "2027-01-01T00:00:00Z"}),
+        ];
+        assert_eq!(
+            find_sso_token(&entries, "https://corp.awsapps.com/start/"),
+            Some(SsoToken { expires_at: NOW + 8 * 3600, refreshable: true })
+        );
+        assert_eq!(find_sso_token(&entries, "https://none.awsapps.com/start"), None);
+    }
+
+    #[test]
+    fn azure_databricks_token_targets_resource_and_tenant() {
+        assert_eq!(azure_token_args(TokenSource::AzureCli, Some("tid")), vec!["account", "get-access-token", "--output", "json"]);
+        let args = azure_token_args(TokenSource::AzureDatabricks, Some("tid"));
+        assert!(args.windows(2).any(|w| w == ["--resource", DATABRICKS_AZURE_RESOURCE_ID]));
+        assert!(args.windows(2).any(|w| w == ["--tenant", "tid"]));
+        assert!(!azure_token_args(TokenSource::AzureDatabricks, Some("")).contains(&"--tenant"));
+    }
+
+    #[test]
+    fn azure_identity_blocks_on_missing_or_expired_tokens() {
+        let expected = expected_run_secs("apply");
+        let identity = CloudCredentials {
+            cloud: Some("azure".to_string()),
+            azure_databricks_use_identity: Some(true),
+            ..Default::default()
+        };
+        let healthy = vec![
+            assess(TokenSource::AzureCli, Some(NOW + 600), true, true, NOW, expected),
+            assess(TokenSource::AzureDatabricks, Some(NOW + 3600), true, true, NOW, expected),
+        ];
+        assert_eq!(azure_identity_error(&identity, &healthy), None);
+
+        let failed = vec![refresh_failed(TokenSource::AzureDatabricks, "AADSTS65001: consent required", expected)];
+        let error = azure_identity_error(&identity, &failed).unwrap();
+        assert!(error.contains("Azure Databricks") && error.contains("AADSTS65001"));
+
+        let expired = vec![assess(TokenSource::AzureCli, Some(NOW - 60), true, true, NOW, expected)];
+        assert!(azure_identity_error(&identity, &expired).unwrap().contains("has expired"));
+
+        // Without identity mode the same results are only warnings
+        let sp = CloudCredentials { cloud: Some("azure".to_string()), ..Default::default() };
+        assert_eq!(azure_identity_error(&sp, &failed), None);
+    }
+
+    #[test]
+    fn keep_alive_warns_when_renewal_fails_or_falls_short() {
+        assert_eq!(keep_alive_warning(TokenSource::AzureCli, Ok(Some(NOW + 3600)), NOW), None);
+        assert_eq!(keep_alive_warning(TokenSource::AzureCli, Ok(None), NOW), None);
+
+        let short = keep_alive_warning(TokenSource::AzureDatabricks, Ok(Some(NOW + 600)), NOW).unwrap();
+        assert!(short.contains("expires in about 10 min"));
+
+        let failed = keep_alive_warning(TokenSource::AzureCli, Err("AADSTS70043".to_string()), NOW).unwrap();
+        assert!(failed.contains("could not be renewed") && failed.contains("AADSTS70043"));
+    }
+
+    #[test]
+    fn reads_azure_expires_on_as_number_or_string() {
+        assert_eq!(azure_token_expiry(&serde_json::json!({"expires_on": NOW})), Some(NOW));
+        assert_eq!(azure_token_expiry(&serde_json::json!({"expires_on": NOW.to_string()})), Some(NOW));
+        assert_eq!(azure_token_expiry(&serde_json::json!({"expiresOn": "2026-01-01 00:00:00.000000"})), None);
+    }
+}
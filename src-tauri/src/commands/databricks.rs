@@ -1,17 +1,27 @@
 //! Databricks authentication and Unity Catalog permission commands.
 
 use super::debug_log;
-use super::{databricks_accounts_host, http_client, is_valid_uuid};
+use super::cancellation::CancelToken;
+use super::cli_runner::{CliCommand, CliTool};
+use super::metadata_cache;
+use super::{databricks_accounts_host, is_valid_uuid};
 #[cfg(debug_assertions)]
 use super::mask_sensitive_id;
-use super::{CloudCredentials, MetastoreInfo, UCPermissionCheck};
+use super::preflight::WARNING;
+use super::region_aliases::same_region;
+#[cfg(debug_assertions)]
+use super::region_aliases::canonical_region;
+use super::{CloudCredentials, MetastoreInfo, PreflightEntry, PreflightReport, UCPermissionCheck};
+use crate::databricks_api::{self, AccountAuth, AccountClient, ApiError, Metastore, PrivilegeAssignment};
 use crate::dependencies;
+use crate::endpoints::https_url;
 use serde::Serialize;
 use std::fs;
 use std::process::Stdio;
+use tauri::AppHandle;
 
 /// Azure AD resource ID for Databricks - used to obtain tokens for account-level APIs
-const DATABRICKS_AZURE_RESOURCE_ID: &str = "2ff814a6-3304-4ab8-85cb-cd0e6f879c1d";
+pub(super) const DATABRICKS_AZURE_RESOURCE_ID: &str = "2ff814a6-3304-4ab8-85cb-cd0e6f879c1d";
 
 const MSG_NO_METASTORE_PREFIX: &str = "No metastore found in region.";
 const MSG_METASTORE_UNAVAILABLE: &str =
@@ -38,20 +48,19 @@ fn get_azure_databricks_token_with_fallback(
         token_args.push(tid.to_string());
     }
 
-    let token_output = super::silent_cmd(az_cli_path)
+    let token_output = CliCommand::with_path(CliTool::Azure, az_cli_path)
         .args(&token_args)
-        .output()
-        .map_err(|e| format!("Failed to get Azure AD token: {}", e))?;
+        .run()?;
 
-    if token_output.status.success() {
-        let token = String::from_utf8_lossy(&token_output.stdout).trim().to_string();
+    if token_output.success {
+        let token = token_output.stdout.trim().to_string();
         if token.is_empty() {
             return Err("Azure AD token response was empty.".to_string());
         }
         return Ok(token);
     }
 
-    let stderr = String::from_utf8_lossy(&token_output.stderr).to_lowercase();
+    let stderr = token_output.stderr.to_lowercase();
     let needs_interactive_login = stderr.contains("interaction_required")
         || stderr.contains("consent_required")
         || stderr.contains("aadsts65001")
@@ -61,7 +70,7 @@ fn get_azure_databricks_token_with_fallback(
     if !needs_interactive_login {
         return Err(format!(
             "Failed to authenticate with Azure AD: {}",
-            String::from_utf8_lossy(&token_output.stderr).trim()
+            token_output.stderr.trim()
         ));
     }
 
@@ -130,25 +139,67 @@ fn get_azure_databricks_token_with_fallback(
 
     login_result?;
 
-    let retry_output = super::silent_cmd(az_cli_path)
+    let retry_output = CliCommand::with_path(CliTool::Azure, az_cli_path)
         .args(&token_args)
-        .output()
-        .map_err(|e| format!("Failed to get Azure AD token after login: {}", e))?;
+        .run()?;
 
-    if !retry_output.status.success() {
+    if !retry_output.success {
         return Err(format!(
             "Failed to authenticate with Azure AD after interactive login: {}",
-            String::from_utf8_lossy(&retry_output.stderr).trim()
+            retry_output.stderr.trim()
         ));
     }
 
-    let token = String::from_utf8_lossy(&retry_output.stdout).trim().to_string();
+    let token = retry_output.stdout.trim().to_string();
     if token.is_empty() {
         return Err("Azure AD token response was empty after interactive login.".to_string());
     }
     Ok(token)
 }
 
+/// Account API auth for the deployment credentials: a CLI profile, a service
+/// principal, or the cloud identity (Azure CLI token, GCP ID token).
+pub(crate) async fn account_auth(credentials: &CloudCredentials) -> Result<AccountAuth, String> {
+    let non_empty = |v: &Option<String>| v.clone().filter(|s| !s.is_empty());
+    if credentials.databricks_auth_type.as_deref() == Some("profile") {
+        let profile = non_empty(&credentials.databricks_profile).unwrap_or_else(|| "DEFAULT".to_string());
+        return Ok(AccountAuth::CliProfile(profile));
+    }
+    if let (Some(client_id), Some(client_secret)) = (
+        non_empty(&credentials.databricks_client_id),
+        non_empty(&credentials.databricks_client_secret),
+    ) {
+        return Ok(AccountAuth::ServicePrincipal {
+            client_id,
+            client_secret,
+        });
+    }
+    match credentials.cloud.as_deref() {
+        Some("azure") => {
+            let az = dependencies::find_azure_cli_path().ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
+            let tenant = credentials.azure_tenant_id.clone();
+            tokio::task::spawn_blocking(move || get_azure_databricks_token_with_fallback(&az, tenant.as_deref()))
+                .await
+                .map_err(|e| format!("Token task panicked: {}", e))?
+                .map(AccountAuth::AzureAd)
+        }
+        Some("gcp") => {
+            let token = if let Some(sa_json) = non_empty(&credentials.gcp_credentials_json) {
+                databricks_api::gcp_id_token_from_key(&sa_json).await?
+            } else if let (Some(oauth), Some(sa)) = (
+                non_empty(&credentials.gcp_oauth_token),
+                non_empty(&credentials.gcp_service_account_email),
+            ) {
+                databricks_api::gcp_id_token_via_iam(&oauth, &sa).await?
+            } else {
+                return Err("GCP needs a service account key or an impersonated service account".to_string());
+            };
+            Ok(AccountAuth::GcpToken(token))
+        }
+        _ => Err("No Databricks account credentials provided".to_string()),
+    }
+}
+
 /// List Databricks CLI profiles for a given cloud.
 #[tauri::command]
 pub fn get_databricks_profiles(cloud: String) -> Vec<dependencies::DatabricksProfile> {
@@ -156,8 +207,10 @@ pub fn get_databricks_profiles(cloud: String) -> Vec<dependencies::DatabricksPro
 }
 
 /// Run interactive `databricks auth login` for a given cloud/account.
+/// Output is captured and the authorization URL forwarded to the frontend
+/// (see [`super::login_flow`]); cancellable via `cancel_cli_login`.
 #[tauri::command]
-pub async fn databricks_cli_login(cloud: String, account_id: String) -> Result<String, String> {
+pub async fn databricks_cli_login(app: AppHandle, cloud: String, account_id: String) -> Result<String, String> {
     let cli_path = dependencies::find_databricks_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Databricks CLI"))?;
 
@@ -165,33 +218,14 @@ pub async fn databricks_cli_login(cloud: String, account_id: String) -> Result<S
 
     let profile_name = format!("deployer-{}", &account_id[..8.min(account_id.len())]);
 
-    // Clear the token cache to force re-authentication
-    if let Some(home) = dirs::home_dir() {
-        let token_cache_path = home.join(".databricks").join("token-cache.json");
-        if token_cache_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&token_cache_path) {
-                if let Ok(mut cache) = serde_json::from_str::<serde_json::Value>(&content) {
-                    if let Some(obj) = cache.as_object_mut() {
-                        let keys_to_remove: Vec<String> = obj
-                            .keys()
-                            .filter(|k| k.contains(&account_id) || k.contains(&host))
-                            .cloned()
-                            .collect();
-
-                        for key in keys_to_remove {
-                            obj.remove(&key);
-                        }
-
-                        if let Ok(new_content) = serde_json::to_string_pretty(&cache) {
-                            let _ = std::fs::write(&token_cache_path, new_content);
-                        }
-                    }
-                }
-            }
+    // Drop this account's cached tokens to force re-authentication
+    if let Some(cache_path) = super::databricks_profiles::token_cache_path() {
+        if let Err(_e) = super::databricks_profiles::invalidate_cached_tokens(&cache_path, &host, Some(&account_id)) {
+            debug_log!("[databricks_cli_login] Could not clear token cache: {}", _e);
         }
     }
 
-    let mut child = super::silent_cmd(&cli_path)
+    let child = super::silent_cmd(&cli_path)
         .args([
             "auth",
             "login",
@@ -202,17 +236,28 @@ pub async fn databricks_cli_login(cloud: String, account_id: String) -> Result<S
             "--profile",
             &profile_name,
         ])
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
         .map_err(|e| format!("Failed to run Databricks CLI: {}", e))?;
 
-    let status = child
-        .wait()
-        .map_err(|e| format!("Failed to wait for Databricks CLI: {}", e))?;
-
-    if status.success() {
+    let exit = super::login_flow::run_login_process(
+        &app,
+        "databricks",
+        child,
+        std::time::Duration::from_secs(300),
+        "Databricks login timed out after 5 minutes. Please try again.",
+    )
+    .await?;
+
+    if exit.success {
+        if let Some(config_path) = dependencies::get_databricks_config_path() {
+            super::bootstrap_artifacts::record_quietly(
+                &app,
+                super::bootstrap_artifacts::ArtifactKind::DatabricksProfile { name: profile_name.clone(), config_path },
+            );
+        }
         Ok(format!(
             "Login successful! Profile '{}' created/updated.",
             profile_name
@@ -221,7 +266,10 @@ pub async fn databricks_cli_login(cloud: String, account_id: String) -> Result<S
         let profiles = dependencies::get_databricks_profiles_for_cloud(&cloud);
         if profiles.iter().any(|p| p.name == profile_name) {
             Ok(format!("Profile '{}' is ready.", profile_name))
+        } else if exit.cancelled {
+            Err("LOGIN_CANCELLED".to_string())
         } else {
+            debug_log!("[databricks_cli_login] login failed: {}", exit.stderr);
             Err("Login failed or was cancelled. Please try again.".to_string())
         }
     }
@@ -273,6 +321,7 @@ pub fn get_databricks_profile_credentials(
 /// Create a Databricks CLI profile with service principal credentials.
 #[tauri::command]
 pub fn create_databricks_sp_profile(
+    app: AppHandle,
     cloud: String,
     account_id: String,
     client_id: String,
@@ -342,6 +391,10 @@ pub fn create_databricks_sp_profile(
 
     fs::write(&config_path, new_content)
         .map_err(|e| format!("Failed to write config file: {}", e))?;
+    super::bootstrap_artifacts::record_quietly(
+        &app,
+        super::bootstrap_artifacts::ArtifactKind::DatabricksProfile { name: profile_name.clone(), config_path },
+    );
 
     Ok(profile_name)
 }
@@ -354,60 +407,30 @@ pub async fn validate_databricks_credentials(
     client_secret: String,
     cloud: String,
 ) -> Result<String, String> {
-    let accounts_host = databricks_accounts_host(&cloud);
-
-    let token_url = format!(
-        "https://{}/oidc/accounts/{}/v1/token",
-        accounts_host, account_id
-    );
-
-    let client = http_client()?;
-
-    let token_response = client
-        .post(&token_url)
-        .form(&[
-            ("grant_type", "client_credentials"),
-            ("scope", "all-apis"),
-        ])
-        .basic_auth(&client_id, Some(&client_secret))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to connect to Databricks: {}", e))?;
-
-    if !token_response.status().is_success() {
-        let status = token_response.status();
-        let error_text = token_response.text().await.unwrap_or_default();
-        return Err(format!(
-            "Authentication failed ({}): Invalid credentials or account ID. {}",
-            status, error_text
-        ));
+    let client = AccountClient::new(
+        &cloud,
+        &account_id,
+        AccountAuth::ServicePrincipal {
+            client_id,
+            client_secret,
+        },
+    )?;
+
+    if let Err(e) = client.access_token().await {
+        return Err(match e.status() {
+            Some(status) => format!(
+                "Authentication failed ({}): Invalid credentials or account ID. {}",
+                status,
+                e.body()
+            ),
+            None => e.to_string(),
+        });
     }
 
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse token response: {}", e))?;
-
-    let access_token = token_json["access_token"]
-        .as_str()
-        .ok_or("No access token in response")?;
-
     // Use SCIM API to list users — only account admins can do this
-    let users_url = format!(
-        "https://{}/api/2.0/accounts/{}/scim/v2/Users?count=1",
-        accounts_host, account_id
-    );
-
-    let users_response = client
-        .get(&users_url)
-        .bearer_auth(access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to verify account access: {}", e))?;
-
-    if !users_response.status().is_success() {
-        let status = users_response.status();
-        if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::UNAUTHORIZED {
+    let users = match client.list_users(1).await {
+        Ok(users) => users,
+        Err(e) if e.is_auth_failure() => {
             return Err(
                 "Service principal does not have account admin privileges. \
                 Please grant 'Account admin' role in Databricks Account Console → \
@@ -415,18 +438,19 @@ pub async fn validate_databricks_credentials(
                     .to_string(),
             );
         }
-        return Err(format!(
-            "Cannot verify account access ({}). Check your Account ID and service principal permissions.",
-            status
-        ));
-    }
-
-    let users_json: serde_json::Value = users_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        Err(ApiError::Network(e)) => return Err(format!("Failed to verify account access: {}", e)),
+        Err(e) => {
+            return Err(match e.status() {
+                Some(status) => format!(
+                    "Cannot verify account access ({}). Check your Account ID and service principal permissions.",
+                    status
+                ),
+                None => e.to_string(),
+            });
+        }
+    };
 
-    if users_json.get("totalResults").is_some() || users_json.get("Resources").is_some() {
+    if users.total_results.is_some() || users.resources.is_some() {
         return Ok("Credentials validated - Account Admin access confirmed".to_string());
     }
 
@@ -439,6 +463,13 @@ pub async fn validate_databricks_credentials(
 pub async fn validate_databricks_profile(
     profile_name: String,
     cloud: String,
+) -> Result<String, String> {
+    super::run_blocking(move || validate_databricks_profile_blocking(profile_name, cloud)).await
+}
+
+fn validate_databricks_profile_blocking(
+    profile_name: String,
+    cloud: String,
 ) -> Result<String, String> {
     let cli_path = dependencies::find_databricks_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Databricks CLI"))?;
@@ -446,17 +477,16 @@ pub async fn validate_databricks_profile(
     let accounts_host = databricks_accounts_host(&cloud);
 
     // Use the CLI to list users (requires account admin access)
-    let output = super::silent_cmd(&cli_path)
+    let output = CliCommand::with_path(CliTool::Databricks, &cli_path)
         .args([
             "account", "users", "list",
             "--profile", &profile_name,
             "--output", "json",
         ])
-        .output()
-        .map_err(|e| format!("Failed to run Databricks CLI: {}", e))?;
+        .run()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
+        let stderr = &output.stderr;
         let stderr_trimmed = stderr.trim();
 
         if stderr_trimmed.contains("unauthorized") || stderr_trimmed.contains("401") {
@@ -485,21 +515,21 @@ pub async fn validate_databricks_profile(
 
 // ─── Unity Catalog ──────────────────────────────────────────────────────────
 
-/// Normalize a region string for case-/punctuation-insensitive comparison.
-fn normalize_region(s: &str) -> String {
-    s.to_lowercase().replace(' ', "").replace('-', "")
+/// Account metastores, served from [`metadata_cache`] unless `refresh` is set.
+pub(super) async fn list_metastores(
+    client: &AccountClient,
+    cloud: &str,
+    credentials: &CloudCredentials,
+    refresh: bool,
+) -> Result<Vec<Metastore>, ApiError> {
+    let host = https_url(databricks_accounts_host(cloud), "");
+    let key = metadata_cache::key("metastores", Some(credentials), &[cloud, &host]);
+    metadata_cache::get_or_fetch_async(key, refresh, client.list_metastores()).await
 }
 
-/// Find the first metastore in a JSON array whose region matches (normalized).
-fn find_metastore_for_region<'a>(
-    metastores: Option<&'a Vec<serde_json::Value>>,
-    region: &str,
-) -> Option<&'a serde_json::Value> {
-    let region_normalized = normalize_region(region);
-    metastores?.iter().find(|m| {
-        let mr = m["region"].as_str().unwrap_or("");
-        normalize_region(mr) == region_normalized
-    })
+/// Find the first metastore in `region` on `cloud`, in any spelling of the region name.
+pub(super) fn find_metastore_for_region<'a>(metastores: &'a [Metastore], cloud: &str, region: &str) -> Option<&'a Metastore> {
+    metastores.iter().find(|m| same_region(cloud, &m.region, region))
 }
 
 /// Generate a message about metastore ownership for permission guidance.
@@ -551,11 +581,139 @@ fn get_metastore_owner_info(metastore_owner: &str, credentials: &CloudCredential
     }
 }
 
-/// Check Unity Catalog permissions (metastore presence and grants).
+/// Name of the metastore entry in Unity Catalog checklists.
+const UC_METASTORE_CHECK: &str = "Unity Catalog metastore";
+
+/// Privileges needed to create the deployment's catalog, in checklist order.
+const UC_CATALOG_PRIVILEGES: [&str; 3] =
+    ["CREATE_CATALOG", "CREATE_EXTERNAL_LOCATION", "CREATE_STORAGE_CREDENTIAL"];
+
+/// Unity Catalog privilege management docs, linked from remediation.
+const UC_PRIVILEGES_DOCS_URL: &str =
+    "https://docs.databricks.com/en/data-governance/unity-catalog/manage-privileges/index.html";
+
+/// Result when metastore detection could not run; deployment proceeds.
+fn metastore_unknown(region: String, message: &str) -> UCPermissionCheck {
+    UCPermissionCheck {
+        metastore: MetastoreInfo {
+            exists: false,
+            metastore_id: None,
+            metastore_name: None,
+            region: Some(region),
+        },
+        has_create_catalog: true,
+        has_create_external_location: true,
+        has_create_storage_credential: true,
+        can_create_catalog: true,
+        message: message.to_string(),
+        report: PreflightReport::single(PreflightEntry::skipped(UC_METASTORE_CHECK, message)),
+    }
+}
+
+/// Result when the region has no metastore; the deployer becomes its admin.
+fn no_metastore(region: String, credentials: &CloudCredentials) -> UCPermissionCheck {
+    let mut check = metastore_unknown(
+        region,
+        &format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(credentials)),
+    );
+    check.report = PreflightReport::single(PreflightEntry::pass(UC_METASTORE_CHECK, &check.message));
+    check
+}
+
+/// Metastore entry plus one entry per catalog-creation privilege. `granted` is
+/// `None` when the metastore's grants couldn't be inspected.
+fn uc_report(metastore: &Metastore, granted: Option<[bool; 3]>, principal: &str) -> PreflightReport {
+    let mut report = PreflightReport::single(PreflightEntry::pass(
+        UC_METASTORE_CHECK,
+        &format!("Using metastore '{}'", metastore.name),
+    ));
+    for (i, privilege) in UC_CATALOG_PRIVILEGES.iter().enumerate() {
+        report.push(match granted.map(|g| g[i]) {
+            Some(true) => PreflightEntry::pass(privilege, "Granted"),
+            Some(false) => PreflightEntry::fail(privilege, WARNING, "Not granted on the metastore")
+                .remediation(format!(
+                    "Ask a metastore admin to run: GRANT {} ON METASTORE TO `{}`",
+                    privilege.replace('_', " "),
+                    principal
+                ))
+                .docs(UC_PRIVILEGES_DOCS_URL),
+            None => PreflightEntry::skipped(privilege, "Metastore grants could not be inspected")
+                .docs(UC_PRIVILEGES_DOCS_URL),
+        });
+    }
+    report
+}
+
+/// Result for a metastore listing when grants cannot be inspected: the
+/// regional metastore with ownership guidance, or [`no_metastore`].
+fn metastore_check(
+    metastores: &[Metastore],
+    cloud: &str,
+    region: String,
+    credentials: &CloudCredentials,
+) -> UCPermissionCheck {
+    let Some(metastore) = find_metastore_for_region(metastores, cloud, &region) else {
+        return no_metastore(region, credentials);
+    };
+    UCPermissionCheck {
+        metastore: MetastoreInfo {
+            exists: true,
+            metastore_id: Some(metastore.metastore_id.clone()),
+            metastore_name: Some(metastore.name.clone()),
+            region: Some(region),
+        },
+        has_create_catalog: false,
+        has_create_external_location: false,
+        has_create_storage_credential: false,
+        can_create_catalog: false,
+        message: get_metastore_owner_info(&metastore.owner, credentials),
+        report: uc_report(metastore, None, &get_current_identity(credentials)),
+    }
+}
+
+/// (CREATE_CATALOG, CREATE_EXTERNAL_LOCATION, CREATE_STORAGE_CREDENTIAL)
+/// granted to `principal` on the metastore.
+fn granted_privileges(assignments: &[PrivilegeAssignment], principal: &str) -> (bool, bool, bool) {
+    let mut create_catalog = false;
+    let mut create_ext_loc = false;
+    let mut create_storage_cred = false;
+
+    for assignment in assignments.iter().filter(|a| a.principal == principal) {
+        for privilege in &assignment.privileges {
+            match privilege.as_str() {
+                "CREATE_CATALOG" => create_catalog = true,
+                "CREATE_EXTERNAL_LOCATION" => create_ext_loc = true,
+                "CREATE_STORAGE_CREDENTIAL" => create_storage_cred = true,
+                "ALL_PRIVILEGES" => {
+                    create_catalog = true;
+                    create_ext_loc = true;
+                    create_storage_cred = true;
+                }
+                _ => {}
+            }
+        }
+    }
+    (create_catalog, create_ext_loc, create_storage_cred)
+}
+
+/// Check Unity Catalog permissions (metastore presence and grants). The
+/// metastore list is cached unless `refresh` is set; grants are always read.
 #[tauri::command]
 pub async fn check_uc_permissions(
     credentials: CloudCredentials,
     region: String,
+    refresh: Option<bool>,
+    request_id: Option<String>,
+) -> Result<UCPermissionCheck, String> {
+    let refresh = refresh.unwrap_or(false);
+    super::cancellation::cancellable(request_id, |cancel| uc_permission_check(credentials, region, refresh, cancel)).await
+}
+
+async fn uc_permission_check(
+    credentials: CloudCredentials,
+    region: String,
+    refresh: bool,
+    cancel: CancelToken,
 ) -> Result<UCPermissionCheck, String> {
     let cloud = credentials.cloud.as_deref().unwrap_or_else(|| {
         if credentials.azure_tenant_id.is_some() {
@@ -581,25 +739,16 @@ pub async fn check_uc_permissions(
     debug_log!("[check_uc_permissions] ENTER: cloud={}, auth_type={}, region={}", cloud, auth_type, region);
 
     // Azure Identity mode: use Azure CLI to get token and call Databricks API directly
-    if cloud == "azure" && credentials.azure_databricks_use_identity == Some(true) {
+    if super::uses_azure_identity(&credentials) {
         debug_log!("[check_uc_permissions] Using Azure identity mode");
         
         let az_cli_path = match dependencies::find_azure_cli_path() {
             Some(path) => path,
             None => {
-                return Ok(UCPermissionCheck {
-                    metastore: MetastoreInfo {
-                        exists: false,
-                        metastore_id: None,
-                        metastore_name: None,
-                        region: Some(region),
-                    },
-                    has_create_catalog: true,
-                    has_create_external_location: true,
-                    has_create_storage_credential: true,
-                    can_create_catalog: true,
-                    message: "Azure CLI not installed. Metastore detection unavailable.".to_string(),
-                });
+                return Ok(metastore_unknown(
+                    region,
+                    "Azure CLI not installed. Metastore detection unavailable.",
+                ));
             }
         };
         
@@ -624,109 +773,33 @@ pub async fn check_uc_permissions(
             }
             Err(e) => {
                 debug_log!("[check_uc_permissions] az token acquisition failed: {}", e);
-                return Ok(UCPermissionCheck {
-                    metastore: MetastoreInfo {
-                        exists: false,
-                        metastore_id: None,
-                        metastore_name: None,
-                        region: Some(region),
-                    },
-                    has_create_catalog: true,
-                    has_create_external_location: true,
-                    has_create_storage_credential: true,
-                    can_create_catalog: true,
-                    message: MSG_METASTORE_UNAVAILABLE.to_string(),
-                });
+                return Ok(metastore_unknown(region, MSG_METASTORE_UNAVAILABLE));
             }
         };
         
         // Use the Azure AD token directly for metastores API (no token exchange needed)
-        let client = http_client()?;
-            
-        let metastores_url = format!(
-            "https://accounts.azuredatabricks.net/api/2.0/accounts/{}/metastores",
-            account_id
-        );
-        
+        let client = AccountClient::new("azure", account_id, AccountAuth::AzureAd(azure_token))?;
         debug_log!(
             "[check_uc_permissions] Calling metastores API for account: {}",
             mask_sensitive_id(account_id)
         );
-        
-        debug_log!("[check_uc_permissions] sending metastores GET to {}", metastores_url);
-        let metastores_response = client
-            .get(&metastores_url)
-            .bearer_auth(&azure_token)
-            .send()
-            .await;
-        debug_log!("[check_uc_permissions] metastores response received, ok={}", metastores_response.is_ok());
-        
-        if let Ok(metastores_resp) = metastores_response {
-            if metastores_resp.status().is_success() {
-                if let Ok(metastores_json) = metastores_resp.json::<serde_json::Value>().await {
-                    let metastores = metastores_json["metastores"].as_array();
-                    debug_log!(
-                        "[check_uc_permissions] Metastores API success: found {} metastore(s)",
-                        metastores.map(|arr| arr.len()).unwrap_or(0)
-                    );
 
-                    if let Some(metastore) = find_metastore_for_region(metastores, &region) {
-                        let metastore_id = metastore["metastore_id"].as_str().unwrap_or("");
-                        let metastore_name = metastore["name"].as_str().unwrap_or("");
-                        let metastore_owner = metastore["owner"].as_str().unwrap_or("");
-                        
-                        let message = get_metastore_owner_info(metastore_owner, &credentials);
-                        
-                        return Ok(UCPermissionCheck {
-                            metastore: MetastoreInfo {
-                                exists: true,
-                                metastore_id: Some(metastore_id.to_string()),
-                                metastore_name: Some(metastore_name.to_string()),
-                                region: Some(region),
-                            },
-                            has_create_catalog: false,
-                            has_create_external_location: false,
-                            has_create_storage_credential: false,
-                            can_create_catalog: false,
-                            message,
-                        });
-                    } else {
-                        return Ok(UCPermissionCheck {
-                            metastore: MetastoreInfo {
-                                exists: false,
-                                metastore_id: None,
-                                metastore_name: None,
-                                region: Some(region),
-                            },
-                            has_create_catalog: true,
-                            has_create_external_location: true,
-                            has_create_storage_credential: true,
-                            can_create_catalog: true,
-                            message: format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(&credentials)),
-                        });
-                    }
-                }
+        match list_metastores(&client, cloud, &credentials, refresh).await {
+            Ok(metastores) => {
+                debug_log!(
+                    "[check_uc_permissions] Metastores API success: found {} metastore(s)",
+                    metastores.len()
+                );
+                return Ok(metastore_check(&metastores, cloud, region, &credentials));
+            }
+            Err(_e) => {
+                debug_log!("[check_uc_permissions] Azure identity path: falling back: {}", _e);
             }
         }
-        
-        // Fallback if any step fails
-        debug_log!("[check_uc_permissions] Azure identity path: falling back (some step failed)");
-        return Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: false,
-                metastore_id: None,
-                metastore_name: None,
-                region: Some(region),
-            },
-            has_create_catalog: true,
-            has_create_external_location: true,
-            has_create_storage_credential: true,
-            can_create_catalog: true,
-            message: MSG_METASTORE_UNAVAILABLE.to_string(),
-        });
+        return Ok(metastore_unknown(region, MSG_METASTORE_UNAVAILABLE));
     }
 
-    // For AWS/Azure with profile auth, use Databricks CLI to list metastores
+    // For AWS/Azure with profile auth, list metastores with the profile's token
     debug_log!("[check_uc_permissions] checking profile path: auth_type={}, cloud={}", auth_type, cloud);
     if auth_type == "profile" && cloud != "gcp" {
         let profile_name = credentials
@@ -734,73 +807,15 @@ pub async fn check_uc_permissions(
             .as_deref()
             .unwrap_or("DEFAULT");
 
-        let cli_path = dependencies::find_databricks_cli_path();
-        debug_log!("[check_uc_permissions] profile={}, cli found={}", profile_name, cli_path.is_some());
-
-        if let Some(cli) = cli_path {
-            debug_log!("[check_uc_permissions] running: databricks account metastores list -p {}", profile_name);
-            let output = super::silent_cmd(&cli)
-                .args([
-                    "account",
-                    "metastores",
-                    "list",
-                    "--output",
-                    "json",
-                    "-p",
-                    profile_name,
-                ])
-                .output();
-            debug_log!("[check_uc_permissions] CLI command finished, success={}", output.as_ref().map(|o| o.status.success()).unwrap_or(false));
-
-            if let Ok(out) = output {
-                if out.status.success() {
-                    let stdout = String::from_utf8_lossy(&out.stdout);
-
-                    if let Ok(metastores_json) =
-                        serde_json::from_str::<serde_json::Value>(&stdout)
-                    {
-                        if let Some(arr) = metastores_json.as_array() {
-                            if let Some(metastore) = find_metastore_for_region(Some(arr), &region) {
-                                let metastore_id =
-                                    metastore["metastore_id"].as_str().unwrap_or("");
-                                let metastore_name = metastore["name"].as_str().unwrap_or("");
-                                let metastore_owner = metastore["owner"].as_str().unwrap_or("");
-
-                                let message = get_metastore_owner_info(metastore_owner, &credentials);
-
-                                return Ok(UCPermissionCheck {
-                                    metastore: MetastoreInfo {
-                                        exists: true,
-                                        metastore_id: Some(metastore_id.to_string()),
-                                        metastore_name: Some(metastore_name.to_string()),
-                                        region: Some(region),
-                                    },
-                                    has_create_catalog: false,
-                                    has_create_external_location: false,
-                                    has_create_storage_credential: false,
-                                    can_create_catalog: false,
-                                    message,
-                                });
-                            }
-                        }
-                    }
-                }
+        let client = AccountClient::new(cloud, account_id, AccountAuth::CliProfile(profile_name.to_string()))?;
+        debug_log!("[check_uc_permissions] listing metastores with profile {}", profile_name);
+        match list_metastores(&client, cloud, &credentials, refresh).await {
+            Ok(metastores) => return Ok(metastore_check(&metastores, cloud, region, &credentials)),
+            Err(_e) => {
+                debug_log!("[check_uc_permissions] profile metastore listing failed: {}", _e);
             }
         }
-
-        return Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: false,
-                metastore_id: None,
-                metastore_name: None,
-                region: Some(region),
-            },
-            has_create_catalog: true,
-            has_create_external_location: true,
-            has_create_storage_credential: true,
-            can_create_catalog: true,
-            message: format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(&credentials)),
-        });
+        return Ok(no_metastore(region, &credentials));
     }
 
     // For GCP, generate an ID token and call the Databricks Account Metastores API
@@ -814,69 +829,7 @@ pub async fn check_uc_permissions(
             .as_ref()
             .filter(|s| !s.is_empty())
         {
-            if let Ok(sa_creds) = serde_json::from_str::<serde_json::Value>(sa_json) {
-                let client_email = sa_creds["client_email"].as_str();
-                let private_key = sa_creds["private_key"].as_str();
-
-                if let (Some(email), Some(key)) = (client_email, private_key) {
-                    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-
-                    let now = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-
-                    #[derive(Serialize)]
-                    struct IdTokenClaims {
-                        iss: String,
-                        sub: String,
-                        aud: String,
-                        target_audience: String,
-                        iat: u64,
-                        exp: u64,
-                    }
-
-                    let claims = IdTokenClaims {
-                        iss: email.to_string(),
-                        sub: email.to_string(),
-                        aud: "https://oauth2.googleapis.com/token".to_string(),
-                        target_audience: "https://accounts.gcp.databricks.com".to_string(),
-                        iat: now,
-                        exp: now + 3600,
-                    };
-
-                    let header = Header::new(Algorithm::RS256);
-
-                    if let Ok(encoding_key) = EncodingKey::from_rsa_pem(key.as_bytes()) {
-                        if let Ok(assertion) = encode(&header, &claims, &encoding_key) {
-                            let client = http_client()?;
-                            let token_response = client
-                                .post("https://oauth2.googleapis.com/token")
-                                .form(&[
-                                    (
-                                        "grant_type",
-                                        "urn:ietf:params:oauth:grant-type:jwt-bearer",
-                                    ),
-                                    ("assertion", &assertion),
-                                ])
-                                .send()
-                                .await;
-
-                            if let Ok(resp) = token_response {
-                                if resp.status().is_success() {
-                                    if let Ok(token_json) =
-                                        resp.json::<serde_json::Value>().await
-                                    {
-                                        id_token = token_json["id_token"]
-                                            .as_str()
-                                            .map(|s| s.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            id_token = databricks_api::gcp_id_token_from_key(sa_json).await.ok();
         }
 
         // Method 2: Use IAM Credentials API with OAuth token
@@ -903,55 +856,19 @@ pub async fn check_uc_permissions(
                     .as_ref()
                     .filter(|s| !s.is_empty())
                 {
-                    let client = http_client()?;
-
-                    let generate_token_url = format!(
-                        "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/{}:generateIdToken",
-                        sa_email
-                    );
-
                     debug_log!(
                         "[check_uc_permissions] Calling IAM generateIdToken for SA: {}",
                         mask_sensitive_id(sa_email)
                     );
 
-                    let token_response = client
-                        .post(&generate_token_url)
-                        .bearer_auth(oauth_token)
-                        .json(&serde_json::json!({
-                            "audience": "https://accounts.gcp.databricks.com",
-                            "includeEmail": true
-                        }))
-                        .send()
-                        .await;
-
-                    if let Ok(resp) = token_response {
-                        let status = resp.status();
-                        debug_log!(
-                            "[check_uc_permissions] IAM API response status: {}",
-                            status
-                        );
-                        if status.is_success() {
-                            if let Ok(token_json) = resp.json::<serde_json::Value>().await {
-                                id_token =
-                                    token_json["token"].as_str().map(|s| s.to_string());
-                                debug_log!(
-                                    "[check_uc_permissions] Got ID token via IAM API: {}",
-                                    id_token.is_some()
-                                );
-                            }
-                        } else {
-                            let _error_body = resp.text().await.unwrap_or_default();
-                            debug_log!(
-                                "[check_uc_permissions] IAM API error: {}",
-                                _error_body
-                            );
+                    match databricks_api::gcp_id_token_via_iam(oauth_token, sa_email).await {
+                        Ok(token) => {
+                            debug_log!("[check_uc_permissions] Got ID token via IAM API");
+                            id_token = Some(token);
+                        }
+                        Err(_e) => {
+                            debug_log!("[check_uc_permissions] IAM API error: {}", _e);
                         }
-                    } else if let Err(_e) = token_response {
-                        debug_log!(
-                            "[check_uc_permissions] IAM API request failed: {}",
-                            _e
-                        );
                     }
                 }
             }
@@ -966,21 +883,22 @@ pub async fn check_uc_permissions(
                 .filter(|s| !s.is_empty())
             {
                 if let Some(gcloud_cli) = dependencies::find_gcloud_cli_path() {
-                    let mut id_token_cmd = super::silent_cmd(&gcloud_cli);
+                    let mut id_token_cmd = CliCommand::with_path(CliTool::Gcloud, &gcloud_cli);
                     id_token_cmd.args([
                         "auth",
                         "print-identity-token",
                         "--impersonate-service-account",
                         sa_email,
                         "--audiences",
-                        "https://accounts.gcp.databricks.com",
+                        databricks_api::GCP_ACCOUNTS_AUDIENCE,
                         "--include-email",
                     ]);
+                    id_token_cmd.cancel_token(&cancel);
 
-                    if let Ok(output) = id_token_cmd.output() {
-                        if output.status.success() {
+                    if let Ok(output) = id_token_cmd.run_async().await {
+                        if output.success {
                             let token =
-                                String::from_utf8_lossy(&output.stdout).trim().to_string();
+                                output.stdout.trim().to_string();
                             if !token.is_empty() {
                                 id_token = Some(token);
                                 debug_log!(
@@ -988,7 +906,7 @@ pub async fn check_uc_permissions(
                                 );
                             }
                         } else {
-                            let _stderr = String::from_utf8_lossy(&output.stderr);
+                            let _stderr = &output.stderr;
                             debug_log!(
                                 "[check_uc_permissions] gcloud CLI failed: {}",
                                 _stderr
@@ -1006,92 +924,30 @@ pub async fn check_uc_permissions(
 
         // If we got an ID token, call the Databricks Metastores API
         if let Some(token) = id_token {
-            let client = http_client()?;
-            let metastores_url = format!(
-                "https://accounts.gcp.databricks.com/api/2.0/accounts/{}/metastores",
-                account_id
-            );
-
+            let client = AccountClient::new("gcp", account_id, AccountAuth::GcpToken(token))?;
             debug_log!(
                 "[check_uc_permissions] Calling Databricks Metastores API for account: {}",
                 mask_sensitive_id(account_id)
             );
 
-            let metastores_response = client.get(&metastores_url).bearer_auth(&token).send().await;
-
-            if let Ok(resp) = metastores_response {
-                let status = resp.status();
-                debug_log!("[check_uc_permissions] Databricks API status: {}", status);
-
-                if status.is_success() {
-                    if let Ok(metastores_json) = resp.json::<serde_json::Value>().await {
-                        let metastores = metastores_json["metastores"].as_array();
-                        debug_log!(
-                            "[check_uc_permissions] Metastores API success: found {} metastore(s)",
-                            metastores.map(|arr| arr.len()).unwrap_or(0)
-                        );
-                        
-                        debug_log!(
-                            "[check_uc_permissions] Looking for region: {} (normalized: {})",
-                            region,
-                            normalize_region(&region)
-                        );
-
-                        if let Some(metastore) = find_metastore_for_region(metastores, &region) {
-                            let metastore_id =
-                                metastore["metastore_id"].as_str().unwrap_or("");
-                            let metastore_name = metastore["name"].as_str().unwrap_or("");
-                            let metastore_owner = metastore["owner"].as_str().unwrap_or("");
-
-                            let message = get_metastore_owner_info(metastore_owner, &credentials);
-
-                            return Ok(UCPermissionCheck {
-                                metastore: MetastoreInfo {
-                                    exists: true,
-                                    metastore_id: Some(metastore_id.to_string()),
-                                    metastore_name: Some(metastore_name.to_string()),
-                                    region: Some(region),
-                                },
-                                has_create_catalog: false,
-                                has_create_external_location: false,
-                                has_create_storage_credential: false,
-                                can_create_catalog: false,
-                                message,
-                            });
-                        } else {
-                            return Ok(UCPermissionCheck {
-                                metastore: MetastoreInfo {
-                                    exists: false,
-                                    metastore_id: None,
-                                    metastore_name: None,
-                                    region: Some(region),
-                                },
-                                has_create_catalog: true,
-                                has_create_external_location: true,
-                                has_create_storage_credential: true,
-                                can_create_catalog: true,
-                                message: format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(&credentials)),
-                            });
-                        }
-                    }
+            match list_metastores(&client, cloud, &credentials, refresh).await {
+                Ok(metastores) => {
+                    debug_log!(
+                        "[check_uc_permissions] Metastores API success: found {} metastore(s), looking for region: {} (normalized: {})",
+                        metastores.len(),
+                        region,
+                        canonical_region(cloud, &region)
+                    );
+                    return Ok(metastore_check(&metastores, cloud, region, &credentials));
+                }
+                Err(_e) => {
+                    debug_log!("[check_uc_permissions] Databricks API error: {}", _e);
                 }
             }
         }
 
         // Fallback when GCP ID token could not be obtained — allow deployment to proceed
-        return Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: false,
-                metastore_id: None,
-                metastore_name: None,
-                region: Some(region),
-            },
-            has_create_catalog: true,
-            has_create_external_location: true,
-            has_create_storage_credential: true,
-            can_create_catalog: true,
-            message: MSG_METASTORE_UNAVAILABLE.to_string(),
-        });
+        return Ok(metastore_unknown(region, MSG_METASTORE_UNAVAILABLE));
     }
 
     // Service principal credentials path
@@ -1108,224 +964,176 @@ pub async fn check_uc_permissions(
         .filter(|s| !s.is_empty())
         .ok_or("Client Secret is required for permission check")?;
 
-    let accounts_host = databricks_accounts_host(cloud);
-
-    let token_url = format!(
-        "https://{}/oidc/accounts/{}/v1/token",
-        accounts_host, account_id
-    );
-
-    debug_log!("[check_uc_permissions] requesting OAuth token from {}", token_url);
-    let client = http_client()?;
-
-    let token_response = client
-        .post(&token_url)
-        .form(&[
-            ("grant_type", "client_credentials"),
-            ("scope", "all-apis"),
-        ])
-        .basic_auth(client_id, Some(client_secret))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get OAuth token: {}", e))?;
+    let client = AccountClient::new(
+        cloud,
+        account_id,
+        AccountAuth::ServicePrincipal {
+            client_id: client_id.clone(),
+            client_secret: client_secret.clone(),
+        },
+    )?;
+
+    debug_log!("[check_uc_permissions] requesting OAuth token");
+    client.access_token().await.map_err(|e| match e {
+        ApiError::Network(e) => format!("Failed to get OAuth token: {}", e),
+        ApiError::UnexpectedHtml => "Received unexpected HTML response from Databricks token endpoint. Please verify your Databricks Account ID and credentials.".to_string(),
+        ApiError::Parse(e) => format!("Failed to parse token: {}", e),
+        _ => "Failed to authenticate with Databricks".to_string(),
+    })?;
+    debug_log!("[check_uc_permissions] OAuth token obtained successfully");
 
-    if !token_response.status().is_success() {
-        return Err("Failed to authenticate with Databricks".to_string());
-    }
+    let metastores = match list_metastores(&client, cloud, &credentials, refresh).await {
+        Ok(metastores) => metastores,
+        Err(ApiError::Network(e)) => return Err(format!("Failed to list metastores: {}", e)),
+        // e.g. a login page returned instead of JSON
+        Err(ApiError::UnexpectedHtml) => {
+            return Err(
+                "Received unexpected HTML response from Databricks API. This may indicate an authentication issue. Please verify your Databricks Account ID and credentials.".to_string()
+            );
+        }
+        Err(ApiError::Parse(e)) => return Err(format!("Failed to parse metastores: {}", e)),
+        Err(_e) => {
+            debug_log!("[check_uc_permissions] metastores request failed: {}", _e);
+            return Ok(no_metastore(region, &credentials));
+        }
+    };
 
-    // Detect HTML responses on token endpoint
-    let token_content_type = token_response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    if token_content_type.contains("text/html") {
-        return Err(
-            "Received unexpected HTML response from Databricks token endpoint. Please verify your Databricks Account ID and credentials.".to_string()
-        );
-    }
+    let Some(metastore) = find_metastore_for_region(&metastores, cloud, &region) else {
+        return Ok(no_metastore(region, &credentials));
+    };
+    let owner_info = get_metastore_owner_info(&metastore.owner, &credentials);
 
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse token: {}", e))?;
+    // Metastore owner has implicit full privileges
+    let (has_create_catalog, has_create_external_location, has_create_storage_credential) =
+        if metastore.owner == *client_id {
+            (true, true, true)
+        } else {
+            debug_log!("[check_uc_permissions] fetching permissions for {}", metastore.metastore_id);
+            client
+                .metastore_permissions(&metastore.metastore_id)
+                .await
+                .map(|assignments| granted_privileges(&assignments, client_id))
+                .unwrap_or((false, false, false))
+        };
 
-    let access_token = token_json["access_token"]
-        .as_str()
-        .ok_or("No access token in response")?;
-    debug_log!("[check_uc_permissions] OAuth token obtained successfully");
+    let can_create =
+        has_create_catalog && has_create_external_location && has_create_storage_credential;
+    let permissions_msg = if can_create {
+        "You have the required permissions to create catalogs.".to_string()
+    } else {
+        let mut missing = Vec::new();
+        if !has_create_catalog {
+            missing.push("CREATE_CATALOG");
+        }
+        if !has_create_storage_credential {
+            missing.push("CREATE_STORAGE_CREDENTIAL");
+        }
+        if !has_create_external_location {
+            missing.push("CREATE_EXTERNAL_LOCATION");
+        }
+        format!(
+            "Missing permissions: {}. Contact your Metastore Admin.",
+            missing.join(", ")
+        )
+    };
+    let message = format!("{} {}", owner_info, permissions_msg);
+
+    Ok(UCPermissionCheck {
+        metastore: MetastoreInfo {
+            exists: true,
+            metastore_id: Some(metastore.metastore_id.clone()),
+            metastore_name: Some(metastore.name.clone()),
+            region: Some(region),
+        },
+        has_create_catalog,
+        has_create_external_location,
+        has_create_storage_credential,
+        can_create_catalog: can_create,
+        message,
+        report: uc_report(
+            metastore,
+            Some([has_create_catalog, has_create_external_location, has_create_storage_credential]),
+            client_id,
+        ),
+    })
+}
 
-    // List metastores (account-level API requires /accounts/{account_id} in path)
-    let metastores_url = format!(
-        "https://{}/api/2.0/accounts/{}/metastores",
-        accounts_host, account_id
-    );
+/// Outcome of [`grant_metastore_permissions`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetastoreGrant {
+    pub metastore_id: String,
+    pub principal: String,
+    /// Privileges granted by this call.
+    pub granted: Vec<String>,
+    /// Privileges the principal already held.
+    pub already_granted: Vec<String>,
+}
 
-    debug_log!("[check_uc_permissions] listing metastores from {}", metastores_url);
-    let metastores_response = client
-        .get(&metastores_url)
-        .bearer_auth(access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to list metastores: {}", e))?;
-    debug_log!("[check_uc_permissions] metastores response status={}", metastores_response.status());
-
-    if !metastores_response.status().is_success() {
-        return Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: false,
-                metastore_id: None,
-                metastore_name: None,
-                region: Some(region.clone()),
-            },
-            has_create_catalog: true,
-            has_create_external_location: true,
-            has_create_storage_credential: true,
-            can_create_catalog: true,
-            message: format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(&credentials)),
-        });
+/// Grant whichever catalog-creation privileges `principal` lacks on the metastore.
+async fn grant_missing_privileges(
+    client: &AccountClient,
+    metastore_id: &str,
+    principal: &str,
+) -> Result<MetastoreGrant, ApiError> {
+    let held = |assignments: &[PrivilegeAssignment]| {
+        let (catalog, external_location, storage_credential) = granted_privileges(assignments, principal);
+        [catalog, external_location, storage_credential]
+    };
+    let before = held(&client.metastore_permissions(metastore_id).await?);
+    let (already, missing): (Vec<_>, Vec<_>) = UC_CATALOG_PRIVILEGES.iter().zip(before).partition(|(_, has)| *has);
+    let missing: Vec<&str> = missing.into_iter().map(|(privilege, _)| *privilege).collect();
+
+    let mut grant = MetastoreGrant {
+        metastore_id: metastore_id.to_string(),
+        principal: principal.to_string(),
+        granted: Vec::new(),
+        already_granted: already.into_iter().map(|(privilege, _)| privilege.to_string()).collect(),
+    };
+    if missing.is_empty() {
+        return Ok(grant);
     }
+    let after = held(&client.grant_metastore_privileges(metastore_id, principal, &missing).await?);
+    grant.granted = UC_CATALOG_PRIVILEGES
+        .iter()
+        .zip(after)
+        .filter(|(privilege, has)| *has && missing.contains(*privilege))
+        .map(|(privilege, _)| privilege.to_string())
+        .collect();
+    Ok(grant)
+}
 
-    // Detect HTML responses (e.g., login page returned instead of JSON)
-    let content_type = metastores_response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    if content_type.contains("text/html") {
-        return Err(
-            "Received unexpected HTML response from Databricks API. This may indicate an authentication issue. Please verify your Databricks Account ID and credentials.".to_string()
-        );
-    }
+/// Grant the catalog-creation privileges [`check_uc_permissions`] reported
+/// missing. `credentials` must belong to a metastore admin; `principal` is
+/// who deploys, defaulting to the credentials' service principal.
+#[tauri::command]
+pub async fn grant_metastore_permissions(
+    credentials: CloudCredentials,
+    metastore_id: String,
+    principal: Option<String>,
+) -> Result<MetastoreGrant, String> {
+    let principal = principal
+        .or_else(|| credentials.databricks_client_id.clone())
+        .filter(|s| !s.is_empty())
+        .ok_or("The principal to grant privileges to is required")?;
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks account ID is required")?;
 
-    let metastores_json: serde_json::Value = metastores_response
-        .json()
+    let auth = account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    grant_missing_privileges(&client, &metastore_id, &principal)
         .await
-        .map_err(|e| format!("Failed to parse metastores: {}", e))?;
-
-    let metastores = metastores_json["metastores"].as_array();
-
-    if let Some(metastore) = find_metastore_for_region(metastores, &region) {
-        let metastore_id = metastore["metastore_id"].as_str().unwrap_or("");
-        let metastore_name = metastore["name"].as_str().unwrap_or("");
-        let metastore_owner = metastore["owner"].as_str().unwrap_or("");
-
-        let owner_info = get_metastore_owner_info(metastore_owner, &credentials);
-
-        // Metastore owner has implicit full privileges
-        let is_owner = metastore_owner == client_id.as_str();
-
-        let permissions_url = format!(
-            "https://{}/api/2.0/accounts/{}/metastores/{}/permissions",
-            accounts_host, account_id, metastore_id
-        );
-
-        debug_log!("[check_uc_permissions] fetching permissions from {}", permissions_url);
-        let permissions_response = client
-            .get(&permissions_url)
-            .bearer_auth(access_token)
-            .send()
-            .await;
-        debug_log!("[check_uc_permissions] permissions response received");
-
-        let (has_create_catalog, has_create_external_location, has_create_storage_credential) =
-            if is_owner {
-                (true, true, true)
-            } else if let Ok(resp) = permissions_response {
-                if resp.status().is_success() {
-                    if let Ok(perm_json) = resp.json::<serde_json::Value>().await {
-                        let assignments = perm_json["privilege_assignments"].as_array();
-                        let mut create_catalog = false;
-                        let mut create_ext_loc = false;
-                        let mut create_storage_cred = false;
-
-                        if let Some(arr) = assignments {
-                            for assignment in arr {
-                                let principal = assignment["principal"].as_str().unwrap_or("");
-                                if principal != client_id.as_str() {
-                                    continue;
-                                }
-                                if let Some(privileges) = assignment["privileges"].as_array() {
-                                    for priv_val in privileges {
-                                        let priv_str = priv_val.as_str().unwrap_or("");
-                                        match priv_str {
-                                            "CREATE_CATALOG" => create_catalog = true,
-                                            "CREATE_EXTERNAL_LOCATION" => create_ext_loc = true,
-                                            "CREATE_STORAGE_CREDENTIAL" => {
-                                                create_storage_cred = true
-                                            }
-                                            "ALL_PRIVILEGES" => {
-                                                create_catalog = true;
-                                                create_ext_loc = true;
-                                                create_storage_cred = true;
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        (create_catalog, create_ext_loc, create_storage_cred)
-                    } else {
-                        (false, false, false)
-                    }
-                } else {
-                    (false, false, false)
-                }
-            } else {
-                (false, false, false)
-            };
-
-        let can_create =
-            has_create_catalog && has_create_external_location && has_create_storage_credential;
-        let permissions_msg = if can_create {
-            "You have the required permissions to create catalogs.".to_string()
-        } else {
-            let mut missing = Vec::new();
-            if !has_create_catalog {
-                missing.push("CREATE_CATALOG");
+        .map_err(|e| match e {
+            ApiError::Forbidden(_) => {
+                "Only a metastore admin can grant privileges on this metastore. Ask the metastore owner to grant them."
+                    .to_string()
             }
-            if !has_create_storage_credential {
-                missing.push("CREATE_STORAGE_CREDENTIAL");
-            }
-            if !has_create_external_location {
-                missing.push("CREATE_EXTERNAL_LOCATION");
-            }
-            format!(
-                "Missing permissions: {}. Contact your Metastore Admin.",
-                missing.join(", ")
-            )
-        };
-        let message = format!("{} {}", owner_info, permissions_msg);
-
-        Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: true,
-                metastore_id: Some(metastore_id.to_string()),
-                metastore_name: Some(metastore_name.to_string()),
-                region: Some(region),
-            },
-            has_create_catalog,
-            has_create_external_location,
-            has_create_storage_credential,
-            can_create_catalog: can_create,
-            message,
-        })
-    } else {
-        Ok(UCPermissionCheck {
-            metastore: MetastoreInfo {
-                exists: false,
-                metastore_id: None,
-                metastore_name: None,
-                region: Some(region),
-            },
-            has_create_catalog: true,
-            has_create_external_location: true,
-            has_create_storage_credential: true,
-            can_create_catalog: true,
-            message: format!("{} {} will be Metastore Admin.", MSG_NO_METASTORE_PREFIX, get_current_identity(&credentials)),
+            ApiError::NotFound(_) => format!("Metastore '{}' not found", metastore_id),
+            e => format!("Failed to grant metastore privileges: {}", e),
         })
-    }
 }
 
 /// Validate Azure identity (account admin) for Databricks access.
@@ -1358,32 +1166,26 @@ pub async fn validate_azure_databricks_identity(
     .map_err(|e| format!("Token task panicked: {}", e))??;
     
     // Use the Azure AD token directly for SCIM API (no token exchange needed)
-    let client = http_client()?;
-    let users_url = format!(
-        "https://accounts.azuredatabricks.net/api/2.0/accounts/{}/scim/v2/Users?count=1",
-        account_id
-    );
-    
-    let users_response = client
-        .get(&users_url)
-        .bearer_auth(&azure_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to verify account access: {}", e))?;
-    
-    if !users_response.status().is_success() {
-        let status = users_response.status();
-        if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::UNAUTHORIZED {
+    let client = AccountClient::new("azure", &account_id, AccountAuth::AzureAd(azure_token))?;
+    match client.list_users(1).await {
+        Ok(_) => {}
+        Err(e) if e.is_auth_failure() => {
             return Err(format!(
                 "Your Azure account ({}) does not have account admin privileges.\n\n\
                 Please grant the 'Account admin' role in Databricks Account Console → User Management.",
                 azure_account_email
             ));
         }
-        return Err(format!(
-            "Cannot verify account access ({}). Check your Databricks Account ID.",
-            status
-        ));
+        Err(ApiError::Network(e)) => return Err(format!("Failed to verify account access: {}", e)),
+        Err(e) => {
+            return Err(match e.status() {
+                Some(status) => format!(
+                    "Cannot verify account access ({}). Check your Databricks Account ID.",
+                    status
+                ),
+                None => e.to_string(),
+            });
+        }
     }
     
     Ok(format!("Azure identity validated - Account Admin access confirmed for: {}", azure_account_email))
@@ -1393,75 +1195,84 @@ pub async fn validate_azure_databricks_identity(
 mod tests {
     use super::*;
 
-    // ── normalize_region ────────────────────────────────────────────────
-
-    #[test]
-    fn normalize_region_lowercase() {
-        assert_eq!(normalize_region("US-East-1"), "useast1");
-    }
+    // ── find_metastore_for_region ───────────────────────────────────────
 
-    #[test]
-    fn normalize_region_removes_spaces() {
-        assert_eq!(normalize_region("East US 2"), "eastus2");
+    fn metastore(id: &str, region: &str) -> Metastore {
+        Metastore {
+            metastore_id: id.to_string(),
+            region: region.to_string(),
+            ..Default::default()
+        }
     }
 
     #[test]
-    fn normalize_region_removes_hyphens() {
-        assert_eq!(normalize_region("us-west-2"), "uswest2");
+    fn find_metastore_matching_region() {
+        let metastores = vec![metastore("ms-1", "us-east-1"), metastore("ms-2", "eu-west-1")];
+        let result = find_metastore_for_region(&metastores, "aws", "us-east-1");
+        assert!(result.is_some());
+        assert_eq!(result.unwrap().metastore_id, "ms-1");
     }
 
     #[test]
-    fn normalize_region_already_normalized() {
-        assert_eq!(normalize_region("useast1"), "useast1");
+    fn find_metastore_case_insensitive() {
+        let metastores = vec![metastore("ms-1", "US-East-1")];
+        let result = find_metastore_for_region(&metastores, "aws", "us-east-1");
+        assert!(result.is_some());
     }
 
     #[test]
-    fn normalize_region_empty() {
-        assert_eq!(normalize_region(""), "");
+    fn find_metastore_no_match() {
+        let metastores = vec![metastore("ms-1", "eu-west-1")];
+        let result = find_metastore_for_region(&metastores, "aws", "us-east-1");
+        assert!(result.is_none());
     }
 
-    // ── find_metastore_for_region ───────────────────────────────────────
-
     #[test]
-    fn find_metastore_matching_region() {
-        let metastores = vec![
-            serde_json::json!({"metastore_id": "ms-1", "region": "us-east-1", "name": "east"}),
-            serde_json::json!({"metastore_id": "ms-2", "region": "eu-west-1", "name": "west"}),
-        ];
-        let result = find_metastore_for_region(Some(&metastores), "us-east-1");
-        assert!(result.is_some());
-        assert_eq!(result.unwrap()["metastore_id"], "ms-1");
+    fn find_metastore_missing_region() {
+        let metastores = vec![metastore("ms-1", "")];
+        let result = find_metastore_for_region(&metastores, "aws", "us-east-1");
+        assert!(result.is_none());
     }
 
     #[test]
-    fn find_metastore_case_insensitive() {
-        let metastores = vec![
-            serde_json::json!({"metastore_id": "ms-1", "region": "US-East-1", "name": "east"}),
-        ];
-        let result = find_metastore_for_region(Some(&metastores), "us-east-1");
-        assert!(result.is_some());
+    fn find_metastore_matches_azure_display_name_but_not_other_clouds() {
+        let metastores = vec![metastore("ms-1", "East US 2")];
+        assert!(find_metastore_for_region(&metastores, "azure", "eastus2").is_some());
+        let metastores = vec![metastore("ms-2", "us-east1")];
+        assert!(find_metastore_for_region(&metastores, "aws", "us-east-1").is_none());
     }
 
     #[test]
-    fn find_metastore_no_match() {
-        let metastores = vec![
-            serde_json::json!({"metastore_id": "ms-1", "region": "eu-west-1", "name": "west"}),
-        ];
-        let result = find_metastore_for_region(Some(&metastores), "us-east-1");
+    fn find_metastore_empty_list() {
+        let result = find_metastore_for_region(&[], "aws", "us-east-1");
         assert!(result.is_none());
     }
 
+    // ── granted_privileges ──────────────────────────────────────────────
+
     #[test]
-    fn find_metastore_none_list() {
-        let result = find_metastore_for_region(None, "us-east-1");
-        assert!(result.is_none());
+    fn granted_privileges_only_counts_principal() {
+        let assignments = vec![
+            PrivilegeAssignment {
+                principal: "sp".to_string(),
+                privileges: vec!["CREATE_CATALOG".to_string(), "CREATE_EXTERNAL_LOCATION".to_string()],
+            },
+            PrivilegeAssignment {
+                principal: "someone-else".to_string(),
+                privileges: vec!["CREATE_STORAGE_CREDENTIAL".to_string()],
+            },
+        ];
+        assert_eq!(granted_privileges(&assignments, "sp"), (true, true, false));
     }
 
     #[test]
-    fn find_metastore_empty_list() {
-        let metastores = vec![];
-        let result = find_metastore_for_region(Some(&metastores), "us-east-1");
-        assert!(result.is_none());
+    fn granted_privileges_all_privileges() {
+        let assignments = vec![PrivilegeAssignment {
+            principal: "sp".to_string(),
+            privileges: vec!["ALL_PRIVILEGES".to_string()],
+        }];
+        assert_eq!(granted_privileges(&assignments, "sp"), (true, true, true));
+        assert_eq!(granted_privileges(&[], "sp"), (false, false, false));
     }
 
     // ── get_metastore_owner_info ────────────────────────────────────────
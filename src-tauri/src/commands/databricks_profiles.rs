@@ -0,0 +1,581 @@
+//! Health checks for Databricks CLI profiles in `~/.databrickscfg`.
+//!
+//! The credentials step only lists service principal profiles for the selected
+//! cloud, which leaves users guessing why a profile is missing or fails later.
+//! [`validate_all_databricks_profiles`] checks every profile and says which
+//! one is usable and what is wrong with the rest.
+//!
+//! It also inspects and edits the CLI's OAuth token cache
+//! (`~/.databricks/token-cache.json`) so a stale login can be dropped for
+//! one host/account without touching the others.
+
+use super::{databricks_accounts_host, debug_log};
+use crate::databricks_api::{AccountAuth, AccountClient, ApiError};
+use crate::dependencies;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub(crate) const STATUS_VALID: &str = "valid";
+pub(crate) const STATUS_EXPIRED: &str = "expired";
+pub(crate) const STATUS_WRONG_CLOUD: &str = "wrong_cloud";
+pub(crate) const STATUS_MISSING_SCOPES: &str = "missing_scopes";
+pub(crate) const STATUS_WORKSPACE_LEVEL: &str = "workspace_level";
+pub(crate) const STATUS_INCOMPLETE: &str = "incomplete";
+pub(crate) const STATUS_UNSUPPORTED: &str = "unsupported";
+pub(crate) const STATUS_UNREACHABLE: &str = "unreachable";
+pub(crate) const STATUS_ERROR: &str = "error";
+
+/// Health of one profile.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProfileHealth {
+    pub name: String,
+    pub host: String,
+    /// Cloud inferred from the host; `None` for custom hosts.
+    pub cloud: Option<String>,
+    pub account_id: Option<String>,
+    /// "service_principal", "oauth", or "pat".
+    pub auth_kind: String,
+    /// One of the `STATUS_*` values.
+    pub status: String,
+    /// Expiry of the cached OAuth access token, as written by the CLI.
+    pub token_expiry: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileHealthReport {
+    pub profiles: Vec<ProfileHealth>,
+    /// First valid profile, preferring service principals (the only kind
+    /// that works for workspaces created during the deployment).
+    pub recommended: Option<String>,
+}
+
+fn auth_kind(data: &HashMap<String, String>) -> &'static str {
+    if data.contains_key("client_id") && data.contains_key("client_secret") {
+        "service_principal"
+    } else if data.contains_key("token") {
+        "pat"
+    } else {
+        "oauth"
+    }
+}
+
+/// Outcome of the offline checks.
+enum Precheck {
+    /// The profile can't be used for account-level calls.
+    Done(ProfileHealth),
+    /// Account-level profile; status comes from a live check.
+    Live(ProfileHealth),
+}
+
+fn precheck(name: &str, data: &HashMap<String, String>, expected_cloud: Option<&str>) -> Precheck {
+    let host = data.get("host").cloned().unwrap_or_default();
+    let cloud = dependencies::cloud_for_host(&host);
+    let account_id = data.get("account_id").filter(|s| !s.is_empty()).cloned();
+    let kind = auth_kind(data);
+
+    let mut entry = ProfileHealth {
+        name: name.to_string(),
+        host: host.clone(),
+        cloud: cloud.map(str::to_string),
+        account_id: account_id.clone(),
+        auth_kind: kind.to_string(),
+        status: STATUS_VALID.to_string(),
+        token_expiry: None,
+        message: String::new(),
+    };
+    let mut done = |status: &str, message: String| {
+        entry.status = status.to_string();
+        entry.message = message;
+        Precheck::Done(entry.clone())
+    };
+
+    if host.is_empty() {
+        return done(STATUS_INCOMPLETE, "Profile has no host.".to_string());
+    }
+    let Some(cloud) = cloud else {
+        return done(STATUS_UNSUPPORTED, format!("Host '{}' is not a Databricks cloud host.", host));
+    };
+    if let Some(expected) = expected_cloud.filter(|c| *c != cloud) {
+        return done(
+            STATUS_WRONG_CLOUD,
+            format!("Profile targets {}, not {}.", cloud.to_uppercase(), expected.to_uppercase()),
+        );
+    }
+    if !host.contains(databricks_accounts_host(cloud)) {
+        return done(
+            STATUS_WORKSPACE_LEVEL,
+            format!(
+                "Workspace-level profile. Deployments need an account-level profile for https://{}.",
+                databricks_accounts_host(cloud)
+            ),
+        );
+    }
+    if account_id.is_none() {
+        return done(STATUS_INCOMPLETE, "Profile has no account_id.".to_string());
+    }
+    if kind == "pat" {
+        return done(
+            STATUS_UNSUPPORTED,
+            "Personal access tokens only work with workspaces; use OAuth or a service principal.".to_string(),
+        );
+    }
+    Precheck::Live(entry)
+}
+
+/// (status, message) for a live account-API check.
+fn classify_access(result: Result<(), ApiError>, profile: &ProfileHealth) -> (&'static str, String) {
+    let relogin = format!(
+        "databricks auth login --host {} --account-id {} --profile {}",
+        profile.host,
+        profile.account_id.as_deref().unwrap_or(""),
+        profile.name
+    );
+    let is_sp = profile.auth_kind == "service_principal";
+    match result {
+        Ok(()) => (STATUS_VALID, "Account admin access confirmed.".to_string()),
+        Err(ApiError::Unauthorized(_)) if is_sp => (
+            STATUS_EXPIRED,
+            "Client secret was rejected; it may have expired or been revoked.".to_string(),
+        ),
+        Err(ApiError::Unauthorized(_) | ApiError::Auth(_)) => {
+            (STATUS_EXPIRED, format!("Login expired. Re-authenticate with:\n{}", relogin))
+        }
+        Err(ApiError::Forbidden(_)) => (
+            STATUS_MISSING_SCOPES,
+            "Authenticated, but the identity is not an account admin or the token lacks the all-apis scope.".to_string(),
+        ),
+        Err(ApiError::Network(e)) => (STATUS_UNREACHABLE, format!("Accounts host unreachable: {}", e)),
+        Err(e) => (STATUS_ERROR, e.to_string()),
+    }
+}
+
+/// Whether `auth` can list account users (requires account admin).
+pub(crate) async fn check_account_access(cloud: &str, account_id: &str, auth: AccountAuth) -> Result<(), ApiError> {
+    let client = AccountClient::new(cloud, account_id, auth).map_err(ApiError::Auth)?;
+    client.list_users(1).await.map(|_| ())
+}
+
+/// Expiry of the CLI's cached OAuth token for a profile. The cache is keyed by
+/// profile name (newer CLIs) or by `{host}/oidc/accounts/{account_id}`.
+fn cached_token_expiry(cache: &serde_json::Value, profile: &ProfileHealth) -> Option<String> {
+    let tokens = cache["tokens"].as_object()?;
+    let account_key = format!(
+        "{}/oidc/accounts/{}",
+        profile.host.trim_end_matches('/'),
+        profile.account_id.as_deref().unwrap_or("")
+    );
+    tokens
+        .get(&profile.name)
+        .or_else(|| tokens.get(&account_key))?["expiry"]
+        .as_str()
+        .map(str::to_string)
+}
+
+fn read_token_cache() -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(token_cache_path()?).ok()?).ok()
+}
+
+fn recommended_profile(profiles: &[ProfileHealth]) -> Option<String> {
+    let valid = |p: &&ProfileHealth| p.status == STATUS_VALID;
+    profiles
+        .iter()
+        .filter(valid)
+        .find(|p| p.auth_kind == "service_principal")
+        .or_else(|| profiles.iter().find(valid))
+        .map(|p| p.name.clone())
+}
+
+/// Check every profile in `~/.databrickscfg`: cloud, account-level host,
+/// token validity, and account admin access. `cloud` flags profiles for
+/// other clouds as `wrong_cloud` without calling them.
+#[tauri::command]
+pub async fn validate_all_databricks_profiles(cloud: Option<String>) -> Result<ProfileHealthReport, String> {
+    let token_cache = read_token_cache();
+    let mut profiles = Vec::new();
+
+    for (name, data) in dependencies::read_databricks_config_sections() {
+        let mut entry = match precheck(&name, &data, cloud.as_deref()) {
+            Precheck::Done(done) => {
+                profiles.push(done);
+                continue;
+            }
+            Precheck::Live(pending) => pending,
+        };
+
+        let auth = match (data.get("client_id"), data.get("client_secret")) {
+            (Some(client_id), Some(client_secret)) => AccountAuth::ServicePrincipal {
+                client_id: client_id.clone(),
+                client_secret: client_secret.clone(),
+            },
+            _ => {
+                entry.token_expiry = token_cache.as_ref().and_then(|c| cached_token_expiry(c, &entry));
+                AccountAuth::CliProfile(name.clone())
+            }
+        };
+        let profile_cloud = entry.cloud.clone().unwrap_or_default();
+        let account_id = entry.account_id.clone().unwrap_or_default();
+        let result = check_account_access(&profile_cloud, &account_id, auth).await;
+        let (status, message) = classify_access(result, &entry);
+        debug_log!("[validate_all_databricks_profiles] {}: {}", name, status);
+        entry.status = status.to_string();
+        entry.message = message;
+        profiles.push(entry);
+    }
+
+    Ok(ProfileHealthReport {
+        recommended: recommended_profile(&profiles),
+        profiles,
+    })
+}
+
+// ─── Token cache ────────────────────────────────────────────────────────────
+
+/// A token cache entry. Token values are never returned.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CachedToken {
+    /// Cache key: `{host}/oidc/accounts/{id}`, a workspace host, or a profile name.
+    pub key: String,
+    pub host: Option<String>,
+    pub account_id: Option<String>,
+    /// Profile the entry belongs to, when keyed by profile name.
+    pub profile: Option<String>,
+    pub expiry: Option<String>,
+    pub has_refresh_token: bool,
+}
+
+pub(crate) fn token_cache_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".databricks").join("token-cache.json"))
+}
+
+/// `https://host/` and `host` compare equal.
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+/// Token entries: under `tokens` (current CLI format) or top-level (older).
+fn token_entries(cache: &serde_json::Value) -> Option<&serde_json::Map<String, serde_json::Value>> {
+    match cache.get("tokens") {
+        Some(tokens) => tokens.as_object(),
+        None => cache.as_object(),
+    }
+}
+
+fn token_entries_mut(cache: &mut serde_json::Value) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    if cache.get("tokens").is_some() {
+        cache["tokens"].as_object_mut()
+    } else {
+        cache.as_object_mut()
+    }
+}
+
+/// Resolve a cache key to (host, account_id, profile). Profile-name keys are
+/// looked up in the config sections.
+fn describe_key(key: &str, sections: &[(String, HashMap<String, String>)]) -> (Option<String>, Option<String>, Option<String>) {
+    if key.starts_with("https://") || key.starts_with("http://") {
+        let (host, account) = match key.split_once("/oidc/accounts/") {
+            Some((host, rest)) => (host, Some(rest.trim_end_matches('/').to_string())),
+            None => (key, None),
+        };
+        return (Some(normalize_host(host)), account, None);
+    }
+    match sections.iter().find(|(name, _)| name == key) {
+        Some((name, data)) => (
+            data.get("host").map(|h| normalize_host(h)),
+            data.get("account_id").cloned(),
+            Some(name.clone()),
+        ),
+        None => (None, None, Some(key.to_string())),
+    }
+}
+
+fn describe_cache(cache: &serde_json::Value, sections: &[(String, HashMap<String, String>)]) -> Vec<CachedToken> {
+    let Some(entries) = token_entries(cache) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter(|(_, entry)| entry.is_object())
+        .map(|(key, entry)| {
+            let (host, account_id, profile) = describe_key(key, sections);
+            CachedToken {
+                key: key.clone(),
+                host,
+                account_id,
+                profile,
+                expiry: entry["expiry"].as_str().map(str::to_string),
+                has_refresh_token: entry["refresh_token"].as_str().is_some_and(|t| !t.is_empty()),
+            }
+        })
+        .collect()
+}
+
+/// Remove entries for `host` (and `account_id`, when given), leaving the rest
+/// of the document untouched. Returns the removed keys.
+fn remove_cached_tokens(
+    cache: &mut serde_json::Value,
+    host: &str,
+    account_id: Option<&str>,
+    sections: &[(String, HashMap<String, String>)],
+) -> Vec<String> {
+    let host = normalize_host(host);
+    let matching: Vec<String> = describe_cache(cache, sections)
+        .into_iter()
+        .filter(|t| t.host.as_deref() == Some(host.as_str()))
+        .filter(|t| account_id.is_none() || t.account_id.as_deref() == account_id)
+        .map(|t| t.key)
+        .collect();
+    if let Some(entries) = token_entries_mut(cache) {
+        for key in &matching {
+            entries.remove(key);
+        }
+    }
+    matching
+}
+
+/// Drop cached tokens for `host`/`account_id` from the cache file. The file is
+/// copied to `token-cache.json.bak` before it is rewritten; nothing is written
+/// when no entry matches.
+pub(crate) fn invalidate_cached_tokens(path: &Path, host: &str, account_id: Option<&str>) -> Result<Vec<String>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read token cache: {}", e)),
+    };
+    let mut cache: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse token cache: {}", e))?;
+
+    let removed = remove_cached_tokens(&mut cache, host, account_id, &dependencies::read_databricks_config_sections());
+    if removed.is_empty() {
+        return Ok(removed);
+    }
+
+    fs::copy(path, path.with_extension("json.bak"))
+        .map_err(|e| format!("Failed to back up token cache: {}", e))?;
+    let updated = serde_json::to_string_pretty(&cache)
+        .map_err(|e| format!("Failed to serialize token cache: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, updated).map_err(|e| format!("Failed to write token cache: {}", e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write token cache: {}", e))?;
+    Ok(removed)
+}
+
+/// List the Databricks CLI's cached OAuth tokens (metadata only).
+#[tauri::command]
+pub fn list_databricks_cached_tokens() -> Result<Vec<CachedToken>, String> {
+    let Some(path) = token_cache_path().filter(|p| p.exists()) else {
+        return Ok(Vec::new());
+    };
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read token cache: {}", e))?;
+    let cache: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse token cache: {}", e))?;
+    Ok(describe_cache(&cache, &dependencies::read_databricks_config_sections()))
+}
+
+/// Remove cached tokens for a host, optionally limited to one account.
+/// Returns the removed cache keys.
+#[tauri::command]
+pub fn invalidate_databricks_token(host: String, account: Option<String>) -> Result<Vec<String>, String> {
+    let path = token_cache_path().ok_or("Could not determine home directory")?;
+    invalidate_cached_tokens(&path, &host, account.as_deref().filter(|a| !a.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    const ACCOUNT: &str = "01234567-89ab-cdef-0123-456789abcdef";
+
+    fn status_of(result: Precheck) -> String {
+        match result {
+            Precheck::Done(done) => done.status,
+            Precheck::Live(_) => "live".to_string(),
+        }
+    }
+
+    fn live(result: Precheck) -> ProfileHealth {
+        match result {
+            Precheck::Live(entry) => entry,
+            Precheck::Done(done) => panic!("expected a live check, got {}", done.status),
+        }
+    }
+
+    #[test]
+    fn precheck_flags_unusable_profiles() {
+        assert_eq!(status_of(precheck("a", &data(&[]), None)), STATUS_INCOMPLETE);
+        assert_eq!(
+            status_of(precheck("a", &data(&[("host", "https://dbx.example.com")]), None)),
+            STATUS_UNSUPPORTED
+        );
+        assert_eq!(
+            status_of(precheck("a", &data(&[("host", "https://accounts.gcp.databricks.com")]), Some("aws"))),
+            STATUS_WRONG_CLOUD
+        );
+        assert_eq!(
+            status_of(precheck("a", &data(&[("host", "https://dbc-1.cloud.databricks.com"), ("token", "dapi")]), None)),
+            STATUS_WORKSPACE_LEVEL
+        );
+        assert_eq!(
+            status_of(precheck("a", &data(&[("host", "https://accounts.cloud.databricks.com")]), None)),
+            STATUS_INCOMPLETE
+        );
+        assert_eq!(
+            status_of(precheck(
+                "a",
+                &data(&[("host", "https://accounts.cloud.databricks.com"), ("account_id", ACCOUNT), ("token", "dapi")]),
+                None
+            )),
+            STATUS_UNSUPPORTED
+        );
+    }
+
+    #[test]
+    fn precheck_passes_account_profiles_to_live_check() {
+        let sp = data(&[
+            ("host", "https://accounts.azuredatabricks.net"),
+            ("account_id", ACCOUNT),
+            ("client_id", "cid"),
+            ("client_secret", "secret"),
+        ]);
+        let pending = live(precheck("sp", &sp, Some("azure")));
+        assert_eq!(pending.cloud.as_deref(), Some("azure"));
+        assert_eq!(pending.auth_kind, "service_principal");
+    }
+
+    #[test]
+    fn classify_access_maps_errors() {
+        let mut profile = live(precheck(
+            "p",
+            &data(&[("host", "https://accounts.cloud.databricks.com"), ("account_id", ACCOUNT)]),
+            None,
+        ));
+        assert_eq!(classify_access(Ok(()), &profile).0, STATUS_VALID);
+        let (status, message) = classify_access(Err(ApiError::Auth("no token".to_string())), &profile);
+        assert_eq!(status, STATUS_EXPIRED);
+        assert!(message.contains("databricks auth login"), "{}", message);
+        assert_eq!(classify_access(Err(ApiError::Forbidden(String::new())), &profile).0, STATUS_MISSING_SCOPES);
+        assert_eq!(classify_access(Err(ApiError::Network("dns".to_string())), &profile).0, STATUS_UNREACHABLE);
+
+        profile.auth_kind = "service_principal".to_string();
+        let (status, message) = classify_access(Err(ApiError::Unauthorized(String::new())), &profile);
+        assert_eq!(status, STATUS_EXPIRED);
+        assert!(message.contains("Client secret"), "{}", message);
+    }
+
+    #[test]
+    fn cached_token_expiry_by_profile_or_account() {
+        let profile = live(precheck(
+            "deployer",
+            &data(&[("host", "https://accounts.cloud.databricks.com/"), ("account_id", ACCOUNT)]),
+            None,
+        ));
+        let by_account = serde_json::json!({"version": 1, "tokens": {
+            format!("https://accounts.cloud.databricks.com/oidc/accounts/{}", ACCOUNT): {"expiry": "2026-01-01T00:00:00Z"}
+        }});
+        assert_eq!(cached_token_expiry(&by_account, &profile).as_deref(), Some("2026-01-01T00:00:00Z"));
+        let by_name = serde_json::json!({"tokens": {"deployer": {"expiry": "2027-01-01T00:00:00Z"}}});
+        assert_eq!(cached_token_expiry(&by_name, &profile).as_deref(), Some("2027-01-01T00:00:00Z"));
+        assert_eq!(cached_token_expiry(&serde_json::json!({}), &profile), None);
+    }
+
+    #[test]
+    fn recommended_prefers_service_principals() {
+        let entry = |name: &str, kind: &str, status: &str| ProfileHealth {
+            name: name.to_string(),
+            host: String::new(),
+            cloud: None,
+            account_id: None,
+            auth_kind: kind.to_string(),
+            status: status.to_string(),
+            token_expiry: None,
+            message: String::new(),
+        };
+        let profiles = vec![
+            entry("sso", "oauth", STATUS_VALID),
+            entry("old-sp", "service_principal", STATUS_EXPIRED),
+            entry("sp", "service_principal", STATUS_VALID),
+        ];
+        assert_eq!(recommended_profile(&profiles).as_deref(), Some("sp"));
+        assert_eq!(recommended_profile(&profiles[..2]).as_deref(), Some("sso"));
+        assert_eq!(recommended_profile(&profiles[1..2]), None);
+    }
+
+    // ── token cache ─────────────────────────────────────────────────────
+
+    fn sample_cache() -> serde_json::Value {
+        serde_json::json!({
+            "version": 1,
+            "tokens": {
+                format!("https://accounts.cloud.databricks.com/oidc/accounts/{}", ACCOUNT): {
+                    "access_token": "a", "refresh_token": "r", "expiry": "2026-01-01T00:00:00Z"
+                },
+                "https://accounts.cloud.databricks.com/oidc/accounts/other": {"access_token": "b"},
+                "https://dbc-1.cloud.databricks.com": {"access_token": "c"},
+                "sso": {"access_token": "d"}
+            }
+        })
+    }
+
+    fn sections() -> Vec<(String, HashMap<String, String>)> {
+        vec![(
+            "sso".to_string(),
+            data(&[("host", "https://accounts.cloud.databricks.com/"), ("account_id", ACCOUNT)]),
+        )]
+    }
+
+    #[test]
+    fn describe_cache_hides_tokens_and_resolves_profiles() {
+        let tokens = describe_cache(&sample_cache(), &sections());
+        assert_eq!(tokens.len(), 4);
+        let account = tokens.iter().find(|t| t.account_id.as_deref() == Some(ACCOUNT) && t.profile.is_none()).unwrap();
+        assert_eq!(account.host.as_deref(), Some("accounts.cloud.databricks.com"));
+        assert!(account.has_refresh_token);
+        let sso = tokens.iter().find(|t| t.key == "sso").unwrap();
+        assert_eq!(sso.account_id.as_deref(), Some(ACCOUNT));
+        assert!(!serde_json::to_string(&tokens).unwrap().contains("access_token"));
+    }
+
+    #[test]
+    fn remove_cached_tokens_matches_host_and_account() {
+        let mut cache = sample_cache();
+        let mut removed = remove_cached_tokens(&mut cache, "https://accounts.cloud.databricks.com/", Some(ACCOUNT), &sections());
+        removed.sort();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[1], "sso");
+        assert_eq!(cache["version"], 1);
+        assert_eq!(cache["tokens"].as_object().unwrap().len(), 2);
+
+        let removed = remove_cached_tokens(&mut cache, "accounts.cloud.databricks.com", None, &[]);
+        assert_eq!(removed, vec!["https://accounts.cloud.databricks.com/oidc/accounts/other".to_string()]);
+        assert!(cache["tokens"].get("https://dbc-1.cloud.databricks.com").is_some());
+    }
+
+    #[test]
+    fn invalidate_cached_tokens_backs_up_before_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token-cache.json");
+        fs::write(&path, serde_json::to_string(&sample_cache()).unwrap()).unwrap();
+
+        assert!(invalidate_cached_tokens(&path, "unknown.example.com", None).unwrap().is_empty());
+        assert!(!path.with_extension("json.bak").exists());
+
+        let removed = invalidate_cached_tokens(&path, "https://dbc-1.cloud.databricks.com", None).unwrap();
+        assert_eq!(removed.len(), 1);
+        let backup: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.with_extension("json.bak")).unwrap()).unwrap();
+        assert_eq!(backup, sample_cache());
+        let updated: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(updated["tokens"].as_object().unwrap().len(), 3);
+        assert!(invalidate_cached_tokens(&dir.path().join("missing.json"), "x", None).unwrap().is_empty());
+    }
+}
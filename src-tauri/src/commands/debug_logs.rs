@@ -0,0 +1,206 @@
+//! Terraform debug logs (`TF_LOG`) kept per run.
+//!
+//! When a run asks for a log level — once through `run_terraform_command`'s
+//! `debug_log_level`, or for every run through the deployment's run settings —
+//! Terraform writes its log to `<deployment>/.deployer-runs/<millis>-<command>.log`
+//! via `TF_LOG_PATH`, keeping the run output readable. The newest
+//! [`MAX_DEBUG_LOGS`] files are kept. [`get_terraform_debug_log`] returns the
+//! tail of one of them with authorization headers redacted, so provider
+//! failures can be investigated without rerunning in a terminal.
+
+use super::audit::REDACTED;
+use super::{debug_log, get_deployments_dir, sanitize_deployment_name};
+use serde::Serialize;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Directory of per-run debug logs inside a deployment.
+pub(crate) const RUNS_DIR: &str = ".deployer-runs";
+
+/// Debug logs kept per deployment; TRACE logs run to hundreds of megabytes.
+const MAX_DEBUG_LOGS: usize = 10;
+
+const DEFAULT_TAIL_LINES: usize = 500;
+
+/// Only the end of a log is read.
+const MAX_READ_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Variables that turn Terraform logging on.
+const LOG_LEVEL_VARS: &[&str] = &["TF_LOG", "TF_LOG_CORE", "TF_LOG_PROVIDER"];
+
+/// A debug log file.
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugLogFile {
+    pub name: String,
+    pub size: u64,
+    /// Unix timestamp (seconds) of the last write.
+    pub modified: Option<u64>,
+}
+
+/// The tail of a debug log and the logs available.
+#[derive(Debug, Clone, Serialize)]
+pub struct TerraformDebugLog {
+    /// Log shown, `None` when the deployment has no debug logs.
+    pub file: Option<String>,
+    pub content: String,
+    pub size: u64,
+    /// Earlier lines were left out.
+    pub truncated: bool,
+    /// Newest first.
+    pub files: Vec<DebugLogFile>,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Whether `vars` turn on Terraform logging.
+pub(crate) fn log_requested<'a>(mut vars: impl Iterator<Item = (&'a String, &'a String)>) -> bool {
+    vars.any(|(name, value)| LOG_LEVEL_VARS.contains(&name.as_str()) && !value.is_empty() && !value.eq_ignore_ascii_case("OFF"))
+}
+
+/// Debug logs of a deployment, newest first.
+fn list_logs(deployment_dir: &Path) -> Vec<(PathBuf, DebugLogFile)> {
+    let Ok(entries) = fs::read_dir(deployment_dir.join(RUNS_DIR)) else {
+        return Vec::new();
+    };
+    let mut logs: Vec<(u64, PathBuf, DebugLogFile)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            let name = path.file_name()?.to_str()?.to_string();
+            let millis: u64 = name.strip_suffix(".log")?.split('-').next()?.parse().ok()?;
+            let metadata = e.metadata().ok()?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            Some((millis, path, DebugLogFile { name, size: metadata.len(), modified }))
+        })
+        .collect();
+    logs.sort_by_key(|(millis, _, _)| std::cmp::Reverse(*millis));
+    logs.into_iter().map(|(_, path, file)| (path, file)).collect()
+}
+
+/// Path for a new debug log of a `subcommand` run, pruning the oldest logs.
+pub(crate) fn new_log_path(deployment_dir: &Path, subcommand: &str) -> Option<PathBuf> {
+    let dir = deployment_dir.join(RUNS_DIR);
+    if let Err(_e) = fs::create_dir_all(&dir) {
+        debug_log!("[debug_logs] Failed to create {}: {}", dir.display(), _e);
+        return None;
+    }
+    for (old, _) in list_logs(deployment_dir).iter().skip(MAX_DEBUG_LOGS - 1) {
+        let _ = fs::remove_file(old);
+    }
+    let mut millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mut path = dir.join(format!("{}-{}.log", millis, subcommand));
+    while path.exists() {
+        millis += 1;
+        path = dir.join(format!("{}-{}.log", millis, subcommand));
+    }
+    Some(path)
+}
+
+/// `line` with the value of an `Authorization` header replaced.
+fn redact_line(line: &str) -> String {
+    match line.to_ascii_lowercase().find("authorization:") {
+        Some(i) => format!("{} {}", &line[..i + "authorization:".len()], REDACTED),
+        None => line.to_string(),
+    }
+}
+
+/// The last `lines` lines of a log (reading at most [`MAX_READ_BYTES`]), and
+/// whether anything before them was left out.
+fn read_tail(path: &Path, lines: usize) -> Result<(String, u64, bool), String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open debug log: {}", e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = size.saturating_sub(MAX_READ_BYTES);
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|e| format!("Failed to read debug log: {}", e))?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let mut all: Vec<&str> = text.lines().collect();
+    if start > 0 && !all.is_empty() {
+        // Starts mid-line
+        all.remove(0);
+    }
+    let skip = all.len().saturating_sub(lines);
+    let content: String = all[skip..].iter().map(|l| format!("{}\n", redact_line(l))).collect();
+    Ok((content, size, start > 0 || skip > 0))
+}
+
+fn debug_log_of(deployment_dir: &Path, file: Option<&str>, tail_lines: usize) -> Result<TerraformDebugLog, String> {
+    let logs = list_logs(deployment_dir);
+    let selected = match file {
+        Some(name) => Some(logs.iter().find(|(_, f)| f.name == name).ok_or_else(|| format!("Debug log not found: {}", name))?),
+        None => logs.first(),
+    };
+    let (file, content, size, truncated) = match selected {
+        Some((path, f)) => {
+            let (content, size, truncated) = read_tail(path, tail_lines)?;
+            (Some(f.name.clone()), content, size, truncated)
+        }
+        None => (None, String::new(), 0, false),
+    };
+    Ok(TerraformDebugLog { file, content, size, truncated, files: logs.into_iter().map(|(_, f)| f).collect() })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Tail of a deployment's Terraform debug log: the newest one, or `file` from
+/// the returned list. `tail_lines` defaults to 500.
+#[tauri::command]
+pub fn get_terraform_debug_log(
+    app: AppHandle,
+    deployment_name: String,
+    file: Option<String>,
+    tail_lines: Option<usize>,
+) -> Result<TerraformDebugLog, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    debug_log_of(&deployment_dir, file.as_deref(), tail_lines.unwrap_or(DEFAULT_TAIL_LINES).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn log_paths_are_pruned_and_requested_by_level() {
+        let dir = tempfile::tempdir().unwrap();
+        for _ in 0..MAX_DEBUG_LOGS + 3 {
+            let path = new_log_path(dir.path(), "apply").unwrap();
+            fs::write(&path, "x\n").unwrap();
+        }
+        assert_eq!(list_logs(dir.path()).len(), MAX_DEBUG_LOGS);
+
+        let env = |k: &str, v: &str| HashMap::from([(k.to_string(), v.to_string())]);
+        assert!(log_requested(env("TF_LOG", "TRACE").iter()));
+        assert!(log_requested(env("TF_LOG_PROVIDER", "debug").iter()));
+        assert!(!log_requested(env("TF_LOG", "off").iter()));
+        assert!(!log_requested(env("TF_LOG_PATH", "/tmp/x").iter()));
+    }
+
+    #[test]
+    fn tail_is_redacted_and_reports_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(debug_log_of(dir.path(), None, 10).unwrap().file, None);
+
+        let path = new_log_path(dir.path(), "plan").unwrap();
+        fs::write(&path, "first\nsecond\nGET /api HTTP/1.1\nAuthorization: Bearer dapi123\nlast\n").unwrap();
+        let log = debug_log_of(dir.path(), None, 3).unwrap();
+        assert!(log.truncated);
+        assert_eq!(log.content, "GET /api HTTP/1.1\nAuthorization: (redacted)\nlast\n");
+        assert_eq!(log.files.len(), 1);
+
+        let name = log.file.unwrap();
+        assert!(!debug_log_of(dir.path(), Some(&name), 100).unwrap().truncated);
+        assert!(debug_log_of(dir.path(), Some("../meta.json"), 100).is_err());
+    }
+}
@@ -1,14 +1,17 @@
 //! Terraform deployment, configuration, and lifecycle management commands.
 
 use super::{
-    copy_dir_all, debug_log, get_deployments_dir, get_templates_dir, opt_non_empty,
+    copy_dir_all, debug_log, get_deployments_dir, opt_non_empty,
     sanitize_deployment_name, sanitize_template_id, CloudCredentials,
 };
+use super::notifications::RunOutcome;
 use crate::dependencies::{self, DependencyStatus};
+use crate::storage::Environment;
 use crate::terraform::{self, DeploymentStatus, CURRENT_PROCESS, DEPLOYMENT_STATUS};
 use std::collections::HashMap;
+use serde::Serialize;
 use std::fs;
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
 
 // ─── Helpers (deployment-local) ─────────────────────────────────────────────
 
@@ -40,7 +43,7 @@ fn safe_zip_entry_path(base_dir: &std::path::Path, entry_name: &str) -> Result<s
 }
 
 /// Set an environment variable from an optional credential value.
-fn set_env_if_present(env_vars: &mut HashMap<String, String>, key: &str, value: &Option<String>) {
+pub(super) fn set_env_if_present(env_vars: &mut HashMap<String, String>, key: &str, value: &Option<String>) {
     if let Some(v) = value {
         if !v.is_empty() {
             env_vars.insert(key.to_string(), v.clone());
@@ -54,121 +57,15 @@ fn has_databricks_sp_creds(credentials: &CloudCredentials) -> bool {
         && opt_non_empty(&credentials.databricks_client_secret)
 }
 
-/// Locate a Google Application Default Credentials JSON file.
-///
-/// The Databricks Terraform provider authenticates via Google's ADC chain
-/// when `google_service_account` is set (impersonation mode). We check:
-///   1. The standard ADC path (`gcloud auth application-default login`)
-///   2. The legacy per-account path (`gcloud auth login`)
-fn find_gcp_adc_path() -> Option<String> {
-    let home = dirs::home_dir()?;
-
-    let standard_adc = home.join(".config/gcloud/application_default_credentials.json");
-    if standard_adc.exists() {
-        debug_log!("[find_gcp_adc_path] found standard ADC: {:?}", standard_adc);
-        return Some(standard_adc.to_string_lossy().to_string());
-    }
-
-    let gcloud = dependencies::find_gcloud_cli_path()?;
-    let account = super::silent_cmd(&gcloud)
-        .args(["config", "get-value", "account"])
-        .output()
-        .ok()
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .filter(|s| !s.is_empty() && s != "(unset)")?;
-
-    let legacy_adc = home
-        .join(".config/gcloud/legacy_credentials")
-        .join(&account)
-        .join("adc.json");
-    if legacy_adc.exists() {
-        debug_log!("[find_gcp_adc_path] found legacy ADC for {}: {:?}", account, legacy_adc);
-        return Some(legacy_adc.to_string_lossy().to_string());
-    }
-
-    debug_log!("[find_gcp_adc_path] no ADC file found");
-    None
-}
-
-/// Get a fresh GCP user OAuth token via gcloud CLI (for the Google Terraform provider).
-/// Bypasses impersonation so the token belongs to the user, not the SA.
-///
-/// Uses the `CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT` env-var override (set to
-/// empty) instead of mutating the global gcloud config, avoiding race conditions
-/// and leaving the user's config untouched.
-fn refresh_gcp_user_token() -> Option<String> {
-    let gcloud = dependencies::find_gcloud_cli_path()?;
-
-    let token_output = super::silent_cmd(&gcloud)
-        .args(["auth", "print-access-token"])
-        .env("CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT", "")
-        .output()
-        .ok();
-
-    token_output
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .filter(|s| !s.is_empty())
-}
-
 /// Build the environment variables map that Terraform needs from credentials.
-fn build_env_vars(credentials: &CloudCredentials) -> HashMap<String, String> {
+pub(crate) fn build_env_vars(credentials: &CloudCredentials) -> HashMap<String, String> {
     let mut env_vars = HashMap::new();
 
-    // AWS credentials — clear conflicting env vars to prevent inherited shell values from clashing
-    if let Some(profile) = &credentials.aws_profile {
-        if !profile.is_empty() {
-            env_vars.insert("AWS_PROFILE".to_string(), profile.clone());
-            env_vars.insert("AWS_ACCESS_KEY_ID".to_string(), String::new());
-            env_vars.insert("AWS_SECRET_ACCESS_KEY".to_string(), String::new());
-            env_vars.insert("AWS_SESSION_TOKEN".to_string(), String::new());
-        }
-    } else {
-        set_env_if_present(&mut env_vars, "AWS_ACCESS_KEY_ID", &credentials.aws_access_key_id);
-        set_env_if_present(&mut env_vars, "AWS_SECRET_ACCESS_KEY", &credentials.aws_secret_access_key);
-        set_env_if_present(&mut env_vars, "AWS_SESSION_TOKEN", &credentials.aws_session_token);
-        env_vars.insert("AWS_PROFILE".to_string(), String::new());
-    }
-    set_env_if_present(&mut env_vars, "AWS_DEFAULT_REGION", &credentials.aws_region);
-
-    // Azure credentials
-    set_env_if_present(&mut env_vars, "ARM_TENANT_ID", &credentials.azure_tenant_id);
-    set_env_if_present(&mut env_vars, "ARM_SUBSCRIPTION_ID", &credentials.azure_subscription_id);
-    set_env_if_present(&mut env_vars, "ARM_CLIENT_ID", &credentials.azure_client_id);
-    set_env_if_present(&mut env_vars, "ARM_CLIENT_SECRET", &credentials.azure_client_secret);
-
-    // GCP credentials
-    let is_gcp = credentials.cloud.as_deref() == Some("gcp");
-
-    if let Some(project_id) = &credentials.gcp_project_id {
-        if !project_id.is_empty() {
-            env_vars.insert("GOOGLE_PROJECT".to_string(), project_id.clone());
-            env_vars.insert("GCLOUD_PROJECT".to_string(), project_id.clone());
-            env_vars.insert("CLOUDSDK_CORE_PROJECT".to_string(), project_id.clone());
-        }
-    }
-
-    if opt_non_empty(&credentials.gcp_credentials_json) {
-        set_env_if_present(&mut env_vars, "GOOGLE_CREDENTIALS", &credentials.gcp_credentials_json);
-    } else if is_gcp {
-        // Databricks SDK uses Google ADC for impersonation auth — point it at gcloud creds
-        if let Some(adc_path) = find_gcp_adc_path() {
-            env_vars.insert("GOOGLE_APPLICATION_CREDENTIALS".to_string(), adc_path);
-        }
-
-        // Google Terraform provider needs an OAuth token when no GOOGLE_CREDENTIALS is set
-        let token = refresh_gcp_user_token()
-            .or_else(|| credentials.gcp_oauth_token.clone().filter(|s| !s.is_empty()));
-        if let Some(t) = token {
-            env_vars.insert("GOOGLE_OAUTH_ACCESS_TOKEN".to_string(), t);
-        }
-    } else {
-        set_env_if_present(
-            &mut env_vars,
-            "GOOGLE_OAUTH_ACCESS_TOKEN",
-            &credentials.gcp_oauth_token,
-        );
+    // Only the deployment's cloud: another cloud's leftover credentials
+    // (e.g. ARM_* while deploying to AWS) must not reach Terraform
+    let cloud = credentials.cloud.as_deref().unwrap_or("aws");
+    if let Some(provider) = super::cloud_provider::provider(cloud) {
+        provider.apply_env(credentials, &mut env_vars);
     }
 
     // Databricks credentials
@@ -186,11 +83,19 @@ fn build_env_vars(credentials: &CloudCredentials) -> HashMap<String, String> {
     let profile_has_sp_creds = databricks_auth_type == "profile"
         && has_databricks_sp_creds(credentials);
 
+    let is_gcp = credentials.cloud.as_deref() == Some("gcp");
     let is_azure = credentials.cloud.as_deref() == Some("azure");
 
     // Databricks auth — clear conflicting env vars to prevent inherited shell values from clashing
     if is_gcp {
         env_vars.insert("DATABRICKS_CONFIG_FILE".to_string(), "/dev/null".to_string());
+    } else if super::uses_azure_identity(credentials) {
+        // Identity mode: the provider runs `az account get-access-token` for the
+        // Databricks resource, so inherited tokens or profiles must not win over it
+        for key in ["DATABRICKS_TOKEN", "DATABRICKS_CLIENT_ID", "DATABRICKS_CLIENT_SECRET", "DATABRICKS_CONFIG_PROFILE"] {
+            env_vars.insert(key.to_string(), String::new());
+        }
+        env_vars.insert("DATABRICKS_AUTH_TYPE".to_string(), "azure-cli".to_string());
     } else if !is_azure {
         if databricks_auth_type == "profile" && !profile_has_sp_creds {
             set_env_if_present(
@@ -269,6 +174,7 @@ pub async fn check_dependencies() -> HashMap<String, DependencyStatus> {
         let mut deps = HashMap::new();
 
         deps.insert("terraform".to_string(), dependencies::check_terraform());
+        deps.insert("tofu".to_string(), dependencies::check_tofu());
         deps.insert("git".to_string(), dependencies::check_git());
         deps.insert("aws".to_string(), dependencies::check_aws_cli());
         deps.insert("azure".to_string(), dependencies::check_azure_cli());
@@ -284,6 +190,32 @@ pub async fn check_dependencies() -> HashMap<String, DependencyStatus> {
     .unwrap_or_default()
 }
 
+// ─── Dependency Search Paths ────────────────────────────────────────────────
+
+/// Get the extra directories searched for CLI binaries.
+#[tauri::command]
+pub fn get_dependency_search_paths(app: AppHandle) -> Vec<String> {
+    super::settings::load(&app).dependency_search_paths
+}
+
+/// Set the extra directories searched for CLI binaries (e.g. asdf or scoop
+/// shims in nonstandard locations). Returns the normalized list.
+#[tauri::command]
+pub fn set_dependency_search_paths(app: AppHandle, paths: Vec<String>) -> Result<Vec<String>, String> {
+    super::settings::update(&app, |settings| {
+        Ok(super::settings::AppSettings { dependency_search_paths: paths, ..settings })
+    })
+    .map(|settings| settings.dependency_search_paths)
+}
+
+/// Report every copy of a dependency found on disk and which one is used.
+#[tauri::command]
+pub async fn locate_dependency(name: String) -> Result<dependencies::DependencyLocation, String> {
+    tokio::task::spawn_blocking(move || dependencies::locate(&name))
+        .await
+        .map_err(|e| format!("Failed to locate dependency: {}", e))?
+}
+
 /// Check connectivity to external services required by Terraform.
 ///
 /// Returns a map of domain names to reachability status.  Used on the
@@ -315,32 +247,14 @@ pub async fn check_terraform_connectivity() -> HashMap<String, bool> {
     results
 }
 
-/// Download and install Terraform.
-#[tauri::command]
-pub async fn install_terraform() -> Result<String, String> {
-    let url = dependencies::get_terraform_download_url();
-    let install_dir = dependencies::get_terraform_install_path();
-
-    let response = reqwest::get(url)
-        .await
-        .map_err(|e| format!("Failed to download Terraform: {}", e))?;
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
-    let zip_path = temp_dir.path().join("terraform.zip");
-
-    fs::write(&zip_path, &bytes).map_err(|e| format!("Failed to write zip: {}", e))?;
-
-    let file = fs::File::open(&zip_path).map_err(|e| e.to_string())?;
+/// Extract a zip archive into `dest_dir`, marking files executable on Unix.
+fn extract_zip(zip_path: &std::path::Path, dest_dir: &std::path::Path) -> Result<(), String> {
+    let file = fs::File::open(zip_path).map_err(|e| e.to_string())?;
     let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
-        let outpath = safe_zip_entry_path(&install_dir, file.name())?;
+        let outpath = safe_zip_entry_path(dest_dir, file.name())?;
 
         if file.name().ends_with('/') {
             fs::create_dir_all(&outpath).map_err(|e| e.to_string())?;
@@ -360,6 +274,32 @@ pub async fn install_terraform() -> Result<String, String> {
             }
         }
     }
+    Ok(())
+}
+
+/// Download and install Terraform.
+#[tauri::command]
+pub async fn install_terraform() -> Result<String, String> {
+    let url = dependencies::get_terraform_download_url();
+    let install_dir = dependencies::get_terraform_install_path();
+
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to download Terraform: {}", e))?;
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let zip_path = temp_dir.path().join("terraform.zip");
+
+    tokio::fs::write(&zip_path, &bytes)
+        .await
+        .map_err(|e| format!("Failed to write zip: {}", e))?;
+    let dest = install_dir.clone();
+    super::run_blocking(move || extract_zip(&zip_path, &dest)).await?;
 
     Ok(format!(
         "Terraform installed to {}",
@@ -367,197 +307,546 @@ pub async fn install_terraform() -> Result<String, String> {
     ))
 }
 
-/// Save deployment configuration (copy template + generate `terraform.tfvars`).
-#[tauri::command]
-pub fn save_configuration(
-    app: AppHandle,
-    template_id: String,
-    deployment_name: String,
-    values: HashMap<String, serde_json::Value>,
-    credentials: Option<CloudCredentials>,
+// ─── Dependency Installation ────────────────────────────────────────────────
+
+/// Event emitted while `install_dependency` runs.
+const INSTALL_PROGRESS_EVENT: &str = "dependency-install-progress";
+
+/// Payload of [`INSTALL_PROGRESS_EVENT`].
+#[derive(Clone, Serialize)]
+pub struct InstallProgress {
+    pub dependency: String,
+    /// "starting", "downloading", "installing", "done" or "error".
+    pub stage: String,
+    pub message: String,
+    /// Download progress (0-100) when the size is known.
+    pub percent: Option<u8>,
+}
+
+fn emit_progress(app: &AppHandle, dependency: &str, stage: &str, message: &str, percent: Option<u8>) {
+    let _ = app.emit(
+        INSTALL_PROGRESS_EVENT,
+        InstallProgress {
+            dependency: dependency.to_string(),
+            stage: stage.to_string(),
+            message: message.to_string(),
+            percent,
+        },
+    );
+}
+
+/// Stream a download to `dest`, emitting progress roughly every 5%.
+async fn download_with_progress(
+    app: &AppHandle,
+    dependency: &str,
+    url: &str,
+    dest: &std::path::Path,
+) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", dependency, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: HTTP {}", dependency, response.status()));
+    }
+
+    let total = response.content_length();
+    let mut file = tokio::fs::File::create(dest)
+        .await
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+    let mut received: u64 = 0;
+    let mut last_percent: Option<u8> = None;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        received += chunk.len() as u64;
+
+        if let Some(total) = total.filter(|t| *t > 0) {
+            let percent = ((received * 100) / total).min(100) as u8;
+            if last_percent.is_none_or(|last| percent >= last + 5) {
+                last_percent = Some(percent);
+                emit_progress(app, dependency, "downloading", "Downloading…", Some(percent));
+            }
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write download: {}", e))
+}
+
+/// Run an installer process off the async runtime and surface its stderr on failure.
+/// Check a downloaded release archive against the release's SHA256SUMS file.
+async fn verify_download(url: &str, checksums_url: &str, path: &std::path::Path) -> Result<(), String> {
+    let file_name = url.rsplit('/').next().unwrap_or(url);
+    let response = reqwest::get(checksums_url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download checksums for {}: {}", file_name, e))?;
+    let checksums = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read checksums for {}: {}", file_name, e))?;
+    let expected = dependencies::expected_sha256(&checksums, file_name)
+        .ok_or_else(|| format!("No published checksum for {}", file_name))?;
+
+    let path = path.to_path_buf();
+    let actual = super::run_blocking(move || super::templates::sha256_file(&path)).await?;
+    if actual != expected {
+        return Err(format!(
+            "Checksum mismatch for {}: the download is corrupted or was tampered with",
+            file_name
+        ));
+    }
+    Ok(())
+}
+
+async fn run_installer(program: std::path::PathBuf, args: Vec<String>) -> Result<(), String> {
+    let display = program.display().to_string();
+    let output = tokio::task::spawn_blocking(move || super::silent_cmd(&program).args(&args).output())
+        .await
+        .map_err(|e| format!("Installer task failed: {}", e))?
+        .map_err(|e| format!("Failed to run {}: {}", display, e))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let detail = if stderr.trim().is_empty() { stdout } else { stderr };
+    Err(format!("{} failed: {}", display, detail.trim()))
+}
+
+async fn run_install_method(
+    app: &AppHandle,
+    dependency: &str,
+    method: dependencies::InstallMethod,
+    manager_path: Option<std::path::PathBuf>,
 ) -> Result<String, String> {
-    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
-    let safe_template_id = sanitize_template_id(&template_id)?;
+    use dependencies::{InstallMethod, PackageManager};
+
+    let home = dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string())?;
+
+    match method {
+        InstallMethod::PackageManager { manager, args } => {
+            let manager_path =
+                manager_path.ok_or_else(|| "Package manager not found".to_string())?;
+            emit_progress(app, dependency, "installing", &format!("Running {}…", manager_path.display()), None);
+
+            if manager == PackageManager::Apt {
+                // apt needs root; pkexec shows the desktop's graphical auth prompt.
+                // Refresh the package lists first, in the same prompt, so a stale
+                // cache doesn't fail the install.
+                let mut pkexec_args = vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "\"$0\" update && \"$0\" \"$@\"".to_string(),
+                    manager_path.display().to_string(),
+                ];
+                pkexec_args.extend(args);
+                run_installer("pkexec".into(), pkexec_args).await?;
+            } else {
+                run_installer(manager_path.clone(), args).await?;
+            }
+            let tool = manager_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            Ok(format!("Installed {} with {}", dependency, tool))
+        }
+        InstallMethod::BinaryZip { url, checksums_url } => {
+            let install_dir = dependencies::get_terraform_install_path();
+            let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+            let zip_path = temp_dir.path().join("download.zip");
+            download_with_progress(app, dependency, &url, &zip_path).await?;
+            emit_progress(app, dependency, "installing", "Verifying checksum…", None);
+            verify_download(&url, &checksums_url, &zip_path).await?;
+
+            emit_progress(app, dependency, "installing", "Extracting…", None);
+            extract_zip(&zip_path, &install_dir)?;
+            Ok(format!("{} installed to {}", dependency, install_dir.display()))
+        }
+        InstallMethod::AwsBundle { url } => {
+            let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+            let zip_path = temp_dir.path().join("awscliv2.zip");
+            download_with_progress(app, dependency, &url, &zip_path).await?;
+
+            emit_progress(app, dependency, "installing", "Running AWS CLI installer…", None);
+            extract_zip(&zip_path, temp_dir.path())?;
+            let install_dir = home.join(".local/aws-cli");
+            let bin_dir = home.join(".local/bin");
+            run_installer(
+                temp_dir.path().join("aws").join("install"),
+                vec![
+                    "-i".into(),
+                    install_dir.display().to_string(),
+                    "-b".into(),
+                    bin_dir.display().to_string(),
+                    "--update".into(),
+                ],
+            )
+            .await?;
+            Ok(format!("AWS CLI installed to {}", bin_dir.display()))
+        }
+        InstallMethod::GcloudArchive { url } => {
+            let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+            let archive_path = temp_dir.path().join("google-cloud-cli.tar.gz");
+            download_with_progress(app, dependency, &url, &archive_path).await?;
+
+            emit_progress(app, dependency, "installing", "Extracting Google Cloud SDK…", None);
+            // The archive unpacks to google-cloud-sdk/, one of the gcloud search locations
+            run_installer(
+                "tar".into(),
+                vec![
+                    "-xzf".into(),
+                    archive_path.display().to_string(),
+                    "-C".into(),
+                    home.display().to_string(),
+                ],
+            )
+            .await?;
+            Ok(format!("Google Cloud SDK installed to {}", home.join("google-cloud-sdk").display()))
+        }
+    }
+}
 
-    let templates_dir = get_templates_dir(&app)?;
-    let template_dir = templates_dir.join(&safe_template_id);
-    let template_variables_path = template_dir.join("variables.tf");
+/// Install a missing CLI dependency ("terraform", "tofu", "git", "aws",
+/// "azure", "gcloud" or "databricks") using the system package manager or a direct
+/// download. Emits `dependency-install-progress` events while running.
+#[tauri::command]
+pub async fn install_dependency(app: AppHandle, name: String) -> Result<String, String> {
+    emit_progress(&app, &name, "starting", &format!("Installing {}…", name), None);
 
-    if !template_variables_path.exists() {
-        return Err("Template not found".to_string());
+    let result = if name == "terraform" {
+        install_terraform().await
+    } else {
+        let detected = dependencies::detect_package_manager();
+        let manager = detected.as_ref().map(|(m, _)| *m);
+        match dependencies::install_method(&name, manager, std::env::consts::OS, std::env::consts::ARCH) {
+            Ok(method) => {
+                debug_log!("[install_dependency] {} via {:?}", name, method);
+                run_install_method(&app, &name, method, detected.map(|(_, p)| p)).await
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    match &result {
+        Ok(message) => emit_progress(&app, &name, "done", message, Some(100)),
+        Err(e) => emit_progress(&app, &name, "error", e, None),
     }
+    result
+}
 
-    let deployments_dir = get_deployments_dir(&app)?;
-    let deployment_dir = deployments_dir.join(&safe_deployment_name);
+/// Get the engine (Terraform or OpenTofu) used by a deployment.
+#[tauri::command]
+pub fn get_deployment_engine(app: AppHandle, deployment_name: String) -> Result<terraform::Engine, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    Ok(terraform::deployment_engine(&deployment_dir))
+}
+
+/// Select the engine (Terraform or OpenTofu) for a deployment.
+#[tauri::command]
+pub fn set_deployment_engine(
+    app: AppHandle,
+    deployment_name: String,
+    engine: terraform::Engine,
+) -> Result<(), String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
 
     if !deployment_dir.exists() {
-        fs::create_dir_all(&deployment_dir).map_err(|e| e.to_string())?;
-        copy_dir_all(&template_dir, &deployment_dir)?;
+        return Err("Deployment not found. Please save configuration first.".to_string());
+    }
+    if engine.find_path().is_none() {
+        return Err(format!(
+            "{} is not installed. Install it from the Dependencies screen first.",
+            engine.binary_name()
+        ));
     }
 
-    let tfvars_path = deployment_dir.join("terraform.tfvars");
-    let variables_path = deployment_dir.join("variables.tf");
+    terraform::set_deployment_engine(&deployment_dir, engine)
+}
 
-    // Merge credentials into values for terraform variables that need them
-    let mut merged_values = values.clone();
-    if let Some(creds) = credentials {
-        if let Some(ref account_id) = creds.databricks_account_id {
-            if !account_id.is_empty() {
-                merged_values.insert(
-                    "databricks_account_id".to_string(),
-                    serde_json::Value::String(account_id.clone()),
-                );
-            }
+/// Add the Terraform variables derived from the selected credentials
+/// (account ID, auth type, tenant/subscription, GCP project and auth method).
+fn merge_credential_values(merged_values: &mut HashMap<String, serde_json::Value>, creds: &CloudCredentials) {
+    if let Some(ref account_id) = creds.databricks_account_id {
+        if !account_id.is_empty() {
+            merged_values.insert(
+                "databricks_account_id".to_string(),
+                serde_json::Value::String(account_id.clone()),
+            );
         }
+    }
 
-        // Map UI auth type to Terraform databricks_auth_type: azure-cli (Azure Identity),
-        // oauth-m2m (service principal), databricks-cli (OAuth/SSO profile)
-        let auth_type = match creds.databricks_auth_type.as_deref() {
-            Some("profile") => {
-                if creds.cloud.as_deref() == Some("azure") && creds.azure_databricks_use_identity == Some(true) {
-                    "azure-cli"
-                } else if has_databricks_sp_creds(&creds) {
-                    "oauth-m2m"
-                } else {
-                    "databricks-cli"
-                }
+    // Map UI auth type to Terraform databricks_auth_type: azure-cli (Azure Identity),
+    // oauth-m2m (service principal), databricks-cli (OAuth/SSO profile)
+    let auth_type = match creds.databricks_auth_type.as_deref() {
+        Some("profile") => {
+            if super::uses_azure_identity(creds) {
+                "azure-cli"
+            } else if has_databricks_sp_creds(creds) {
+                "oauth-m2m"
+            } else {
+                "databricks-cli"
             }
-            _ => "oauth-m2m",
-        };
-        merged_values.insert(
-            "databricks_auth_type".to_string(),
-            serde_json::Value::String(auth_type.to_string()),
-        );
+        }
+        _ => "oauth-m2m",
+    };
+    merged_values.insert(
+        "databricks_auth_type".to_string(),
+        serde_json::Value::String(auth_type.to_string()),
+    );
 
-        // Azure-specific Databricks variables
-        if creds.cloud.as_deref() == Some("azure") {
-            if auth_type == "oauth-m2m" {
-                if let Some(client_id) = &creds.databricks_client_id {
-                    if !client_id.is_empty() {
-                        merged_values.insert(
-                            "databricks_client_id".to_string(),
-                            serde_json::Value::String(client_id.clone()),
-                        );
-                    }
+    // Azure-specific Databricks variables
+    if creds.cloud.as_deref() == Some("azure") {
+        if auth_type == "oauth-m2m" {
+            if let Some(client_id) = &creds.databricks_client_id {
+                if !client_id.is_empty() {
+                    merged_values.insert(
+                        "databricks_client_id".to_string(),
+                        serde_json::Value::String(client_id.clone()),
+                    );
                 }
-                if let Some(client_secret) = &creds.databricks_client_secret {
-                    if !client_secret.is_empty() {
-                        merged_values.insert(
-                            "databricks_client_secret".to_string(),
-                            serde_json::Value::String(client_secret.clone()),
-                        );
-                    }
+            }
+            if let Some(client_secret) = &creds.databricks_client_secret {
+                if !client_secret.is_empty() {
+                    merged_values.insert(
+                        "databricks_client_secret".to_string(),
+                        serde_json::Value::String(client_secret.clone()),
+                    );
                 }
-            } else if auth_type == "databricks-cli" {
-                // Only write profile for databricks-cli, not for azure-cli
-                if let Some(profile) = &creds.databricks_profile {
-                    if !profile.is_empty() {
-                        merged_values.insert(
-                            "databricks_profile".to_string(),
-                            serde_json::Value::String(profile.clone()),
-                        );
-                    }
+            }
+        } else if auth_type == "databricks-cli" {
+            // Only write profile for databricks-cli, not for azure-cli
+            if let Some(profile) = &creds.databricks_profile {
+                if !profile.is_empty() {
+                    merged_values.insert(
+                        "databricks_profile".to_string(),
+                        serde_json::Value::String(profile.clone()),
+                    );
                 }
             }
         }
+    }
 
-        if let Some(tenant_id) = &creds.azure_tenant_id {
-            if !tenant_id.is_empty() {
+    if let Some(tenant_id) = &creds.azure_tenant_id {
+        if !tenant_id.is_empty() {
+            merged_values.insert(
+                "tenant_id".to_string(),
+                serde_json::Value::String(tenant_id.clone()),
+            );
+        }
+    }
+    if let Some(sub_id) = &creds.azure_subscription_id {
+        if !sub_id.is_empty() {
+            merged_values.insert(
+                "subscription_id".to_string(),
+                serde_json::Value::String(sub_id.clone()),
+            );
+            merged_values.insert(
+                "azure_subscription_id".to_string(),
+                serde_json::Value::String(sub_id.clone()),
+            );
+            merged_values.insert(
+                "az_subscription".to_string(),
+                serde_json::Value::String(sub_id.clone()),
+            );
+        }
+    }
+
+    // GCP-specific variables
+    if creds.cloud.as_deref() == Some("gcp") {
+        if let Some(project_id) = &creds.gcp_project_id {
+            if !project_id.is_empty() {
                 merged_values.insert(
-                    "tenant_id".to_string(),
-                    serde_json::Value::String(tenant_id.clone()),
+                    "google_project_name".to_string(),
+                    serde_json::Value::String(project_id.clone()),
+                );
+                merged_values.insert(
+                    "google_project".to_string(),
+                    serde_json::Value::String(project_id.clone()),
+                );
+                merged_values.insert(
+                    "project".to_string(),
+                    serde_json::Value::String(project_id.clone()),
                 );
             }
         }
-        if let Some(sub_id) = &creds.azure_subscription_id {
-            if !sub_id.is_empty() {
+
+        let gcp_auth_method = if opt_non_empty(&creds.gcp_credentials_json) {
+            "credentials"
+        } else {
+            "impersonation"
+        };
+
+        merged_values.insert(
+            "gcp_auth_method".to_string(),
+            serde_json::Value::String(gcp_auth_method.to_string()),
+        );
+
+        if let Some(ref email) = creds.gcp_service_account_email {
+            if !email.is_empty() {
                 merged_values.insert(
-                    "subscription_id".to_string(),
-                    serde_json::Value::String(sub_id.clone()),
+                    "google_service_account_email".to_string(),
+                    serde_json::Value::String(email.clone()),
                 );
                 merged_values.insert(
-                    "azure_subscription_id".to_string(),
-                    serde_json::Value::String(sub_id.clone()),
+                    "databricks_google_service_account".to_string(),
+                    serde_json::Value::String(email.clone()),
                 );
+            }
+        }
+
+        if let Some(ref json) = creds.gcp_credentials_json {
+            if !json.is_empty() {
                 merged_values.insert(
-                    "az_subscription".to_string(),
-                    serde_json::Value::String(sub_id.clone()),
+                    "google_credentials_json".to_string(),
+                    serde_json::Value::String(json.clone()),
                 );
             }
         }
+    }
+}
 
-        // GCP-specific variables
-        if creds.cloud.as_deref() == Some("gcp") {
-            if let Some(project_id) = &creds.gcp_project_id {
-                if !project_id.is_empty() {
-                    merged_values.insert(
-                        "google_project_name".to_string(),
-                        serde_json::Value::String(project_id.clone()),
-                    );
-                    merged_values.insert(
-                        "google_project".to_string(),
-                        serde_json::Value::String(project_id.clone()),
-                    );
-                    merged_values.insert(
-                        "project".to_string(),
-                        serde_json::Value::String(project_id.clone()),
-                    );
-                }
-            }
+/// A configuration rendered for Terraform.
+pub(crate) struct RenderedConfiguration {
+    /// Contents of `terraform.tfvars`, without sensitive values.
+    pub tfvars: String,
+    pub variables: Vec<terraform::TerraformVariable>,
+    /// Sensitive values, passed as `TF_VAR_*` at run time.
+    pub secrets: std::collections::BTreeMap<String, String>,
+}
 
-            let gcp_auth_method = if opt_non_empty(&creds.gcp_credentials_json) {
-                "credentials"
-            } else {
-                "impersonation"
-            };
+/// Render `values` for Terraform: credentials are merged in, the tag policy
+/// applied and sensitive variables split out of `terraform.tfvars`.
+pub(crate) fn render_tfvars(
+    env: &dyn Environment,
+    template_id: &str,
+    variables_path: &std::path::Path,
+    values: HashMap<String, serde_json::Value>,
+    credentials: Option<&CloudCredentials>,
+) -> Result<RenderedConfiguration, String> {
+    let mut merged_values = values;
+    if let Some(creds) = credentials {
+        merge_credential_values(&mut merged_values, creds);
+    }
 
-            merged_values.insert(
-                "gcp_auth_method".to_string(),
-                serde_json::Value::String(gcp_auth_method.to_string()),
-            );
+    let variables_content = fs::read_to_string(variables_path).map_err(|e| e.to_string())?;
+    let variables = terraform::parse_variables_tf(&variables_content)?;
+    super::tagging::apply_tag_policy(env, template_id, &mut merged_values, &variables)?;
+    let secrets = super::secret_vars::split_sensitive(&mut merged_values, &variables);
 
-            if let Some(ref email) = creds.gcp_service_account_email {
-                if !email.is_empty() {
-                    merged_values.insert(
-                        "google_service_account_email".to_string(),
-                        serde_json::Value::String(email.clone()),
-                    );
-                    merged_values.insert(
-                        "databricks_google_service_account".to_string(),
-                        serde_json::Value::String(email.clone()),
-                    );
-                }
-            }
+    Ok(RenderedConfiguration {
+        tfvars: terraform::generate_tfvars(&merged_values, &variables),
+        variables,
+        secrets,
+    })
+}
 
-            if let Some(ref json) = creds.gcp_credentials_json {
-                if !json.is_empty() {
-                    merged_values.insert(
-                        "google_credentials_json".to_string(),
-                        serde_json::Value::String(json.clone()),
-                    );
-                }
-            }
-        }
+/// Save deployment configuration (copy template + generate `terraform.tfvars`).
+#[tauri::command]
+pub fn save_configuration(
+    app: AppHandle,
+    template_id: String,
+    deployment_name: String,
+    values: HashMap<String, serde_json::Value>,
+    credentials: Option<CloudCredentials>,
+    expiry: Option<super::expiry::ExpirySettings>,
+    post_deploy: Option<super::post_deploy::PostDeploySettings>,
+) -> Result<String, String> {
+    let saved = save_configuration_in(
+        &app,
+        &template_id,
+        &deployment_name,
+        values,
+        credentials,
+        expiry,
+        post_deploy,
+    )?;
+    super::recent_items::record_quietly(&app, super::recent_items::ItemKind::Template, &template_id, None);
+    super::recent_items::record_quietly(&app, super::recent_items::ItemKind::Deployment, &deployment_name, Some("save"));
+    Ok(saved)
+}
+
+/// Copy the template into the deployment directory and write `terraform.tfvars`.
+/// Shared by the desktop command and the headless CLI.
+pub(crate) fn save_configuration_in(
+    env: &dyn Environment,
+    template_id: &str,
+    deployment_name: &str,
+    values: HashMap<String, serde_json::Value>,
+    credentials: Option<CloudCredentials>,
+    expiry: Option<super::expiry::ExpirySettings>,
+    post_deploy: Option<super::post_deploy::PostDeploySettings>,
+) -> Result<String, String> {
+    let safe_deployment_name = sanitize_deployment_name(deployment_name)?;
+    let safe_template_id = sanitize_template_id(template_id)?;
+    let _lock = super::deployment_lock::acquire(env, &safe_deployment_name, "save")?;
+
+    let templates_dir = env.templates_dir()?;
+    let template_dir = templates_dir.join(&safe_template_id);
+    let template_variables_path = template_dir.join("variables.tf");
+
+    if !template_variables_path.exists() {
+        return Err("Template not found".to_string());
     }
 
-    let variables_content = fs::read_to_string(&variables_path).map_err(|e| e.to_string())?;
-    let variables = terraform::parse_variables_tf(&variables_content);
+    let deployments_dir = env.deployments_dir()?;
+    let deployment_dir = deployments_dir.join(&safe_deployment_name);
 
-    let tfvars_content = terraform::generate_tfvars(&merged_values, &variables);
-    fs::write(&tfvars_path, tfvars_content).map_err(|e| e.to_string())?;
+    if !deployment_dir.exists() {
+        fs::create_dir_all(&deployment_dir).map_err(|e| e.to_string())?;
+        copy_dir_all(&template_dir, &deployment_dir)?;
+    }
+    super::audit::record_template(&deployment_dir, &safe_template_id)?;
+    if let Some(settings) = &post_deploy {
+        super::post_deploy::write_settings(&deployment_dir, settings)?;
+    }
+
+    let tfvars_path = deployment_dir.join("terraform.tfvars");
+    let variables_path = deployment_dir.join("variables.tf");
+
+    if let Some(settings) = &expiry {
+        super::expiry::write_expiry(&deployment_dir, Some(settings), credentials.as_ref())?;
+    }
+
+    let rendered = render_tfvars(env, &safe_template_id, &variables_path, values, credentials.as_ref())?;
+    super::secret_vars::write_secrets(env, &deployment_dir, &rendered.secrets)?;
+    super::config_changes::backup_tfvars(&deployment_dir, &rendered.tfvars)?;
+    fs::write(&tfvars_path, rendered.tfvars).map_err(|e| e.to_string())?;
 
     Ok(deployment_dir.to_string_lossy().to_string())
 }
 
 /// Run a Terraform command (init, apply, destroy, etc.) in a background thread.
+/// `debug_log_level` (e.g. `DEBUG`, `TRACE`) writes a Terraform debug log for
+/// this run; see [`super::debug_logs`]. `targets` limits this run to some
+/// resources or forces their replacement. A destroy refuses to delete
+/// data-bearing resources unless `confirm` covers them (see `preview_rollback`).
 #[tauri::command]
 pub async fn run_terraform_command(
     app: AppHandle,
     deployment_name: String,
     command: String,
     credentials: CloudCredentials,
+    debug_log_level: Option<String>,
+    targets: Option<super::run_settings::RunTargets>,
+    confirm: Option<super::rollback::RollbackConfirmation>,
 ) -> Result<(), String> {
     let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let debug_log_level = debug_log_level.map(|l| l.trim().to_uppercase()).filter(|l| !l.is_empty());
+    if let Some(level) = &debug_log_level {
+        if !super::run_settings::LOG_LEVELS.contains(&level.as_str()) {
+            return Err(format!("Unknown log level '{}'", level));
+        }
+    }
+    let run_args = targets.unwrap_or_default().args(&command)?;
 
     // Check if a Terraform deployment is already in progress
     {
@@ -590,39 +879,100 @@ pub async fn run_terraform_command(
     if !deployment_dir.exists() {
         return Err("Deployment not found. Please save configuration first.".to_string());
     }
-
-    let env_vars = build_env_vars(&credentials);
+    // Held by the background thread until the run finishes
+    let PreparedRun { mut env_vars, run_record, credential_warnings, lock, state } = {
+        let (env, name, dir, command, creds, confirm) = (
+            app.clone(),
+            safe_deployment_name.clone(),
+            deployment_dir.clone(),
+            command.clone(),
+            credentials.clone(),
+            confirm.unwrap_or_default(),
+        );
+        super::run_blocking(move || prepare_terraform_run(&env, &name, &dir, &command, &creds, &confirm)).await?
+    };
+    if let Some(level) = debug_log_level {
+        env_vars.insert("TF_LOG".to_string(), level);
+    }
+    let post_deploy_credentials = (command == "apply").then(|| credentials.clone());
+    let cloud = credentials.cloud.clone();
 
     // Reset deployment status before starting Terraform
     {
         let mut status = DEPLOYMENT_STATUS.lock().map_err(|e| e.to_string())?;
         status.running = true;
-        status.command = Some(format!("terraform {}", command));
-        status.output = String::new();
+        status.command = Some(format!(
+            "{} {}",
+            terraform::deployment_engine(&deployment_dir).binary_name(),
+            command
+        ));
+        status.output = credential_warnings.iter().map(|w| format!("Warning: {}\n", w)).collect();
         status.success = None;
         status.can_rollback = terraform::check_state_exists(&deployment_dir);
+        status.credential_warnings = credential_warnings;
     }
+    // Azure CLI tokens last about an hour, less than a long apply can take
+    let keep_alive = super::credential_refresh::keep_azure_tokens_alive(&credentials, DEPLOYMENT_STATUS.clone());
 
     // Run terraform in background thread
     let status_clone = DEPLOYMENT_STATUS.clone();
     let process_clone = CURRENT_PROCESS.clone();
     let cmd = command.clone();
+    let notify_app = app.clone();
     let dir = deployment_dir.clone();
     let is_apply = cmd == "apply";
 
     std::thread::spawn(move || {
+        let _lock = lock;
+        let _keep_alive = keep_alive;
         let env_vars_for_retry = if is_apply { Some(env_vars.clone()) } else { None };
+        let finish_run = || {
+            let (output, success) = status_clone
+                .lock()
+                .map(|s| (s.output.clone(), s.success == Some(true)))
+                .unwrap_or_default();
+            let record = record_finished_run(&dir, &run_record, success, &output, cloud.as_deref());
+            let outcome = if success {
+                RunOutcome::Succeeded
+            } else if output.contains(CANCELLED_NOTE) {
+                RunOutcome::Cancelled
+            } else {
+                RunOutcome::Failed
+            };
+            let duration = record.finished_at.saturating_sub(record.started_at);
+            state.encrypt();
+            super::run_recovery::clear(&dir);
+            super::notifications::notify_run_finished(&notify_app, &safe_deployment_name, &cmd, outcome, duration);
+            super::telemetry::record_run_finished(&notify_app, &dir, &cmd, outcome, duration);
+        };
+        // Workspace setup requested at save time, logged before the run is marked done
+        let post_deploy = || {
+            if let Some(creds) = &post_deploy_credentials {
+                let result = tauri::async_runtime::block_on(super::post_deploy::run_for_deployment(
+                    &dir,
+                    creds,
+                    state.key(),
+                ));
+                if let Ok(mut s) = status_clone.lock() {
+                    s.output.push_str(&super::post_deploy::format_result(&result));
+                }
+            }
+        };
 
-        match terraform::run_terraform(&cmd, &dir, env_vars) {
+        // Output goes to a log file so the run survives an app restart
+        let log_path = super::run_recovery::log_path(&dir);
+        match terraform::run_terraform_logged(&cmd, &dir, env_vars, &run_args, &log_path) {
             Ok(mut child) => {
+                super::run_recovery::record_start(&dir, child.id(), &run_record);
                 let set_pid = |pid: u32| {
                     if let Ok(mut proc) = process_clone.lock() {
                         *proc = Some(pid);
                     }
                 };
 
-                let success = match terraform::stream_and_wait(
+                let success = match terraform::follow_and_wait(
                     &mut child,
+                    &log_path,
                     status_clone.clone(),
                     &set_pid,
                 ) {
@@ -636,11 +986,16 @@ pub async fn run_terraform_command(
                         if let Ok(mut proc) = process_clone.lock() {
                             *proc = None;
                         }
+                        finish_run();
                         return;
                     }
                 };
 
                 if success {
+                    if cmd == "destroy" {
+                        super::expiry::clear_expiry(&dir);
+                    }
+                    post_deploy();
                     if let Ok(mut s) = status_clone.lock() {
                         s.running = false;
                         s.success = Some(true);
@@ -650,9 +1005,15 @@ pub async fn run_terraform_command(
                     let (ok, can_rollback) = terraform::import_and_retry_apply(
                         &dir,
                         &retry_env,
+                        &run_args,
+                        &log_path,
                         status_clone.clone(),
                         process_clone.clone(),
+                        &|pid| super::run_recovery::record_start(&dir, pid, &run_record),
                     );
+                    if ok {
+                        post_deploy();
+                    }
                     if let Ok(mut s) = status_clone.lock() {
                         s.running = false;
                         s.success = Some(ok);
@@ -678,11 +1039,114 @@ pub async fn run_terraform_command(
                 }
             }
         }
+        finish_run();
     });
 
     Ok(())
 }
 
+/// A Terraform run whose checks have passed, ready to start.
+pub(crate) struct PreparedRun {
+    pub env_vars: HashMap<String, String>,
+    pub run_record: super::audit::RunRecord,
+    /// Logins that may expire before the run ends.
+    pub credential_warnings: Vec<String>,
+    pub lock: super::deployment_lock::DeploymentLock,
+    pub state: super::state_encryption::DecryptedState,
+}
+
+/// Checks and setup shared by the app and the headless CLI before running
+/// `command`: the command is allowed for this deployment, logins are fresh,
+/// an apply targets the account the deployment was created in, and a destroy
+/// only deletes data-bearing resources `confirmation` covers. Then takes the
+/// deployment lock, decrypts and backs up the state, and starts the audit
+/// record. Blocking; call from async code through `run_blocking`.
+pub(crate) fn prepare_terraform_run(
+    env: &dyn Environment,
+    deployment_name: &str,
+    deployment_dir: &std::path::Path,
+    command: &str,
+    credentials: &CloudCredentials,
+    confirmation: &super::rollback::RollbackConfirmation,
+) -> Result<PreparedRun, String> {
+    super::run_settings::ensure_command_allowed(env, deployment_dir, command)?;
+    let secret_env = super::secret_vars::tf_var_env(env, deployment_dir)?;
+
+    // Refresh SSO/OAuth logins up front and warn if one won't outlast the run
+    let freshness = super::credential_refresh::check_credentials(command, credentials);
+    if let Some(e) = super::credential_refresh::azure_identity_error(credentials, &freshness) {
+        return Err(e);
+    }
+    let credential_warnings = freshness.into_iter().filter_map(|f| f.warning).collect();
+
+    if command == "apply" {
+        let report = super::credential_context::verify_deployment_context(env, deployment_dir, credentials);
+        if let Some(e) = super::credential_context::mismatch_error(&report) {
+            return Err(e);
+        }
+    }
+
+    let lock = super::deployment_lock::acquire(env, deployment_name, command)?;
+    if command == "destroy" {
+        super::rollback::check_confirmation(env, deployment_dir, confirmation)?;
+    }
+    super::recent_items::record_quietly(
+        env,
+        super::recent_items::ItemKind::Deployment,
+        deployment_name,
+        Some(command),
+    );
+    let state = super::state_encryption::prepare_run(env, deployment_dir)?;
+    if command == "apply" || command == "destroy" {
+        super::state_backups::backup_state(deployment_dir, command)?;
+    }
+
+    let mut env_vars = build_env_vars(credentials);
+    env_vars.extend(secret_env);
+    // Who is applying or destroying, for the audit trail
+    let resolved_identities = if command == "apply" || command == "destroy" {
+        super::credential_context::resolve_identities(deployment_dir, credentials)
+    } else {
+        Vec::new()
+    };
+    let run_record = super::audit::RunRecord {
+        command: command.to_string(),
+        engine: terraform::deployment_engine(deployment_dir).binary_name().to_string(),
+        started_at: super::audit::now_secs(),
+        finished_at: 0,
+        success: false,
+        identities: super::audit::identities_used(credentials),
+        resolved_identities,
+        plan_summary: None,
+        result_summary: None,
+    };
+
+    Ok(PreparedRun { env_vars, run_record, credential_warnings, lock, state })
+}
+
+/// Write the audit record of a finished run. A successful apply also
+/// records who applied it and the environment it ran in. Returns the
+/// completed record.
+pub(crate) fn record_finished_run(
+    deployment_dir: &std::path::Path,
+    run_record: &super::audit::RunRecord,
+    success: bool,
+    output: &str,
+    cloud: Option<&str>,
+) -> super::audit::RunRecord {
+    let record = super::audit::RunRecord {
+        finished_at: super::audit::now_secs(),
+        success,
+        ..run_record.clone()
+    };
+    if success && record.command == "apply" {
+        super::audit::record_applied_by(deployment_dir, &record.resolved_identities);
+        super::environment_snapshot::record_apply_environment(deployment_dir, cloud);
+    }
+    super::audit::record_run(deployment_dir, record.clone(), output);
+    record
+}
+
 /// Get current deployment status.
 #[tauri::command]
 pub fn get_deployment_status() -> Result<DeploymentStatus, String> {
@@ -698,7 +1162,11 @@ pub fn reset_deployment_status() -> Result<(), String> {
     Ok(())
 }
 
-/// Cancel a running deployment by killing the Terraform process.
+/// Appended to the run output by [`cancel_deployment`].
+const CANCELLED_NOTE: &str = "\n\nDeployment cancelled by user.";
+
+/// Cancel a running deployment by stopping the Terraform process and its
+/// provider plugins.
 #[tauri::command]
 pub fn cancel_deployment() -> Result<(), String> {
     let proc_id = {
@@ -707,40 +1175,40 @@ pub fn cancel_deployment() -> Result<(), String> {
     };
 
     if let Some(pid) = proc_id {
-        #[cfg(unix)]
-        {
-            super::silent_cmd("kill")
-                .args(["-TERM", &pid.to_string()])
-                .output()
-                .map_err(|e| e.to_string())?;
-        }
-
-        #[cfg(windows)]
-        {
-            super::silent_cmd("taskkill")
-                .args(["/F", "/PID", &pid.to_string()])
-                .output()
-                .map_err(|e| e.to_string())?;
-        }
+        terraform::kill_process_tree(pid)?;
 
         if let Ok(mut status) = DEPLOYMENT_STATUS.lock() {
             status.running = false;
             status.success = Some(false);
-            status.output.push_str("\n\nDeployment cancelled by user.");
+            status.output.push_str(CANCELLED_NOTE);
         }
     }
 
     Ok(())
 }
 
-/// Rollback a deployment (runs `terraform destroy`).
+/// Rollback a deployment (runs `terraform destroy`). Resources that hold data
+/// (see `preview_rollback`) are only destroyed when `confirm` allows it. With
+/// `keep_data` they are taken out of the state first and left in place.
 #[tauri::command]
 pub async fn rollback_deployment(
     app: AppHandle,
     deployment_name: String,
     credentials: CloudCredentials,
+    confirm: Option<super::rollback::RollbackConfirmation>,
+    keep_data: Option<bool>,
 ) -> Result<(), String> {
-    run_terraform_command(app, deployment_name, "destroy".to_string(), credentials).await
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    super::run_settings::ensure_command_allowed(&app, &deployment_dir, "destroy")?;
+    // Keep-data takes every data-bearing resource out of the state, so there
+    // is nothing left to confirm.
+    if keep_data.unwrap_or(false) {
+        let _preserved =
+            super::rollback::preserve_data_resources(&app, &safe_deployment_name, &deployment_dir, &credentials).await?;
+        debug_log!("[rollback] Keeping {} data resources of {}", _preserved.len(), safe_deployment_name);
+    }
+    run_terraform_command(app, deployment_name, "destroy".to_string(), credentials, None, None, confirm).await
 }
 
 /// Read cloud credentials from environment / CLI config.
@@ -1275,6 +1743,38 @@ mod tests {
         assert!(!env.contains_key("DATABRICKS_CLIENT_ID"));
     }
 
+    #[test]
+    fn build_env_vars_azure_identity_uses_cli_auth() {
+        let creds = CloudCredentials {
+            cloud: Some("azure".to_string()),
+            azure_tenant_id: Some("tid".to_string()),
+            azure_databricks_use_identity: Some(true),
+            databricks_auth_type: Some("profile".to_string()),
+            databricks_profile: Some("my-profile".to_string()),
+            ..Default::default()
+        };
+        let env = build_env_vars(&creds);
+        assert_eq!(env.get("DATABRICKS_AUTH_TYPE"), Some(&"azure-cli".to_string()));
+        assert_eq!(env.get("DATABRICKS_CONFIG_PROFILE"), Some(&String::new()));
+        assert_eq!(env.get("DATABRICKS_TOKEN"), Some(&String::new()));
+        assert_eq!(env.get("ARM_TENANT_ID"), Some(&"tid".to_string()));
+    }
+
+    #[test]
+    fn build_env_vars_only_applies_selected_cloud() {
+        let creds = CloudCredentials {
+            cloud: Some("aws".to_string()),
+            aws_profile: Some("my-profile".to_string()),
+            azure_client_secret: Some("csec".to_string()),
+            gcp_credentials_json: Some("{}".to_string()),
+            ..Default::default()
+        };
+        let env = build_env_vars(&creds);
+        assert_eq!(env.get("AWS_PROFILE"), Some(&"my-profile".to_string()));
+        assert!(!env.contains_key("ARM_CLIENT_SECRET"));
+        assert!(!env.contains_key("GOOGLE_CREDENTIALS"));
+    }
+
     #[test]
     fn build_env_vars_aws_session_token_optional() {
         let creds = CloudCredentials {
@@ -0,0 +1,235 @@
+//! Side-by-side comparison of two deployments.
+//!
+//! [`compare_deployments`] lines up the template each deployment was created
+//! from, its `terraform.tfvars` values and its state outputs, and returns only
+//! what differs, e.g. to find why prod behaves differently from dev. Secret
+//! values are compared but never returned: a differing secret is reported as
+//! changed with both sides redacted.
+
+use super::audit::{self, REDACTED};
+use super::{get_deployments_dir, sanitize_deployment_name};
+use crate::terraform;
+use serde::Serialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Template and engine details of one side of a comparison.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeploymentSide {
+    pub name: String,
+    pub template_id: Option<String>,
+    pub templates_version: Option<String>,
+    pub engine: String,
+    pub last_applied_at: Option<u64>,
+}
+
+/// A variable or output that differs. A side is `None` when it isn't set there.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueDifference {
+    pub name: String,
+    pub left: Option<serde_json::Value>,
+    pub right: Option<serde_json::Value>,
+    /// Secret on at least one side; set values show as redacted.
+    pub redacted: bool,
+}
+
+/// Result of [`compare_deployments`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentComparison {
+    pub left: DeploymentSide,
+    pub right: DeploymentSide,
+    pub same_template: bool,
+    pub same_templates_version: bool,
+    pub variables: Vec<ValueDifference>,
+    pub outputs: Vec<ValueDifference>,
+    /// Variables and outputs set to the same value on both sides.
+    pub unchanged_variables: usize,
+    pub unchanged_outputs: usize,
+}
+
+/// A named value and whether it is secret.
+type Entry = (String, serde_json::Value, bool);
+
+fn side(name: &str, deployment_dir: &Path) -> DeploymentSide {
+    let meta = audit::read_meta(deployment_dir);
+    DeploymentSide {
+        name: name.to_string(),
+        template_id: meta.as_ref().map(|m| m.template_id.clone()),
+        templates_version: meta.as_ref().map(|m| m.templates_version.clone()),
+        engine: terraform::deployment_engine(deployment_dir).binary_name().to_string(),
+        last_applied_at: meta.and_then(|m| m.last_applied_at),
+    }
+}
+
+fn variable_entries(deployment_dir: &Path) -> Vec<Entry> {
+    audit::tfvars_entries(deployment_dir)
+        .into_iter()
+        .map(|(name, value, secret)| (name, serde_json::Value::String(value), secret))
+        .collect()
+}
+
+/// State outputs; `key` decrypts state encrypted at rest.
+fn output_entries(deployment_dir: &Path, key: Option<&[u8; 32]>) -> Vec<Entry> {
+    let state: serde_json::Value = super::state_encryption::read_state(deployment_dir, key)
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or(serde_json::Value::Null);
+    let Some(outputs) = state["outputs"].as_object() else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .map(|(name, output)| {
+            (name.clone(), output["value"].clone(), output["sensitive"].as_bool().unwrap_or(false))
+        })
+        .collect()
+}
+
+/// Entries that differ, in `left` order followed by names only on the right,
+/// and the number that are equal.
+fn diff_entries(left: Vec<Entry>, right: Vec<Entry>) -> (Vec<ValueDifference>, usize) {
+    let redact = |value: Option<serde_json::Value>, secret: bool| {
+        value.map(|v| if secret { serde_json::json!(REDACTED) } else { v })
+    };
+    let mut differences = Vec::new();
+    let mut unchanged = 0;
+    let mut right_only: Vec<Entry> = right.clone();
+
+    for (name, left_value, left_secret) in left {
+        let matching = right.iter().find(|(n, _, _)| *n == name);
+        right_only.retain(|(n, _, _)| *n != name);
+        let (right_value, right_secret) = match matching {
+            Some((_, value, secret)) => (Some(value.clone()), *secret),
+            None => (None, false),
+        };
+        if right_value.as_ref() == Some(&left_value) {
+            unchanged += 1;
+            continue;
+        }
+        let secret = left_secret || right_secret;
+        differences.push(ValueDifference {
+            name,
+            left: redact(Some(left_value), secret),
+            right: redact(right_value, secret),
+            redacted: secret,
+        });
+    }
+    differences.extend(right_only.into_iter().map(|(name, value, secret)| ValueDifference {
+        name,
+        left: None,
+        right: redact(Some(value), secret),
+        redacted: secret,
+    }));
+    (differences, unchanged)
+}
+
+fn compare(
+    (left_name, left_dir): (&str, &Path),
+    (right_name, right_dir): (&str, &Path),
+    key: Option<&[u8; 32]>,
+) -> DeploymentComparison {
+    let (left, right) = (side(left_name, left_dir), side(right_name, right_dir));
+    let (variables, unchanged_variables) = diff_entries(variable_entries(left_dir), variable_entries(right_dir));
+    let (outputs, unchanged_outputs) = diff_entries(output_entries(left_dir, key), output_entries(right_dir, key));
+    DeploymentComparison {
+        same_template: left.template_id == right.template_id,
+        same_templates_version: left.templates_version == right.templates_version,
+        left,
+        right,
+        variables,
+        outputs,
+        unchanged_variables,
+        unchanged_outputs,
+    }
+}
+
+/// Compare the template, `terraform.tfvars` values and state outputs of two
+/// deployments. Only differences are listed; secrets are redacted.
+#[tauri::command]
+pub fn compare_deployments(
+    app: AppHandle,
+    left_deployment: String,
+    right_deployment: String,
+) -> Result<DeploymentComparison, String> {
+    let deployments_dir = get_deployments_dir(&app)?;
+    let mut dirs = Vec::new();
+    for name in [&left_deployment, &right_deployment] {
+        let safe_name = sanitize_deployment_name(name)?;
+        let dir = deployments_dir.join(&safe_name);
+        if !dir.is_dir() {
+            return Err(format!("Deployment not found: {}", safe_name));
+        }
+        dirs.push((safe_name, dir));
+    }
+
+    let key = if dirs.iter().any(|(_, dir)| super::state_encryption::is_enabled(dir)) {
+        Some(super::state_encryption::load_key(&app)?)
+    } else {
+        None
+    };
+    let ((left_name, left_dir), (right_name, right_dir)) = (&dirs[0], &dirs[1]);
+    Ok(compare(
+        (left_name.as_str(), left_dir.as_path()),
+        (right_name.as_str(), right_dir.as_path()),
+        key.as_ref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn entry(name: &str, value: serde_json::Value, secret: bool) -> Entry {
+        (name.to_string(), value, secret)
+    }
+
+    #[test]
+    fn lists_changed_added_and_removed_values() {
+        let left = vec![
+            entry("region", json!("us-east-1"), false),
+            entry("prefix", json!("dev"), false),
+            entry("client_secret", json!("abc"), true),
+            entry("only_left", json!("x"), false),
+        ];
+        let right = vec![
+            entry("only_right", json!(true), false),
+            entry("region", json!("us-east-1"), false),
+            entry("prefix", json!("prod"), false),
+            entry("client_secret", json!("xyz"), true),
+        ];
+        let (differences, unchanged) = diff_entries(left, right);
+        assert_eq!(unchanged, 1);
+        let names: Vec<&str> = differences.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["prefix", "client_secret", "only_left", "only_right"]);
+        assert_eq!(differences[1].left, Some(json!(REDACTED)));
+        assert_eq!(differences[1].right, Some(json!(REDACTED)));
+        assert_eq!(differences[2].right, None);
+    }
+
+    #[test]
+    fn compares_templates_variables_and_outputs() {
+        let dev = tempfile::tempdir().unwrap();
+        let prod = tempfile::tempdir().unwrap();
+        for (dir, prefix, url) in [(&dev, "dev", "https://dev.cloud.databricks.com"), (&prod, "prod", "https://prod.cloud.databricks.com")] {
+            fs::write(dir.path().join("terraform.tfvars"), format!("prefix = \"{}\"\nregion = \"us-east-1\"\n", prefix)).unwrap();
+            let state = json!({"outputs": {
+                "workspace_url": {"value": url, "sensitive": false},
+                "metastore_id": {"value": "m-1", "sensitive": false},
+            }});
+            fs::write(dir.path().join("terraform.tfstate"), state.to_string()).unwrap();
+        }
+        audit::record_template(dev.path(), "aws-simple").unwrap();
+        audit::record_template(prod.path(), "aws-sra").unwrap();
+
+        let comparison = compare(("dev", dev.path()), ("prod", prod.path()), None);
+        assert!(!comparison.same_template && comparison.same_templates_version);
+        assert_eq!(comparison.right.template_id.as_deref(), Some("aws-sra"));
+        assert_eq!(comparison.variables.len(), 1);
+        assert_eq!(comparison.variables[0].right, Some(json!("\"prod\"")));
+        assert_eq!(comparison.unchanged_variables, 1);
+        assert_eq!(comparison.outputs.len(), 1);
+        assert_eq!(comparison.outputs[0].name, "workspace_url");
+        assert_eq!(comparison.unchanged_outputs, 1);
+    }
+}
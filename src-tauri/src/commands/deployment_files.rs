@@ -0,0 +1,310 @@
+//! Read-only view of a deployment's files, and opening them in an editor.
+//!
+//! [`list_deployment_files`] walks the deployment directory (skipping the
+//! provider cache, git metadata, run logs and stored secret values) and
+//! [`read_deployment_file`] returns one file's text for the built-in viewer. Values of secret variables in tfvars
+//! files are redacted, Terraform state is never returned because it holds
+//! every secret in plain text, and large files are cut at [`MAX_VIEW_BYTES`].
+//! [`open_in_editor`] hands a file to the system's default editor.
+
+use super::audit::{is_secret_name, REDACTED};
+use super::{get_deployments_dir, sanitize_deployment_name};
+use crate::terraform;
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::AppHandle;
+
+/// Largest part of a file returned to the viewer.
+const MAX_VIEW_BYTES: usize = 512 * 1024;
+
+/// Directories not listed: provider binaries, git metadata, and run and
+/// debug logs, which can echo secret values.
+const SKIPPED_DIRS: &[&str] = &[".terraform", ".git", super::debug_logs::RUNS_DIR];
+
+/// Files not listed: sensitive variable values kept out of tfvars.
+const SKIPPED_FILES: &[&str] = &[super::secret_vars::SECRETS_FILE];
+
+/// A file in a deployment directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentFile {
+    /// Path relative to the deployment directory, `/`-separated.
+    pub path: String,
+    pub size: u64,
+    /// Unix timestamp (seconds) of the last modification.
+    pub modified: Option<u64>,
+    /// Whether [`read_deployment_file`] will show it.
+    pub viewable: bool,
+}
+
+/// File contents for the viewer.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentFileContent {
+    pub path: String,
+    pub content: String,
+    pub size: u64,
+    /// The content stops at [`MAX_VIEW_BYTES`].
+    pub truncated: bool,
+    /// Variables whose values were replaced with [`REDACTED`].
+    pub redacted: Vec<String>,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn is_state_file(name: &str) -> bool {
+    name.ends_with(".tfstate") || name.contains(".tfstate.")
+}
+
+fn is_tfvars_file(name: &str) -> bool {
+    name.ends_with(".tfvars") || name == "terraform.tfvars.example"
+}
+
+/// Whether a relative path is inside a skipped directory or is a skipped file.
+fn is_skipped(rel: &Path) -> bool {
+    let names: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    let in_skipped_dir = names.iter().rev().skip(1).any(|n| SKIPPED_DIRS.contains(&n.as_str()));
+    in_skipped_dir || names.last().is_some_and(|n| SKIPPED_FILES.contains(&n.as_str()))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<DeploymentFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) {
+                collect_files(root, &path, files);
+            }
+        } else if metadata.is_file() && !SKIPPED_FILES.contains(&name.as_str()) {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            files.push(DeploymentFile {
+                path: rel.to_string_lossy().replace('\\', "/"),
+                size: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+                viewable: !is_state_file(&name),
+            });
+        }
+    }
+}
+
+/// Resolve a relative path inside the deployment directory to an existing
+/// file, refusing anything that would leave it or that isn't listed.
+fn resolve_file(deployment_dir: &Path, rel_path: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(rel_path);
+    if rel_path.is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err("Invalid file path".to_string());
+    }
+    let path = deployment_dir.join(rel);
+    let canonical = path.canonicalize().map_err(|_| format!("File not found: {}", rel_path))?;
+    let root = deployment_dir.canonicalize().map_err(|_| "Deployment not found".to_string())?;
+    if !canonical.starts_with(&root) || !canonical.is_file() {
+        return Err(format!("File not found: {}", rel_path));
+    }
+    // Checked on the resolved path too, so a symlink can't point into them
+    if is_skipped(rel) || is_skipped(canonical.strip_prefix(&root).unwrap_or(&canonical)) {
+        return Err(format!("{} is not shown because it can contain secrets", rel_path));
+    }
+    Ok(canonical)
+}
+
+/// Replace the values of secret variables in a tfvars file, keeping the
+/// layout. Multi-line values are collapsed to the placeholder.
+fn redact_tfvars(content: &str, sensitive: &[String]) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(content.len());
+    let mut redacted = Vec::new();
+    let mut depth: i32 = 0;
+    let mut skipping = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if depth > 0 {
+            depth += trimmed.matches(['[', '{']).count() as i32 - trimmed.matches([']', '}']).count() as i32;
+            if !skipping {
+                out.push_str(line);
+                out.push('\n');
+            }
+            continue;
+        }
+        skipping = false;
+        match trimmed.split_once('=') {
+            Some((name, value)) if !trimmed.starts_with('#') && !trimmed.starts_with("//") => {
+                let name = name.trim().trim_matches('"');
+                depth = value.matches(['[', '{']).count() as i32 - value.matches([']', '}']).count() as i32;
+                if sensitive.iter().any(|s| s == name) || is_secret_name(name) {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    out.push_str(&format!("{}{} = \"{}\"\n", indent, name, REDACTED));
+                    redacted.push(name.to_string());
+                    skipping = true;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    (out, redacted)
+}
+
+fn read_file(deployment_dir: &Path, rel_path: &str) -> Result<DeploymentFileContent, String> {
+    let path = resolve_file(deployment_dir, rel_path)?;
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if is_state_file(&name) {
+        return Err("Terraform state is not shown because it contains secrets in plain text".to_string());
+    }
+
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", rel_path, e))?;
+    let size = bytes.len() as u64;
+    let truncated = bytes.len() > MAX_VIEW_BYTES;
+    let shown = &bytes[..bytes.len().min(MAX_VIEW_BYTES)];
+    if shown.contains(&0) {
+        return Err(format!("{} is a binary file", rel_path));
+    }
+    let mut content = String::from_utf8_lossy(shown).to_string();
+
+    let mut redacted = Vec::new();
+    if is_tfvars_file(&name) {
+        let variables = fs::read_to_string(deployment_dir.join("variables.tf")).unwrap_or_default();
+        let sensitive: Vec<String> = terraform::parse_variables_tf(&variables)?
+            .into_iter()
+            .filter(|v| v.sensitive)
+            .map(|v| v.name)
+            .collect();
+        (content, redacted) = redact_tfvars(&content, &sensitive);
+    }
+
+    Ok(DeploymentFileContent { path: rel_path.to_string(), content, size, truncated, redacted })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List the files of a deployment with size and modification time.
+#[tauri::command]
+pub fn list_deployment_files(app: AppHandle, deployment_name: String) -> Result<Vec<DeploymentFile>, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.is_dir() {
+        return Err("Deployment not found".to_string());
+    }
+    let mut files = Vec::new();
+    collect_files(&deployment_dir, &deployment_dir, &mut files);
+    Ok(files)
+}
+
+/// Contents of one deployment file for the built-in viewer.
+#[tauri::command]
+pub fn read_deployment_file(app: AppHandle, deployment_name: String, path: String) -> Result<DeploymentFileContent, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    read_file(&get_deployments_dir(&app)?.join(&safe_name), &path)
+}
+
+/// Open a deployment file in the system's default editor.
+#[tauri::command]
+pub fn open_in_editor(app: AppHandle, deployment_name: String, path: String) -> Result<(), String> {
+    use std::process::Command;
+
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let file = resolve_file(&get_deployments_dir(&app)?.join(&safe_name), &path)?;
+
+    #[cfg(target_os = "macos")]
+    {
+        // `-t` picks the default text editor rather than the app registered for `.tf`
+        Command::new("open")
+            .arg("-t")
+            .arg(&file)
+            .spawn()
+            .map_err(|e| format!("Failed to open editor: {}", e))?;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("cmd")
+            .args(["/C", "start", ""])
+            .arg(&file)
+            .spawn()
+            .map_err(|e| format!("Failed to open editor: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Command::new("xdg-open")
+            .arg(&file)
+            .spawn()
+            .map_err(|e| format!("Failed to open editor: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_files_and_refuses_paths_outside() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.tf"), "resource {}\n").unwrap();
+        fs::write(dir.path().join("terraform.tfstate"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join(".terraform/providers")).unwrap();
+        fs::write(dir.path().join(".terraform/providers/binary"), [0u8; 4]).unwrap();
+        fs::create_dir_all(dir.path().join("modules/net")).unwrap();
+        fs::write(dir.path().join("modules/net/main.tf"), "").unwrap();
+        fs::create_dir_all(dir.path().join(".deployer-runs")).unwrap();
+        fs::write(dir.path().join(".deployer-runs/apply.log"), "TF_VAR_password=x").unwrap();
+        fs::write(dir.path().join(".deployer-secrets.json"), "{}").unwrap();
+
+        let mut files = Vec::new();
+        collect_files(dir.path(), dir.path(), &mut files);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["main.tf", "modules/net/main.tf", "terraform.tfstate"]);
+        assert!(!files[2].viewable);
+
+        assert!(resolve_file(dir.path(), "../main.tf").is_err());
+        assert!(resolve_file(dir.path(), "/etc/passwd").is_err());
+        assert!(resolve_file(dir.path(), "modules").is_err());
+        assert!(read_file(dir.path(), "terraform.tfstate").unwrap_err().contains("state"));
+        assert!(read_file(dir.path(), ".deployer-runs/apply.log").unwrap_err().contains("secrets"));
+        assert!(read_file(dir.path(), ".deployer-secrets.json").unwrap_err().contains("secrets"));
+        assert_eq!(read_file(dir.path(), "modules/net/main.tf").unwrap().content, "");
+    }
+
+    #[test]
+    fn tfvars_secrets_are_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("variables.tf"), "variable \"admin_user\" {\n  sensitive = true\n}\n").unwrap();
+        fs::write(
+            dir.path().join("terraform.tfvars"),
+            "prefix = \"demo\"\nadmin_user = \"jane@example.com\"\nclient_secret = {\n  value = \"x\"\n}\ntags = {\n  Owner = \"a\"\n}\n",
+        )
+        .unwrap();
+
+        let file = read_file(dir.path(), "terraform.tfvars").unwrap();
+        assert_eq!(file.redacted, vec!["admin_user", "client_secret"]);
+        assert_eq!(
+            file.content,
+            "prefix = \"demo\"\nadmin_user = \"(redacted)\"\nclient_secret = \"(redacted)\"\ntags = {\n  Owner = \"a\"\n}\n"
+        );
+        assert!(!file.truncated);
+    }
+
+    #[test]
+    fn large_and_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("big.log"), "a".repeat(MAX_VIEW_BYTES + 10)).unwrap();
+        fs::write(dir.path().join("blob.bin"), [1u8, 0, 2]).unwrap();
+
+        let big = read_file(dir.path(), "big.log").unwrap();
+        assert!(big.truncated);
+        assert_eq!((big.content.len(), big.size), (MAX_VIEW_BYTES, MAX_VIEW_BYTES as u64 + 10));
+        assert!(read_file(dir.path(), "blob.bin").unwrap_err().contains("binary"));
+    }
+}
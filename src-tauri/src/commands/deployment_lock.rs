@@ -0,0 +1,274 @@
+//! Per-deployment lock files.
+//!
+//! Saving a configuration and running Terraform both rewrite files in the
+//! deployment directory, and the desktop app, the headless CLI and background
+//! threads (the expiry scheduler) can all touch the same deployment. Each of
+//! these holds a lock file in `<data dir>/locks/<deployment>.lock` recording
+//! the owning PID and a heartbeat refreshed while the operation runs. A lock
+//! whose process is gone, or whose heartbeat stopped, is stale and taken over.
+
+use super::{debug_log, sanitize_deployment_name};
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// How often a held lock's heartbeat is refreshed.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A heartbeat older than this marks the lock stale even if the PID is alive
+/// (the PID may have been reused after a crash or reboot).
+const STALE_AFTER_SECS: u64 = 120;
+
+/// Contents of a lock file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockInfo {
+    /// Random token identifying the holder, so only it removes the file.
+    pub id: String,
+    pub pid: u32,
+    /// What holds the lock ("save", "apply", "destroy", ...).
+    pub operation: String,
+    /// Unix timestamps (seconds).
+    pub acquired_at: u64,
+    pub heartbeat_at: u64,
+}
+
+/// A held lock. Released (file removed) when dropped.
+pub(crate) struct DeploymentLock {
+    path: PathBuf,
+    id: String,
+    stop: Arc<AtomicBool>,
+    heartbeat: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Drop for DeploymentLock {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.heartbeat.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+        if read_lock(&self.path).is_some_and(|info| info.id == self.id) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn lock_path(env: &dyn Environment, deployment_name: &str) -> Result<PathBuf, String> {
+    Ok(env.locks_dir()?.join(format!("{}.lock", sanitize_deployment_name(deployment_name)?)))
+}
+
+/// Whether `pid` is a running process. Errs on the side of "alive" when the
+/// check itself can't run, so a lock is never stolen from a live holder.
+pub(crate) fn process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        super::silent_cmd("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(true)
+    }
+    #[cfg(windows)]
+    {
+        super::silent_cmd("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+            .unwrap_or(true)
+    }
+}
+
+/// Read a lock file. A file that exists but can't be parsed (a holder that
+/// crashed mid-write, or is writing right now) is reported with its
+/// modification time as the heartbeat and no PID.
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok().or_else(|| {
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        Some(LockInfo {
+            id: String::new(),
+            pid: 0,
+            operation: "unknown".to_string(),
+            acquired_at: modified,
+            heartbeat_at: modified,
+        })
+    })
+}
+
+fn is_stale(info: &LockInfo, now: u64) -> bool {
+    now.saturating_sub(info.heartbeat_at) > STALE_AFTER_SECS || (info.pid != 0 && !process_alive(info.pid))
+}
+
+fn write_lock(path: &Path, info: &LockInfo) -> Result<(), String> {
+    let json = serde_json::to_string(info).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write lock file: {}", e))
+}
+
+fn locked_error(info: &LockInfo) -> String {
+    format!(
+        "Deployment is busy: {} is already running (PID {}). Wait for it to finish and try again.",
+        info.operation, info.pid
+    )
+}
+
+fn heartbeat(path: PathBuf, mut info: LockInfo, stop: Arc<AtomicBool>) {
+    loop {
+        std::thread::park_timeout(HEARTBEAT_INTERVAL);
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        // Stop refreshing if another process took the lock over
+        if read_lock(&path).is_none_or(|current| current.id != info.id) {
+            debug_log!("[deployment_lock] lost lock {}", path.display());
+            return;
+        }
+        info.heartbeat_at = super::audit::now_secs();
+        let _ = write_lock(&path, &info);
+    }
+}
+
+/// Create the lock file at `path` for `operation`, taking over a stale one.
+fn acquire_at(path: PathBuf, operation: &str) -> Result<DeploymentLock, String> {
+    // Second attempt only after removing a stale lock
+    for _ in 0..2 {
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                let now = super::audit::now_secs();
+                let info = LockInfo {
+                    id: format!("{:016x}", rand::random::<u64>()),
+                    pid: std::process::id(),
+                    operation: operation.to_string(),
+                    acquired_at: now,
+                    heartbeat_at: now,
+                };
+                let json = serde_json::to_string(&info).map_err(|e| e.to_string())?;
+                if let Err(e) = file.write_all(json.as_bytes()) {
+                    let _ = fs::remove_file(&path);
+                    return Err(format!("Failed to write lock file: {}", e));
+                }
+                let stop = Arc::new(AtomicBool::new(false));
+                let handle = {
+                    let (path, info, stop) = (path.clone(), info.clone(), stop.clone());
+                    std::thread::spawn(move || heartbeat(path, info, stop))
+                };
+                return Ok(DeploymentLock { path, id: info.id, stop, heartbeat: Some(handle) });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let Some(existing) = read_lock(&path) else { continue };
+                if !is_stale(&existing, super::audit::now_secs()) {
+                    return Err(locked_error(&existing));
+                }
+                debug_log!("[deployment_lock] taking over stale lock {} (PID {})", path.display(), existing.pid);
+                // Re-check right before removing so a lock created meanwhile survives
+                if read_lock(&path).is_some_and(|current| current.id == existing.id) {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+            Err(e) => return Err(format!("Failed to create lock file: {}", e)),
+        }
+    }
+    Err("Deployment is busy in another process. Try again.".to_string())
+}
+
+/// Lock a deployment for `operation` until the returned guard is dropped.
+/// Fails if another live process or thread holds the lock.
+pub(crate) fn acquire(env: &dyn Environment, deployment_name: &str, operation: &str) -> Result<DeploymentLock, String> {
+    acquire_at(lock_path(env, deployment_name)?, operation)
+}
+
+/// Current holder of a deployment's lock, ignoring stale locks.
+pub(crate) fn current_lock(env: &dyn Environment, deployment_name: &str) -> Result<Option<LockInfo>, String> {
+    let path = lock_path(env, deployment_name)?;
+    Ok(read_lock(&path).filter(|info| !is_stale(info, super::audit::now_secs())))
+}
+
+/// Who, if anyone, is currently saving or running Terraform on a deployment.
+#[tauri::command]
+pub fn get_deployment_lock(app: AppHandle, deployment_name: String) -> Result<Option<LockInfo>, String> {
+    current_lock(&app, &deployment_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new(if cfg!(windows) { "cmd" } else { "true" })
+            .args(if cfg!(windows) { vec!["/C", "exit"] } else { vec![] })
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+
+        let lock = acquire(&paths, "demo", "apply").unwrap();
+        let err = acquire(&paths, "demo", "save").err().unwrap();
+        assert!(err.contains("apply"), "{}", err);
+        assert_eq!(current_lock(&paths, "demo").unwrap().unwrap().operation, "apply");
+        // Other deployments are independent
+        drop(acquire(&paths, "other", "save").unwrap());
+
+        drop(lock);
+        assert!(!dir.path().join("locks/demo.lock").exists());
+        assert!(current_lock(&paths, "demo").unwrap().is_none());
+        drop(acquire(&paths, "demo", "save").unwrap());
+    }
+
+    #[test]
+    fn stale_locks_are_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        let path = lock_path(&paths, "demo").unwrap();
+        let now = super::super::audit::now_secs();
+
+        // Holder process exited
+        let dead = LockInfo {
+            id: "dead".to_string(),
+            pid: dead_pid(),
+            operation: "apply".to_string(),
+            acquired_at: now,
+            heartbeat_at: now,
+        };
+        write_lock(&path, &dead).unwrap();
+        drop(acquire(&paths, "demo", "save").unwrap());
+
+        // Holder alive (this process) but heartbeat stopped long ago
+        let silent = LockInfo { id: "silent".to_string(), pid: std::process::id(), heartbeat_at: now - 600, ..dead };
+        write_lock(&path, &silent).unwrap();
+        let lock = acquire(&paths, "demo", "save").unwrap();
+        assert_eq!(read_lock(&path).unwrap().pid, std::process::id());
+        assert_ne!(read_lock(&path).unwrap().id, "silent");
+        drop(lock);
+    }
+
+    #[test]
+    fn unreadable_fresh_lock_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        fs::write(lock_path(&paths, "demo").unwrap(), "{").unwrap();
+        assert!(acquire(&paths, "demo", "save").is_err());
+        assert_eq!(lock_path(&paths, "../demo").unwrap(), lock_path(&paths, "demo").unwrap());
+    }
+}
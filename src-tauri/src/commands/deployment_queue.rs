@@ -0,0 +1,467 @@
+//! Queue of Terraform runs executed one after another.
+//!
+//! Only one Terraform run can be active at a time, so several operations
+//! (say `plan` then `apply` for three deployments) are queued with
+//! [`enqueue_run`] and started in order by a background worker, each through
+//! [`run_terraform_command`](super::run_terraform_command) as if the user had
+//! clicked it. A run can depend on the one before it and is skipped when that
+//! one didn't succeed. Queued runs can be reordered ([`reorder_queue`]) or
+//! cancelled ([`cancel_queued`]) until they start; cancelling a started run
+//! stops its Terraform process. Queued destroys need the same confirmation
+//! for data-bearing resources as a rollback.
+//!
+//! The queue lives in memory only: the credentials it holds aren't written
+//! to disk, and queued runs don't survive a restart.
+
+use super::rollback::RollbackConfirmation;
+use super::{debug_log, get_deployments_dir, lock_or_recover, sanitize_deployment_name, CloudCredentials};
+use crate::terraform::{CURRENT_PROCESS, DEPLOYMENT_STATUS};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted with the whole queue whenever a run starts or finishes.
+pub const DEPLOYMENT_QUEUE_EVENT: &str = "deployment-queue";
+
+/// Commands that can be queued.
+const QUEUEABLE_COMMANDS: &[&str] = &["init", "plan", "apply", "destroy"];
+
+/// Finished runs kept in the queue for display.
+const MAX_FINISHED: usize = 20;
+
+/// How often the worker checks whether the previous run has released its deployment.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the worker checks whether a run it started has spawned Terraform.
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+lazy_static::lazy_static! {
+    static ref QUEUE: Mutex<RunQueue> = Mutex::new(RunQueue::default());
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static WORKER_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuedRunState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    /// Not started because the run before it didn't succeed.
+    Skipped,
+    Cancelled,
+}
+
+/// A run in the queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedRun {
+    pub id: u64,
+    pub deployment_name: String,
+    pub command: String,
+    /// Only start when the run before it succeeded.
+    pub requires_previous_success: bool,
+    pub state: QueuedRunState,
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    /// Why the run failed or was skipped.
+    pub message: Option<String>,
+    #[serde(skip)]
+    credentials: CloudCredentials,
+    /// Consent to delete data-bearing resources, for destroys.
+    #[serde(skip)]
+    confirmation: RollbackConfirmation,
+    /// Set once the run's Terraform process is the current one, so cancelling
+    /// only stops a process this run owns.
+    #[serde(skip)]
+    started: bool,
+}
+
+/// Finished runs first, then the running one, then the queued ones in the
+/// order they will start.
+#[derive(Debug, Default)]
+struct RunQueue {
+    runs: Vec<QueuedRun>,
+}
+
+impl RunQueue {
+    fn push(&mut self, run: QueuedRun) {
+        self.runs.push(run);
+    }
+
+    /// Put the queued runs in the order of `ids`, which must name each of them once.
+    fn reorder(&mut self, ids: &[u64]) -> Result<(), String> {
+        let queued: Vec<u64> = self.runs.iter().filter(|r| r.state == QueuedRunState::Queued).map(|r| r.id).collect();
+        let mut sorted = ids.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let mut expected = queued.clone();
+        expected.sort_unstable();
+        if sorted.len() != ids.len() || sorted != expected {
+            return Err("The new order must list every queued run exactly once".to_string());
+        }
+
+        let (mut queued_runs, mut runs): (Vec<QueuedRun>, Vec<QueuedRun>) =
+            self.runs.drain(..).partition(|r| r.state == QueuedRunState::Queued);
+        for id in ids {
+            if let Some(i) = queued_runs.iter().position(|r| r.id == *id) {
+                runs.push(queued_runs.remove(i));
+            }
+        }
+        self.runs = runs;
+        Ok(())
+    }
+
+    /// Cancel a run that hasn't finished. Returns whether its Terraform
+    /// process had started and needs stopping.
+    fn cancel(&mut self, id: u64, now: u64) -> Result<bool, String> {
+        let run = self.runs.iter_mut().find(|r| r.id == id).ok_or_else(|| format!("Queued run {} not found", id))?;
+        let started = match run.state {
+            QueuedRunState::Queued => false,
+            QueuedRunState::Running => run.started,
+            _ => return Err("The run has already finished".to_string()),
+        };
+        run.state = QueuedRunState::Cancelled;
+        run.finished_at = Some(now);
+        Ok(started)
+    }
+
+    /// Whether a run picked by [`start_next`](Self::start_next) is still
+    /// meant to run (it wasn't cancelled meanwhile).
+    fn is_running(&self, id: u64) -> bool {
+        self.runs.iter().any(|r| r.id == id && r.state == QueuedRunState::Running)
+    }
+
+    /// Record that the run's Terraform process is now the current one.
+    /// Returns false when the run was cancelled meanwhile.
+    fn mark_started(&mut self, id: u64) -> bool {
+        match self.runs.iter_mut().find(|r| r.id == id && r.state == QueuedRunState::Running) {
+            Some(run) => {
+                run.started = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark the next run that can start as running and return it, skipping
+    /// runs whose previous run didn't succeed. `None` when nothing is queued
+    /// or a run is still in progress.
+    fn start_next(&mut self, now: u64) -> Option<QueuedRun> {
+        if self.runs.iter().any(|r| r.state == QueuedRunState::Running) {
+            return None;
+        }
+        loop {
+            let i = self.runs.iter().position(|r| r.state == QueuedRunState::Queued)?;
+            let previous_ok = i == 0 || self.runs[i - 1].state == QueuedRunState::Succeeded;
+            let run = &mut self.runs[i];
+            if run.requires_previous_success && !previous_ok {
+                run.state = QueuedRunState::Skipped;
+                run.finished_at = Some(now);
+                run.message = Some("Skipped because the previous run did not succeed".to_string());
+                continue;
+            }
+            run.state = QueuedRunState::Running;
+            run.started_at = Some(now);
+            return Some(run.clone());
+        }
+    }
+
+    /// Record the outcome of a run, unless it was cancelled meanwhile.
+    fn finish(&mut self, id: u64, success: bool, message: Option<String>, now: u64) {
+        if let Some(run) = self.runs.iter_mut().find(|r| r.id == id && r.state == QueuedRunState::Running) {
+            run.state = if success { QueuedRunState::Succeeded } else { QueuedRunState::Failed };
+            run.finished_at = Some(now);
+            run.message = message;
+        }
+        self.prune();
+    }
+
+    /// Drop the oldest finished runs beyond [`MAX_FINISHED`].
+    fn prune(&mut self) {
+        let finished = |r: &QueuedRun| !matches!(r.state, QueuedRunState::Queued | QueuedRunState::Running);
+        let excess = self.runs.iter().filter(|r| finished(r)).count().saturating_sub(MAX_FINISHED);
+        let mut dropped = 0;
+        self.runs.retain(|r| {
+            if dropped < excess && finished(r) {
+                dropped += 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn snapshot(&self) -> Vec<QueuedRun> {
+        self.runs.clone()
+    }
+}
+
+// ─── Worker ─────────────────────────────────────────────────────────────────
+
+fn emit_queue(app: &AppHandle) {
+    let _ = app.emit(DEPLOYMENT_QUEUE_EVENT, lock_or_recover(&QUEUE).snapshot());
+}
+
+/// Wait until no run is in progress and `deployment_name` isn't locked, so
+/// a run started outside the queue (or the previous run's cleanup) finishes first.
+fn wait_until_idle(app: &AppHandle, deployment_name: &str) {
+    loop {
+        let running = lock_or_recover(&DEPLOYMENT_STATUS).running;
+        let locked = super::deployment_lock::current_lock(app, deployment_name).ok().flatten().is_some();
+        if !running && !locked {
+            return;
+        }
+        std::thread::sleep(IDLE_POLL_INTERVAL);
+    }
+}
+
+/// Wait until the run just started has spawned Terraform (or already ended).
+fn wait_for_process() {
+    loop {
+        let spawned = lock_or_recover(&CURRENT_PROCESS).is_some();
+        if spawned || !lock_or_recover(&DEPLOYMENT_STATUS).running {
+            return;
+        }
+        std::thread::sleep(PROCESS_POLL_INTERVAL);
+    }
+}
+
+fn execute(app: &AppHandle, run: &QueuedRun) -> (bool, Option<String>) {
+    wait_until_idle(app, &run.deployment_name);
+    if !lock_or_recover(&QUEUE).is_running(run.id) {
+        return (false, None);
+    }
+    // A destroy's confirmation is checked again as it starts: an earlier
+    // queued apply may have added data resources
+    let started = tauri::async_runtime::block_on(super::run_terraform_command(
+        app.clone(),
+        run.deployment_name.clone(),
+        run.command.clone(),
+        run.credentials.clone(),
+        None,
+        None,
+        Some(run.confirmation.clone()),
+    ));
+    if let Err(e) = started {
+        return (false, Some(e));
+    }
+    wait_for_process();
+    if !lock_or_recover(&QUEUE).mark_started(run.id) {
+        // Cancelled while Terraform was starting
+        let _ = super::cancel_deployment();
+    }
+    let success = super::expiry::wait_for_deployment();
+    (success, (!success).then(|| "Terraform failed. Open the deployment to review the output.".to_string()))
+}
+
+/// Start queued runs one at a time until the queue is empty. Only one worker
+/// runs at a time.
+fn run_worker(app: AppHandle) {
+    loop {
+        let next = lock_or_recover(&QUEUE).start_next(super::audit::now_secs());
+        let Some(run) = next else {
+            WORKER_ACTIVE.store(false, Ordering::SeqCst);
+            // A run queued between the check and the store would otherwise wait
+            let pending = lock_or_recover(&QUEUE).runs.iter().any(|r| r.state == QueuedRunState::Queued);
+            if pending && !WORKER_ACTIVE.swap(true, Ordering::SeqCst) {
+                continue;
+            }
+            emit_queue(&app);
+            return;
+        };
+        emit_queue(&app);
+
+        debug_log!("[deployment_queue] Starting {} {} (#{})", run.command, run.deployment_name, run.id);
+        let (success, message) = execute(&app, &run);
+        lock_or_recover(&QUEUE).finish(run.id, success, message, super::audit::now_secs());
+        debug_log!("[deployment_queue] Finished #{} (success: {})", run.id, success);
+        emit_queue(&app);
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Add a Terraform run to the end of the queue and start the queue if it is
+/// idle. With `requires_previous_success`, the run is skipped unless the run
+/// queued before it succeeds. A destroy needs `confirm` to cover the
+/// data-bearing resources in the state, checked now and again when it starts.
+#[tauri::command]
+pub fn enqueue_run(
+    app: AppHandle,
+    deployment_name: String,
+    command: String,
+    credentials: CloudCredentials,
+    requires_previous_success: Option<bool>,
+    confirm: Option<RollbackConfirmation>,
+) -> Result<QueuedRun, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    if !QUEUEABLE_COMMANDS.contains(&command.as_str()) {
+        return Err(format!("Unknown command: {}", command));
+    }
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.is_dir() {
+        return Err("Deployment not found. Please save configuration first.".to_string());
+    }
+    super::run_settings::ensure_command_allowed(&app, &deployment_dir, &command)?;
+    let confirmation = confirm.unwrap_or_default();
+    if command == "destroy" {
+        super::rollback::check_confirmation(&app, &deployment_dir, &confirmation)?;
+    }
+
+    let run = QueuedRun {
+        id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        deployment_name: safe_name,
+        command,
+        requires_previous_success: requires_previous_success.unwrap_or(false),
+        state: QueuedRunState::Queued,
+        queued_at: super::audit::now_secs(),
+        started_at: None,
+        finished_at: None,
+        message: None,
+        credentials,
+        confirmation,
+        started: false,
+    };
+    lock_or_recover(&QUEUE).push(run.clone());
+
+    if !WORKER_ACTIVE.swap(true, Ordering::SeqCst) {
+        let worker_app = app.clone();
+        std::thread::spawn(move || run_worker(worker_app));
+    } else {
+        emit_queue(&app);
+    }
+    Ok(run)
+}
+
+/// Runs in the queue: recently finished ones, the running one, then the
+/// queued ones in start order.
+#[tauri::command]
+pub fn get_queue() -> Vec<QueuedRun> {
+    lock_or_recover(&QUEUE).snapshot()
+}
+
+/// Change the start order of the queued runs. `run_ids` lists every queued run.
+#[tauri::command]
+pub fn reorder_queue(app: AppHandle, run_ids: Vec<u64>) -> Result<Vec<QueuedRun>, String> {
+    let snapshot = {
+        let mut queue = lock_or_recover(&QUEUE);
+        queue.reorder(&run_ids)?;
+        queue.snapshot()
+    };
+    emit_queue(&app);
+    Ok(snapshot)
+}
+
+/// Cancel a queued run. A run whose Terraform process has started is stopped
+/// the same way as `cancel_deployment`; one still waiting for its turn just
+/// never starts.
+#[tauri::command]
+pub fn cancel_queued(app: AppHandle, run_id: u64) -> Result<Vec<QueuedRun>, String> {
+    let (started, snapshot) = {
+        let mut queue = lock_or_recover(&QUEUE);
+        let started = queue.cancel(run_id, super::audit::now_secs())?;
+        (started, queue.snapshot())
+    };
+    if started {
+        super::cancel_deployment()?;
+    }
+    emit_queue(&app);
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(entries: &[(u64, bool)]) -> RunQueue {
+        let mut queue = RunQueue::default();
+        for (id, requires_previous_success) in entries {
+            queue.push(QueuedRun {
+                id: *id,
+                deployment_name: format!("dep-{}", id),
+                command: "plan".to_string(),
+                requires_previous_success: *requires_previous_success,
+                state: QueuedRunState::Queued,
+                queued_at: 0,
+                started_at: None,
+                finished_at: None,
+                message: None,
+                credentials: CloudCredentials::default(),
+                confirmation: RollbackConfirmation::default(),
+                started: false,
+            });
+        }
+        queue
+    }
+
+    fn states(queue: &RunQueue) -> Vec<(u64, QueuedRunState)> {
+        queue.runs.iter().map(|r| (r.id, r.state)).collect()
+    }
+
+    #[test]
+    fn runs_start_in_order_and_dependents_are_skipped() {
+        let mut q = queue(&[(1, false), (2, true), (3, true), (4, false)]);
+
+        assert_eq!(q.start_next(10).unwrap().id, 1);
+        assert!(q.start_next(10).is_none(), "one run at a time");
+        q.finish(1, true, None, 11);
+
+        assert_eq!(q.start_next(12).unwrap().id, 2);
+        q.finish(2, false, Some("boom".into()), 13);
+
+        // 3 depends on the failed 2; 4 runs regardless
+        assert_eq!(q.start_next(14).unwrap().id, 4);
+        use QueuedRunState::*;
+        assert_eq!(states(&q), vec![(1, Succeeded), (2, Failed), (3, Skipped), (4, Running)]);
+        assert!(q.runs[2].message.as_deref().unwrap().contains("previous run"));
+    }
+
+    #[test]
+    fn reorder_only_accepts_the_queued_runs() {
+        let mut q = queue(&[(1, false), (2, false), (3, false)]);
+        q.start_next(0);
+
+        assert!(q.reorder(&[3]).is_err());
+        assert!(q.reorder(&[3, 3]).is_err());
+        assert!(q.reorder(&[1, 3, 2]).is_err());
+        q.reorder(&[3, 2]).unwrap();
+        let ids: Vec<u64> = q.runs.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn cancelled_runs_stay_cancelled_and_history_is_pruned() {
+        let mut q = queue(&[(1, false), (2, true)]);
+        q.start_next(0);
+        assert!(q.mark_started(1));
+        assert!(q.cancel(1, 1).unwrap());
+        assert!(!q.mark_started(1));
+        q.finish(1, false, None, 2);
+        assert_eq!(q.runs[0].state, QueuedRunState::Cancelled);
+        assert!(!q.cancel(2, 3).unwrap());
+        assert!(q.cancel(2, 3).is_err());
+        assert!(q.start_next(4).is_none());
+
+        let ids: Vec<(u64, bool)> = (10..10 + MAX_FINISHED as u64 + 5).map(|id| (id, false)).collect();
+        let mut q = queue(&ids);
+        while let Some(run) = q.start_next(0) {
+            q.finish(run.id, true, None, 1);
+        }
+        assert_eq!(q.runs.len(), MAX_FINISHED);
+        assert_eq!(q.runs[0].id, 15);
+    }
+
+    #[test]
+    fn cancelling_a_run_waiting_for_its_turn_stops_nothing() {
+        let mut q = queue(&[(1, false)]);
+        q.start_next(0);
+        // Picked by the worker but still waiting for the deployment to go idle
+        assert!(!q.cancel(1, 1).unwrap());
+        assert!(!q.is_running(1));
+        assert!(!q.mark_started(1));
+    }
+}
@@ -0,0 +1,491 @@
+//! Sync directory groups into the Databricks account.
+//!
+//! Lists groups from Azure AD (Microsoft Graph) or AWS IAM Identity Center,
+//! then mirrors the selected ones as Databricks account groups with their
+//! members and, given a workspace ID, grants them access to the workspace.
+//! Provisioning goes through [`super::identity_batch`]; this module only
+//! reads the directory. It is a one-off copy, not continuous SCIM
+//! provisioning; re-running adds new members but never removes any.
+
+use super::identity_batch::{self, BatchGroup, BatchUser, IdentityBatch, ProvisionReport};
+use super::{debug_log, http_client, is_valid_uuid, opt_non_empty, CloudCredentials};
+use crate::databricks_api::AccountClient;
+use crate::dependencies;
+use crate::endpoints::https_url;
+use serde::Serialize;
+
+/// Azure AD via Microsoft Graph.
+const SOURCE_AZURE_AD: &str = "azure_ad";
+/// AWS IAM Identity Center identity store.
+const SOURCE_IDENTITY_CENTER: &str = "identity_center";
+
+const GRAPH_HOST: &str = "graph.microsoft.com";
+
+/// Graph pages followed per listing, to bound very large tenants. A listing
+/// with more is an error rather than a silently partial result.
+const MAX_GRAPH_PAGES: usize = 20;
+
+/// A group in the source directory.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DirectoryGroup {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DirectoryMember {
+    email: String,
+    display_name: Option<String>,
+}
+
+// ─── Azure AD (Microsoft Graph) ─────────────────────────────────────────────
+
+/// Graph token: client credentials when an Azure service principal is set,
+/// otherwise the Azure CLI login.
+async fn graph_token(credentials: &CloudCredentials) -> Result<String, String> {
+    if opt_non_empty(&credentials.azure_client_id) && opt_non_empty(&credentials.azure_client_secret) {
+        let tenant = credentials
+            .azure_tenant_id
+            .clone()
+            .filter(|s| !s.is_empty())
+            .ok_or("Azure Tenant ID is required")?;
+        let url = https_url("login.microsoftonline.com", &format!("/{}/oauth2/v2.0/token", tenant));
+        let resp = http_client()?
+            .post(&url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", credentials.azure_client_id.as_deref().unwrap_or_default()),
+                ("client_secret", credentials.azure_client_secret.as_deref().unwrap_or_default()),
+                ("scope", "https://graph.microsoft.com/.default"),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to get Microsoft Graph token: {}", e))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            return Err(format!(
+                "Azure AD authentication failed ({}): {}",
+                status,
+                resp.text().await.unwrap_or_default()
+            ));
+        }
+        let json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Azure AD token response: {}", e))?;
+        return json["access_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "No access token in Azure AD response".to_string());
+    }
+
+    let az = dependencies::find_azure_cli_path().ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
+    let tenant = credentials.azure_tenant_id.clone().filter(|s| !s.is_empty());
+    let output = tokio::task::spawn_blocking(move || {
+        let mut cmd = super::silent_cmd(&az);
+        cmd.args(["account", "get-access-token", "--resource-type", "ms-graph", "--query", "accessToken", "-o", "tsv"]);
+        if let Some(tenant) = &tenant {
+            cmd.args(["--tenant", tenant]);
+        }
+        cmd.output()
+    })
+    .await
+    .map_err(|e| format!("Token task panicked: {}", e))?
+    .map_err(|e| format!("Failed to run Azure CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to get Microsoft Graph token: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// GET a Graph collection, following `@odata.nextLink` for up to
+/// [`MAX_GRAPH_PAGES`] pages.
+async fn graph_list(token: &str, path_and_query: &str) -> Result<Vec<serde_json::Value>, String> {
+    let http = http_client()?;
+    let mut url = Some(https_url(GRAPH_HOST, path_and_query));
+    let mut items = Vec::new();
+    let mut pages = 0;
+    while let Some(next) = url.take() {
+        pages += 1;
+        let resp = http
+            .get(&next)
+            .bearer_auth(token)
+            .header("ConsistencyLevel", "eventual")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call Microsoft Graph: {}", e))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            return Err(format!(
+                "Microsoft Graph request failed ({}): {}",
+                status,
+                resp.text().await.unwrap_or_default()
+            ));
+        }
+        let page: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Microsoft Graph response: {}", e))?;
+        items.extend(page["value"].as_array().cloned().unwrap_or_default());
+        url = page["@odata.nextLink"].as_str().map(str::to_string);
+        if url.is_some() && pages >= MAX_GRAPH_PAGES {
+            return Err(format!(
+                "Microsoft Graph returned more than {} items, the most one listing reads; use a narrower filter or group",
+                items.len()
+            ));
+        }
+    }
+    Ok(items)
+}
+
+fn parse_graph_group(group: &serde_json::Value) -> Option<DirectoryGroup> {
+    Some(DirectoryGroup {
+        id: group["id"].as_str()?.to_string(),
+        name: group["displayName"].as_str()?.to_string(),
+        description: group["description"].as_str().filter(|s| !s.is_empty()).map(str::to_string),
+    })
+}
+
+/// Email of a Graph user: `mail`, else a UPN that isn't a guest `#EXT#` alias.
+fn parse_graph_member(user: &serde_json::Value) -> Option<DirectoryMember> {
+    let email = user["mail"]
+        .as_str()
+        .filter(|m| !m.is_empty())
+        .or_else(|| user["userPrincipalName"].as_str().filter(|u| u.contains('@') && !u.contains("#EXT#")))?;
+    Some(DirectoryMember {
+        email: email.to_string(),
+        display_name: user["displayName"].as_str().map(str::to_string),
+    })
+}
+
+/// Graph `$filter` literal with single quotes doubled.
+fn odata_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+async fn azure_groups(token: &str, filter: Option<&str>) -> Result<Vec<DirectoryGroup>, String> {
+    let mut path = "/v1.0/groups?$select=id,displayName,description&$top=100".to_string();
+    if let Some(filter) = filter {
+        path.push_str(&format!("&$filter=startswith(displayName,{})", odata_string(filter)));
+    }
+    Ok(graph_list(token, &path).await?.iter().filter_map(parse_graph_group).collect())
+}
+
+async fn azure_group_with_members(token: &str, group_id: &str) -> Result<(DirectoryGroup, Vec<DirectoryMember>), String> {
+    let http = http_client()?;
+    let resp = http
+        .get(https_url(GRAPH_HOST, &format!("/v1.0/groups/{}?$select=id,displayName,description", group_id)))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to call Microsoft Graph: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Azure AD group {} not found ({})", group_id, resp.status()));
+    }
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Microsoft Graph response: {}", e))?;
+    let group = parse_graph_group(&json).ok_or_else(|| format!("Azure AD group {} has no display name", group_id))?;
+    let members = graph_list(
+        token,
+        &format!(
+            "/v1.0/groups/{}/transitiveMembers/microsoft.graph.user?$select=mail,userPrincipalName,displayName&$top=100",
+            group_id
+        ),
+    )
+    .await?
+    .iter()
+    .filter_map(parse_graph_member)
+    .collect();
+    Ok((group, members))
+}
+
+// ─── AWS IAM Identity Center ────────────────────────────────────────────────
+
+/// Run an AWS CLI command with the deployment's AWS credentials and parse its JSON output.
+fn aws_json(credentials: &CloudCredentials, args: &[&str]) -> Result<serde_json::Value, String> {
+    let aws = dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
+    let mut cmd = super::silent_cmd(&aws);
+    cmd.args(args).args(["--output", "json"]);
+    if let Some(region) = credentials.aws_region.as_deref().filter(|r| !r.is_empty()) {
+        cmd.args(["--region", region]);
+    }
+    super::aws::apply_aws_credentials(&mut cmd, credentials)?;
+    let output = cmd.output().map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "aws {} failed: {}",
+            args.iter().take(2).copied().collect::<Vec<_>>().join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse AWS CLI output: {}", e))
+}
+
+fn identity_store_id(credentials: &CloudCredentials) -> Result<String, String> {
+    aws_json(credentials, &["sso-admin", "list-instances"])?["Instances"][0]["IdentityStoreId"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "No IAM Identity Center instance found in this account and region".to_string())
+}
+
+fn parse_identity_store_groups(json: &serde_json::Value) -> Vec<DirectoryGroup> {
+    json["Groups"]
+        .as_array()
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|g| {
+                    Some(DirectoryGroup {
+                        id: g["GroupId"].as_str()?.to_string(),
+                        name: g["DisplayName"].as_str()?.to_string(),
+                        description: g["Description"].as_str().filter(|s| !s.is_empty()).map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Primary email of an identity store user, falling back to the first one.
+fn parse_identity_store_user(json: &serde_json::Value) -> Option<DirectoryMember> {
+    let emails = json["Emails"].as_array()?;
+    let email = emails
+        .iter()
+        .find(|e| e["Primary"].as_bool() == Some(true))
+        .or_else(|| emails.first())?["Value"]
+        .as_str()?;
+    Some(DirectoryMember {
+        email: email.to_string(),
+        display_name: json["DisplayName"].as_str().map(str::to_string),
+    })
+}
+
+fn identity_center_groups(credentials: &CloudCredentials, filter: Option<&str>) -> Result<Vec<DirectoryGroup>, String> {
+    let store = identity_store_id(credentials)?;
+    let groups = parse_identity_store_groups(&aws_json(
+        credentials,
+        &["identitystore", "list-groups", "--identity-store-id", &store],
+    )?);
+    Ok(match filter.map(str::to_lowercase) {
+        Some(filter) => groups.into_iter().filter(|g| g.name.to_lowercase().starts_with(&filter)).collect(),
+        None => groups,
+    })
+}
+
+fn identity_center_groups_with_members(
+    credentials: &CloudCredentials,
+    group_ids: &[String],
+) -> Result<Vec<(DirectoryGroup, Vec<DirectoryMember>)>, String> {
+    let store = identity_store_id(credentials)?;
+    let all_groups = parse_identity_store_groups(&aws_json(
+        credentials,
+        &["identitystore", "list-groups", "--identity-store-id", &store],
+    )?);
+    let mut result = Vec::new();
+    for group_id in group_ids {
+        let group = all_groups
+            .iter()
+            .find(|g| &g.id == group_id)
+            .cloned()
+            .ok_or_else(|| format!("Identity Center group {} not found", group_id))?;
+        let memberships = aws_json(
+            credentials,
+            &["identitystore", "list-group-memberships", "--identity-store-id", &store, "--group-id", group_id],
+        )?;
+        let mut members = Vec::new();
+        for user_id in memberships["GroupMemberships"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m["MemberId"]["UserId"].as_str())
+        {
+            let user = aws_json(
+                credentials,
+                &["identitystore", "describe-user", "--identity-store-id", &store, "--user-id", user_id],
+            )?;
+            match parse_identity_store_user(&user) {
+                Some(member) => members.push(member),
+                None => {
+                    debug_log!("[directory_sync] skipping Identity Center user {} without email", user_id);
+                }
+            }
+        }
+        result.push((group, members));
+    }
+    Ok(result)
+}
+
+// ─── Sync ───────────────────────────────────────────────────────────────────
+
+/// Provisioning batch mirroring directory groups: each group gets
+/// `workspace_permission`; members inherit access through the group.
+fn build_batch(
+    groups: Vec<(DirectoryGroup, Vec<DirectoryMember>)>,
+    workspace_permission: Option<String>,
+) -> Result<IdentityBatch, String> {
+    let mut batch = IdentityBatch::default();
+    for (group, members) in groups {
+        for member in members {
+            batch.users.push(BatchUser {
+                email: member.email,
+                display_name: member.display_name,
+                groups: vec![group.name.clone()],
+                workspace_permission: None,
+            });
+        }
+        batch.groups.push(BatchGroup {
+            name: group.name,
+            workspace_permission: workspace_permission.clone(),
+        });
+    }
+    identity_batch::normalize_batch(batch)
+}
+
+fn check_source(source: &str) -> Result<(), String> {
+    if source == SOURCE_AZURE_AD || source == SOURCE_IDENTITY_CENTER {
+        Ok(())
+    } else {
+        Err(format!("Unknown directory '{}' (expected azure_ad or identity_center)", source))
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List groups in Azure AD (`azure_ad`) or IAM Identity Center
+/// (`identity_center`), optionally only names starting with `filter`.
+#[tauri::command]
+pub async fn list_directory_groups(
+    credentials: CloudCredentials,
+    source: String,
+    filter: Option<String>,
+) -> Result<Vec<DirectoryGroup>, String> {
+    check_source(&source)?;
+    let filter = filter.filter(|f| !f.trim().is_empty());
+    let mut groups = if source == SOURCE_AZURE_AD {
+        azure_groups(&graph_token(&credentials).await?, filter.as_deref()).await?
+    } else {
+        tokio::task::spawn_blocking(move || identity_center_groups(&credentials, filter.as_deref()))
+            .await
+            .map_err(|e| format!("Directory task panicked: {}", e))??
+    };
+    groups.sort_by_key(|g| g.name.to_lowercase());
+    Ok(groups)
+}
+
+/// Mirror the selected directory groups and their members as Databricks
+/// account groups, granting them `workspace_permission` on `workspace_id`.
+#[tauri::command]
+pub async fn sync_directory_groups(
+    credentials: CloudCredentials,
+    source: String,
+    group_ids: Vec<String>,
+    workspace_id: Option<u64>,
+    workspace_permission: Option<String>,
+) -> Result<ProvisionReport, String> {
+    check_source(&source)?;
+    if group_ids.is_empty() {
+        return Err("Select at least one group to sync".to_string());
+    }
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks account ID is required")?;
+
+    let groups = if source == SOURCE_AZURE_AD {
+        if let Some(bad) = group_ids.iter().find(|id| !is_valid_uuid(id)) {
+            return Err(format!("Invalid Azure AD group ID: {}", bad));
+        }
+        let token = graph_token(&credentials).await?;
+        let mut groups = Vec::new();
+        for group_id in &group_ids {
+            groups.push(azure_group_with_members(&token, group_id).await?);
+        }
+        groups
+    } else {
+        let creds = credentials.clone();
+        tokio::task::spawn_blocking(move || identity_center_groups_with_members(&creds, &group_ids))
+            .await
+            .map_err(|e| format!("Directory task panicked: {}", e))??
+    };
+    let permission = workspace_id.map(|_| workspace_permission.unwrap_or_else(|| "USER".to_string()));
+    let batch = build_batch(groups, permission)?;
+
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    Ok(identity_batch::provision(&client, &batch, workspace_id).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_members_prefer_mail_and_skip_guest_upns() {
+        let member = parse_graph_member(&serde_json::json!({
+            "mail": "jane@contoso.com", "userPrincipalName": "jane_contoso.com#EXT#@tenant.onmicrosoft.com"
+        }));
+        assert_eq!(member.unwrap().email, "jane@contoso.com");
+        let upn = parse_graph_member(&serde_json::json!({ "mail": null, "userPrincipalName": "bob@contoso.com" }));
+        assert_eq!(upn.unwrap().email, "bob@contoso.com");
+        let guest = parse_graph_member(&serde_json::json!({ "userPrincipalName": "x_y.com#EXT#@tenant.onmicrosoft.com" }));
+        assert_eq!(guest, None);
+    }
+
+    #[test]
+    fn escapes_odata_literals() {
+        assert_eq!(odata_string("O'Brien team"), "'O''Brien team'");
+    }
+
+    #[test]
+    fn parses_identity_store_output() {
+        let groups = parse_identity_store_groups(&serde_json::json!({
+            "Groups": [{ "GroupId": "g-1", "DisplayName": "Data Engineers", "Description": "" }]
+        }));
+        assert_eq!(groups, vec![DirectoryGroup { id: "g-1".into(), name: "Data Engineers".into(), description: None }]);
+
+        let user = parse_identity_store_user(&serde_json::json!({
+            "DisplayName": "Jane",
+            "Emails": [{ "Value": "old@example.com" }, { "Value": "jane@example.com", "Primary": true }]
+        }));
+        assert_eq!(user.unwrap().email, "jane@example.com");
+        assert_eq!(parse_identity_store_user(&serde_json::json!({ "UserName": "nobody" })), None);
+    }
+
+    #[test]
+    fn builds_batch_with_group_access_only() {
+        let group = DirectoryGroup {
+            id: "g".into(),
+            name: "analysts".into(),
+            description: None,
+        };
+        let members = vec![
+            DirectoryMember {
+                email: "a@example.com".into(),
+                display_name: Some("A".into()),
+            },
+            DirectoryMember {
+                email: "A@example.com".into(),
+                display_name: None,
+            },
+        ];
+        let batch = build_batch(vec![(group, members)], Some("user".into())).unwrap();
+        assert_eq!(batch.users.len(), 1);
+        assert_eq!(batch.users[0].groups, vec!["analysts"]);
+        assert_eq!(batch.users[0].workspace_permission, None);
+        assert_eq!(batch.groups[0].workspace_permission.as_deref(), Some("USER"));
+    }
+
+    #[test]
+    fn rejects_unknown_sources() {
+        assert!(check_source("okta").is_err());
+        assert!(check_source(SOURCE_IDENTITY_CENTER).is_ok());
+    }
+}
@@ -0,0 +1,759 @@
+//! Customer-managed keys (CMK / CMEK) for workspace encryption.
+//!
+//! aws-sra, azure-sra and gcp-sra can encrypt workspace storage and managed
+//! services with customer-managed keys. These commands list the keys visible
+//! to the deployment credentials, check that a chosen key is usable by
+//! Databricks (a key policy that doesn't grant the Databricks account only
+//! fails when the workspace is created), and map the selection onto the
+//! template's variables.
+
+use super::aws::apply_aws_credentials;
+use super::preflight::{BLOCKER, WARNING};
+use super::debug_log;
+use super::variable_sources::cli_json;
+use super::{non_empty_str, CloudCredentials, PreflightEntry, PreflightReport};
+use crate::dependencies;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// AWS account Databricks uses to access customer-managed keys.
+const DATABRICKS_AWS_ACCOUNT_ID: &str = "414351767826";
+/// Application ID of the AzureDatabricks first-party service principal.
+const AZURE_DATABRICKS_APP_ID: &str = "2ff814a6-3304-4ab8-85cb-cd0e6f879c1d";
+
+const AWS_CMK_DOCS_URL: &str = "https://docs.databricks.com/aws/en/security/keys/configure-customer-managed-keys";
+const AZURE_CMK_DOCS_URL: &str = "https://learn.microsoft.com/azure/databricks/security/keys/cmk-managed-services-azure/";
+const GCP_CMEK_DOCS_URL: &str = "https://docs.databricks.com/gcp/en/security/keys/customer-managed-keys";
+
+/// A key the deployment credentials can see.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EncryptionKey {
+    /// Key ARN (AWS), versionless key URL (Azure), or key resource name (GCP).
+    pub id: String,
+    pub name: String,
+    /// Region, vault, or key ring, for the label.
+    pub detail: String,
+    /// Resource ID of the Key Vault holding the key (Azure only).
+    pub key_vault_id: Option<String>,
+}
+
+/// Keys chosen in the wizard. GCP uses one key for both purposes.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EncryptionKeySelection {
+    pub workspace_storage_key: Option<String>,
+    pub managed_services_key: Option<String>,
+    pub key_vault_id: Option<String>,
+}
+
+fn cloud_of(credentials: &CloudCredentials) -> String {
+    credentials.cloud.clone().unwrap_or_else(|| "aws".to_string())
+}
+
+// ─── AWS ────────────────────────────────────────────────────────────────────
+
+fn aws_kms(credentials: &CloudCredentials, region: &str, args: &[&str]) -> Result<serde_json::Value, String> {
+    let aws_cli = dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
+    let mut cmd = super::silent_cmd(&aws_cli);
+    cmd.arg("kms").args(args).args(["--region", region, "--output", "json"]);
+    apply_aws_credentials(&mut cmd, credentials)?;
+    let output = cmd.output().map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("AWS CLI error: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse AWS response: {}", e))
+}
+
+/// `aws kms list-aliases` output. AWS-managed aliases (`alias/aws/...`) can't
+/// be granted to Databricks and unaliased keys have no readable name, so
+/// only customer aliases pointing at a key are listed, by key ARN.
+fn parse_kms_aliases(json: &serde_json::Value, region: &str) -> Vec<EncryptionKey> {
+    json["Aliases"]
+        .as_array()
+        .map(|aliases| {
+            aliases
+                .iter()
+                .filter_map(|a| {
+                    let name = a["AliasName"].as_str()?;
+                    let key_id = a["TargetKeyId"].as_str()?;
+                    if name.starts_with("alias/aws/") {
+                        return None;
+                    }
+                    let (prefix, _) = a["AliasArn"].as_str()?.split_once(":alias/")?;
+                    Some(EncryptionKey {
+                        id: format!("{}:key/{}", prefix, key_id),
+                        name: name.trim_start_matches("alias/").to_string(),
+                        detail: region.to_string(),
+                        key_vault_id: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Region segment of a KMS key ARN.
+fn arn_region(arn: &str) -> Option<&str> {
+    arn.split(':').nth(3).filter(|r| !r.is_empty())
+}
+
+fn one_or_many(value: &serde_json::Value) -> Vec<&str> {
+    match value {
+        serde_json::Value::String(s) => vec![s.as_str()],
+        serde_json::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// IAM action pattern match: case-insensitive, with a trailing `*` wildcard.
+fn action_matches(pattern: &str, action: &str) -> bool {
+    let (pattern, action) = (pattern.to_ascii_lowercase(), action.to_ascii_lowercase());
+    match pattern.strip_suffix('*') {
+        Some(prefix) => action.starts_with(prefix),
+        None => pattern == action,
+    }
+}
+
+/// Whether a policy principal covers the Databricks AWS account.
+fn grants_databricks(principal: &serde_json::Value) -> bool {
+    if principal.as_str() == Some("*") {
+        return true;
+    }
+    one_or_many(&principal["AWS"])
+        .iter()
+        .any(|p| *p == "*" || p.contains(DATABRICKS_AWS_ACCOUNT_ID))
+}
+
+/// Check a key policy document for the access Databricks needs. Returns the
+/// entry for the "Key policy" check; `account_id` is the Databricks account,
+/// matched against a `DatabricksAccountId` condition when the policy has one.
+fn key_policy_entry(policy: &serde_json::Value, account_id: Option<&str>) -> PreflightEntry {
+    const NAME: &str = "Key policy";
+    const REQUIRED: [&str; 3] = ["kms:Encrypt", "kms:Decrypt", "kms:GenerateDataKey"];
+
+    let statements = match &policy["Statement"] {
+        serde_json::Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        statement @ serde_json::Value::Object(_) => vec![statement],
+        _ => Vec::new(),
+    };
+    let databricks: Vec<_> = statements
+        .into_iter()
+        .filter(|s| s["Effect"].as_str() == Some("Allow") && grants_databricks(&s["Principal"]))
+        .collect();
+
+    let missing: Vec<&str> = REQUIRED
+        .iter()
+        .filter(|action| {
+            !databricks
+                .iter()
+                .any(|s| one_or_many(&s["Action"]).iter().any(|p| action_matches(p, action)))
+        })
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return PreflightEntry::fail(
+            NAME,
+            BLOCKER,
+            &format!("Key policy doesn't allow Databricks ({}) {}", DATABRICKS_AWS_ACCOUNT_ID, missing.join(", ")),
+        )
+        .remediation(format!(
+            "Add a statement allowing arn:aws:iam::{}:root kms:Encrypt, kms:Decrypt and kms:GenerateDataKey*",
+            DATABRICKS_AWS_ACCOUNT_ID
+        ))
+        .docs(AWS_CMK_DOCS_URL);
+    }
+
+    let scoped: Vec<&str> = databricks
+        .iter()
+        .flat_map(|s| {
+            s["Condition"]
+                .as_object()
+                .into_iter()
+                .flat_map(|ops| ops.values())
+                .filter_map(|keys| keys.as_object())
+                .flat_map(|keys| keys.iter())
+                .filter(|(key, _)| key.ends_with("DatabricksAccountId"))
+                .flat_map(|(_, v)| one_or_many(v))
+        })
+        .collect();
+    match account_id {
+        Some(id) if !scoped.is_empty() && !scoped.contains(&id) => PreflightEntry::fail(
+            NAME,
+            BLOCKER,
+            &format!("Key policy is limited to Databricks account {}", scoped.join(", ")),
+        )
+        .remediation(format!("Add {} to the DatabricksAccountId condition", id))
+        .docs(AWS_CMK_DOCS_URL),
+        _ => PreflightEntry::pass(NAME, "Key policy grants Databricks encrypt and decrypt access"),
+    }
+}
+
+fn aws_key_state_entry(metadata: &serde_json::Value) -> PreflightEntry {
+    const NAME: &str = "Key state";
+    let state = metadata["KeyState"].as_str().unwrap_or("unknown");
+    if state != "Enabled" {
+        return PreflightEntry::fail(NAME, BLOCKER, &format!("Key is {}", state))
+            .remediation("Enable the key or choose another one")
+            .docs(AWS_CMK_DOCS_URL);
+    }
+    let usage = metadata["KeyUsage"].as_str().unwrap_or("");
+    let spec = metadata["KeySpec"].as_str().unwrap_or("");
+    if usage != "ENCRYPT_DECRYPT" || spec != "SYMMETRIC_DEFAULT" {
+        return PreflightEntry::fail(NAME, BLOCKER, &format!("Key is {} {}, not a symmetric encryption key", spec, usage))
+            .remediation("Choose a symmetric ENCRYPT_DECRYPT key")
+            .docs(AWS_CMK_DOCS_URL);
+    }
+    PreflightEntry::pass(NAME, "Key is an enabled symmetric encryption key")
+}
+
+fn validate_aws(credentials: &CloudCredentials, key_id: &str, region: Option<&str>) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let Some(key_region) = arn_region(key_id) else {
+        return PreflightReport::single(
+            PreflightEntry::fail("Key ARN", BLOCKER, &format!("{} isn't a KMS key ARN", key_id))
+                .remediation("Use the full arn:aws:kms:<region>:<account>:key/<id> ARN"),
+        );
+    };
+    if let Some(region) = region.filter(|r| *r != key_region) {
+        report.push(
+            PreflightEntry::fail("Key region", BLOCKER, &format!("Key is in {}, the workspace in {}", key_region, region))
+                .remediation("Choose a key in the workspace region")
+                .docs(AWS_CMK_DOCS_URL),
+        );
+    }
+
+    match aws_kms(credentials, key_region, &["describe-key", "--key-id", key_id]) {
+        Ok(json) => report.push(aws_key_state_entry(&json["KeyMetadata"])),
+        Err(e) => {
+            report.push(PreflightEntry::fail("Key state", BLOCKER, &format!("Couldn't describe key: {}", e)));
+            return report;
+        }
+    }
+
+    let account_id = non_empty_str(&credentials.databricks_account_id);
+    match aws_kms(credentials, key_region, &["get-key-policy", "--key-id", key_id, "--policy-name", "default"]) {
+        Ok(json) => match json["Policy"].as_str().map(serde_json::from_str::<serde_json::Value>) {
+            Some(Ok(policy)) => report.push(key_policy_entry(&policy, account_id)),
+            _ => report.push(PreflightEntry::skipped("Key policy", "Key policy couldn't be parsed")),
+        },
+        Err(e) => report.push(PreflightEntry::skipped("Key policy", &format!("Couldn't read key policy: {}", e))),
+    }
+    report
+}
+
+// ─── Azure ──────────────────────────────────────────────────────────────────
+
+fn az() -> Result<std::path::PathBuf, String> {
+    dependencies::find_azure_cli_path().ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))
+}
+
+/// `az keyvault key list` output for one vault; disabled keys are dropped.
+fn parse_vault_keys(json: &serde_json::Value, vault: &serde_json::Value) -> Vec<EncryptionKey> {
+    let vault_name = vault["name"].as_str().unwrap_or("");
+    json.as_array()
+        .map(|keys| {
+            keys.iter()
+                .filter(|k| k["attributes"]["enabled"].as_bool().unwrap_or(true))
+                .filter_map(|k| {
+                    Some(EncryptionKey {
+                        id: k["kid"].as_str()?.to_string(),
+                        name: k["name"].as_str()?.to_string(),
+                        detail: vault_name.to_string(),
+                        key_vault_id: vault["id"].as_str().map(String::from),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn list_azure_keys(credentials: &CloudCredentials) -> Result<Vec<EncryptionKey>, String> {
+    let az = az()?;
+    let subscription = non_empty_str(&credentials.azure_subscription_id).ok_or("Azure subscription is required")?;
+    let vaults = cli_json(az.clone(), &["keyvault", "list", "--subscription", subscription, "--output", "json"], "Azure CLI")?;
+    let mut keys = Vec::new();
+    for vault in vaults.as_array().into_iter().flatten() {
+        let Some(name) = vault["name"].as_str() else { continue };
+        // Vaults the caller can list but not read keys from are skipped
+        match cli_json(
+            az.clone(),
+            &["keyvault", "key", "list", "--vault-name", name, "--subscription", subscription, "--output", "json"],
+            "Azure CLI",
+        ) {
+            Ok(json) => keys.extend(parse_vault_keys(&json, vault)),
+            Err(e) => {
+                debug_log!("[list_encryption_keys] skipping vault {}: {}", name, e);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Vault name from a key URL such as `https://myvault.vault.azure.net/keys/name`.
+fn vault_name(key_id: &str) -> Option<&str> {
+    key_id
+        .strip_prefix("https://")?
+        .split('.')
+        .next()
+        .filter(|n| !n.is_empty())
+}
+
+fn azure_vault_entry(vault: &serde_json::Value) -> PreflightEntry {
+    const NAME: &str = "Key Vault protection";
+    let props = &vault["properties"];
+    if !props["enableSoftDelete"].as_bool().unwrap_or(true) || !props["enablePurgeProtection"].as_bool().unwrap_or(false) {
+        return PreflightEntry::fail(NAME, BLOCKER, "Key Vault needs soft delete and purge protection enabled")
+            .remediation("Enable purge protection on the Key Vault")
+            .docs(AZURE_CMK_DOCS_URL);
+    }
+    PreflightEntry::pass(NAME, "Soft delete and purge protection are enabled")
+}
+
+fn azure_key_entry(key: &serde_json::Value) -> PreflightEntry {
+    const NAME: &str = "Key type";
+    if !key["attributes"]["enabled"].as_bool().unwrap_or(true) {
+        return PreflightEntry::fail(NAME, BLOCKER, "Key is disabled").docs(AZURE_CMK_DOCS_URL);
+    }
+    let kty = key["key"]["kty"].as_str().unwrap_or("");
+    if !kty.starts_with("RSA") {
+        return PreflightEntry::fail(NAME, BLOCKER, &format!("Key type is {}, Databricks needs an RSA key", kty))
+            .remediation("Choose an RSA or RSA-HSM key")
+            .docs(AZURE_CMK_DOCS_URL);
+    }
+    let ops = one_or_many(&key["key"]["keyOps"]);
+    let missing: Vec<&str> = ["wrapKey", "unwrapKey"].into_iter().filter(|op| !ops.contains(op)).collect();
+    if !missing.is_empty() {
+        return PreflightEntry::fail(NAME, BLOCKER, &format!("Key doesn't permit {}", missing.join(", ")))
+            .remediation("Allow wrapKey and unwrapKey operations on the key")
+            .docs(AZURE_CMK_DOCS_URL);
+    }
+    PreflightEntry::pass(NAME, &format!("{} key permits wrap and unwrap", kty))
+}
+
+/// Whether the vault's access policies let `object_id` (the AzureDatabricks
+/// service principal) get, wrap and unwrap keys. Vaults using Azure RBAC
+/// aren't checked, since role assignments can be inherited from any scope.
+fn azure_access_entry(vault: &serde_json::Value, object_id: Option<&str>) -> PreflightEntry {
+    const NAME: &str = "Databricks access";
+    let props = &vault["properties"];
+    if props["enableRbacAuthorization"].as_bool().unwrap_or(false) {
+        return PreflightEntry::skipped(NAME, "Vault uses Azure RBAC; role assignments aren't checked");
+    }
+    let Some(object_id) = object_id else {
+        return PreflightEntry::skipped(NAME, "Couldn't look up the AzureDatabricks service principal");
+    };
+    let permissions: Vec<String> = props["accessPolicies"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|p| p["objectId"].as_str().is_some_and(|id| id.eq_ignore_ascii_case(object_id)))
+        .flat_map(|p| one_or_many(&p["permissions"]["keys"]))
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let missing: Vec<&str> = ["get", "wrapKey", "unwrapKey"]
+        .into_iter()
+        .filter(|p| !permissions.iter().any(|g| g == "all" || *g == p.to_ascii_lowercase()))
+        .collect();
+    if missing.is_empty() {
+        PreflightEntry::pass(NAME, "AzureDatabricks can get, wrap and unwrap keys")
+    } else {
+        PreflightEntry::fail(NAME, WARNING, &format!("AzureDatabricks access policy is missing {}", missing.join(", ")))
+            .remediation("Add an access policy for AzureDatabricks with Get, Wrap Key and Unwrap Key")
+            .docs(AZURE_CMK_DOCS_URL)
+    }
+}
+
+fn validate_azure(credentials: &CloudCredentials, key_id: &str) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let Some(name) = vault_name(key_id) else {
+        return PreflightReport::single(
+            PreflightEntry::fail("Key ID", BLOCKER, &format!("{} isn't a Key Vault key URL", key_id))
+                .remediation("Use the key identifier, e.g. https://<vault>.vault.azure.net/keys/<name>"),
+        );
+    };
+    let az = match az() {
+        Ok(az) => az,
+        Err(e) => return PreflightReport::single(PreflightEntry::skipped("Key Vault protection", &e)),
+    };
+    let mut vault_args = vec!["keyvault", "show", "--name", name, "--output", "json"];
+    if let Some(subscription) = non_empty_str(&credentials.azure_subscription_id) {
+        vault_args.extend(["--subscription", subscription]);
+    }
+    let vault = match cli_json(az.clone(), &vault_args, "Azure CLI") {
+        Ok(vault) => vault,
+        Err(e) => {
+            report.push(PreflightEntry::fail("Key Vault protection", BLOCKER, &format!("Couldn't read Key Vault: {}", e)));
+            return report;
+        }
+    };
+    report.push(azure_vault_entry(&vault));
+    match cli_json(az.clone(), &["keyvault", "key", "show", "--id", key_id, "--output", "json"], "Azure CLI") {
+        Ok(key) => report.push(azure_key_entry(&key)),
+        Err(e) => report.push(PreflightEntry::fail("Key type", BLOCKER, &format!("Couldn't read key: {}", e))),
+    }
+    let object_id = cli_json(az, &["ad", "sp", "show", "--id", AZURE_DATABRICKS_APP_ID, "--output", "json"], "Azure CLI")
+        .ok()
+        .and_then(|sp| sp["id"].as_str().map(String::from));
+    report.push(azure_access_entry(&vault, object_id.as_deref()));
+    report
+}
+
+// ─── GCP ────────────────────────────────────────────────────────────────────
+
+fn gcloud() -> Result<std::path::PathBuf, String> {
+    dependencies::find_gcloud_cli_path().ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))
+}
+
+/// `gcloud kms keys list` output for one key ring: enabled encryption keys only.
+fn parse_gcp_keys(json: &serde_json::Value, keyring: &str) -> Vec<EncryptionKey> {
+    let ring = keyring.rsplit('/').next().unwrap_or(keyring);
+    json.as_array()
+        .map(|keys| {
+            keys.iter()
+                .filter(|k| k["purpose"].as_str() == Some("ENCRYPT_DECRYPT"))
+                .filter(|k| k["primary"]["state"].as_str() == Some("ENABLED"))
+                .filter_map(|k| {
+                    let id = k["name"].as_str()?;
+                    Some(EncryptionKey {
+                        id: id.to_string(),
+                        name: id.rsplit('/').next().unwrap_or(id).to_string(),
+                        detail: ring.to_string(),
+                        key_vault_id: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn list_gcp_keys(credentials: &CloudCredentials, region: &str) -> Result<Vec<EncryptionKey>, String> {
+    let gcloud = gcloud()?;
+    let project = non_empty_str(&credentials.gcp_project_id).ok_or("GCP project is required")?;
+    let rings = cli_json(
+        gcloud.clone(),
+        &["kms", "keyrings", "list", "--location", region, "--project", project, "--format=json"],
+        "gcloud",
+    )?;
+    let mut keys = Vec::new();
+    for ring in rings.as_array().into_iter().flatten() {
+        let Some(name) = ring["name"].as_str() else { continue };
+        match cli_json(gcloud.clone(), &["kms", "keys", "list", "--keyring", name, "--format=json"], "gcloud") {
+            Ok(json) => keys.extend(parse_gcp_keys(&json, name)),
+            Err(e) => {
+                debug_log!("[list_encryption_keys] skipping key ring {}: {}", name, e);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Location segment of `projects/P/locations/L/keyRings/R/cryptoKeys/K`.
+fn gcp_key_location(key_id: &str) -> Option<&str> {
+    let parts: Vec<&str> = key_id.split('/').collect();
+    match parts.as_slice() {
+        ["projects", _, "locations", location, "keyRings", _, "cryptoKeys", _] => Some(location),
+        _ => None,
+    }
+}
+
+fn gcp_key_entry(key: &serde_json::Value) -> PreflightEntry {
+    const NAME: &str = "Key state";
+    let purpose = key["purpose"].as_str().unwrap_or("unknown");
+    if purpose != "ENCRYPT_DECRYPT" {
+        return PreflightEntry::fail(NAME, BLOCKER, &format!("Key purpose is {}", purpose))
+            .remediation("Choose a symmetric ENCRYPT_DECRYPT key")
+            .docs(GCP_CMEK_DOCS_URL);
+    }
+    match key["primary"]["state"].as_str() {
+        Some("ENABLED") => PreflightEntry::pass(NAME, "Key is an enabled symmetric encryption key"),
+        state => PreflightEntry::fail(NAME, BLOCKER, &format!("Primary key version is {}", state.unwrap_or("missing")))
+            .remediation("Enable the primary key version or choose another key")
+            .docs(GCP_CMEK_DOCS_URL),
+    }
+}
+
+/// Roles that let a member encrypt and decrypt with a Cloud KMS key.
+const GCP_KEY_USER_ROLES: &[&str] = &["roles/cloudkms.cryptoKeyEncrypterDecrypter", "roles/owner"];
+
+/// Whether the key's IAM policy (`gcloud kms keys get-iam-policy`) lets the
+/// Databricks service account use the key. Without a service account there
+/// is nothing to check against.
+fn gcp_access_entry(policy: &serde_json::Value, service_account: Option<&str>) -> PreflightEntry {
+    const NAME: &str = "Databricks access";
+    let Some(service_account) = service_account else {
+        return PreflightEntry::skipped(NAME, "No Databricks service account to check the key policy against");
+    };
+    let member = format!("serviceAccount:{}", service_account);
+    let granted = policy["bindings"].as_array().into_iter().flatten().any(|binding| {
+        binding["role"].as_str().is_some_and(|role| GCP_KEY_USER_ROLES.contains(&role))
+            && binding["members"].as_array().into_iter().flatten().any(|m| m.as_str().is_some_and(|m| m.eq_ignore_ascii_case(&member)))
+    });
+    if granted {
+        PreflightEntry::pass(NAME, &format!("{} can encrypt and decrypt with the key", service_account))
+    } else {
+        PreflightEntry::fail(NAME, BLOCKER, &format!("{} can't use the key", service_account))
+            .remediation("Grant the service account roles/cloudkms.cryptoKeyEncrypterDecrypter on the key")
+            .docs(GCP_CMEK_DOCS_URL)
+    }
+}
+
+fn validate_gcp(credentials: &CloudCredentials, key_id: &str, region: Option<&str>) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let Some(location) = gcp_key_location(key_id) else {
+        return PreflightReport::single(
+            PreflightEntry::fail("Key ID", BLOCKER, &format!("{} isn't a Cloud KMS key name", key_id))
+                .remediation("Use projects/<project>/locations/<region>/keyRings/<ring>/cryptoKeys/<key>"),
+        );
+    };
+    if let Some(region) = region.filter(|r| *r != location) {
+        report.push(
+            PreflightEntry::fail("Key region", BLOCKER, &format!("Key is in {}, the workspace in {}", location, region))
+                .remediation("Choose a key in the workspace region")
+                .docs(GCP_CMEK_DOCS_URL),
+        );
+    }
+    let gcloud = match gcloud() {
+        Ok(gcloud) => gcloud,
+        Err(e) => {
+            report.push(PreflightEntry::fail("Key state", BLOCKER, &format!("Couldn't describe key: {}", e)));
+            return report;
+        }
+    };
+    match cli_json(gcloud.clone(), &["kms", "keys", "describe", key_id, "--format=json"], "gcloud") {
+        Ok(key) => report.push(gcp_key_entry(&key)),
+        Err(e) => {
+            report.push(PreflightEntry::fail("Key state", BLOCKER, &format!("Couldn't describe key: {}", e)));
+            return report;
+        }
+    }
+    let service_account = non_empty_str(&credentials.gcp_service_account_email);
+    match cli_json(gcloud, &["kms", "keys", "get-iam-policy", key_id, "--format=json"], "gcloud") {
+        Ok(policy) => report.push(gcp_access_entry(&policy, service_account)),
+        Err(e) => report.push(PreflightEntry::skipped("Databricks access", &format!("Couldn't read key IAM policy: {}", e))),
+    }
+    report
+}
+
+// ─── Template variables ─────────────────────────────────────────────────────
+
+/// Template variables that point `template_id` at the selected keys.
+pub(crate) fn key_variables(
+    template_id: &str,
+    selection: &EncryptionKeySelection,
+) -> Result<HashMap<String, serde_json::Value>, String> {
+    let storage = non_empty_str(&selection.workspace_storage_key).map(str::trim);
+    let managed = non_empty_str(&selection.managed_services_key).map(str::trim);
+    let mut vars = HashMap::new();
+    match template_id {
+        "aws-sra" => {
+            if let Some(arn) = storage {
+                vars.insert("existing_workspace_storage_key_arn".to_string(), arn.into());
+            }
+            if let Some(arn) = managed {
+                vars.insert("existing_managed_services_key_arn".to_string(), arn.into());
+            }
+        }
+        // The template only accepts existing keys without a new hub (its
+        // existing_cmk_ids validation fails when create_hub is true)
+        "azure-sra" => {
+            let (Some(storage), Some(managed)) = (storage, managed) else {
+                return Err("azure-sra needs both a managed disk and a managed services key".to_string());
+            };
+            let vault = non_empty_str(&selection.key_vault_id).map(str::trim).ok_or("Key Vault resource ID is required")?;
+            vars.insert("create_hub".to_string(), false.into());
+            vars.insert("cmk_enabled".to_string(), true.into());
+            vars.insert(
+                "existing_cmk_ids".to_string(),
+                serde_json::json!({
+                    "key_vault_id": vault,
+                    "managed_disk_key_id": storage,
+                    "managed_services_key_id": managed,
+                }),
+            );
+        }
+        "gcp-sra" => {
+            let key = storage.or(managed).ok_or("Select a Cloud KMS key")?;
+            if storage.is_some() && managed.is_some() && storage != managed {
+                return Err("gcp-sra uses a single key for storage and managed services".to_string());
+            }
+            vars.insert("kms_key_id".to_string(), key.into());
+            vars.insert("use_existing_cmek".to_string(), false.into());
+        }
+        other => return Err(format!("Template {} doesn't support customer-managed keys", other)),
+    }
+    Ok(vars)
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List customer-managed keys usable for workspace encryption: KMS keys with
+/// an alias (AWS), Key Vault keys (Azure), or Cloud KMS keys (GCP) in `region`.
+#[tauri::command]
+pub async fn list_encryption_keys(credentials: CloudCredentials, region: Option<String>) -> Result<Vec<EncryptionKey>, String> {
+    let cloud = cloud_of(&credentials);
+    let region = region.filter(|r| !r.is_empty());
+    let mut keys = super::run_blocking(move || match cloud.as_str() {
+        "aws" => {
+            let region = region
+                .or_else(|| credentials.aws_region.clone().filter(|r| !r.is_empty()))
+                .unwrap_or_else(|| "us-east-1".to_string());
+            Ok(parse_kms_aliases(&aws_kms(&credentials, &region, &["list-aliases"])?, &region))
+        }
+        "azure" => list_azure_keys(&credentials),
+        "gcp" => list_gcp_keys(&credentials, region.as_deref().ok_or("GCP region is required")?),
+        other => Err(format!("Unsupported cloud: {}", other)),
+    })
+    .await
+    .map_err(|e| format!("Failed to list encryption keys: {}", e))?;
+    keys.sort_by_key(|k| k.name.to_lowercase());
+    Ok(keys)
+}
+
+/// Check that a key exists, is usable for encryption, and grants Databricks
+/// access. `region` is the workspace region, compared with the key's.
+#[tauri::command]
+pub async fn validate_encryption_key(
+    credentials: CloudCredentials,
+    key_id: String,
+    region: Option<String>,
+) -> Result<PreflightReport, String> {
+    let key_id = key_id.trim().to_string();
+    if key_id.is_empty() {
+        return Err("Key ID is required".to_string());
+    }
+    let cloud = cloud_of(&credentials);
+    let region = region.filter(|r| !r.is_empty());
+    super::run_blocking(move || match cloud.as_str() {
+        "aws" => Ok(validate_aws(&credentials, &key_id, region.as_deref())),
+        "azure" => Ok(validate_azure(&credentials, &key_id)),
+        "gcp" => Ok(validate_gcp(&credentials, &key_id, region.as_deref())),
+        other => Err(format!("Unsupported cloud: {}", other)),
+    })
+    .await
+}
+
+/// Template variables for the selected keys, to merge into the deployment's
+/// tfvars. Fails for templates without customer-managed key support.
+#[tauri::command]
+pub fn encryption_key_variables(
+    template_id: String,
+    selection: EncryptionKeySelection,
+) -> Result<HashMap<String, serde_json::Value>, String> {
+    key_variables(&super::sanitize_template_id(&template_id)?, &selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn kms_aliases_map_to_key_arns() {
+        let json = json!({ "Aliases": [
+            { "AliasName": "alias/aws/s3", "AliasArn": "arn:aws:kms:us-east-1:123:alias/aws/s3", "TargetKeyId": "k0" },
+            { "AliasName": "alias/dbx-storage", "AliasArn": "arn:aws:kms:us-east-1:123:alias/dbx-storage", "TargetKeyId": "k1" },
+            { "AliasName": "alias/unused", "AliasArn": "arn:aws:kms:us-east-1:123:alias/unused" }
+        ]});
+        let keys = parse_kms_aliases(&json, "us-east-1");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].id, "arn:aws:kms:us-east-1:123:key/k1");
+        assert_eq!(keys[0].name, "dbx-storage");
+        assert_eq!(arn_region(&keys[0].id), Some("us-east-1"));
+    }
+
+    #[test]
+    fn key_policy_requires_databricks_actions() {
+        let granted = json!({ "Statement": [{
+            "Effect": "Allow",
+            "Principal": { "AWS": "arn:aws:iam::414351767826:root" },
+            "Action": ["kms:Encrypt", "kms:Decrypt", "kms:GenerateDataKey*"],
+            "Condition": { "StringEquals": { "aws:PrincipalTag/DatabricksAccountId": ["acct-1"] } }
+        }]});
+        assert_eq!(key_policy_entry(&granted, Some("acct-1")).status, "pass");
+        assert_eq!(key_policy_entry(&granted, Some("acct-2")).status, "fail");
+
+        let partial = json!({ "Statement": {
+            "Effect": "Allow",
+            "Principal": { "AWS": ["arn:aws:iam::414351767826:root"] },
+            "Action": "kms:Decrypt"
+        }});
+        let entry = key_policy_entry(&partial, None);
+        assert_eq!(entry.status, "fail");
+        assert!(entry.message.contains("kms:Encrypt, kms:GenerateDataKey"));
+
+        let admin_only = json!({ "Statement": [{
+            "Effect": "Allow", "Principal": { "AWS": "arn:aws:iam::123:root" }, "Action": "kms:*"
+        }]});
+        assert_eq!(key_policy_entry(&admin_only, None).status, "fail");
+    }
+
+    #[test]
+    fn azure_access_policy_checks_databricks_permissions() {
+        let vault = json!({ "properties": { "accessPolicies": [
+            { "objectId": "SP-1", "permissions": { "keys": ["Get", "WrapKey", "UnwrapKey"] } }
+        ]}});
+        assert_eq!(azure_access_entry(&vault, Some("sp-1")).status, "pass");
+        assert_eq!(azure_access_entry(&vault, Some("sp-2")).status, "fail");
+        let rbac = json!({ "properties": { "enableRbacAuthorization": true } });
+        assert_eq!(azure_access_entry(&rbac, Some("sp-1")).status, "skipped");
+        assert_eq!(vault_name("https://myvault.vault.azure.net/keys/k"), Some("myvault"));
+    }
+
+    #[test]
+    fn gcp_keys_filter_enabled_encryption_keys() {
+        let ring = "projects/p/locations/us-central1/keyRings/r";
+        let json = json!([
+            { "name": format!("{}/cryptoKeys/a", ring), "purpose": "ENCRYPT_DECRYPT", "primary": { "state": "ENABLED" } },
+            { "name": format!("{}/cryptoKeys/b", ring), "purpose": "ASYMMETRIC_SIGN" },
+            { "name": format!("{}/cryptoKeys/c", ring), "purpose": "ENCRYPT_DECRYPT", "primary": { "state": "DISABLED" } }
+        ]);
+        let keys = parse_gcp_keys(&json, ring);
+        assert_eq!(keys.len(), 1);
+        assert_eq!((keys[0].name.as_str(), keys[0].detail.as_str()), ("a", "r"));
+        assert_eq!(gcp_key_location(&keys[0].id), Some("us-central1"));
+        assert_eq!(gcp_key_location("projects/p/keyRings/r"), None);
+    }
+
+    #[test]
+    fn gcp_key_policy_checks_service_account() {
+        let policy = json!({ "bindings": [
+            { "role": "roles/cloudkms.viewer", "members": ["serviceAccount:viewer@p.iam.gserviceaccount.com"] },
+            { "role": "roles/cloudkms.cryptoKeyEncrypterDecrypter", "members": ["serviceAccount:DBX@p.iam.gserviceaccount.com"] }
+        ]});
+        assert_eq!(gcp_access_entry(&policy, Some("dbx@p.iam.gserviceaccount.com")).status, "pass");
+        assert_eq!(gcp_access_entry(&policy, Some("viewer@p.iam.gserviceaccount.com")).status, "fail");
+        assert_eq!(gcp_access_entry(&json!({}), Some("dbx@p.iam.gserviceaccount.com")).status, "fail");
+        assert_eq!(gcp_access_entry(&policy, None).status, "skipped");
+    }
+
+    #[test]
+    fn key_variables_per_template() {
+        let selection = EncryptionKeySelection {
+            workspace_storage_key: Some("arn:storage".to_string()),
+            managed_services_key: Some("arn:managed".to_string()),
+            key_vault_id: Some("/subscriptions/s/vaults/v".to_string()),
+        };
+        let aws = key_variables("aws-sra", &selection).unwrap();
+        assert_eq!(aws["existing_workspace_storage_key_arn"], "arn:storage");
+        assert_eq!(aws["existing_managed_services_key_arn"], "arn:managed");
+
+        let azure = key_variables("azure-sra", &selection).unwrap();
+        assert_eq!(azure["existing_cmk_ids"]["managed_disk_key_id"], "arn:storage");
+        assert_eq!(azure["cmk_enabled"], true);
+        assert_eq!(azure["create_hub"], false);
+
+        assert!(key_variables("gcp-sra", &selection).is_err());
+        let gcp = EncryptionKeySelection { workspace_storage_key: Some("projects/p/k".to_string()), ..Default::default() };
+        assert_eq!(key_variables("gcp-sra", &gcp).unwrap()["kms_key_id"], "projects/p/k");
+
+        assert!(key_variables("aws-simple", &selection).is_err());
+    }
+
+    #[test]
+    fn aws_template_declares_key_variables() {
+        let variables = include_str!("../../templates/aws-sra/variables.tf");
+        assert!(variables.contains("variable \"existing_workspace_storage_key_arn\""));
+        assert!(variables.contains("variable \"existing_managed_services_key_arn\""));
+        let gcp = include_str!("../../templates/gcp-sra/variables.tf");
+        assert!(gcp.contains("variable \"kms_key_id\""));
+    }
+}
@@ -0,0 +1,270 @@
+//! Tool versions used by the last successful apply.
+//!
+//! After each successful apply the engine version, the provider versions from
+//! the dependency lock file, the cloud and Databricks CLI versions and the OS
+//! are recorded in the deployment metadata. [`compare_environment`] captures
+//! the same details now and reports the differences that can change what a
+//! later plan, apply or destroy does.
+
+use super::{debug_log, get_deployments_dir, sanitize_deployment_name};
+use crate::dependencies::{self, DependencyStatus};
+use crate::terraform::{self, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+/// A named tool or provider and its version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// Environment a Terraform run used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentSnapshot {
+    pub captured_at: u64,
+    /// `std::env::consts` names, e.g. "macos" / "aarch64".
+    pub os: String,
+    pub arch: String,
+    pub cloud: Option<String>,
+    /// "terraform" or "tofu".
+    pub engine: String,
+    pub engine_version: Option<String>,
+    /// Registry address and version of each provider in the lock file.
+    pub providers: Vec<ToolVersion>,
+    /// Installed Databricks and cloud CLIs.
+    pub clis: Vec<ToolVersion>,
+}
+
+/// One material difference between the recorded and current environment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnvironmentDifference {
+    pub component: String,
+    pub recorded: Option<String>,
+    pub current: Option<String>,
+    pub message: String,
+}
+
+/// Result of [`compare_environment`].
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvironmentComparison {
+    /// `None` when no successful apply has been recorded yet.
+    pub recorded: Option<EnvironmentSnapshot>,
+    pub current: EnvironmentSnapshot,
+    pub differences: Vec<EnvironmentDifference>,
+}
+
+// ─── Capture ────────────────────────────────────────────────────────────────
+
+fn cli_checks(cloud: Option<&str>) -> Vec<fn() -> DependencyStatus> {
+    let mut checks: Vec<fn() -> DependencyStatus> = vec![dependencies::check_databricks_cli];
+    match cloud {
+        Some("aws") => checks.push(dependencies::check_aws_cli),
+        Some("azure") => checks.push(dependencies::check_azure_cli),
+        Some("gcp") => checks.push(dependencies::check_gcloud_cli),
+        _ => {}
+    }
+    checks
+}
+
+/// Versions in use for `deployment_dir` right now. Runs each CLI once.
+fn capture(deployment_dir: &Path, cloud: Option<&str>) -> EnvironmentSnapshot {
+    let engine = terraform::deployment_engine(deployment_dir);
+    let engine_status = match engine {
+        Engine::Terraform => dependencies::check_terraform(),
+        Engine::Tofu => dependencies::check_tofu(),
+    };
+    let providers = super::provider_locks::read_locked_providers(deployment_dir)
+        .into_iter()
+        .filter_map(|p| Some(ToolVersion { name: p.source, version: p.version? }))
+        .collect();
+    let clis = cli_checks(cloud)
+        .into_iter()
+        .map(|check| check())
+        .filter(|status| status.installed)
+        .filter_map(|status| Some(ToolVersion { name: status.name, version: status.version? }))
+        .collect();
+
+    EnvironmentSnapshot {
+        captured_at: super::audit::now_secs(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cloud: cloud.map(str::to_string),
+        engine: engine.binary_name().to_string(),
+        engine_version: engine_status.version,
+        providers,
+        clis,
+    }
+}
+
+/// Store the current environment as the one the last successful apply used.
+/// Logged, not raised.
+pub(crate) fn record_apply_environment(deployment_dir: &Path, cloud: Option<&str>) {
+    let Some(mut meta) = super::audit::read_meta(deployment_dir) else {
+        return;
+    };
+    meta.last_apply_environment = Some(capture(deployment_dir, cloud));
+    if let Err(_e) = super::audit::write_meta(deployment_dir, &meta) {
+        debug_log!("[environment_snapshot] Failed to record apply environment: {}", _e);
+    }
+}
+
+// ─── Comparison ─────────────────────────────────────────────────────────────
+
+/// Whether a version change can alter a run: a new minor release for the
+/// engine and providers, a new major release for CLIs. Unparseable versions
+/// count when they differ at all.
+fn materially_different(recorded: &str, current: &str, major_only: bool) -> bool {
+    match (dependencies::parse_version(recorded), dependencies::parse_version(current)) {
+        (Some(a), Some(b)) if major_only => a.0 != b.0,
+        (Some(a), Some(b)) => (a.0, a.1) != (b.0, b.1),
+        _ => recorded != current,
+    }
+}
+
+fn difference(component: &str, recorded: Option<&str>, current: Option<&str>) -> EnvironmentDifference {
+    let message = match (recorded, current) {
+        (Some(r), Some(c)) => format!("{} was {} at the last apply and is now {}", component, r, c),
+        (Some(r), None) => format!("{} {} was used at the last apply but is no longer available", component, r),
+        (None, Some(c)) => format!("{} {} was not used at the last apply", component, c),
+        (None, None) => format!("{} has changed since the last apply", component),
+    };
+    EnvironmentDifference {
+        component: component.to_string(),
+        recorded: recorded.map(str::to_string),
+        current: current.map(str::to_string),
+        message,
+    }
+}
+
+/// Version pairs by name that differ materially, including ones only on one side.
+fn compare_versions(recorded: &[ToolVersion], current: &[ToolVersion], major_only: bool) -> Vec<EnvironmentDifference> {
+    let find = |list: &[ToolVersion], name: &str| list.iter().find(|t| t.name == name).map(|t| t.version.clone());
+    let mut differences: Vec<EnvironmentDifference> = recorded
+        .iter()
+        .filter_map(|r| match find(current, &r.name) {
+            Some(c) if !materially_different(&r.version, &c, major_only) => None,
+            c => Some(difference(&r.name, Some(r.version.as_str()), c.as_deref())),
+        })
+        .collect();
+    differences.extend(
+        current
+            .iter()
+            .filter(|c| find(recorded, &c.name).is_none())
+            .map(|c| difference(&c.name, None, Some(c.version.as_str()))),
+    );
+    differences
+}
+
+/// Material differences between the environment of the last apply and now.
+/// CLIs that were not installed at the last apply are not reported.
+fn differences(recorded: &EnvironmentSnapshot, current: &EnvironmentSnapshot) -> Vec<EnvironmentDifference> {
+    let mut differences = Vec::new();
+    if (&recorded.os, &recorded.arch) != (&current.os, &current.arch) {
+        differences.push(difference(
+            "Platform",
+            Some(format!("{}/{}", recorded.os, recorded.arch).as_str()),
+            Some(format!("{}/{}", current.os, current.arch).as_str()),
+        ));
+    }
+    if recorded.engine != current.engine {
+        differences.push(difference("Engine", Some(recorded.engine.as_str()), Some(current.engine.as_str())));
+    } else if recorded.engine_version.as_deref() != current.engine_version.as_deref() {
+        let changed = match (&recorded.engine_version, &current.engine_version) {
+            (Some(r), Some(c)) => materially_different(r, c, false),
+            _ => true,
+        };
+        if changed {
+            differences.push(difference(
+                &recorded.engine,
+                recorded.engine_version.as_deref(),
+                current.engine_version.as_deref(),
+            ));
+        }
+    }
+    differences.extend(compare_versions(&recorded.providers, &current.providers, false));
+    differences.extend(
+        compare_versions(&recorded.clis, &current.clis, true)
+            .into_iter()
+            .filter(|d| d.recorded.is_some()),
+    );
+    differences
+}
+
+/// Compare the tool versions a run would use now with the ones recorded at
+/// the deployment's last successful apply.
+#[tauri::command]
+pub async fn compare_environment(app: AppHandle, deployment_name: String) -> Result<EnvironmentComparison, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.is_dir() {
+        return Err("Deployment not found".to_string());
+    }
+    super::run_blocking(move || {
+        let recorded = super::audit::read_meta(&deployment_dir).and_then(|m| m.last_apply_environment);
+        let cloud = recorded.as_ref().and_then(|r| r.cloud.clone());
+        let current = capture(&deployment_dir, cloud.as_deref());
+        let differences = recorded.as_ref().map(|r| differences(r, &current)).unwrap_or_default();
+        Ok(EnvironmentComparison { recorded, current, differences })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, version: &str) -> ToolVersion {
+        ToolVersion { name: name.to_string(), version: version.to_string() }
+    }
+
+    fn snapshot(engine_version: &str, providers: Vec<ToolVersion>, clis: Vec<ToolVersion>) -> EnvironmentSnapshot {
+        EnvironmentSnapshot {
+            captured_at: 0,
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cloud: Some("aws".to_string()),
+            engine: "terraform".to_string(),
+            engine_version: Some(engine_version.to_string()),
+            providers,
+            clis,
+        }
+    }
+
+    const DATABRICKS: &str = "registry.terraform.io/databricks/databricks";
+    const AWS: &str = "registry.terraform.io/hashicorp/aws";
+
+    #[test]
+    fn patch_releases_are_not_material() {
+        let recorded = snapshot("1.9.5", vec![tool(DATABRICKS, "1.58.0")], vec![tool("AWS CLI", "2.15.0")]);
+        let current = snapshot("1.9.8", vec![tool(DATABRICKS, "1.58.3")], vec![tool("AWS CLI", "2.17.1")]);
+        assert!(differences(&recorded, &current).is_empty());
+    }
+
+    #[test]
+    fn reports_engine_provider_cli_and_platform_changes() {
+        let recorded = snapshot(
+            "1.9.5",
+            vec![tool(DATABRICKS, "1.58.0"), tool(AWS, "5.80.0")],
+            vec![tool("AWS CLI", "2.15.0"), tool("Databricks CLI", "0.240.0")],
+        );
+        let mut current = snapshot("1.10.0", vec![tool(DATABRICKS, "1.60.0")], vec![tool("AWS CLI", "3.0.0")]);
+        current.arch = "aarch64".to_string();
+
+        let components: Vec<String> = differences(&recorded, &current).into_iter().map(|d| d.component).collect();
+        assert_eq!(components, vec!["Platform", "terraform", DATABRICKS, AWS, "AWS CLI", "Databricks CLI"]);
+
+        let removed = differences(&recorded, &current).into_iter().find(|d| d.component == AWS).unwrap();
+        assert_eq!(removed.current, None);
+        assert!(removed.message.contains("no longer available"));
+    }
+
+    #[test]
+    fn newly_installed_clis_are_not_reported() {
+        let recorded = snapshot("1.9.5", vec![], vec![]);
+        let current = snapshot("1.9.5", vec![tool(AWS, "5.80.0")], vec![tool("AWS CLI", "2.15.0")]);
+        let components: Vec<String> = differences(&recorded, &current).into_iter().map(|d| d.component).collect();
+        assert_eq!(components, vec![AWS]);
+    }
+}
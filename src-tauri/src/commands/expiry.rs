@@ -0,0 +1,397 @@
+//! Scheduled auto-destroy for sandbox deployments.
+//!
+//! A deployment can carry an expiry (`.deployer-expiry.json` in its directory).
+//! On launch the app scans for expired deployments: unattended ones are
+//! destroyed with credentials resolved from the environment plus the
+//! non-secret hints saved with the expiry; the rest are reported to the UI so
+//! the user can confirm the destroy.
+
+use super::audit::now_secs;
+use super::{
+    debug_log, get_deployments_dir, lock_or_recover, sanitize_deployment_name, CloudCredentials,
+};
+use crate::terraform::{self, DEPLOYMENT_STATUS};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Marker file in a deployment directory recording its expiry.
+const EXPIRY_FILE: &str = ".deployer-expiry.json";
+
+/// Event emitted after the launch scan with deployments awaiting confirmation.
+pub const EXPIRED_DEPLOYMENTS_EVENT: &str = "deployments-expired";
+
+/// Event emitted when an unattended auto-destroy finishes.
+pub const AUTO_DESTROY_EVENT: &str = "deployment-auto-destroy";
+
+/// Longest TTL accepted (90 days); longer-lived deployments shouldn't use auto-destroy.
+const MAX_TTL_HOURS: u64 = 24 * 90;
+
+/// Expiry requested when saving a configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpirySettings {
+    pub ttl_hours: u64,
+    /// Destroy without asking when the app starts after expiry.
+    #[serde(default)]
+    pub unattended: bool,
+}
+
+/// Expiry stored alongside a deployment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentExpiry {
+    /// Unix timestamp (seconds).
+    pub expires_at: u64,
+    pub unattended: bool,
+    /// Credentials with secrets removed (profiles, tenant, project, account ID),
+    /// merged with environment credentials for unattended destroys.
+    #[serde(default)]
+    pub credentials_hint: Option<CloudCredentials>,
+}
+
+/// A deployment past its expiry.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiredDeployment {
+    pub name: String,
+    pub expires_at: u64,
+    pub unattended: bool,
+    pub cloud: Option<String>,
+}
+
+/// Outcome of an unattended auto-destroy.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoDestroyResult {
+    pub name: String,
+    pub success: bool,
+    pub message: String,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Copy of the credentials without secrets, tokens, or key material.
+fn strip_secrets(creds: &CloudCredentials) -> CloudCredentials {
+    CloudCredentials {
+        aws_profile: creds.aws_profile.clone(),
+        aws_region: creds.aws_region.clone(),
+        azure_tenant_id: creds.azure_tenant_id.clone(),
+        azure_subscription_id: creds.azure_subscription_id.clone(),
+        azure_databricks_use_identity: creds.azure_databricks_use_identity,
+        azure_account_email: creds.azure_account_email.clone(),
+        gcp_project_id: creds.gcp_project_id.clone(),
+        gcp_use_adc: creds.gcp_use_adc,
+        gcp_service_account_email: creds.gcp_service_account_email.clone(),
+        databricks_account_id: creds.databricks_account_id.clone(),
+        databricks_profile: creds.databricks_profile.clone(),
+        databricks_auth_type: creds.databricks_auth_type.clone(),
+        cloud: creds.cloud.clone(),
+        ..Default::default()
+    }
+}
+
+/// Fill fields missing from `resolved` with the saved hint.
+fn merge_hint(mut resolved: CloudCredentials, hint: &CloudCredentials) -> CloudCredentials {
+    macro_rules! fill {
+        ($($field:ident),*) => {
+            $(if resolved.$field.is_none() {
+                resolved.$field = hint.$field.clone();
+            })*
+        };
+    }
+    fill!(
+        aws_profile,
+        aws_region,
+        azure_tenant_id,
+        azure_subscription_id,
+        azure_databricks_use_identity,
+        azure_account_email,
+        gcp_project_id,
+        gcp_use_adc,
+        gcp_service_account_email,
+        databricks_account_id,
+        databricks_profile,
+        databricks_auth_type,
+        cloud
+    );
+    resolved
+}
+
+pub(crate) fn read_expiry(deployment_dir: &Path) -> Option<DeploymentExpiry> {
+    let content = fs::read_to_string(deployment_dir.join(EXPIRY_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Record (or with `None`, clear) the expiry for a deployment.
+pub(crate) fn write_expiry(
+    deployment_dir: &Path,
+    settings: Option<&ExpirySettings>,
+    credentials: Option<&CloudCredentials>,
+) -> Result<Option<DeploymentExpiry>, String> {
+    let marker = deployment_dir.join(EXPIRY_FILE);
+    let Some(settings) = settings else {
+        clear_expiry(deployment_dir);
+        return Ok(None);
+    };
+
+    if settings.ttl_hours == 0 || settings.ttl_hours > MAX_TTL_HOURS {
+        return Err(format!("Expiry must be between 1 and {} hours", MAX_TTL_HOURS));
+    }
+
+    let expiry = DeploymentExpiry {
+        expires_at: now_secs() + settings.ttl_hours * 3600,
+        unattended: settings.unattended,
+        credentials_hint: credentials.map(strip_secrets),
+    };
+    let json = serde_json::to_string_pretty(&expiry)
+        .map_err(|e| format!("Failed to serialize expiry: {}", e))?;
+    fs::write(&marker, json).map_err(|e| format!("Failed to save expiry: {}", e))?;
+    Ok(Some(expiry))
+}
+
+/// Remove the expiry marker, e.g. after a successful destroy.
+pub(crate) fn clear_expiry(deployment_dir: &Path) {
+    let marker = deployment_dir.join(EXPIRY_FILE);
+    if marker.exists() {
+        let _ = fs::remove_file(marker);
+    }
+}
+
+/// Expired deployments under `deployments_dir`. Expired deployments that no
+/// longer have state (already destroyed) have their marker cleared instead.
+fn scan_expired(deployments_dir: &Path, now: u64) -> Vec<(ExpiredDeployment, DeploymentExpiry)> {
+    let Ok(entries) = fs::read_dir(deployments_dir) else {
+        return Vec::new();
+    };
+
+    let mut expired: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter_map(|dir| {
+            let expiry = read_expiry(&dir)?;
+            if expiry.expires_at > now {
+                return None;
+            }
+            if !terraform::check_state_exists(&dir) {
+                clear_expiry(&dir);
+                return None;
+            }
+            let name = dir.file_name()?.to_string_lossy().to_string();
+            let cloud = expiry.credentials_hint.as_ref().and_then(|c| c.cloud.clone());
+            Some((
+                ExpiredDeployment {
+                    name,
+                    expires_at: expiry.expires_at,
+                    unattended: expiry.unattended,
+                    cloud,
+                },
+                expiry,
+            ))
+        })
+        .collect();
+    expired.sort_by_key(|(d, _)| d.expires_at);
+    expired
+}
+
+/// Block until the current Terraform run finishes; returns its success.
+pub(super) fn wait_for_deployment() -> bool {
+    loop {
+        {
+            let status = lock_or_recover(&DEPLOYMENT_STATUS);
+            if !status.running {
+                return status.success == Some(true);
+            }
+        }
+        std::thread::sleep(Duration::from_secs(2));
+    }
+}
+
+fn auto_destroy(app: &AppHandle, deployment: &ExpiredDeployment, expiry: &DeploymentExpiry) -> AutoDestroyResult {
+    let hint = expiry.credentials_hint.clone().unwrap_or_default();
+    let Some(cloud) = hint.cloud.clone() else {
+        return AutoDestroyResult {
+            name: deployment.name.clone(),
+            success: false,
+            message: "No cloud recorded for this deployment; destroy it manually.".to_string(),
+        };
+    };
+
+    let credentials = match super::get_cloud_credentials(cloud) {
+        Ok(resolved) => merge_hint(resolved, &hint),
+        Err(e) => {
+            return AutoDestroyResult { name: deployment.name.clone(), success: false, message: e };
+        }
+    };
+
+    // No confirmation: resources that hold data are never destroyed unattended
+    let started = tauri::async_runtime::block_on(super::run_terraform_command(
+        app.clone(),
+        deployment.name.clone(),
+        "destroy".to_string(),
+        credentials,
+        None,
+        None,
+        None,
+    ));
+    if let Err(e) = started {
+        return AutoDestroyResult { name: deployment.name.clone(), success: false, message: e };
+    }
+
+    let success = wait_for_deployment();
+    AutoDestroyResult {
+        name: deployment.name.clone(),
+        success,
+        message: if success {
+            "Expired deployment destroyed.".to_string()
+        } else {
+            "Auto-destroy failed. Open the deployment to review the output.".to_string()
+        },
+    }
+}
+
+/// Launch-time scheduler: destroy unattended expired deployments, then report
+/// the ones that need confirmation. Runs on a background thread.
+pub fn run_expiry_scheduler(app: &AppHandle) {
+    let Ok(deployments_dir) = get_deployments_dir(app) else {
+        return;
+    };
+
+    let mut awaiting_confirmation = Vec::new();
+    for (deployment, expiry) in scan_expired(&deployments_dir, now_secs()) {
+        if !deployment.unattended {
+            awaiting_confirmation.push(deployment);
+            continue;
+        }
+        debug_log!("[expiry] Auto-destroying expired deployment {}", deployment.name);
+        let result = auto_destroy(app, &deployment, &expiry);
+        if !result.success {
+            debug_log!("[expiry] Auto-destroy of {} failed: {}", result.name, result.message);
+            awaiting_confirmation.push(deployment);
+        }
+        let _ = app.emit(AUTO_DESTROY_EVENT, &result);
+    }
+
+    if !awaiting_confirmation.is_empty() {
+        let _ = app.emit(EXPIRED_DEPLOYMENTS_EVENT, &awaiting_confirmation);
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Get the expiry of a deployment, if one is set.
+#[tauri::command]
+pub fn get_deployment_expiry(app: AppHandle, deployment_name: String) -> Result<Option<DeploymentExpiry>, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    Ok(read_expiry(&deployment_dir))
+}
+
+/// Set or clear (`settings: None`) the expiry of an existing deployment.
+#[tauri::command]
+pub fn set_deployment_expiry(
+    app: AppHandle,
+    deployment_name: String,
+    settings: Option<ExpirySettings>,
+    credentials: Option<CloudCredentials>,
+) -> Result<Option<DeploymentExpiry>, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found. Please save configuration first.".to_string());
+    }
+    write_expiry(&deployment_dir, settings.as_ref(), credentials.as_ref())
+}
+
+/// List deployments past their expiry that still have resources.
+#[tauri::command]
+pub fn list_expired_deployments(app: AppHandle) -> Result<Vec<ExpiredDeployment>, String> {
+    let deployments_dir = get_deployments_dir(&app)?;
+    Ok(scan_expired(&deployments_dir, now_secs())
+        .into_iter()
+        .map(|(d, _)| d)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(ttl_hours: u64) -> ExpirySettings {
+        ExpirySettings { ttl_hours, unattended: false }
+    }
+
+    #[test]
+    fn write_and_clear_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let expiry = write_expiry(dir.path(), Some(&settings(2)), None).unwrap().unwrap();
+        assert!(expiry.expires_at >= now_secs() + 2 * 3600 - 5);
+        assert_eq!(read_expiry(dir.path()).unwrap().expires_at, expiry.expires_at);
+
+        assert!(write_expiry(dir.path(), None, None).unwrap().is_none());
+        assert!(read_expiry(dir.path()).is_none());
+    }
+
+    #[test]
+    fn write_expiry_rejects_out_of_range_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(write_expiry(dir.path(), Some(&settings(0)), None).is_err());
+        assert!(write_expiry(dir.path(), Some(&settings(MAX_TTL_HOURS + 1)), None).is_err());
+    }
+
+    #[test]
+    fn strip_secrets_keeps_only_hints() {
+        let creds = CloudCredentials {
+            aws_profile: Some("sandbox".to_string()),
+            aws_secret_access_key: Some("secret".to_string()),
+            databricks_client_secret: Some("dbx-secret".to_string()),
+            gcp_oauth_token: Some("ya29".to_string()),
+            cloud: Some("aws".to_string()),
+            ..Default::default()
+        };
+        let hint = strip_secrets(&creds);
+        assert_eq!(hint.aws_profile.as_deref(), Some("sandbox"));
+        assert_eq!(hint.cloud.as_deref(), Some("aws"));
+        assert!(hint.aws_secret_access_key.is_none());
+        assert!(hint.databricks_client_secret.is_none());
+        assert!(hint.gcp_oauth_token.is_none());
+    }
+
+    #[test]
+    fn merge_hint_prefers_resolved_values() {
+        let resolved = CloudCredentials {
+            aws_region: Some("us-east-1".to_string()),
+            ..Default::default()
+        };
+        let hint = CloudCredentials {
+            aws_region: Some("eu-west-1".to_string()),
+            aws_profile: Some("sandbox".to_string()),
+            ..Default::default()
+        };
+        let merged = merge_hint(resolved, &hint);
+        assert_eq!(merged.aws_region.as_deref(), Some("us-east-1"));
+        assert_eq!(merged.aws_profile.as_deref(), Some("sandbox"));
+    }
+
+    #[test]
+    fn scan_expired_skips_future_and_clears_destroyed() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |name: &str, expires_at: u64, with_state: bool| {
+            let dir = root.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            let expiry = DeploymentExpiry { expires_at, unattended: false, credentials_hint: None };
+            fs::write(dir.join(EXPIRY_FILE), serde_json::to_string(&expiry).unwrap()).unwrap();
+            if with_state {
+                fs::write(dir.join("terraform.tfstate"), r#"{"resources":[{"type":"x"}]}"#).unwrap();
+            }
+            dir
+        };
+        write("expired", 100, true);
+        write("future", 10_000, true);
+        let destroyed = write("destroyed", 100, false);
+
+        let expired = scan_expired(root.path(), 1_000);
+        let names: Vec<&str> = expired.iter().map(|(d, _)| d.name.as_str()).collect();
+        assert_eq!(names, vec!["expired"]);
+        assert!(read_expiry(&destroyed).is_none());
+    }
+}
@@ -1,18 +1,49 @@
 //! GCP authentication, permission checking, and service account management commands.
+//!
+//! The app never writes the user's gcloud configuration. Impersonation is
+//! per invocation: `--impersonate-service-account` with the service account
+//! from the credentials, or `CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT`
+//! cleared when a command must run as the user. An impersonation the user
+//! configured themselves is only read.
 
 use super::debug_log;
+use super::cancellation::CancelToken;
+use super::cli_runner::{CliCommand, CliTool};
 use super::{http_client, is_valid_uuid, CLI_LOGIN_PROCESS};
 #[cfg(debug_assertions)]
 use super::mask_sensitive_id;
-use super::{CloudCredentials, CloudPermissionCheck};
+use super::bootstrap_artifacts::{self, ArtifactKind};
+use super::preflight::BLOCKER;
+use super::{CloudCredentials, CloudPermissionCheck, PreflightEntry, PreflightReport};
+use crate::databricks_api::{self, AccountAuth, AccountClient, ApiError};
 use crate::dependencies;
+use crate::endpoints::https_url;
+use crate::storage::Environment;
 use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Guide to editing custom IAM roles, linked from permission check remediation.
+const GCP_CUSTOM_ROLES_DOCS_URL: &str = "https://cloud.google.com/iam/docs/creating-custom-roles";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GcpProject {
     pub project_id: String,
     pub name: String,
     pub state: String,
+    #[serde(default)]
+    pub project_number: String,
+}
+
+impl GcpProject {
+    /// From a Resource Manager v1 project, as returned by the API and `gcloud projects list`.
+    fn from_json(p: &serde_json::Value) -> Self {
+        Self {
+            project_id: p["projectId"].as_str().unwrap_or("").to_string(),
+            name: p["name"].as_str().unwrap_or("").to_string(),
+            state: p["lifecycleState"].as_str().unwrap_or("").to_string(),
+            project_number: p["projectNumber"].as_str().unwrap_or("").to_string(),
+        }
+    }
 }
 
 /// Required GCP permissions for Databricks workspace deployment.
@@ -82,17 +113,25 @@ pub struct GcpValidation {
     pub impersonated_account: Option<String>,
 }
 
+/// Result of checking an existing service account for reuse.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExistingServiceAccountCheck {
+    pub service_account: String,
+    /// Required permissions the service account lacks on the project.
+    pub missing_permissions: Vec<String>,
+    /// Whether the service account can be impersonated for deployments.
+    pub ready: bool,
+    pub message: String,
+}
+
 // ─── Helpers ────────────────────────────────────────────────────────────────
 
 /// Create a skipped permission check result with a reason message.
 fn skip_gcp_permission_check(reason: &str) -> CloudPermissionCheck {
-    CloudPermissionCheck {
-        has_all_permissions: true,
-        checked_permissions: vec![],
-        missing_permissions: vec![],
-        message: format!("{}. Permission check skipped.", reason),
-        is_warning: true,
-    }
+    CloudPermissionCheck::skipped(PreflightEntry::skipped(
+        "IAM permissions",
+        &format!("{}. Permission check skipped.", reason),
+    ))
 }
 
 /// Generate an OAuth access token from a service account JSON key (no gcloud needed).
@@ -167,7 +206,7 @@ async fn generate_gcp_token_from_json_key(sa_json: &str) -> Result<String, Strin
 
 /// Get GCP OAuth token using multiple fallback methods.
 /// Priority: 1) Existing token in credentials, 2) Generate from JSON key, 3) gcloud CLI.
-async fn get_gcp_oauth_token(
+pub(super) async fn get_gcp_oauth_token(
     credentials: &CloudCredentials,
 ) -> Result<(String, Option<String>), String> {
     // Method 1: Use existing OAuth token from credentials
@@ -203,40 +242,58 @@ async fn get_gcp_oauth_token(
 
     debug_log!("[check_gcp_permissions] Falling back to gcloud CLI for token");
 
-    let impersonate_output = super::silent_cmd(&gcloud_cli)
+    let service_account = credentials.gcp_service_account_email.clone();
+    super::run_blocking(move || gcloud_access_token(&gcloud_cli, service_account)).await
+}
+
+/// gcloud command that runs as the signed-in user, ignoring any
+/// `auth/impersonate_service_account` in the user's configuration.
+pub(super) fn gcloud_as_user(gcloud_cli: &std::path::Path) -> CliCommand {
+    let mut cmd = CliCommand::with_path(CliTool::Gcloud, gcloud_cli);
+    cmd.env("CLOUDSDK_AUTH_IMPERSONATE_SERVICE_ACCOUNT", "");
+    cmd
+}
+
+/// Impersonation the user configured in gcloud themselves, if any.
+fn configured_impersonation(gcloud_cli: &std::path::Path) -> Option<String> {
+    CliCommand::with_path(CliTool::Gcloud, gcloud_cli)
         .args(["config", "get-value", "auth/impersonate_service_account"])
-        .output()
-        .ok();
+        .run()
+        .ok()
+        .filter(|o| o.success)
+        .and_then(|o| o.value())
+}
 
-    let impersonated_account = impersonate_output
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .filter(|s| !s.is_empty() && s != "(unset)");
+/// Access token from the gcloud CLI, impersonating `service_account` (or the
+/// user's own configured impersonation) when there is one.
+fn gcloud_access_token(
+    gcloud_cli: &std::path::Path,
+    service_account: Option<String>,
+) -> Result<(String, Option<String>), String> {
+    let impersonated_account = service_account
+        .filter(|s| !s.is_empty())
+        .or_else(|| configured_impersonation(gcloud_cli));
 
     let token_output = if let Some(ref sa_email) = impersonated_account {
-        super::silent_cmd(&gcloud_cli)
+        gcloud_as_user(gcloud_cli)
             .args([
                 "auth",
                 "print-access-token",
                 "--impersonate-service-account",
                 sa_email,
             ])
-            .output()
-            .map_err(|e| format!("Failed to get impersonated token: {}", e))?
+            .run()?
     } else {
-        super::silent_cmd(&gcloud_cli)
+        gcloud_as_user(gcloud_cli)
             .args(["auth", "print-access-token"])
-            .output()
-            .map_err(|e| format!("Failed to get access token: {}", e))?
+            .run()?
     };
 
-    if !token_output.status.success() {
+    if !token_output.success {
         return Err("Failed to get access token from gcloud CLI".to_string());
     }
 
-    let token = String::from_utf8_lossy(&token_output.stdout)
-        .trim()
-        .to_string();
+    let token = token_output.stdout.trim().to_string();
     Ok((token, impersonated_account))
 }
 
@@ -246,6 +303,12 @@ async fn get_gcp_oauth_token(
 #[tauri::command]
 pub async fn validate_gcp_credentials(
     credentials: CloudCredentials,
+) -> Result<GcpValidation, String> {
+    super::run_blocking(move || validate_gcp_credentials_blocking(credentials)).await
+}
+
+fn validate_gcp_credentials_blocking(
+    credentials: CloudCredentials,
 ) -> Result<GcpValidation, String> {
     let gcloud_cli = dependencies::find_gcloud_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
@@ -253,147 +316,57 @@ pub async fn validate_gcp_credentials(
     let use_adc = credentials.gcp_use_adc.unwrap_or(true);
 
     if use_adc {
-        // Check for service account impersonation
-        let mut impersonate_cmd = super::silent_cmd(&gcloud_cli);
-        impersonate_cmd.args(["config", "get-value", "auth/impersonate_service_account"]);
-
-        let impersonate_output = impersonate_cmd
-            .output()
-            .map_err(|e| format!("Failed to check impersonated account: {}", e))?;
-
-        let impersonated_account = if impersonate_output.status.success() {
-            let acc = String::from_utf8_lossy(&impersonate_output.stdout)
-                .trim()
-                .to_string();
-            if acc.is_empty() || acc == "(unset)" {
-                None
-            } else {
-                Some(acc)
-            }
-        } else {
-            None
-        };
-
-        // Get OAuth access token (handle impersonation correctly)
-        let oauth_token = if impersonated_account.is_some() {
-            // Temporarily unset impersonation to get the user's actual token
-            let _ = super::silent_cmd(&gcloud_cli)
-                .args(["config", "unset", "auth/impersonate_service_account"])
-                .output();
-
-            let mut token_cmd = super::silent_cmd(&gcloud_cli);
-            token_cmd.args(["auth", "print-access-token"]);
-
-            let token_output = token_cmd
-                .output()
-                .map_err(|e| format!("Failed to run gcloud: {}", e))?;
-
-            // Restore impersonation immediately
-            if let Some(ref sa) = impersonated_account {
-                let _ = super::silent_cmd(&gcloud_cli)
-                    .args(["config", "set", "auth/impersonate_service_account", sa])
-                    .output();
-            }
-
-            if !token_output.status.success() {
-                let stderr = String::from_utf8_lossy(&token_output.stderr);
-                return Ok(GcpValidation {
-                    valid: false,
-                    project_id: None,
-                    account: None,
-                    message: format!(
-                        "No GCP credentials found. Please run 'gcloud auth login' first. Error: {}",
-                        stderr.trim()
-                    ),
-                    oauth_token: None,
-                    impersonated_account: None,
-                });
-            }
-
-            String::from_utf8_lossy(&token_output.stdout)
-                .trim()
-                .to_string()
-        } else {
-            let mut token_cmd = super::silent_cmd(&gcloud_cli);
-            token_cmd.args(["auth", "print-access-token"]);
+        // Service account to impersonate: the one in the credentials, else
+        // one the user configured in gcloud themselves
+        let impersonated_account = credentials
+            .gcp_service_account_email
+            .clone()
+            .filter(|s| !s.is_empty())
+            .or_else(|| configured_impersonation(&gcloud_cli));
 
-            let token_output = token_cmd
-                .output()
-                .map_err(|e| format!("Failed to run gcloud: {}", e))?;
+        // Get the user's own OAuth access token
+        let token_output = gcloud_as_user(&gcloud_cli)
+            .args(["auth", "print-access-token"])
+            .run()?;
 
-            if !token_output.status.success() {
-                let stderr = String::from_utf8_lossy(&token_output.stderr);
-                return Ok(GcpValidation {
-                    valid: false,
-                    project_id: None,
-                    account: None,
-                    message: format!(
-                        "No GCP credentials found. Please run 'gcloud auth login' first. Error: {}",
-                        stderr.trim()
-                    ),
-                    oauth_token: None,
-                    impersonated_account: None,
-                });
-            }
+        if !token_output.success {
+            let stderr = &token_output.stderr;
+            return Ok(GcpValidation {
+                valid: false,
+                project_id: None,
+                account: None,
+                message: format!(
+                    "No GCP credentials found. Please run 'gcloud auth login' first. Error: {}",
+                    stderr.trim()
+                ),
+                oauth_token: None,
+                impersonated_account: None,
+            });
+        }
 
-            String::from_utf8_lossy(&token_output.stdout)
-                .trim()
-                .to_string()
-        };
+        let oauth_token = token_output.stdout.trim().to_string();
 
         // Get current account
-        let mut account_cmd = super::silent_cmd(&gcloud_cli);
+        let mut account_cmd = CliCommand::with_path(CliTool::Gcloud, &gcloud_cli);
         account_cmd.args(["config", "get-value", "account"]);
 
-        let account_output = account_cmd
-            .output()
-            .map_err(|e| format!("Failed to get account: {}", e))?;
-
-        let account = if account_output.status.success() {
-            let acc = String::from_utf8_lossy(&account_output.stdout)
-                .trim()
-                .to_string();
-            if acc.is_empty() {
-                None
-            } else {
-                Some(acc)
-            }
-        } else {
-            None
-        };
+        let account_output = account_cmd.run()?;
+
+        let account = if account_output.success { account_output.value() } else { None };
 
         // Get default project
-        let mut project_cmd = super::silent_cmd(&gcloud_cli);
+        let mut project_cmd = CliCommand::with_path(CliTool::Gcloud, &gcloud_cli);
         project_cmd.args(["config", "get-value", "project"]);
 
-        let project_output = project_cmd
-            .output()
-            .map_err(|e| format!("Failed to get project: {}", e))?;
-
-        let project_id = if project_output.status.success() {
-            let proj = String::from_utf8_lossy(&project_output.stdout)
-                .trim()
-                .to_string();
-            if proj.is_empty() {
-                None
-            } else {
-                Some(proj)
-            }
-        } else {
-            None
-        };
+        let project_output = project_cmd.run()?;
+
+        let project_id = if project_output.success { project_output.value() } else { None };
 
         let final_project_id = credentials.gcp_project_id.clone().or(project_id);
 
         // Validate project exists
         if let Some(proj_id) = credentials.gcp_project_id.as_ref().filter(|s| !s.is_empty()) {
-            if impersonated_account.is_some() {
-                let _ = super::silent_cmd(&gcloud_cli)
-                    .args(["config", "unset", "auth/impersonate_service_account"])
-                    .output();
-            }
-
-            let mut describe_cmd = super::silent_cmd(&gcloud_cli);
+            let mut describe_cmd = gcloud_as_user(&gcloud_cli);
             describe_cmd.args([
                 "projects",
                 "describe",
@@ -401,18 +374,10 @@ pub async fn validate_gcp_credentials(
                 "--format=value(projectId)",
             ]);
 
-            let describe_output = describe_cmd
-                .output()
-                .map_err(|e| format!("Failed to validate project: {}", e))?;
+            let describe_output = describe_cmd.run()?;
 
-            if let Some(ref sa) = impersonated_account {
-                let _ = super::silent_cmd(&gcloud_cli)
-                    .args(["config", "set", "auth/impersonate_service_account", sa])
-                    .output();
-            }
-
-            if !describe_output.status.success() {
-                let stderr = String::from_utf8_lossy(&describe_output.stderr);
+            if !describe_output.success {
+                let stderr = &describe_output.stderr;
 
                 let error_msg = if stderr.contains("NOT_FOUND") || stderr.contains("not exist") {
                     format!(
@@ -511,36 +476,65 @@ pub async fn validate_gcp_credentials(
 /// List GCP projects accessible to the current authenticated user.
 #[tauri::command]
 pub fn get_gcp_projects() -> Result<Vec<GcpProject>, String> {
-    let gcloud_cli = dependencies::find_gcloud_cli_path()
-        .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
-
-    let output = super::silent_cmd(&gcloud_cli)
+    let output = CliCommand::new(CliTool::Gcloud)?
         .args(["projects", "list", "--format=json", "--sort-by=name"])
-        .output()
-        .map_err(|e| format!("Failed to list GCP projects: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gcloud error: {}", stderr.trim()));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: Vec<serde_json::Value> =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse response: {}", e))?;
+        .run_checked()?;
+    let json: Vec<serde_json::Value> = output.json()?;
 
     let projects: Vec<GcpProject> = json
         .iter()
         .filter(|p| p["lifecycleState"].as_str() == Some("ACTIVE"))
-        .map(|p| GcpProject {
-            project_id: p["projectId"].as_str().unwrap_or("").to_string(),
-            name: p["name"].as_str().unwrap_or("").to_string(),
-            state: p["lifecycleState"].as_str().unwrap_or("").to_string(),
-        })
+        .map(GcpProject::from_json)
         .collect();
 
     Ok(projects)
 }
 
+/// List GCP projects accessible to the given credentials via the Resource
+/// Manager API, with their number and lifecycle state. Works with a service
+/// account key or OAuth token as well as a gcloud login.
+#[tauri::command]
+pub async fn list_gcp_projects(credentials: CloudCredentials) -> Result<Vec<GcpProject>, String> {
+    let (token, _) = get_gcp_oauth_token(&credentials).await?;
+    let client = http_client()?;
+    let url = https_url("cloudresourcemanager.googleapis.com", "/v1/projects");
+
+    let mut projects = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = client.get(&url).bearer_auth(&token);
+        if let Some(page_token) = &page_token {
+            request = request.query(&[("pageToken", page_token)]);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to list GCP projects: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("Failed to list GCP projects ({}): {}", status, error_text));
+        }
+
+        let page: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        if let Some(items) = page["projects"].as_array() {
+            projects.extend(items.iter().map(GcpProject::from_json));
+        }
+        page_token = page["nextPageToken"].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    // Active projects first, then by name
+    projects.sort_by_key(|p| (p.state != "ACTIVE", p.name.to_lowercase()));
+    Ok(projects)
+}
+
 /// Trigger interactive GCP login with a 5-minute timeout.
 /// Supports cancellation via `cancel_cli_login`.
 #[tauri::command]
@@ -604,6 +598,20 @@ pub async fn gcp_login() -> Result<String, String> {
     result
 }
 
+/// The SA is unknown to the Databricks account (403 from the account API).
+fn sa_not_in_databricks(email: &str) -> String {
+    format!(
+        "Service account not authorized in Databricks.\n\n\
+        The service account '{}' has not been added to the Databricks Account Console.\n\n\
+        Please add it:\n\
+        1. Go to accounts.gcp.databricks.com\n\
+        2. Navigate to User management → Users\n\
+        3. Click 'Add user' and enter: {}\n\
+        4. Grant the 'Account admin' role",
+        email, email
+    )
+}
+
 /// Validate GCP Databricks account access.
 #[tauri::command]
 pub async fn validate_gcp_databricks_access(
@@ -643,89 +651,43 @@ pub async fn validate_gcp_databricks_access(
             mask_sensitive_id(email)
         );
 
-        let client = http_client()?;
-        let generate_token_url = format!(
-            "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/{}:generateIdToken",
-            email
-        );
-
-        let token_response = client
-            .post(&generate_token_url)
-            .bearer_auth(&oauth_token)
-            .json(&serde_json::json!({
-                "audience": "https://accounts.gcp.databricks.com",
-                "includeEmail": true
-            }))
-            .send()
-            .await;
-
-        if let Ok(resp) = token_response {
-            if resp.status().is_success() {
-                if let Ok(token_json) = resp.json::<serde_json::Value>().await {
-                    if let Some(id_token) = token_json["token"].as_str() {
-                        let metastores_url = format!(
-                            "https://accounts.gcp.databricks.com/api/2.0/accounts/{}/metastores",
-                            account_id
-                        );
-
-                        debug_log!(
-                            "[validate_gcp_databricks_access] Calling Databricks API to verify access"
-                        );
-
-                        let db_response = client
-                            .get(&metastores_url)
-                            .bearer_auth(id_token)
-                            .send()
-                            .await;
-
-                        if let Ok(db_resp) = db_response {
-                            let status = db_resp.status();
-                            debug_log!(
-                                "[validate_gcp_databricks_access] Databricks API status: {}",
-                                status
-                            );
-
-                            if status.as_u16() == 403 {
-                                return Err(format!(
-                                    "Service account not authorized in Databricks.\n\n\
-                                    The service account '{}' has not been added to the Databricks Account Console.\n\n\
-                                    Please add it:\n\
-                                    1. Go to accounts.gcp.databricks.com\n\
-                                    2. Navigate to User management → Users\n\
-                                    3. Click 'Add user' and enter: {}\n\
-                                    4. Grant the 'Account admin' role",
-                                    email, email
-                                ));
-                            } else if status.as_u16() == 401 {
-                                return Err("Authentication failed. Please verify your GCP credentials and try again.".to_string());
-                            } else if !status.is_success() {
-                                let error_body = db_resp.text().await.unwrap_or_default();
-                                return Err(format!(
-                                    "Databricks API error ({}): {}",
-                                    status, error_body
-                                ));
-                            }
-
-                            return Ok(format!(
-                                "Databricks access verified for service account: {}",
-                                email
-                            ));
-                        }
+        match databricks_api::gcp_id_token_via_iam(&oauth_token, email).await {
+            Ok(id_token) => {
+                debug_log!(
+                    "[validate_gcp_databricks_access] Calling Databricks API to verify access"
+                );
+
+                let client = AccountClient::new("gcp", &account_id, AccountAuth::GcpToken(id_token))?;
+                match client.list_metastores().await {
+                    Ok(_) => {
+                        return Ok(format!(
+                            "Databricks access verified for service account: {}",
+                            email
+                        ));
                     }
+                    Err(ApiError::Forbidden(_)) => return Err(sa_not_in_databricks(email)),
+                    Err(ApiError::Unauthorized(_)) => {
+                        return Err("Authentication failed. Please verify your GCP credentials and try again.".to_string());
+                    }
+                    Err(ApiError::Network(_e)) => {
+                        debug_log!("[validate_gcp_databricks_access] Databricks API request failed: {}", _e);
+                    }
+                    Err(e) => return Err(e.to_string()),
                 }
-            } else {
-                let status = resp.status();
-                if status.as_u16() == 403 {
-                    return Err(format!(
-                        "Cannot generate ID token for service account.\n\n\
-                        The service account '{}' may not have the 'Service Account Token Creator' role on itself.\n\n\
-                        Run this command to fix:\n\
-                        gcloud iam service-accounts add-iam-policy-binding {} \\\n  \
-                        --member='serviceAccount:{}' \\\n  \
-                        --role='roles/iam.serviceAccountTokenCreator'",
-                        email, email, email
-                    ));
-                }
+            }
+            Err(ApiError::Forbidden(_)) => {
+                return Err(format!(
+                    "Cannot generate ID token for service account.\n\n\
+                    The service account '{}' may not have the 'Service Account Token Creator' role on itself.\n\n\
+                    Run this command to fix:\n\
+                    gcloud iam service-accounts add-iam-policy-binding {} \\\n  \
+                    --member='serviceAccount:{}' \\\n  \
+                    --role='roles/iam.serviceAccountTokenCreator'",
+                    email, email, email
+                ));
+            }
+            Err(_e) => {
+                debug_log!("[validate_gcp_databricks_access] ID token generation failed: {}", _e);
             }
         }
     }
@@ -767,75 +729,31 @@ pub async fn validate_gcp_databricks_access_with_key(
         .to_string();
 
     // Generate ID token for Databricks
-    let client = http_client()?;
-    let generate_token_url = format!(
-        "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/{}:generateIdToken",
-        sa_email
-    );
-
-    let token_response = client
-        .post(&generate_token_url)
-        .bearer_auth(&oauth_token)
-        .json(&serde_json::json!({
-            "audience": "https://accounts.gcp.databricks.com",
-            "includeEmail": true
-        }))
-        .send()
+    let id_token = databricks_api::gcp_id_token_via_iam(&oauth_token, &sa_email)
         .await
-        .map_err(|e| format!("Failed to generate ID token: {}", e))?;
-
-    if !token_response.status().is_success() {
-        let status = token_response.status();
-        if status.as_u16() == 403 {
-            return Err(format!(
+        .map_err(|e| match e {
+            ApiError::Forbidden(_) => format!(
                 "Cannot generate ID token for service account '{}'.\n\n\
                 Ensure the service account has the 'Service Account Token Creator' role on itself.",
                 sa_email
-            ));
-        }
-        let error_text = token_response.text().await.unwrap_or_default();
-        return Err(format!("ID token generation failed ({}): {}", status, error_text));
-    }
-
-    let token_json: serde_json::Value = token_response
-        .json()
-        .await
-        .map_err(|_| "Failed to parse ID token response".to_string())?;
-
-    let id_token = token_json["token"]
-        .as_str()
-        .ok_or("No token in ID token response")?;
+            ),
+            ApiError::Network(e) => format!("Failed to generate ID token: {}", e),
+            ApiError::Parse(_) => "Failed to parse ID token response".to_string(),
+            e => match e.status() {
+                Some(status) => format!("ID token generation failed ({}): {}", status, e.body()),
+                None => e.to_string(),
+            },
+        })?;
 
     // Verify Databricks account access
-    let metastores_url = format!(
-        "https://accounts.gcp.databricks.com/api/2.0/accounts/{}/metastores",
-        account_id
-    );
-
-    let db_response = client
-        .get(&metastores_url)
-        .bearer_auth(id_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to connect to Databricks: {}", e))?;
-
-    let status = db_response.status();
-    if status.as_u16() == 403 {
-        return Err(format!(
-            "Service account not authorized in Databricks.\n\n\
-            The service account '{}' has not been added to the Databricks Account Console.\n\n\
-            Please add it:\n\
-            1. Go to accounts.gcp.databricks.com\n\
-            2. Navigate to User management → Users\n\
-            3. Click 'Add user' and enter: {}\n\
-            4. Grant the 'Account admin' role",
-            sa_email, sa_email
-        ));
-    } else if status.as_u16() == 401 {
-        return Err("Authentication failed. The service account key may be invalid or expired.".to_string());
-    } else if !status.is_success() {
-        let error_body = db_response.text().await.unwrap_or_default();
-        return Err(format!("Databricks API error ({}): {}", status, error_body));
+    let client = AccountClient::new("gcp", &account_id, AccountAuth::GcpToken(id_token))?;
+    match client.list_metastores().await {
+        Ok(_) => {}
+        Err(ApiError::Forbidden(_)) => return Err(sa_not_in_databricks(&sa_email)),
+        Err(ApiError::Unauthorized(_)) => {
+            return Err("Authentication failed. The service account key may be invalid or expired.".to_string());
+        }
+        Err(e) => return Err(e.to_string()),
     }
 
     Ok(format!(
@@ -848,6 +766,14 @@ pub async fn validate_gcp_databricks_access_with_key(
 #[tauri::command]
 pub async fn check_gcp_permissions(
     credentials: CloudCredentials,
+    request_id: Option<String>,
+) -> Result<CloudPermissionCheck, String> {
+    super::cancellation::cancellable(request_id, |cancel| gcp_permission_check(credentials, cancel)).await
+}
+
+async fn gcp_permission_check(
+    credentials: CloudCredentials,
+    cancel: CancelToken,
 ) -> Result<CloudPermissionCheck, String> {
     let required_permissions = vec![
         "compute.networks.create",
@@ -863,15 +789,13 @@ pub async fn check_gcp_permissions(
         proj.clone()
     } else {
         if let Some(gcloud_cli) = dependencies::find_gcloud_cli_path() {
-            let config_output = super::silent_cmd(&gcloud_cli)
-                .args(["config", "get-value", "project"])
-                .output()
-                .ok();
+            let mut config_cmd = CliCommand::with_path(CliTool::Gcloud, &gcloud_cli);
+            config_cmd.args(["config", "get-value", "project"]).cancel_token(&cancel);
+            let config_output = config_cmd.run_async().await.ok();
 
             config_output
-                .filter(|o| o.status.success())
-                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-                .filter(|p| !p.is_empty() && p != "(unset)")
+                .filter(|o| o.success)
+                .and_then(|o| o.value())
                 .unwrap_or_default()
         } else {
             String::new()
@@ -961,15 +885,8 @@ pub async fn check_gcp_permissions(
         })
         .unwrap_or_default();
 
-    let checked_permissions: Vec<String> =
-        required_permissions.iter().map(|s| s.to_string()).collect();
-
-    let missing_permissions: Vec<String> = required_permissions
-        .iter()
-        .filter(|p| !granted_permissions.contains(&p.to_string()))
-        .map(|s| s.to_string())
-        .collect();
-
+    let report = gcp_permission_report(&required_permissions, &granted_permissions, &project_id);
+    let missing_permissions = report.failed();
     let has_all = missing_permissions.is_empty();
 
     let message = if has_all {
@@ -995,21 +912,44 @@ pub async fn check_gcp_permissions(
         )
     };
 
-    Ok(CloudPermissionCheck {
-        has_all_permissions: has_all,
-        checked_permissions,
-        missing_permissions,
-        message,
-        is_warning: !has_all,
-    })
+    Ok(CloudPermissionCheck::from_report(report, message))
+}
+
+/// Checklist from a `testIamPermissions` response. The API evaluates the
+/// caller's effective permissions on the project, so a missing one is a blocker.
+fn gcp_permission_report(required: &[&str], granted: &[String], project_id: &str) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    for permission in required {
+        report.push(if granted.iter().any(|g| g == permission) {
+            PreflightEntry::pass(permission, "Granted")
+        } else {
+            PreflightEntry::fail(permission, BLOCKER, "Not granted on the project")
+                .remediation(format!(
+                    "gcloud iam roles update DatabricksWorkspaceDeployer --project={} --add-permissions={}",
+                    project_id, permission
+                ))
+                .docs(GCP_CUSTOM_ROLES_DOCS_URL)
+        });
+    }
+    report
 }
 
 /// Create a GCP service account for Databricks deployment.
 ///
 /// Creates the SA, creates a custom role with minimal required permissions,
-/// grants that role to the SA, grants Token Creator to user, and configures impersonation.
+/// grants that role to the SA, grants Token Creator to user, and waits until the
+/// user can impersonate it.
 #[tauri::command]
 pub async fn create_gcp_service_account(
+    app: AppHandle,
+    project_id: String,
+    sa_name: String,
+) -> Result<String, String> {
+    super::run_blocking(move || create_gcp_service_account_blocking(&app, project_id, sa_name)).await
+}
+
+fn create_gcp_service_account_blocking(
+    env: &dyn Environment,
     project_id: String,
     sa_name: String,
 ) -> Result<String, String> {
@@ -1037,14 +977,11 @@ pub async fn create_gcp_service_account(
     }
 
     // Step 0: Get current user's email
-    let user_output = super::silent_cmd(&gcloud_cli)
+    let user_output = gcloud_as_user(&gcloud_cli)
         .args(["config", "get-value", "account"])
-        .output()
-        .map_err(|e| format!("Failed to get current user: {}", e))?;
+        .run()?;
 
-    let user_email = String::from_utf8_lossy(&user_output.stdout)
-        .trim()
-        .to_string();
+    let user_email = user_output.stdout.trim().to_string();
     if user_email.is_empty() {
         return Err(
             "No authenticated user found. Please run 'gcloud auth login' first.".to_string(),
@@ -1054,7 +991,7 @@ pub async fn create_gcp_service_account(
     let sa_email = format!("{}@{}.iam.gserviceaccount.com", sa_name, project_id);
 
     // Step 1: Create service account
-    let create_output = super::silent_cmd(&gcloud_cli)
+    let create_output = gcloud_as_user(&gcloud_cli)
         .args([
             "iam",
             "service-accounts",
@@ -1067,23 +1004,27 @@ pub async fn create_gcp_service_account(
             "--project",
             &project_id,
         ])
-        .output()
-        .map_err(|e| format!("Failed to run gcloud: {}", e))?;
+        .run()?;
 
-    if !create_output.status.success() {
-        let stderr = String::from_utf8_lossy(&create_output.stderr);
+    if !create_output.success {
+        let stderr = &create_output.stderr;
         if !stderr.contains("already exists") {
             return Err(format!(
                 "Failed to create service account: {}",
                 stderr.trim()
             ));
         }
+    } else {
+        bootstrap_artifacts::record_quietly(
+            env,
+            ArtifactKind::GcpServiceAccount { project_id: project_id.clone(), email: sa_email.clone() },
+        );
     }
 
     // Step 2a: Create custom role
     let permissions_str = GCP_DATABRICKS_PERMISSIONS.join(",");
 
-    let create_role_output = super::silent_cmd(&gcloud_cli)
+    let create_role_output = gcloud_as_user(&gcloud_cli)
         .args([
             "iam",
             "roles",
@@ -1098,11 +1039,10 @@ pub async fn create_gcp_service_account(
             "--permissions",
             &permissions_str,
         ])
-        .output()
-        .map_err(|e| format!("Failed to create custom role: {}", e))?;
+        .run()?;
 
-    if !create_role_output.status.success() {
-        let stderr = String::from_utf8_lossy(&create_role_output.stderr);
+    if !create_role_output.success {
+        let stderr = &create_role_output.stderr;
         if !stderr.contains("already exists") {
             if stderr.contains("PERMISSION_DENIED") || stderr.contains("permission") {
                 return Err(format!(
@@ -1119,12 +1059,17 @@ pub async fn create_gcp_service_account(
                 stderr.trim()
             ));
         }
+    } else {
+        bootstrap_artifacts::record_quietly(
+            env,
+            ArtifactKind::GcpCustomRole { project_id: project_id.clone(), role_id: GCP_CUSTOM_ROLE_NAME.to_string() },
+        );
     }
 
     // Step 2b: Grant custom role to the SA
     let custom_role_path = format!("projects/{}/roles/{}", project_id, GCP_CUSTOM_ROLE_NAME);
 
-    let grant_output = super::silent_cmd(&gcloud_cli)
+    let grant_output = gcloud_as_user(&gcloud_cli)
         .args([
             "projects",
             "add-iam-policy-binding",
@@ -1136,19 +1081,27 @@ pub async fn create_gcp_service_account(
             "--condition",
             "None",
         ])
-        .output()
-        .map_err(|e| format!("Failed to grant custom role: {}", e))?;
+        .run()?;
 
-    if !grant_output.status.success() {
-        let stderr = String::from_utf8_lossy(&grant_output.stderr);
+    if !grant_output.success {
+        let stderr = &grant_output.stderr;
         return Err(format!(
             "Failed to grant custom role to service account: {}",
             stderr.trim()
         ));
     }
+    bootstrap_artifacts::record_quietly(
+        env,
+        ArtifactKind::GcpIamBinding {
+            project_id: project_id.clone(),
+            service_account: None,
+            member: format!("serviceAccount:{}", sa_email),
+            role: custom_role_path.clone(),
+        },
+    );
 
     // Step 3: Grant Service Account Token Creator role to user
-    let token_creator_output = super::silent_cmd(&gcloud_cli)
+    let token_creator_output = gcloud_as_user(&gcloud_cli)
         .args([
             "iam",
             "service-accounts",
@@ -1161,19 +1114,27 @@ pub async fn create_gcp_service_account(
             "--project",
             &project_id,
         ])
-        .output()
-        .map_err(|e| format!("Failed to grant Token Creator role: {}", e))?;
+        .run()?;
 
-    if !token_creator_output.status.success() {
-        let stderr = String::from_utf8_lossy(&token_creator_output.stderr);
+    if !token_creator_output.success {
+        let stderr = &token_creator_output.stderr;
         return Err(format!(
             "Failed to grant Token Creator role: {}",
             stderr.trim()
         ));
     }
+    bootstrap_artifacts::record_quietly(
+        env,
+        ArtifactKind::GcpIamBinding {
+            project_id: project_id.clone(),
+            service_account: Some(sa_email.clone()),
+            member: format!("user:{}", user_email),
+            role: "roles/iam.serviceAccountTokenCreator".to_string(),
+        },
+    );
 
     // Step 3b: Grant SA the Token Creator role on itself
-    let sa_self_token_creator = super::silent_cmd(&gcloud_cli)
+    let sa_self_token_creator = gcloud_as_user(&gcloud_cli)
         .args([
             "iam",
             "service-accounts",
@@ -1186,61 +1147,49 @@ pub async fn create_gcp_service_account(
             "--project",
             &project_id,
         ])
-        .output()
-        .map_err(|e| format!("Failed to grant SA self Token Creator role: {}", e))?;
+        .run()?;
 
-    if !sa_self_token_creator.status.success() {
-        let _stderr = String::from_utf8_lossy(&sa_self_token_creator.stderr);
+    if !sa_self_token_creator.success {
+        let _stderr = &sa_self_token_creator.stderr;
         debug_log!(
             "[create_gcp_service_account] Warning: Could not grant SA self Token Creator role: {}",
             _stderr.trim()
         );
+    } else {
+        bootstrap_artifacts::record_quietly(
+            env,
+            ArtifactKind::GcpIamBinding {
+                project_id: project_id.clone(),
+                service_account: Some(sa_email.clone()),
+                member: format!("serviceAccount:{}", sa_email),
+                role: "roles/iam.serviceAccountTokenCreator".to_string(),
+            },
+        );
     }
 
-    // Step 4: Configure impersonation
-    let impersonate_output = super::silent_cmd(&gcloud_cli)
-        .args([
-            "config",
-            "set",
-            "auth/impersonate_service_account",
-            &sa_email,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to configure impersonation: {}", e))?;
-
-    if !impersonate_output.status.success() {
-        let stderr = String::from_utf8_lossy(&impersonate_output.stderr);
-        return Err(format!(
-            "Failed to configure impersonation: {}",
-            stderr.trim()
-        ));
-    }
-
-    // Step 5: Wait for IAM propagation
+    // Step 4: Wait for IAM propagation, until the user can impersonate the SA.
+    // The SA is passed per invocation; the user's gcloud config is left alone.
     let max_attempts = 24;
     let mut attempt = 0;
 
     loop {
         attempt += 1;
 
-        let token_test = super::silent_cmd(&gcloud_cli)
-            .args(["auth", "print-access-token"])
-            .output();
+        let token_test = gcloud_as_user(&gcloud_cli)
+            .args(["auth", "print-access-token", "--impersonate-service-account", &sa_email])
+            .timeout(std::time::Duration::from_secs(30))
+            .run();
 
         if let Ok(output) = token_test {
-            if output.status.success() {
+            if output.success {
                 break;
             }
         }
 
         if attempt >= max_attempts {
-            let _ = super::silent_cmd(&gcloud_cli)
-                .args(["config", "unset", "auth/impersonate_service_account"])
-                .output();
-
             return Err(format!(
-                "Service account created, but IAM propagation timed out after 120 seconds. \
-                Please wait a minute and then run: gcloud config set auth/impersonate_service_account {}",
+                "Service account {} created, but IAM propagation timed out after 120 seconds. \
+                Please wait a minute and validate the credentials again.",
                 sa_email
             ));
         }
@@ -1251,15 +1200,150 @@ pub async fn create_gcp_service_account(
     Ok(sa_email)
 }
 
+/// Required deployer permissions `token` does not hold on the project,
+/// via `testIamPermissions`.
+async fn missing_deployer_permissions(token: &str, project_id: &str) -> Result<Vec<String>, String> {
+    let client = http_client()?;
+    let url = https_url(
+        "cloudresourcemanager.googleapis.com",
+        &format!("/v1/projects/{}:testIamPermissions", project_id),
+    );
+    let mut granted: Vec<String> = Vec::new();
+    // testIamPermissions accepts at most 100 permissions per call
+    for chunk in GCP_DATABRICKS_PERMISSIONS.chunks(100) {
+        let response = client
+            .post(&url)
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "permissions": chunk }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to check permissions: {}", e))?;
+        let status = response.status();
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse permission check response: {}", e))?;
+        if !status.is_success() {
+            let message = json["error"]["message"].as_str().unwrap_or("Unknown API error");
+            return Err(format!("Permission check failed ({}): {}", status, message));
+        }
+        granted.extend(granted_permissions(&json));
+    }
+    Ok(missing_from(GCP_DATABRICKS_PERMISSIONS, &granted))
+}
+
+/// Permissions listed in a `testIamPermissions` response.
+fn granted_permissions(response: &serde_json::Value) -> Vec<String> {
+    response["permissions"]
+        .as_array()
+        .map(|permissions| permissions.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// Entries of `required` that aren't in `granted`, in order.
+fn missing_from(required: &[&str], granted: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|p| !granted.iter().any(|g| g == *p))
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// Whether `email` is a plain `name@....gserviceaccount.com` address.
+fn is_service_account_email(email: &str) -> bool {
+    email.ends_with(".gserviceaccount.com")
+        && email.split_once('@').is_some_and(|(name, _)| !name.is_empty())
+        && email.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '-' | '_'))
+}
+
+/// Report for a service account lacking deployer permissions, with the
+/// command that grants them.
+fn missing_permissions_message(sa_email: &str, project_id: &str, missing: &[String]) -> String {
+    format!(
+        "Service account {} is missing {} required permission(s): {}\n\n\
+        Grant them with a custom role, for example:\n\
+        gcloud iam roles update {} --project={} --add-permissions={}",
+        sa_email,
+        missing.len(),
+        missing.join(", "),
+        GCP_CUSTOM_ROLE_NAME,
+        project_id,
+        missing.join(",")
+    )
+}
+
+/// Reuse an existing service account instead of creating one.
+///
+/// Checks that the caller can impersonate the SA and that the SA holds every
+/// permission in the deployer role on the project. IAM is never modified:
+/// missing permissions are reported with the command an admin can run to fix
+/// them.
+#[tauri::command]
+pub async fn use_existing_gcp_service_account(
+    project_id: String,
+    service_account_email: String,
+) -> Result<ExistingServiceAccountCheck, String> {
+    let project_id = project_id.trim().to_string();
+    let sa_email = service_account_email.trim().to_string();
+    if !super::credential_context::is_valid_gcp_project_id(&project_id) {
+        return Err("Invalid GCP project ID".to_string());
+    }
+    if !is_service_account_email(&sa_email) {
+        return Err("Service account email must be a *.gserviceaccount.com address".to_string());
+    }
+
+    let gcloud_cli = dependencies::find_gcloud_cli_path()
+        .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
+
+    // Step 1: Token as the SA, which proves the caller may impersonate it
+    let (sa, project) = (sa_email.clone(), project_id.clone());
+    let token = super::run_blocking(move || {
+        let output = gcloud_as_user(&gcloud_cli)
+            .args(["auth", "print-access-token", "--impersonate-service-account", &sa])
+            .run()?;
+        if !output.success {
+            let stderr = &output.stderr;
+            return Err(format!(
+                "Cannot impersonate {}: {}\n\n\
+                Ask an admin to grant you Service Account Token Creator on it:\n\
+                gcloud iam service-accounts add-iam-policy-binding {} --member=user:<your-email> \
+                --role=roles/iam.serviceAccountTokenCreator --project={}",
+                sa,
+                stderr.trim(),
+                sa,
+                project
+            ));
+        }
+        Ok(output.stdout.trim().to_string())
+    })
+    .await?;
+
+    // Step 2: Permissions the SA holds on the project
+    let missing = missing_deployer_permissions(&token, &project_id).await?;
+    if !missing.is_empty() {
+        let message = missing_permissions_message(&sa_email, &project_id, &missing);
+        return Ok(ExistingServiceAccountCheck {
+            service_account: sa_email,
+            missing_permissions: missing,
+            ready: false,
+            message,
+        });
+    }
+
+    Ok(ExistingServiceAccountCheck {
+        message: format!("Using existing service account {}", sa_email),
+        service_account: sa_email,
+        missing_permissions: Vec::new(),
+        ready: true,
+    })
+}
+
 /// Add a GCP service account to Databricks Account Console with Account Admin role.
 #[tauri::command]
 pub async fn add_service_account_to_databricks(
     account_id: String,
     service_account_email: String,
 ) -> Result<String, String> {
-    let accounts_host = "accounts.gcp.databricks.com";
-    let client = http_client()?;
-
     if account_id.is_empty() {
         return Err("Databricks Account ID is required".to_string());
     }
@@ -1270,66 +1354,75 @@ pub async fn add_service_account_to_databricks(
     let gcloud_cli = dependencies::find_gcloud_cli_path()
         .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
 
-    let user_output = super::silent_cmd(&gcloud_cli)
+    let oauth_token = super::run_blocking(move || gcloud_user_access_token(&gcloud_cli)).await?;
+
+    // Step 1: Create user via SCIM API
+    let client = AccountClient::new("gcp", &account_id, AccountAuth::GcpToken(oauth_token))?;
+    let display_name = service_account_email
+        .split('@')
+        .next()
+        .unwrap_or(&service_account_email);
+
+    let user_id = match client.create_user(&service_account_email, display_name).await {
+        Ok(user) if user.id.is_empty() => return Err("No user ID in create response".to_string()),
+        Ok(user) => user.id,
+        Err(ApiError::Conflict(_)) => client
+            .find_user(&service_account_email)
+            .await
+            .map_err(|e| format!("Failed to find existing user: {}", e))?
+            .ok_or("User not found after conflict response")?
+            .id,
+        Err(e) if e.is_auth_failure() => {
+            return Err(
+                "You don't have permission to add users to Databricks. \
+                Make sure you are logged in as a Databricks account admin."
+                    .to_string(),
+            );
+        }
+        Err(ApiError::Parse(e)) => return Err(format!("Failed to parse create response: {}", e)),
+        Err(e) => {
+            return Err(match e.status() {
+                Some(status) => format!("Failed to create user ({}): {}", status, e.body()),
+                None => e.to_string(),
+            });
+        }
+    };
+
+    // Step 2: Grant Account Admin role
+    client
+        .grant_account_admin(&user_id)
+        .await
+        .map_err(|e| match e {
+            ApiError::Network(e) => format!("Failed to grant admin role: {}", e),
+            e => format!("Failed to grant Account Admin role: {}", e.body()),
+        })?;
+
+    Ok(format!(
+        "Service account '{}' added to Databricks with Account Admin role",
+        service_account_email
+    ))
+}
+
+/// OAuth token for the logged-in gcloud user (not an impersonated SA).
+fn gcloud_user_access_token(gcloud_cli: &std::path::Path) -> Result<String, String> {
+    let user_output = CliCommand::with_path(CliTool::Gcloud, gcloud_cli)
         .args(["config", "get-value", "account"])
-        .output()
-        .map_err(|e| format!("Failed to get current user: {}", e))?;
+        .run()?;
 
-    let user_email = String::from_utf8_lossy(&user_output.stdout)
-        .trim()
-        .to_string();
+    let user_email = user_output.stdout.trim().to_string();
     if user_email.is_empty() {
         return Err(
             "No authenticated user found. Please run 'gcloud auth login' first.".to_string(),
         );
     }
 
-    // Check if impersonation is currently configured
-    let impersonate_check = super::silent_cmd(&gcloud_cli)
-        .args(["config", "get-value", "auth/impersonate_service_account"])
-        .output()
-        .ok();
-
-    let current_impersonation = impersonate_check.and_then(|o| {
-        if o.status.success() {
-            let val = String::from_utf8_lossy(&o.stdout).trim().to_string();
-            if val.is_empty() || val == "(unset)" {
-                None
-            } else {
-                Some(val)
-            }
-        } else {
-            None
-        }
-    });
-
-    // Temporarily disable impersonation
-    if current_impersonation.is_some() {
-        let _ = super::silent_cmd(&gcloud_cli)
-            .args(["config", "unset", "auth/impersonate_service_account"])
-            .output();
-    }
-
-    // Get a fresh OAuth token for the USER
-    let token_output = super::silent_cmd(&gcloud_cli)
+    // Get a fresh OAuth token for the USER, bypassing any impersonation
+    let token_output = gcloud_as_user(gcloud_cli)
         .args(["auth", "print-access-token"])
-        .output()
-        .map_err(|e| format!("Failed to get OAuth token: {}", e))?;
-
-    // Restore impersonation
-    if let Some(ref sa_email) = current_impersonation {
-        let _ = super::silent_cmd(&gcloud_cli)
-            .args([
-                "config",
-                "set",
-                "auth/impersonate_service_account",
-                sa_email,
-            ])
-            .output();
-    }
+        .run()?;
 
-    if !token_output.status.success() {
-        let stderr = String::from_utf8_lossy(&token_output.stderr);
+    if !token_output.success {
+        let stderr = &token_output.stderr;
         return Err(format!(
             "Failed to get OAuth token for {}. Make sure you're logged in with 'gcloud auth login'. Error: {}",
             user_email,
@@ -1337,136 +1430,44 @@ pub async fn add_service_account_to_databricks(
         ));
     }
 
-    let oauth_token = String::from_utf8_lossy(&token_output.stdout)
-        .trim()
-        .to_string();
-
-    // Step 1: Create user via SCIM API
-    let create_user_url = format!(
-        "https://{}/api/2.0/accounts/{}/scim/v2/Users",
-        accounts_host, account_id
-    );
-
-    let create_user_body = serde_json::json!({
-        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
-        "userName": service_account_email,
-        "displayName": service_account_email.split('@').next().unwrap_or(&service_account_email),
-        "active": true
-    });
-
-    let create_response = client
-        .post(&create_user_url)
-        .bearer_auth(&oauth_token)
-        .header("Content-Type", "application/scim+json")
-        .json(&create_user_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to connect to Databricks: {}", e))?;
-
-    let create_status = create_response.status();
-    let create_text = create_response.text().await.unwrap_or_default();
-
-    let user_id: String;
-
-    if create_status.is_success() {
-        let create_json: serde_json::Value = serde_json::from_str(&create_text)
-            .map_err(|e| format!("Failed to parse create response: {}", e))?;
-        user_id = create_json["id"]
-            .as_str()
-            .ok_or("No user ID in create response")?
-            .to_string();
-    } else if create_status == reqwest::StatusCode::CONFLICT {
-        let list_url = format!(
-            "https://{}/api/2.0/accounts/{}/scim/v2/Users?filter=userName eq \"{}\"",
-            accounts_host, account_id, service_account_email
-        );
-
-        let list_response = client
-            .get(&list_url)
-            .bearer_auth(&oauth_token)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to find existing user: {}", e))?;
-
-        if !list_response.status().is_success() {
-            return Err(format!(
-                "Failed to find existing user: {}",
-                list_response.status()
-            ));
-        }
-
-        let list_json: serde_json::Value = list_response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse list response: {}", e))?;
+    Ok(token_output.stdout.trim().to_string())
+}
 
-        let resources = list_json["Resources"]
-            .as_array()
-            .ok_or("No Resources in list response")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if resources.is_empty() {
-            return Err("User not found after conflict response".to_string());
-        }
+    // ── use_existing_gcp_service_account ────────────────────────────────
 
-        user_id = resources[0]["id"]
-            .as_str()
-            .ok_or("No user ID in list response")?
-            .to_string();
-    } else if create_status == reqwest::StatusCode::FORBIDDEN
-        || create_status == reqwest::StatusCode::UNAUTHORIZED
-    {
-        return Err(
-            "You don't have permission to add users to Databricks. \
-            Make sure you are logged in as a Databricks account admin."
-                .to_string(),
-        );
-    } else {
-        return Err(format!(
-            "Failed to create user ({}): {}",
-            create_status, create_text
-        ));
+    #[test]
+    fn service_account_email_format() {
+        assert!(is_service_account_email("deployer@my-proj.iam.gserviceaccount.com"));
+        assert!(!is_service_account_email("@my-proj.iam.gserviceaccount.com"));
+        assert!(!is_service_account_email("dev@example.com"));
+        assert!(!is_service_account_email("a b@my-proj.iam.gserviceaccount.com"));
+        assert!(!is_service_account_email("x@y.gserviceaccount.com --flag"));
     }
 
-    // Step 2: Grant Account Admin role
-    let update_url = format!(
-        "https://{}/api/2.0/accounts/{}/scim/v2/Users/{}",
-        accounts_host, account_id, user_id
-    );
-
-    let patch_body = serde_json::json!({
-        "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
-        "Operations": [
-            {
-                "op": "add",
-                "path": "roles",
-                "value": [
-                    {
-                        "value": "account_admin"
-                    }
-                ]
-            }
-        ]
-    });
-
-    let patch_response = client
-        .patch(&update_url)
-        .bearer_auth(&oauth_token)
-        .header("Content-Type", "application/scim+json")
-        .json(&patch_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to grant admin role: {}", e))?;
+    #[test]
+    fn missing_permissions_from_test_iam_response() {
+        let response = serde_json::json!({"permissions": ["compute.networks.create", "storage.buckets.create"]});
+        let granted = granted_permissions(&response);
+        let required = ["compute.networks.create", "iam.roles.create", "storage.buckets.create"];
+        assert_eq!(missing_from(&required, &granted), vec!["iam.roles.create"]);
 
-    if !patch_response.status().is_success() {
-        let error_text = patch_response.text().await.unwrap_or_default();
-        return Err(format!(
-            "Failed to grant Account Admin role: {}",
-            error_text
-        ));
+        // An empty response means nothing was granted
+        assert!(granted_permissions(&serde_json::json!({})).is_empty());
+        assert_eq!(missing_from(&required, &[]).len(), 3);
     }
 
-    Ok(format!(
-        "Service account '{}' added to Databricks with Account Admin role",
-        service_account_email
-    ))
+    #[test]
+    fn missing_permissions_message_lists_grant_command() {
+        let missing = vec!["iam.roles.create".to_string(), "compute.routers.create".to_string()];
+        let message = missing_permissions_message("sa@p.iam.gserviceaccount.com", "p", &missing);
+        assert!(message.contains("missing 2 required permission(s): iam.roles.create, compute.routers.create"));
+        assert!(message.contains(&format!(
+            "gcloud iam roles update {} --project=p --add-permissions=iam.roles.create,compute.routers.create",
+            GCP_CUSTOM_ROLE_NAME
+        )));
+    }
 }
@@ -0,0 +1,208 @@
+//! GCP API enablement preflight.
+//!
+//! A Databricks workspace on GCP needs a handful of Google APIs enabled in the
+//! project. Terraform only finds out when it first calls a disabled API, often
+//! well into an apply, so [`check_and_enable_gcp_apis`] reads their state from
+//! the Service Usage API up front and, once the user has confirmed, enables the
+//! missing ones in a single batch.
+
+use super::credential_context::is_valid_gcp_project_id;
+use super::{debug_log, http_client, CloudCredentials};
+use crate::endpoints::https_url;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// APIs the GCP templates need enabled.
+const REQUIRED_GCP_SERVICES: &[&str] = &[
+    "compute.googleapis.com",
+    "storage.googleapis.com",
+    "iam.googleapis.com",
+    "iamcredentials.googleapis.com",
+    "serviceusage.googleapis.com",
+];
+
+/// How long to wait for a batch enable operation.
+const ENABLE_TIMEOUT: Duration = Duration::from_secs(180);
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// State of one required API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GcpServiceState {
+    pub name: String,
+    pub enabled: bool,
+    /// Service Usage state, e.g. `ENABLED`, `DISABLED`.
+    pub state: String,
+}
+
+/// Required APIs of a project, after enabling missing ones if requested.
+#[derive(Debug, Clone, Serialize)]
+pub struct GcpApiReport {
+    pub project_id: String,
+    pub services: Vec<GcpServiceState>,
+    /// APIs still disabled.
+    pub missing: Vec<String>,
+    /// APIs enabled by this call.
+    pub enabled: Vec<String>,
+    pub ready: bool,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Required service states from a `services:batchGet` response. Services the
+/// response leaves out are reported as disabled.
+fn service_states(response: &serde_json::Value) -> Vec<GcpServiceState> {
+    let returned = response["services"].as_array().cloned().unwrap_or_default();
+    REQUIRED_GCP_SERVICES
+        .iter()
+        .map(|name| {
+            let state = returned
+                .iter()
+                .find(|s| {
+                    s["config"]["name"].as_str() == Some(name)
+                        || s["name"].as_str().is_some_and(|n| n.rsplit('/').next() == Some(name))
+                })
+                .and_then(|s| s["state"].as_str())
+                .unwrap_or("STATE_UNSPECIFIED")
+                .to_string();
+            GcpServiceState { name: name.to_string(), enabled: state == "ENABLED", state }
+        })
+        .collect()
+}
+
+async fn api_error(response: reqwest::Response, action: &str) -> String {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+    format!("Failed to {} ({}): {}", action, status, message)
+}
+
+async fn fetch_states(client: &reqwest::Client, token: &str, project_id: &str) -> Result<Vec<GcpServiceState>, String> {
+    let url = https_url("serviceusage.googleapis.com", &format!("/v1/projects/{}/services:batchGet", project_id));
+    let names: Vec<(&str, String)> = REQUIRED_GCP_SERVICES
+        .iter()
+        .map(|s| ("names", format!("projects/{}/services/{}", project_id, s)))
+        .collect();
+    let response = client
+        .get(&url)
+        .bearer_auth(token)
+        .query(&names)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to read GCP API state: {}", e))?;
+    if !response.status().is_success() {
+        return Err(api_error(response, "read GCP API state").await);
+    }
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Service Usage response: {}", e))?;
+    Ok(service_states(&json))
+}
+
+/// Enable `services` and wait for the long-running operation to finish.
+async fn enable_services(client: &reqwest::Client, token: &str, project_id: &str, services: &[String]) -> Result<(), String> {
+    let url = https_url("serviceusage.googleapis.com", &format!("/v1/projects/{}/services:batchEnable", project_id));
+    let response = client
+        .post(&url)
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "serviceIds": services }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to enable GCP APIs: {}", e))?;
+    if !response.status().is_success() {
+        return Err(api_error(response, "enable GCP APIs").await);
+    }
+    let mut operation: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Service Usage response: {}", e))?;
+
+    let started = Instant::now();
+    while operation["done"].as_bool() != Some(true) {
+        let name = operation["name"].as_str().ok_or("Service Usage returned no operation name")?.to_string();
+        if started.elapsed() > ENABLE_TIMEOUT {
+            return Err(format!("Timed out waiting for GCP APIs to be enabled (operation {})", name));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let response = client
+            .get(https_url("serviceusage.googleapis.com", &format!("/v1/{}", name)))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to check enable operation: {}", e))?;
+        if !response.status().is_success() {
+            return Err(api_error(response, "check enable operation").await);
+        }
+        operation = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Service Usage response: {}", e))?;
+    }
+
+    match operation["error"]["message"].as_str() {
+        Some(message) => Err(format!("Failed to enable GCP APIs: {}", message)),
+        None => Ok(()),
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Check whether the APIs the GCP templates need are enabled in a project.
+/// With `enable` set (after the user confirms), missing APIs are enabled
+/// first and the report reflects the state afterwards.
+#[tauri::command]
+pub async fn check_and_enable_gcp_apis(
+    credentials: CloudCredentials,
+    project_id: Option<String>,
+    enable: Option<bool>,
+) -> Result<GcpApiReport, String> {
+    let project_id = project_id
+        .or_else(|| credentials.gcp_project_id.clone())
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .ok_or("GCP Project ID is required")?;
+    if !is_valid_gcp_project_id(&project_id) {
+        return Err("Invalid GCP project ID".to_string());
+    }
+
+    let (token, _) = super::gcp::get_gcp_oauth_token(&credentials).await?;
+    let client = http_client()?;
+    let mut services = fetch_states(&client, &token, &project_id).await?;
+
+    let mut enabled = Vec::new();
+    let missing: Vec<String> = services.iter().filter(|s| !s.enabled).map(|s| s.name.clone()).collect();
+    if enable.unwrap_or(false) && !missing.is_empty() {
+        debug_log!("[gcp_apis] Enabling {} API(s) in {}", missing.len(), project_id);
+        enable_services(&client, &token, &project_id, &missing).await?;
+        services = fetch_states(&client, &token, &project_id).await?;
+        enabled = missing.into_iter().filter(|name| services.iter().any(|s| &s.name == name && s.enabled)).collect();
+    }
+
+    let missing: Vec<String> = services.iter().filter(|s| !s.enabled).map(|s| s.name.clone()).collect();
+    Ok(GcpApiReport { project_id, ready: missing.is_empty(), services, missing, enabled })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn states_match_by_config_or_resource_name() {
+        let response = json!({
+            "services": [
+                { "name": "projects/123/services/compute.googleapis.com", "config": { "name": "compute.googleapis.com" }, "state": "ENABLED" },
+                { "name": "projects/123/services/iam.googleapis.com", "state": "DISABLED" },
+                { "name": "projects/123/services/storage.googleapis.com", "state": "ENABLED" }
+            ]
+        });
+        let states = service_states(&response);
+        assert_eq!(states.len(), REQUIRED_GCP_SERVICES.len());
+        let state = |name: &str| states.iter().find(|s| s.name == name).unwrap().clone();
+        assert!(state("compute.googleapis.com").enabled);
+        assert!(state("storage.googleapis.com").enabled);
+        assert_eq!(state("iam.googleapis.com").state, "DISABLED");
+        assert_eq!(state("serviceusage.googleapis.com").state, "STATE_UNSPECIFIED");
+        assert!(!state("serviceusage.googleapis.com").enabled);
+    }
+}
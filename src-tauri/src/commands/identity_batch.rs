@@ -0,0 +1,470 @@
+//! Batch user and group provisioning into the Databricks account.
+//!
+//! A CSV or JSON file lists users and groups; provisioning creates what is
+//! missing via account SCIM, adds users to their groups, and (given a
+//! workspace ID) assigns workspace permissions, so a new account doesn't
+//! need to be bootstrapped by hand in the console.
+//!
+//! CSV files have a header row with the columns `type` (`user`/`group`),
+//! `name` (email for users), `display_name`, `groups` (`;`-separated), and
+//! `workspace_permission` (`USER`/`ADMIN`); only `name` is required. JSON
+//! files hold `{"users": [...], "groups": [...]}` with the same fields.
+
+use super::{databricks_accounts_host, CloudCredentials};
+use crate::databricks_api::{AccountClient, ApiError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+
+/// Entries accepted per file; the account SCIM API is rate-limited.
+const MAX_ENTRIES: usize = 1000;
+
+const PERMISSIONS: &[&str] = &["USER", "ADMIN"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchUser {
+    pub email: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Account groups the user is added to (created if missing).
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub workspace_permission: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchGroup {
+    pub name: String,
+    #[serde(default)]
+    pub workspace_permission: Option<String>,
+}
+
+/// Parsed and validated provisioning file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdentityBatch {
+    #[serde(default)]
+    pub users: Vec<BatchUser>,
+    /// Every group to provision: those listed explicitly plus those users
+    /// reference.
+    #[serde(default)]
+    pub groups: Vec<BatchGroup>,
+}
+
+/// Outcome of one provisioning action.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProvisionStep {
+    /// "create_group", "create_user", "add_members", or "assign_workspace".
+    pub action: String,
+    pub target: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvisionReport {
+    pub users: usize,
+    pub groups: usize,
+    pub failed: usize,
+    pub steps: Vec<ProvisionStep>,
+}
+
+// ─── Parsing ────────────────────────────────────────────────────────────────
+
+/// Split one CSV line, honoring double-quoted fields with `""` escapes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+fn parse_csv(content: &str) -> Result<IdentityBatch, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'));
+    let (_, header) = lines.next().ok_or("The file is empty")?;
+    let columns: Vec<String> = split_csv_line(header.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .map(|c| c.to_lowercase())
+        .collect();
+    let col = |name: &str| columns.iter().position(|c| c == name);
+    let name_col = col("name")
+        .or_else(|| col("email"))
+        .ok_or("CSV header must include a 'name' column")?;
+    let (type_col, display_col, groups_col, permission_col) =
+        (col("type"), col("display_name"), col("groups"), col("workspace_permission"));
+
+    let mut batch = IdentityBatch::default();
+    for (index, line) in lines {
+        let fields = split_csv_line(line);
+        let get = |c: Option<usize>| {
+            c.and_then(|i| fields.get(i))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let name = get(Some(name_col)).ok_or_else(|| format!("Line {}: missing name", index + 1))?;
+        let kind = get(type_col).unwrap_or_else(|| "user".to_string()).to_lowercase();
+        let workspace_permission = get(permission_col);
+        match kind.as_str() {
+            "user" => batch.users.push(BatchUser {
+                email: name,
+                display_name: get(display_col),
+                groups: get(groups_col)
+                    .map(|g| g.split(';').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                    .unwrap_or_default(),
+                workspace_permission,
+            }),
+            "group" => batch.groups.push(BatchGroup { name, workspace_permission }),
+            other => return Err(format!("Line {}: unknown type '{}' (expected user or group)", index + 1, other)),
+        }
+    }
+    Ok(batch)
+}
+
+fn is_valid_email(email: &str) -> bool {
+    email
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.contains('@'))
+        && !email.chars().any(|c| c.is_whitespace() || c == '"')
+}
+
+fn normalize_permission(permission: &Option<String>, target: &str) -> Result<Option<String>, String> {
+    match permission.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        None => Ok(None),
+        Some(p) => {
+            let upper = p.to_uppercase();
+            if PERMISSIONS.contains(&upper.as_str()) {
+                Ok(Some(upper))
+            } else {
+                Err(format!("{}: workspace permission must be USER or ADMIN, not '{}'", target, p))
+            }
+        }
+    }
+}
+
+/// Validate entries, merge duplicates, and add groups referenced by users.
+pub(crate) fn normalize_batch(batch: IdentityBatch) -> Result<IdentityBatch, String> {
+    let mut users: Vec<BatchUser> = Vec::new();
+    for user in batch.users {
+        let email = user.email.trim().to_string();
+        if !is_valid_email(&email) {
+            return Err(format!("Invalid email address: {}", email));
+        }
+        let permission = normalize_permission(&user.workspace_permission, &email)?;
+        let groups: Vec<String> = user.groups.iter().map(|g| g.trim().to_string()).filter(|g| !g.is_empty()).collect();
+        match users.iter_mut().find(|u| u.email.eq_ignore_ascii_case(&email)) {
+            Some(existing) => {
+                for group in groups {
+                    if !existing.groups.contains(&group) {
+                        existing.groups.push(group);
+                    }
+                }
+                existing.display_name = existing.display_name.take().or(user.display_name);
+                existing.workspace_permission = existing.workspace_permission.take().or(permission);
+            }
+            None => users.push(BatchUser {
+                email,
+                display_name: user.display_name.filter(|d| !d.trim().is_empty()),
+                groups,
+                workspace_permission: permission,
+            }),
+        }
+    }
+
+    let mut groups: Vec<BatchGroup> = Vec::new();
+    let referenced = users.iter().flat_map(|u| u.groups.iter()).map(|g| BatchGroup {
+        name: g.clone(),
+        workspace_permission: None,
+    });
+    for group in batch.groups.into_iter().chain(referenced) {
+        let name = group.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Group names cannot be empty".to_string());
+        }
+        let permission = normalize_permission(&group.workspace_permission, &name)?;
+        match groups.iter_mut().find(|g| g.name == name) {
+            Some(existing) => existing.workspace_permission = existing.workspace_permission.take().or(permission),
+            None => groups.push(BatchGroup {
+                name,
+                workspace_permission: permission,
+            }),
+        }
+    }
+
+    if users.len() + groups.len() > MAX_ENTRIES {
+        return Err(format!("At most {} users and groups can be provisioned at once", MAX_ENTRIES));
+    }
+    Ok(IdentityBatch { users, groups })
+}
+
+/// Parse a provisioning file; the format follows the extension (`.json`,
+/// otherwise CSV).
+pub(crate) fn parse_batch(path: &Path) -> Result<IdentityBatch, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let batch = if is_json {
+        serde_json::from_str(&content).map_err(|e| format!("Invalid JSON: {}", e))?
+    } else {
+        parse_csv(&content)?
+    };
+    normalize_batch(batch)
+}
+
+// ─── Provisioning ───────────────────────────────────────────────────────────
+
+fn record<T>(steps: &mut Vec<ProvisionStep>, action: &str, target: &str, result: &Result<T, String>, ok: &str) {
+    steps.push(ProvisionStep {
+        action: action.to_string(),
+        target: target.to_string(),
+        success: result.is_ok(),
+        message: match result {
+            Ok(_) => ok.to_string(),
+            Err(e) => e.clone(),
+        },
+    });
+}
+
+/// ID of a principal from its create call, or from `find` when it already
+/// exists (`find` only runs then). Returns (id, created).
+async fn created_or_found(
+    kind: &str,
+    created: Result<String, ApiError>,
+    find: impl Future<Output = Result<Option<String>, ApiError>>,
+) -> Result<(String, bool), String> {
+    match created {
+        Ok(id) => Ok((id, true)),
+        Err(ApiError::Conflict(_)) => match find.await? {
+            Some(id) => Ok((id, false)),
+            None => Err(format!("{} exists but could not be looked up", kind)),
+        },
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn ensure_group(client: &AccountClient, name: &str) -> Result<(String, bool), String> {
+    let created = client.create_group(name).await.map(|g| g.id);
+    created_or_found("Group", created, async { client.find_group(name).await.map(|g| g.map(|g| g.id)) }).await
+}
+
+async fn ensure_user(client: &AccountClient, user: &BatchUser) -> Result<(String, bool), String> {
+    let display_name = user.display_name.as_deref().unwrap_or(&user.email);
+    let created = client.create_user(&user.email, display_name).await.map(|u| u.id);
+    created_or_found("User", created, async { client.find_user(&user.email).await.map(|u| u.map(|u| u.id)) }).await
+}
+
+fn created_message(kind: &str, created: bool) -> String {
+    if created {
+        format!("Created {}", kind)
+    } else {
+        "Already exists".to_string()
+    }
+}
+
+/// Provision a batch against the account. Failures are recorded per step;
+/// later steps skip principals whose creation failed.
+pub(crate) async fn provision(client: &AccountClient, batch: &IdentityBatch, workspace_id: Option<u64>) -> ProvisionReport {
+    let mut steps = Vec::new();
+    let mut group_ids: HashMap<&str, String> = HashMap::new();
+    let mut user_ids: HashMap<&str, String> = HashMap::new();
+
+    for group in &batch.groups {
+        let result = ensure_group(client, &group.name).await;
+        let message = result.as_ref().map(|(_, created)| created_message("group", *created)).unwrap_or_default();
+        record(&mut steps, "create_group", &group.name, &result, &message);
+        if let Ok((id, _)) = result {
+            group_ids.insert(&group.name, id);
+        }
+    }
+
+    for user in &batch.users {
+        let result = ensure_user(client, user).await;
+        let message = result.as_ref().map(|(_, created)| created_message("user", *created)).unwrap_or_default();
+        record(&mut steps, "create_user", &user.email, &result, &message);
+        if let Ok((id, _)) = result {
+            user_ids.insert(&user.email, id);
+        }
+    }
+
+    for group in &batch.groups {
+        let Some(group_id) = group_ids.get(group.name.as_str()) else {
+            continue;
+        };
+        let members: Vec<String> = batch
+            .users
+            .iter()
+            .filter(|u| u.groups.contains(&group.name))
+            .filter_map(|u| user_ids.get(u.email.as_str()).cloned())
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        let result = client.add_group_members(group_id, &members).await.map_err(String::from);
+        let message = format!("Added {} member(s)", members.len());
+        record(&mut steps, "add_members", &group.name, &result, &message);
+    }
+
+    if let Some(workspace_id) = workspace_id {
+        let principals = batch
+            .groups
+            .iter()
+            .filter_map(|g| Some((g.name.as_str(), group_ids.get(g.name.as_str())?, g.workspace_permission.as_deref()?)))
+            .chain(batch.users.iter().filter_map(|u| {
+                Some((u.email.as_str(), user_ids.get(u.email.as_str())?, u.workspace_permission.as_deref()?))
+            }));
+        for (target, principal_id, permission) in principals {
+            let result = client
+                .assign_workspace_permission(workspace_id, principal_id, permission)
+                .await
+                .map_err(String::from);
+            let message = format!("Granted {} on workspace {}", permission, workspace_id);
+            record(&mut steps, "assign_workspace", target, &result, &message);
+        }
+    }
+
+    ProvisionReport {
+        users: batch.users.len(),
+        groups: batch.groups.len(),
+        failed: steps.iter().filter(|s| !s.success).count(),
+        steps,
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Parse and validate a provisioning file without calling the account API.
+#[tauri::command]
+pub fn preview_identity_batch(file_path: String) -> Result<IdentityBatch, String> {
+    parse_batch(Path::new(&file_path))
+}
+
+/// Create the users and groups listed in a CSV/JSON file in the Databricks
+/// account, add group members, and (with `workspace_id`) assign workspace
+/// permissions.
+#[tauri::command]
+pub async fn provision_account_identities(
+    credentials: CloudCredentials,
+    file_path: String,
+    workspace_id: Option<u64>,
+) -> Result<ProvisionReport, String> {
+    let batch = parse_batch(Path::new(&file_path))?;
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks account ID is required")?;
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    client.access_token().await.map_err(|e| {
+        format!("Failed to authenticate to {}: {}", databricks_accounts_host(&cloud), e)
+    })?;
+    Ok(provision(&client, &batch, workspace_id).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quoted_csv_fields() {
+        assert_eq!(
+            split_csv_line(r#"user,a@example.com,"Doe, Jane","data ""eng"";admins",ADMIN"#),
+            vec!["user", "a@example.com", "Doe, Jane", "data \"eng\";admins", "ADMIN"]
+        );
+    }
+
+    #[test]
+    fn parses_csv_users_and_groups() {
+        let csv = "type,name,display_name,groups,workspace_permission\n\
+                   # comment\n\
+                   user,a@example.com,Jane,data-eng;admins,user\n\
+                   user,b@example.com,,data-eng,\n\
+                   group,analysts,,,USER\n";
+        let batch = normalize_batch(parse_csv(csv).unwrap()).unwrap();
+        assert_eq!(batch.users.len(), 2);
+        assert_eq!(batch.users[0].groups, vec!["data-eng", "admins"]);
+        assert_eq!(batch.users[0].workspace_permission.as_deref(), Some("USER"));
+        let groups: Vec<&str> = batch.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(groups, vec!["analysts", "data-eng", "admins"]);
+    }
+
+    #[test]
+    fn email_column_without_type_means_users() {
+        let batch = parse_csv("email\nx@example.com\n").unwrap();
+        assert_eq!(batch.users[0].email, "x@example.com");
+    }
+
+    #[test]
+    fn rejects_bad_entries() {
+        assert!(parse_csv("display_name\nJane\n").unwrap_err().contains("'name' column"));
+        assert!(parse_csv("type,name\nrobot,x\n").unwrap_err().contains("unknown type"));
+        let bad_email = IdentityBatch {
+            users: vec![BatchUser {
+                email: "jane".into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(normalize_batch(bad_email).unwrap_err(), "Invalid email address: jane");
+        let bad_permission = IdentityBatch {
+            groups: vec![BatchGroup {
+                name: "g".into(),
+                workspace_permission: Some("owner".into()),
+            }],
+            ..Default::default()
+        };
+        assert!(normalize_batch(bad_permission).unwrap_err().contains("USER or ADMIN"));
+    }
+
+    #[test]
+    fn merges_duplicate_users() {
+        let batch = IdentityBatch {
+            users: vec![
+                BatchUser {
+                    email: "a@example.com".into(),
+                    groups: vec!["x".into()],
+                    ..Default::default()
+                },
+                BatchUser {
+                    email: "A@example.com".into(),
+                    groups: vec!["y".into()],
+                    workspace_permission: Some("admin".into()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let batch = normalize_batch(batch).unwrap();
+        assert_eq!(batch.users.len(), 1);
+        assert_eq!(batch.users[0].groups, vec!["x", "y"]);
+        assert_eq!(batch.users[0].workspace_permission.as_deref(), Some("ADMIN"));
+    }
+
+    #[test]
+    fn parses_json_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.json");
+        fs::write(&path, r#"{"users": [{"email": "a@example.com", "groups": ["eng"]}]}"#).unwrap();
+        let batch = parse_batch(&path).unwrap();
+        assert_eq!(batch.groups, vec![BatchGroup { name: "eng".into(), workspace_permission: None }]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Launch arguments and second-launch handling.
+//!
+//! Only one instance of the app runs. Launching it again focuses the existing
+//! window, and arguments naming a deployment are forwarded to the UI, which
+//! opens that deployment. Two forms are accepted:
+//!
+//! - `--deployment <name>` (or `--deployment=<name>`)
+//! - `workspace-creator://deployment/<name>` or
+//!   `workspace-creator://open?deployment=<name>`, as passed on the command
+//!   line by an OS URL handler
+//!
+//! A request made at first launch is held until the UI asks for it with
+//! [`take_launch_request`]; later ones are also emitted as
+//! [`OPEN_DEPLOYMENT_EVENT`].
+
+use super::{
+    debug_log, lock_or_recover, sanitize_deployment_name, strip_invalid_name_chars,
+    MAX_DEPLOYMENT_NAME_LEN,
+};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// URL scheme of deep links.
+pub const DEEP_LINK_SCHEME: &str = "workspace-creator";
+
+/// Emitted with a [`LaunchRequest`] when a second launch names a deployment.
+pub const OPEN_DEPLOYMENT_EVENT: &str = "open-deployment";
+
+lazy_static::lazy_static! {
+    /// Latest request not yet picked up by the UI.
+    static ref PENDING_LAUNCH: Mutex<Option<LaunchRequest>> = Mutex::new(None);
+}
+
+/// What a launch asked the UI to open.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LaunchRequest {
+    pub deployment: String,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Deployment named by a `workspace-creator://` URL.
+fn parse_deep_link(link: &str) -> Option<String> {
+    let url = reqwest::Url::parse(link).ok()?;
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return None;
+    }
+    match url.host_str()? {
+        "deployment" | "deployments" => url
+            .path_segments()?
+            .find(|segment| !segment.is_empty())
+            .and_then(percent_decode),
+        "open" => url
+            .query_pairs()
+            .find(|(key, _)| key == "deployment")
+            .map(|(_, value)| value.into_owned()),
+        _ => None,
+    }
+}
+
+/// Percent-decode a URL path segment.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse("x:/").ok()?;
+    url.set_query(Some(&format!("v={}", segment)));
+    url.query_pairs().next().map(|(_, value)| value.into_owned())
+}
+
+/// Deployment name for a name from a link or flag: invalid characters are
+/// stripped exactly as when the deployment folder was named, the result is
+/// cut to [`MAX_DEPLOYMENT_NAME_LEN`], and only then are outer hyphens
+/// trimmed, so the cut never leaves one dangling.
+fn normalize_name(raw: &str) -> String {
+    let mut name = strip_invalid_name_chars(raw);
+    let mut end = 0;
+    for (i, c) in name.char_indices() {
+        if i + c.len_utf8() > MAX_DEPLOYMENT_NAME_LEN {
+            break;
+        }
+        end = i + c.len_utf8();
+    }
+    name.truncate(end);
+    name.trim_matches('-').to_string()
+}
+
+/// Find a deployment request in launch arguments (program name included).
+fn parse_launch_args(args: &[String]) -> Option<LaunchRequest> {
+    let mut args = args.iter().skip(1);
+    let mut name = None;
+    while let Some(arg) = args.next() {
+        if arg == "--deployment" {
+            name = args.next().cloned();
+        } else if let Some(value) = arg.strip_prefix("--deployment=") {
+            name = Some(value.to_string());
+        } else if arg.starts_with(&format!("{}:", DEEP_LINK_SCHEME)) {
+            name = parse_deep_link(arg);
+        }
+    }
+    let name = name?;
+    match sanitize_deployment_name(&normalize_name(&name)) {
+        Ok(deployment) => Some(LaunchRequest { deployment }),
+        Err(_e) => {
+            debug_log!("[launch] Ignoring deployment argument {:?}: {}", name, _e);
+            None
+        }
+    }
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Record a request from this process's own arguments. Called once at startup.
+pub fn handle_initial_launch() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(request) = parse_launch_args(&args) {
+        *lock_or_recover(&PENDING_LAUNCH) = Some(request);
+    }
+}
+
+/// Handle a second launch: focus this instance and forward any deployment
+/// it named to the UI.
+pub fn handle_second_launch(app: &AppHandle, args: &[String]) {
+    focus_main_window(app);
+    if let Some(request) = parse_launch_args(args) {
+        *lock_or_recover(&PENDING_LAUNCH) = Some(request.clone());
+        let _ = app.emit(OPEN_DEPLOYMENT_EVENT, &request);
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// The deployment a launch asked to open, if the UI hasn't handled it yet.
+/// Clears the request.
+#[tauri::command]
+pub fn take_launch_request() -> Option<LaunchRequest> {
+    lock_or_recover(&PENDING_LAUNCH).take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("databricks-deployer")
+            .chain(list.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn parsed(list: &[&str]) -> Option<String> {
+        parse_launch_args(&args(list)).map(|r| r.deployment)
+    }
+
+    #[test]
+    fn parses_deployment_flags() {
+        assert_eq!(parsed(&["--deployment", "prod-ws"]).as_deref(), Some("prod-ws"));
+        assert_eq!(parsed(&["--deployment=dev_ws"]).as_deref(), Some("dev_ws"));
+        assert_eq!(parsed(&[]), None);
+        assert_eq!(parsed(&["--deployment"]), None);
+        // The program name itself is never taken as an argument
+        assert_eq!(parse_launch_args(&["--deployment=x".to_string()]), None);
+    }
+
+    #[test]
+    fn parses_deep_links() {
+        assert_eq!(parsed(&["workspace-creator://deployment/prod-ws"]).as_deref(), Some("prod-ws"));
+        assert_eq!(parsed(&["workspace-creator://deployments/prod-ws/"]).as_deref(), Some("prod-ws"));
+        assert_eq!(parsed(&["workspace-creator://open?deployment=my%20ws"]).as_deref(), Some("myws"));
+        assert_eq!(parsed(&["workspace-creator://settings"]), None);
+        assert_eq!(parsed(&["other-app://deployment/prod-ws"]), None);
+        // Path traversal is stripped like any other invalid name character
+        assert_eq!(parsed(&["workspace-creator://deployment/..%2F..%2Fetc"]).as_deref(), Some("etc"));
+        assert_eq!(parsed(&["--deployment", "../"]), None);
+    }
+
+    #[test]
+    fn long_names_are_cut_before_hyphens_are_trimmed() {
+        let long = format!("{}-b c", "a".repeat(MAX_DEPLOYMENT_NAME_LEN - 1));
+        assert_eq!(normalize_name(&long), "a".repeat(MAX_DEPLOYMENT_NAME_LEN - 1));
+        // Same name the deployment folder got
+        assert_eq!(normalize_name("prod.eu"), sanitize_deployment_name("prod.eu").unwrap());
+        let wide = "é".repeat(MAX_DEPLOYMENT_NAME_LEN);
+        assert!(normalize_name(&wide).len() <= MAX_DEPLOYMENT_NAME_LEN);
+    }
+}
@@ -0,0 +1,246 @@
+//! Interactive CLI logins driven from the app.
+//!
+//! A GUI app launched from Finder/Explorer has no terminal, so login CLIs
+//! (`az login`, `databricks auth login`) run with captured output. Each line
+//! is scanned for a verification URL and user code, which are sent to the
+//! frontend as a [`LoginPrompt`] while the process is polled to completion.
+//! `aws sso login` runs the same way; it prints its user code on the line
+//! after the prompt, which [`parse_with_context`] accounts for.
+
+use super::{debug_log, lock_or_recover, CLI_LOGIN_PROCESS};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::process::Child;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying a [`LoginPrompt`] to the frontend.
+pub const LOGIN_PROMPT_EVENT: &str = "cli-login-prompt";
+
+/// Phrases meaning the CLI expects the user to open the URL themselves
+/// (it did not or could not launch a browser).
+const MANUAL_OPEN_HINTS: &[&str] = &[
+    "open the page",
+    "open the following",
+    "following link",
+    "please visit",
+    "navigate to",
+    "open this url",
+    "browser did not open",
+    "unable to open",
+    "failed to open",
+];
+
+lazy_static! {
+    static ref URL_RE: Regex = Regex::new(r#"https?://[^\s"'<>]+"#).unwrap();
+    static ref USER_CODE_RE: Regex =
+        Regex::new(r"(?i:enter the code|user code|the code|code:)\s*:?\s*([A-Z0-9]{4,}(?:-[A-Z0-9]{4,})?)\b").unwrap();
+    /// A user code printed on its own line (`aws sso login` puts it below the prompt).
+    static ref BARE_CODE_RE: Regex = Regex::new(r"^[A-Z0-9]{4,}-[A-Z0-9]{4,}$").unwrap();
+}
+
+/// Verification details surfaced by a login CLI.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct LoginPrompt {
+    /// "azure", "databricks", "aws", ...
+    pub provider: String,
+    pub verification_url: Option<String>,
+    pub user_code: Option<String>,
+    /// The output line the details came from.
+    pub message: String,
+    /// Whether the app opened the URL in the browser.
+    pub opened_browser: bool,
+}
+
+/// Extract a verification URL and user code from one line of CLI output.
+pub(crate) fn parse_login_line(line: &str) -> (Option<String>, Option<String>) {
+    let url = URL_RE
+        .find(line)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', ')', ';']).to_string());
+    let code = USER_CODE_RE
+        .captures(line)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_string());
+    (url, code)
+}
+
+/// Like [`parse_login_line`], but also accepts a bare user code when the
+/// previous non-empty line asked for one.
+fn parse_with_context(prev: &str, line: &str) -> (Option<String>, Option<String>) {
+    let (url, code) = parse_login_line(line);
+    let code = code.or_else(|| {
+        let asks_for_code = prev.trim_end().to_lowercase().ends_with("code:");
+        let bare = line.trim();
+        (asks_for_code && BARE_CODE_RE.is_match(bare)).then(|| bare.to_string())
+    });
+    (url, code)
+}
+
+/// The app opens the URL only for device-code flows or when the CLI asks the
+/// user to; otherwise the CLI has already launched the browser itself.
+fn should_open_browser(line: &str, prompt: &LoginPrompt) -> bool {
+    let lower = line.to_lowercase();
+    prompt.user_code.is_some() || MANUAL_OPEN_HINTS.iter().any(|hint| lower.contains(hint))
+}
+
+/// Outcome of a login process.
+#[derive(Debug, Default)]
+pub(crate) struct LoginExit {
+    pub success: bool,
+    /// Cancelled via `cancel_cli_login` (or killed externally).
+    pub cancelled: bool,
+    /// Captured stderr, trimmed.
+    pub stderr: String,
+    /// Captured stdout, trimmed.
+    pub stdout: String,
+}
+
+fn forward_lines<R: Read + Send + 'static>(reader: R, is_stderr: bool, tx: mpsc::Sender<(bool, String)>) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if tx.send((is_stderr, line)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Run a spawned login CLI (stdout/stderr piped) to completion: hold the
+/// login slot so `cancel_cli_login` can stop it, emit [`LOGIN_PROMPT_EVENT`]
+/// when a verification URL or code appears, and give up after `timeout`.
+pub(crate) async fn run_login_process(
+    app: &AppHandle,
+    provider: &str,
+    mut child: Child,
+    timeout: Duration,
+    timeout_message: &str,
+) -> Result<LoginExit, String> {
+    super::acquire_login_slot(child.id()).inspect_err(|_| {
+        let _ = child.kill();
+    })?;
+
+    let (tx, rx) = mpsc::channel();
+    let readers: Vec<_> = [
+        child.stdout.take().map(|out| forward_lines(out, false, tx.clone())),
+        child.stderr.take().map(|err| forward_lines(err, true, tx.clone())),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    drop(tx);
+
+    let mut exit = LoginExit::default();
+    let mut prompt = LoginPrompt {
+        provider: provider.to_string(),
+        ..Default::default()
+    };
+    let mut prev_line = String::new();
+    let mut handle_line = |is_stderr: bool, line: String| {
+        let (url, code) = parse_with_context(&prev_line, &line);
+        let changed = (url.is_some() && url != prompt.verification_url)
+            || (code.is_some() && code != prompt.user_code);
+        if changed {
+            prompt.verification_url = url.or(prompt.verification_url.take());
+            prompt.user_code = code.or(prompt.user_code.take());
+            prompt.message = line.trim().to_string();
+            // Hints like "open the following URL:" often sit on the line above the URL
+            let context = format!("{} {}", prev_line, line);
+            if let Some(url) = prompt.verification_url.clone().filter(|_| !prompt.opened_browser && should_open_browser(&context, &prompt)) {
+                prompt.opened_browser = super::open_url(url).is_ok();
+            }
+            debug_log!("[login:{}] prompt: url={:?} code={:?}", prompt.provider, prompt.verification_url, prompt.user_code);
+            let _ = app.emit(LOGIN_PROMPT_EVENT, &prompt);
+        }
+        if !line.trim().is_empty() {
+            prev_line = line.clone();
+        }
+        let buf = if is_stderr { &mut exit.stderr } else { &mut exit.stdout };
+        buf.push_str(&line);
+        buf.push('\n');
+    };
+
+    let start = Instant::now();
+    let status = loop {
+        while let Ok((is_stderr, line)) = rx.try_recv() {
+            handle_line(is_stderr, line);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) if start.elapsed() >= timeout => {
+                let _ = child.kill();
+                break Err(timeout_message.to_string());
+            }
+            Ok(None) => tokio::time::sleep(Duration::from_millis(300)).await,
+            Err(e) => break Err(format!("Error waiting for {} login: {}", provider, e)),
+        }
+    };
+
+    // Pipes close when the process exits; collect whatever is left.
+    for reader in readers {
+        let _ = reader.join();
+    }
+    while let Ok((is_stderr, line)) = rx.try_recv() {
+        handle_line(is_stderr, line);
+    }
+
+    let was_cancelled = lock_or_recover(&CLI_LOGIN_PROCESS).is_none();
+    super::release_login_slot();
+
+    let status = status?;
+    exit.success = status.success();
+    exit.stderr = exit.stderr.trim().to_string();
+    exit.stdout = exit.stdout.trim().to_string();
+    exit.cancelled = !exit.success
+        && (was_cancelled
+            || exit.stderr.is_empty()
+            || exit.stderr.contains("Killed")
+            || exit.stderr.contains("terminated"));
+    Ok(exit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_azure_device_code_line() {
+        let (url, code) = parse_login_line(
+            "To sign in, use a web browser to open the page https://microsoft.com/devicelogin and enter the code ABCD2EFGH to authenticate.",
+        );
+        assert_eq!(url.as_deref(), Some("https://microsoft.com/devicelogin"));
+        assert_eq!(code.as_deref(), Some("ABCD2EFGH"));
+    }
+
+    #[test]
+    fn parses_aws_sso_code_and_trims_punctuation() {
+        let (url, code) = parse_login_line("Then enter the code: WXYZ-KLMN at https://device.sso.us-east-1.amazonaws.com/.");
+        assert_eq!(url.as_deref(), Some("https://device.sso.us-east-1.amazonaws.com/"));
+        assert_eq!(code.as_deref(), Some("WXYZ-KLMN"));
+    }
+
+    #[test]
+    fn parses_code_on_line_after_prompt() {
+        assert_eq!(parse_with_context("Then enter the code:", "QWER-TYUI").1.as_deref(), Some("QWER-TYUI"));
+        assert_eq!(parse_with_context("Successfully logged into Start URL:", "QWER-TYUI").1, None);
+        assert_eq!(parse_with_context("Then enter the code:", "https://device.sso.us-east-1.amazonaws.com/").1, None);
+    }
+
+    #[test]
+    fn ignores_lines_without_prompts() {
+        assert_eq!(parse_login_line("Retrieving tenants and subscriptions..."), (None, None));
+        // Lowercase words after "code" are not user codes
+        assert_eq!(parse_login_line("exit code 1").1, None);
+    }
+
+    #[test]
+    fn opens_browser_only_when_cli_asks() {
+        let mut prompt = LoginPrompt::default();
+        assert!(!should_open_browser("Opening browser at https://accounts.cloud.databricks.com/oidc", &prompt));
+        assert!(should_open_browser("Please visit https://example.com to continue", &prompt));
+        prompt.user_code = Some("ABCD-EFGH".to_string());
+        assert!(should_open_browser("https://microsoft.com/devicelogin", &prompt));
+    }
+}
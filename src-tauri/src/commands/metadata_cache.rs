@@ -0,0 +1,240 @@
+//! Short-lived cache for cloud metadata the wizard lists repeatedly.
+//!
+//! Subscriptions, resource groups, locations, and Unity Catalog metastores
+//! are kept for [`TTL`] under a key made of the query and a hash of the
+//! credentials it ran with, so going back and forth between wizard steps
+//! doesn't re-run the same CLI or API call. Commands take a `refresh` flag
+//! that skips the cache and stores the new result. Failures are never cached.
+//!
+//! With `persist_metadata_cache` turned on in the settings, entries are also
+//! written to `metadata-cache.json` in the data directory and survive a
+//! restart. Entries hold only the listed metadata, never credentials.
+
+use super::audit::now_secs;
+use super::{debug_log, lock_or_recover, CloudCredentials};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub(crate) const CACHE_FILE: &str = "metadata-cache.json";
+
+/// How long an entry is served before it is fetched again, in seconds.
+pub(crate) const TTL: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    stored_at: u64,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    /// Where entries are persisted, when enabled in the settings.
+    file: Option<PathBuf>,
+}
+
+impl Cache {
+    fn save(&self) {
+        let Some(file) = &self.file else { return };
+        let result = serde_json::to_string(&self.entries)
+            .map_err(|e| e.to_string())
+            .and_then(|content| fs::write(file, content).map_err(|e| e.to_string()));
+        if let Err(_e) = result {
+            debug_log!("[metadata_cache] Failed to write {}: {}", file.display(), _e);
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<Cache> = Mutex::new(Cache::default());
+}
+
+/// Cache key for a `kind` of query with its parameters. `credentials` is
+/// `None` for queries that run as the signed-in CLI user.
+pub(crate) fn key(kind: &str, credentials: Option<&CloudCredentials>, params: &[&str]) -> String {
+    let identity = match credentials {
+        Some(credentials) => fingerprint(credentials),
+        None => "cli".to_string(),
+    };
+    let mut key = format!("{}:{}", kind, identity);
+    for param in params {
+        key.push(':');
+        key.push_str(param);
+    }
+    key
+}
+
+/// Hash identifying a set of credentials without revealing them.
+fn fingerprint(credentials: &CloudCredentials) -> String {
+    let bytes = serde_json::to_vec(credentials).unwrap_or_default();
+    Sha256::digest(&bytes)
+        .iter()
+        .take(12)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn lookup<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let cache = lock_or_recover(&CACHE);
+    let entry = cache.entries.get(key)?;
+    if now_secs().saturating_sub(entry.stored_at) >= TTL {
+        return None;
+    }
+    serde_json::from_value(entry.value.clone()).ok()
+}
+
+fn store<T: Serialize>(key: String, value: &T) {
+    let Ok(value) = serde_json::to_value(value) else { return };
+    let mut cache = lock_or_recover(&CACHE);
+    let now = now_secs();
+    cache.entries.retain(|_, entry| now.saturating_sub(entry.stored_at) < TTL);
+    cache.entries.insert(key, Entry { stored_at: now, value });
+    cache.save();
+}
+
+/// The cached value for `key`, or the result of `fetch` (stored on success).
+pub(crate) fn get_or_fetch<T, E>(key: String, refresh: bool, fetch: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if !refresh {
+        if let Some(value) = lookup(&key) {
+            return Ok(value);
+        }
+    }
+    let value = fetch()?;
+    store(key, &value);
+    Ok(value)
+}
+
+/// [`get_or_fetch`] for async queries.
+pub(crate) async fn get_or_fetch_async<T, E>(
+    key: String,
+    refresh: bool,
+    fetch: impl Future<Output = Result<T, E>>,
+) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if !refresh {
+        if let Some(value) = lookup(&key) {
+            return Ok(value);
+        }
+    }
+    let value = fetch.await?;
+    store(key, &value);
+    Ok(value)
+}
+
+/// Persist entries to `file`, loading what it already holds, or keep them in
+/// memory only (removing a previously used file). Called when settings apply.
+pub(crate) fn set_cache_file(file: Option<PathBuf>) {
+    let mut cache = lock_or_recover(&CACHE);
+    if cache.file == file {
+        return;
+    }
+    if let Some(old) = cache.file.take() {
+        let _ = fs::remove_file(old);
+    }
+    if let Some(path) = &file {
+        let stored: HashMap<String, Entry> = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let now = now_secs();
+        for (key, entry) in stored {
+            if now.saturating_sub(entry.stored_at) < TTL {
+                cache.entries.entry(key).or_insert(entry);
+            }
+        }
+    }
+    cache.file = file;
+    cache.save();
+}
+
+/// Drop the entry for `key`, e.g. after a change that makes it stale.
+pub(crate) fn remove(key: &str) {
+    let mut cache = lock_or_recover(&CACHE);
+    if cache.entries.remove(key).is_some() {
+        cache.save();
+    }
+}
+
+/// Drop every entry, e.g. after signing in as someone else.
+pub(crate) fn clear() {
+    let mut cache = lock_or_recover(&CACHE);
+    cache.entries.clear();
+    cache.save();
+}
+
+/// Forget all cached cloud metadata.
+#[tauri::command]
+pub fn clear_metadata_cache() {
+    clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_separate_credentials_and_params() {
+        let a = CloudCredentials { aws_profile: Some("a".into()), ..Default::default() };
+        let b = CloudCredentials { aws_profile: Some("b".into()), ..Default::default() };
+        assert_ne!(key("vpcs", Some(&a), &["us-east-1"]), key("vpcs", Some(&b), &["us-east-1"]));
+        assert_ne!(key("vpcs", Some(&a), &["us-east-1"]), key("vpcs", Some(&a), &["eu-west-1"]));
+        assert_eq!(key("vpcs", None, &["x"]), "vpcs:cli:x");
+
+        let secret = CloudCredentials { azure_client_secret: Some("hunter2".into()), ..Default::default() };
+        assert!(!key("groups", Some(&secret), &[]).contains("hunter2"));
+    }
+
+    #[test]
+    fn cached_until_refreshed_and_errors_not_stored() {
+        let key = key("test-cached", None, &[]);
+        let fetch = |n: u32| move || Ok::<_, String>(n);
+        assert_eq!(get_or_fetch(key.clone(), false, fetch(1)), Ok(1));
+        assert_eq!(get_or_fetch(key.clone(), false, fetch(2)), Ok(1));
+        assert_eq!(get_or_fetch(key.clone(), true, fetch(3)), Ok(3));
+
+        let failing = super::key("test-failing", None, &[]);
+        assert!(get_or_fetch(failing.clone(), false, || Err::<u32, _>("down".to_string())).is_err());
+        assert_eq!(get_or_fetch(failing, false, fetch(4)), Ok(4));
+    }
+
+    #[test]
+    fn removed_entries_are_fetched_again() {
+        let key = key("test-removed", None, &[]);
+        assert_eq!(get_or_fetch(key.clone(), false, || Ok::<_, String>(1)), Ok(1));
+        remove(&key);
+        assert_eq!(get_or_fetch(key, false, || Ok::<_, String>(2)), Ok(2));
+    }
+
+    #[test]
+    fn expired_entries_are_fetched_again() {
+        let key = key("test-expired", None, &[]);
+        lock_or_recover(&CACHE)
+            .entries
+            .insert(key.clone(), Entry { stored_at: now_secs() - TTL, value: serde_json::json!(1) });
+        assert_eq!(get_or_fetch(key, false, || Ok::<_, String>(2)), Ok(2));
+    }
+
+    #[test]
+    fn persisted_entries_are_written_to_the_cache_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(CACHE_FILE);
+        set_cache_file(Some(file.clone()));
+        get_or_fetch(key("test-persisted", None, &[]), false, || Ok::<_, String>(7)).unwrap();
+        let stored: HashMap<String, Entry> = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(stored["test-persisted:cli"].value, 7);
+
+        set_cache_file(None);
+        assert!(!file.exists(), "turning persistence off removes the file");
+    }
+}
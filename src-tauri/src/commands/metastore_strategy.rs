@@ -0,0 +1,312 @@
+//! Explicit choice between reusing a Unity Catalog metastore and creating one.
+//!
+//! The templates look up a metastore in the workspace's region and create a
+//! new one when none matches, which accounts with a one-metastore-per-region
+//! policy don't want to happen silently. [`plan_metastore_strategy`] reports
+//! which of the two a deployment will do and, given a choice, pins it in
+//! `terraform.tfvars`. Region names are compared per cloud, so a metastore
+//! registered in `East US` can be picked for a workspace in `eastus`.
+
+use super::databricks::{find_metastore_for_region, list_metastores};
+use super::region_aliases::same_region;
+use super::{get_deployments_dir, sanitize_deployment_name, CloudCredentials};
+use crate::databricks_api::{AccountClient, Metastore};
+use crate::terraform;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+const TFVARS_FILE: &str = "terraform.tfvars";
+
+/// Variables holding the metastore ID, and whether leaving one empty makes
+/// the template create a metastore (rather than assign none).
+const METASTORE_ID_VARIABLES: &[(&str, bool)] = &[
+    ("existing_metastore_id", true),
+    ("databricks_metastore_id", true),
+    ("regional_metastore_id", false),
+];
+
+/// Required flag some templates use alongside the ID.
+const METASTORE_EXISTS_VARIABLE: &str = "metastore_exists";
+
+/// Region variables, in the order templates declare them.
+const REGION_VARIABLES: &[&str] = &["region", "location", "google_region"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetastoreAction {
+    Reuse,
+    CreateNew,
+    /// The template assigns no metastore when none is given.
+    Unassigned,
+}
+
+/// What the user picked.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MetastoreChoice {
+    Reuse { metastore_id: String },
+    CreateNew,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetastoreSummary {
+    pub id: String,
+    pub name: String,
+    pub region: String,
+}
+
+impl From<&Metastore> for MetastoreSummary {
+    fn from(m: &Metastore) -> Self {
+        Self { id: m.metastore_id.clone(), name: m.name.clone(), region: m.region.clone() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetastoreStrategy {
+    /// Workspace region from `terraform.tfvars`.
+    pub region: String,
+    pub action: MetastoreAction,
+    /// Metastore that will be reused.
+    pub metastore: Option<MetastoreSummary>,
+    /// Account metastores in the workspace's region.
+    pub candidates: Vec<MetastoreSummary>,
+    pub message: String,
+    /// tfvars variable that holds the choice.
+    pub variable: String,
+    /// Whether the choice was written to `terraform.tfvars`.
+    pub written: bool,
+}
+
+/// The deployment's metastore ID variable and whether empty means "create".
+fn metastore_variable(deployment_dir: &Path) -> Result<(&'static str, bool, bool), String> {
+    let content = fs::read_to_string(deployment_dir.join("variables.tf")).map_err(|_| "Deployment not found".to_string())?;
+    let declared: Vec<String> = terraform::parse_variables_tf(&content)?.into_iter().map(|v| v.name).collect();
+    let (name, creates) = METASTORE_ID_VARIABLES
+        .iter()
+        .copied()
+        .find(|(name, _)| declared.iter().any(|d| d == *name))
+        .ok_or("This template has no metastore setting")?;
+    Ok((name, creates, declared.iter().any(|d| d == METASTORE_EXISTS_VARIABLE)))
+}
+
+fn tfvar(entries: &[(String, String)], name: &str) -> Option<String> {
+    entries
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty() && value != "null")
+}
+
+/// Decide what the deployment will do. Without a `choice` this is what the
+/// template does today: the configured ID, else the regional metastore, else
+/// a new one.
+fn plan<'a>(
+    metastores: &'a [Metastore],
+    cloud: &str,
+    region: &str,
+    configured: Option<&str>,
+    choice: Option<&MetastoreChoice>,
+    creates_when_empty: bool,
+) -> Result<(MetastoreAction, Option<&'a Metastore>), String> {
+    let regional = find_metastore_for_region(metastores, cloud, region);
+    let reuse = |id: &str| -> Result<(MetastoreAction, Option<&'a Metastore>), String> {
+        let metastore = metastores
+            .iter()
+            .find(|m| m.metastore_id == id)
+            .ok_or_else(|| format!("Metastore '{}' was not found in the account", id))?;
+        if !same_region(cloud, &metastore.region, region) {
+            return Err(format!(
+                "Metastore '{}' is in {}; a workspace in {} can only use a metastore in its own region",
+                metastore.name, metastore.region, region
+            ));
+        }
+        Ok((MetastoreAction::Reuse, Some(metastore)))
+    };
+    match choice {
+        Some(MetastoreChoice::Reuse { metastore_id }) => reuse(metastore_id),
+        Some(MetastoreChoice::CreateNew) if !creates_when_empty => {
+            Err("This template can't create a metastore; choose an existing one".to_string())
+        }
+        Some(MetastoreChoice::CreateNew) => match regional {
+            Some(existing) => Err(format!(
+                "Metastore '{}' already serves {}; Databricks allows one metastore per region",
+                existing.name, region
+            )),
+            None => Ok((MetastoreAction::CreateNew, None)),
+        },
+        None => match (configured, regional) {
+            (Some(id), _) => reuse(id),
+            (None, Some(existing)) if creates_when_empty => Ok((MetastoreAction::Reuse, Some(existing))),
+            (None, _) if creates_when_empty => Ok((MetastoreAction::CreateNew, None)),
+            (None, _) => Ok((MetastoreAction::Unassigned, None)),
+        },
+    }
+}
+
+/// `content` with `name` set to `value` (HCL literal), or removed when
+/// `value` is `None`. Only single-line assignments are replaced.
+fn set_tfvar(content: &str, name: &str, value: Option<&str>) -> String {
+    // Top-level only: indented lines are keys inside maps and objects
+    let is_assignment = |line: &str| {
+        !line.starts_with(char::is_whitespace) && line.split_once('=').is_some_and(|(key, _)| key.trim() == name)
+    };
+    let mut found = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        if !is_assignment(line) {
+            lines.push(line.to_string());
+            continue;
+        }
+        found = true;
+        if let Some(value) = value {
+            lines.push(format!("{} = {}", name, value));
+        }
+    }
+    if let (false, Some(value)) = (found, value) {
+        lines.push(format!("{} = {}", name, value));
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// Write the decision into `terraform.tfvars`, keeping a backup.
+fn write_choice(
+    deployment_dir: &Path,
+    variable: &str,
+    has_exists_flag: bool,
+    metastore: Option<&Metastore>,
+) -> Result<(), String> {
+    let path = deployment_dir.join(TFVARS_FILE);
+    let current = fs::read_to_string(&path).unwrap_or_default();
+    let id = metastore.map(|m| format!("\"{}\"", m.metastore_id));
+    let mut updated = set_tfvar(&current, variable, id.as_deref());
+    if has_exists_flag {
+        updated = set_tfvar(&updated, METASTORE_EXISTS_VARIABLE, Some(if metastore.is_some() { "true" } else { "false" }));
+    }
+    super::config_changes::backup_tfvars(deployment_dir, &updated)?;
+    fs::write(&path, updated).map_err(|e| format!("Failed to write {}: {}", TFVARS_FILE, e))
+}
+
+fn describe(action: MetastoreAction, metastore: Option<&Metastore>, region: &str) -> String {
+    match (action, metastore) {
+        (MetastoreAction::Reuse, Some(m)) => format!("Will reuse metastore '{}' ({})", m.name, m.metastore_id),
+        (MetastoreAction::CreateNew, _) => format!("Will create a new metastore in {}", region),
+        _ => "No metastore will be assigned to the workspace".to_string(),
+    }
+}
+
+/// Report whether the deployment will reuse a metastore or create one in its
+/// region. With a `choice`, validate it against the account's metastores and
+/// pin it in `terraform.tfvars`.
+#[tauri::command]
+pub async fn plan_metastore_strategy(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+    choice: Option<MetastoreChoice>,
+    refresh: Option<bool>,
+) -> Result<MetastoreStrategy, String> {
+    let deployment_dir = get_deployments_dir(&app)?.join(sanitize_deployment_name(&deployment_name)?);
+    let (variable, creates_when_empty, has_exists_flag, entries) = {
+        let dir = deployment_dir.clone();
+        super::run_blocking(move || {
+            let (variable, creates_when_empty, has_exists_flag) = metastore_variable(&dir)?;
+            let tfvars = fs::read_to_string(dir.join(TFVARS_FILE)).unwrap_or_default();
+            Ok((variable, creates_when_empty, has_exists_flag, super::audit::parse_tfvars(&tfvars)))
+        })
+        .await?
+    };
+    let region = REGION_VARIABLES
+        .iter()
+        .find_map(|name| tfvar(&entries, name))
+        .ok_or("Set the deployment's region first")?;
+    let configured = tfvar(&entries, variable);
+
+    let cloud = credentials.cloud.clone().unwrap_or_else(|| "aws".to_string());
+    let account_id = credentials
+        .databricks_account_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or("Databricks account ID is required")?;
+    let auth = super::databricks::account_auth(&credentials).await?;
+    let client = AccountClient::new(&cloud, &account_id, auth)?;
+    let metastores = list_metastores(&client, &cloud, &credentials, refresh.unwrap_or(false))
+        .await
+        .map_err(|e| format!("Failed to list metastores: {}", e))?;
+
+    let (action, metastore) = plan(&metastores, &cloud, &region, configured.as_deref(), choice.as_ref(), creates_when_empty)?;
+    let written = choice.is_some();
+    if written {
+        let (app, dir, chosen) = (app.clone(), deployment_dir.clone(), metastore.cloned());
+        super::run_blocking(move || {
+            let _lock = super::deployment_lock::acquire(&app, &deployment_name, "metastore_strategy")?;
+            write_choice(&dir, variable, has_exists_flag, chosen.as_ref())
+        })
+        .await?;
+    }
+
+    Ok(MetastoreStrategy {
+        message: describe(action, metastore, &region),
+        action,
+        metastore: metastore.map(MetastoreSummary::from),
+        candidates: metastores
+            .iter()
+            .filter(|m| same_region(&cloud, &m.region, &region))
+            .map(MetastoreSummary::from)
+            .collect(),
+        variable: variable.to_string(),
+        written,
+        region,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metastore(id: &str, region: &str) -> Metastore {
+        Metastore { metastore_id: id.to_string(), name: format!("ms-{}", id), region: region.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn plan_reuses_regional_metastore_and_guards_region_mismatch() {
+        let metastores = vec![metastore("1", "East US"), metastore("2", "westeurope")];
+
+        let (action, reused) = plan(&metastores, "azure", "eastus", None, None, true).unwrap();
+        assert_eq!(action, MetastoreAction::Reuse);
+        assert_eq!(reused.unwrap().metastore_id, "1");
+
+        let choice = MetastoreChoice::Reuse { metastore_id: "2".to_string() };
+        assert!(plan(&metastores, "azure", "eastus", None, Some(&choice), true).unwrap_err().contains("own region"));
+        assert!(plan(&metastores, "azure", "eastus", None, Some(&MetastoreChoice::CreateNew), true).is_err());
+
+        let (action, _) = plan(&metastores, "azure", "northeurope", None, Some(&MetastoreChoice::CreateNew), true).unwrap();
+        assert_eq!(action, MetastoreAction::CreateNew);
+        let (action, _) = plan(&metastores, "azure", "northeurope", None, None, false).unwrap();
+        assert_eq!(action, MetastoreAction::Unassigned);
+    }
+
+    #[test]
+    fn set_tfvar_replaces_appends_and_removes() {
+        let content = "location = \"eastus\"\nexisting_metastore_id = \"\"\n";
+        let set = set_tfvar(content, "existing_metastore_id", Some("\"ms-1\""));
+        assert_eq!(set, "location = \"eastus\"\nexisting_metastore_id = \"ms-1\"\n");
+        assert_eq!(set_tfvar(&set, "metastore_exists", Some("true")), format!("{}metastore_exists = true\n", set));
+        assert_eq!(set_tfvar(&set, "existing_metastore_id", None), "location = \"eastus\"\n");
+    }
+
+    #[test]
+    fn write_choice_pins_id_and_exists_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(TFVARS_FILE), "region = \"us-east-1\"\nmetastore_exists = false\n").unwrap();
+
+        write_choice(dir.path(), "existing_metastore_id", true, Some(&metastore("1", "us-east-1"))).unwrap();
+        let written = fs::read_to_string(dir.path().join(TFVARS_FILE)).unwrap();
+        assert!(written.contains("metastore_exists = true"));
+        assert!(written.contains("existing_metastore_id = \"1\""));
+        assert!(dir.path().join(crate::commands::config_changes::TFVARS_BACKUP_DIR).is_dir());
+    }
+}
@@ -1,38 +1,201 @@
 //! Command handlers for the Tauri desktop application.
 //!
 //! This module is split into submodules by cloud provider and feature area:
+//! - [`account_admins`] - Users and service principals holding the Databricks account admin role
+//! - [`account_cleanup`] - Orphaned account network, storage, and credential configurations left by deleted workspaces
+//! - [`account_configs`] - Existing Databricks network, storage, and credential configurations for reuse
+//! - [`app_config`] - Export/import of shareable settings and organization profiles for team onboarding
+//! - [`audit`] - Deployment run history and audit report export
 //! - [`aws`] - AWS authentication and permission checking
+//! - [`bootstrap_artifacts`] - Inventory and cleanup of service accounts, roles, bindings, and profiles the app created
+//! - [`bundle`] - Standalone Terraform bundle export with secrets stripped
+//! - [`cancellation`] - Request ids for aborting long-running permission and Unity Catalog checks
+//! - [`ci_pipeline`] - CI/CD workflow generation for deployment repositories
 //! - [`azure`] - Azure authentication and permission checking
+//! - [`cli_runner`] - Cloud CLI calls with credentials, timeouts, and masked debug logging
+//! - [`cloud_provider`] - Per-cloud credential validation, permission checks, regions, and Terraform environment
+//! - [`config_changes`] - tfvars diff preview and backups before a configuration is rewritten
+//! - [`credential_context`] - Active subscription/project/account checked against the configuration before apply
+//! - [`credential_refresh`] - Token expiry checks and refreshes before Terraform runs
 //! - [`databricks`] - Databricks authentication and Unity Catalog permissions
+//! - [`databricks_profiles`] - Databricks CLI profile health checks and token cache management
+//! - [`debug_logs`] - Per-run Terraform debug logs (`TF_LOG`) with tail reads
 //! - [`deployment`] - Terraform deployment, configuration, and lifecycle management
+//! - [`deployment_compare`] - Template, tfvars and output differences between two deployments, secrets redacted
+//! - [`deployment_files`] - Deployment file listing, redacted viewer contents, and opening files in an editor
+//! - [`deployment_lock`] - Per-deployment lock files with stale-lock detection
+//! - [`deployment_queue`] - Queued Terraform runs started in order, optionally depending on the previous run
+//! - [`directory_sync`] - Azure AD / IAM Identity Center groups mirrored into the Databricks account
+//! - [`encryption_keys`] - Customer-managed key listing, key policy validation, and template variables
+//! - [`environment_snapshot`] - Tool, provider and OS versions recorded at apply and compared before later runs
+//! - [`expiry`] - Deployment TTLs and scheduled auto-destroy
 //! - [`gcp`] - GCP authentication, permission checking, and service account management
+//! - [`gcp_apis`] - Required GCP API state checks and enablement before deploying
+//! - [`git_hosting`] - GitLab / Bitbucket credentials and provider-agnostic repo creation
 //! - [`github`] - Git repository initialization and GitHub integration
+//! - [`identity_batch`] - Batch user/group provisioning into the Databricks account via SCIM
+//! - [`launch`] - Second-launch focus and deep-link arguments naming a deployment
+//! - [`login_flow`] - Captured interactive CLI logins with prompts forwarded to the UI
+//! - [`metadata_cache`] - TTL cache for subscription, resource group, region, and metastore lists
+//! - [`metastore_strategy`] - Reuse-or-create metastore decision pinned in tfvars, with a region mismatch guard
+//! - [`notifications`] - Native desktop notifications when deployment runs finish
+//! - [`org_profiles`] - Shared presets (region, prefix, tags, CIDRs, security settings) that prefill template variable defaults
+//! - [`orphan_scan`] - Cloud resources named or tagged for a deployment that its state doesn't track
+//! - [`post_deploy`] - Optional workspace setup (cluster policy, SQL warehouse, users) after apply
+//! - [`preflight`] - Concurrent pre-deployment checklist with per-check status events
+//! - [`private_connectivity`] - PrivateLink / Private Service Connect settings validation and reachability
+//! - [`provider_locks`] - Multi-platform provider checksums in the Terraform dependency lock file
+//! - [`quotas`] - Pre-deployment cloud quota checks
+//! - [`recent_items`] - Recently used and pinned deployments and templates for the command palette
+//! - [`region_aliases`] - Per-cloud region name normalization (display names, zones) for metastore and region matching
+//! - [`regions`] - Catalog of regions where Databricks is available
+//! - [`resource_names`] - Naming-rule and availability checks for globally unique names
+//! - [`rollback`] - Destroy plan preview with confirmation for data-bearing resources
+//! - [`run_recovery`] - Reattach to or close out Terraform runs interrupted by an app restart
+//! - [`run_settings`] - Per-deployment extra Terraform arguments, environment, and provider toggles
+//! - [`secret_vars`] - Sensitive variables stored encrypted and passed as `TF_VAR_*` instead of tfvars
+//! - [`settings`] - Application-wide settings with migration of older settings files
+//! - [`ssh_keys`] - SSH key detection, generation, and GitHub registration
+//! - [`state_backups`] - Terraform state snapshots before apply/destroy, with restore
+//! - [`state_encryption`] - Optional at-rest encryption of local Terraform state
+//! - [`tagging`] - Organization tag policy with per-cloud tag validation
+//! - [`telemetry`] - Opt-in anonymous usage telemetry with a documented event schema
+//! - [`template_lint`] - Best-practice checks (NAT egress, open SSH, storage encryption, metastore region) on templates and deployments
+//! - [`template_upgrade`] - Diff and upgrade deployments to the current template version
 //! - [`templates`] - Template setup, listing, and variable parsing
+//! - [`variable_sources`] - Dynamic dropdown options for template variables
 
+pub mod account_admins;
+pub mod account_cleanup;
+pub mod account_configs;
+pub mod app_config;
 pub mod assistant;
+pub mod audit;
 pub mod aws;
 pub mod azure;
+pub mod bootstrap_artifacts;
+pub mod bundle;
+pub mod cancellation;
+pub mod ci_pipeline;
+pub mod cli_runner;
+pub mod cloud_provider;
+pub mod config_changes;
+pub mod credential_context;
+pub mod credential_refresh;
 pub mod databricks;
+pub mod databricks_profiles;
+pub mod debug_logs;
 pub mod deployment;
+pub mod deployment_compare;
+pub mod deployment_files;
+pub mod deployment_lock;
+pub mod deployment_queue;
+pub mod directory_sync;
+pub mod encryption_keys;
+pub mod environment_snapshot;
+pub mod expiry;
 pub mod gcp;
+pub mod gcp_apis;
+pub mod git_hosting;
 pub mod github;
+pub mod identity_batch;
+pub mod launch;
+pub mod login_flow;
+pub mod metadata_cache;
+pub mod metastore_strategy;
+pub mod notifications;
+pub mod org_profiles;
+pub mod orphan_scan;
+pub mod post_deploy;
+pub mod preflight;
+pub mod private_connectivity;
+pub mod provider_locks;
+pub mod quotas;
+pub mod recent_items;
+pub mod region_aliases;
+pub mod regions;
+pub mod resource_names;
+pub mod rollback;
+pub mod run_recovery;
+pub mod run_settings;
+pub mod secret_vars;
+pub mod settings;
+pub mod ssh_keys;
+pub mod state_backups;
+pub mod state_encryption;
+pub mod tagging;
+pub mod telemetry;
+pub mod template_lint;
+pub mod template_upgrade;
 pub mod templates;
+pub mod variable_sources;
 
 // Re-export all commands so lib.rs can reference them as commands::function_name
+pub use account_admins::*;
+pub use account_cleanup::*;
+pub use account_configs::*;
+pub use app_config::*;
 pub use assistant::*;
+pub use audit::*;
 pub use aws::*;
 pub use azure::*;
+pub use bootstrap_artifacts::*;
+pub use bundle::*;
+pub use cancellation::*;
+pub use ci_pipeline::*;
+pub use config_changes::*;
+pub use credential_context::*;
+pub use credential_refresh::*;
 pub use databricks::*;
+pub use databricks_profiles::*;
+pub use debug_logs::*;
 pub use deployment::*;
+pub use deployment_compare::*;
+pub use deployment_files::*;
+pub use deployment_lock::*;
+pub use deployment_queue::*;
+pub use directory_sync::*;
+pub use encryption_keys::*;
+pub use environment_snapshot::*;
+pub use expiry::*;
 pub use gcp::*;
+pub use gcp_apis::*;
+pub use git_hosting::*;
 pub use github::*;
+pub use identity_batch::*;
+pub use launch::*;
+pub use metadata_cache::*;
+pub use metastore_strategy::*;
+pub use notifications::*;
+pub use org_profiles::*;
+pub use orphan_scan::*;
+pub use post_deploy::*;
+pub use preflight::*;
+pub use private_connectivity::*;
+pub use provider_locks::*;
+pub use quotas::*;
+pub use recent_items::*;
+pub use regions::*;
+pub use resource_names::*;
+pub use rollback::*;
+pub use run_recovery::*;
+pub use run_settings::*;
+pub use settings::*;
+pub use ssh_keys::*;
+pub use state_backups::*;
+pub use state_encryption::*;
+pub use tagging::*;
+pub use telemetry::*;
+pub use template_lint::*;
+pub use template_upgrade::*;
 pub use templates::*;
+pub use variable_sources::*;
 
+use crate::storage::Environment;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager};
 
 lazy_static::lazy_static! {
     /// PID of the currently running CLI login process (shared by Azure, AWS SSO, GCP,
@@ -176,6 +339,30 @@ pub struct CloudPermissionCheck {
     pub message: String,
     /// `true` = soft warning (can continue), `false` = hard block.
     pub is_warning: bool,
+    /// Per-permission checklist the fields above are derived from.
+    #[serde(default)]
+    pub report: PreflightReport,
+}
+
+impl CloudPermissionCheck {
+    /// Summarise a permission checklist: failed entries are the missing
+    /// permissions, and only failed blockers make the result a hard block.
+    pub(crate) fn from_report(report: PreflightReport, message: impl Into<String>) -> Self {
+        Self {
+            has_all_permissions: report.failed().is_empty(),
+            checked_permissions: report.checked(),
+            missing_permissions: report.failed(),
+            message: message.into(),
+            is_warning: report.status() != "fail",
+            report,
+        }
+    }
+
+    /// A permission check that couldn't run; deployment may proceed.
+    pub(crate) fn skipped(entry: PreflightEntry) -> Self {
+        let message = entry.message.clone();
+        Self::from_report(PreflightReport::single(entry), message)
+    }
 }
 
 /// Unity Catalog metastore info.
@@ -196,12 +383,15 @@ pub struct UCPermissionCheck {
     pub has_create_storage_credential: bool,
     pub can_create_catalog: bool,
     pub message: String,
+    /// Metastore and privilege checklist.
+    #[serde(default)]
+    pub report: PreflightReport,
 }
 
 // ─── Constants ──────────────────────────────────────────────────────────────
 
 /// Increment when embedded templates change to trigger a refresh.
-pub(crate) const TEMPLATES_VERSION: &str = "2.77.0";
+pub(crate) const TEMPLATES_VERSION: &str = "2.81.0";
 
 /// Variables that are automatically set by the app and hidden from the UI form.
 pub(crate) const INTERNAL_VARIABLES: &[&str] = &[
@@ -229,40 +419,177 @@ pub(crate) fn silent_cmd<S: AsRef<std::ffi::OsStr>>(program: S) -> std::process:
     cmd
 }
 
+/// Run blocking CLI or filesystem work on tokio's blocking pool so async commands
+/// don't stall the Tauri runtime (and the UI) while a subprocess runs.
+pub(crate) async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+}
+
 /// Recursively copy a directory tree. Used for templates and deployments.
-pub(crate) fn copy_dir_all(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
-    fs::create_dir_all(dst).map_err(|e| e.to_string())?;
+///
+/// Read-only destination files are overwritten, Unix permission bits (including
+/// executable) are kept, and symlinks are recreated as links on Unix. On Windows
+/// paths use the `\\?\` extended-length form so deep trees don't hit MAX_PATH,
+/// and symlinks are copied by target since creating links needs extra privileges.
+/// A directory link pointing back into the tree being copied is an error.
+pub(crate) fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), String> {
+    copy_tree(&long_path(src), &long_path(dst), &mut Vec::new())
+}
 
-    for entry in fs::read_dir(src).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let ty = entry.file_type().map_err(|e| e.to_string())?;
+/// `ancestors` holds the canonical paths of the directories currently being
+/// copied, so a followed link that leads back into one of them is caught.
+fn copy_tree(src: &Path, dst: &Path, ancestors: &mut Vec<PathBuf>) -> Result<(), String> {
+    let canonical =
+        fs::canonicalize(src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    if ancestors.contains(&canonical) {
+        return Err(format!(
+            "Link loop: {} points back into a directory being copied",
+            src.display()
+        ));
+    }
+    ancestors.push(canonical);
+    let result = copy_entries(src, dst, ancestors);
+    ancestors.pop();
+    result
+}
+
+fn copy_entries(src: &Path, dst: &Path, ancestors: &mut Vec<PathBuf>) -> Result<(), String> {
+    fs::create_dir_all(dst).map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+
+    let entries =
+        fs::read_dir(src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
-
-        if ty.is_dir() {
-            copy_dir_all(&src_path, &dst_path)?;
+        let ty = entry
+            .file_type()
+            .map_err(|e| format!("Failed to inspect {}: {}", src_path.display(), e))?;
+
+        if ty.is_symlink() {
+            copy_symlink(&src_path, &dst_path, ancestors)?;
+        } else if ty.is_dir() {
+            copy_tree(&src_path, &dst_path, ancestors)?;
         } else {
-            fs::copy(&src_path, &dst_path).map_err(|e| e.to_string())?;
+            copy_file(&src_path, &dst_path)?;
         }
     }
 
     Ok(())
 }
 
+fn copy_file(src: &Path, dst: &Path) -> Result<(), String> {
+    if let Ok(meta) = fs::symlink_metadata(dst) {
+        if meta.file_type().is_symlink() {
+            // Replace the link rather than writing through it
+            fs::remove_file(dst)
+                .map_err(|e| format!("Failed to replace {}: {}", dst.display(), e))?;
+        } else if meta.permissions().readonly() {
+            // Windows refuses to overwrite read-only files
+            let mut perms = meta.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            perms.set_readonly(false);
+            fs::set_permissions(dst, perms)
+                .map_err(|e| format!("Failed to make {} writable: {}", dst.display(), e))?;
+        }
+    }
+
+    fs::copy(src, dst).map_err(|e| {
+        format!("Failed to copy {} to {}: {}", src.display(), dst.display(), e)
+    })?;
+
+    #[cfg(unix)]
+    {
+        let perms = fs::metadata(src)
+            .map_err(|e| format!("Failed to inspect {}: {}", src.display(), e))?
+            .permissions();
+        fs::set_permissions(dst, perms)
+            .map_err(|e| format!("Failed to set permissions on {}: {}", dst.display(), e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dst: &Path, _ancestors: &mut Vec<PathBuf>) -> Result<(), String> {
+    let target =
+        fs::read_link(src).map_err(|e| format!("Failed to read link {}: {}", src.display(), e))?;
+    if let Ok(meta) = fs::symlink_metadata(dst) {
+        let removed = if meta.is_dir() {
+            fs::remove_dir_all(dst)
+        } else {
+            fs::remove_file(dst)
+        };
+        removed.map_err(|e| format!("Failed to replace {}: {}", dst.display(), e))?;
+    }
+    std::os::unix::fs::symlink(&target, dst)
+        .map_err(|e| format!("Failed to create link {}: {}", dst.display(), e))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, dst: &Path, ancestors: &mut Vec<PathBuf>) -> Result<(), String> {
+    let meta = fs::metadata(src)
+        .map_err(|e| format!("Failed to follow link {}: {}", src.display(), e))?;
+    if meta.is_dir() {
+        copy_tree(src, dst, ancestors)
+    } else {
+        copy_file(src, dst)
+    }
+}
+
+/// Extended-length (`\\?\`) form of an absolute Windows path, which lifts the
+/// 260-character MAX_PATH limit. Relative paths can't take the prefix.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn extended_length_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") {
+        path.to_string()
+    } else if let Some(unc) = path.strip_prefix(r"\\") {
+        format!(r"\\?\UNC\{}", unc.replace('/', "\\"))
+    } else if path.len() >= 3
+        && path.as_bytes()[0].is_ascii_alphabetic()
+        && path.as_bytes()[1] == b':'
+        && matches!(path.as_bytes()[2], b'\\' | b'/')
+    {
+        format!(r"\\?\{}", path.replace('/', "\\"))
+    } else {
+        path.to_string()
+    }
+}
+
+fn long_path(path: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Component;
+        // `.` and `..` aren't resolved once the prefix is applied
+        let normalized = path
+            .components()
+            .all(|c| !matches!(c, Component::CurDir | Component::ParentDir));
+        if normalized {
+            return PathBuf::from(extended_length_path(&path.to_string_lossy()));
+        }
+    }
+    path.to_path_buf()
+}
+
 /// Resolve the app-data templates directory.
-pub(crate) fn get_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    Ok(app_data_dir.join("templates"))
+pub(crate) fn get_templates_dir(env: &dyn Environment) -> Result<PathBuf, String> {
+    env.templates_dir()
 }
 
 /// Resolve (and create) the app-data deployments directory.
-pub(crate) fn get_deployments_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    let deployments_dir = app_data_dir.join("deployments");
-    fs::create_dir_all(&deployments_dir).map_err(|e| e.to_string())?;
-    Ok(deployments_dir)
+pub(crate) fn get_deployments_dir(env: &dyn Environment) -> Result<PathBuf, String> {
+    env.deployments_dir()
 }
 
+/// Longest deployment name [`sanitize_deployment_name`] accepts, in bytes.
+pub(crate) const MAX_DEPLOYMENT_NAME_LEN: usize = 200;
+
 /// Sanitize deployment name to prevent path traversal attacks.
 /// Only allows alphanumeric characters, hyphens, and underscores.
 pub(crate) fn sanitize_deployment_name(name: &str) -> Result<String, String> {
@@ -270,10 +597,7 @@ pub(crate) fn sanitize_deployment_name(name: &str) -> Result<String, String> {
         return Err("Deployment name cannot be empty".to_string());
     }
 
-    let sanitized: String = name
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-        .collect();
+    let sanitized = strip_invalid_name_chars(name);
 
     if sanitized.is_empty() {
         return Err("Deployment name contains no valid characters".to_string());
@@ -281,13 +605,20 @@ pub(crate) fn sanitize_deployment_name(name: &str) -> Result<String, String> {
     if sanitized.starts_with('-') {
         return Err("Deployment name cannot start with a hyphen".to_string());
     }
-    if sanitized.len() > 200 {
+    if sanitized.len() > MAX_DEPLOYMENT_NAME_LEN {
         return Err("Deployment name is too long (max 200 characters)".to_string());
     }
 
     Ok(sanitized)
 }
 
+/// `name` without the characters [`sanitize_deployment_name`] strips.
+pub(crate) fn strip_invalid_name_chars(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
 /// Mask sensitive identifiers for debug logging (show first 4 and last 4 chars).
 #[cfg_attr(not(debug_assertions), allow(dead_code))]
 pub(crate) fn mask_sensitive_id(id: &str) -> String {
@@ -299,11 +630,9 @@ pub(crate) fn mask_sensitive_id(id: &str) -> String {
 
 /// Databricks account-level API hostname for the given cloud provider.
 pub(crate) fn databricks_accounts_host(cloud: &str) -> &'static str {
-    match cloud {
-        "azure" => "accounts.azuredatabricks.net",
-        "gcp" => "accounts.gcp.databricks.com",
-        _ => "accounts.cloud.databricks.com",
-    }
+    cloud_provider::provider(cloud)
+        .unwrap_or(&cloud_provider::Aws)
+        .databricks_host()
 }
 
 /// Check if a string is a valid UUID v4 format (xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx).
@@ -317,8 +646,9 @@ pub(crate) fn is_valid_uuid(s: &str) -> bool {
 
 /// Create a standard HTTP client with a 30-second timeout.
 ///
-/// Automatically configures the client with system proxy settings
-/// detected via [`crate::proxy`] when no proxy env vars are present.
+/// Automatically configures the client with the proxy from the app settings,
+/// or system proxy settings detected via [`crate::proxy`] when no proxy env
+/// vars are present.
 /// Uses `native-tls` to trust the OS certificate store (important for
 /// corporate TLS inspection).
 pub(crate) fn http_client() -> Result<reqwest::Client, String> {
@@ -326,9 +656,14 @@ pub(crate) fn http_client() -> Result<reqwest::Client, String> {
         .timeout(std::time::Duration::from_secs(30));
 
     if let Some(proxy_url) = crate::proxy::get_https_proxy() {
-        if let Ok(proxy) = reqwest::Proxy::all(&proxy_url) {
+        if let Ok(mut proxy) = reqwest::Proxy::all(&proxy_url) {
+            if let Some(no_proxy) = crate::proxy::manual_no_proxy() {
+                proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy));
+            }
             builder = builder.proxy(proxy);
         }
+    } else if crate::proxy::proxy_disabled() {
+        builder = builder.no_proxy();
     }
 
     builder
@@ -338,7 +673,17 @@ pub(crate) fn http_client() -> Result<reqwest::Client, String> {
 
 /// Check if an `Option<String>` contains a non-empty value.
 pub(crate) fn opt_non_empty(opt: &Option<String>) -> bool {
-    opt.as_ref().map(|s| !s.is_empty()).unwrap_or(false)
+    non_empty_str(opt).is_some()
+}
+
+/// The value of an `Option<String>`, unless it is empty.
+pub(crate) fn non_empty_str(opt: &Option<String>) -> Option<&str> {
+    opt.as_deref().filter(|s| !s.is_empty())
+}
+
+/// Azure deployment whose Databricks provider authenticates with the Azure CLI login.
+pub(crate) fn uses_azure_identity(credentials: &CloudCredentials) -> bool {
+    credentials.cloud.as_deref() == Some("azure") && credentials.azure_databricks_use_identity == Some(true)
 }
 
 /// Sanitize template ID to prevent path traversal attacks.
@@ -543,6 +888,21 @@ mod tests {
         assert!(opt_non_empty(&Some("value".to_string())));
     }
 
+    // ── run_blocking ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn run_blocking_returns_closure_result() {
+        assert_eq!(run_blocking(|| Ok(42)).await, Ok(42));
+        assert_eq!(
+            run_blocking(|| Err::<(), _>("boom".to_string())).await,
+            Err("boom".to_string())
+        );
+        assert!(run_blocking(|| -> Result<(), String> { panic!("worker died") })
+            .await
+            .unwrap_err()
+            .starts_with("Background task failed"));
+    }
+
     // ── copy_dir_all (filesystem integration) ───────────────────────────
 
     #[test]
@@ -613,5 +973,70 @@ mod tests {
             &dst.path().join("output"),
         );
         assert!(result.is_err());
+        assert!(result.unwrap_err().contains("/nonexistent/path"));
+    }
+
+    #[test]
+    fn copy_tree_stops_at_a_directory_already_being_copied() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("main.tf"), "").unwrap();
+
+        let mut ancestors = vec![fs::canonicalize(src.path()).unwrap()];
+        let result = copy_tree(src.path(), &dst.path().join("output"), &mut ancestors);
+
+        assert!(result.unwrap_err().contains("Link loop"));
+        assert!(!dst.path().join("output").exists());
+    }
+
+    #[test]
+    fn copy_dir_all_overwrites_read_only_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let dst_target = dst.path().join("output");
+
+        fs::write(src.path().join("main.tf"), "new").unwrap();
+        fs::create_dir_all(&dst_target).unwrap();
+        let existing = dst_target.join("main.tf");
+        fs::write(&existing, "old").unwrap();
+        let mut perms = fs::metadata(&existing).unwrap().permissions();
+        perms.set_readonly(true);
+        fs::set_permissions(&existing, perms).unwrap();
+
+        copy_dir_all(&src.path().to_path_buf(), &dst_target).unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_all_keeps_executable_bits_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let dst_target = dst.path().join("output");
+
+        let script = src.path().join("setup.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::create_dir_all(src.path().join("modules")).unwrap();
+        std::os::unix::fs::symlink("modules", src.path().join("mods")).unwrap();
+        std::os::unix::fs::symlink("setup.sh", src.path().join("run.sh")).unwrap();
+
+        copy_dir_all(&src.path().to_path_buf(), &dst_target).unwrap();
+
+        let mode = fs::metadata(dst_target.join("setup.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(fs::read_link(dst_target.join("mods")).unwrap(), PathBuf::from("modules"));
+        assert_eq!(fs::read_link(dst_target.join("run.sh")).unwrap(), PathBuf::from("setup.sh"));
+    }
+
+    #[test]
+    fn extended_length_path_forms() {
+        assert_eq!(extended_length_path(r"C:\deploy\tpl"), r"\\?\C:\deploy\tpl");
+        assert_eq!(extended_length_path("C:/deploy/tpl"), r"\\?\C:\deploy\tpl");
+        assert_eq!(extended_length_path(r"\\server\share\x"), r"\\?\UNC\server\share\x");
+        assert_eq!(extended_length_path(r"\\?\C:\already"), r"\\?\C:\already");
+        assert_eq!(extended_length_path(r"relative\dir"), r"relative\dir");
     }
 }
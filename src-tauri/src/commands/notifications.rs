@@ -0,0 +1,167 @@
+//! Native notifications when a deployment run finishes.
+//!
+//! Applies and destroys can take half an hour, so the user has usually moved
+//! on by the time one ends. A desktop notification with the deployment name,
+//! outcome and duration is posted through the notification plugin; if the
+//! platform refuses it, the failure is only logged.
+
+use super::debug_log;
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Title of the test notification.
+const APP_NAME: &str = "Databricks Deployer";
+
+/// Notification preferences, stored in the app settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub on_success: bool,
+    pub on_failure: bool,
+    pub on_cancel: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { enabled: true, on_success: true, on_failure: true, on_cancel: true }
+    }
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RunOutcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn load_settings(env: &dyn Environment) -> NotificationSettings {
+    super::settings::load(env).notifications
+}
+
+fn wants(settings: &NotificationSettings, outcome: RunOutcome) -> bool {
+    settings.enabled
+        && match outcome {
+            RunOutcome::Succeeded => settings.on_success,
+            RunOutcome::Failed => settings.on_failure,
+            RunOutcome::Cancelled => settings.on_cancel,
+        }
+}
+
+/// "1h 5m", "12m 3s", "40s".
+fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {}m", h, m)
+    } else if m > 0 {
+        format!("{}m {}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Notification title and body for a finished run.
+fn message(deployment_name: &str, command: &str, outcome: RunOutcome, duration_secs: u64) -> (String, String) {
+    let verb = match command {
+        "apply" => "Deployment",
+        "destroy" => "Destroy",
+        other => other,
+    };
+    let result = match outcome {
+        RunOutcome::Succeeded => "succeeded",
+        RunOutcome::Failed => "failed",
+        RunOutcome::Cancelled => "was cancelled",
+    };
+    (
+        format!("{} {}", verb, result),
+        format!("{} {} after {}", deployment_name, result, format_duration(duration_secs)),
+    )
+}
+
+/// Post a desktop notification through the notification plugin.
+fn show(app: &AppHandle, title: &str, body: &str) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+/// Notify that `command` on `deployment_name` finished, if the settings ask
+/// for it. Runs in the background; failures are only logged.
+pub(crate) fn notify_run_finished(
+    app: &AppHandle,
+    deployment_name: &str,
+    command: &str,
+    outcome: RunOutcome,
+    duration_secs: u64,
+) {
+    // Short runs (init, plan) finish while the user is still watching
+    if !matches!(command, "apply" | "destroy") || !wants(&load_settings(app), outcome) {
+        return;
+    }
+    let (title, body) = message(deployment_name, command, outcome, duration_secs);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(_e) = show(&app, &title, &body) {
+            debug_log!("[notifications] {}", _e);
+        }
+    });
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Get the deployment notification preferences.
+#[tauri::command]
+pub fn get_notification_settings(app: AppHandle) -> NotificationSettings {
+    load_settings(&app)
+}
+
+/// Save the deployment notification preferences.
+#[tauri::command]
+pub fn set_notification_settings(app: AppHandle, settings: NotificationSettings) -> Result<(), String> {
+    super::settings::update(&app, |current| Ok(super::settings::AppSettings { notifications: settings, ..current }))
+        .map(|_| ())
+}
+
+/// Post a sample notification so the user can check that the OS shows them.
+#[tauri::command]
+pub async fn send_test_notification(app: AppHandle) -> Result<(), String> {
+    super::run_blocking(move || show(&app, APP_NAME, "Notifications are working")).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_includes_name_and_duration() {
+        let (title, body) = message("prod-ws", "apply", RunOutcome::Succeeded, 1325);
+        assert_eq!(title, "Deployment succeeded");
+        assert_eq!(body, "prod-ws succeeded after 22m 5s");
+        let (title, _) = message("prod-ws", "destroy", RunOutcome::Cancelled, 40);
+        assert_eq!(title, "Destroy was cancelled");
+        assert_eq!(format_duration(3720), "1h 2m");
+        assert_eq!(format_duration(9), "9s");
+    }
+
+    #[test]
+    fn settings_filter_outcomes() {
+        let mut settings = NotificationSettings::default();
+        assert!(wants(&settings, RunOutcome::Failed));
+        settings.on_success = false;
+        assert!(!wants(&settings, RunOutcome::Succeeded));
+        settings.enabled = false;
+        assert!(!wants(&settings, RunOutcome::Failed));
+
+        // Older or partial files fall back to defaults per field
+        let partial: NotificationSettings = serde_json::from_str(r#"{"on_cancel": false}"#).unwrap();
+        assert!(partial.enabled && !partial.on_cancel);
+    }
+}
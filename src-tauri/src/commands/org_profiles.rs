@@ -0,0 +1,337 @@
+//! Organization profiles: shared presets for template variables.
+//!
+//! A profile is a named JSON document with an organization's standard inputs:
+//! the region per cloud, a naming prefix, tags, and any other variable by name
+//! (CIDR ranges, security settings). Profiles are kept in `org-profiles.json`
+//! in the data directory, either saved from the UI or imported from an HTTPS
+//! URL so a platform team can publish one for everybody. Applying a profile to
+//! [`get_template_variables`](super::get_template_variables) replaces the
+//! template's defaults for the variables the template declares.
+
+use super::{debug_log, lock_or_recover};
+use crate::storage::Environment;
+use crate::terraform::TerraformVariable;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const PROFILES_FILE: &str = "org-profiles.json";
+
+const MAX_NAME_LEN: usize = 64;
+
+/// Variables that hold the deployment region in the bundled templates.
+const REGION_VARIABLES: &[&str] = &["region", "location", "google_region"];
+
+/// Variables that hold the resource naming prefix.
+const PREFIX_VARIABLES: &[&str] = &["prefix", "resource_prefix"];
+
+const TAGS_VARIABLE: &str = "tags";
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write cycles of the profiles file.
+    static ref PROFILES_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Standard template inputs for an organization.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrgProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Default region by cloud ("aws", "azure", "gcp").
+    #[serde(default)]
+    pub regions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub naming_prefix: Option<String>,
+    /// Merged into the template's `tags` variable.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// Values by variable name, e.g. CIDR ranges and security settings.
+    /// These win over the fields above.
+    #[serde(default)]
+    pub variables: BTreeMap<String, serde_json::Value>,
+    /// Where the profile was imported from, if it was.
+    #[serde(default)]
+    pub source_url: Option<String>,
+}
+
+// ─── Storage ────────────────────────────────────────────────────────────────
+
+fn load(path: &Path) -> Vec<OrgProfile> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, profiles: &[OrgProfile]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write organization profiles: {}", e))
+}
+
+/// Load the profiles, let `edit` change them, then sort by name and save.
+fn update(
+    env: &dyn Environment,
+    edit: impl FnOnce(&mut Vec<OrgProfile>) -> Result<(), String>,
+) -> Result<Vec<OrgProfile>, String> {
+    let path = env.data_file(PROFILES_FILE)?;
+    let _guard = lock_or_recover(&PROFILES_LOCK);
+    let mut profiles = load(&path);
+    edit(&mut profiles)?;
+    profiles.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    save(&path, &profiles)?;
+    Ok(profiles)
+}
+
+/// Save `profile`, replacing the one with the same name.
+pub(super) fn upsert(env: &dyn Environment, profile: OrgProfile) -> Result<Vec<OrgProfile>, String> {
+    update(env, |profiles| {
+        profiles.retain(|p| p.name != profile.name);
+        profiles.push(profile);
+        Ok(())
+    })
+}
+
+/// Saved profiles, sorted by name.
+pub(super) fn saved_profiles(env: &dyn Environment) -> Result<Vec<OrgProfile>, String> {
+    Ok(load(&env.data_file(PROFILES_FILE)?))
+}
+
+fn find(env: &dyn Environment, name: &str) -> Result<OrgProfile, String> {
+    saved_profiles(env)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Organization profile '{}' not found", name))
+}
+
+/// Trim the name and reject unknown clouds and malformed variable names.
+pub(super) fn validate(profile: &mut OrgProfile) -> Result<(), String> {
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    if profile.name.len() > MAX_NAME_LEN {
+        return Err(format!("Profile name must be at most {} characters", MAX_NAME_LEN));
+    }
+    if let Some(cloud) = profile.regions.keys().find(|c| !matches!(c.as_str(), "aws" | "azure" | "gcp")) {
+        return Err(format!("Unknown cloud in regions: {}", cloud));
+    }
+    let invalid_name = |name: &String| name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if let Some(name) = profile.variables.keys().find(|n| invalid_name(n)) {
+        return Err(format!("Invalid variable name: {}", name));
+    }
+    Ok(())
+}
+
+// ─── Applying ───────────────────────────────────────────────────────────────
+
+/// Variable default text: strings as-is, anything else as JSON, which
+/// `generate_tfvars` accepts for lists, maps and objects.
+fn default_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The template's tags default (when it is JSON) with the profile's tags on top.
+fn merged_tags(template_default: Option<&str>, tags: &BTreeMap<String, String>) -> String {
+    let mut merged: serde_json::Map<String, serde_json::Value> = template_default
+        .and_then(|d| serde_json::from_str(d).ok())
+        .unwrap_or_default();
+    for (key, value) in tags {
+        merged.insert(key.clone(), serde_json::Value::String(value.clone()));
+    }
+    serde_json::Value::Object(merged).to_string()
+}
+
+/// Values `profile` sets for a template on `cloud`, by variable name.
+fn profile_values(profile: &OrgProfile, cloud: &str, variables: &[TerraformVariable]) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    if let Some(region) = profile.regions.get(cloud).filter(|r| !r.is_empty()) {
+        for name in REGION_VARIABLES {
+            values.insert(name.to_string(), region.clone());
+        }
+    }
+    if let Some(prefix) = profile.naming_prefix.as_deref().filter(|p| !p.is_empty()) {
+        for name in PREFIX_VARIABLES {
+            values.insert(name.to_string(), prefix.to_string());
+        }
+    }
+    if !profile.tags.is_empty() {
+        let template_default = variables.iter().find(|v| v.name == TAGS_VARIABLE).and_then(|v| v.default.as_deref());
+        values.insert(TAGS_VARIABLE.to_string(), merged_tags(template_default, &profile.tags));
+    }
+    for (name, value) in &profile.variables {
+        values.insert(name.clone(), default_text(value));
+    }
+    values
+}
+
+/// Replace the defaults of the variables `profile` covers. Variables the
+/// template doesn't declare are ignored. Returns how many were set.
+fn apply_profile(profile: &OrgProfile, cloud: &str, variables: &mut [TerraformVariable]) -> usize {
+    let values = profile_values(profile, cloud, variables);
+    let mut applied = 0;
+    for variable in variables.iter_mut() {
+        if let Some(value) = values.get(&variable.name) {
+            variable.default = Some(value.clone());
+            applied += 1;
+        }
+    }
+    debug_log!("[org_profiles] Applied '{}' to {} variable(s)", profile.name, applied);
+    applied
+}
+
+/// Apply the saved profile `name` to a template's variables.
+pub(crate) fn apply_saved_profile(
+    env: &dyn Environment,
+    name: &str,
+    template_id: &str,
+    variables: &mut [TerraformVariable],
+) -> Result<(), String> {
+    let profile = find(env, name)?;
+    let cloud = template_id.split('-').next().unwrap_or_default();
+    apply_profile(&profile, cloud, variables);
+    Ok(())
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Saved organization profiles, sorted by name.
+#[tauri::command]
+pub fn list_org_profiles(app: AppHandle) -> Result<Vec<OrgProfile>, String> {
+    saved_profiles(&app)
+}
+
+/// Create or replace the profile with the same name. Returns the updated list.
+#[tauri::command]
+pub fn save_org_profile(app: AppHandle, profile: OrgProfile) -> Result<Vec<OrgProfile>, String> {
+    let mut profile = profile;
+    validate(&mut profile)?;
+    upsert(&app, profile)
+}
+
+/// Delete a profile. Returns the updated list.
+#[tauri::command]
+pub fn delete_org_profile(app: AppHandle, name: String) -> Result<Vec<OrgProfile>, String> {
+    update(&app, |profiles| {
+        let before = profiles.len();
+        profiles.retain(|p| p.name != name);
+        if profiles.len() == before {
+            return Err(format!("Organization profile '{}' not found", name));
+        }
+        Ok(())
+    })
+}
+
+/// Download a profile published at an HTTPS URL and save it, replacing a
+/// profile with the same name. Importing again refreshes it.
+#[tauri::command]
+pub async fn import_org_profile(app: AppHandle, url: String) -> Result<OrgProfile, String> {
+    let url = url.trim().to_string();
+    if !url.starts_with("https://") {
+        return Err("Organization profiles can only be imported over HTTPS".to_string());
+    }
+    let response = super::http_client()?
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download profile: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download profile: HTTP {}", response.status()));
+    }
+    let mut profile: OrgProfile = response
+        .json()
+        .await
+        .map_err(|e| format!("Not a valid organization profile: {}", e))?;
+    profile.source_url = Some(url);
+    validate(&mut profile)?;
+
+    upsert(&app, profile.clone())?;
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+
+    fn variable(name: &str, default: Option<&str>) -> TerraformVariable {
+        TerraformVariable {
+            name: name.to_string(),
+            description: String::new(),
+            var_type: "string".to_string(),
+            default: default.map(str::to_string),
+            required: default.is_none(),
+            sensitive: false,
+            validation: None,
+            meta: Default::default(),
+        }
+    }
+
+    fn profile() -> OrgProfile {
+        OrgProfile {
+            name: "acme".to_string(),
+            regions: BTreeMap::from([("aws".to_string(), "eu-west-1".to_string())]),
+            naming_prefix: Some("acme".to_string()),
+            tags: BTreeMap::from([("CostCenter".to_string(), "1234".to_string())]),
+            variables: BTreeMap::from([
+                ("cidr_block".to_string(), serde_json::json!("10.20.0.0/16")),
+                ("public_access_enabled".to_string(), serde_json::json!(false)),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn applies_only_to_declared_variables_for_the_cloud() {
+        let mut vars = vec![
+            variable("region", Some("us-east-1")),
+            variable("prefix", None),
+            variable("cidr_block", Some("10.4.0.0/16")),
+            variable("tags", Some(r#"{"Owner":"platform"}"#)),
+            variable("public_access_enabled", Some("true")),
+            variable("admin_user", None),
+        ];
+        assert_eq!(apply_profile(&profile(), "aws", &mut vars), 5);
+        let default = |name: &str| vars.iter().find(|v| v.name == name).unwrap().default.clone();
+        assert_eq!(default("region").as_deref(), Some("eu-west-1"));
+        assert_eq!(default("prefix").as_deref(), Some("acme"));
+        assert_eq!(default("cidr_block").as_deref(), Some("10.20.0.0/16"));
+        assert_eq!(default("tags").as_deref(), Some(r#"{"CostCenter":"1234","Owner":"platform"}"#));
+        assert_eq!(default("public_access_enabled").as_deref(), Some("false"));
+        assert_eq!(default("admin_user"), None);
+
+        // No Azure region in the profile, so the template's location stays
+        let mut azure = vec![variable("location", Some("eastus2"))];
+        assert_eq!(apply_profile(&profile(), "azure", &mut azure), 0);
+        assert_eq!(azure[0].default.as_deref(), Some("eastus2"));
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let mut unnamed = OrgProfile { name: "  ".to_string(), ..Default::default() };
+        assert!(validate(&mut unnamed).is_err());
+        let mut bad_cloud = OrgProfile { regions: BTreeMap::from([("oci".to_string(), "x".to_string())]), ..profile() };
+        assert!(validate(&mut bad_cloud).unwrap_err().contains("oci"));
+        let mut bad_var = OrgProfile { variables: BTreeMap::from([("a b".to_string(), serde_json::json!(1))]), ..profile() };
+        assert!(validate(&mut bad_var).is_err());
+    }
+
+    #[test]
+    fn saved_profiles_replace_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = StoragePaths::from_data_dir(dir.path());
+        upsert(&env, profile()).unwrap();
+        upsert(&env, OrgProfile { name: "Beta".to_string(), ..Default::default() }).unwrap();
+        let saved = upsert(&env, OrgProfile { naming_prefix: Some("acme2".to_string()), ..profile() }).unwrap();
+
+        assert_eq!(saved.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["acme", "Beta"]);
+        assert_eq!(find(&env, "acme").unwrap().naming_prefix.as_deref(), Some("acme2"));
+        assert!(find(&env, "missing").is_err());
+    }
+}
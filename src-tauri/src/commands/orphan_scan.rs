@@ -0,0 +1,690 @@
+//! Cloud resources left behind by failed deployments.
+//!
+//! An apply that fails partway, or state that was lost or reset, can leave
+//! VPCs, buckets, IAM roles, and resource groups that Terraform no longer
+//! tracks but that still cost money. [`scan_orphaned_resources`] lists the
+//! resources tagged or named with the deployment's prefix, drops the ones the
+//! state knows about, and deletes the ones the user selects:
+//!
+//! - AWS: resources carrying the templates' default tag (`Project` or
+//!   `Resource` = `<prefix>`), plus IAM roles and S3 buckets named `<prefix>-...`
+//! - Azure: resources and resource groups named `<prefix>-...`
+//! - GCP: networks, subnetworks, buckets, and service accounts named `<prefix>-...`
+//!
+//! Names of other deployments whose prefix extends this one (`dev-eu` next
+//! to `dev`) are skipped. The scan fails when the state can't be read, local
+//! or pulled from a remote backend: an unknown state would make every named
+//! resource look orphaned.
+//!
+//! A name match alone doesn't prove a resource belongs to the deployment, so
+//! only resources carrying the prefix tag, or tracked in one of the
+//! deployment's state snapshots, can be deleted from the app. The others, and
+//! types the app can't delete, are listed so they can be checked and removed
+//! in the cloud console. Deletion does not force: a non-empty bucket or a role
+//! with attached policies reports the CLI error.
+
+use super::cli_runner::{CliCommand, CliTool};
+use super::{account_cleanup, deployment_lock, get_deployments_dir, sanitize_deployment_name, state_encryption, CloudCredentials};
+use crate::terraform;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// tfvars variables the templates name cloud resources after.
+const PREFIX_VARIABLES: &[&str] = &["prefix", "resource_prefix", "workspace_name"];
+
+/// Default tags the AWS templates set to the prefix.
+const AWS_PREFIX_TAGS: &[&str] = &["Project", "Resource"];
+
+/// State attributes that identify a resource in the cloud.
+const ID_ATTRIBUTES: &[&str] = &["id", "arn", "self_link", "name", "email", "bucket"];
+
+/// A resource named or tagged for the deployment that the state doesn't track.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrphanedResource {
+    /// ARN, Azure resource ID, self link, bucket name, or service account email.
+    pub id: String,
+    pub name: String,
+    /// `ec2:vpc`, `Microsoft.Network/virtualNetworks`, `compute:network`, ...
+    pub resource_type: String,
+    /// Carries the prefix tag or was tracked in one of the deployment's state
+    /// snapshots, rather than only being named like its resources.
+    pub owned: bool,
+    /// Whether the app can delete it; others must be removed in the console.
+    pub deletable: bool,
+    #[serde(skip)]
+    delete: Option<(CliTool, Vec<String>)>,
+}
+
+impl OrphanedResource {
+    fn new(id: String, name: String, resource_type: String, delete: Option<(CliTool, Vec<String>)>) -> Self {
+        OrphanedResource { id, name, resource_type, owned: false, deletable: false, delete }
+    }
+
+    fn set_owned(&mut self, owned: bool) {
+        self.owned = owned;
+        self.deletable = owned && self.delete.is_some();
+    }
+}
+
+/// Result of a scan, and of deleting the selected resources when asked to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OrphanScan {
+    pub prefix: String,
+    /// Orphaned resources found (after deletion, what is left).
+    pub resources: Vec<OrphanedResource>,
+    pub removed: Vec<String>,
+    /// `(id, error)` for resources that could not be deleted.
+    pub failed: Vec<(String, String)>,
+    /// Resource lists that could not be read, so the scan may be incomplete.
+    pub warnings: Vec<String>,
+}
+
+/// Where to look, read from the deployment's configuration.
+#[derive(Debug, Clone, Default)]
+struct ScanTarget {
+    cloud: String,
+    prefix: String,
+    /// Prefixes of other deployments that extend `prefix`.
+    other_prefixes: Vec<String>,
+    /// AWS region, Azure subscription, or GCP project.
+    scope: Option<String>,
+}
+
+impl ScanTarget {
+    fn owns_name(&self, name: &str) -> bool {
+        account_cleanup::owns_name(name, &self.prefix, &self.other_prefixes)
+    }
+}
+
+// ─── State ──────────────────────────────────────────────────────────────────
+
+/// Lowercased identifiers of every managed resource instance in the state.
+fn state_identifiers(state: &str) -> Result<HashSet<String>, String> {
+    let state: serde_json::Value =
+        serde_json::from_str(state).map_err(|e| format!("The deployment's state is not valid JSON: {}", e))?;
+    if !state["resources"].is_array() {
+        return Err("The deployment's state has no resource list".to_string());
+    }
+    let mut ids = HashSet::new();
+    for resource in state["resources"].as_array().into_iter().flatten() {
+        if resource["mode"] != "managed" {
+            continue;
+        }
+        for instance in resource["instances"].as_array().into_iter().flatten() {
+            for attribute in ID_ATTRIBUTES {
+                if let Some(value) = instance["attributes"][attribute].as_str().filter(|v| !v.is_empty()) {
+                    ids.insert(value.to_lowercase());
+                }
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// The deployment's current state: the local file (decrypted with `key` when
+/// encrypted at rest), or else `terraform state pull` from its backend.
+fn current_state(deployment_dir: &Path, key: Option<&[u8; 32]>, credentials: &CloudCredentials) -> Result<String, String> {
+    if state_encryption::has_local_state(deployment_dir) {
+        return state_encryption::read_state(deployment_dir, key)
+            .ok_or_else(|| "The deployment's state could not be read or decrypted".to_string());
+    }
+    if !deployment_dir.join(".terraform").is_dir() {
+        return Err("The deployment has no state to compare against; run init or apply first".to_string());
+    }
+    terraform::run_state_pull(deployment_dir, &super::build_env_vars(credentials))
+        .map_err(|e| format!("Failed to pull the deployment's state: {}", e))
+}
+
+/// Identifiers tracked in the deployment's state snapshots: resources it
+/// created before its state was lost or reset. Unreadable snapshots are skipped.
+fn snapshot_identifiers(deployment_dir: &Path, key: Option<&[u8; 32]>) -> HashSet<String> {
+    let backup_dir = deployment_dir.join(super::state_backups::BACKUP_DIR);
+    let mut ids = HashSet::new();
+    for backup in super::state_backups::list_backups(deployment_dir) {
+        let Ok(content) = fs::read_to_string(backup_dir.join(&backup.id)) else {
+            continue;
+        };
+        let state = match key {
+            Some(key) if state_encryption::is_envelope(&content) => state_encryption::unseal(&content, key).ok(),
+            _ => Some(content),
+        };
+        ids.extend(state.and_then(|s| state_identifiers(&s).ok()).into_iter().flatten());
+    }
+    ids
+}
+
+/// Whether the state tracks the resource with cloud identifier `id`. AWS
+/// resources without an `arn` attribute (NAT gateways, ...) match on the ID
+/// at the end of their ARN.
+fn is_tracked(id: &str, tracked: &HashSet<String>) -> bool {
+    let id = id.to_lowercase();
+    tracked.contains(&id) || arn_parts(&id).is_some_and(|(_, _, _, resource_id)| tracked.contains(resource_id))
+}
+
+// ─── AWS ────────────────────────────────────────────────────────────────────
+
+/// `(service, region, resource type, resource id)` of an ARN such as
+/// `arn:aws:ec2:us-east-1:123:vpc/vpc-1` or `arn:aws:s3:::bucket`.
+fn arn_parts(arn: &str) -> Option<(&str, &str, &str, &str)> {
+    let parts: Vec<&str> = arn.splitn(6, ':').collect();
+    if parts.len() != 6 || parts[0] != "arn" {
+        return None;
+    }
+    let (kind, id) = match parts[5].split_once('/') {
+        Some((kind, id)) => (kind, id),
+        None if parts[2] == "s3" => ("bucket", parts[5]),
+        None => parts[5].split_once(':').unwrap_or(("", parts[5])),
+    };
+    Some((parts[2], parts[3], kind, id))
+}
+
+/// AWS CLI arguments deleting the resource, for the supported types.
+fn aws_delete_args(arn: &str) -> Option<Vec<String>> {
+    let (service, region, kind, id) = arn_parts(arn)?;
+    let (command, flag) = match (service, kind) {
+        ("ec2", "vpc") => ("delete-vpc", "--vpc-id"),
+        ("ec2", "subnet") => ("delete-subnet", "--subnet-id"),
+        ("ec2", "security-group") => ("delete-security-group", "--group-id"),
+        ("ec2", "route-table") => ("delete-route-table", "--route-table-id"),
+        ("ec2", "internet-gateway") => ("delete-internet-gateway", "--internet-gateway-id"),
+        ("ec2", "natgateway") => ("delete-nat-gateway", "--nat-gateway-id"),
+        ("ec2", "elastic-ip") => ("release-address", "--allocation-id"),
+        ("ec2", "vpc-endpoint") => ("delete-vpc-endpoints", "--vpc-endpoint-ids"),
+        ("s3", "bucket") => return Some(vec!["s3api".into(), "delete-bucket".into(), "--bucket".into(), id.into()]),
+        ("iam", "role") => {
+            let name = id.rsplit('/').next().unwrap_or(id);
+            return Some(vec!["iam".into(), "delete-role".into(), "--role-name".into(), name.into()]);
+        }
+        _ => return None,
+    };
+    Some(vec!["ec2".into(), command.into(), flag.into(), id.into(), "--region".into(), region.into()])
+}
+
+fn aws_resource(arn: String, name: Option<String>) -> OrphanedResource {
+    let (resource_type, id) = arn_parts(&arn)
+        .map(|(service, _, kind, id)| (format!("{}:{}", service, kind), id.to_string()))
+        .unwrap_or_default();
+    let delete = aws_delete_args(&arn).map(|args| (CliTool::Aws, args));
+    OrphanedResource::new(arn, name.unwrap_or(id), resource_type, delete)
+}
+
+/// Resources from `resourcegroupstaggingapi get-resources`, named by their
+/// `Name` tag. They carry the prefix tag, so they are owned.
+fn parse_aws_tagged(json: &serde_json::Value) -> Vec<OrphanedResource> {
+    json["ResourceTagMappingList"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|mapping| {
+            let arn = mapping["ResourceARN"].as_str()?.to_string();
+            let name = mapping["Tags"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|tag| tag["Key"] == "Name")
+                .and_then(|tag| tag["Value"].as_str())
+                .map(str::to_string);
+            let mut resource = aws_resource(arn, name);
+            resource.set_owned(true);
+            Some(resource)
+        })
+        .collect()
+}
+
+fn parse_aws_roles(json: &serde_json::Value, target: &ScanTarget) -> Vec<OrphanedResource> {
+    json["Roles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|role| role["RoleName"].as_str().is_some_and(|name| target.owns_name(name)))
+        .filter_map(|role| Some(aws_resource(role["Arn"].as_str()?.to_string(), None)))
+        .collect()
+}
+
+fn parse_aws_buckets(json: &serde_json::Value, target: &ScanTarget) -> Vec<OrphanedResource> {
+    json["Buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|bucket| bucket["Name"].as_str())
+        .filter(|name| target.owns_name(name))
+        .map(|name| aws_resource(format!("arn:aws:s3:::{}", name), None))
+        .collect()
+}
+
+// ─── Azure ──────────────────────────────────────────────────────────────────
+
+const AZURE_RESOURCE_GROUP: &str = "Microsoft.Resources/resourceGroups";
+
+/// `az resource list` / `az group list` entries named with the prefix.
+fn parse_azure_resources(json: &serde_json::Value, target: &ScanTarget) -> Vec<OrphanedResource> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|resource| {
+            let id = resource["id"].as_str()?.to_string();
+            let name = resource["name"].as_str().filter(|name| target.owns_name(name))?.to_string();
+            let resource_type = resource["type"].as_str().unwrap_or(AZURE_RESOURCE_GROUP).to_string();
+            let args = if resource_type.eq_ignore_ascii_case(AZURE_RESOURCE_GROUP) {
+                let subscription = id.split('/').nth(2).unwrap_or_default();
+                vec!["group", "delete", "--name", name.as_str(), "--subscription", subscription, "--yes", "--no-wait"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            } else {
+                vec!["resource".to_string(), "delete".to_string(), "--ids".to_string(), id.clone()]
+            };
+            Some(OrphanedResource::new(id, name, resource_type, Some((CliTool::Azure, args))))
+        })
+        .collect()
+}
+
+// ─── GCP ────────────────────────────────────────────────────────────────────
+
+/// `gcloud compute networks [subnets] list` entries named with the prefix.
+fn parse_gcp_networks(json: &serde_json::Value, target: &ScanTarget, project: &str) -> Vec<OrphanedResource> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|network| {
+            let name = network["name"].as_str().filter(|name| target.owns_name(name))?.to_string();
+            let self_link = network["selfLink"].as_str()?.to_string();
+            let mut args: Vec<String> = vec!["compute".into(), "networks".into()];
+            let resource_type = match network["region"].as_str() {
+                Some(region) => {
+                    let region = region.rsplit('/').next().unwrap_or(region);
+                    args.extend(["subnets".into(), "delete".into(), name.clone(), "--region".into(), region.into()]);
+                    "compute:subnetwork"
+                }
+                None => {
+                    args.extend(["delete".into(), name.clone()]);
+                    "compute:network"
+                }
+            };
+            args.extend(["--project".into(), project.into(), "--quiet".into()]);
+            Some(OrphanedResource::new(self_link, name, resource_type.to_string(), Some((CliTool::Gcloud, args))))
+        })
+        .collect()
+}
+
+fn parse_gcp_buckets(json: &serde_json::Value, target: &ScanTarget) -> Vec<OrphanedResource> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|bucket| bucket["name"].as_str())
+        .filter(|name| target.owns_name(name))
+        .map(|name| {
+            let args = vec!["storage".into(), "buckets".into(), "delete".into(), format!("gs://{}", name), "--quiet".into()];
+            OrphanedResource::new(name.to_string(), name.to_string(), "storage:bucket".to_string(), Some((CliTool::Gcloud, args)))
+        })
+        .collect()
+}
+
+fn parse_gcp_service_accounts(json: &serde_json::Value, target: &ScanTarget, project: &str) -> Vec<OrphanedResource> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|account| account["email"].as_str())
+        .filter(|email| email.split('@').next().is_some_and(|name| target.owns_name(name)))
+        .map(|email| {
+            let args = ["iam", "service-accounts", "delete", email, "--project", project, "--quiet"]
+                .into_iter()
+                .map(String::from)
+                .collect();
+            OrphanedResource::new(email.to_string(), email.to_string(), "iam:serviceAccount".to_string(), Some((CliTool::Gcloud, args)))
+        })
+        .collect()
+}
+
+// ─── Scan ───────────────────────────────────────────────────────────────────
+
+/// Run one list call, recording a warning instead of failing the whole scan.
+fn list(
+    tool: CliTool,
+    args: &[&str],
+    credentials: &CloudCredentials,
+    warnings: &mut Vec<String>,
+    parse: impl FnOnce(&serde_json::Value) -> Vec<OrphanedResource>,
+) -> Vec<OrphanedResource> {
+    let result = CliCommand::new(tool)
+        .map_err(String::from)
+        .and_then(|mut cmd| {
+            cmd.args(args).credentials(credentials)?;
+            Ok(cmd.run_checked()?)
+        })
+        .and_then(|output| output.json::<serde_json::Value>());
+    match result {
+        Ok(json) => parse(&json),
+        Err(e) => {
+            warnings.push(format!("Could not list `{}`: {}", args[..args.len().min(3)].join(" "), e));
+            Vec::new()
+        }
+    }
+}
+
+fn list_candidates(target: &ScanTarget, credentials: &CloudCredentials, warnings: &mut Vec<String>) -> Result<Vec<OrphanedResource>, String> {
+    let prefix = target.prefix.as_str();
+    let mut found = Vec::new();
+    match target.cloud.as_str() {
+        "aws" => {
+            let region = target.scope.as_deref().ok_or("The deployment has no AWS region configured")?;
+            for tag in AWS_PREFIX_TAGS {
+                let tag_filter = format!("Key={},Values={}", tag, prefix);
+                let tagged = ["resourcegroupstaggingapi", "get-resources", "--tag-filters", &tag_filter, "--region", region, "--output", "json"];
+                found.extend(list(CliTool::Aws, &tagged, credentials, warnings, parse_aws_tagged));
+            }
+            let roles = ["iam", "list-roles", "--output", "json"];
+            found.extend(list(CliTool::Aws, &roles, credentials, warnings, |json| parse_aws_roles(json, target)));
+            let buckets = ["s3api", "list-buckets", "--output", "json"];
+            found.extend(list(CliTool::Aws, &buckets, credentials, warnings, |json| parse_aws_buckets(json, target)));
+        }
+        "azure" => {
+            let mut subscription = Vec::new();
+            if let Some(id) = &target.scope {
+                subscription = vec!["--subscription", id.as_str()];
+            }
+            for command in ["resource", "group"] {
+                let args: Vec<&str> = [command, "list", "--output", "json"].into_iter().chain(subscription.iter().copied()).collect();
+                found.extend(list(CliTool::Azure, &args, credentials, warnings, |json| parse_azure_resources(json, target)));
+            }
+        }
+        "gcp" => {
+            let project = target.scope.as_deref().ok_or("The deployment has no GCP project configured")?;
+            let subnets = ["compute", "networks", "subnets", "list", "--project", project, "--format=json"];
+            found.extend(list(CliTool::Gcloud, &subnets, credentials, warnings, |json| parse_gcp_networks(json, target, project)));
+            let networks = ["compute", "networks", "list", "--project", project, "--format=json"];
+            found.extend(list(CliTool::Gcloud, &networks, credentials, warnings, |json| parse_gcp_networks(json, target, project)));
+            let buckets = ["storage", "buckets", "list", "--project", project, "--format=json"];
+            found.extend(list(CliTool::Gcloud, &buckets, credentials, warnings, |json| parse_gcp_buckets(json, target)));
+            let accounts = ["iam", "service-accounts", "list", "--project", project, "--format=json"];
+            found.extend(list(CliTool::Gcloud, &accounts, credentials, warnings, |json| parse_gcp_service_accounts(json, target, project)));
+        }
+        other => return Err(format!("Orphaned resource scans are not supported for '{}'", other)),
+    }
+    Ok(found)
+}
+
+/// Identifiers the scan compares candidates against.
+#[derive(Debug, Default)]
+struct KnownIds {
+    /// Tracked in the current state.
+    tracked: HashSet<String>,
+    /// Tracked in an earlier state snapshot.
+    previously_tracked: HashSet<String>,
+}
+
+/// Candidates the state doesn't track, without duplicates (a tagged bucket
+/// is also found by name, and the tagged listing comes first). Ones tracked in
+/// an earlier snapshot are owned.
+fn untracked(candidates: Vec<OrphanedResource>, known: &KnownIds) -> Vec<OrphanedResource> {
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|r| !is_tracked(&r.id, &known.tracked) && seen.insert(r.id.to_lowercase()))
+        .map(|mut r| {
+            let owned = r.owned || is_tracked(&r.id, &known.previously_tracked);
+            r.set_owned(owned);
+            r
+        })
+        .collect()
+}
+
+/// Deletion order: dependents before the networks and groups that hold them.
+fn delete_rank(resource_type: &str) -> u8 {
+    match resource_type {
+        "ec2:natgateway" | "ec2:vpc-endpoint" => 0,
+        "ec2:internet-gateway" | "compute:subnetwork" => 2,
+        "ec2:vpc" | "compute:network" => 3,
+        t if t.eq_ignore_ascii_case(AZURE_RESOURCE_GROUP) => 3,
+        _ => 1,
+    }
+}
+
+fn scan(target: &ScanTarget, credentials: &CloudCredentials, known: &KnownIds) -> Result<OrphanScan, String> {
+    let mut scan = OrphanScan { prefix: target.prefix.clone(), ..Default::default() };
+    let candidates = list_candidates(target, credentials, &mut scan.warnings)?;
+    scan.resources = untracked(candidates, known);
+    Ok(scan)
+}
+
+/// Delete the selected resources from a fresh scan, so nothing that has since
+/// been imported into the state is touched, then scan again.
+fn delete_selected(target: &ScanTarget, credentials: &CloudCredentials, known: &KnownIds, ids: &[String]) -> Result<OrphanScan, String> {
+    let mut selected: Vec<OrphanedResource> = scan(target, credentials, known)?
+        .resources
+        .into_iter()
+        .filter(|r| ids.contains(&r.id))
+        .collect();
+    selected.sort_by_key(|r| delete_rank(&r.resource_type));
+
+    let (mut removed, mut failed) = (Vec::new(), Vec::new());
+    for resource in selected {
+        let Some((tool, args)) = resource.delete.as_ref().filter(|_| resource.deletable) else {
+            let reason = if resource.delete.is_some() {
+                "Only named like this deployment's resources; check it and delete it in the cloud console"
+            } else {
+                "Delete this resource in the cloud console"
+            };
+            failed.push((resource.id, reason.to_string()));
+            continue;
+        };
+        let result = CliCommand::new(*tool)
+            .map_err(String::from)
+            .and_then(|mut cmd| {
+                cmd.args(args).credentials(credentials)?;
+                Ok(cmd.run_checked()?)
+            });
+        match result {
+            Ok(_) => removed.push(resource.id),
+            Err(e) => failed.push((resource.id, e)),
+        }
+    }
+
+    let mut after = scan(target, credentials, known)?;
+    after.removed = removed;
+    after.failed = failed;
+    Ok(after)
+}
+
+fn scan_target(deployment_dir: &Path, prefix: String, other_prefixes: Vec<String>, credentials: &CloudCredentials) -> ScanTarget {
+    let tfvars = fs::read_to_string(deployment_dir.join("terraform.tfvars")).unwrap_or_default();
+    let entries = super::audit::parse_tfvars(&tfvars);
+    let configured = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            entries
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().trim_matches('"').to_string())
+                .filter(|value| !value.is_empty())
+        })
+    };
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+    let cloud = non_empty(&credentials.cloud).unwrap_or_else(|| "aws".to_string());
+    let scope = match cloud.as_str() {
+        "aws" => configured(&["region"]).or_else(|| non_empty(&credentials.aws_region)),
+        "azure" => configured(&["azure_subscription_id", "subscription_id"]).or_else(|| non_empty(&credentials.azure_subscription_id)),
+        "gcp" => configured(&["google_project_name", "google_project", "project"]).or_else(|| non_empty(&credentials.gcp_project_id)),
+        _ => None,
+    };
+    ScanTarget { cloud, prefix, other_prefixes, scope }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List cloud resources tagged or named with the deployment's prefix that the
+/// Terraform state doesn't track, and delete the selected ones. With no `ids`
+/// nothing is deleted. The deployment is locked while scanning so a running
+/// apply's new resources aren't mistaken for orphans. Fails when the state
+/// can't be read.
+#[tauri::command]
+pub async fn scan_orphaned_resources(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+    ids: Option<Vec<String>>,
+) -> Result<OrphanScan, String> {
+    let deployment_dir = get_deployments_dir(&app)?.join(sanitize_deployment_name(&deployment_name)?);
+    let prefix = account_cleanup::deployment_prefix(&app, &deployment_name, PREFIX_VARIABLES)?;
+    let other_prefixes = account_cleanup::other_prefixes(&app, &deployment_name, &prefix, PREFIX_VARIABLES)?;
+    let lock = deployment_lock::acquire(&app, &deployment_name, "orphan_scan")?;
+    let key = if state_encryption::is_enabled(&deployment_dir) {
+        Some(state_encryption::load_key(&app)?)
+    } else {
+        None
+    };
+    let target = scan_target(&deployment_dir, prefix, other_prefixes, &credentials);
+
+    super::run_blocking(move || {
+        let _lock = lock;
+        let known = KnownIds {
+            tracked: state_identifiers(&current_state(&deployment_dir, key.as_ref(), &credentials)?)?,
+            previously_tracked: snapshot_identifiers(&deployment_dir, key.as_ref()),
+        };
+        match ids.filter(|ids| !ids.is_empty()) {
+            Some(ids) => delete_selected(&target, &credentials, &known, &ids),
+            None => scan(&target, &credentials, &known),
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(prefix: &str, other_prefixes: &[&str]) -> ScanTarget {
+        ScanTarget {
+            prefix: prefix.to_string(),
+            other_prefixes: other_prefixes.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tracked_resources_match_by_arn_id_or_self_link() {
+        let state = serde_json::json!({
+            "resources": [
+                { "mode": "managed", "type": "aws_vpc", "name": "main", "instances": [
+                    { "attributes": { "id": "vpc-1", "arn": "arn:aws:ec2:us-east-1:123:vpc/vpc-1" } }
+                ]},
+                { "mode": "managed", "type": "aws_nat_gateway", "name": "nat", "instances": [
+                    { "attributes": { "id": "nat-1" } }
+                ]},
+                { "mode": "managed", "type": "google_compute_network", "name": "vpc", "instances": [
+                    { "attributes": { "id": "projects/p/global/networks/dev-vpc",
+                                      "self_link": "https://www.googleapis.com/compute/v1/projects/p/global/networks/dev-vpc" } }
+                ]},
+                { "mode": "data", "type": "aws_iam_role", "name": "existing", "instances": [
+                    { "attributes": { "arn": "arn:aws:iam::123:role/dev-existing" } }
+                ]}
+            ]
+        });
+        let tracked = state_identifiers(&state.to_string()).unwrap();
+
+        assert!(is_tracked("arn:aws:ec2:us-east-1:123:vpc/vpc-1", &tracked));
+        assert!(is_tracked("arn:aws:ec2:us-east-1:123:natgateway/nat-1", &tracked));
+        assert!(is_tracked("https://www.googleapis.com/compute/v1/projects/p/global/networks/dev-vpc", &tracked));
+        assert!(!is_tracked("arn:aws:ec2:us-east-1:123:vpc/vpc-2", &tracked));
+        assert!(!is_tracked("arn:aws:iam::123:role/dev-existing", &tracked), "data sources are not managed");
+    }
+
+    #[test]
+    fn unreadable_state_is_an_error_not_an_empty_state() {
+        assert!(state_identifiers("").is_err());
+        assert!(state_identifiers("<html>").is_err());
+        assert!(state_identifiers("{}").is_err());
+        assert!(state_identifiers(r#"{"version": 4, "resources": []}"#).unwrap().is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let err = current_state(dir.path(), None, &CloudCredentials::default()).unwrap_err();
+        assert!(err.contains("no state"));
+        fs::write(state_encryption::encrypted_path(&dir.path().join("terraform.tfstate")), "{}").unwrap();
+        assert!(current_state(dir.path(), None, &CloudCredentials::default()).unwrap_err().contains("decrypted"));
+    }
+
+    #[test]
+    fn only_tagged_or_previously_tracked_resources_are_deletable() {
+        let roles = serde_json::json!({ "Roles": [
+            { "RoleName": "dev-cross-account", "Arn": "arn:aws:iam::123:role/dev-cross-account" },
+            { "RoleName": "dev-old", "Arn": "arn:aws:iam::123:role/dev-old" }
+        ]});
+        let tagged = serde_json::json!({ "ResourceTagMappingList": [
+            { "ResourceARN": "arn:aws:ec2:eu-west-1:123:vpc/vpc-1", "Tags": [] }
+        ]});
+        let mut candidates = parse_aws_tagged(&tagged);
+        candidates.extend(parse_aws_roles(&roles, &target("dev", &[])));
+        let known = KnownIds {
+            previously_tracked: HashSet::from(["arn:aws:iam::123:role/dev-old".to_string()]),
+            ..Default::default()
+        };
+
+        let deletable: Vec<(String, bool)> = untracked(candidates, &known).into_iter().map(|r| (r.name, r.deletable)).collect();
+        assert_eq!(
+            deletable,
+            vec![("vpc-1".to_string(), true), ("dev-cross-account".to_string(), false), ("dev-old".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn names_of_deployments_extending_the_prefix_are_skipped() {
+        let buckets = serde_json::json!({ "Buckets": [{ "Name": "dev-root" }, { "Name": "dev-eu-root" }, { "Name": "devops" }] });
+        let buckets = parse_aws_buckets(&buckets, &target("dev", &["dev-eu"]));
+        assert_eq!(buckets.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["dev-root"]);
+    }
+
+    #[test]
+    fn aws_resources_get_delete_commands_for_known_types() {
+        let tagged = serde_json::json!({ "ResourceTagMappingList": [
+            { "ResourceARN": "arn:aws:ec2:eu-west-1:123:subnet/subnet-1", "Tags": [{ "Key": "Name", "Value": "dev-private" }] },
+            { "ResourceARN": "arn:aws:kms:eu-west-1:123:key/abcd", "Tags": [] }
+        ]});
+        let resources = parse_aws_tagged(&tagged);
+        assert_eq!(resources[0].name, "dev-private");
+        assert_eq!(resources[0].resource_type, "ec2:subnet");
+        assert_eq!(
+            resources[0].delete.as_ref().unwrap().1,
+            vec!["ec2", "delete-subnet", "--subnet-id", "subnet-1", "--region", "eu-west-1"]
+        );
+        assert!(!resources[1].deletable);
+
+        let roles = serde_json::json!({ "Roles": [
+            { "RoleName": "dev-cross-account", "Arn": "arn:aws:iam::123:role/dev-cross-account" },
+            { "RoleName": "devops-admin", "Arn": "arn:aws:iam::123:role/devops-admin" }
+        ]});
+        let roles = parse_aws_roles(&roles, &target("dev", &[]));
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].delete.as_ref().unwrap().1, vec!["iam", "delete-role", "--role-name", "dev-cross-account"]);
+
+        let buckets = parse_aws_buckets(&serde_json::json!({ "Buckets": [{ "Name": "dev-root" }] }), &target("dev", &[]));
+        assert_eq!(buckets[0].id, "arn:aws:s3:::dev-root");
+        assert_eq!(buckets[0].resource_type, "s3:bucket");
+    }
+
+    #[test]
+    fn azure_groups_and_gcp_subnets_delete_with_their_scope() {
+        let groups = serde_json::json!([
+            { "id": "/subscriptions/sub-1/resourceGroups/dev-rg", "name": "dev-rg" },
+            { "id": "/subscriptions/sub-1/resourceGroups/other", "name": "other" }
+        ]);
+        let groups = parse_azure_resources(&groups, &target("dev", &[]));
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].resource_type, AZURE_RESOURCE_GROUP);
+        assert!(groups[0].delete.as_ref().unwrap().1.contains(&"sub-1".to_string()));
+
+        let subnets = serde_json::json!([{
+            "name": "dev-subnet",
+            "selfLink": "https://www.googleapis.com/compute/v1/projects/p/regions/us-east1/subnetworks/dev-subnet",
+            "region": "https://www.googleapis.com/compute/v1/projects/p/regions/us-east1"
+        }]);
+        let subnets = parse_gcp_networks(&subnets, &target("dev", &[]), "p");
+        assert_eq!(subnets[0].resource_type, "compute:subnetwork");
+        assert_eq!(
+            subnets[0].delete.as_ref().unwrap().1,
+            vec!["compute", "networks", "subnets", "delete", "dev-subnet", "--region", "us-east1", "--project", "p", "--quiet"]
+        );
+        assert!(delete_rank("compute:subnetwork") < delete_rank("compute:network"));
+    }
+}
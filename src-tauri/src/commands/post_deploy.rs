@@ -0,0 +1,377 @@
+//! Optional workspace configuration after a successful apply.
+//!
+//! When saving a configuration the user can ask for a starter cluster policy,
+//! a small SQL warehouse, and a list of users to add to the new workspace.
+//! The request is stored in `.deployer-post-deploy.json`; after `apply`
+//! succeeds the steps run against the workspace API using the deployment's
+//! Databricks credentials. Each step is independent, so one failure doesn't
+//! stop the rest, and re-running is safe (existing objects are reported, not
+//! duplicated).
+
+use super::{
+    debug_log, get_deployments_dir, lock_or_recover, opt_non_empty, sanitize_deployment_name, state_encryption,
+    CloudCredentials,
+};
+use crate::databricks_api::{AccountAuth, ApiError, WorkspaceClient};
+use crate::terraform::DEPLOYMENT_STATUS;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Marker file in a deployment directory holding the requested steps.
+const POST_DEPLOY_FILE: &str = ".deployer-post-deploy.json";
+
+const STARTER_POLICY_NAME: &str = "Starter - Small Clusters";
+const STARTER_WAREHOUSE_NAME: &str = "Starter Warehouse";
+const STARTER_WAREHOUSE_SIZE: &str = "2X-Small";
+const STARTER_WAREHOUSE_AUTO_STOP_MINS: u32 = 10;
+
+/// Users accepted per deployment; larger rosters belong in SCIM provisioning.
+const MAX_INVITES: usize = 100;
+
+/// Post-deploy steps requested when saving a configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostDeploySettings {
+    #[serde(default)]
+    pub cluster_policy: bool,
+    #[serde(default)]
+    pub sql_warehouse: bool,
+    /// Emails added to the workspace as users.
+    #[serde(default)]
+    pub invite_users: Vec<String>,
+}
+
+impl PostDeploySettings {
+    fn is_empty(&self) -> bool {
+        !self.cluster_policy && !self.sql_warehouse && self.invite_users.is_empty()
+    }
+}
+
+/// Outcome of one post-deploy step.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PostDeployStep {
+    /// "cluster_policy", "sql_warehouse", or "invite_user".
+    pub step: String,
+    /// Name of the policy/warehouse, or the invited email.
+    pub target: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostDeployResult {
+    pub workspace_url: String,
+    pub steps: Vec<PostDeployStep>,
+}
+
+// ─── Settings ───────────────────────────────────────────────────────────────
+
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.chars().any(|c| c.is_whitespace() || c == '"' || c == ',')
+        && !domain.contains('@')
+}
+
+/// Trim, deduplicate, and validate the invite list.
+fn normalize_settings(settings: &PostDeploySettings) -> Result<PostDeploySettings, String> {
+    let mut invite_users: Vec<String> = Vec::new();
+    for email in settings.invite_users.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        if !is_valid_email(email) {
+            return Err(format!("Invalid email address: {}", email));
+        }
+        if !invite_users.iter().any(|e| e.eq_ignore_ascii_case(email)) {
+            invite_users.push(email.to_string());
+        }
+    }
+    if invite_users.len() > MAX_INVITES {
+        return Err(format!("At most {} users can be invited after deployment", MAX_INVITES));
+    }
+    Ok(PostDeploySettings {
+        invite_users,
+        ..settings.clone()
+    })
+}
+
+/// Store the post-deploy request for a deployment; a request with no steps
+/// removes it.
+pub(crate) fn write_settings(deployment_dir: &Path, settings: &PostDeploySettings) -> Result<(), String> {
+    let path = deployment_dir.join(POST_DEPLOY_FILE);
+    let settings = normalize_settings(settings)?;
+    if settings.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to clear post-deploy settings: {}", e))?;
+        }
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize post-deploy settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save post-deploy settings: {}", e))
+}
+
+pub(crate) fn read_settings(deployment_dir: &Path) -> Option<PostDeploySettings> {
+    let content = fs::read_to_string(deployment_dir.join(POST_DEPLOY_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// ─── Steps ──────────────────────────────────────────────────────────────────
+
+/// The `workspace_url` output from the deployment's state.
+fn workspace_url_from_state(deployment_dir: &Path, key: Option<&[u8; 32]>) -> Option<String> {
+    let state: serde_json::Value = serde_json::from_str(&state_encryption::read_state(deployment_dir, key)?).ok()?;
+    state["outputs"]["workspace_url"]["value"]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Workspace auth for the deployment's Databricks credentials. GCP ID tokens
+/// are minted for the accounts host, so GCP needs a profile or service principal.
+async fn workspace_auth(credentials: &CloudCredentials) -> Result<AccountAuth, String> {
+    let has_databricks_auth = credentials.databricks_auth_type.as_deref() == Some("profile")
+        || (opt_non_empty(&credentials.databricks_client_id) && opt_non_empty(&credentials.databricks_client_secret));
+    if credentials.cloud.as_deref() == Some("gcp") && !has_databricks_auth {
+        return Err("Post-deploy configuration needs Databricks service principal or CLI profile credentials".to_string());
+    }
+    super::databricks::account_auth(credentials).await
+}
+
+/// Starter policy: small autoscaling clusters on the latest LTS runtime that
+/// shut down when idle.
+fn starter_policy_definition() -> serde_json::Value {
+    serde_json::json!({
+        "spark_version": { "type": "unlimited", "defaultValue": "auto:latest-lts" },
+        "autotermination_minutes": { "type": "range", "minValue": 10, "maxValue": 60, "defaultValue": 30 },
+        "autoscale.min_workers": { "type": "fixed", "value": 1 },
+        "autoscale.max_workers": { "type": "range", "maxValue": 4, "defaultValue": 2 }
+    })
+}
+
+/// The workspace APIs report duplicates as 409 or as a 400 "already exists".
+fn already_exists(e: &ApiError) -> bool {
+    matches!(e, ApiError::Conflict(_)) || e.body().to_lowercase().contains("already exists")
+}
+
+fn step(step: &str, target: &str, result: Result<String, ApiError>, exists_message: &str) -> PostDeployStep {
+    let (success, message) = match result {
+        Ok(message) => (true, message),
+        Err(e) if already_exists(&e) => (true, exists_message.to_string()),
+        Err(e) => (false, e.to_string()),
+    };
+    PostDeployStep {
+        step: step.to_string(),
+        target: target.to_string(),
+        success,
+        message,
+    }
+}
+
+/// Run the requested steps against a workspace. The policy and warehouse
+/// are looked up by name first: a failed create may still have gone
+/// through, and re-runs must not add a second one.
+pub(crate) async fn run_steps(client: &WorkspaceClient, settings: &PostDeploySettings) -> Vec<PostDeployStep> {
+    let mut steps = Vec::new();
+    if settings.cluster_policy {
+        let result = match client.find_cluster_policy(STARTER_POLICY_NAME).await {
+            Ok(Some(id)) => Ok(format!("Cluster policy already exists ({})", id)),
+            Ok(None) => client
+                .create_cluster_policy(STARTER_POLICY_NAME, &starter_policy_definition())
+                .await
+                .map(|id| format!("Created cluster policy {}", id)),
+            Err(e) => Err(e),
+        };
+        steps.push(step("cluster_policy", STARTER_POLICY_NAME, result, "Cluster policy already exists"));
+    }
+    if settings.sql_warehouse {
+        let result = match client.find_sql_warehouse(STARTER_WAREHOUSE_NAME).await {
+            Ok(Some(id)) => Ok(format!("SQL warehouse already exists ({})", id)),
+            Ok(None) => client
+                .create_sql_warehouse(STARTER_WAREHOUSE_NAME, STARTER_WAREHOUSE_SIZE, STARTER_WAREHOUSE_AUTO_STOP_MINS)
+                .await
+                .map(|id| format!("Created SQL warehouse {}", id)),
+            Err(e) => Err(e),
+        };
+        steps.push(step("sql_warehouse", STARTER_WAREHOUSE_NAME, result, "SQL warehouse already exists"));
+    }
+    for email in &settings.invite_users {
+        let result = client.create_user(email).await.map(|_| "Added to workspace".to_string());
+        steps.push(step("invite_user", email, result, "Already a workspace user"));
+    }
+    steps
+}
+
+/// Run the saved post-deploy steps for a deployment. `Ok(None)` when none
+/// were requested.
+pub(crate) async fn run_for_deployment(
+    deployment_dir: &Path,
+    credentials: &CloudCredentials,
+    key: Option<&[u8; 32]>,
+) -> Result<Option<PostDeployResult>, String> {
+    let Some(settings) = read_settings(deployment_dir).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let workspace_url = workspace_url_from_state(deployment_dir, key)
+        .ok_or("Deployment state has no workspace_url output")?;
+    let auth = workspace_auth(credentials).await?;
+    let client = WorkspaceClient::new(&workspace_url, auth)?;
+    debug_log!("[post_deploy] configuring {}", workspace_url);
+    let steps = run_steps(&client, &settings).await;
+    Ok(Some(PostDeployResult { workspace_url, steps }))
+}
+
+/// Human-readable lines for the deployment log.
+pub(crate) fn format_result(result: &Result<Option<PostDeployResult>, String>) -> String {
+    match result {
+        Ok(None) => String::new(),
+        Ok(Some(result)) => {
+            let mut out = format!("\nConfiguring workspace {}...\n", result.workspace_url);
+            for s in &result.steps {
+                out.push_str(&format!(
+                    "  {} {} '{}': {}\n",
+                    if s.success { "[ok]" } else { "[failed]" },
+                    s.step.replace('_', " "),
+                    s.target,
+                    s.message
+                ));
+            }
+            out
+        }
+        Err(e) => format!("\nWarning: post-deploy configuration skipped: {}\n", e),
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Post-deploy steps saved for a deployment.
+#[tauri::command]
+pub fn get_post_deploy_settings(app: AppHandle, deployment_name: String) -> Result<Option<PostDeploySettings>, String> {
+    let dir = get_deployments_dir(&app)?.join(sanitize_deployment_name(&deployment_name)?);
+    Ok(read_settings(&dir))
+}
+
+/// Re-run the saved post-deploy steps, e.g. after fixing a failed invite.
+#[tauri::command]
+pub async fn run_post_deploy(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+) -> Result<PostDeployResult, String> {
+    if lock_or_recover(&DEPLOYMENT_STATUS).running {
+        return Err("Cannot configure the workspace while a deployment is running".to_string());
+    }
+    let dir = get_deployments_dir(&app)?.join(sanitize_deployment_name(&deployment_name)?);
+    if !dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    let key = if state_encryption::is_enabled(&dir) {
+        Some(state_encryption::load_key(&app)?)
+    } else {
+        None
+    };
+    run_for_deployment(&dir, &credentials, key.as_ref())
+        .await?
+        .ok_or_else(|| "No post-deploy configuration saved for this deployment".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_emails() {
+        assert!(is_valid_email("jane.doe@example.com"));
+        assert!(!is_valid_email("jane"));
+        assert!(!is_valid_email("@example.com"));
+        assert!(!is_valid_email("jane@localhost"));
+        assert!(!is_valid_email("jane doe@example.com"));
+        assert!(!is_valid_email("a@b@example.com"));
+    }
+
+    #[test]
+    fn settings_round_trip_and_dedupe() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = PostDeploySettings {
+            cluster_policy: true,
+            sql_warehouse: false,
+            invite_users: vec![" a@example.com ".into(), "A@example.com".into(), "".into(), "b@example.com".into()],
+        };
+        write_settings(dir.path(), &settings).unwrap();
+        let saved = read_settings(dir.path()).unwrap();
+        assert_eq!(saved.invite_users, vec!["a@example.com", "b@example.com"]);
+        assert!(saved.cluster_policy);
+
+        // Nothing requested clears the file
+        write_settings(dir.path(), &PostDeploySettings::default()).unwrap();
+        assert_eq!(read_settings(dir.path()), None);
+    }
+
+    #[test]
+    fn rejects_invalid_invites() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = PostDeploySettings {
+            invite_users: vec!["not-an-email".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            write_settings(dir.path(), &settings).unwrap_err(),
+            "Invalid email address: not-an-email"
+        );
+    }
+
+    #[test]
+    fn reads_workspace_url_output() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("terraform.tfstate"),
+            r#"{"outputs": {"workspace_url": {"value": "https://dbc-1.cloud.databricks.com/", "type": "string"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            workspace_url_from_state(dir.path(), None).as_deref(),
+            Some("https://dbc-1.cloud.databricks.com/")
+        );
+    }
+
+    #[tokio::test]
+    async fn picks_workspace_auth_from_credentials() {
+        let sp = CloudCredentials {
+            databricks_client_id: Some("id".into()),
+            databricks_client_secret: Some("secret".into()),
+            ..Default::default()
+        };
+        assert!(matches!(workspace_auth(&sp).await, Ok(AccountAuth::ServicePrincipal { .. })));
+        let profile = CloudCredentials {
+            databricks_auth_type: Some("profile".into()),
+            databricks_profile: Some("acct".into()),
+            ..Default::default()
+        };
+        assert!(matches!(workspace_auth(&profile).await, Ok(AccountAuth::CliProfile(p)) if p == "acct"));
+        let gcp = CloudCredentials {
+            cloud: Some("gcp".into()),
+            ..Default::default()
+        };
+        assert!(workspace_auth(&gcp).await.is_err());
+    }
+
+    #[test]
+    fn formats_step_results() {
+        let result = Ok(Some(PostDeployResult {
+            workspace_url: "https://w".into(),
+            steps: vec![PostDeployStep {
+                step: "invite_user".into(),
+                target: "a@example.com".into(),
+                success: false,
+                message: "Databricks API error (403 Forbidden)".into(),
+            }],
+        }));
+        assert!(format_result(&result).contains("[failed] invite user 'a@example.com'"));
+        assert_eq!(format_result(&Ok(None)), "");
+    }
+}
@@ -0,0 +1,553 @@
+//! Pre-deployment checklist run as one concurrent pipeline.
+//!
+//! The wizard used to call credential validation, permission checks, Unity
+//! Catalog checks, quota checks and private endpoint checks one after another. `run_preflight_checks`
+//! starts every check that applies to the selected cloud at once, emits a
+//! [`PREFLIGHT_CHECK_EVENT`] as each one starts and finishes, and returns a
+//! consolidated pass/warn/fail summary.
+
+use super::cloud_provider::provider;
+use super::{non_empty_str, private_connectivity, CloudCredentials, CloudPermissionCheck, UCPermissionCheck};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+/// Event emitted whenever a preflight check changes status.
+pub const PREFLIGHT_CHECK_EVENT: &str = "preflight-check";
+
+/// One line of a checklist: a single permission, role or privilege.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PreflightEntry {
+    pub name: String,
+    /// "pass", "fail" or "skipped".
+    pub status: String,
+    /// How much a failure matters: "blocker", "warning" or "info".
+    pub severity: String,
+    pub message: String,
+    pub remediation: Option<String>,
+    pub docs_url: Option<String>,
+}
+
+impl PreflightEntry {
+    fn new(name: &str, status: &str, severity: &str, message: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: status.to_string(),
+            severity: severity.to_string(),
+            message: message.to_string(),
+            remediation: None,
+            docs_url: None,
+        }
+    }
+
+    pub(crate) fn pass(name: &str, message: &str) -> Self {
+        Self::new(name, PASS, INFO, message)
+    }
+
+    pub(crate) fn fail(name: &str, severity: &str, message: &str) -> Self {
+        Self::new(name, FAIL, severity, message)
+    }
+
+    /// A check that couldn't run; reported as a warning, never a blocker.
+    pub(crate) fn skipped(name: &str, message: &str) -> Self {
+        Self::new(name, SKIPPED, WARNING, message)
+    }
+
+    pub(crate) fn remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+
+    pub(crate) fn docs(mut self, url: &str) -> Self {
+        self.docs_url = Some(url.to_string());
+        self
+    }
+}
+
+/// Cloud-agnostic checklist produced by the permission and Unity Catalog checks.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PreflightReport {
+    pub entries: Vec<PreflightEntry>,
+}
+
+impl PreflightReport {
+    pub(crate) fn push(&mut self, entry: PreflightEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Report holding a single entry.
+    pub(crate) fn single(entry: PreflightEntry) -> Self {
+        Self { entries: vec![entry] }
+    }
+
+    /// "fail" when a blocker failed, "warn" for failed warnings or skipped
+    /// checks, otherwise "pass".
+    pub(crate) fn status(&self) -> &'static str {
+        let failed = |severity: &str| {
+            self.entries.iter().any(|e| e.status == FAIL && e.severity == severity)
+        };
+        if failed(BLOCKER) {
+            FAIL
+        } else if failed(WARNING) || self.entries.iter().any(|e| e.status == SKIPPED) {
+            WARN
+        } else {
+            PASS
+        }
+    }
+
+    /// Names of the entries that were evaluated (passed or failed).
+    pub(crate) fn checked(&self) -> Vec<String> {
+        self.names(|e| e.status == PASS || e.status == FAIL)
+    }
+
+    /// Names of the failed entries.
+    pub(crate) fn failed(&self) -> Vec<String> {
+        self.names(|e| e.status == FAIL)
+    }
+
+    fn names(&self, keep: impl Fn(&PreflightEntry) -> bool) -> Vec<String> {
+        self.entries.iter().filter(|e| keep(e)).map(|e| e.name.clone()).collect()
+    }
+}
+
+/// Status of one preflight check (also the [`PREFLIGHT_CHECK_EVENT`] payload).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightCheckResult {
+    /// Stable identifier: "credentials", "databricks", "permissions", "unity_catalog", "quotas".
+    pub id: String,
+    pub name: String,
+    /// "running", "pass", "warn", "fail" or "skipped".
+    pub status: String,
+    pub message: String,
+    pub duration_ms: u64,
+    /// Itemised findings, for checks that produce a checklist.
+    pub report: PreflightReport,
+}
+
+/// Consolidated outcome of all preflight checks.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightSummary {
+    /// Worst status across the checks that ran: "pass", "warn" or "fail".
+    pub status: String,
+    pub passed: usize,
+    pub warnings: usize,
+    pub failed: usize,
+    pub checks: Vec<PreflightCheckResult>,
+}
+
+/// Outcome of a check before it is timed and labelled.
+type Outcome = (&'static str, String, PreflightReport);
+
+const PASS: &str = "pass";
+const WARN: &str = "warn";
+const FAIL: &str = "fail";
+const SKIPPED: &str = "skipped";
+
+/// Entry severities.
+pub(crate) const BLOCKER: &str = "blocker";
+pub(crate) const WARNING: &str = "warning";
+pub(crate) const INFO: &str = "info";
+
+/// Outcome without itemised entries.
+fn outcome(status: &'static str, message: impl Into<String>) -> Outcome {
+    (status, message.into(), PreflightReport::default())
+}
+
+/// Cloud the credentials target, inferred from provider fields when unset.
+fn cloud_of(credentials: &CloudCredentials) -> String {
+    match credentials.cloud.as_deref().filter(|c| !c.is_empty()) {
+        Some(cloud) => cloud.to_string(),
+        None if credentials.azure_tenant_id.is_some() => "azure".to_string(),
+        None if credentials.gcp_project_id.is_some() => "gcp".to_string(),
+        None => "aws".to_string(),
+    }
+}
+
+/// Region for the Unity Catalog check: explicit argument, then the usual template variables.
+fn target_region(region: Option<String>, values: &HashMap<String, serde_json::Value>) -> Option<String> {
+    region.filter(|r| !r.is_empty()).or_else(|| {
+        ["region", "location", "google_region"]
+            .iter()
+            .find_map(|key| values.get(*key).and_then(|v| v.as_str()))
+            .filter(|r| !r.is_empty())
+            .map(|r| r.to_string())
+    })
+}
+
+pub(crate) fn permission_outcome(check: &CloudPermissionCheck) -> Outcome {
+    (check.report.status(), check.message.clone(), check.report.clone())
+}
+
+pub(crate) fn uc_outcome(check: &UCPermissionCheck) -> Outcome {
+    (check.report.status(), check.message.clone(), check.report.clone())
+}
+
+pub(crate) fn quota_outcome(report: &super::QuotaReport) -> Outcome {
+    let short: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|c| !c.sufficient)
+        .map(|c| c.name.as_str())
+        .collect();
+    if short.is_empty() {
+        outcome(PASS, format!("{} quota(s) sufficient in {}", report.checks.len(), report.region))
+    } else {
+        outcome(FAIL, format!("Insufficient quota in {}: {}", report.region, short.join(", ")))
+    }
+}
+
+/// Combine finished checks into a summary; skipped checks don't affect the status.
+pub(crate) fn summarize(checks: Vec<PreflightCheckResult>) -> PreflightSummary {
+    let count = |status: &str| checks.iter().filter(|c| c.status == status).count();
+    let (passed, warnings, failed) = (count(PASS), count(WARN), count(FAIL));
+    let status = if failed > 0 {
+        FAIL
+    } else if warnings > 0 {
+        WARN
+    } else {
+        PASS
+    };
+    PreflightSummary {
+        status: status.to_string(),
+        passed,
+        warnings,
+        failed,
+        checks,
+    }
+}
+
+/// Run one check, reporting "running" before and the final status after.
+async fn run_check<F>(
+    report: &(dyn Fn(&PreflightCheckResult) + Sync),
+    id: &str,
+    name: &str,
+    check: F,
+) -> PreflightCheckResult
+where
+    F: Future<Output = Outcome>,
+{
+    let mut result = PreflightCheckResult {
+        id: id.to_string(),
+        name: name.to_string(),
+        status: "running".to_string(),
+        message: String::new(),
+        duration_ms: 0,
+        report: PreflightReport::default(),
+    };
+    report(&result);
+
+    let started = Instant::now();
+    let (status, message, entries) = check.await;
+    result.status = status.to_string();
+    result.message = message;
+    result.report = entries;
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    report(&result);
+    result
+}
+
+fn skipped(reason: &str) -> Outcome {
+    outcome(SKIPPED, reason)
+}
+
+async fn credentials_check(cloud: &str, credentials: &CloudCredentials) -> Outcome {
+    let Some(provider) = provider(cloud) else {
+        return outcome(FAIL, format!("Unsupported cloud: {}", cloud));
+    };
+    match provider.validate_credentials(credentials.clone()).await {
+        Ok(Some(message)) => outcome(PASS, message),
+        Ok(None) => skipped("Service principal credentials are verified by the permission check"),
+        Err(e) => outcome(FAIL, e),
+    }
+}
+
+async fn databricks_check(cloud: &str, credentials: &CloudCredentials) -> Outcome {
+    let Some(account_id) = non_empty_str(&credentials.databricks_account_id) else {
+        return skipped("No Databricks account ID provided");
+    };
+    let result = if let (Some(id), Some(secret)) = (
+        non_empty_str(&credentials.databricks_client_id),
+        non_empty_str(&credentials.databricks_client_secret),
+    ) {
+        super::validate_databricks_credentials(
+            account_id.to_string(),
+            id.to_string(),
+            secret.to_string(),
+            cloud.to_string(),
+        )
+        .await
+    } else if let Some(profile) = non_empty_str(&credentials.databricks_profile) {
+        super::validate_databricks_profile(profile.to_string(), cloud.to_string()).await
+    } else {
+        return skipped("Databricks access is verified by the Unity Catalog check");
+    };
+    match result {
+        Ok(message) => outcome(PASS, message),
+        Err(e) => outcome(FAIL, e),
+    }
+}
+
+async fn permissions_check(cloud: &str, credentials: &CloudCredentials) -> Outcome {
+    let Some(provider) = provider(cloud) else {
+        return outcome(FAIL, format!("Unsupported cloud: {}", cloud));
+    };
+    match provider.check_permissions(credentials.clone(), None).await {
+        Ok(check) => permission_outcome(&check),
+        Err(e) => outcome(FAIL, e),
+    }
+}
+
+async fn unity_catalog_check(credentials: &CloudCredentials, region: Option<String>) -> Outcome {
+    if non_empty_str(&credentials.databricks_account_id).is_none() {
+        return skipped("No Databricks account ID provided");
+    }
+    let Some(region) = region else {
+        return skipped("No region selected");
+    };
+    match super::check_uc_permissions(credentials.clone(), region, None, None).await {
+        Ok(check) => uc_outcome(&check),
+        Err(e) => outcome(FAIL, e),
+    }
+}
+
+async fn quotas_check(
+    template_id: Option<String>,
+    values: HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+) -> Outcome {
+    let Some(template_id) = template_id.filter(|t| !t.is_empty()) else {
+        return skipped("No template selected");
+    };
+    match super::check_cloud_quotas(template_id, values, credentials.clone()).await {
+        Ok(report) => quota_outcome(&report),
+        // Quota lookups are advisory; an unreadable quota shouldn't block a deployment
+        Err(e) => outcome(WARN, format!("Quota check unavailable: {}", e)),
+    }
+}
+
+async fn private_connectivity_check(
+    template_id: Option<String>,
+    values: HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+    templates_dir: Option<PathBuf>,
+) -> Outcome {
+    let Some(template_id) = template_id.filter(|t| !t.is_empty()) else {
+        return skipped("No template selected");
+    };
+    if !private_connectivity::uses_private_connectivity(&template_id, &values) {
+        return skipped("Template doesn't use private endpoints");
+    }
+    let creds = credentials.clone();
+    let result = super::run_blocking(move || {
+        Ok(private_connectivity::check(&template_id, &values, &creds, templates_dir.as_deref()))
+    })
+    .await;
+    match result {
+        Ok(report) => {
+            let failed = report.failed();
+            let message = if failed.is_empty() {
+                "Private endpoint settings are valid".to_string()
+            } else {
+                format!("Private connectivity issues: {}", failed.join(", "))
+            };
+            (report.status(), message, report)
+        }
+        Err(e) => outcome(FAIL, e),
+    }
+}
+
+/// Run every applicable check concurrently, calling `report` on each status change.
+pub(crate) async fn run_checks(
+    credentials: CloudCredentials,
+    template_id: Option<String>,
+    values: HashMap<String, serde_json::Value>,
+    region: Option<String>,
+    templates_dir: Option<PathBuf>,
+    report: &(dyn Fn(&PreflightCheckResult) + Sync),
+) -> PreflightSummary {
+    let cloud = cloud_of(&credentials);
+    let region = target_region(region, &values);
+
+    let (creds, databricks, permissions, uc, quotas, private) = tokio::join!(
+        run_check(report, "credentials", "Cloud credentials", credentials_check(&cloud, &credentials)),
+        run_check(report, "databricks", "Databricks account access", databricks_check(&cloud, &credentials)),
+        run_check(report, "permissions", "Cloud permissions", permissions_check(&cloud, &credentials)),
+        run_check(report, "unity_catalog", "Unity Catalog", unity_catalog_check(&credentials, region)),
+        run_check(report, "quotas", "Cloud quotas", quotas_check(template_id.clone(), values.clone(), &credentials)),
+        run_check(
+            report,
+            "private_connectivity",
+            "Private connectivity",
+            private_connectivity_check(template_id, values, &credentials, templates_dir),
+        ),
+    );
+    summarize(vec![creds, databricks, permissions, uc, quotas, private])
+}
+
+/// Run the pre-deployment checklist concurrently, emitting a
+/// [`PREFLIGHT_CHECK_EVENT`] per status change.
+#[tauri::command]
+pub async fn run_preflight_checks(
+    app: AppHandle,
+    credentials: CloudCredentials,
+    template_id: Option<String>,
+    values: Option<HashMap<String, serde_json::Value>>,
+    region: Option<String>,
+) -> Result<PreflightSummary, String> {
+    let emit = |result: &PreflightCheckResult| {
+        let _ = app.emit(PREFLIGHT_CHECK_EVENT, result.clone());
+    };
+    let templates_dir = super::get_templates_dir(&app).ok();
+    Ok(run_checks(credentials, template_id, values.unwrap_or_default(), region, templates_dir, &emit).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{QuotaCheck, QuotaReport};
+    use std::sync::Mutex;
+
+    fn result(id: &str, status: &str) -> PreflightCheckResult {
+        PreflightCheckResult {
+            id: id.to_string(),
+            name: id.to_string(),
+            status: status.to_string(),
+            message: String::new(),
+            duration_ms: 0,
+            report: PreflightReport::default(),
+        }
+    }
+
+    #[test]
+    fn summary_takes_worst_status_and_ignores_skipped() {
+        let summary = summarize(vec![result("a", PASS), result("b", SKIPPED)]);
+        assert_eq!((summary.status.as_str(), summary.passed), (PASS, 1));
+
+        let summary = summarize(vec![result("a", PASS), result("b", WARN)]);
+        assert_eq!(summary.status, WARN);
+
+        let summary = summarize(vec![result("a", WARN), result("b", FAIL), result("c", FAIL)]);
+        assert_eq!((summary.status.as_str(), summary.warnings, summary.failed), (FAIL, 1, 2));
+    }
+
+    #[test]
+    fn report_status_depends_on_failed_severity() {
+        let mut report = PreflightReport::single(PreflightEntry::pass("ec2:CreateVpc", "Allowed"));
+        assert_eq!(report.status(), PASS);
+
+        report.push(PreflightEntry::fail("s3:CreateBucket", WARNING, "Denied"));
+        assert_eq!(report.status(), WARN);
+
+        report.push(PreflightEntry::fail("iam:PassRole", BLOCKER, "Denied"));
+        assert_eq!(report.status(), FAIL);
+        assert_eq!(report.checked().len(), 3);
+        assert_eq!(report.failed(), vec!["s3:CreateBucket", "iam:PassRole"]);
+
+        let skipped = PreflightReport::single(PreflightEntry::skipped("IAM", "No CLI"));
+        assert_eq!(skipped.status(), WARN);
+        assert!(skipped.checked().is_empty());
+    }
+
+    #[test]
+    fn permission_check_derives_legacy_fields_from_report() {
+        let mut report = PreflightReport::single(PreflightEntry::pass("Contributor", "Assigned"));
+        report.push(
+            PreflightEntry::fail("User Access Administrator", WARNING, "Not assigned")
+                .remediation("az role assignment create ...")
+                .docs("https://example.com"),
+        );
+        let check = CloudPermissionCheck::from_report(report, "Missing role(s)");
+        assert!(!check.has_all_permissions);
+        assert!(check.is_warning);
+        assert_eq!(check.checked_permissions, vec!["Contributor", "User Access Administrator"]);
+        assert_eq!(check.missing_permissions, vec!["User Access Administrator"]);
+        let (status, message, report) = permission_outcome(&check);
+        assert_eq!((status, message.as_str()), (WARN, "Missing role(s)"));
+        assert_eq!(report.entries[1].remediation.as_deref(), Some("az role assignment create ..."));
+
+        let skipped = CloudPermissionCheck::skipped(PreflightEntry::skipped("IAM", "CLI missing"));
+        assert!(skipped.has_all_permissions && skipped.is_warning);
+        assert_eq!(skipped.message, "CLI missing");
+        assert_eq!(permission_outcome(&skipped).0, WARN);
+
+        let blocked = CloudPermissionCheck::from_report(
+            PreflightReport::single(PreflightEntry::fail("x", BLOCKER, "no")),
+            "blocked",
+        );
+        assert!(!blocked.is_warning);
+    }
+
+    #[test]
+    fn quota_outcome_lists_insufficient_quotas() {
+        let quota = |name: &str, sufficient| QuotaCheck {
+            name: name.to_string(),
+            limit: None,
+            used: None,
+            required: 1.0,
+            sufficient,
+            message: String::new(),
+        };
+        let mut report = QuotaReport {
+            cloud: "aws".to_string(),
+            region: "us-east-1".to_string(),
+            checks: vec![quota("VPCs", true), quota("Elastic IPs", false)],
+            all_sufficient: false,
+        };
+        let (status, message, _) = quota_outcome(&report);
+        assert_eq!(status, FAIL);
+        assert!(message.contains("Elastic IPs") && !message.contains("VPCs"));
+
+        report.checks.pop();
+        assert_eq!(quota_outcome(&report).0, PASS);
+    }
+
+    #[test]
+    fn region_falls_back_to_template_values() {
+        let mut values = HashMap::new();
+        values.insert("location".to_string(), serde_json::json!("westeurope"));
+        assert_eq!(target_region(None, &values).as_deref(), Some("westeurope"));
+        assert_eq!(
+            target_region(Some("eastus".to_string()), &values).as_deref(),
+            Some("eastus")
+        );
+        assert_eq!(target_region(Some(String::new()), &HashMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn checks_report_running_then_final_status() {
+        // Unsupported cloud: every check finishes without touching a CLI.
+        let credentials = CloudCredentials {
+            cloud: Some("oracle".to_string()),
+            ..Default::default()
+        };
+        let events = Mutex::new(Vec::new());
+        let record = |r: &PreflightCheckResult| {
+            events.lock().unwrap().push((r.id.clone(), r.status.clone()));
+        };
+
+        let summary = run_checks(credentials, None, HashMap::new(), None, None, &record).await;
+
+        assert_eq!(summary.checks.len(), 6);
+        assert_eq!(summary.status, FAIL);
+        let status = |id: &str| summary.checks.iter().find(|c| c.id == id).unwrap().status.clone();
+        assert_eq!(status("credentials"), FAIL);
+        assert_eq!(status("databricks"), SKIPPED);
+        assert_eq!(status("quotas"), SKIPPED);
+        assert_eq!(status("private_connectivity"), SKIPPED);
+
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.len(), 12);
+        for id in ["credentials", "databricks", "permissions", "unity_catalog", "quotas", "private_connectivity"] {
+            let statuses: Vec<&str> = events
+                .iter()
+                .filter(|(e, _)| e == id)
+                .map(|(_, s)| s.as_str())
+                .collect();
+            assert_eq!(statuses[0], "running");
+            assert_ne!(statuses[1], "running");
+        }
+    }
+}
@@ -0,0 +1,595 @@
+//! Private connectivity validation: AWS PrivateLink, Azure Private Link and
+//! GCP Private Service Connect.
+//!
+//! The SRA templates (and azure-pl-sts) route workspace traffic through
+//! private endpoints. A missing endpoint ID, a subnet outside the VNet, or a
+//! service attachment for the wrong region only fails late in `terraform
+//! apply`, so the required values are checked up front and the preflight
+//! check also asks the cloud whether the endpoint services are reachable.
+
+use super::aws::apply_aws_credentials;
+use super::preflight::{BLOCKER, WARNING};
+use super::quotas::{value_bool, value_str};
+use super::{CloudCredentials, PreflightEntry, PreflightReport};
+use crate::dependencies;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+const AWS_PRIVATELINK_DOCS_URL: &str = "https://docs.databricks.com/aws/en/security/network/classic/privatelink";
+const AZURE_PRIVATE_LINK_DOCS_URL: &str =
+    "https://learn.microsoft.com/azure/databricks/security/network/classic/private-link";
+const GCP_PSC_DOCS_URL: &str = "https://docs.databricks.com/gcp/en/security/network/classic/private-service-connect";
+
+// ─── Values ─────────────────────────────────────────────────────────────────
+
+/// A list variable as sent by the form: a JSON array, a JSON-encoded array
+/// string, or a comma-separated string. Nulls and blanks are dropped.
+fn value_list(values: &HashMap<String, serde_json::Value>, key: &str) -> Vec<String> {
+    let items = match values.get(key) {
+        Some(serde_json::Value::Array(items)) => items.clone(),
+        Some(serde_json::Value::String(s)) => match serde_json::from_str::<Vec<serde_json::Value>>(s) {
+            Ok(items) => items,
+            Err(_) => s.split(',').map(|p| serde_json::Value::String(p.to_string())).collect(),
+        },
+        _ => Vec::new(),
+    };
+    items
+        .iter()
+        .filter_map(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Whether a deployment of `template_id` with `values` uses private endpoints.
+pub(crate) fn uses_private_connectivity(template_id: &str, values: &HashMap<String, serde_json::Value>) -> bool {
+    match template_id {
+        "aws-sra" | "azure-pl-sts" => true,
+        "gcp-sra" => value_bool(values, "use_psc", false),
+        _ => false,
+    }
+}
+
+// ─── CIDRs ──────────────────────────────────────────────────────────────────
+
+/// IPv4 CIDR as (network address, prefix length).
+fn parse_cidr(cidr: &str) -> Option<(u32, u8)> {
+    let (addr, prefix) = cidr.trim().split_once('/')?;
+    let addr: Ipv4Addr = addr.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok().filter(|p| *p <= 32)?;
+    Some((u32::from(addr), prefix))
+}
+
+fn mask(prefix: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0)
+}
+
+fn cidr_contains(outer: (u32, u8), inner: (u32, u8)) -> bool {
+    inner.1 >= outer.1 && inner.0 & mask(outer.1) == outer.0 & mask(outer.1)
+}
+
+fn cidrs_overlap(a: (u32, u8), b: (u32, u8)) -> bool {
+    let prefix = a.1.min(b.1);
+    a.0 & mask(prefix) == b.0 & mask(prefix)
+}
+
+/// Check `subnets` are valid CIDRs inside `within` (when given) that don't
+/// overlap each other or `avoid`.
+fn subnet_entry(name: &str, subnets: &[String], within: Option<&str>, avoid: &[String], docs: &str) -> PreflightEntry {
+    let fail = |message: String| PreflightEntry::fail(name, BLOCKER, &message).docs(docs);
+    let mut parsed = Vec::new();
+    for subnet in subnets {
+        match parse_cidr(subnet) {
+            Some(cidr) => parsed.push((subnet, cidr)),
+            None => return fail(format!("'{}' is not a valid IPv4 CIDR block", subnet)),
+        }
+    }
+    if let Some((outer_raw, outer)) = within.and_then(|w| parse_cidr(w).map(|c| (w, c))) {
+        if let Some((subnet, _)) = parsed.iter().find(|(_, c)| !cidr_contains(outer, *c)) {
+            return fail(format!("{} is outside the network range {}", subnet, outer_raw))
+                .remediation(format!("Choose a range inside {}", outer_raw));
+        }
+    }
+    for (i, (subnet, cidr)) in parsed.iter().enumerate() {
+        if let Some((other, _)) = parsed[i + 1..].iter().find(|(_, c)| cidrs_overlap(*cidr, *c)) {
+            return fail(format!("{} overlaps {}", subnet, other));
+        }
+        if let Some(other) = avoid.iter().find(|o| parse_cidr(o).is_some_and(|c| cidrs_overlap(*cidr, c))) {
+            return fail(format!("{} overlaps the workspace subnet {}", subnet, other));
+        }
+    }
+    PreflightEntry::pass(name, &format!("{} valid", subnets.join(", ")))
+}
+
+// ─── Static validation ──────────────────────────────────────────────────────
+
+fn missing(name: &str, variable: &str, docs: &str) -> PreflightEntry {
+    PreflightEntry::fail(name, BLOCKER, &format!("{} is required", variable))
+        .remediation(format!("Set {}", variable))
+        .docs(docs)
+}
+
+/// Entry for an ID variable that must be set and start with `prefix`.
+fn id_entry(
+    values: &HashMap<String, serde_json::Value>,
+    name: &str,
+    variable: &str,
+    prefix: &str,
+    docs: &str,
+) -> PreflightEntry {
+    match value_str(values, &[variable]) {
+        None => missing(name, variable, docs),
+        Some(id) if !id.starts_with(prefix) => PreflightEntry::fail(
+            name,
+            BLOCKER,
+            &format!("{} '{}' should start with '{}'", variable, id, prefix),
+        )
+        .docs(docs),
+        Some(id) => PreflightEntry::pass(name, id),
+    }
+}
+
+fn validate_aws(values: &HashMap<String, serde_json::Value>, report: &mut PreflightReport) {
+    let docs = AWS_PRIVATELINK_DOCS_URL;
+    if value_str(values, &["network_configuration"]) == Some("custom") {
+        report.push(id_entry(values, "Workspace VPC endpoint", "custom_workspace_vpce_id", "vpce-", docs));
+        report.push(id_entry(values, "Relay VPC endpoint", "custom_relay_vpce_id", "vpce-", docs));
+        report.push(id_entry(values, "VPC", "custom_vpc_id", "vpc-", docs));
+        let subnets = value_list(values, "custom_private_subnet_ids");
+        report.push(if subnets.len() < 2 {
+            let message = "At least two private subnets in different availability zones are required";
+            PreflightEntry::fail("Private subnets", BLOCKER, message)
+                .remediation("Set custom_private_subnet_ids")
+                .docs(docs)
+        } else if let Some(bad) = subnets.iter().find(|s| !s.starts_with("subnet-")) {
+            PreflightEntry::fail("Private subnets", BLOCKER, &format!("'{}' is not a subnet ID", bad)).docs(docs)
+        } else {
+            PreflightEntry::pass("Private subnets", &subnets.join(", "))
+        });
+    } else {
+        let privatelink = value_list(values, "privatelink_subnets_cidr");
+        report.push(if privatelink.is_empty() {
+            missing("PrivateLink subnets", "privatelink_subnets_cidr", docs)
+        } else {
+            subnet_entry(
+                "PrivateLink subnets",
+                &privatelink,
+                value_str(values, &["vpc_cidr_range"]),
+                &value_list(values, "private_subnets_cidr"),
+                docs,
+            )
+        });
+    }
+
+    let frontend = value_str(values, &["frontend_vpce_id"]);
+    if let Some(id) = frontend {
+        report.push(if id.starts_with("vpce-") {
+            PreflightEntry::pass("Front-end VPC endpoint", id)
+        } else {
+            let message = format!("frontend_vpce_id '{}' should start with 'vpce-'", id);
+            PreflightEntry::fail("Front-end VPC endpoint", BLOCKER, &message).docs(docs)
+        });
+    }
+    if !value_bool(values, "public_access_enabled", true) && frontend.is_none() {
+        report.push(
+            PreflightEntry::fail(
+                "Front-end access",
+                BLOCKER,
+                "Public access is disabled but no front-end VPC endpoint is set, so the workspace UI would be unreachable",
+            )
+            .remediation("Set frontend_vpce_id or enable public access")
+            .docs(docs),
+        );
+    }
+}
+
+fn validate_azure(values: &HashMap<String, serde_json::Value>, report: &mut PreflightReport) {
+    let docs = AZURE_PRIVATE_LINK_DOCS_URL;
+    report.push(match value_str(values, &["subnet_private_endpoint_cidr"]) {
+        None => missing("Private endpoint subnet", "subnet_private_endpoint_cidr", docs),
+        Some(cidr) => subnet_entry(
+            "Private endpoint subnet",
+            &[cidr.to_string()],
+            value_str(values, &["cidr_dp"]),
+            &value_list(values, "subnet_workspace_cidrs"),
+            docs,
+        ),
+    });
+    if !value_bool(values, "public_network_access_enabled", true) {
+        report.push(
+            PreflightEntry::fail(
+                "Front-end access",
+                WARNING,
+                "Public network access is disabled; Terraform and users must reach the workspace through the private endpoint",
+            )
+            .remediation("Deploy from a network that resolves privatelink.azuredatabricks.net to the private endpoint")
+            .docs(docs),
+        );
+    }
+}
+
+/// Region segment of a `projects/<p>/regions/<r>/serviceAttachments/<name>` URI.
+fn attachment_region(uri: &str) -> Option<&str> {
+    let mut parts = uri.split('/');
+    parts.find(|p| *p == "regions")?;
+    parts.next().filter(|r| !r.is_empty())
+}
+
+fn attachment_entry(values: &HashMap<String, serde_json::Value>, name: &str, variable: &str) -> PreflightEntry {
+    let docs = GCP_PSC_DOCS_URL;
+    let Some(uri) = value_str(values, &[variable]) else {
+        return missing(name, variable, docs);
+    };
+    let region = value_str(values, &["google_region"]);
+    match attachment_region(uri) {
+        None => PreflightEntry::fail(name, BLOCKER, &format!("'{}' is not a service attachment URI", uri)).docs(docs),
+        Some(r) if region.is_some_and(|region| region != r) => PreflightEntry::fail(
+            name,
+            BLOCKER,
+            &format!("Service attachment is in {} but the workspace is in {}", r, region.unwrap_or_default()),
+        )
+        .remediation("Use the service attachment listed for the workspace region")
+        .docs(docs),
+        Some(_) => PreflightEntry::pass(name, uri),
+    }
+}
+
+fn validate_gcp(values: &HashMap<String, serde_json::Value>, report: &mut PreflightReport) {
+    let docs = GCP_PSC_DOCS_URL;
+    report.push(attachment_entry(values, "Workspace service attachment", "workspace_service_attachment"));
+    report.push(attachment_entry(values, "Relay service attachment", "relay_service_attachment"));
+    if let Some(cidr) = value_str(values, &["google_pe_subnet_ip_cidr_range"]) {
+        let nodes: Vec<String> = value_str(values, &["nodes_ip_cidr_range"]).map(String::from).into_iter().collect();
+        report.push(subnet_entry("PSC endpoint subnet", &[cidr.to_string()], None, &nodes, docs));
+    }
+    if value_bool(values, "use_existing_databricks_vpc_eps", false) {
+        for (name, variable) in [
+            ("Existing workspace endpoint", "existing_databricks_vpc_ep_workspace"),
+            ("Existing relay endpoint", "existing_databricks_vpc_ep_relay"),
+        ] {
+            report.push(match value_str(values, &[variable]) {
+                Some(id) => PreflightEntry::pass(name, id),
+                None => missing(name, variable, docs),
+            });
+        }
+    }
+}
+
+/// Check the values a private deployment needs: endpoint IDs, service
+/// attachments and subnet ranges. Empty for templates or settings without
+/// private connectivity.
+pub(crate) fn validate(template_id: &str, values: &HashMap<String, serde_json::Value>) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    if !uses_private_connectivity(template_id, values) {
+        return report;
+    }
+    match template_id.split('-').next() {
+        Some("aws") => validate_aws(values, &mut report),
+        Some("azure") => validate_azure(values, &mut report),
+        Some("gcp") => validate_gcp(values, &mut report),
+        _ => {}
+    }
+    report
+}
+
+// ─── Reachability ───────────────────────────────────────────────────────────
+
+/// `primary_endpoint` (or `secondary_endpoint` for DoD) of `region` in a
+/// map variable such as aws-sra's `workspace_config` default.
+fn endpoint_service_from_tf(variables_tf: &str, variable: &str, region: &str, secondary: bool) -> Option<String> {
+    let start = variables_tf.find(&format!("variable \"{}\"", variable))?;
+    let block = &variables_tf[start + 1..];
+    let block = &block[..block.find("\nvariable \"").unwrap_or(block.len())];
+    let block = &block[block.find(&format!("\"{}\"", region))?..];
+    let entry = &block[..block.find('}')?];
+    let key = if secondary { "secondary_endpoint" } else { "primary_endpoint" };
+    let value = entry[entry.find(key)?..].split_once('=')?.1.trim_start();
+    Some(value.strip_prefix('"')?.split('"').next()?.to_string())
+}
+
+fn aws_ec2(credentials: &CloudCredentials, region: &str, args: &[&str]) -> Result<serde_json::Value, String> {
+    let aws_cli = dependencies::find_aws_cli_path().ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
+    let mut cmd = super::silent_cmd(&aws_cli);
+    cmd.arg("ec2").args(args).args(["--region", region, "--output", "json"]);
+    apply_aws_credentials(&mut cmd, credentials)?;
+    let output = cmd.output().map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse AWS response: {}", e))
+}
+
+fn aws_service_entry(credentials: &CloudCredentials, region: &str, name: &str, service: &str) -> PreflightEntry {
+    match aws_ec2(credentials, region, &["describe-vpc-endpoint-services", "--service-names", service]) {
+        Ok(json) if json["ServiceDetails"].as_array().is_some_and(|s| !s.is_empty()) => {
+            PreflightEntry::pass(name, &format!("{} is reachable", service))
+        }
+        Ok(_) => not_reachable(name, service, region),
+        Err(e) if e.contains("InvalidServiceName") => not_reachable(name, service, region),
+        Err(e) => PreflightEntry::skipped(name, &format!("Couldn't query endpoint services: {}", e)),
+    }
+}
+
+fn not_reachable(name: &str, service: &str, region: &str) -> PreflightEntry {
+    PreflightEntry::fail(name, BLOCKER, &format!("Endpoint service {} isn't available in {}", service, region))
+        .remediation("Check the region's Databricks endpoint service names")
+        .docs(AWS_PRIVATELINK_DOCS_URL)
+}
+
+fn aws_endpoint_entry(credentials: &CloudCredentials, region: &str, name: &str, vpce_id: &str) -> PreflightEntry {
+    match aws_ec2(credentials, region, &["describe-vpc-endpoints", "--vpc-endpoint-ids", vpce_id]) {
+        Ok(json) => match json["VpcEndpoints"][0]["State"].as_str() {
+            Some(state) if state.eq_ignore_ascii_case("available") => {
+                PreflightEntry::pass(name, &format!("{} is available", vpce_id))
+            }
+            Some(state) => PreflightEntry::fail(name, BLOCKER, &format!("{} is {}", vpce_id, state))
+                .remediation("Accept the endpoint connection and wait until it is available")
+                .docs(AWS_PRIVATELINK_DOCS_URL),
+            None => PreflightEntry::fail(name, BLOCKER, &format!("{} not found in {}", vpce_id, region))
+                .docs(AWS_PRIVATELINK_DOCS_URL),
+        },
+        Err(e) if e.contains("NotFound") => PreflightEntry::fail(name, BLOCKER, &format!("{} not found in {}", vpce_id, region))
+            .docs(AWS_PRIVATELINK_DOCS_URL),
+        Err(e) => PreflightEntry::skipped(name, &format!("Couldn't query VPC endpoints: {}", e)),
+    }
+}
+
+fn aws_reachability(
+    values: &HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+    templates_dir: Option<&Path>,
+    report: &mut PreflightReport,
+) {
+    let Some(region) = value_str(values, &["region"]) else {
+        return;
+    };
+    if value_str(values, &["network_configuration"]) == Some("custom") {
+        for (name, variable) in [
+            ("Workspace VPC endpoint", "custom_workspace_vpce_id"),
+            ("Relay VPC endpoint", "custom_relay_vpce_id"),
+        ] {
+            if let Some(id) = value_str(values, &[variable]) {
+                report.push(aws_endpoint_entry(credentials, region, &format!("{} state", name), id));
+            }
+        }
+    } else {
+        let variables_tf = templates_dir.and_then(|d| std::fs::read_to_string(d.join("aws-sra").join("variables.tf")).ok());
+        let dod = value_str(values, &["databricks_gov_shard"]) == Some("dod");
+        for (name, variable) in [("Workspace endpoint service", "workspace_config"), ("Relay endpoint service", "scc_relay_config")] {
+            match variables_tf.as_deref().and_then(|tf| endpoint_service_from_tf(tf, variable, region, dod)) {
+                Some(service) => report.push(aws_service_entry(credentials, region, name, &service)),
+                None => report.push(PreflightEntry::skipped(name, &format!("No endpoint service known for {}", region))),
+            }
+        }
+    }
+    if let Some(id) = value_str(values, &["frontend_vpce_id"]) {
+        report.push(aws_endpoint_entry(credentials, region, "Front-end VPC endpoint state", id));
+    }
+}
+
+/// PSC connection status of an existing forwarding rule.
+fn gcp_endpoint_entry(project: &str, region: &str, name: &str, rule: &str) -> PreflightEntry {
+    let Some(gcloud) = dependencies::find_gcloud_cli_path() else {
+        return PreflightEntry::skipped(name, &crate::errors::cli_not_found("Google Cloud CLI"));
+    };
+    let output = super::silent_cmd(&gcloud)
+        .args(["compute", "forwarding-rules", "describe", rule, "--region", region, "--project", project, "--format", "json"])
+        .output();
+    let output = match output {
+        Ok(o) if o.status.success() => o,
+        Ok(o) => {
+            return PreflightEntry::fail(name, BLOCKER, &format!("Endpoint {} not found: {}", rule, String::from_utf8_lossy(&o.stderr).trim()))
+                .docs(GCP_PSC_DOCS_URL)
+        }
+        Err(e) => return PreflightEntry::skipped(name, &format!("Failed to run gcloud: {}", e)),
+    };
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+    match json["pscConnectionStatus"].as_str() {
+        Some("ACCEPTED") => PreflightEntry::pass(name, &format!("{} is connected", rule)),
+        status => PreflightEntry::fail(
+            name,
+            BLOCKER,
+            &format!("{} connection status is {}", rule, status.unwrap_or("unknown")),
+        )
+        .remediation("Ask Databricks to allow the project for the regional service attachment")
+        .docs(GCP_PSC_DOCS_URL),
+    }
+}
+
+fn gcp_reachability(values: &HashMap<String, serde_json::Value>, credentials: &CloudCredentials, report: &mut PreflightReport) {
+    if !value_bool(values, "use_existing_PSC_EP", false) {
+        return;
+    }
+    let project = credentials
+        .gcp_project_id
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .or_else(|| value_str(values, &["google_project", "project"]));
+    let (Some(project), Some(region)) = (project, value_str(values, &["google_region"])) else {
+        return;
+    };
+    for (name, variable) in [("Workspace PSC endpoint", "workspace_pe"), ("Relay PSC endpoint", "relay_pe")] {
+        if let Some(rule) = value_str(values, &[variable]) {
+            report.push(gcp_endpoint_entry(project, region, name, rule));
+        }
+    }
+}
+
+/// [`validate`], then, when the values are usable, ask the cloud whether the
+/// endpoint services and existing endpoints are reachable.
+pub(crate) fn check(
+    template_id: &str,
+    values: &HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+    templates_dir: Option<&Path>,
+) -> PreflightReport {
+    let mut report = validate(template_id, values);
+    if report.failed().is_empty() {
+        match template_id {
+            "aws-sra" => aws_reachability(values, credentials, templates_dir, &mut report),
+            "gcp-sra" => gcp_reachability(values, credentials, &mut report),
+            _ => {}
+        }
+    }
+    report
+}
+
+/// Validate a template's private connectivity settings without calling the cloud.
+#[tauri::command]
+pub fn validate_private_connectivity(
+    template_id: String,
+    values: HashMap<String, serde_json::Value>,
+) -> Result<PreflightReport, String> {
+    let template_id = super::sanitize_template_id(&template_id)?;
+    Ok(validate(&template_id, &values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, serde_json::Value)]) -> HashMap<String, serde_json::Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn cidr_containment_and_overlap() {
+        let vnet = parse_cidr("10.0.0.0/16").unwrap();
+        assert!(cidr_contains(vnet, parse_cidr("10.0.2.0/26").unwrap()));
+        assert!(!cidr_contains(vnet, parse_cidr("10.1.0.0/24").unwrap()));
+        assert!(cidrs_overlap(parse_cidr("10.0.0.0/24").unwrap(), parse_cidr("10.0.0.128/25").unwrap()));
+        assert!(!cidrs_overlap(parse_cidr("10.0.0.0/24").unwrap(), parse_cidr("10.0.1.0/24").unwrap()));
+        assert_eq!(mask(0), 0);
+        assert!(parse_cidr("10.0.0.0/33").is_none() && parse_cidr("10.0.0.0").is_none());
+    }
+
+    #[test]
+    fn aws_custom_mode_requires_endpoint_ids() {
+        let report = validate(
+            "aws-sra",
+            &values(&[
+                ("network_configuration", json!("custom")),
+                ("custom_workspace_vpce_id", json!("vpce-123")),
+                ("custom_relay_vpce_id", json!("endpoint-1")),
+                ("custom_vpc_id", json!("vpc-1")),
+                ("custom_private_subnet_ids", json!(["subnet-a"])),
+            ]),
+        );
+        assert_eq!(report.failed(), vec!["Relay VPC endpoint", "Private subnets"]);
+        assert_eq!(report.status(), "fail");
+    }
+
+    #[test]
+    fn aws_isolated_mode_checks_privatelink_subnets() {
+        let base = [
+            ("network_configuration", json!("isolated")),
+            ("vpc_cidr_range", json!("10.0.0.0/18")),
+            ("private_subnets_cidr", json!(["10.0.0.0/22", "10.0.4.0/22"])),
+        ];
+        let mut ok = values(&base);
+        ok.insert("privatelink_subnets_cidr".to_string(), json!("[\"10.0.28.0/26\", \"10.0.28.64/26\"]"));
+        assert!(validate("aws-sra", &ok).failed().is_empty());
+
+        let mut overlap = values(&base);
+        overlap.insert("privatelink_subnets_cidr".to_string(), json!(["10.0.4.0/26"]));
+        let report = validate("aws-sra", &overlap);
+        assert!(report.entries[0].message.contains("overlaps the workspace subnet 10.0.4.0/22"));
+
+        let mut outside = values(&base);
+        outside.insert("privatelink_subnets_cidr".to_string(), json!(["10.1.0.0/26"]));
+        assert!(validate("aws-sra", &outside).entries[0].message.contains("outside"));
+    }
+
+    #[test]
+    fn aws_private_only_access_needs_frontend_endpoint() {
+        let mut v = values(&[
+            ("network_configuration", json!("isolated")),
+            ("privatelink_subnets_cidr", json!(["10.0.28.0/26"])),
+            ("public_access_enabled", json!(false)),
+        ]);
+        assert_eq!(validate("aws-sra", &v).failed(), vec!["Front-end access"]);
+        v.insert("frontend_vpce_id".to_string(), json!("vpce-front"));
+        assert!(validate("aws-sra", &v).failed().is_empty());
+    }
+
+    #[test]
+    fn azure_private_endpoint_subnet_must_fit_vnet() {
+        let mut v = values(&[
+            ("cidr_dp", json!("10.0.0.0/16")),
+            ("subnet_workspace_cidrs", json!(["10.0.0.0/24", "10.0.1.0/24"])),
+            ("subnet_private_endpoint_cidr", json!("10.0.2.0/26")),
+        ]);
+        assert_eq!(validate("azure-pl-sts", &v).status(), "pass");
+
+        v.insert("subnet_private_endpoint_cidr".to_string(), json!("10.0.1.0/26"));
+        assert_eq!(validate("azure-pl-sts", &v).status(), "fail");
+
+        v.insert("subnet_private_endpoint_cidr".to_string(), json!("10.0.2.0/26"));
+        v.insert("public_network_access_enabled".to_string(), json!(false));
+        assert_eq!(validate("azure-pl-sts", &v).status(), "warn");
+    }
+
+    #[test]
+    fn gcp_psc_attachments_must_match_region() {
+        assert!(validate("gcp-sra", &HashMap::new()).entries.is_empty());
+
+        let v = values(&[
+            ("use_psc", json!(true)),
+            ("google_region", json!("us-central1")),
+            ("workspace_service_attachment", json!("projects/prod/regions/us-central1/serviceAttachments/plproxy")),
+            ("relay_service_attachment", json!("projects/prod/regions/us-east1/serviceAttachments/ngrok")),
+        ]);
+        assert_eq!(validate("gcp-sra", &v).failed(), vec!["Relay service attachment"]);
+        assert_eq!(attachment_region("projects/p/regions/europe-west1/serviceAttachments/x"), Some("europe-west1"));
+        assert_eq!(attachment_region("not-a-uri"), None);
+    }
+
+    #[test]
+    fn endpoint_services_read_from_template_defaults() {
+        let tf = r#"
+variable "scc_relay_config" {
+  default = {
+    "us-east-1" = {
+      primary_endpoint = "com.amazonaws.vpce.us-east-1.vpce-svc-relay"
+    }
+  }
+}
+
+variable "workspace_config" {
+  default = {
+    "us-east-1" = {
+      primary_endpoint = "com.amazonaws.vpce.us-east-1.vpce-svc-rest"
+    }
+    "us-gov-west-1" = {
+      primary_endpoint   = "com.amazonaws.vpce.us-gov-west-1.vpce-svc-civ"
+      secondary_endpoint = "com.amazonaws.vpce.us-gov-west-1.vpce-svc-dod"
+    }
+  }
+}
+"#;
+        assert_eq!(
+            endpoint_service_from_tf(tf, "workspace_config", "us-east-1", false).as_deref(),
+            Some("com.amazonaws.vpce.us-east-1.vpce-svc-rest")
+        );
+        assert_eq!(
+            endpoint_service_from_tf(tf, "scc_relay_config", "us-east-1", false).as_deref(),
+            Some("com.amazonaws.vpce.us-east-1.vpce-svc-relay")
+        );
+        assert_eq!(
+            endpoint_service_from_tf(tf, "workspace_config", "us-gov-west-1", true).as_deref(),
+            Some("com.amazonaws.vpce.us-gov-west-1.vpce-svc-dod")
+        );
+        assert_eq!(endpoint_service_from_tf(tf, "workspace_config", "eu-west-1", false), None);
+    }
+
+    #[test]
+    fn bundled_aws_sra_lists_endpoint_services() {
+        let tf = include_str!("../../templates/aws-sra/variables.tf");
+        for variable in ["workspace_config", "scc_relay_config"] {
+            let service = endpoint_service_from_tf(tf, variable, "us-west-2", false).unwrap();
+            assert!(service.starts_with("com.amazonaws.vpce.us-west-2.vpce-svc-"), "{}", service);
+        }
+    }
+}
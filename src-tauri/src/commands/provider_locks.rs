@@ -0,0 +1,181 @@
+//! Terraform dependency lock files (`.terraform.lock.hcl`).
+//!
+//! `terraform init` only records provider checksums for the platform it runs
+//! on, so a repository pushed from a Mac fails `init` in a Linux CI runner.
+//! [`update_provider_locks`] runs `terraform providers lock` for every
+//! platform in [`DEFAULT_PLATFORMS`] (or the ones asked for) and reports the
+//! locked providers. The lock file is committed with the rest of the
+//! deployment's Terraform sources.
+
+use super::{debug_log, get_deployments_dir, sanitize_deployment_name};
+use crate::{hcl_reader, terraform};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Terraform's dependency lock file.
+pub(crate) const LOCK_FILE: &str = ".terraform.lock.hcl";
+
+/// Platforms locked by default: developer machines and common CI runners.
+const DEFAULT_PLATFORMS: &[&str] = &["darwin_amd64", "darwin_arm64", "linux_amd64", "linux_arm64", "windows_amd64"];
+
+const KNOWN_OS: &[&str] = &["darwin", "linux", "windows", "freebsd", "openbsd", "solaris"];
+const KNOWN_ARCH: &[&str] = &["amd64", "arm64", "386", "arm"];
+
+/// A provider recorded in the lock file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockedProvider {
+    /// Registry address, e.g. `registry.terraform.io/databricks/databricks`.
+    pub source: String,
+    pub version: Option<String>,
+    pub constraints: Option<String>,
+    /// Checksums recorded across all platforms.
+    pub hashes: usize,
+}
+
+/// Result of [`update_provider_locks`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderLocks {
+    pub platforms: Vec<String>,
+    pub providers: Vec<LockedProvider>,
+    /// Terraform's output.
+    pub output: String,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Validate `os_arch` platform names, defaulting to [`DEFAULT_PLATFORMS`].
+fn resolve_platforms(platforms: Option<Vec<String>>) -> Result<Vec<String>, String> {
+    let platforms = match platforms {
+        Some(p) if !p.is_empty() => p,
+        _ => return Ok(DEFAULT_PLATFORMS.iter().map(|p| p.to_string()).collect()),
+    };
+    let mut resolved: Vec<String> = Vec::new();
+    for platform in platforms {
+        let platform = platform.trim().to_ascii_lowercase();
+        let valid = platform
+            .split_once('_')
+            .is_some_and(|(os, arch)| KNOWN_OS.contains(&os) && KNOWN_ARCH.contains(&arch));
+        if !valid {
+            return Err(format!("Unknown platform: {} (expected os_arch, e.g. linux_amd64)", platform));
+        }
+        if !resolved.contains(&platform) {
+            resolved.push(platform);
+        }
+    }
+    Ok(resolved)
+}
+
+/// Providers recorded in a lock file's contents.
+fn parse_lock_file(content: &str) -> Result<Vec<LockedProvider>, String> {
+    let body = hcl_reader::parse(content)?;
+    Ok(body
+        .blocks("provider")
+        .filter_map(|block| {
+            let source = block.labels.first()?.clone();
+            let string = |name: &str| block.body.attribute(name).map(|e| e.string_or_text().to_string());
+            let hashes = block
+                .body
+                .attribute("hashes")
+                .map(|e| e.text.matches("\"h1:").count() + e.text.matches("\"zh:").count())
+                .unwrap_or(0);
+            Some(LockedProvider { source, version: string("version"), constraints: string("constraints"), hashes })
+        })
+        .collect())
+}
+
+pub(super) fn read_locked_providers(deployment_dir: &Path) -> Vec<LockedProvider> {
+    let Ok(content) = fs::read_to_string(deployment_dir.join(LOCK_FILE)) else {
+        return Vec::new();
+    };
+    parse_lock_file(&content).unwrap_or_else(|_e| {
+        debug_log!("[provider_locks] Failed to parse {}: {}", LOCK_FILE, _e);
+        Vec::new()
+    })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Record provider checksums for several platforms in the deployment's lock
+/// file, so the pushed repository initializes on other operating systems.
+/// `platforms` are `os_arch` names and default to macOS, Linux and Windows.
+#[tauri::command]
+pub async fn update_provider_locks(
+    app: AppHandle,
+    deployment_name: String,
+    platforms: Option<Vec<String>>,
+) -> Result<ProviderLocks, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    if !deployment_dir.is_dir() {
+        return Err("Deployment not found".to_string());
+    }
+    let platforms = resolve_platforms(platforms)?;
+    let lock = super::deployment_lock::acquire(&app, &safe_name, "providers-lock")?;
+
+    // Registry mirrors and the plugin cache from the run settings apply here too
+    let (_, env) = super::run_settings::run_overrides(&deployment_dir, "providers");
+    let env_vars: HashMap<String, String> = env.into_iter().collect();
+
+    super::run_blocking(move || {
+        let _lock = lock;
+        let output = terraform::run_providers_lock(&platforms, &deployment_dir, &env_vars)
+            .map_err(|e| format!("terraform providers lock failed: {}", e.trim()))?;
+        debug_log!("[provider_locks] Locked {} platform(s) in {:?}", platforms.len(), deployment_dir);
+        Ok(ProviderLocks { providers: read_locked_providers(&deployment_dir), platforms, output })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platforms_default_and_validate() {
+        assert_eq!(resolve_platforms(None).unwrap().len(), DEFAULT_PLATFORMS.len());
+        assert_eq!(resolve_platforms(Some(vec![])).unwrap().len(), DEFAULT_PLATFORMS.len());
+        assert_eq!(
+            resolve_platforms(Some(vec!["Linux_amd64".into(), "linux_amd64".into(), "darwin_arm64".into()])).unwrap(),
+            vec!["linux_amd64", "darwin_arm64"]
+        );
+        assert!(resolve_platforms(Some(vec!["linux".into()])).is_err());
+        assert!(resolve_platforms(Some(vec!["linux_amd64 -fs-mirror=/tmp".into()])).is_err());
+    }
+
+    #[test]
+    fn lock_file_providers_are_parsed() {
+        let content = r#"# This file is maintained automatically by "terraform init".
+# Manual edits may be lost in future updates.
+
+provider "registry.terraform.io/databricks/databricks" {
+  version     = "1.58.0"
+  constraints = ">= 1.50.0"
+  hashes = [
+    "h1:abc=",
+    "h1:def=",
+    "zh:0123",
+  ]
+}
+
+provider "registry.terraform.io/hashicorp/aws" {
+  version = "5.80.0"
+  hashes = [
+    "h1:xyz=",
+  ]
+}
+"#;
+        let providers = parse_lock_file(content).unwrap();
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].source, "registry.terraform.io/databricks/databricks");
+        assert_eq!(providers[0].version.as_deref(), Some("1.58.0"));
+        assert_eq!(providers[0].constraints.as_deref(), Some(">= 1.50.0"));
+        assert_eq!(providers[0].hashes, 3);
+        assert_eq!((providers[1].constraints.clone(), providers[1].hashes), (None, 1));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_locked_providers(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+//! Pre-deployment cloud quota checks.
+//!
+//! Each template needs a handful of quota-limited resources (VPCs, Elastic
+//! IPs, NAT gateways, VNets, public IPs, networks, routers, vCPUs). These
+//! checks compare the template's requirements against the remaining quota in
+//! the target region so an exhausted limit is reported before `terraform
+//! apply` fails halfway through.
+
+use super::aws::apply_aws_credentials;
+use super::azure::{arm_list, arm_token_sp};
+use super::gcp::get_gcp_oauth_token;
+use super::{http_client, CloudCredentials};
+use crate::dependencies;
+use crate::endpoints::https_url;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Result of checking one quota.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaCheck {
+    pub name: String,
+    pub limit: Option<f64>,
+    pub used: Option<f64>,
+    pub required: f64,
+    /// False only when the quota was read and the remaining amount is too low.
+    pub sufficient: bool,
+    pub message: String,
+}
+
+/// Quota checks for a template in its target region.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaReport {
+    pub cloud: String,
+    pub region: String,
+    pub checks: Vec<QuotaCheck>,
+    pub all_sufficient: bool,
+}
+
+/// A quota-limited resource a template creates.
+#[derive(Debug, Clone, PartialEq)]
+struct QuotaRequirement {
+    key: &'static str,
+    label: &'static str,
+    required: f64,
+}
+
+/// vCPUs needed to start a small cluster (driver + one worker, 4 vCPUs each).
+const CLUSTER_VCPUS: f64 = 8.0;
+
+// ─── Requirements ───────────────────────────────────────────────────────────
+
+pub(super) fn value_bool(values: &HashMap<String, serde_json::Value>, key: &str, default: bool) -> bool {
+    match values.get(key) {
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::String(s)) => s == "true",
+        _ => default,
+    }
+}
+
+pub(super) fn value_str<'a>(values: &'a HashMap<String, serde_json::Value>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|k| values.get(*k).and_then(|v| v.as_str()))
+        .find(|s| !s.is_empty())
+}
+
+fn req(key: &'static str, label: &'static str, required: f64) -> QuotaRequirement {
+    QuotaRequirement { key, label, required }
+}
+
+/// Quota-limited resources a template creates with the given variable values.
+fn template_requirements(template_id: &str, values: &HashMap<String, serde_json::Value>) -> Vec<QuotaRequirement> {
+    let mut reqs = Vec::new();
+    match template_id {
+        "aws-simple" if value_bool(values, "create_new_vpc", true) => {
+            reqs.push(req("vpc", "VPCs", 1.0));
+            reqs.push(req("eip", "Elastic IPs", 1.0));
+            reqs.push(req("nat_gateway", "NAT gateways", 1.0));
+        }
+        "aws-sra" if value_str(values, &["network_configuration"]) != Some("custom") => {
+            reqs.push(req("vpc", "VPCs", 1.0));
+        }
+        "azure-simple" => {
+            if value_bool(values, "create_new_vnet", true) {
+                reqs.push(req("VirtualNetworks", "Virtual networks", 1.0));
+                reqs.push(req("PublicIPAddresses", "Public IP addresses", 1.0));
+            }
+            reqs.push(req("cores", "Regional vCPUs", CLUSTER_VCPUS));
+        }
+        "azure-pl-sts" => {
+            reqs.push(req("VirtualNetworks", "Virtual networks", 1.0));
+            reqs.push(req("PublicIPAddresses", "Public IP addresses", 1.0));
+            reqs.push(req("cores", "Regional vCPUs", CLUSTER_VCPUS));
+        }
+        "azure-sra" => {
+            let hub = value_bool(values, "create_hub", true);
+            let spoke = value_bool(values, "create_workspace_vnet", true);
+            let vnets = hub as u8 + spoke as u8;
+            if vnets > 0 {
+                reqs.push(req("VirtualNetworks", "Virtual networks", vnets as f64));
+            }
+            if hub {
+                reqs.push(req("PublicIPAddresses", "Public IP addresses", 1.0));
+            }
+            reqs.push(req("cores", "Regional vCPUs", CLUSTER_VCPUS));
+        }
+        "gcp-simple" => {
+            reqs.push(req("NETWORKS", "VPC networks", 1.0));
+            reqs.push(req("ROUTERS", "Cloud Routers", 1.0));
+            reqs.push(req("CPUS", "Regional vCPUs", CLUSTER_VCPUS));
+        }
+        "gcp-sra" => {
+            if !value_bool(values, "use_existing_vpc", false) {
+                reqs.push(req("NETWORKS", "VPC networks", 1.0));
+                reqs.push(req("ROUTERS", "Cloud Routers", 1.0));
+            }
+            reqs.push(req("CPUS", "Regional vCPUs", CLUSTER_VCPUS));
+        }
+        _ => {}
+    }
+    reqs
+}
+
+fn evaluate(requirement: &QuotaRequirement, usage: Option<(f64, f64)>) -> QuotaCheck {
+    let Some((used, limit)) = usage else {
+        return QuotaCheck {
+            name: requirement.label.to_string(),
+            limit: None,
+            used: None,
+            required: requirement.required,
+            sufficient: true,
+            message: format!("{} quota could not be checked", requirement.label),
+        };
+    };
+
+    let remaining = (limit - used).max(0.0);
+    let sufficient = remaining >= requirement.required;
+    let message = if sufficient {
+        format!("{}: {} of {} used, {} needed", requirement.label, used, limit, requirement.required)
+    } else {
+        format!(
+            "{}: only {} of {} remaining but {} needed. Request a quota increase or free up resources before deploying.",
+            requirement.label, remaining, limit, requirement.required
+        )
+    };
+    QuotaCheck {
+        name: requirement.label.to_string(),
+        limit: Some(limit),
+        used: Some(used),
+        required: requirement.required,
+        sufficient,
+        message,
+    }
+}
+
+// ─── Parsing ────────────────────────────────────────────────────────────────
+
+/// `az network list-usages` / `az vm list-usage` output → name → (used, limit).
+fn parse_azure_usages(json: &serde_json::Value) -> HashMap<String, (f64, f64)> {
+    json.as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|u| {
+                    let name = u["name"]["value"].as_str()?;
+                    let used = number(&u["currentValue"])?;
+                    let limit = number(&u["limit"])?;
+                    Some((name.to_string(), (used, limit)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Compute API region / project resource → metric → (used, limit).
+fn parse_gcp_quotas(json: &serde_json::Value) -> HashMap<String, (f64, f64)> {
+    json["quotas"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|q| {
+                    let metric = q["metric"].as_str()?;
+                    Some((metric.to_string(), (number(&q["usage"])?, number(&q["limit"])?)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Azure CLI reports usage numbers as strings; accept both forms.
+fn number(value: &serde_json::Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str()?.parse().ok())
+}
+
+// ─── Queries ────────────────────────────────────────────────────────────────
+
+fn run_json(mut cmd: std::process::Command) -> Option<serde_json::Value> {
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// AWS Service Quotas codes: (service, quota code, default limit, usage query, usage array key).
+fn aws_quota_spec(key: &str) -> Option<(&'static str, &'static str, f64, &'static [&'static str], &'static str)> {
+    match key {
+        "vpc" => Some(("vpc", "L-F678F1CE", 5.0, &["describe-vpcs"], "Vpcs")),
+        "eip" => Some(("ec2", "L-0263D0A3", 5.0, &["describe-addresses"], "Addresses")),
+        // The NAT gateway limit is per AZ; comparing the regional count is conservative
+        "nat_gateway" => Some((
+            "vpc",
+            "L-FE5A380F",
+            5.0,
+            &["describe-nat-gateways", "--filter", "Name=state,Values=available,pending"],
+            "NatGateways",
+        )),
+        _ => None,
+    }
+}
+
+fn aws_usage(credentials: &CloudCredentials, region: &str, key: &str) -> Option<(f64, f64)> {
+    let aws_cli = dependencies::find_aws_cli_path()?;
+    let (service, code, default_limit, usage_args, usage_key) = aws_quota_spec(key)?;
+
+    let mut quota_cmd = super::silent_cmd(&aws_cli);
+    quota_cmd.args([
+        "service-quotas", "get-service-quota", "--service-code", service, "--quota-code", code,
+        "--region", region, "--output", "json",
+    ]);
+    apply_aws_credentials(&mut quota_cmd, credentials).ok()?;
+    // Without servicequotas:GetServiceQuota, fall back to the AWS default limit
+    let limit = run_json(quota_cmd)
+        .and_then(|j| j["Quota"]["Value"].as_f64())
+        .unwrap_or(default_limit);
+
+    let mut usage_cmd = super::silent_cmd(&aws_cli);
+    usage_cmd
+        .arg("ec2")
+        .args(usage_args)
+        .args(["--region", region, "--output", "json"]);
+    apply_aws_credentials(&mut usage_cmd, credentials).ok()?;
+    let used = run_json(usage_cmd)?[usage_key].as_array()?.len() as f64;
+
+    Some((used, limit))
+}
+
+fn azure_usages(credentials: &CloudCredentials, location: &str) -> HashMap<String, (f64, f64)> {
+    let Some(az) = dependencies::find_azure_cli_path() else {
+        return HashMap::new();
+    };
+    let mut usages = HashMap::new();
+    for args in [["network", "list-usages"], ["vm", "list-usage"]] {
+        let mut cmd = super::silent_cmd(&az);
+        cmd.args(args).args(["--location", location, "--output", "json"]);
+        if let Some(sub) = credentials.azure_subscription_id.as_deref().filter(|s| !s.is_empty()) {
+            cmd.args(["--subscription", sub]);
+        }
+        if let Some(json) = run_json(cmd) {
+            usages.extend(parse_azure_usages(&json));
+        }
+    }
+    usages
+}
+
+/// The same usages as [`azure_usages`], read from the ARM REST API as the
+/// Service Principal in `credentials`.
+async fn azure_usages_sp(credentials: &CloudCredentials, location: &str) -> HashMap<String, (f64, f64)> {
+    let Some(subscription_id) = credentials.azure_subscription_id.as_deref().filter(|s| !s.is_empty()) else {
+        return HashMap::new();
+    };
+    let Ok(client) = http_client() else {
+        return HashMap::new();
+    };
+    let Ok(token) = arm_token_sp(&client, credentials).await else {
+        return HashMap::new();
+    };
+    let mut usages = HashMap::new();
+    for (provider, api_version) in [("Microsoft.Network", "2023-09-01"), ("Microsoft.Compute", "2023-07-01")] {
+        let url = https_url(
+            "management.azure.com",
+            &format!(
+                "/subscriptions/{}/providers/{}/locations/{}/usages?api-version={}",
+                subscription_id, provider, location, api_version
+            ),
+        );
+        if let Ok(items) = arm_list(&client, &token, url, "usages").await {
+            usages.extend(parse_azure_usages(&serde_json::Value::Array(items)));
+        }
+    }
+    usages
+}
+
+/// Project and region quotas from the Compute API, authenticated the same
+/// way as the permission check: token, service account key, then gcloud.
+async fn gcp_quotas(credentials: &CloudCredentials, project: &str, region: &str) -> HashMap<String, (f64, f64)> {
+    let Ok(client) = http_client() else {
+        return HashMap::new();
+    };
+    let Ok((token, _)) = get_gcp_oauth_token(credentials).await else {
+        return HashMap::new();
+    };
+    let mut quotas = HashMap::new();
+    let project_path = format!("/compute/v1/projects/{}", project);
+    // Regional CPUS overrides any project-level metric of the same name
+    for path in [project_path.clone(), format!("{}/regions/{}", project_path, region)] {
+        let response = client
+            .get(https_url("compute.googleapis.com", &path))
+            .bearer_auth(&token)
+            .send()
+            .await;
+        let Ok(response) = response else { continue };
+        if !response.status().is_success() {
+            continue;
+        }
+        if let Ok(json) = response.json::<serde_json::Value>().await {
+            quotas.extend(parse_gcp_quotas(&json));
+        }
+    }
+    quotas
+}
+
+fn template_cloud(template_id: &str) -> Result<&'static str, String> {
+    match template_id.split('-').next() {
+        Some("aws") => Ok("aws"),
+        Some("azure") => Ok("azure"),
+        Some("gcp") => Ok("gcp"),
+        _ => Err(format!("Unknown cloud for template: {}", template_id)),
+    }
+}
+
+/// Region the template deploys to.
+fn quota_region(
+    cloud: &str,
+    values: &HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+) -> Result<String, String> {
+    match cloud {
+        "aws" => value_str(values, &["region"])
+            .map(String::from)
+            .or_else(|| credentials.aws_region.clone().filter(|s| !s.is_empty()))
+            .ok_or_else(|| "AWS region is required".to_string()),
+        "azure" => value_str(values, &["location"])
+            .map(String::from)
+            .ok_or_else(|| "Azure location is required".to_string()),
+        _ => value_str(values, &["google_region"])
+            .map(String::from)
+            .ok_or_else(|| "GCP region is required".to_string()),
+    }
+}
+
+async fn check_quotas(
+    template_id: &str,
+    values: &HashMap<String, serde_json::Value>,
+    credentials: &CloudCredentials,
+) -> Result<QuotaReport, String> {
+    let cloud = template_cloud(template_id)?;
+    let region = quota_region(cloud, values, credentials)?;
+    let requirements = template_requirements(template_id, values);
+
+    let usages: HashMap<String, (f64, f64)> = match cloud {
+        "aws" => {
+            let keys: Vec<&'static str> = requirements.iter().map(|r| r.key).collect();
+            let (credentials, region) = (credentials.clone(), region.clone());
+            super::run_blocking(move || {
+                Ok(keys
+                    .into_iter()
+                    .filter_map(|key| Some((key.to_string(), aws_usage(&credentials, &region, key)?)))
+                    .collect())
+            })
+            .await?
+        }
+        "azure" if credentials.azure_client_secret.as_ref().is_some_and(|s| !s.is_empty()) => {
+            azure_usages_sp(credentials, &region).await
+        }
+        "azure" => {
+            let (credentials, region) = (credentials.clone(), region.clone());
+            super::run_blocking(move || Ok(azure_usages(&credentials, &region))).await?
+        }
+        _ => {
+            let project = credentials
+                .gcp_project_id
+                .clone()
+                .filter(|s| !s.is_empty())
+                .or_else(|| value_str(values, &["google_project_name", "google_project"]).map(String::from))
+                .ok_or_else(|| "GCP project is required".to_string())?;
+            gcp_quotas(credentials, &project, &region).await
+        }
+    };
+
+    let checks: Vec<QuotaCheck> = requirements
+        .iter()
+        .map(|r| evaluate(r, usages.get(r.key).copied()))
+        .collect();
+    Ok(QuotaReport {
+        cloud: cloud.to_string(),
+        region,
+        all_sufficient: checks.iter().all(|c| c.sufficient),
+        checks,
+    })
+}
+
+/// Check the target region's remaining quota against what a template will
+/// create. Service Principal and service account credentials are used when
+/// set; otherwise the signed-in CLI user is.
+#[tauri::command]
+pub async fn check_cloud_quotas(
+    template_id: String,
+    values: HashMap<String, serde_json::Value>,
+    credentials: CloudCredentials,
+) -> Result<QuotaReport, String> {
+    let template_id = super::sanitize_template_id(&template_id)?;
+    check_quotas(&template_id, &values, &credentials).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, serde_json::Value)]) -> HashMap<String, serde_json::Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn aws_simple_requirements_follow_vpc_choice() {
+        let keys: Vec<&str> = template_requirements("aws-simple", &HashMap::new()).iter().map(|r| r.key).collect();
+        assert_eq!(keys, vec!["vpc", "eip", "nat_gateway"]);
+        assert!(template_requirements("aws-simple", &values(&[("create_new_vpc", json!(false))])).is_empty());
+    }
+
+    #[test]
+    fn azure_sra_counts_hub_and_spoke() {
+        let reqs = template_requirements("azure-sra", &HashMap::new());
+        assert_eq!(reqs[0], req("VirtualNetworks", "Virtual networks", 2.0));
+        let reqs = template_requirements("azure-sra", &values(&[("create_hub", json!("false"))]));
+        assert_eq!(reqs[0].required, 1.0);
+        assert!(reqs.iter().all(|r| r.key != "PublicIPAddresses"));
+    }
+
+    #[test]
+    fn evaluate_flags_insufficient_quota() {
+        let check = evaluate(&req("vpc", "VPCs", 1.0), Some((5.0, 5.0)));
+        assert!(!check.sufficient);
+        assert!(check.message.contains("only 0 of 5 remaining"));
+
+        assert!(evaluate(&req("vpc", "VPCs", 1.0), Some((3.0, 5.0))).sufficient);
+
+        let unknown = evaluate(&req("vpc", "VPCs", 1.0), None);
+        assert!(unknown.sufficient);
+        assert!(unknown.limit.is_none());
+    }
+
+    #[test]
+    fn parse_azure_usages_string_numbers() {
+        let json = json!([
+            {"name": {"value": "VirtualNetworks", "localizedValue": "Virtual Networks"}, "currentValue": "49", "limit": "50"},
+            {"name": {"value": "cores"}, "currentValue": 10, "limit": 100}
+        ]);
+        let usages = parse_azure_usages(&json);
+        assert_eq!(usages["VirtualNetworks"], (49.0, 50.0));
+        assert_eq!(usages["cores"], (10.0, 100.0));
+    }
+
+    #[test]
+    fn parse_gcp_region_quotas() {
+        let json = json!({"quotas": [{"metric": "CPUS", "limit": 24.0, "usage": 20.0}, {"metric": "ROUTERS", "limit": 10, "usage": 0}]});
+        let quotas = parse_gcp_quotas(&json);
+        assert_eq!(quotas["CPUS"], (20.0, 24.0));
+        assert_eq!(quotas["ROUTERS"], (0.0, 10.0));
+    }
+
+    #[test]
+    fn template_cloud_from_id() {
+        assert_eq!(template_cloud("gcp-sra").unwrap(), "gcp");
+        assert!(template_cloud("custom").is_err());
+    }
+}
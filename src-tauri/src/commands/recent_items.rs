@@ -0,0 +1,226 @@
+//! Recently used and pinned deployments and templates, for the command palette.
+//!
+//! Saving a configuration records its template and deployment, and every
+//! Terraform run records the deployment together with the command it ran, so
+//! the palette can offer "re-run plan on prod-workspace". Entries live in
+//! `recent-items.json` in the data directory. Pinned entries are kept until
+//! unpinned; other entries are trimmed to the [`MAX_RECENT`] most recent per
+//! kind. Entries for deployments or templates that no longer exist are hidden.
+
+use super::{debug_log, lock_or_recover, sanitize_deployment_name, sanitize_template_id};
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const ITEMS_FILE: &str = "recent-items.json";
+
+/// Unpinned entries kept per kind.
+const MAX_RECENT: usize = 20;
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write cycles of the items file.
+    static ref ITEMS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Deployment,
+    Template,
+}
+
+/// A deployment or template the user worked with or pinned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentItem {
+    pub kind: ItemKind,
+    /// Deployment name or template ID.
+    pub id: String,
+    /// Last Terraform command run on a deployment (`plan`, `apply`, ...).
+    pub last_action: Option<String>,
+    /// Unix timestamp (seconds) of the last use; 0 if only ever pinned.
+    pub last_used: u64,
+    pub pinned: bool,
+}
+
+// ─── Storage ────────────────────────────────────────────────────────────────
+
+fn load(path: &Path) -> Vec<RecentItem> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, items: &[RecentItem]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write recent items: {}", e))
+}
+
+/// Pinned entries first, then most recently used.
+fn sort(items: &mut [RecentItem]) {
+    items.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.last_used.cmp(&a.last_used)));
+}
+
+/// Drop the oldest unpinned entries beyond [`MAX_RECENT`] of each kind.
+fn trim(items: &mut Vec<RecentItem>) {
+    sort(items);
+    let mut seen = [0usize; 2];
+    items.retain(|item| {
+        if item.pinned {
+            return true;
+        }
+        let count = &mut seen[item.kind as usize];
+        *count += 1;
+        *count <= MAX_RECENT
+    });
+}
+
+/// Load the items, let `edit` change them, then trim and save.
+fn update(env: &dyn Environment, edit: impl FnOnce(&mut Vec<RecentItem>)) -> Result<Vec<RecentItem>, String> {
+    let path = env.data_file(ITEMS_FILE)?;
+    let _guard = lock_or_recover(&ITEMS_LOCK);
+    let mut items = load(&path);
+    edit(&mut items);
+    trim(&mut items);
+    save(&path, &items)?;
+    Ok(items)
+}
+
+/// Whether the deployment or template still exists.
+fn exists(env: &dyn Environment, item: &RecentItem) -> bool {
+    let dir = match item.kind {
+        ItemKind::Deployment => env.deployments_dir(),
+        ItemKind::Template => env.templates_dir(),
+    };
+    dir.map(|dir| dir.join(&item.id).is_dir()).unwrap_or(false)
+}
+
+fn sanitize(kind: ItemKind, id: &str) -> Result<String, String> {
+    match kind {
+        ItemKind::Deployment => sanitize_deployment_name(id),
+        ItemKind::Template => sanitize_template_id(id),
+    }
+}
+
+/// Mark an item as just used. `action` is the Terraform command for deployments.
+pub(crate) fn record(env: &dyn Environment, kind: ItemKind, id: &str, action: Option<&str>) -> Result<(), String> {
+    let id = sanitize(kind, id)?;
+    let now = super::audit::now_secs();
+    update(env, |items| match items.iter_mut().find(|i| i.kind == kind && i.id == id) {
+        Some(item) => {
+            item.last_used = now;
+            if action.is_some() {
+                item.last_action = action.map(str::to_string);
+            }
+        }
+        None => items.push(RecentItem {
+            kind,
+            id,
+            last_action: action.map(str::to_string),
+            last_used: now,
+            pinned: false,
+        }),
+    })
+    .map(|_| ())
+}
+
+/// [`record`], logging instead of failing: the palette history must never
+/// fail the save or run that triggered it.
+pub(crate) fn record_quietly(env: &dyn Environment, kind: ItemKind, id: &str, action: Option<&str>) {
+    if let Err(_e) = record(env, kind, id, action) {
+        debug_log!("[recent_items] Failed to record {}: {}", id, _e);
+    }
+}
+
+fn list(env: &dyn Environment, kind: Option<ItemKind>, limit: Option<usize>) -> Result<Vec<RecentItem>, String> {
+    let path = env.data_file(ITEMS_FILE)?;
+    let mut items = load(&path);
+    items.retain(|item| kind.is_none_or(|k| item.kind == k) && exists(env, item));
+    sort(&mut items);
+    items.truncate(limit.unwrap_or(usize::MAX));
+    Ok(items)
+}
+
+fn set_pinned(env: &dyn Environment, kind: ItemKind, id: &str, pinned: bool) -> Result<Vec<RecentItem>, String> {
+    let id = sanitize(kind, id)?;
+    let item = RecentItem { kind, id, last_action: None, last_used: 0, pinned };
+    if pinned && !exists(env, &item) {
+        return Err(format!("'{}' no longer exists", item.id));
+    }
+    update(env, |items| match items.iter_mut().find(|i| i.kind == kind && i.id == item.id) {
+        Some(existing) => existing.pinned = pinned,
+        None if pinned => items.push(item),
+        None => {}
+    })?;
+    list(env, None, None)
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Recently used and pinned items, pinned first. `kind` narrows the list to
+/// deployments or templates.
+#[tauri::command]
+pub fn get_recent_items(app: AppHandle, kind: Option<ItemKind>, limit: Option<usize>) -> Result<Vec<RecentItem>, String> {
+    list(&app, kind, limit)
+}
+
+/// Pin or unpin a deployment or template. Returns the updated list.
+#[tauri::command]
+pub fn pin_item(app: AppHandle, kind: ItemKind, id: String, pinned: bool) -> Result<Vec<RecentItem>, String> {
+    set_pinned(&app, kind, &id, pinned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+
+    fn paths_with(deployments: &[&str], templates: &[&str]) -> (tempfile::TempDir, StoragePaths) {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        for name in deployments {
+            fs::create_dir_all(paths.deployments_dir().unwrap().join(name)).unwrap();
+        }
+        for id in templates {
+            fs::create_dir_all(paths.templates_dir().unwrap().join(id)).unwrap();
+        }
+        (dir, paths)
+    }
+
+    #[test]
+    fn records_last_action_and_hides_removed_items() {
+        let (_dir, paths) = paths_with(&["prod", "dev"], &["aws-simple"]);
+        record(&paths, ItemKind::Template, "aws-simple", None).unwrap();
+        record(&paths, ItemKind::Deployment, "prod", Some("plan")).unwrap();
+        record(&paths, ItemKind::Deployment, "gone", Some("apply")).unwrap();
+        record(&paths, ItemKind::Deployment, "prod", None).unwrap();
+
+        let deployments = list(&paths, Some(ItemKind::Deployment), None).unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].id, "prod");
+        assert_eq!(deployments[0].last_action.as_deref(), Some("plan"));
+        assert_eq!(list(&paths, None, None).unwrap().len(), 2);
+        assert_eq!(list(&paths, None, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn pinned_items_come_first_and_survive_trimming() {
+        let (_dir, paths) = paths_with(&["prod"], &[]);
+        set_pinned(&paths, ItemKind::Deployment, "prod", true).unwrap();
+        for n in 0..MAX_RECENT + 5 {
+            record(&paths, ItemKind::Deployment, &format!("dep-{}", n), Some("plan")).unwrap();
+        }
+
+        let stored = load(&paths.data_file(ITEMS_FILE).unwrap());
+        assert_eq!(stored.len(), MAX_RECENT + 1);
+        assert!(stored[0].pinned && stored[0].id == "prod");
+
+        let items = set_pinned(&paths, ItemKind::Deployment, "prod", false).unwrap();
+        assert!(items.iter().all(|i| !i.pinned));
+        assert!(set_pinned(&paths, ItemKind::Deployment, "missing", true).is_err());
+        assert!(set_pinned(&paths, ItemKind::Template, "../etc", true).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Region name normalization per cloud.
+//!
+//! Region names reach the app in several spellings: Azure display names
+//! (`East US 2`), availability zones (`us-east-1a`, `us-east1-b`), and
+//! whatever the Databricks API echoes back. Stripping every hyphen made AWS
+//! `us-east-1` and GCP `us-east1` look the same, so [`canonical_region`]
+//! instead resolves a name against the cloud's own region catalog and returns
+//! that cloud's region ID. Names the catalog doesn't know yet keep the
+//! cloud's ID syntax, with any zone suffix removed.
+
+use super::regions::regions_for;
+
+/// Lowercase letters and digits only, for loose comparison within one cloud.
+fn compact(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// `name` in the cloud's ID syntax: hyphen-separated for AWS and GCP, run
+/// together for Azure. AWS (`us-east-1a`) and GCP (`us-east1-b`) zones are
+/// reduced to their region.
+fn syntactic_id(cloud: &str, name: &str) -> String {
+    let lowered = name.trim().to_lowercase();
+    match cloud {
+        "azure" => compact(&lowered),
+        "aws" | "gcp" => {
+            let mut id = lowered
+                .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("-");
+            let bytes = id.as_bytes();
+            let zone_suffix = match (cloud, bytes) {
+                ("aws", [.., digit, zone]) if digit.is_ascii_digit() && zone.is_ascii_lowercase() => 1,
+                ("gcp", [.., digit, b'-', zone]) if digit.is_ascii_digit() && zone.is_ascii_lowercase() => 2,
+                _ => 0,
+            };
+            id.truncate(id.len() - zone_suffix);
+            id
+        }
+        _ => lowered.chars().filter(|c| !c.is_whitespace()).collect(),
+    }
+}
+
+/// The region ID `region` refers to on `cloud`: a catalog ID when the name
+/// is one or matches a display name, otherwise the name in the cloud's ID
+/// syntax. Hyphens are never dropped for AWS or GCP, so `useast-1` is not
+/// taken for `us-east-1`.
+pub(crate) fn canonical_region(cloud: &str, region: &str) -> String {
+    let id = syntactic_id(cloud, region);
+    let Some(catalog) = regions_for(cloud) else {
+        return id;
+    };
+    if catalog.iter().any(|r| r.id == id) {
+        return id;
+    }
+    let display_key = compact(region);
+    catalog
+        .iter()
+        .find(|r| compact(r.display_name) == display_key)
+        .map(|r| r.id.to_string())
+        .unwrap_or(id)
+}
+
+/// Whether two names refer to the same region on `cloud`. Empty names match nothing.
+pub(crate) fn same_region(cloud: &str, a: &str, b: &str) -> bool {
+    !a.trim().is_empty() && !b.trim().is_empty() && canonical_region(cloud, a) == canonical_region(cloud, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aws_names_resolve_to_region_ids() {
+        assert_eq!(canonical_region("aws", "US-East-1"), "us-east-1");
+        assert_eq!(canonical_region("aws", "us_west_2"), "us-west-2");
+        assert_eq!(canonical_region("aws", "us-east-1a"), "us-east-1");
+        assert_eq!(canonical_region("aws", "US East (N. Virginia)"), "us-east-1");
+        assert_eq!(canonical_region("aws", "me-south-9b"), "me-south-9");
+    }
+
+    #[test]
+    fn azure_display_names_resolve_to_region_ids() {
+        assert_eq!(canonical_region("azure", "East US 2"), "eastus2");
+        assert_eq!(canonical_region("azure", "eastus2"), "eastus2");
+        assert_eq!(canonical_region("azure", "east-us"), "eastus");
+        assert!(same_region("azure", "West Europe", "westeurope"));
+    }
+
+    #[test]
+    fn gcp_zones_and_spellings_resolve_to_region_ids() {
+        assert_eq!(canonical_region("gcp", "us-east1-b"), "us-east1");
+        assert_eq!(canonical_region("gcp", "US-EAST1"), "us-east1");
+        assert_eq!(canonical_region("gcp", "europe-west3-c"), "europe-west3");
+    }
+
+    #[test]
+    fn regions_of_other_clouds_are_not_conflated() {
+        assert_eq!(canonical_region("gcp", "us-east-1"), "us-east-1");
+        assert!(!same_region("aws", "useast-1", "us-east-1"));
+        assert!(!same_region("aws", "us-east-1", "us-east1-b"));
+        assert!(!same_region("gcp", "us-east1", "us-east-1"));
+        assert!(!same_region("aws", "us-east-1", "us-east-2"));
+        assert!(!same_region("aws", "", ""));
+    }
+}
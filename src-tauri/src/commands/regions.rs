@@ -0,0 +1,168 @@
+//! Catalog of cloud regions where Databricks workspaces can be deployed.
+//!
+//! Maintained by hand from the Databricks "supported regions" pages; update
+//! it when Databricks adds regions. Region pickers and variable data sources
+//! filter against it so users can't choose a region the account API rejects.
+
+use serde::Serialize;
+
+/// A region where Databricks is available.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SupportedRegion {
+    pub id: &'static str,
+    pub display_name: &'static str,
+    pub geography: &'static str,
+    /// Accounts console host that manages workspaces in this region.
+    pub accounts_host: &'static str,
+    /// Deployment caveats (separate control plane, limited availability).
+    pub notes: Option<&'static str>,
+}
+
+pub(super) const AWS_ACCOUNTS: &str = "accounts.cloud.databricks.com";
+const AWS_GOV_ACCOUNTS: &str = "accounts.cloud.databricks.us";
+pub(super) const AZURE_ACCOUNTS: &str = "accounts.azuredatabricks.net";
+pub(super) const GCP_ACCOUNTS: &str = "accounts.gcp.databricks.com";
+
+const fn region(
+    id: &'static str,
+    display_name: &'static str,
+    geography: &'static str,
+    accounts_host: &'static str,
+) -> SupportedRegion {
+    SupportedRegion { id, display_name, geography, accounts_host, notes: None }
+}
+
+pub(super) static AWS_REGIONS: &[SupportedRegion] = &[
+    region("us-east-1", "US East (N. Virginia)", "Americas", AWS_ACCOUNTS),
+    region("us-east-2", "US East (Ohio)", "Americas", AWS_ACCOUNTS),
+    region("us-west-1", "US West (N. California)", "Americas", AWS_ACCOUNTS),
+    region("us-west-2", "US West (Oregon)", "Americas", AWS_ACCOUNTS),
+    region("ca-central-1", "Canada (Central)", "Americas", AWS_ACCOUNTS),
+    region("sa-east-1", "South America (São Paulo)", "Americas", AWS_ACCOUNTS),
+    region("eu-central-1", "Europe (Frankfurt)", "Europe", AWS_ACCOUNTS),
+    region("eu-west-1", "Europe (Ireland)", "Europe", AWS_ACCOUNTS),
+    region("eu-west-2", "Europe (London)", "Europe", AWS_ACCOUNTS),
+    region("eu-west-3", "Europe (Paris)", "Europe", AWS_ACCOUNTS),
+    region("ap-south-1", "Asia Pacific (Mumbai)", "Asia Pacific", AWS_ACCOUNTS),
+    region("ap-southeast-1", "Asia Pacific (Singapore)", "Asia Pacific", AWS_ACCOUNTS),
+    region("ap-southeast-2", "Asia Pacific (Sydney)", "Asia Pacific", AWS_ACCOUNTS),
+    region("ap-northeast-1", "Asia Pacific (Tokyo)", "Asia Pacific", AWS_ACCOUNTS),
+    region("ap-northeast-2", "Asia Pacific (Seoul)", "Asia Pacific", AWS_ACCOUNTS),
+    SupportedRegion {
+        id: "us-gov-west-1",
+        display_name: "AWS GovCloud (US-West)",
+        geography: "Americas",
+        accounts_host: AWS_GOV_ACCOUNTS,
+        notes: Some("GovCloud uses a separate Databricks account and control plane (SRA template only)"),
+    },
+];
+
+pub(super) static AZURE_REGIONS: &[SupportedRegion] = &[
+    region("eastus", "East US", "Americas", AZURE_ACCOUNTS),
+    region("eastus2", "East US 2", "Americas", AZURE_ACCOUNTS),
+    region("centralus", "Central US", "Americas", AZURE_ACCOUNTS),
+    region("northcentralus", "North Central US", "Americas", AZURE_ACCOUNTS),
+    region("southcentralus", "South Central US", "Americas", AZURE_ACCOUNTS),
+    region("westus", "West US", "Americas", AZURE_ACCOUNTS),
+    region("westus2", "West US 2", "Americas", AZURE_ACCOUNTS),
+    region("westus3", "West US 3", "Americas", AZURE_ACCOUNTS),
+    region("canadacentral", "Canada Central", "Americas", AZURE_ACCOUNTS),
+    region("canadaeast", "Canada East", "Americas", AZURE_ACCOUNTS),
+    region("brazilsouth", "Brazil South", "Americas", AZURE_ACCOUNTS),
+    region("northeurope", "North Europe", "Europe", AZURE_ACCOUNTS),
+    region("westeurope", "West Europe", "Europe", AZURE_ACCOUNTS),
+    region("uksouth", "UK South", "Europe", AZURE_ACCOUNTS),
+    region("ukwest", "UK West", "Europe", AZURE_ACCOUNTS),
+    region("francecentral", "France Central", "Europe", AZURE_ACCOUNTS),
+    region("germanywestcentral", "Germany West Central", "Europe", AZURE_ACCOUNTS),
+    region("switzerlandnorth", "Switzerland North", "Europe", AZURE_ACCOUNTS),
+    region("norwayeast", "Norway East", "Europe", AZURE_ACCOUNTS),
+    region("swedencentral", "Sweden Central", "Europe", AZURE_ACCOUNTS),
+    region("uaenorth", "UAE North", "Middle East & Africa", AZURE_ACCOUNTS),
+    region("qatarcentral", "Qatar Central", "Middle East & Africa", AZURE_ACCOUNTS),
+    region("southafricanorth", "South Africa North", "Middle East & Africa", AZURE_ACCOUNTS),
+    region("centralindia", "Central India", "Asia Pacific", AZURE_ACCOUNTS),
+    region("southindia", "South India", "Asia Pacific", AZURE_ACCOUNTS),
+    region("eastasia", "East Asia", "Asia Pacific", AZURE_ACCOUNTS),
+    region("southeastasia", "Southeast Asia", "Asia Pacific", AZURE_ACCOUNTS),
+    region("japaneast", "Japan East", "Asia Pacific", AZURE_ACCOUNTS),
+    region("japanwest", "Japan West", "Asia Pacific", AZURE_ACCOUNTS),
+    region("koreacentral", "Korea Central", "Asia Pacific", AZURE_ACCOUNTS),
+    region("australiaeast", "Australia East", "Asia Pacific", AZURE_ACCOUNTS),
+    region("australiasoutheast", "Australia Southeast", "Asia Pacific", AZURE_ACCOUNTS),
+    region("australiacentral", "Australia Central", "Asia Pacific", AZURE_ACCOUNTS),
+];
+
+pub(super) static GCP_REGIONS: &[SupportedRegion] = &[
+    region("us-central1", "Iowa", "Americas", GCP_ACCOUNTS),
+    region("us-east1", "South Carolina", "Americas", GCP_ACCOUNTS),
+    region("us-east4", "Northern Virginia", "Americas", GCP_ACCOUNTS),
+    region("us-west1", "Oregon", "Americas", GCP_ACCOUNTS),
+    region("us-west4", "Las Vegas", "Americas", GCP_ACCOUNTS),
+    region("northamerica-northeast1", "Montréal", "Americas", GCP_ACCOUNTS),
+    region("southamerica-east1", "São Paulo", "Americas", GCP_ACCOUNTS),
+    region("europe-west1", "Belgium", "Europe", GCP_ACCOUNTS),
+    region("europe-west2", "London", "Europe", GCP_ACCOUNTS),
+    region("europe-west3", "Frankfurt", "Europe", GCP_ACCOUNTS),
+    region("asia-northeast1", "Tokyo", "Asia Pacific", GCP_ACCOUNTS),
+    region("asia-south1", "Mumbai", "Asia Pacific", GCP_ACCOUNTS),
+    region("asia-southeast1", "Singapore", "Asia Pacific", GCP_ACCOUNTS),
+    region("australia-southeast1", "Sydney", "Asia Pacific", GCP_ACCOUNTS),
+];
+
+/// Catalog for a cloud ("aws", "azure" or "gcp").
+pub(crate) fn regions_for(cloud: &str) -> Option<&'static [SupportedRegion]> {
+    super::cloud_provider::provider(cloud).map(|p| p.regions())
+}
+
+/// Look up a region by ID or display name, in any spelling
+/// [`canonical_region`](super::region_aliases::canonical_region) accepts.
+pub(crate) fn find_supported_region(cloud: &str, region: &str) -> Option<&'static SupportedRegion> {
+    let wanted = super::region_aliases::canonical_region(cloud, region);
+    regions_for(cloud)?.iter().find(|r| r.id == wanted)
+}
+
+/// List regions where Databricks workspaces can be deployed for a cloud.
+#[tauri::command]
+pub fn list_supported_regions(cloud: String) -> Result<Vec<SupportedRegion>, String> {
+    regions_for(&cloud)
+        .map(|r| r.to_vec())
+        .ok_or_else(|| format!("Unknown cloud: {}", cloud))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_ids_unique_per_cloud() {
+        for cloud in ["aws", "azure", "gcp"] {
+            let regions = regions_for(cloud).unwrap();
+            let mut ids: Vec<&str> = regions.iter().map(|r| r.id).collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), regions.len(), "duplicate region in {}", cloud);
+        }
+    }
+
+    #[test]
+    fn find_supported_region_normalizes() {
+        assert_eq!(find_supported_region("azure", "East US 2").unwrap().id, "eastus2");
+        assert_eq!(find_supported_region("aws", "US-EAST-1").unwrap().id, "us-east-1");
+        assert!(find_supported_region("aws", "me-south-1").is_none());
+        assert!(find_supported_region("oci", "us-ashburn-1").is_none());
+    }
+
+    #[test]
+    fn govcloud_uses_separate_accounts_host() {
+        let gov = find_supported_region("aws", "us-gov-west-1").unwrap();
+        assert_eq!(gov.accounts_host, AWS_GOV_ACCOUNTS);
+        assert!(gov.notes.is_some());
+    }
+
+    #[test]
+    fn list_supported_regions_rejects_unknown_cloud() {
+        assert!(list_supported_regions("aws".to_string()).unwrap().len() > 10);
+        assert!(list_supported_regions("oci".to_string()).is_err());
+    }
+}
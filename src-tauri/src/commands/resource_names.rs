@@ -0,0 +1,400 @@
+//! Pre-deployment checks for globally unique resource names.
+//!
+//! Validates the names a template will create against each cloud's naming
+//! rules, then checks real availability: S3 and GCS bucket names, Azure
+//! storage account names (via DNS) and Databricks workspace names already
+//! used in the account.
+
+use super::{debug_log, http_client, CloudCredentials};
+use crate::databricks_api::{AccountAuth, AccountClient};
+use crate::dependencies;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Outcome of checking one name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NameCheck {
+    /// Template variable the name comes from.
+    pub field: String,
+    pub name: String,
+    pub resource_type: String,
+    /// "ok", "invalid", "taken" or "unverified".
+    pub status: String,
+    pub message: String,
+}
+
+/// All name checks for a deployment; `valid` is false if any name is invalid or taken.
+#[derive(Debug, Clone, Serialize)]
+pub struct NameValidationReport {
+    pub checks: Vec<NameCheck>,
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NameKind {
+    S3Bucket,
+    /// aws-simple prefix: also used in the root bucket `<prefix>-root-<8 chars>`.
+    AwsPrefix,
+    AzureStorageAccount,
+    GcsBucket,
+    Workspace,
+}
+
+impl NameKind {
+    fn label(self) -> &'static str {
+        match self {
+            NameKind::S3Bucket => "S3 bucket",
+            NameKind::AwsPrefix => "Workspace prefix",
+            NameKind::AzureStorageAccount => "Azure storage account",
+            NameKind::GcsBucket => "GCS bucket",
+            NameKind::Workspace => "Databricks workspace",
+        }
+    }
+}
+
+/// Length of `-root-` plus the 8-character random suffix aws-simple appends.
+const AWS_ROOT_BUCKET_SUFFIX_LEN: usize = 14;
+
+// ─── Naming Rules ───────────────────────────────────────────────────────────
+
+fn starts_and_ends_alnum(name: &str) -> bool {
+    let alnum = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    alnum(name.chars().next()) && alnum(name.chars().last())
+}
+
+fn s3_bucket_rules(name: &str) -> Result<(), String> {
+    if !(3..=63).contains(&name.len()) {
+        return Err("must be 3-63 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.') {
+        return Err("may only contain lowercase letters, digits, hyphens and dots".to_string());
+    }
+    if !starts_and_ends_alnum(name) {
+        return Err("must start and end with a lowercase letter or digit".to_string());
+    }
+    if name.contains("..") {
+        return Err("must not contain consecutive dots".to_string());
+    }
+    if name.split('.').count() == 4 && name.split('.').all(|p| p.parse::<u8>().is_ok()) {
+        return Err("must not be formatted as an IP address".to_string());
+    }
+    if name.starts_with("xn--") || name.starts_with("sthree-") || name.ends_with("-s3alias") {
+        return Err("uses a prefix or suffix reserved by AWS".to_string());
+    }
+    Ok(())
+}
+
+fn aws_prefix_rules(name: &str) -> Result<(), String> {
+    let max = 63 - AWS_ROOT_BUCKET_SUFFIX_LEN;
+    if name.len() > max {
+        return Err(format!("must be at most {} characters (it is used in the root bucket name)", max));
+    }
+    s3_bucket_rules(&format!("{}-root-abcdefgh", name))
+        .map_err(|e| format!("is used in the root S3 bucket name, which {}", e))
+}
+
+fn azure_storage_rules(name: &str) -> Result<(), String> {
+    if !(3..=24).contains(&name.len()) {
+        return Err("must be 3-24 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+        return Err("may only contain lowercase letters and digits".to_string());
+    }
+    Ok(())
+}
+
+fn gcs_bucket_rules(name: &str) -> Result<(), String> {
+    if !(3..=63).contains(&name.len()) {
+        return Err("must be 3-63 characters".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_' || c == '.')
+    {
+        return Err("may only contain lowercase letters, digits, hyphens, underscores and dots".to_string());
+    }
+    if !starts_and_ends_alnum(name) {
+        return Err("must start and end with a lowercase letter or digit".to_string());
+    }
+    if name.starts_with("goog") || name.contains("google") {
+        return Err("must not start with \"goog\" or contain \"google\"".to_string());
+    }
+    Ok(())
+}
+
+fn workspace_rules(name: &str) -> Result<(), String> {
+    if !(3..=64).contains(&name.len()) {
+        return Err("must be 3-64 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("may only contain letters, digits, hyphens and underscores".to_string());
+    }
+    Ok(())
+}
+
+fn check_rules(kind: NameKind, name: &str) -> Result<(), String> {
+    match kind {
+        NameKind::S3Bucket => s3_bucket_rules(name),
+        NameKind::AwsPrefix => workspace_rules(name).and_then(|_| aws_prefix_rules(name)),
+        NameKind::AzureStorageAccount => azure_storage_rules(name),
+        NameKind::GcsBucket => gcs_bucket_rules(name),
+        NameKind::Workspace => workspace_rules(name),
+    }
+}
+
+// ─── Names Per Template ─────────────────────────────────────────────────────
+
+fn value_str<'a>(values: &'a HashMap<String, serde_json::Value>, key: &str) -> Option<&'a str> {
+    values.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Names a template creates from the given values: (field, name, kind).
+fn names_to_check(template_id: &str, values: &HashMap<String, serde_json::Value>) -> Vec<(String, String, NameKind)> {
+    let cloud = template_id.split('-').next().unwrap_or("");
+    let mut names = Vec::new();
+    let mut push = |field: &str, kind: NameKind| {
+        if let Some(name) = value_str(values, field) {
+            names.push((field.to_string(), name.to_string(), kind));
+        }
+    };
+
+    if template_id == "aws-simple" {
+        push("prefix", NameKind::AwsPrefix);
+    }
+    push("workspace_name", NameKind::Workspace);
+    push("databricks_workspace_name", NameKind::Workspace);
+
+    if cloud == "azure" {
+        push("root_storage_name", NameKind::AzureStorageAccount);
+    }
+    match cloud {
+        "aws" => push("uc_storage_name", NameKind::S3Bucket),
+        "azure" => push("uc_storage_name", NameKind::AzureStorageAccount),
+        "gcp" => push("uc_storage_name", NameKind::GcsBucket),
+        _ => {}
+    }
+    names
+}
+
+// ─── Availability ───────────────────────────────────────────────────────────
+
+/// Interpret an anonymous bucket lookup: 404 means nobody owns the name, and
+/// the bucket answering at all (a redirect, denied access or a conflict)
+/// means someone does. Anything else, like a 400 for a malformed request,
+/// says nothing about the name and is returned as an error.
+fn bucket_status_taken(status: u16) -> Result<bool, String> {
+    match status {
+        404 => Ok(false),
+        200 | 301 | 307 | 401 | 403 | 409 => Ok(true),
+        _ => Err(format!("the lookup returned HTTP {}", status)),
+    }
+}
+
+async fn bucket_taken(client: &reqwest::Client, url: &str) -> Result<bool, String> {
+    let resp = client.head(url).send().await.map_err(|e| format!("the lookup failed: {}", e))?;
+    bucket_status_taken(resp.status().as_u16())
+}
+
+/// Storage account names are global DNS labels; a resolving blob endpoint means the name is taken.
+async fn azure_storage_taken(name: &str) -> Result<bool, String> {
+    let host = format!("{}.blob.core.windows.net:443", name);
+    tokio::task::spawn_blocking(move || -> Result<bool, String> {
+        use std::net::ToSocketAddrs;
+        // Without working DNS a failed lookup says nothing about the name
+        "management.azure.com:443"
+            .to_socket_addrs()
+            .map_err(|e| format!("DNS lookups are failing: {}", e))?;
+        Ok(host.to_socket_addrs().map(|mut a| a.next().is_some()).unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("the lookup failed: {}", e))?
+}
+
+fn parse_workspace_names(json: &serde_json::Value) -> Vec<String> {
+    json.as_array()
+        .or_else(|| json["workspaces"].as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|w| w["workspace_name"].as_str().map(|s| s.to_lowercase()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Workspace names in the Databricks account (AWS/GCP), using SP OAuth or a CLI profile.
+async fn account_workspace_names(cloud: &str, credentials: &CloudCredentials) -> Option<Vec<String>> {
+    let account_id = credentials.databricks_account_id.as_deref().filter(|s| !s.is_empty())?;
+    let client_id = credentials.databricks_client_id.as_deref().filter(|s| !s.is_empty());
+    let client_secret = credentials.databricks_client_secret.as_deref().filter(|s| !s.is_empty());
+    if let (Some(client_id), Some(client_secret)) = (client_id, client_secret) {
+        let auth = AccountAuth::ServicePrincipal {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+        };
+        let workspaces = AccountClient::new(cloud, account_id, auth).ok()?.list_workspaces().await.ok()?;
+        return Some(workspaces.into_iter().map(|w| w.workspace_name.to_lowercase()).collect());
+    }
+
+    let profile = credentials.databricks_profile.clone().filter(|s| !s.is_empty())?;
+    let cli = dependencies::find_databricks_cli_path()?;
+    let output = tokio::task::spawn_blocking(move || {
+        super::silent_cmd(&cli)
+            .args(["account", "workspaces", "list", "--profile", &profile, "--output", "json"])
+            .output()
+    })
+    .await
+    .ok()?
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_workspace_names(&serde_json::from_slice(&output.stdout).ok()?))
+}
+
+fn name_check(field: &str, name: &str, kind: NameKind, status: &str, message: String) -> NameCheck {
+    NameCheck {
+        field: field.to_string(),
+        name: name.to_string(),
+        resource_type: kind.label().to_string(),
+        status: status.to_string(),
+        message,
+    }
+}
+
+/// Validate the names a template will create against cloud naming rules and
+/// check that globally unique names (buckets, storage accounts) and
+/// workspace names are still available.
+#[tauri::command]
+pub async fn validate_resource_names(
+    template_id: String,
+    values: HashMap<String, serde_json::Value>,
+    credentials: CloudCredentials,
+) -> Result<NameValidationReport, String> {
+    let template_id = super::sanitize_template_id(&template_id)?;
+    let cloud = template_id.split('-').next().unwrap_or("").to_string();
+    let client = http_client()?;
+
+    let names = names_to_check(&template_id, &values);
+    let workspace_names = if cloud != "azure" && names.iter().any(|(_, _, k)| *k == NameKind::Workspace || *k == NameKind::AwsPrefix) {
+        account_workspace_names(&cloud, &credentials).await
+    } else {
+        None
+    };
+
+    let mut checks = Vec::new();
+    for (field, name, kind) in names {
+        if let Err(rule) = check_rules(kind, &name) {
+            checks.push(name_check(&field, &name, kind, "invalid", format!("{} name {}", kind.label(), rule)));
+            continue;
+        }
+
+        let taken = match kind {
+            NameKind::S3Bucket => bucket_taken(&client, &format!("https://s3.amazonaws.com/{}", name)).await,
+            NameKind::GcsBucket => {
+                bucket_taken(&client, &format!("https://storage.googleapis.com/storage/v1/b/{}", name)).await
+            }
+            NameKind::AzureStorageAccount => azure_storage_taken(&name).await,
+            NameKind::Workspace | NameKind::AwsPrefix => workspace_names
+                .as_ref()
+                .map(|existing| existing.contains(&name.to_lowercase()))
+                .ok_or_else(|| "the account's workspaces couldn't be listed".to_string()),
+        };
+        debug_log!("[validate_resource_names] {} '{}' taken={:?}", kind.label(), name, taken);
+
+        checks.push(match taken {
+            Ok(true) => name_check(
+                &field,
+                &name,
+                kind,
+                "taken",
+                format!("{} name '{}' is already in use. Choose a different name.", kind.label(), name),
+            ),
+            Ok(false) => name_check(&field, &name, kind, "ok", format!("{} name '{}' is available", kind.label(), name)),
+            Err(reason) => name_check(
+                &field,
+                &name,
+                kind,
+                "unverified",
+                format!("Couldn't verify whether {} name '{}' is available: {}", kind.label(), name, reason),
+            ),
+        });
+    }
+
+    Ok(NameValidationReport {
+        valid: checks.iter().all(|c| c.status == "ok" || c.status == "unverified"),
+        checks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn s3_bucket_rules_cases() {
+        assert!(s3_bucket_rules("my-uc-bucket").is_ok());
+        assert!(s3_bucket_rules("ab").is_err());
+        assert!(s3_bucket_rules("My-Bucket").is_err());
+        assert!(s3_bucket_rules("-bucket").is_err());
+        assert!(s3_bucket_rules("a..b").is_err());
+        assert!(s3_bucket_rules("192.168.1.1").is_err());
+        assert!(s3_bucket_rules("xn--bucket").is_err());
+    }
+
+    #[test]
+    fn aws_prefix_accounts_for_root_bucket_suffix() {
+        assert!(aws_prefix_rules("dbx-dev").is_ok());
+        assert!(aws_prefix_rules(&"a".repeat(50)).is_err());
+        assert!(check_rules(NameKind::AwsPrefix, "Dbx_Dev").is_err());
+    }
+
+    #[test]
+    fn azure_storage_rules_cases() {
+        assert!(azure_storage_rules("ucstorage01").is_ok());
+        assert!(azure_storage_rules("uc-storage").is_err());
+        assert!(azure_storage_rules("averyveryverylongstorageacct").is_err());
+    }
+
+    #[test]
+    fn gcs_bucket_rules_cases() {
+        assert!(gcs_bucket_rules("uc_bucket-01").is_ok());
+        assert!(gcs_bucket_rules("google-data").is_err());
+        assert!(gcs_bucket_rules("goog-bucket").is_err());
+    }
+
+    #[test]
+    fn names_for_templates() {
+        let values: HashMap<String, serde_json::Value> = [
+            ("prefix", json!("dbx")),
+            ("uc_storage_name", json!("dbx-uc")),
+            ("workspace_name", json!("")),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect();
+        let names = names_to_check("aws-simple", &values);
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0].2, NameKind::AwsPrefix);
+        assert_eq!(names[1].2, NameKind::S3Bucket);
+
+        let names = names_to_check("gcp-simple", &values);
+        assert_eq!(names, vec![("uc_storage_name".to_string(), "dbx-uc".to_string(), NameKind::GcsBucket)]);
+    }
+
+    #[test]
+    fn bucket_status_interpretation() {
+        assert_eq!(bucket_status_taken(404), Ok(false));
+        assert_eq!(bucket_status_taken(403), Ok(true));
+        assert_eq!(bucket_status_taken(409), Ok(true));
+        assert!(bucket_status_taken(400).unwrap_err().contains("HTTP 400"));
+        assert!(bucket_status_taken(500).is_err());
+    }
+
+    #[test]
+    fn parse_workspace_names_both_shapes() {
+        assert_eq!(parse_workspace_names(&json!([{"workspace_name": "Prod"}])), vec!["prod"]);
+        assert_eq!(parse_workspace_names(&json!({"workspaces": [{"workspace_name": "dev"}]})), vec!["dev"]);
+    }
+}
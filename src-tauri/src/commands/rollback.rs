@@ -0,0 +1,490 @@
+//! Preview of what a rollback (destroy) would remove.
+//!
+//! [`preview_rollback`] runs `terraform plan -destroy -json` and lists the
+//! resources that would be deleted, flagging the ones that hold data: storage
+//! buckets and accounts, the Unity Catalog metastore and catalogs, and
+//! encryption keys. Every destroy (rollback, queued, unattended expiry or the
+//! headless CLI) refuses to delete such resources unless the matching
+//! [`RollbackConfirmation`] flag is set; see [`check_confirmation`].
+//!
+//! In keep-data mode those resources are instead removed from the state with
+//! `terraform state rm` before destroying, so only compute and network go
+//! away. Resources whose destroy would delete kept data too (S3 bucket
+//! settings, the Azure resource group holding a kept account) go with them.
+//! What was left behind (with cloud IDs, for import or manual cleanup) is
+//! recorded in `.deployer-preserved.json`.
+
+use super::{debug_log, get_deployments_dir, lock_or_recover, sanitize_deployment_name, state_encryption, CloudCredentials};
+use crate::storage::Environment;
+use crate::terraform::{self, DEPLOYMENT_STATUS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Kinds of data-bearing resources, each needing its own confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataCategory {
+    /// Buckets, storage accounts and containers (workspace root storage, catalog storage).
+    Storage,
+    /// Unity Catalog metastore and catalogs.
+    Metastore,
+    /// Customer-managed keys; data encrypted with them becomes unreadable.
+    EncryptionKeys,
+}
+
+/// Explicit consent to delete each kind of data-bearing resource.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RollbackConfirmation {
+    pub storage: bool,
+    pub metastore: bool,
+    pub encryption_keys: bool,
+}
+
+impl RollbackConfirmation {
+    fn allows(&self, category: DataCategory) -> bool {
+        match category {
+            DataCategory::Storage => self.storage,
+            DataCategory::Metastore => self.metastore,
+            DataCategory::EncryptionKeys => self.encryption_keys,
+        }
+    }
+}
+
+/// A resource destroy would delete.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedDeletion {
+    pub address: String,
+    pub resource_type: String,
+    /// Set for data-bearing resources.
+    pub data_category: Option<DataCategory>,
+}
+
+/// Result of [`preview_rollback`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackPreview {
+    pub resources: Vec<PlannedDeletion>,
+    /// Subset of `resources` that hold data.
+    pub data_resources: Vec<PlannedDeletion>,
+    /// Flags `rollback_deployment` needs set in its confirmation.
+    pub required_confirmations: Vec<DataCategory>,
+    /// Errors reported by Terraform while planning.
+    pub errors: Vec<String>,
+}
+
+/// A data-bearing resource left in place by a keep-data rollback.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreservedResource {
+    pub address: String,
+    pub resource_type: String,
+    /// Cloud ID from the state (bucket name, storage account ID, ...).
+    pub id: Option<String>,
+    /// Unix timestamp (seconds).
+    pub preserved_at: u64,
+}
+
+/// Record of resources kept by keep-data rollbacks, inside the deployment.
+const PRESERVED_FILE: &str = ".deployer-preserved.json";
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn data_category(resource_type: &str) -> Option<DataCategory> {
+    match resource_type {
+        "aws_s3_bucket"
+        | "azurerm_storage_account"
+        | "azurerm_storage_container"
+        | "azurerm_storage_data_lake_gen2_filesystem"
+        | "google_storage_bucket" => Some(DataCategory::Storage),
+        "databricks_metastore" | "databricks_catalog" => Some(DataCategory::Metastore),
+        "aws_kms_key" | "azurerm_key_vault" | "azurerm_key_vault_key" | "google_kms_crypto_key" => {
+            Some(DataCategory::EncryptionKeys)
+        }
+        _ => None,
+    }
+}
+
+fn deletion(address: String, resource_type: String) -> PlannedDeletion {
+    let data_category = data_category(&resource_type);
+    PlannedDeletion { address, resource_type, data_category }
+}
+
+/// Deletions and error diagnostics from `terraform plan -destroy -json`
+/// (one JSON message per line).
+fn parse_plan_messages(output: &str) -> (Vec<PlannedDeletion>, Vec<String>) {
+    let mut deletions = Vec::new();
+    let mut errors = Vec::new();
+    for message in output.lines().filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok()) {
+        match message["type"].as_str() {
+            Some("planned_change") if message["change"]["action"] == "delete" => {
+                let resource = &message["change"]["resource"];
+                if let (Some(address), Some(resource_type)) = (resource["addr"].as_str(), resource["resource_type"].as_str()) {
+                    deletions.push(deletion(address.to_string(), resource_type.to_string()));
+                }
+            }
+            Some("diagnostic") if message["@level"] == "error" => {
+                let summary = message["diagnostic"]["summary"].as_str().unwrap_or("Unknown error");
+                match message["diagnostic"]["detail"].as_str().filter(|d| !d.is_empty()) {
+                    Some(detail) => errors.push(format!("{}: {}", summary, detail)),
+                    None => errors.push(summary.to_string()),
+                }
+            }
+            _ => {}
+        }
+    }
+    (deletions, errors)
+}
+
+/// A managed resource instance recorded in a Terraform state file.
+struct StateResource {
+    deletion: PlannedDeletion,
+    /// Cloud ID (`attributes.id`).
+    id: Option<String>,
+    attributes: serde_json::Value,
+}
+
+/// Managed resources recorded in a Terraform state file.
+fn state_resources(state: &str) -> Vec<StateResource> {
+    let Ok(state) = serde_json::from_str::<serde_json::Value>(state) else {
+        return Vec::new();
+    };
+    let mut resources = Vec::new();
+    for resource in state["resources"].as_array().into_iter().flatten() {
+        if resource["mode"] != "managed" {
+            continue;
+        }
+        let (Some(resource_type), Some(name)) = (resource["type"].as_str(), resource["name"].as_str()) else {
+            continue;
+        };
+        let base = match resource["module"].as_str() {
+            Some(module) => format!("{}.{}.{}", module, resource_type, name),
+            None => format!("{}.{}", resource_type, name),
+        };
+        for instance in resource["instances"].as_array().into_iter().flatten() {
+            let address = match &instance["index_key"] {
+                serde_json::Value::Number(n) => format!("{}[{}]", base, n),
+                serde_json::Value::String(s) => format!("{}[\"{}\"]", base, s),
+                _ => base.clone(),
+            };
+            let attributes = instance["attributes"].clone();
+            let id = attributes["id"].as_str().map(str::to_string);
+            resources.push(StateResource { deletion: deletion(address, resource_type.to_string()), id, attributes });
+        }
+    }
+    resources
+}
+
+/// Resource group name from an Azure resource ID (lowercased, as Azure
+/// compares them case-insensitively).
+fn azure_resource_group(id: &str) -> Option<String> {
+    let lower = id.to_lowercase();
+    let rest = &lower[lower.find("/resourcegroups/")? + "/resourcegroups/".len()..];
+    rest.split('/').next().filter(|name| !name.is_empty()).map(str::to_string)
+}
+
+/// Resources whose destroy would take a kept data resource down with it:
+/// the settings resources of a kept S3 bucket (policy, public access block,
+/// encryption, versioning, ...), and the Azure resource group containing a
+/// kept resource, since deleting a group deletes everything in it.
+fn dependents_of<'a>(resources: &'a [StateResource], kept: &[&StateResource]) -> Vec<&'a StateResource> {
+    let buckets: Vec<&str> = kept
+        .iter()
+        .filter(|r| r.deletion.resource_type == "aws_s3_bucket")
+        .filter_map(|r| r.id.as_deref())
+        .collect();
+    let groups: Vec<String> = kept
+        .iter()
+        .filter(|r| r.deletion.resource_type.starts_with("azurerm_"))
+        .filter_map(|r| r.id.as_deref().and_then(azure_resource_group))
+        .collect();
+    resources
+        .iter()
+        .filter(|r| r.deletion.data_category.is_none())
+        .filter(|r| {
+            let resource_type = r.deletion.resource_type.as_str();
+            if resource_type.starts_with("aws_s3_bucket_") {
+                r.attributes["bucket"].as_str().is_some_and(|b| buckets.contains(&b))
+            } else if resource_type == "azurerm_resource_group" {
+                r.attributes["name"].as_str().is_some_and(|n| groups.contains(&n.to_lowercase()))
+            } else {
+                false
+            }
+        })
+        .collect()
+}
+
+fn required_confirmations(resources: &[PlannedDeletion]) -> Vec<DataCategory> {
+    let mut categories: Vec<DataCategory> = Vec::new();
+    for category in resources.iter().filter_map(|r| r.data_category) {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+/// Refuse to destroy data-bearing resources in the state that `confirmation`
+/// doesn't cover.
+pub(crate) fn check_confirmation(
+    env: &dyn Environment,
+    deployment_dir: &Path,
+    confirmation: &RollbackConfirmation,
+) -> Result<(), String> {
+    let key = if state_encryption::is_enabled(deployment_dir) {
+        Some(state_encryption::load_key(env)?)
+    } else {
+        None
+    };
+    let Some(state) = state_encryption::read_state(deployment_dir, key.as_ref()) else {
+        return Ok(());
+    };
+    let unconfirmed: Vec<String> = state_resources(&state)
+        .into_iter()
+        .filter(|r| r.deletion.data_category.is_some_and(|c| !confirmation.allows(c)))
+        .map(|r| r.deletion.address)
+        .collect();
+    if unconfirmed.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Destroy would delete resources that hold data: {}. Review them with the rollback preview and confirm their deletion to continue.",
+        unconfirmed.join(", ")
+    ))
+}
+
+fn read_preserved(deployment_dir: &Path) -> Vec<PreservedResource> {
+    fs::read_to_string(deployment_dir.join(PRESERVED_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Remove data-bearing resources, and the resources whose destroy would
+/// delete them along the way, from the state so a following destroy leaves
+/// them in place. The state is snapshotted first. Returns what was
+/// preserved.
+fn preserve_in_state(
+    deployment_dir: &Path,
+    env_vars: &HashMap<String, String>,
+) -> Result<Vec<PreservedResource>, String> {
+    let Some(state) = state_encryption::read_state(deployment_dir, None) else {
+        return Ok(Vec::new());
+    };
+    let now = super::audit::now_secs();
+    let resources = state_resources(&state);
+    let kept: Vec<&StateResource> = resources.iter().filter(|r| r.deletion.data_category.is_some()).collect();
+    let preserved: Vec<PreservedResource> = kept
+        .iter()
+        .copied()
+        .chain(dependents_of(&resources, &kept))
+        .map(|r| PreservedResource {
+            address: r.deletion.address.clone(),
+            resource_type: r.deletion.resource_type.clone(),
+            id: r.id.clone(),
+            preserved_at: now,
+        })
+        .collect();
+    if preserved.is_empty() {
+        return Ok(preserved);
+    }
+
+    super::state_backups::backup_state(deployment_dir, "keep-data")?;
+    let addresses: Vec<String> = preserved.iter().map(|r| r.address.clone()).collect();
+    terraform::run_state_rm(&addresses, deployment_dir, env_vars)
+        .map_err(|e| format!("Failed to remove data resources from state: {}", e.trim()))?;
+
+    let mut record = read_preserved(deployment_dir);
+    record.retain(|r| !addresses.contains(&r.address));
+    record.extend(preserved.iter().cloned());
+    let json = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    fs::write(deployment_dir.join(PRESERVED_FILE), json)
+        .map_err(|e| format!("Failed to record preserved resources: {}", e))?;
+    Ok(preserved)
+}
+
+/// Keep-data mode: take data-bearing resources out of Terraform's hands
+/// before a rollback. Holds the deployment lock and decrypts the state for
+/// the duration.
+pub(crate) async fn preserve_data_resources(
+    app: &AppHandle,
+    deployment_name: &str,
+    deployment_dir: &Path,
+    credentials: &CloudCredentials,
+) -> Result<Vec<PreservedResource>, String> {
+    let lock = super::deployment_lock::acquire(app, deployment_name, "keep-data")?;
+    let state = state_encryption::prepare_run(app, deployment_dir)?;
+    let env_vars = super::build_env_vars(credentials);
+    let dir = deployment_dir.to_path_buf();
+    super::run_blocking(move || {
+        let _lock = lock;
+        let result = preserve_in_state(&dir, &env_vars);
+        drop(state);
+        result
+    })
+    .await
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List what a rollback would delete by running `terraform plan -destroy`.
+#[tauri::command]
+pub async fn preview_rollback(
+    app: AppHandle,
+    deployment_name: String,
+    credentials: CloudCredentials,
+) -> Result<RollbackPreview, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_deployment_name);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    if lock_or_recover(&DEPLOYMENT_STATUS).running {
+        return Err("Cannot preview a rollback while a deployment is running".to_string());
+    }
+
+    let mut env_vars = super::build_env_vars(&credentials);
+    env_vars.extend(super::secret_vars::tf_var_env(&app, &deployment_dir)?);
+    let lock = super::deployment_lock::acquire(&app, &safe_deployment_name, "plan")?;
+    let state = state_encryption::prepare_run(&app, &deployment_dir)?;
+    let dir = deployment_dir.clone();
+    let output = super::run_blocking(move || {
+        let _lock = lock;
+        let result = terraform::run_terraform("plan-destroy", &dir, env_vars)
+            .and_then(|child| child.wait_with_output().map_err(|e| e.to_string()));
+        drop(state);
+        result
+    })
+    .await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (resources, mut errors) = parse_plan_messages(&stdout);
+    if !output.status.success() && errors.is_empty() {
+        errors.push(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    debug_log!("[rollback] Destroy plan for {}: {} resources", safe_deployment_name, resources.len());
+
+    let data_resources: Vec<PlannedDeletion> = resources.iter().filter(|r| r.data_category.is_some()).cloned().collect();
+    Ok(RollbackPreview {
+        required_confirmations: required_confirmations(&data_resources),
+        resources,
+        data_resources,
+        errors,
+    })
+}
+
+/// Data-bearing resources kept by earlier keep-data rollbacks.
+#[tauri::command]
+pub fn get_preserved_resources(app: AppHandle, deployment_name: String) -> Result<Vec<PreservedResource>, String> {
+    let safe_deployment_name = sanitize_deployment_name(&deployment_name)?;
+    Ok(read_preserved(&get_deployments_dir(&app)?.join(safe_deployment_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_destroy_plan_messages() {
+        let output = [
+            r#"{"@level":"info","type":"version","terraform":"1.9.8"}"#,
+            r#"{"@level":"info","type":"planned_change","change":{"resource":{"addr":"aws_s3_bucket.root_storage_bucket","resource_type":"aws_s3_bucket"},"action":"delete"}}"#,
+            r#"{"@level":"info","type":"planned_change","change":{"resource":{"addr":"module.unity_catalog.databricks_metastore.this[0]","resource_type":"databricks_metastore"},"action":"delete"}}"#,
+            r#"{"@level":"info","type":"planned_change","change":{"resource":{"addr":"aws_vpc.main","resource_type":"aws_vpc"},"action":"delete"}}"#,
+            r#"{"@level":"info","type":"planned_change","change":{"resource":{"addr":"aws_iam_role.x","resource_type":"aws_iam_role"},"action":"update"}}"#,
+            r#"{"@level":"error","type":"diagnostic","diagnostic":{"severity":"error","summary":"No valid credential sources found","detail":""}}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let (deletions, errors) = parse_plan_messages(&output);
+        assert_eq!(deletions.len(), 3);
+        assert_eq!(deletions[0].data_category, Some(DataCategory::Storage));
+        assert_eq!(deletions[1].data_category, Some(DataCategory::Metastore));
+        assert_eq!(deletions[2].data_category, None);
+        assert_eq!(errors, vec!["No valid credential sources found".to_string()]);
+        assert_eq!(
+            required_confirmations(&deletions),
+            vec![DataCategory::Storage, DataCategory::Metastore]
+        );
+    }
+
+    #[test]
+    fn state_resources_build_addresses() {
+        let state = serde_json::json!({
+            "resources": [
+                {"mode": "managed", "type": "google_storage_bucket", "name": "uc", "module": "module.catalog",
+                 "instances": [{"index_key": 0}, {"index_key": 1}]},
+                {"mode": "managed", "type": "google_kms_crypto_key", "name": "cmek",
+                 "instances": [{"index_key": "main", "attributes": {"id": "projects/p/locations/us/keyRings/r/cryptoKeys/k"}}]},
+                {"mode": "data", "type": "google_storage_bucket", "name": "lookup", "instances": [{}]},
+                {"mode": "managed", "type": "google_compute_network", "name": "vpc", "instances": [{}]}
+            ]
+        });
+        let resources = state_resources(&state.to_string());
+        assert_eq!(resources[0].id, None);
+        assert_eq!(resources[2].id.as_deref(), Some("projects/p/locations/us/keyRings/r/cryptoKeys/k"));
+        let addresses: Vec<String> = resources.into_iter().map(|r| r.deletion.address).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                "module.catalog.google_storage_bucket.uc[0]",
+                "module.catalog.google_storage_bucket.uc[1]",
+                "google_kms_crypto_key.cmek[\"main\"]",
+                "google_compute_network.vpc",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeping_data_keeps_bucket_settings_and_resource_groups() {
+        let state = serde_json::json!({"resources": [
+            {"mode": "managed", "type": "aws_s3_bucket", "name": "root",
+             "instances": [{"attributes": {"id": "dbx-root"}}]},
+            {"mode": "managed", "type": "aws_s3_bucket_policy", "name": "root",
+             "instances": [{"attributes": {"id": "dbx-root", "bucket": "dbx-root"}}]},
+            {"mode": "managed", "type": "aws_s3_bucket_versioning", "name": "root",
+             "instances": [{"attributes": {"id": "dbx-root", "bucket": "dbx-root"}}]},
+            {"mode": "managed", "type": "aws_s3_bucket_policy", "name": "logs",
+             "instances": [{"attributes": {"id": "dbx-logs", "bucket": "dbx-logs"}}]},
+            {"mode": "managed", "type": "azurerm_storage_account", "name": "uc",
+             "instances": [{"attributes": {"id": "/subscriptions/s/resourceGroups/Data-RG/providers/Microsoft.Storage/storageAccounts/uc"}}]},
+            {"mode": "managed", "type": "azurerm_resource_group", "name": "data",
+             "instances": [{"attributes": {"id": "/subscriptions/s/resourceGroups/Data-RG", "name": "Data-RG"}}]},
+            {"mode": "managed", "type": "azurerm_resource_group", "name": "network",
+             "instances": [{"attributes": {"id": "/subscriptions/s/resourceGroups/net-rg", "name": "net-rg"}}]}
+        ]});
+        let resources = state_resources(&state.to_string());
+        let kept: Vec<&StateResource> = resources.iter().filter(|r| r.deletion.data_category.is_some()).collect();
+        let dependents: Vec<&str> = dependents_of(&resources, &kept).iter().map(|r| r.deletion.address.as_str()).collect();
+        assert_eq!(
+            dependents,
+            vec!["aws_s3_bucket_policy.root", "aws_s3_bucket_versioning.root", "azurerm_resource_group.data"]
+        );
+    }
+
+    #[test]
+    fn rollback_requires_confirmation_for_data_resources() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = crate::storage::StoragePaths::from_data_dir(dir.path().join("data"));
+        let deployment = dir.path().join("dep");
+        std::fs::create_dir_all(&deployment).unwrap();
+
+        // No state yet: nothing to protect
+        assert!(check_confirmation(&paths, &deployment, &RollbackConfirmation::default()).is_ok());
+
+        let state = serde_json::json!({"resources": [
+            {"mode": "managed", "type": "aws_s3_bucket", "name": "root", "instances": [{}]},
+            {"mode": "managed", "type": "aws_vpc", "name": "main", "instances": [{}]}
+        ]});
+        std::fs::write(deployment.join("terraform.tfstate"), state.to_string()).unwrap();
+
+        let err = check_confirmation(&paths, &deployment, &RollbackConfirmation::default()).unwrap_err();
+        assert!(err.contains("aws_s3_bucket.root") && !err.contains("aws_vpc"), "{}", err);
+        let metastore_only = RollbackConfirmation { metastore: true, ..Default::default() };
+        assert!(check_confirmation(&paths, &deployment, &metastore_only).is_err());
+        let storage = RollbackConfirmation { storage: true, ..Default::default() };
+        assert!(check_confirmation(&paths, &deployment, &storage).is_ok());
+    }
+}
@@ -0,0 +1,350 @@
+//! Terraform runs that outlive the app.
+//!
+//! While a run is in flight its PID and run record are kept in
+//! `<deployment>/.deployer-run.json`, and Terraform writes its output to
+//! `<deployment>/.deployer-run.log` rather than to pipes. If the app quits
+//! mid-run, the next start finds the record: a run that is still going is
+//! reattached (its log followed into the deployment status), and one that
+//! died is recorded as interrupted with suggestions for getting back to a
+//! consistent state.
+
+use super::audit::RunRecord;
+use super::notifications::RunOutcome;
+use super::{debug_log, get_deployments_dir, lock_or_recover};
+use crate::terraform::{self, CURRENT_PROCESS, DEPLOYMENT_STATUS};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+/// In-flight run record inside a deployment.
+const RUN_FILE: &str = ".deployer-run.json";
+
+/// Terraform output of the in-flight run.
+const RUN_LOG: &str = ".deployer-run.log";
+
+/// Emitted at startup with the runs found from a previous session.
+const INTERRUPTED_RUNS_EVENT: &str = "interrupted-runs";
+
+/// A run whose log hasn't changed for this long isn't treated as alive even
+/// if its PID is (Terraform prints progress every 10 seconds while applying,
+/// and the PID may have been reused after a reboot).
+const LOG_SILENCE_LIMIT_SECS: u64 = 600;
+
+/// Lines of the log kept in [`InterruptedRun::output_tail`].
+const TAIL_LINES: usize = 40;
+
+lazy_static::lazy_static! {
+    /// Runs recovered at startup, surfaced to the UI once it loads.
+    static ref LAST_INTERRUPTED_RUNS: std::sync::Mutex<Vec<InterruptedRun>> = std::sync::Mutex::new(Vec::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InFlightRun {
+    pid: u32,
+    record: RunRecord,
+}
+
+/// A run left over from a previous app session.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InterruptedRun {
+    pub deployment_name: String,
+    pub command: String,
+    pub pid: u32,
+    pub started_at: u64,
+    /// Terraform was still running and the app reattached to it.
+    pub reattached: bool,
+    /// Last lines of output before the interruption.
+    pub output_tail: String,
+    /// Next steps to bring state and cloud resources back in sync.
+    pub suggestions: Vec<String>,
+}
+
+// ─── Run files ──────────────────────────────────────────────────────────────
+
+/// Log file Terraform writes to for a run in `deployment_dir`.
+pub(crate) fn log_path(deployment_dir: &Path) -> PathBuf {
+    deployment_dir.join(RUN_LOG)
+}
+
+/// Persist the in-flight run once Terraform has started.
+pub(crate) fn record_start(deployment_dir: &Path, pid: u32, record: &RunRecord) {
+    let run = InFlightRun { pid, record: record.clone() };
+    let written = serde_json::to_string_pretty(&run)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(deployment_dir.join(RUN_FILE), json).map_err(|e| e.to_string()));
+    if let Err(_e) = written {
+        debug_log!("[run_recovery] Failed to record run: {}", _e);
+    }
+}
+
+/// Forget the in-flight run after it finished (its output is in the audit log).
+pub(crate) fn clear(deployment_dir: &Path) {
+    let _ = fs::remove_file(deployment_dir.join(RUN_FILE));
+    let _ = fs::remove_file(log_path(deployment_dir));
+}
+
+fn read_run(deployment_dir: &Path) -> Option<InFlightRun> {
+    serde_json::from_str(&fs::read_to_string(deployment_dir.join(RUN_FILE)).ok()?).ok()
+}
+
+/// Whether a run is recorded as in flight in `deployment_dir`.
+pub(crate) fn has_run(deployment_dir: &Path) -> bool {
+    deployment_dir.join(RUN_FILE).exists()
+}
+
+fn log_age_secs(deployment_dir: &Path, now: u64) -> u64 {
+    fs::metadata(log_path(deployment_dir))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(u64::MAX, |d| now.saturating_sub(d.as_secs()))
+}
+
+fn still_running(deployment_dir: &Path, run: &InFlightRun, now: u64) -> bool {
+    super::deployment_lock::process_alive(run.pid) && log_age_secs(deployment_dir, now) <= LOG_SILENCE_LIMIT_SECS
+}
+
+fn tail(output: &str, lines: usize) -> String {
+    let all: Vec<&str> = output.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+/// Whether a finished run succeeded, judged from its output since the
+/// process that started it (and its exit code) is gone.
+fn succeeded(command: &str, output: &str) -> bool {
+    let (plan, result) = super::audit::summarize_output(output);
+    match result {
+        Some(line) if line.starts_with("Error:") => false,
+        Some(_) => true,
+        None => command == "plan" && plan.is_some(),
+    }
+}
+
+/// What to do after `command` stopped partway.
+fn suggestions(command: &str) -> Vec<String> {
+    let mut steps = vec!["Run plan to compare the state with what exists in the cloud".to_string()];
+    match command {
+        "apply" => steps.push(
+            "Run apply again to finish; resources created before the interruption are in the state or imported on retry"
+                .to_string(),
+        ),
+        "destroy" => steps.push("Run destroy again to remove the remaining resources".to_string()),
+        "init" => steps = vec!["Run init again".to_string()],
+        _ => {}
+    }
+    steps
+}
+
+// ─── Recovery ───────────────────────────────────────────────────────────────
+
+/// Close out a run started by a previous session: audit entry, re-encrypted
+/// state, and the run files removed.
+fn finalize(env: &dyn crate::storage::Environment, deployment_dir: &Path, run: &InFlightRun, output: &str, success: bool) {
+    let record = RunRecord {
+        finished_at: super::audit::now_secs(),
+        success,
+        ..run.record.clone()
+    };
+    super::audit::record_run(deployment_dir, record, output);
+    // The run decrypted the state; encrypt it again now that it's done
+    super::state_encryption::secure_state(env, deployment_dir);
+    clear(deployment_dir);
+}
+
+/// Follow a still-running orphan into the shared deployment status until it exits.
+fn reattach(app: &AppHandle, name: &str, deployment_dir: &Path, run: InFlightRun) -> Result<(), String> {
+    let lock = super::deployment_lock::acquire(app, name, &run.record.command)?;
+    {
+        let mut status = lock_or_recover(&DEPLOYMENT_STATUS);
+        if status.running {
+            return Err("Another deployment is running".to_string());
+        }
+        status.running = true;
+        status.command = Some(format!("{} {}", run.record.engine, run.record.command));
+        status.output = format!("Reattached to {} started before the app restarted.\n", run.record.command);
+        status.success = None;
+        status.can_rollback = terraform::check_state_exists(&deployment_dir.to_path_buf());
+        status.credential_warnings.clear();
+    }
+    *lock_or_recover(&CURRENT_PROCESS) = Some(run.pid);
+
+    let app = app.clone();
+    let name = name.to_string();
+    let dir = deployment_dir.to_path_buf();
+    std::thread::spawn(move || {
+        let _lock = lock;
+        let pid = run.pid;
+        terraform::follow_log(&log_path(&dir), &DEPLOYMENT_STATUS, &mut || {
+            !super::deployment_lock::process_alive(pid)
+        });
+        let output = lock_or_recover(&DEPLOYMENT_STATUS).output.clone();
+        let success = succeeded(&run.record.command, &output);
+        if success && run.record.command == "destroy" {
+            super::expiry::clear_expiry(&dir);
+        }
+        finalize(&app, &dir, &run, &output, success);
+        let outcome = if success { RunOutcome::Succeeded } else { RunOutcome::Failed };
+        let duration = super::audit::now_secs().saturating_sub(run.record.started_at);
+        super::notifications::notify_run_finished(&app, &name, &run.record.command, outcome, duration);
+        super::telemetry::record_run_finished(&app, &dir, &run.record.command, outcome, duration);
+        {
+            let mut status = lock_or_recover(&DEPLOYMENT_STATUS);
+            status.running = false;
+            status.success = Some(success);
+            status.can_rollback = terraform::check_state_exists(&dir);
+        }
+        *lock_or_recover(&CURRENT_PROCESS) = None;
+    });
+    Ok(())
+}
+
+/// Find runs left over from a previous session in `deployments_dir`.
+fn find_runs(deployments_dir: &Path) -> Vec<(String, PathBuf, InFlightRun)> {
+    let Ok(entries) = fs::read_dir(deployments_dir) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<_> = entries
+        .flatten()
+        .filter_map(|e| {
+            let dir = e.path();
+            let run = read_run(&dir)?;
+            Some((e.file_name().to_string_lossy().to_string(), dir, run))
+        })
+        .collect();
+    runs.sort_by_key(|(_, _, run)| std::cmp::Reverse(run.record.started_at));
+    runs
+}
+
+/// Reattach to or close out runs the previous app session left behind, then
+/// tell the UI. Called once at startup. Only one run can be followed at a
+/// time, so the most recent live run is reattached and any others keep
+/// running unobserved until they're recorded on a later start.
+pub fn recover_interrupted_runs(app: &AppHandle) {
+    let Ok(deployments_dir) = get_deployments_dir(app) else {
+        return;
+    };
+    let now = super::audit::now_secs();
+    let mut recovered = Vec::new();
+    let mut reattached_one = false;
+    for (name, dir, run) in find_runs(&deployments_dir) {
+        let output = fs::read_to_string(log_path(&dir)).unwrap_or_default();
+        let mut entry = InterruptedRun {
+            deployment_name: name.clone(),
+            command: run.record.command.clone(),
+            pid: run.pid,
+            started_at: run.record.started_at,
+            reattached: false,
+            output_tail: tail(&output, TAIL_LINES),
+            suggestions: Vec::new(),
+        };
+        if still_running(&dir, &run, now) {
+            if reattached_one {
+                continue;
+            }
+            match reattach(app, &name, &dir, run) {
+                Ok(()) => {
+                    reattached_one = true;
+                    entry.reattached = true;
+                }
+                Err(_e) => {
+                    debug_log!("[run_recovery] Couldn't reattach to {}: {}", name, _e);
+                }
+            }
+        } else {
+            let success = succeeded(&run.record.command, &output);
+            let note = if success { "" } else { "\nRun interrupted: the app exited before it finished.\n" };
+            finalize(app, &dir, &run, &format!("{}{}", output, note), success);
+            if success {
+                // Finished cleanly after the app went away; nothing to recover
+                continue;
+            }
+            entry.suggestions = suggestions(&entry.command);
+        }
+        recovered.push(entry);
+    }
+
+    super::state_encryption::secure_stray_state(app);
+
+    if !recovered.is_empty() {
+        *lock_or_recover(&LAST_INTERRUPTED_RUNS) = recovered.clone();
+        let _ = app.emit(INTERRUPTED_RUNS_EVENT, &recovered);
+    }
+}
+
+/// Runs recovered from the previous session (reattached or interrupted).
+#[tauri::command]
+pub fn get_interrupted_runs() -> Vec<InterruptedRun> {
+    lock_or_recover(&LAST_INTERRUPTED_RUNS).clone()
+}
+
+/// Dismiss the recovered-runs notice.
+#[tauri::command]
+pub fn dismiss_interrupted_runs() {
+    lock_or_recover(&LAST_INTERRUPTED_RUNS).clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str) -> RunRecord {
+        RunRecord {
+            command: command.to_string(),
+            engine: "terraform".to_string(),
+            started_at: 100,
+            finished_at: 0,
+            success: false,
+            identities: Vec::new(),
+            resolved_identities: Vec::new(),
+            plan_summary: None,
+            result_summary: None,
+        }
+    }
+
+    #[test]
+    fn run_files_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("demo");
+        fs::create_dir_all(root.path().join("idle")).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        record_start(&dir, 4242, &record("apply"));
+        fs::write(log_path(&dir), "aws_vpc.this: Creating...\n").unwrap();
+
+        let runs = find_runs(root.path());
+        assert_eq!(runs.len(), 1);
+        assert_eq!((runs[0].0.as_str(), runs[0].2.pid), ("demo", 4242));
+        assert_eq!(runs[0].2.record.command, "apply");
+
+        clear(&dir);
+        assert!(read_run(&dir).is_none());
+        assert!(!log_path(&dir).exists());
+    }
+
+    #[test]
+    fn dead_process_is_not_running() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(log_path(dir.path()), "").unwrap();
+        let mut child = std::process::Command::new(if cfg!(windows) { "cmd" } else { "true" })
+            .args(if cfg!(windows) { vec!["/C", "exit"] } else { vec![] })
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        let now = super::super::audit::now_secs();
+        assert!(!still_running(dir.path(), &InFlightRun { pid, record: record("apply") }, now));
+        let own = InFlightRun { pid: std::process::id(), record: record("apply") };
+        assert!(still_running(dir.path(), &own, now));
+        assert!(!still_running(dir.path(), &own, now + LOG_SILENCE_LIMIT_SECS + 60));
+    }
+
+    #[test]
+    fn success_is_inferred_from_output() {
+        assert!(succeeded("apply", "...\nApply complete! Resources: 3 added, 0 changed, 0 destroyed.\n"));
+        assert!(!succeeded("apply", "aws_vpc.this: Creating...\n"));
+        assert!(!succeeded("destroy", "Error: deleting VPC\n"));
+        assert!(succeeded("plan", "Plan: 2 to add, 0 to change, 0 to destroy.\n"));
+        assert_eq!(suggestions("destroy").len(), 2);
+        assert_eq!(tail("a\nb\nc", 2), "b\nc");
+    }
+}
@@ -0,0 +1,473 @@
+//! Per-deployment advanced Terraform run settings.
+//!
+//! Advanced users can add CLI arguments (`-parallelism=5`,
+//! `-lock-timeout=5m`, ...), environment variables (`TF_LOG=DEBUG`, ...) and
+//! provider-specific environment toggles to every Terraform run of a
+//! deployment. Everything is checked against the allow-lists below when saved
+//! and again when a run starts, so hand-edited metadata can't inject
+//! arbitrary flags or override credentials. Settings live in the deployment's
+//! `.deployer-meta.json`. A `TF_LOG*` level sends Terraform's log to a file in
+//! the deployment's runs directory (see [`super::debug_logs`]).
+//!
+//! Resource targeting (`-target`, `-replace`) is not a setting: it is passed
+//! with a single run as [`RunTargets`] and never saved, so a forgotten target
+//! can't quietly narrow every later apply.
+//!
+//! Plan-only mode, set here per deployment or app-wide in
+//! [`super::settings`], makes [`ensure_command_allowed`] refuse apply and
+//! destroy for teams that review plans locally and change infrastructure
+//! from CI.
+
+use super::audit::{read_meta, try_read_meta, write_meta};
+use super::{debug_log, get_deployments_dir, sanitize_deployment_name};
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+const MAX_ENV_VALUE_LEN: usize = 1024;
+
+/// Advanced settings applied to every Terraform run of a deployment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSettings {
+    /// Extra CLI arguments, each passed only to the subcommands that accept it.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Extra environment variables. Credentials the app sets take precedence.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Enabled [`PROVIDER_TOGGLES`] by id.
+    #[serde(default)]
+    pub provider_toggles: Vec<String>,
+    /// Refuse apply and destroy for this deployment.
+    #[serde(default)]
+    pub plan_only: bool,
+}
+
+/// Kind of value an allowed argument takes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgValue {
+    /// Bare flag, e.g. `-upgrade`.
+    Flag,
+    /// Positive count up to 256.
+    Count,
+    /// Duration such as `30s` or `5m`.
+    Duration,
+    Bool,
+}
+
+/// An allowed CLI argument.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArgSpec {
+    pub flag: &'static str,
+    pub value: ArgValue,
+    /// Terraform subcommands the argument is passed to.
+    pub commands: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// A named set of provider environment variables.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderToggle {
+    pub id: &'static str,
+    pub provider: &'static str,
+    pub description: &'static str,
+    pub env: &'static [(&'static str, &'static str)],
+}
+
+/// Current settings with the allow-lists, for the settings form.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSettingsView {
+    pub settings: RunSettings,
+    pub allowed_args: &'static [ArgSpec],
+    pub allowed_env: &'static [&'static str],
+    pub provider_toggles: &'static [ProviderToggle],
+}
+
+/// Resources a single run is limited to or forced to replace. Given with
+/// the run and never saved.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RunTargets {
+    /// `-target` addresses, e.g. `module.vpc.aws_subnet.private[0]`.
+    pub target: Vec<String>,
+    /// `-replace` addresses.
+    pub replace: Vec<String>,
+}
+
+const ALL_RUNS: &[&str] = &["init", "plan", "apply", "destroy"];
+const CHANGE_RUNS: &[&str] = &["plan", "apply", "destroy"];
+/// Subcommands refused in plan-only mode.
+const APPLYING_RUNS: &[&str] = &["apply", "destroy"];
+
+pub const ALLOWED_ARGS: &[ArgSpec] = &[
+    ArgSpec { flag: "-parallelism", value: ArgValue::Count, commands: CHANGE_RUNS, description: "Concurrent resource operations (default 10)" },
+    ArgSpec { flag: "-lock-timeout", value: ArgValue::Duration, commands: ALL_RUNS, description: "How long to retry acquiring the state lock" },
+    ArgSpec { flag: "-lock", value: ArgValue::Bool, commands: ALL_RUNS, description: "Whether to lock the state during the run" },
+    ArgSpec { flag: "-refresh", value: ArgValue::Bool, commands: CHANGE_RUNS, description: "Whether to refresh resources before planning" },
+    ArgSpec { flag: "-compact-warnings", value: ArgValue::Flag, commands: CHANGE_RUNS, description: "Show warnings in summary form" },
+    ArgSpec { flag: "-upgrade", value: ArgValue::Flag, commands: &["init"], description: "Upgrade providers and modules within their constraints" },
+];
+
+pub const ALLOWED_ENV: &[&str] = &[
+    "TF_LOG",
+    "TF_LOG_CORE",
+    "TF_LOG_PROVIDER",
+    "TF_REGISTRY_CLIENT_TIMEOUT",
+    "TF_REGISTRY_DISCOVERY_RETRY",
+    "TF_PLUGIN_CACHE_DIR",
+    "TF_IN_AUTOMATION",
+    "CHECKPOINT_DISABLE",
+];
+
+pub(super) const LOG_LEVELS: &[&str] = &["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "JSON", "OFF"];
+
+pub const PROVIDER_TOGGLES: &[ProviderToggle] = &[
+    ProviderToggle {
+        id: "aws_disable_imds",
+        provider: "aws",
+        description: "Don't look for credentials in the EC2 instance metadata service",
+        env: &[("AWS_EC2_METADATA_DISABLED", "true")],
+    },
+    ProviderToggle {
+        id: "aws_adaptive_retry",
+        provider: "aws",
+        description: "Retry throttled AWS API calls with adaptive backoff",
+        env: &[("AWS_RETRY_MODE", "adaptive"), ("AWS_MAX_ATTEMPTS", "10")],
+    },
+    ProviderToggle {
+        id: "azure_skip_provider_registration",
+        provider: "azurerm",
+        description: "Don't register Azure resource providers (for identities without subscription-level rights)",
+        env: &[("ARM_RESOURCE_PROVIDER_REGISTRATIONS", "none"), ("ARM_SKIP_PROVIDER_REGISTRATION", "true")],
+    },
+    ProviderToggle {
+        id: "azure_disable_partner_id",
+        provider: "azurerm",
+        description: "Don't send the Terraform partner ID with Azure requests",
+        env: &[("ARM_DISABLE_TERRAFORM_PARTNER_ID", "true")],
+    },
+    ProviderToggle {
+        id: "databricks_debug_headers",
+        provider: "databricks",
+        description: "Include request headers in Databricks provider debug logs",
+        env: &[("DATABRICKS_DEBUG_HEADERS", "true")],
+    },
+    ProviderToggle {
+        id: "databricks_low_rate_limit",
+        provider: "databricks",
+        description: "Limit Databricks API calls to 5 per second",
+        env: &[("DATABRICKS_RATE_LIMIT", "5")],
+    },
+];
+
+// ─── Validation ─────────────────────────────────────────────────────────────
+
+fn valid_value(kind: ArgValue, value: Option<&str>) -> bool {
+    match (kind, value) {
+        (ArgValue::Flag, None) => true,
+        (ArgValue::Count, Some(v)) => v.parse::<u32>().is_ok_and(|n| (1..=256).contains(&n)),
+        (ArgValue::Duration, Some(v)) => ["ms", "s", "m", "h"].iter().any(|unit| {
+            v.strip_suffix(unit).is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        }),
+        (ArgValue::Bool, Some(v)) => v == "true" || v == "false",
+        _ => false,
+    }
+}
+
+/// Resource address, e.g. `module.vpc.aws_subnet.private[0]`.
+fn valid_address(address: &str) -> bool {
+    !address.is_empty()
+        && address.len() <= 256
+        && address.chars().all(|c| c.is_ascii_alphanumeric() || "_-.[]\"".contains(c))
+}
+
+/// The allow-list entry for `arg` if its value is valid.
+fn arg_spec(arg: &str) -> Result<&'static ArgSpec, String> {
+    let (flag, value) = match arg.split_once('=') {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (arg, None),
+    };
+    let spec = ALLOWED_ARGS
+        .iter()
+        .find(|s| s.flag == flag)
+        .ok_or_else(|| format!("Argument '{}' is not allowed", flag))?;
+    if !valid_value(spec.value, value) {
+        return Err(match spec.value {
+            ArgValue::Flag => format!("{} takes no value", flag),
+            ArgValue::Count => format!("{} needs a number from 1 to 256, e.g. {}=5", flag, flag),
+            ArgValue::Duration => format!("{} needs a duration, e.g. {}=5m", flag, flag),
+            ArgValue::Bool => format!("{} needs true or false", flag),
+        });
+    }
+    Ok(spec)
+}
+
+fn check_env(name: &str, value: &str) -> Result<(), String> {
+    if !ALLOWED_ENV.contains(&name) {
+        return Err(format!("Environment variable {} is not allowed", name));
+    }
+    if value.len() > MAX_ENV_VALUE_LEN || value.chars().any(char::is_control) {
+        return Err(format!("Invalid value for {}", name));
+    }
+    if name.starts_with("TF_LOG") && !LOG_LEVELS.contains(&value.to_uppercase().as_str()) {
+        return Err(format!("{} must be one of {}", name, LOG_LEVELS.join(", ")));
+    }
+    Ok(())
+}
+
+/// Every problem with `settings`, empty when they can be saved.
+fn validate(settings: &RunSettings) -> Vec<String> {
+    let mut problems: Vec<String> = settings.extra_args.iter().filter_map(|a| arg_spec(a.trim()).err()).collect();
+    problems.extend(settings.env.iter().filter_map(|(name, value)| check_env(name, value).err()));
+    problems.extend(
+        settings
+            .provider_toggles
+            .iter()
+            .filter(|id| !PROVIDER_TOGGLES.iter().any(|t| &t.id == id))
+            .map(|id| format!("Unknown provider setting '{}'", id)),
+    );
+    problems
+}
+
+// ─── Run-time use ───────────────────────────────────────────────────────────
+
+impl RunTargets {
+    /// `-target=` and `-replace=` arguments for a `subcommand` run. Invalid
+    /// addresses, and replacing during destroy, are errors.
+    pub(crate) fn args(&self, subcommand: &str) -> Result<Vec<String>, String> {
+        if self.target.is_empty() && self.replace.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !CHANGE_RUNS.contains(&subcommand) {
+            return Err(format!("Resources can't be targeted for {}", subcommand));
+        }
+        if !self.replace.is_empty() && subcommand == "destroy" {
+            return Err("Resources can't be replaced during destroy".to_string());
+        }
+        let flags = self.target.iter().map(|a| ("-target", a)).chain(self.replace.iter().map(|a| ("-replace", a)));
+        flags
+            .map(|(flag, address)| {
+                let address = address.trim();
+                if valid_address(address) {
+                    Ok(format!("{}={}", flag, address))
+                } else {
+                    Err(format!("{} needs a resource address, got '{}'", flag, address))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Arguments and environment to add to a Terraform `subcommand` run in
+/// `deployment_dir`. Entries that fail validation are skipped.
+pub(crate) fn run_overrides(deployment_dir: &Path, subcommand: &str) -> (Vec<String>, Vec<(String, String)>) {
+    let Some(settings) = read_meta(deployment_dir).map(|m| m.run_settings) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let args = settings
+        .extra_args
+        .iter()
+        .map(|a| a.trim())
+        .filter(|arg| match arg_spec(arg) {
+            Ok(spec) => spec.commands.contains(&subcommand),
+            Err(_e) => {
+                debug_log!("[run_settings] Skipping argument: {}", _e);
+                false
+            }
+        })
+        .map(String::from)
+        .collect();
+
+    let mut env: Vec<(String, String)> = Vec::new();
+    for toggle in PROVIDER_TOGGLES.iter().filter(|t| settings.provider_toggles.iter().any(|id| id == t.id)) {
+        env.extend(toggle.env.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+    }
+    for (name, value) in &settings.env {
+        match check_env(name, value) {
+            Ok(()) => env.push((name.clone(), value.clone())),
+            Err(_e) => {
+                debug_log!("[run_settings] Skipping variable: {}", _e);
+            }
+        }
+    }
+    (args, env)
+}
+
+/// Refuse `command` when plan-only mode is on for the app or for the
+/// deployment in `deployment_dir`. Checked by every path that starts a run,
+/// whatever the UI shows. Fails closed: when either setting can't be read,
+/// applying runs are refused too.
+pub(crate) fn ensure_command_allowed(env: &dyn Environment, deployment_dir: &Path, command: &str) -> Result<(), String> {
+    if !APPLYING_RUNS.contains(&command) {
+        return Ok(());
+    }
+    let unknown = |e: String| format!("Can't tell whether plan-only mode is on, so {} is refused: {}", command, e);
+    let app_plan_only = super::settings::try_load(env).map_err(unknown)?.plan_only;
+    let meta = try_read_meta(deployment_dir).map_err(unknown)?;
+    let scope = if app_plan_only {
+        "app"
+    } else if meta.is_some_and(|m| m.run_settings.plan_only) {
+        "deployment"
+    } else {
+        return Ok(());
+    };
+    Err(format!(
+        "Plan-only mode is on for this {}: run {} from CI. Plans can still be run here.",
+        scope, command
+    ))
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Advanced run settings of a deployment, with what may be configured.
+#[tauri::command]
+pub fn get_run_settings(app: AppHandle, deployment_name: String) -> Result<RunSettingsView, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    Ok(RunSettingsView {
+        settings: read_meta(&deployment_dir).map(|m| m.run_settings).unwrap_or_default(),
+        allowed_args: ALLOWED_ARGS,
+        allowed_env: ALLOWED_ENV,
+        provider_toggles: PROVIDER_TOGGLES,
+    })
+}
+
+/// Validate and store advanced run settings for a deployment.
+#[tauri::command]
+pub fn save_run_settings(app: AppHandle, deployment_name: String, settings: RunSettings) -> Result<RunSettings, String> {
+    let safe_name = sanitize_deployment_name(&deployment_name)?;
+    let deployment_dir = get_deployments_dir(&app)?.join(&safe_name);
+    save_settings(&deployment_dir, settings)
+}
+
+fn save_settings(deployment_dir: &Path, mut settings: RunSettings) -> Result<RunSettings, String> {
+    settings.extra_args = settings.extra_args.iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect();
+    settings.provider_toggles.sort();
+    settings.provider_toggles.dedup();
+    let problems = validate(&settings);
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+
+    let mut meta = read_meta(deployment_dir).ok_or("Save the deployment configuration before changing run settings")?;
+    meta.run_settings = settings.clone();
+    write_meta(deployment_dir, &meta)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_and_variables_are_checked() {
+        for ok in ["-parallelism=5", "-lock-timeout=5m", "-refresh=false", "-upgrade"] {
+            assert!(arg_spec(ok).is_ok(), "{}", ok);
+        }
+        assert!(arg_spec("-parallelism=0").unwrap_err().contains("1 to 256"));
+        assert!(arg_spec("-lock-timeout=soon").is_err());
+        assert!(arg_spec("-upgrade=true").is_err());
+        assert!(arg_spec("-var=admin=true").unwrap_err().contains("not allowed"));
+        // Targeting is one-shot and can't be saved
+        assert!(arg_spec("-target=module.vpc.aws_subnet.private[0]").unwrap_err().contains("not allowed"));
+
+        let settings = RunSettings {
+            extra_args: vec!["-chdir=/tmp".into()],
+            env: BTreeMap::from([
+                ("TF_LOG".to_string(), "debug".to_string()),
+                ("ARM_CLIENT_SECRET".to_string(), "x".to_string()),
+                ("TF_LOG_PROVIDER".to_string(), "LOUD".to_string()),
+            ]),
+            provider_toggles: vec!["aws_disable_imds".into(), "nope".into()],
+            plan_only: false,
+        };
+        let problems = validate(&settings);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+    }
+
+    #[test]
+    fn targets_are_checked_per_run() {
+        let targets = RunTargets {
+            target: vec![" module.vpc.aws_subnet.private[0] ".into()],
+            replace: vec!["aws_instance.web[\"a\"]".into()],
+        };
+        assert_eq!(
+            targets.args("apply").unwrap(),
+            vec!["-target=module.vpc.aws_subnet.private[0]", "-replace=aws_instance.web[\"a\"]"]
+        );
+        assert!(targets.args("destroy").unwrap_err().contains("replaced"));
+        assert!(targets.args("init").is_err());
+        assert!(RunTargets::default().args("init").unwrap().is_empty());
+
+        let bad = RunTargets { target: vec!["a; rm -rf /".into()], ..Default::default() };
+        assert!(bad.args("plan").unwrap_err().contains("resource address"));
+    }
+
+    #[test]
+    fn saved_settings_apply_per_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = RunSettings {
+            extra_args: vec![" -parallelism=5 ".into(), "-upgrade".into(), "-lock-timeout=30s".into()],
+            env: BTreeMap::from([("TF_LOG".to_string(), "DEBUG".to_string())]),
+            provider_toggles: vec!["azure_skip_provider_registration".into()],
+            plan_only: false,
+        };
+        assert!(save_settings(dir.path(), settings.clone()).unwrap_err().contains("Save the deployment"));
+
+        super::super::audit::record_template(dir.path(), "azure-simple").unwrap();
+        save_settings(dir.path(), settings).unwrap();
+        // Re-saving the template keeps the settings
+        super::super::audit::record_template(dir.path(), "azure-simple").unwrap();
+
+        let (args, env) = run_overrides(dir.path(), "apply");
+        assert_eq!(args, vec!["-parallelism=5", "-lock-timeout=30s"]);
+        let (init_args, _) = run_overrides(dir.path(), "init");
+        assert_eq!(init_args, vec!["-upgrade", "-lock-timeout=30s"]);
+
+        let value = |name: &str| env.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+        assert_eq!(value("ARM_RESOURCE_PROVIDER_REGISTRATIONS").as_deref(), Some("none"));
+        assert_eq!(value("TF_LOG").as_deref(), Some("DEBUG"));
+    }
+
+    #[test]
+    fn plan_only_blocks_apply_and_destroy() {
+        let data = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(data.path());
+        let dir = data.path().join("deployments").join("prod-ws");
+        std::fs::create_dir_all(&dir).unwrap();
+        super::super::audit::record_template(&dir, "aws-simple").unwrap();
+        assert!(ensure_command_allowed(&env, &dir, "apply").is_ok());
+
+        save_settings(&dir, RunSettings { plan_only: true, ..Default::default() }).unwrap();
+        for command in ["init", "plan"] {
+            assert!(ensure_command_allowed(&env, &dir, command).is_ok());
+        }
+        let err = ensure_command_allowed(&env, &dir, "destroy").unwrap_err();
+        assert!(err.contains("this deployment") && err.contains("destroy"));
+
+        save_settings(&dir, RunSettings::default()).unwrap();
+        std::fs::write(data.path().join("settings.json"), r#"{"plan_only": true}"#).unwrap();
+        assert!(ensure_command_allowed(&env, &dir, "apply").unwrap_err().contains("this app"));
+    }
+
+    #[test]
+    fn unreadable_settings_refuse_apply() {
+        let data = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(data.path());
+        let dir = data.path().join("deployments").join("prod-ws");
+        std::fs::create_dir_all(&dir).unwrap();
+        super::super::audit::record_template(&dir, "aws-simple").unwrap();
+
+        std::fs::write(data.path().join("settings.json"), "{ not json").unwrap();
+        assert!(ensure_command_allowed(&env, &dir, "apply").unwrap_err().contains("refused"));
+        assert!(ensure_command_allowed(&env, &dir, "plan").is_ok());
+
+        std::fs::remove_file(data.path().join("settings.json")).unwrap();
+        std::fs::write(dir.join(".deployer-meta.json"), "garbage").unwrap();
+        assert!(ensure_command_allowed(&env, &dir, "destroy").unwrap_err().contains("refused"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Sensitive Terraform variables kept out of `terraform.tfvars`.
+//!
+//! Variables marked `sensitive` in `variables.tf` are not written to
+//! `terraform.tfvars` when a configuration is saved. Their values are
+//! encrypted into `<deployment>/.deployer-secrets.json`, with the key kept in
+//! the app data directory like the other secrets stored at rest, and passed
+//! to Terraform as `TF_VAR_<name>` environment variables on every run.
+
+use crate::crypto;
+use crate::storage::Environment;
+use crate::terraform::{self, TerraformVariable};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Encrypted sensitive values of a deployment, by variable name.
+pub(crate) const SECRETS_FILE: &str = ".deployer-secrets.json";
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn load_key(env: &dyn Environment) -> Result<[u8; 32], String> {
+    crypto::load_or_create_key(&env.data_file("secrets-keyfile")?)
+}
+
+fn is_complex_type(var_type: &str) -> bool {
+    let var_type = var_type.trim().to_lowercase();
+    ["map", "list", "set", "object", "tuple"].iter().any(|t| var_type.starts_with(t))
+}
+
+/// `TF_VAR_*` value for `value`: the raw string for primitive types, an HCL
+/// literal for maps, lists and objects. `None` when there is nothing to pass.
+fn tf_var_value(var: &TerraformVariable, value: &serde_json::Value) -> Option<String> {
+    let values = HashMap::from([(var.name.clone(), value.clone())]);
+    let line = terraform::generate_tfvars(&values, std::slice::from_ref(var));
+    let (_, literal) = line.split_once(" = ")?;
+    match value {
+        serde_json::Value::String(s) if !is_complex_type(&var.var_type) => {
+            Some(s.clone()).filter(|s| !s.is_empty())
+        }
+        _ => Some(literal.to_string()),
+    }
+}
+
+/// Remove the values of sensitive variables from `values`, returning them
+/// formatted for `TF_VAR_*`.
+pub(crate) fn split_sensitive(
+    values: &mut HashMap<String, serde_json::Value>,
+    variables: &[TerraformVariable],
+) -> BTreeMap<String, String> {
+    variables
+        .iter()
+        .filter(|var| var.sensitive)
+        .filter_map(|var| {
+            let value = values.remove(&var.name)?;
+            tf_var_value(var, &value).map(|v| (var.name.clone(), v))
+        })
+        .collect()
+}
+
+/// Replace the stored sensitive values of a deployment.
+pub(crate) fn write_secrets(
+    env: &dyn Environment,
+    deployment_dir: &Path,
+    secrets: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let path = deployment_dir.join(SECRETS_FILE);
+    if secrets.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove stored secrets: {}", e))?;
+        }
+        return Ok(());
+    }
+
+    let key = load_key(env)?;
+    let encrypted = secrets
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), crypto::encrypt(value, &key)?)))
+        .collect::<Result<BTreeMap<String, String>, String>>()?;
+    let json = serde_json::to_string_pretty(&encrypted).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to store secrets: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+fn read_encrypted(deployment_dir: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(deployment_dir.join(SECRETS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Names of the variables with a stored value. Does not need the key.
+pub(crate) fn stored_names(deployment_dir: &Path) -> Vec<String> {
+    read_encrypted(deployment_dir).into_keys().collect()
+}
+
+/// `TF_VAR_*` environment variables for a deployment's stored sensitive values.
+pub(crate) fn tf_var_env(env: &dyn Environment, deployment_dir: &Path) -> Result<HashMap<String, String>, String> {
+    let encrypted = read_encrypted(deployment_dir);
+    if encrypted.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let key = load_key(env)?;
+    encrypted
+        .into_iter()
+        .map(|(name, value)| {
+            let plain = crypto::decrypt(&value, &key)
+                .map_err(|e| format!("Failed to read stored value of '{}': {}", name, e))?;
+            Ok((format!("TF_VAR_{}", name), plain))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+    use serde_json::json;
+
+    const VARIABLES: &str = r#"
+variable "prefix" {
+  type = string
+}
+variable "databricks_client_secret" {
+  type      = string
+  default   = ""
+  sensitive = true
+}
+variable "enable_csp" {
+  type      = bool
+  sensitive = true
+}
+variable "extra" {
+  type      = map(string)
+  default   = {}
+  sensitive = true
+}
+variable "unused_secret" {
+  type      = string
+  default   = ""
+  sensitive = true
+}
+"#;
+
+    #[test]
+    fn sensitive_values_move_to_tf_vars() {
+        let variables = terraform::parse_variables_tf(VARIABLES).unwrap();
+        let mut values = HashMap::from([
+            ("prefix".to_string(), json!("demo")),
+            ("databricks_client_secret".to_string(), json!("s3cr\"et")),
+            ("enable_csp".to_string(), json!("true")),
+            ("extra".to_string(), json!({"a": "b"})),
+            ("unused_secret".to_string(), json!("")),
+        ]);
+
+        let secrets = split_sensitive(&mut values, &variables);
+        assert_eq!(values.keys().collect::<Vec<_>>(), vec!["prefix"]);
+        assert_eq!(secrets["databricks_client_secret"], "s3cr\"et");
+        assert_eq!(secrets["enable_csp"], "true");
+        assert_eq!(secrets["extra"], "{\n  \"a\" = \"b\"\n}");
+        assert!(!secrets.contains_key("unused_secret"));
+        assert!(!terraform::generate_tfvars(&values, &variables).contains("s3cr"));
+    }
+
+    #[test]
+    fn secrets_round_trip_encrypted() {
+        let data = tempfile::tempdir().unwrap();
+        let deployment = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(data.path());
+        let secrets = BTreeMap::from([("databricks_client_secret".to_string(), "hunter2".to_string())]);
+
+        write_secrets(&paths, deployment.path(), &secrets).unwrap();
+        let stored = fs::read_to_string(deployment.path().join(SECRETS_FILE)).unwrap();
+        assert!(!stored.contains("hunter2"));
+        assert_eq!(stored_names(deployment.path()), vec!["databricks_client_secret".to_string()]);
+
+        let env_vars = tf_var_env(&paths, deployment.path()).unwrap();
+        assert_eq!(env_vars["TF_VAR_databricks_client_secret"], "hunter2");
+
+        write_secrets(&paths, deployment.path(), &BTreeMap::new()).unwrap();
+        assert!(!deployment.path().join(SECRETS_FILE).exists());
+        assert!(tf_var_env(&paths, deployment.path()).unwrap().is_empty());
+    }
+}
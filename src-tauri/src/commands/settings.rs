@@ -0,0 +1,415 @@
+//! Application-wide settings.
+//!
+//! Preferences that apply across deployments (default cloud and region,
+//! telemetry opt-in and endpoint, proxy, Terraform version policy,
+//! notifications, organization tag policy, extra dependency search paths,
+//! whether cloud metadata is cached on disk and plan-only mode) live in a
+//! single `settings.json` in the data directory. GitHub, Git hosting and
+//! assistant settings are sections of the same file, read and written by
+//! their own modules through [`load_section`] and [`save_section`]: they
+//! hold credentials encrypted with separate key files and are not part of
+//! [`AppSettings`]. Files written by older versions are folded in on load
+//! and then removed.
+//!
+//! A `settings.json` that can't be read is an error, never replaced by
+//! defaults, so a bad edit can't wipe stored credentials.
+
+use super::debug_log;
+use super::metadata_cache;
+use super::notifications::NotificationSettings;
+use super::tagging::TagSettings;
+use crate::dependencies::{self, TerraformVersionPolicy};
+use crate::proxy::{self, ProxySettings};
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const SETTINGS_FILE: &str = "settings.json";
+const LEGACY_DEPENDENCY_FILE: &str = "dependency-settings.json";
+const LEGACY_NOTIFICATION_FILE: &str = "notification-settings.json";
+/// Sections kept by other modules, and the files they used before.
+const LEGACY_SECTION_FILES: &[(&str, &str)] = &[
+    ("github", "github-settings.json"),
+    ("git_hosting", "git-hosting-settings.json"),
+    ("assistant", "assistant-settings.json"),
+];
+
+const CLOUDS: &[&str] = &["aws", "azure", "gcp"];
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write updates of the settings file.
+    static ref SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Persisted application settings. Missing fields take their defaults, so
+/// files from older versions load unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Cloud preselected in the template picker ("aws", "azure" or "gcp").
+    pub default_cloud: Option<String>,
+    /// Region prefilled in new deployments.
+    pub default_region: Option<String>,
+    /// Whether anonymous usage data may be sent. Off until the user opts in.
+    pub telemetry_opt_in: bool,
+    /// Where telemetry batches are posted; nothing is sent without one.
+    pub telemetry_endpoint: Option<String>,
+    pub proxy: ProxySettings,
+    pub terraform_version_policy: TerraformVersionPolicy,
+    pub notifications: NotificationSettings,
+    /// Tags required in, and prefilled for, every deployment.
+    pub tags: TagSettings,
+    /// Extra directories searched for CLI binaries before built-in locations.
+    pub dependency_search_paths: Vec<String>,
+    /// Keep cached subscription, region and metastore lists on disk so they
+    /// survive a restart (see [`super::metadata_cache`]).
+    pub persist_metadata_cache: bool,
+    /// Refuse apply and destroy for every deployment, e.g. when changes must
+    /// go through CI (see [`super::run_settings::ensure_command_allowed`]).
+    pub plan_only: bool,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok())
+}
+
+type Document = serde_json::Map<String, serde_json::Value>;
+
+/// Fold settings files from older versions into `document`. Returns the
+/// files that were migrated.
+fn migrate_legacy(data_dir: &Path, document: &mut Document) -> Vec<PathBuf> {
+    #[derive(Deserialize)]
+    struct LegacyDependencySettings {
+        #[serde(default)]
+        extra_search_paths: Vec<String>,
+    }
+
+    let mut migrated = Vec::new();
+
+    let path = data_dir.join(LEGACY_DEPENDENCY_FILE);
+    if let Some(legacy) = read_json::<LegacyDependencySettings>(&path) {
+        document.insert("dependency_search_paths".to_string(), legacy.extra_search_paths.into());
+        migrated.push(path);
+    }
+    let path = data_dir.join(LEGACY_NOTIFICATION_FILE);
+    if let Some(legacy) = read_json::<NotificationSettings>(&path) {
+        document.insert("notifications".to_string(), serde_json::to_value(legacy).unwrap_or_default());
+        migrated.push(path);
+    }
+    for (section, file) in LEGACY_SECTION_FILES {
+        let path = data_dir.join(file);
+        if let Some(legacy) = read_json::<serde_json::Value>(&path) {
+            document.entry(section.to_string()).or_insert(legacy);
+            migrated.push(path);
+        }
+    }
+
+    migrated
+}
+
+/// Read `settings.json` as a JSON object; empty when it doesn't exist.
+fn read_document(path: &Path) -> Result<Document, String> {
+    if !path.exists() {
+        return Ok(Document::new());
+    }
+    let unreadable = |e: String| format!("Settings file {} is unreadable ({}); fix or remove it", path.display(), e);
+    let content = fs::read_to_string(path).map_err(|e| unreadable(e.to_string()))?;
+    match serde_json::from_str(&content).map_err(|e| unreadable(e.to_string()))? {
+        serde_json::Value::Object(document) => Ok(document),
+        _ => Err(unreadable("not a JSON object".to_string())),
+    }
+}
+
+fn write_document(env: &dyn Environment, document: &Document) -> Result<(), String> {
+    let path = env.data_file(SETTINGS_FILE)?;
+    let content = serde_json::to_string_pretty(document).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+/// Read the settings file, migrating files from older versions on the way.
+/// Callers hold `SETTINGS_LOCK`.
+fn load_document(env: &dyn Environment) -> Result<Document, String> {
+    let data_dir = env.data_dir()?;
+    let mut document = read_document(&data_dir.join(SETTINGS_FILE))?;
+
+    let migrated = migrate_legacy(&data_dir, &mut document);
+    if !migrated.is_empty() {
+        match write_document(env, &document) {
+            Ok(()) => {
+                for path in migrated {
+                    let _ = fs::remove_file(path);
+                }
+            }
+            Err(_e) => {
+                debug_log!("[settings] Failed to migrate legacy settings: {}", _e);
+            }
+        }
+    }
+
+    Ok(document)
+}
+
+/// Save `settings`, keeping the other modules' sections.
+fn save(env: &dyn Environment, settings: &AppSettings) -> Result<(), String> {
+    let mut document = load_document(env)?;
+    if let serde_json::Value::Object(fields) = serde_json::to_value(settings).map_err(|e| e.to_string())? {
+        document.extend(fields);
+    }
+    write_document(env, &document)
+}
+
+/// Load the settings. An unreadable file is logged and read as defaults;
+/// nothing is saved over it.
+pub(crate) fn load(env: &dyn Environment) -> AppSettings {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    load_locked(env).unwrap_or_else(|_e| {
+        debug_log!("[settings] Using defaults: {}", _e);
+        AppSettings::default()
+    })
+}
+
+/// [`load`], failing when the file can't be read instead of falling back to
+/// the defaults. For checks that must not fail open.
+pub(crate) fn try_load(env: &dyn Environment) -> Result<AppSettings, String> {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    load_locked(env)
+}
+
+/// [`load`] for callers already holding `SETTINGS_LOCK`, failing when the
+/// file can't be read.
+fn load_locked(env: &dyn Environment) -> Result<AppSettings, String> {
+    let document = load_document(env)?;
+    serde_json::from_value(serde_json::Value::Object(document)).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+/// Read a module's own section of the settings file (defaults when absent).
+pub(crate) fn load_section<T: serde::de::DeserializeOwned + Default>(
+    env: &dyn Environment,
+    section: &str,
+) -> Result<T, String> {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    match load_document(env)?.remove(section) {
+        Some(value) => serde_json::from_value(value).map_err(|e| format!("Failed to parse {} settings: {}", section, e)),
+        None => Ok(T::default()),
+    }
+}
+
+/// Replace a module's own section of the settings file.
+pub(crate) fn save_section<T: Serialize>(env: &dyn Environment, section: &str, value: &T) -> Result<(), String> {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    let mut document = load_document(env)?;
+    let value = serde_json::to_value(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    document.insert(section.to_string(), value);
+    write_document(env, &document)
+}
+
+/// Check and normalize settings before they are saved.
+fn validate(mut settings: AppSettings) -> Result<AppSettings, String> {
+    settings.default_cloud = settings
+        .default_cloud
+        .map(|cloud| cloud.trim().to_lowercase())
+        .filter(|cloud| !cloud.is_empty());
+    if let Some(cloud) = &settings.default_cloud {
+        if !CLOUDS.contains(&cloud.as_str()) {
+            return Err(format!("Unknown cloud '{}': expected one of {}", cloud, CLOUDS.join(", ")));
+        }
+    }
+    settings.default_region = settings
+        .default_region
+        .map(|region| region.trim().to_string())
+        .filter(|region| !region.is_empty());
+
+    settings.telemetry_endpoint = settings
+        .telemetry_endpoint
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &settings.telemetry_endpoint {
+        if !url.starts_with("https://") {
+            return Err(format!("Telemetry endpoint must use https://: {}", url));
+        }
+    }
+
+    settings.proxy.validate()?;
+    settings.terraform_version_policy.validate()?;
+    settings.tags.validate()?;
+    settings.dependency_search_paths = dependencies::normalize_search_paths(&settings.dependency_search_paths)?
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+
+    Ok(settings)
+}
+
+/// Push the settings that other modules read from process-wide state.
+fn apply(env: &dyn Environment, settings: &AppSettings) {
+    match dependencies::normalize_search_paths(&settings.dependency_search_paths) {
+        Ok(paths) => dependencies::set_extra_search_paths(paths),
+        Err(_e) => {
+            debug_log!("[settings] Ignoring invalid search paths: {}", _e);
+        }
+    }
+    proxy::set_proxy_settings(settings.proxy.clone());
+    dependencies::set_terraform_version_policy(settings.terraform_version_policy.clone());
+    let cache_file = settings
+        .persist_metadata_cache
+        .then(|| env.data_file(metadata_cache::CACHE_FILE).ok())
+        .flatten();
+    metadata_cache::set_cache_file(cache_file);
+}
+
+/// Apply the persisted settings. Called once at startup.
+pub fn apply_settings_at_startup(app: &AppHandle) {
+    apply(app, &load(app));
+}
+
+/// Change the settings with `edit`, then validate, save and apply them.
+pub(crate) fn update(
+    env: &dyn Environment,
+    edit: impl FnOnce(AppSettings) -> Result<AppSettings, String>,
+) -> Result<AppSettings, String> {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    let settings = validate(edit(load_locked(env)?)?)?;
+    save(env, &settings)?;
+    apply(env, &settings);
+    Ok(settings)
+}
+
+/// Merge a partial JSON object into `target`: objects merge key by key,
+/// anything else (including `null`) replaces the existing value.
+fn merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_patch(target.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Apply a partial update (as sent by the UI) to `settings`.
+fn apply_patch(settings: &AppSettings, patch: serde_json::Value) -> Result<AppSettings, String> {
+    if !patch.is_object() {
+        return Err("Settings update must be a JSON object".to_string());
+    }
+    let mut merged = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    merge_patch(&mut merged, patch);
+    serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Get the application settings.
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    let _guard = super::lock_or_recover(&SETTINGS_LOCK);
+    load_locked(&app)
+}
+
+/// Update the application settings. `patch` only needs the fields being
+/// changed; nested objects such as `proxy` may be partial too. Returns the
+/// saved (normalized) settings.
+#[tauri::command]
+pub fn update_settings(app: AppHandle, patch: serde_json::Value) -> Result<AppSettings, String> {
+    update(&app, |settings| apply_patch(&settings, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxyMode;
+    use crate::storage::StoragePaths;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_files_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        fs::write(dir.path().join(LEGACY_DEPENDENCY_FILE), r#"{"extra_search_paths": ["/opt/tools/bin"]}"#).unwrap();
+        fs::write(dir.path().join(LEGACY_NOTIFICATION_FILE), r#"{"on_success": false}"#).unwrap();
+        fs::write(dir.path().join("github-settings.json"), r#"{"github_token": "enc"}"#).unwrap();
+
+        let settings = load(&paths);
+        assert_eq!(settings.dependency_search_paths, vec!["/opt/tools/bin".to_string()]);
+        assert!(!settings.notifications.on_success && settings.notifications.on_failure);
+        assert!(!settings.telemetry_opt_in);
+
+        assert!(!dir.path().join(LEGACY_DEPENDENCY_FILE).exists());
+        assert!(!dir.path().join(LEGACY_NOTIFICATION_FILE).exists());
+        assert!(!dir.path().join("github-settings.json").exists());
+        assert_eq!(read_json::<AppSettings>(&dir.path().join(SETTINGS_FILE)), Some(settings.clone()));
+        assert_eq!(load(&paths), settings);
+
+        // Sections survive updates of the app settings, and the reverse
+        update(&paths, |s| Ok(AppSettings { plan_only: true, ..s })).unwrap();
+        let github: serde_json::Value = load_section(&paths, "github").unwrap();
+        assert_eq!(github, json!({"github_token": "enc"}));
+        save_section(&paths, "github", &json!({"github_token": "other"})).unwrap();
+        assert!(load(&paths).plan_only);
+        assert_eq!(load_section::<serde_json::Value>(&paths, "assistant").unwrap(), serde_json::Value::Null);
+    }
+
+    #[test]
+    fn unreadable_file_is_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        let settings_path = dir.path().join(SETTINGS_FILE);
+        fs::write(&settings_path, "{ not json").unwrap();
+        fs::write(dir.path().join(LEGACY_NOTIFICATION_FILE), r#"{"on_success": false}"#).unwrap();
+
+        assert_eq!(load(&paths), AppSettings::default());
+        assert!(update(&paths, |s| Ok(AppSettings { plan_only: true, ..s })).is_err());
+        assert!(save_section(&paths, "github", &json!({})).is_err());
+        assert!(load_section::<serde_json::Value>(&paths, "github").is_err());
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), "{ not json");
+        assert!(dir.path().join(LEGACY_NOTIFICATION_FILE).exists());
+    }
+
+    #[test]
+    fn patches_merge_nested_fields() {
+        let mut settings = AppSettings::default();
+        settings.proxy.no_proxy = "localhost".to_string();
+
+        let patched = apply_patch(
+            &settings,
+            json!({"default_cloud": "azure", "proxy": {"mode": "manual", "url": "http://proxy:3128"}}),
+        )
+        .unwrap();
+        assert_eq!(patched.default_cloud.as_deref(), Some("azure"));
+        assert_eq!(patched.proxy.mode, ProxyMode::Manual);
+        assert_eq!(patched.proxy.no_proxy, "localhost");
+        assert_eq!(patched.notifications, settings.notifications);
+
+        let cleared = apply_patch(&patched, json!({"default_cloud": null})).unwrap();
+        assert!(cleared.default_cloud.is_none());
+        assert!(apply_patch(&settings, json!({"telemetry_opt_in": "yes"})).is_err());
+        assert!(apply_patch(&settings, json!([1])).is_err());
+    }
+
+    #[test]
+    fn validation_normalizes_and_rejects() {
+        let settings = AppSettings {
+            default_cloud: Some(" GCP ".to_string()),
+            default_region: Some("  ".to_string()),
+            ..Default::default()
+        };
+        let valid = validate(settings).unwrap();
+        assert_eq!(valid.default_cloud.as_deref(), Some("gcp"));
+        assert!(valid.default_region.is_none());
+
+        let http_endpoint = AppSettings { telemetry_endpoint: Some("http://example.com".to_string()), ..Default::default() };
+        assert!(validate(http_endpoint).is_err());
+        let bad_cloud = AppSettings { default_cloud: Some("oracle".to_string()), ..Default::default() };
+        assert!(validate(bad_cloud).is_err());
+        let bad_paths = AppSettings { dependency_search_paths: vec!["relative/bin".to_string()], ..Default::default() };
+        assert!(validate(bad_paths).is_err());
+        let mut bad_tags = AppSettings::default();
+        bad_tags.tags.required = vec![" ".to_string()];
+        assert!(validate(bad_tags).is_err());
+    }
+}
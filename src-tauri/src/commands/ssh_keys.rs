@@ -0,0 +1,307 @@
+//! SSH key management for git remotes.
+//!
+//! Detects existing keys in `~/.ssh`, generates an app-scoped ed25519 key in
+//! the app data directory, and registers it with GitHub so users without an
+//! HTTPS token can still push over SSH. Deployment repos with an SSH remote are
+//! configured to offer the app key via `core.sshCommand`, which also pins
+//! GitHub's published host keys instead of trusting whatever host answers
+//! first.
+
+use super::debug_log;
+use super::github::get_decrypted_token;
+use super::http_client;
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// An SSH public key found on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshKeyInfo {
+    pub path: String,
+    pub key_type: String,
+    pub comment: String,
+    pub fingerprint: Option<String>,
+    /// True for the key generated by this app (stored in app data).
+    pub app_managed: bool,
+}
+
+const APP_KEY_COMMENT: &str = "databricks-deployer";
+
+/// GitHub's published SSH host keys ("GitHub's SSH key fingerprints" in the
+/// GitHub docs), written to the app's own known_hosts file.
+const GITHUB_KNOWN_HOSTS: &str = "\
+github.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl
+github.com ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBEmKSENjQEezOmxkZMy7opKgwFB9nkt5YRrYMjNuG5N87uRgg6CLrbo5wAdT/y6v0mKV0U2w0WZ2YB/++Tpockg=
+";
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+/// Path of the app-scoped private key (the public key has a `.pub` suffix).
+fn app_key_path(env: &dyn Environment) -> Result<PathBuf, String> {
+    let ssh_dir = env.data_dir()?.join("ssh");
+    fs::create_dir_all(&ssh_dir).map_err(|e| e.to_string())?;
+    Ok(ssh_dir.join("id_ed25519"))
+}
+
+fn pub_path(private_key: &Path) -> PathBuf {
+    let mut name = private_key.as_os_str().to_owned();
+    name.push(".pub");
+    PathBuf::from(name)
+}
+
+/// Whether a git remote URL uses SSH transport.
+pub(super) fn is_ssh_remote(url: &str) -> bool {
+    url.starts_with("ssh://")
+        || (!url.contains("://") && url.contains('@') && url.contains(':'))
+}
+
+/// Write the pinned GitHub host keys next to the app key and return the file.
+fn write_known_hosts(key: &Path) -> Result<PathBuf, String> {
+    let path = key.with_file_name("known_hosts");
+    fs::write(&path, GITHUB_KNOWN_HOSTS).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Build the `core.sshCommand` value that offers the given key in addition to
+/// the user's agent/default keys. Host keys must already be known: GitHub's
+/// from the pinned `known_hosts`, other hosts from the user's own. Paths use
+/// forward slashes so Git for Windows' shell handles them.
+fn ssh_command_for_key(key: &Path, known_hosts: &Path) -> String {
+    let forward = |p: &Path| p.display().to_string().replace('\\', "/");
+    format!(
+        "ssh -i \"{}\" -o StrictHostKeyChecking=yes -o 'UserKnownHostsFile \"{}\" ~/.ssh/known_hosts'",
+        forward(key),
+        forward(known_hosts)
+    )
+}
+
+/// `core.sshCommand` for the app key, if one has been generated.
+pub(super) fn app_ssh_command(env: &dyn Environment) -> Option<String> {
+    let key = app_key_path(env).ok()?;
+    if !key.exists() {
+        return None;
+    }
+    match write_known_hosts(&key) {
+        Ok(known_hosts) => Some(ssh_command_for_key(&key, &known_hosts)),
+        Err(_e) => {
+            debug_log!("[ssh] {}", _e);
+            None
+        }
+    }
+}
+
+/// Parse an OpenSSH public key line: `<type> <base64> [comment]`.
+fn parse_public_key_line(line: &str) -> Option<(String, String)> {
+    let mut parts = line.split_whitespace();
+    let key_type = parts.next()?;
+    let _body = parts.next()?;
+    if !(key_type.starts_with("ssh-") || key_type.starts_with("ecdsa-") || key_type.starts_with("sk-")) {
+        return None;
+    }
+    let comment = parts.collect::<Vec<_>>().join(" ");
+    Some((key_type.to_string(), comment))
+}
+
+/// Extract the fingerprint from `ssh-keygen -lf` output
+/// (e.g. `256 SHA256:abc... comment (ED25519)`).
+fn parse_fingerprint(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find(|p| p.starts_with("SHA256:") || p.starts_with("MD5:"))
+        .map(|s| s.to_string())
+}
+
+fn fingerprint(pub_key: &Path) -> Option<String> {
+    let output = super::silent_cmd("ssh-keygen")
+        .arg("-lf")
+        .arg(pub_key)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_fingerprint(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn read_key_info(pub_key: &Path, app_managed: bool) -> Option<SshKeyInfo> {
+    let content = fs::read_to_string(pub_key).ok()?;
+    let (key_type, comment) = parse_public_key_line(content.lines().next()?)?;
+    Some(SshKeyInfo {
+        path: pub_key.display().to_string(),
+        key_type,
+        comment,
+        fingerprint: fingerprint(pub_key),
+        app_managed,
+    })
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List SSH public keys in `~/.ssh` plus the app-managed key, if present.
+#[tauri::command]
+pub fn ssh_list_keys(app: AppHandle) -> Result<Vec<SshKeyInfo>, String> {
+    let mut keys = Vec::new();
+
+    if let Some(home) = dirs::home_dir() {
+        if let Ok(entries) = fs::read_dir(home.join(".ssh")) {
+            let mut paths: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("pub"))
+                .collect();
+            paths.sort();
+            keys.extend(paths.iter().filter_map(|p| read_key_info(p, false)));
+        }
+    }
+
+    let app_pub = pub_path(&app_key_path(&app)?);
+    if app_pub.exists() {
+        if let Some(info) = read_key_info(&app_pub, true) {
+            keys.push(info);
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Generate the app-scoped ed25519 key. Returns the existing key if already generated.
+#[tauri::command]
+pub fn ssh_generate_key(app: AppHandle) -> Result<SshKeyInfo, String> {
+    let key_path = app_key_path(&app)?;
+    let public = pub_path(&key_path);
+
+    if !key_path.exists() {
+        let output = super::silent_cmd("ssh-keygen")
+            .args(["-t", "ed25519", "-N", "", "-q", "-C", APP_KEY_COMMENT, "-f"])
+            .arg(&key_path)
+            .output()
+            .map_err(|e| format!("Failed to run ssh-keygen (is OpenSSH installed?): {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("ssh-keygen failed: {}", stderr.trim()));
+        }
+        debug_log!("[ssh] Generated app SSH key at {:?}", key_path);
+    }
+
+    read_key_info(&public, true).ok_or_else(|| "Failed to read generated public key".to_string())
+}
+
+/// Register the app SSH key with the authenticated GitHub account.
+#[tauri::command]
+pub async fn github_register_ssh_key(app: AppHandle, title: Option<String>) -> Result<SshKeyInfo, String> {
+    let token = get_decrypted_token(&app)?
+        .ok_or_else(|| "Not authenticated with GitHub. Connect first.".to_string())?;
+
+    let keygen_app = app.clone();
+    let info = super::run_blocking(move || ssh_generate_key(keygen_app)).await?;
+    let public_key = tokio::fs::read_to_string(&info.path)
+        .await
+        .map_err(|e| format!("Failed to read public key: {}", e))?;
+
+    let title = title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| "Databricks Deployer".to_string());
+
+    let client = http_client()?;
+    let resp = client
+        .post("https://api.github.com/user/keys")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("User-Agent", "DatabricksDeployer/1.0")
+        .json(&serde_json::json!({ "title": title, "key": public_key.trim() }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to register SSH key: {}", e))?;
+
+    let status = resp.status();
+    if status.is_success() {
+        debug_log!("[ssh] Registered app SSH key with GitHub");
+        return Ok(info);
+    }
+
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    let already_registered = body["errors"]
+        .as_array()
+        .map(|errs| {
+            errs.iter()
+                .any(|e| e["message"].as_str().unwrap_or("").contains("already in use"))
+        })
+        .unwrap_or(false);
+
+    match status.as_u16() {
+        422 if already_registered => Ok(info),
+        403 | 404 => Err(
+            "GitHub token doesn't have permission to add SSH keys. Reconnect to GitHub.".to_string(),
+        ),
+        _ => Err(format!(
+            "Failed to register SSH key: {}",
+            body["message"].as_str().unwrap_or("Unknown error")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssh_remote_detection() {
+        assert!(is_ssh_remote("git@github.com:acme/infra.git"));
+        assert!(is_ssh_remote("ssh://git@gitlab.com/acme/infra.git"));
+        assert!(!is_ssh_remote("https://github.com/acme/infra.git"));
+        assert!(!is_ssh_remote("https://user@bitbucket.org/ws/repo.git"));
+    }
+
+    #[test]
+    fn parse_public_key_line_with_comment() {
+        let (key_type, comment) =
+            parse_public_key_line("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAA user@host").unwrap();
+        assert_eq!(key_type, "ssh-ed25519");
+        assert_eq!(comment, "user@host");
+    }
+
+    #[test]
+    fn parse_public_key_line_rejects_garbage() {
+        assert!(parse_public_key_line("not a key").is_none());
+        assert!(parse_public_key_line("").is_none());
+    }
+
+    #[test]
+    fn parse_fingerprint_sha256() {
+        assert_eq!(
+            parse_fingerprint("256 SHA256:abcDEF123 databricks-deployer (ED25519)\n").unwrap(),
+            "SHA256:abcDEF123"
+        );
+        assert!(parse_fingerprint("garbage").is_none());
+    }
+
+    #[test]
+    fn ssh_command_uses_forward_slashes() {
+        let cmd = ssh_command_for_key(
+            Path::new("C:\\Users\\me\\id_ed25519"),
+            Path::new("C:\\Users\\me\\known_hosts"),
+        );
+        assert_eq!(
+            cmd,
+            "ssh -i \"C:/Users/me/id_ed25519\" -o StrictHostKeyChecking=yes \
+             -o 'UserKnownHostsFile \"C:/Users/me/known_hosts\" ~/.ssh/known_hosts'"
+        );
+    }
+
+    #[test]
+    fn known_hosts_pins_github_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_known_hosts(&dir.path().join("id_ed25519")).unwrap();
+        assert_eq!(path, dir.path().join("known_hosts"));
+        let content = fs::read_to_string(path).unwrap();
+        assert!(content.lines().all(|l| l.starts_with("github.com ")));
+        assert!(content.contains("ssh-ed25519 "));
+    }
+
+    #[test]
+    fn pub_path_appends_suffix() {
+        assert_eq!(pub_path(Path::new("/tmp/id_ed25519")), PathBuf::from("/tmp/id_ed25519.pub"));
+    }
+}
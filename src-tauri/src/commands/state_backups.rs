@@ -0,0 +1,280 @@
+//! Local Terraform state snapshots.
+//!
+//! Before every apply/destroy, `terraform.tfstate` is copied into
+//! `<deployment>/.state-backups/` so a corrupted or overwritten state can be
+//! restored. Only the newest [`MAX_BACKUPS`] snapshots are kept.
+
+use super::{debug_log, get_deployments_dir, lock_or_recover, sanitize_deployment_name, state_encryption};
+use crate::terraform::DEPLOYMENT_STATUS;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Snapshot directory inside a deployment.
+pub(crate) const BACKUP_DIR: &str = ".state-backups";
+
+const STATE_FILE: &str = "terraform.tfstate";
+
+/// Snapshots kept per deployment.
+const MAX_BACKUPS: usize = 20;
+
+/// A saved copy of `terraform.tfstate`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StateBackup {
+    /// File name inside `.state-backups/`, used to restore it.
+    pub id: String,
+    /// Unix timestamp (milliseconds).
+    pub created_at: u64,
+    /// Command the snapshot was taken before ("apply", "destroy", "restore").
+    pub command: String,
+    pub size_bytes: u64,
+    /// Terraform state serial, if readable.
+    pub serial: Option<u64>,
+    pub resource_count: usize,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `<millis>-<command>.tfstate` → (millis, command).
+fn parse_backup_name(name: &str) -> Option<(u64, String)> {
+    let stem = name.strip_suffix(".tfstate")?;
+    let (millis, command) = stem.split_once('-')?;
+    if command.is_empty() || !command.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some((millis.parse().ok()?, command.to_string()))
+}
+
+fn describe_backup(path: &Path) -> Option<StateBackup> {
+    let id = path.file_name()?.to_str()?.to_string();
+    let (created_at, command) = parse_backup_name(&id)?;
+    let content = fs::read_to_string(path).ok()?;
+    let (serial, resource_count) = state_encryption::envelope_summary(&content).unwrap_or_else(|| {
+        let state: serde_json::Value = serde_json::from_str(&content).unwrap_or_default();
+        (state["serial"].as_u64(), state["resources"].as_array().map_or(0, Vec::len))
+    });
+    Some(StateBackup {
+        id,
+        created_at,
+        command,
+        size_bytes: content.len() as u64,
+        serial,
+        resource_count,
+    })
+}
+
+/// Snapshots for a deployment, newest first.
+pub(crate) fn list_backups(deployment_dir: &Path) -> Vec<StateBackup> {
+    let Ok(entries) = fs::read_dir(deployment_dir.join(BACKUP_DIR)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<StateBackup> = entries
+        .flatten()
+        .filter_map(|e| describe_backup(&e.path()))
+        .collect();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    backups
+}
+
+fn prune_backups(deployment_dir: &Path) {
+    let backup_dir = deployment_dir.join(BACKUP_DIR);
+    for old in list_backups(deployment_dir).iter().skip(MAX_BACKUPS) {
+        if let Err(_e) = fs::remove_file(backup_dir.join(&old.id)) {
+            debug_log!("[state_backups] Failed to prune {}: {}", old.id, _e);
+        }
+    }
+}
+
+/// Copy the current state (plaintext, or the encrypted envelope when state is
+/// encrypted at rest) into `.state-backups/` before `command` runs.
+/// Returns `None` when there is no state yet.
+pub(crate) fn backup_state(deployment_dir: &Path, command: &str) -> Result<Option<StateBackup>, String> {
+    let plain = deployment_dir.join(STATE_FILE);
+    let Some(state_path) = [plain.clone(), state_encryption::encrypted_path(&plain)]
+        .into_iter()
+        .find(|p| fs::metadata(p).is_ok_and(|m| m.len() > 0))
+    else {
+        return Ok(None);
+    };
+
+    let backup_dir = deployment_dir.join(BACKUP_DIR);
+    fs::create_dir_all(&backup_dir).map_err(|e| format!("Failed to create state backup directory: {}", e))?;
+
+    let command: String = command.chars().filter(|c| c.is_ascii_lowercase()).collect();
+    let mut millis = now_millis();
+    let mut target: PathBuf;
+    loop {
+        target = backup_dir.join(format!("{}-{}.tfstate", millis, command));
+        if !target.exists() {
+            break;
+        }
+        millis += 1;
+    }
+
+    fs::copy(&state_path, &target).map_err(|e| format!("Failed to back up state: {}", e))?;
+    prune_backups(deployment_dir);
+    Ok(describe_backup(&target))
+}
+
+/// Replace the current state with a snapshot. The current state is itself
+/// backed up first so a restore can be undone. `key` is set when the
+/// deployment's state is encrypted at rest.
+pub(crate) fn restore_backup(deployment_dir: &Path, backup_id: &str, key: Option<&[u8; 32]>) -> Result<(), String> {
+    if parse_backup_name(backup_id).is_none() || backup_id.contains(['/', '\\']) {
+        return Err("Invalid state backup ID".to_string());
+    }
+    let source = deployment_dir.join(BACKUP_DIR).join(backup_id);
+    let content = fs::read_to_string(&source).map_err(|_| "State backup not found".to_string())?;
+    serde_json::from_str::<serde_json::Value>(&content)
+        .map_err(|e| format!("State backup is not valid JSON: {}", e))?;
+
+    backup_state(deployment_dir, "restore")?;
+
+    let plain = deployment_dir.join(STATE_FILE);
+    let target = if state_encryption::is_envelope(&content) {
+        state_encryption::encrypted_path(&plain)
+    } else {
+        plain.clone()
+    };
+    let tmp = deployment_dir.join(format!("{}.restore-tmp", STATE_FILE));
+    fs::write(&tmp, &content).map_err(|e| format!("Failed to restore state: {}", e))?;
+    fs::rename(&tmp, &target).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to restore state: {}", e)
+    })?;
+
+    if target != plain {
+        // Drop leftover plaintext so it isn't preferred over the restored envelope
+        let _ = fs::remove_file(&plain);
+    } else if let Some(key) = key {
+        state_encryption::encrypt_state(deployment_dir, key)?;
+    }
+    Ok(())
+}
+
+fn deployment_dir(app: &AppHandle, deployment_name: &str) -> Result<PathBuf, String> {
+    let dir = get_deployments_dir(app)?.join(sanitize_deployment_name(deployment_name)?);
+    if !dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    Ok(dir)
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// List state snapshots for a deployment, newest first.
+#[tauri::command]
+pub fn list_state_backups(app: AppHandle, deployment_name: String) -> Result<Vec<StateBackup>, String> {
+    Ok(list_backups(&deployment_dir(&app, &deployment_name)?))
+}
+
+/// Restore a deployment's `terraform.tfstate` from a snapshot.
+#[tauri::command]
+pub fn restore_state_backup(app: AppHandle, deployment_name: String, backup_id: String) -> Result<(), String> {
+    if lock_or_recover(&DEPLOYMENT_STATUS).running {
+        return Err("Cannot restore state while a deployment is running".to_string());
+    }
+    let dir = deployment_dir(&app, &deployment_name)?;
+    let key = if state_encryption::is_enabled(&dir) {
+        Some(state_encryption::load_key(&app)?)
+    } else {
+        None
+    };
+    restore_backup(&dir, &backup_id, key.as_ref())?;
+    super::telemetry::record_feature(&app, super::telemetry::Feature::StateRestore);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_state(dir: &Path, serial: u64) {
+        let state = serde_json::json!({"version": 4, "serial": serial, "resources": [{"type": "aws_vpc"}]});
+        fs::write(dir.join(STATE_FILE), state.to_string()).unwrap();
+    }
+
+    fn current_serial(dir: &Path) -> u64 {
+        let state: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join(STATE_FILE)).unwrap()).unwrap();
+        state["serial"].as_u64().unwrap()
+    }
+
+    #[test]
+    fn parses_backup_names() {
+        assert_eq!(parse_backup_name("1767225600000-apply.tfstate"), Some((1_767_225_600_000, "apply".to_string())));
+        assert_eq!(parse_backup_name("1767225600000-apply.json"), None);
+        assert_eq!(parse_backup_name("abc-apply.tfstate"), None);
+        assert_eq!(parse_backup_name("1-../x.tfstate"), None);
+    }
+
+    #[test]
+    fn backup_skipped_without_state() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(backup_state(dir.path(), "apply").unwrap(), None);
+        assert!(!dir.path().join(BACKUP_DIR).exists());
+    }
+
+    #[test]
+    fn backup_and_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        write_state(dir.path(), 1);
+        let first = backup_state(dir.path(), "apply").unwrap().unwrap();
+        assert_eq!((first.serial, first.resource_count, first.command.as_str()), (Some(1), 1, "apply"));
+
+        write_state(dir.path(), 7);
+        restore_backup(dir.path(), &first.id, None).unwrap();
+        assert_eq!(current_serial(dir.path()), 1);
+
+        // The overwritten state was kept as a "restore" snapshot
+        let backups = list_backups(dir.path());
+        assert_eq!(backups.len(), 2);
+        assert_eq!((backups[0].command.as_str(), backups[0].serial), ("restore", Some(7)));
+    }
+
+    #[test]
+    fn restore_rejects_unknown_or_invalid_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        write_state(dir.path(), 3);
+        assert!(restore_backup(dir.path(), "../terraform.tfstate", None).is_err());
+        assert_eq!(restore_backup(dir.path(), "1-apply.tfstate", None).unwrap_err(), "State backup not found");
+        assert_eq!(current_serial(dir.path()), 3);
+    }
+
+    #[test]
+    fn keeps_only_newest_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        write_state(dir.path(), 1);
+        for _ in 0..MAX_BACKUPS + 3 {
+            backup_state(dir.path(), "apply").unwrap();
+        }
+        let backups = list_backups(dir.path());
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        assert!(backups.windows(2).all(|w| w[0].created_at >= w[1].created_at));
+    }
+
+    #[test]
+    fn backs_up_and_restores_encrypted_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = [3u8; 32];
+        write_state(dir.path(), 4);
+        state_encryption::encrypt_state(dir.path(), &key).unwrap();
+
+        let snapshot = backup_state(dir.path(), "apply").unwrap().unwrap();
+        assert_eq!((snapshot.serial, snapshot.resource_count), (Some(4), 1));
+
+        // Interrupted run left newer plaintext behind; restoring brings back the envelope
+        write_state(dir.path(), 5);
+        restore_backup(dir.path(), &snapshot.id, Some(&key)).unwrap();
+        assert!(!dir.path().join(STATE_FILE).exists());
+        let restored = state_encryption::read_state(dir.path(), Some(&key)).unwrap();
+        assert!(restored.contains("\"serial\":4"));
+    }
+}
@@ -0,0 +1,421 @@
+//! Optional at-rest encryption of local Terraform state.
+//!
+//! When enabled for a deployment (marker file `.deployer-state-encryption`),
+//! `terraform.tfstate` and `terraform.tfstate.backup` are stored as encrypted
+//! envelopes (`*.enc`) and only decrypted for the duration of a Terraform run:
+//! [`prepare_run`] returns a guard that encrypts the state again and removes
+//! the plaintext when dropped. Plaintext left behind when the app was killed
+//! mid-run is encrypted at the next start ([`secure_stray_state`]). State
+//! snapshots in `.state-backups/` are encrypted the same way. The key is kept
+//! in the OS keychain, not next to the data it protects; a key file written
+//! by older versions is moved there on first use.
+//!
+//! The envelope carries the state serial and resource count in plaintext so
+//! the UI can tell whether a deployment has resources without the key.
+
+use super::{debug_log, get_deployments_dir, lock_or_recover, sanitize_deployment_name};
+use crate::crypto;
+use base64::Engine;
+use rand::RngCore;
+use crate::storage::Environment;
+use crate::terraform::DEPLOYMENT_STATUS;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Marker file enabling state encryption for a deployment.
+const ENCRYPTION_MARKER: &str = ".deployer-state-encryption";
+
+/// State files Terraform reads and writes in a deployment directory.
+const STATE_FILES: &[&str] = &["terraform.tfstate", "terraform.tfstate.backup"];
+
+const ENVELOPE_FORMAT: &str = "deployer-encrypted-state-v1";
+
+/// OS keychain service the state key is stored under.
+const KEYRING_SERVICE: &str = "com.databricks.deployer";
+
+/// Key file in the data directory used by older versions.
+const LEGACY_KEY_FILE: &str = "state-keyfile";
+
+/// Encrypted state stored at rest.
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    serial: Option<u64>,
+    resource_count: usize,
+    /// `crypto::encrypt` output.
+    data: String,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+pub(crate) fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".enc");
+    PathBuf::from(name)
+}
+
+/// Write via a temp file + rename so a crash never leaves a truncated file.
+fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+fn parse_envelope(content: &str) -> Option<Envelope> {
+    serde_json::from_str::<Envelope>(content)
+        .ok()
+        .filter(|e| e.format == ENVELOPE_FORMAT)
+}
+
+/// Whether `content` is an encrypted state envelope rather than plaintext state.
+pub(crate) fn is_envelope(content: &str) -> bool {
+    parse_envelope(content).is_some()
+}
+
+/// Serial and resource count of an encrypted envelope.
+pub(crate) fn envelope_summary(content: &str) -> Option<(Option<u64>, usize)> {
+    parse_envelope(content).map(|e| (e.serial, e.resource_count))
+}
+
+pub(crate) fn seal(state: &str, key: &[u8; 32]) -> Result<String, String> {
+    let parsed: serde_json::Value = serde_json::from_str(state).unwrap_or_default();
+    let envelope = Envelope {
+        format: ENVELOPE_FORMAT.to_string(),
+        serial: parsed["serial"].as_u64(),
+        resource_count: parsed["resources"].as_array().map_or(0, Vec::len),
+        data: crypto::encrypt(state, key)?,
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|e| e.to_string())
+}
+
+pub(crate) fn unseal(content: &str, key: &[u8; 32]) -> Result<String, String> {
+    let envelope = parse_envelope(content).ok_or("Not an encrypted state file")?;
+    crypto::decrypt(&envelope.data, key).map_err(|e| format!("Failed to decrypt state: {}", e))
+}
+
+pub(crate) fn is_enabled(deployment_dir: &Path) -> bool {
+    deployment_dir.join(ENCRYPTION_MARKER).exists()
+}
+
+/// Resource count from the encrypted state, for callers without the key.
+pub(crate) fn encrypted_resource_count(deployment_dir: &Path) -> Option<usize> {
+    let content = fs::read_to_string(encrypted_path(&deployment_dir.join(STATE_FILES[0]))).ok()?;
+    envelope_summary(&content).map(|(_, count)| count)
+}
+
+/// Keychain entry for the data directory's state key, so separate data
+/// directories keep separate keys.
+fn keyring_entry(env: &dyn Environment) -> Result<keyring::Entry, String> {
+    let account = format!("state-encryption-key:{}", env.data_dir()?.display());
+    keyring::Entry::new(KEYRING_SERVICE, &account).map_err(|e| format!("OS keychain is not available: {}", e))
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Corrupted state encryption key in the OS keychain: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| "Corrupted state encryption key in the OS keychain".to_string())
+}
+
+/// The state key from the OS keychain. Created on first use, from the
+/// legacy key file when there is one (which is then removed).
+pub(crate) fn load_key(env: &dyn Environment) -> Result<[u8; 32], String> {
+    let entry = keyring_entry(env)?;
+    match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded),
+        Err(keyring::Error::NoEntry) => {
+            let legacy = env.data_file(LEGACY_KEY_FILE)?;
+            let key = if legacy.exists() {
+                crypto::load_or_create_key(&legacy)?
+            } else {
+                let mut key = [0u8; 32];
+                rand::rngs::OsRng.fill_bytes(&mut key);
+                key
+            };
+            entry
+                .set_password(&base64::engine::general_purpose::STANDARD.encode(key))
+                .map_err(|e| format!("Failed to store the state encryption key in the OS keychain: {}", e))?;
+            let _ = fs::remove_file(legacy);
+            Ok(key)
+        }
+        Err(e) => Err(format!("Failed to read the state encryption key from the OS keychain: {}", e)),
+    }
+}
+
+/// Whether the deployment has local state, plaintext or encrypted.
+pub(crate) fn has_local_state(deployment_dir: &Path) -> bool {
+    let plain = deployment_dir.join(STATE_FILES[0]);
+    plain.exists() || encrypted_path(&plain).exists()
+}
+
+/// Current state as plaintext: the working file if present, else the
+/// decrypted envelope (when a key is given).
+pub(crate) fn read_state(deployment_dir: &Path, key: Option<&[u8; 32]>) -> Option<String> {
+    let plain = deployment_dir.join(STATE_FILES[0]);
+    if let Ok(content) = fs::read_to_string(&plain) {
+        return Some(content);
+    }
+    let sealed = fs::read_to_string(encrypted_path(&plain)).ok()?;
+    unseal(&sealed, key?).ok()
+}
+
+/// Encrypt plaintext state files (and snapshots) in place, removing the plaintext.
+pub(crate) fn encrypt_state(deployment_dir: &Path, key: &[u8; 32]) -> Result<(), String> {
+    for name in STATE_FILES {
+        let plain = deployment_dir.join(name);
+        let Ok(content) = fs::read_to_string(&plain) else {
+            continue;
+        };
+        write_atomic(&encrypted_path(&plain), &seal(&content, key)?)?;
+        fs::remove_file(&plain).map_err(|e| format!("Failed to remove plaintext state: {}", e))?;
+    }
+
+    let backups = deployment_dir.join(super::state_backups::BACKUP_DIR);
+    for entry in fs::read_dir(backups).into_iter().flatten().flatten() {
+        let path = entry.path();
+        match fs::read_to_string(&path) {
+            Ok(content) if !is_envelope(&content) => write_atomic(&path, &seal(&content, key)?)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write plaintext state files for a run. An existing plaintext file is newer
+/// (left by an interrupted run) and is kept.
+pub(crate) fn decrypt_state(deployment_dir: &Path, key: &[u8; 32]) -> Result<(), String> {
+    for name in STATE_FILES {
+        let plain = deployment_dir.join(name);
+        if plain.exists() {
+            continue;
+        }
+        if let Ok(sealed) = fs::read_to_string(encrypted_path(&plain)) {
+            write_atomic(&plain, &unseal(&sealed, key)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decrypt everything back to plaintext and remove the encrypted files.
+fn disable(deployment_dir: &Path, key: &[u8; 32]) -> Result<(), String> {
+    decrypt_state(deployment_dir, key)?;
+    for name in STATE_FILES {
+        let _ = fs::remove_file(encrypted_path(&deployment_dir.join(name)));
+    }
+    let backups = deployment_dir.join(super::state_backups::BACKUP_DIR);
+    for entry in fs::read_dir(backups).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if let Ok(content) = fs::read_to_string(&path) {
+            if is_envelope(&content) {
+                write_atomic(&path, &unseal(&content, key)?)?;
+            }
+        }
+    }
+    let _ = fs::remove_file(deployment_dir.join(ENCRYPTION_MARKER));
+    Ok(())
+}
+
+fn enable(deployment_dir: &Path, key: &[u8; 32]) -> Result<(), String> {
+    fs::write(deployment_dir.join(ENCRYPTION_MARKER), "")
+        .map_err(|e| format!("Failed to enable state encryption: {}", e))?;
+    encrypt_state(deployment_dir, key)
+}
+
+/// State decrypted for a run. Dropping it encrypts the state again and
+/// removes the plaintext, however the run ends.
+pub(crate) struct DecryptedState {
+    dir: PathBuf,
+    key: Option<[u8; 32]>,
+}
+
+impl DecryptedState {
+    /// Key the state is encrypted with; `None` when encryption is off.
+    pub(crate) fn key(&self) -> Option<&[u8; 32]> {
+        self.key.as_ref()
+    }
+
+    /// Encrypt the state now, before the guard is dropped. Errors are logged;
+    /// the plaintext is then encrypted on drop or at the next start instead.
+    pub(crate) fn encrypt(&self) {
+        if let Some(key) = &self.key {
+            if let Err(_e) = encrypt_state(&self.dir, key) {
+                debug_log!("[state_encryption] Failed to re-encrypt state: {}", _e);
+            }
+        }
+    }
+}
+
+impl Drop for DecryptedState {
+    fn drop(&mut self) {
+        self.encrypt();
+    }
+}
+
+/// Decrypt the state for a run, until the returned guard is dropped.
+pub(crate) fn prepare_run(env: &dyn Environment, deployment_dir: &Path) -> Result<DecryptedState, String> {
+    let key = if is_enabled(deployment_dir) {
+        let key = load_key(env)?;
+        decrypt_state(deployment_dir, &key)?;
+        Some(key)
+    } else {
+        None
+    };
+    Ok(DecryptedState { dir: deployment_dir.to_path_buf(), key })
+}
+
+/// Encrypt plaintext state a run left in `deployment_dir`, e.g. when the app
+/// was killed mid-run. Errors are logged.
+pub(crate) fn secure_state(env: &dyn Environment, deployment_dir: &Path) {
+    let has_plaintext = STATE_FILES.iter().any(|name| deployment_dir.join(name).exists());
+    if !is_enabled(deployment_dir) || !has_plaintext {
+        return;
+    }
+    match load_key(env) {
+        Ok(key) => DecryptedState { dir: deployment_dir.to_path_buf(), key: Some(key) }.encrypt(),
+        Err(_e) => {
+            debug_log!("[state_encryption] Failed to load key for {}: {}", deployment_dir.display(), _e);
+        }
+    }
+}
+
+/// Encrypt plaintext state left by runs of a previous session. Deployments
+/// with a run still in flight or a lock held (a Terraform run, possibly from
+/// the CLI, still uses the plaintext) are skipped. Called once at startup,
+/// after interrupted runs were recovered.
+pub(crate) fn secure_stray_state(env: &dyn Environment) {
+    let Ok(entries) = get_deployments_dir(env).and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string())) else {
+        return;
+    };
+    for entry in entries.flatten().filter(|e| e.path().is_dir()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let idle = matches!(super::deployment_lock::current_lock(env, &name), Ok(None));
+        if idle && !super::run_recovery::has_run(&entry.path()) {
+            secure_state(env, &entry.path());
+        }
+    }
+}
+
+fn deployment_dir(app: &AppHandle, deployment_name: &str) -> Result<PathBuf, String> {
+    let dir = get_deployments_dir(app)?.join(sanitize_deployment_name(deployment_name)?);
+    if !dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    Ok(dir)
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Whether local state is encrypted at rest for a deployment.
+#[tauri::command]
+pub fn get_state_encryption(app: AppHandle, deployment_name: String) -> Result<bool, String> {
+    Ok(is_enabled(&deployment_dir(&app, &deployment_name)?))
+}
+
+/// Turn at-rest state encryption on or off, converting existing state files.
+#[tauri::command]
+pub fn set_state_encryption(app: AppHandle, deployment_name: String, enabled: bool) -> Result<(), String> {
+    if lock_or_recover(&DEPLOYMENT_STATUS).running {
+        return Err("Cannot change state encryption while a deployment is running".to_string());
+    }
+    let dir = deployment_dir(&app, &deployment_name)?;
+    let key = load_key(&app)?;
+    if enabled {
+        enable(&dir, &key)
+    } else {
+        disable(&dir, &key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATE: &str = r#"{"version": 4, "serial": 9, "resources": [{"type": "aws_vpc"}, {"type": "aws_subnet"}], "outputs": {"token": {"value": "dapi-secret"}}}"#;
+
+    fn key() -> [u8; 32] {
+        [7u8; 32]
+    }
+
+    #[test]
+    fn seal_hides_state_but_keeps_summary() {
+        let sealed = seal(STATE, &key()).unwrap();
+        assert!(!sealed.contains("dapi-secret"));
+        assert_eq!(envelope_summary(&sealed), Some((Some(9), 2)));
+        assert_eq!(unseal(&sealed, &key()).unwrap(), STATE);
+        assert!(unseal(&sealed, &[1u8; 32]).is_err());
+        assert!(!is_envelope(STATE));
+    }
+
+    #[test]
+    fn enable_run_cycle_and_disable() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("terraform.tfstate");
+        fs::write(&state_path, STATE).unwrap();
+        fs::create_dir_all(dir.path().join(super::super::state_backups::BACKUP_DIR)).unwrap();
+        let snapshot = dir.path().join(super::super::state_backups::BACKUP_DIR).join("1-apply.tfstate");
+        fs::write(&snapshot, STATE).unwrap();
+
+        enable(dir.path(), &key()).unwrap();
+        assert!(is_enabled(dir.path()));
+        assert!(!state_path.exists());
+        assert_eq!(encrypted_resource_count(dir.path()), Some(2));
+        assert!(is_envelope(&fs::read_to_string(&snapshot).unwrap()));
+        assert_eq!(read_state(dir.path(), Some(&key())).as_deref(), Some(STATE));
+        assert_eq!(read_state(dir.path(), None), None);
+
+        // A run decrypts, Terraform writes new state, finishing re-encrypts it
+        decrypt_state(dir.path(), &key()).unwrap();
+        assert_eq!(fs::read_to_string(&state_path).unwrap(), STATE);
+        fs::write(&state_path, r#"{"serial": 10, "resources": []}"#).unwrap();
+        drop(DecryptedState { dir: dir.path().to_path_buf(), key: Some(key()) });
+        assert!(!state_path.exists());
+        assert_eq!(encrypted_resource_count(dir.path()), Some(0));
+
+        disable(dir.path(), &key()).unwrap();
+        assert!(!is_enabled(dir.path()));
+        assert_eq!(fs::read_to_string(&state_path).unwrap(), r#"{"serial": 10, "resources": []}"#);
+        assert!(!encrypted_path(&state_path).exists());
+        assert_eq!(fs::read_to_string(&snapshot).unwrap(), STATE);
+    }
+
+    #[test]
+    fn stray_plaintext_is_encrypted_with_the_keychain_key() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let data = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(data.path());
+        let dir = env.deployments_dir().unwrap().join("prod");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(ENCRYPTION_MARKER), "").unwrap();
+        fs::write(dir.join("terraform.tfstate"), STATE).unwrap();
+
+        secure_stray_state(&env);
+        assert!(!dir.join("terraform.tfstate").exists());
+        assert_eq!(encrypted_resource_count(&dir), Some(2));
+        assert!(decode_key("not base64!").is_err());
+        assert!(decode_key(&base64::engine::general_purpose::STANDARD.encode([1u8; 16])).is_err());
+    }
+
+    #[test]
+    fn decrypt_keeps_newer_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("terraform.tfstate");
+        fs::write(encrypted_path(&state_path), seal(STATE, &key()).unwrap()).unwrap();
+        fs::write(&state_path, "{\"serial\": 11}").unwrap();
+        decrypt_state(dir.path(), &key()).unwrap();
+        assert_eq!(fs::read_to_string(&state_path).unwrap(), "{\"serial\": 11}");
+    }
+}
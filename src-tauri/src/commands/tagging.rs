@@ -0,0 +1,357 @@
+//! Organization tag policy.
+//!
+//! Tags the organization mandates (cost center, owner, environment, ...) are
+//! set up once in the application settings: `required` keys must have a value
+//! in every deployment and `defaults` prefill values. When a configuration is
+//! saved, the defaults are merged under the tags entered in the config step,
+//! the result is checked against the target cloud's tag rules and written to
+//! the template's `tags` variable. Templates apply it as AWS provider
+//! `default_tags` and workspace `custom_tags`, Azure resource `tags` and GCP
+//! `labels`.
+
+use super::settings;
+use crate::storage::Environment;
+use crate::terraform::TerraformVariable;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tauri::AppHandle;
+
+/// Template variable the merged tags are written to.
+const TAGS_VARIABLE: &str = "tags";
+
+/// Tag policy stored in the application settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagSettings {
+    /// Keys every deployment must set (e.g. "CostCenter", "Owner").
+    pub required: Vec<String>,
+    /// Values prefilled for every deployment; tags entered in the config step win.
+    pub defaults: BTreeMap<String, String>,
+}
+
+impl TagSettings {
+    /// Trim keys and reject empty or duplicate ones.
+    pub(crate) fn validate(&mut self) -> Result<(), String> {
+        let mut required: Vec<String> = Vec::new();
+        for key in &self.required {
+            let key = key.trim();
+            if key.is_empty() {
+                return Err("Required tag keys must not be empty".to_string());
+            }
+            if !required.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                required.push(key.to_string());
+            }
+        }
+        self.required = required;
+
+        let mut defaults = BTreeMap::new();
+        for (key, value) in &self.defaults {
+            let key = key.trim();
+            if key.is_empty() {
+                return Err("Default tag keys must not be empty".to_string());
+            }
+            if defaults.insert(key.to_string(), value.trim().to_string()).is_some() {
+                return Err(format!("Duplicate default tag '{}'", key));
+            }
+        }
+        self.defaults = defaults;
+        Ok(())
+    }
+}
+
+/// A tag that breaks the cloud's rules.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TagIssue {
+    pub key: String,
+    pub message: String,
+}
+
+/// Tags for a deployment after merging the defaults, with everything that
+/// would stop them from being applied.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagValidation {
+    pub tags: BTreeMap<String, String>,
+    pub issues: Vec<TagIssue>,
+    /// Required keys without a value.
+    pub missing: Vec<String>,
+    pub valid: bool,
+}
+
+// ─── Cloud Rules ────────────────────────────────────────────────────────────
+
+fn max_tags(cloud: &str) -> usize {
+    match cloud {
+        "gcp" => 64,
+        _ => 50,
+    }
+}
+
+fn aws_allowed(c: char) -> bool {
+    c.is_alphanumeric() || c.is_whitespace() || "_.:/=+-@".contains(c)
+}
+
+fn gcp_allowed(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'
+}
+
+/// Check one tag against the cloud's limits on keys and values.
+fn tag_rules(cloud: &str, key: &str, value: &str) -> Result<(), String> {
+    // Tags end up as quoted tfvars strings
+    if key.contains('"') || key.chars().chain(value.chars()).any(char::is_control) {
+        return Err("must not contain quotes or control characters".to_string());
+    }
+    if value.contains("${") || value.contains("%{") {
+        return Err("value must not contain template sequences (${ or %{)".to_string());
+    }
+    let (key_len, value_len) = (key.chars().count(), value.chars().count());
+    match cloud {
+        "aws" => {
+            if !(1..=128).contains(&key_len) {
+                return Err("key must be 1-128 characters".to_string());
+            }
+            if value_len > 256 {
+                return Err("value must be at most 256 characters".to_string());
+            }
+            if key.to_lowercase().starts_with("aws:") {
+                return Err("keys starting with 'aws:' are reserved".to_string());
+            }
+            if !key.chars().chain(value.chars()).all(aws_allowed) {
+                return Err("may only contain letters, digits, spaces and _ . : / = + - @".to_string());
+            }
+        }
+        "azure" => {
+            if !(1..=512).contains(&key_len) {
+                return Err("key must be 1-512 characters".to_string());
+            }
+            if value_len > 256 {
+                return Err("value must be at most 256 characters".to_string());
+            }
+            if key.contains(['<', '>', '%', '&', '\\', '?', '/']) {
+                return Err("key must not contain < > % & \\ ? /".to_string());
+            }
+        }
+        "gcp" => {
+            if !(1..=63).contains(&key_len) || value_len > 63 {
+                return Err("key must be 1-63 characters and value at most 63".to_string());
+            }
+            if !key.starts_with(|c: char| c.is_ascii_lowercase()) {
+                return Err("key must start with a lowercase letter".to_string());
+            }
+            if !key.chars().chain(value.chars()).all(gcp_allowed) {
+                return Err("may only contain lowercase letters, digits, underscores and hyphens".to_string());
+            }
+        }
+        _ => return Err(format!("unknown cloud '{}'", cloud)),
+    }
+    Ok(())
+}
+
+/// GCP labels are lowercase with a restricted charset, so defaults written
+/// for AWS/Azure (e.g. "CostCenter" = "Data Platform") are converted.
+fn to_gcp_label(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if gcp_allowed(c) { c } else { '_' })
+        .collect()
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn cloud_for_template(template_id: &str) -> Option<&'static str> {
+    ["aws", "azure", "gcp"]
+        .into_iter()
+        .find(|cloud| template_id.strip_prefix(cloud).is_some_and(|rest| rest.starts_with('-')))
+}
+
+/// Merge `defaults` under `tags` and check the result for `cloud`.
+fn check_tags(cloud: &str, tags: BTreeMap<String, String>, policy: &TagSettings) -> TagValidation {
+    let mut merged: BTreeMap<String, String> = policy
+        .defaults
+        .iter()
+        .map(|(key, value)| match cloud {
+            "gcp" => (to_gcp_label(key), to_gcp_label(value)),
+            _ => (key.clone(), value.clone()),
+        })
+        .collect();
+    for (key, value) in tags {
+        merged.insert(key.trim().to_string(), value.trim().to_string());
+    }
+
+    let mut issues: Vec<TagIssue> = merged
+        .iter()
+        .filter_map(|(key, value)| {
+            tag_rules(cloud, key, value).err().map(|message| TagIssue { key: key.clone(), message })
+        })
+        .collect();
+    if merged.len() > max_tags(cloud) {
+        issues.push(TagIssue {
+            key: String::new(),
+            message: format!("at most {} tags are allowed, got {}", max_tags(cloud), merged.len()),
+        });
+    }
+
+    // GCP keys are lowercased, so required keys match regardless of case
+    let missing: Vec<String> = policy
+        .required
+        .iter()
+        .filter(|required| {
+            !merged
+                .iter()
+                .any(|(key, value)| key.eq_ignore_ascii_case(required) && !value.is_empty())
+        })
+        .cloned()
+        .collect();
+
+    let valid = issues.is_empty() && missing.is_empty();
+    TagValidation { tags: merged, issues, missing, valid }
+}
+
+/// Tags from a config-step value: a JSON object, or a JSON string holding one.
+fn parse_tags(value: Option<&serde_json::Value>) -> Result<BTreeMap<String, String>, String> {
+    let object = match value {
+        None | Some(serde_json::Value::Null) => return Ok(BTreeMap::new()),
+        Some(serde_json::Value::String(s)) if s.trim().is_empty() => return Ok(BTreeMap::new()),
+        Some(serde_json::Value::String(s)) => serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(s)
+            .map_err(|_| "Tags must be a map of key/value pairs".to_string())?,
+        Some(serde_json::Value::Object(object)) => object.clone(),
+        Some(_) => return Err("Tags must be a map of key/value pairs".to_string()),
+    };
+    Ok(object
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => (key, s),
+            other => (key, other.to_string()),
+        })
+        .collect())
+}
+
+/// Apply the tag policy to the values of a configuration being saved: merge
+/// the default tags into `tags` and fail if required tags are missing or any
+/// tag breaks the cloud's rules. Templates without a `tags` variable are left
+/// alone.
+pub(crate) fn apply_tag_policy(
+    env: &dyn Environment,
+    template_id: &str,
+    values: &mut HashMap<String, serde_json::Value>,
+    variables: &[TerraformVariable],
+) -> Result<(), String> {
+    if !variables.iter().any(|v| v.name == TAGS_VARIABLE) {
+        return Ok(());
+    }
+    let Some(cloud) = cloud_for_template(template_id) else {
+        return Ok(());
+    };
+    let tags = parse_tags(values.get(TAGS_VARIABLE))?;
+    let report = check_tags(cloud, tags, &settings::load(env).tags);
+    if !report.missing.is_empty() {
+        return Err(format!("Missing required tags: {}", report.missing.join(", ")));
+    }
+    if let Some(issue) = report.issues.first() {
+        return Err(match issue.key.as_str() {
+            "" => format!("Invalid tags: {}", issue.message),
+            key => format!("Invalid tag '{}': {}", key, issue.message),
+        });
+    }
+
+    let tags = report.tags.into_iter().map(|(k, v)| (k, serde_json::Value::String(v))).collect();
+    values.insert(TAGS_VARIABLE.to_string(), serde_json::Value::Object(tags));
+    Ok(())
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Merge the default tags into `tags` and check them for `cloud` ("aws",
+/// "azure" or "gcp"). Call with no tags to get the prefilled defaults.
+#[tauri::command]
+pub fn validate_tags(
+    app: AppHandle,
+    cloud: String,
+    tags: Option<BTreeMap<String, String>>,
+) -> Result<TagValidation, String> {
+    let cloud = cloud.trim().to_lowercase();
+    if !["aws", "azure", "gcp"].contains(&cloud.as_str()) {
+        return Err(format!("Unknown cloud '{}'", cloud));
+    }
+    Ok(check_tags(&cloud, tags.unwrap_or_default(), &settings::load(&app).tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoragePaths;
+    use serde_json::json;
+
+    fn tags(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn policy() -> TagSettings {
+        TagSettings {
+            required: vec!["CostCenter".to_string(), "Owner".to_string()],
+            defaults: tags(&[("CostCenter", "Data Platform"), ("Environment", "dev")]),
+        }
+    }
+
+    #[test]
+    fn cloud_rules() {
+        assert!(tag_rules("aws", "Cost Center", "team@example.com").is_ok());
+        assert!(tag_rules("aws", "aws:createdBy", "me").is_err());
+        assert!(tag_rules("aws", "Owner", "a,b").is_err());
+        assert!(tag_rules("aws", &"k".repeat(129), "").is_err());
+        assert!(tag_rules("azure", "Owner", "a,b <team>").is_ok());
+        assert!(tag_rules("azure", "cost/center", "x").is_err());
+        assert!(tag_rules("gcp", "cost_center", "data-platform").is_ok());
+        assert!(tag_rules("gcp", "CostCenter", "x").is_err());
+        assert!(tag_rules("gcp", "1owner", "x").is_err());
+        assert!(tag_rules("gcp", "owner", "Jane").is_err());
+        assert!(tag_rules("azure", "Owner", "${var.secret}").is_err());
+        assert!(tag_rules("azure", "Own\"er", "x").is_err());
+    }
+
+    #[test]
+    fn defaults_merge_under_user_tags() {
+        let report = check_tags("aws", tags(&[("Environment", "prod"), ("Owner", "jane")]), &policy());
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.tags, tags(&[("CostCenter", "Data Platform"), ("Environment", "prod"), ("Owner", "jane")]));
+
+        let report = check_tags("aws", tags(&[("Owner", " ")]), &policy());
+        assert_eq!(report.missing, vec!["Owner".to_string()]);
+        assert!(!report.valid);
+
+        // Defaults become valid GCP labels; required keys match case-insensitively
+        let report = check_tags("gcp", tags(&[("owner", "jane")]), &policy());
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.tags.get("costcenter").map(String::as_str), Some("data_platform"));
+    }
+
+    #[test]
+    fn policy_is_applied_when_saving() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        settings::update(&paths, |mut s| {
+            s.tags = policy();
+            Ok(s)
+        })
+        .unwrap();
+        let variables = crate::terraform::parse_variables_tf(
+            "variable \"tags\" {\n  type    = map(string)\n  default = {}\n}\n",
+        ).unwrap();
+
+        let mut values = HashMap::from([("tags".to_string(), json!("{\"Owner\": \"jane\"}"))]);
+        apply_tag_policy(&paths, "aws-simple", &mut values, &variables).unwrap();
+        assert_eq!(
+            values["tags"],
+            json!({"CostCenter": "Data Platform", "Environment": "dev", "Owner": "jane"})
+        );
+
+        let mut values = HashMap::new();
+        let err = apply_tag_policy(&paths, "azure-simple", &mut values, &variables).unwrap_err();
+        assert!(err.contains("Owner"), "{}", err);
+
+        // Templates without a tags variable are untouched
+        let mut values = HashMap::new();
+        apply_tag_policy(&paths, "aws-simple", &mut values, &[]).unwrap();
+        assert!(values.is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+//! Opt-in anonymous usage telemetry.
+//!
+//! Off unless the user opts in (`telemetry_opt_in` in the app settings), and
+//! nothing leaves the machine until a `telemetry_endpoint` is configured too.
+//! Events are queued in `telemetry-queue.json` and posted in batches; the
+//! queue can be inspected from the UI before anything is sent.
+//!
+//! Event schema (version [`SCHEMA_VERSION`]), one JSON object per event:
+//!
+//! | field              | present on      | example                 |
+//! |--------------------|-----------------|-------------------------|
+//! | `event`            | all             | `feature_used`, `run_finished` |
+//! | `feature`          | `feature_used`  | `bundle_export`         |
+//! | `command`          | `run_finished`  | `apply`, `destroy`      |
+//! | `template`         | `run_finished`  | `aws-simple` (built-in templates only) |
+//! | `cloud`            | `run_finished`  | `aws`, `azure`, `gcp`   |
+//! | `outcome`          | `run_finished`  | `succeeded`, `failed`, `cancelled` |
+//! | `duration_minutes` | `run_finished`  | `23`                    |
+//! | `app_version`      | all             | `1.4.0`                 |
+//! | `os`               | all             | `macos`                 |
+//! | `day`              | all             | days since 1970-01-01   |
+//!
+//! There are no user, machine or installation identifiers, and no deployment,
+//! account or resource names. Batches are posted as
+//! `{"schema_version": 1, "events": [...]}`.
+
+use super::debug_log;
+use super::notifications::RunOutcome;
+use crate::storage::Environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Version of the event schema documented above.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const QUEUE_FILE: &str = "telemetry-queue.json";
+
+/// Oldest events are dropped past this, e.g. while no endpoint is set.
+const MAX_QUEUED_EVENTS: usize = 1000;
+
+/// Events sent per request.
+const BATCH_SIZE: usize = 100;
+
+/// How often queued events are uploaded.
+const UPLOAD_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Only these template ids are reported; custom templates could carry
+/// organization names.
+const KNOWN_TEMPLATES: &[&str] = &[
+    "aws-simple",
+    "aws-sra",
+    "azure-pl-sts",
+    "azure-simple",
+    "azure-sra",
+    "gcp-simple",
+    "gcp-sra",
+];
+
+lazy_static::lazy_static! {
+    /// Serializes reads and writes of the queue file.
+    static ref QUEUE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Features whose usage is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Feature {
+    BundleExport,
+    CiPipeline,
+    TemplateUpgrade,
+    StateRestore,
+}
+
+impl Feature {
+    fn as_str(self) -> &'static str {
+        match self {
+            Feature::BundleExport => "bundle_export",
+            Feature::CiPipeline => "ci_pipeline",
+            Feature::TemplateUpgrade => "template_upgrade",
+            Feature::StateRestore => "state_restore",
+        }
+    }
+}
+
+/// One telemetry event. See the module docs for the schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<u64>,
+    pub app_version: String,
+    pub os: String,
+    pub day: u64,
+}
+
+/// Telemetry state shown in the settings screen.
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryStatus {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    pub queued_events: usize,
+    pub schema_version: u32,
+}
+
+#[derive(Serialize)]
+struct Batch<'a> {
+    schema_version: u32,
+    events: &'a [TelemetryEvent],
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn base_event(event: &str) -> TelemetryEvent {
+    TelemetryEvent {
+        event: event.to_string(),
+        feature: None,
+        command: None,
+        template: None,
+        cloud: None,
+        outcome: None,
+        duration_minutes: None,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        day: super::audit::now_secs() / 86_400,
+    }
+}
+
+fn feature_event(feature: Feature) -> TelemetryEvent {
+    TelemetryEvent { feature: Some(feature.as_str().to_string()), ..base_event("feature_used") }
+}
+
+fn run_event(template_id: Option<&str>, command: &str, outcome: RunOutcome, duration_secs: u64) -> TelemetryEvent {
+    let template = template_id.filter(|id| KNOWN_TEMPLATES.contains(id));
+    TelemetryEvent {
+        command: Some(command.to_string()),
+        template: template.map(str::to_string),
+        cloud: template.and_then(|id| id.split('-').next()).map(str::to_string),
+        outcome: Some(
+            match outcome {
+                RunOutcome::Succeeded => "succeeded",
+                RunOutcome::Failed => "failed",
+                RunOutcome::Cancelled => "cancelled",
+            }
+            .to_string(),
+        ),
+        // Rounded so durations can't single out a run
+        duration_minutes: Some(duration_secs.div_ceil(60)),
+        ..base_event("run_finished")
+    }
+}
+
+fn read_queue(path: &Path) -> Vec<TelemetryEvent> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_queue(path: &Path, events: &[TelemetryEvent]) -> Result<(), String> {
+    let content = serde_json::to_string(events).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("Failed to write telemetry queue: {}", e))
+}
+
+fn enqueue(path: &Path, event: TelemetryEvent) -> Result<(), String> {
+    let _guard = super::lock_or_recover(&QUEUE_LOCK);
+    let mut events = read_queue(path);
+    events.push(event);
+    if events.len() > MAX_QUEUED_EVENTS {
+        events.drain(..events.len() - MAX_QUEUED_EVENTS);
+    }
+    write_queue(path, &events)
+}
+
+fn record(env: &dyn Environment, event: TelemetryEvent) {
+    if !super::settings::load(env).telemetry_opt_in {
+        return;
+    }
+    let written = env.data_file(QUEUE_FILE).and_then(|path| enqueue(&path, event));
+    if let Err(_e) = written {
+        debug_log!("[telemetry] {}", _e);
+    }
+}
+
+/// Count a use of `feature`, if the user opted in.
+pub(crate) fn record_feature(env: &dyn Environment, feature: Feature) {
+    record(env, feature_event(feature));
+}
+
+/// Record how an apply or destroy ended, if the user opted in.
+pub(crate) fn record_run_finished(
+    env: &dyn Environment,
+    deployment_dir: &Path,
+    command: &str,
+    outcome: RunOutcome,
+    duration_secs: u64,
+) {
+    if !matches!(command, "apply" | "destroy") {
+        return;
+    }
+    let template_id = super::audit::read_meta(deployment_dir).map(|meta| meta.template_id);
+    record(env, run_event(template_id.as_deref(), command, outcome, duration_secs));
+}
+
+async fn send_batch(endpoint: &str, events: &[TelemetryEvent]) -> Result<(), String> {
+    let response = super::http_client()?
+        .post(endpoint)
+        .json(&Batch { schema_version: SCHEMA_VERSION, events })
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send telemetry: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Telemetry endpoint returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Upload queued events in batches, removing each batch once accepted.
+/// Returns how many events were sent.
+async fn flush(path: &Path, endpoint: &str) -> Result<usize, String> {
+    let mut sent = 0;
+    loop {
+        let batch: Vec<TelemetryEvent> = {
+            let _guard = super::lock_or_recover(&QUEUE_LOCK);
+            read_queue(path).into_iter().take(BATCH_SIZE).collect()
+        };
+        if batch.is_empty() {
+            return Ok(sent);
+        }
+        send_batch(endpoint, &batch).await?;
+        sent += batch.len();
+
+        // Events recorded meanwhile were appended after the batch
+        let _guard = super::lock_or_recover(&QUEUE_LOCK);
+        let mut events = read_queue(path);
+        events.drain(..batch.len().min(events.len()));
+        write_queue(path, &events)?;
+    }
+}
+
+/// Upload queued events if telemetry is on and an endpoint is configured.
+async fn flush_if_enabled(env: &dyn Environment) -> Result<usize, String> {
+    let settings = super::settings::load(env);
+    match settings.telemetry_endpoint {
+        Some(endpoint) if settings.telemetry_opt_in => flush(&env.data_file(QUEUE_FILE)?, &endpoint).await,
+        _ => Ok(0),
+    }
+}
+
+fn clear_queue(env: &dyn Environment) -> Result<(), String> {
+    let _guard = super::lock_or_recover(&QUEUE_LOCK);
+    let path = env.data_dir()?.join(QUEUE_FILE);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to clear telemetry queue: {}", e)),
+        _ => Ok(()),
+    }
+}
+
+/// Upload queued events periodically. Runs for the lifetime of the app.
+pub fn run_telemetry_uploader(app: &AppHandle) {
+    loop {
+        std::thread::sleep(UPLOAD_INTERVAL);
+        if let Err(_e) = tauri::async_runtime::block_on(flush_if_enabled(app)) {
+            debug_log!("[telemetry] Upload failed: {}", _e);
+        }
+    }
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Whether telemetry is on, where it is sent, and how many events are queued.
+#[tauri::command]
+pub fn get_telemetry_status(app: AppHandle) -> Result<TelemetryStatus, String> {
+    let settings = super::settings::load(&app);
+    let queued_events = {
+        let _guard = super::lock_or_recover(&QUEUE_LOCK);
+        read_queue(&app.data_dir()?.join(QUEUE_FILE)).len()
+    };
+    Ok(TelemetryStatus {
+        enabled: settings.telemetry_opt_in,
+        endpoint: settings.telemetry_endpoint,
+        queued_events,
+        schema_version: SCHEMA_VERSION,
+    })
+}
+
+/// The queued events exactly as they would be sent.
+#[tauri::command]
+pub fn preview_telemetry_events(app: AppHandle) -> Result<Vec<TelemetryEvent>, String> {
+    let _guard = super::lock_or_recover(&QUEUE_LOCK);
+    Ok(read_queue(&app.data_dir()?.join(QUEUE_FILE)))
+}
+
+/// Opt in to or out of telemetry. Opting out discards queued events.
+#[tauri::command]
+pub fn set_telemetry_opt_in(app: AppHandle, enabled: bool) -> Result<(), String> {
+    super::settings::update(&app, |settings| {
+        Ok(super::settings::AppSettings { telemetry_opt_in: enabled, ..settings })
+    })?;
+    if !enabled {
+        clear_queue(&app)?;
+    }
+    Ok(())
+}
+
+/// Upload queued events now. Returns how many were sent.
+#[tauri::command]
+pub async fn flush_telemetry(app: AppHandle) -> Result<usize, String> {
+    flush_if_enabled(&app).await
+}
+
+/// Discard queued events without sending them.
+#[tauri::command]
+pub fn clear_telemetry_queue(app: AppHandle) -> Result<(), String> {
+    clear_queue(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn run_events_carry_no_custom_names() {
+        let event = run_event(Some("gcp-sra"), "apply", RunOutcome::Failed, 61);
+        assert_eq!(event.template.as_deref(), Some("gcp-sra"));
+        assert_eq!(event.cloud.as_deref(), Some("gcp"));
+        assert_eq!(event.outcome.as_deref(), Some("failed"));
+        assert_eq!(event.duration_minutes, Some(2));
+
+        let custom = run_event(Some("acme-corp-prod"), "destroy", RunOutcome::Succeeded, 10);
+        assert!(custom.template.is_none() && custom.cloud.is_none());
+
+        let json = serde_json::to_value(feature_event(Feature::BundleExport)).unwrap();
+        assert_eq!(json["event"], "feature_used");
+        assert_eq!(json["feature"], "bundle_export");
+        assert!(json.get("template").is_none());
+    }
+
+    #[test]
+    fn queue_is_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        for _ in 0..MAX_QUEUED_EVENTS + 5 {
+            enqueue(&path, feature_event(Feature::CiPipeline)).unwrap();
+        }
+        assert_eq!(read_queue(&path).len(), MAX_QUEUED_EVENTS);
+    }
+
+    #[tokio::test]
+    async fn flush_posts_batches_and_empties_queue() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/events"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        let events = vec![feature_event(Feature::StateRestore); BATCH_SIZE + 1];
+        write_queue(&path, &events).unwrap();
+
+        let sent = flush(&path, &format!("{}/events", server.uri())).await.unwrap();
+        assert_eq!(sent, BATCH_SIZE + 1);
+        assert!(read_queue(&path).is_empty());
+
+        let body: serde_json::Value = serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body["schema_version"], SCHEMA_VERSION);
+        assert_eq!(body["events"].as_array().unwrap().len(), BATCH_SIZE);
+    }
+
+    #[tokio::test]
+    async fn failed_upload_keeps_events() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).mount(&server).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        write_queue(&path, &[feature_event(Feature::TemplateUpgrade)]).unwrap();
+        assert!(flush(&path, &server.uri()).await.is_err());
+        assert_eq!(read_queue(&path).len(), 1);
+    }
+}
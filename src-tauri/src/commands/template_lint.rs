@@ -0,0 +1,567 @@
+//! Best-practice checks on Terraform configurations.
+//!
+//! [`lint_template`] and [`lint_deployment`] read every `.tf` file of a
+//! template (bundled or imported) or a deployment and report [`LintFinding`]s:
+//!
+//! - `nat_egress`: networks are created without a NAT gateway for egress
+//! - `open_ssh`: SSH (port 22) is open to the internet
+//! - `storage_encryption`: buckets and storage accounts without encryption
+//!   at rest or in transit
+//! - `metastore_region`: the Unity Catalog metastore is in a different region
+//!   than the workspace
+//!
+//! Checks are structural: expressions are not evaluated, except that
+//! `var.name` in the root module resolves to the variable's default or, for
+//! deployments, its value in `terraform.tfvars`. Anything that can't be
+//! resolved is given the benefit of the doubt.
+
+use super::audit::parse_tfvars;
+use super::region_aliases::same_region;
+use super::templates::tf_files;
+use super::{get_deployments_dir, get_templates_dir, sanitize_deployment_name, sanitize_template_id};
+use crate::hcl_reader::{self, Body, Expression};
+use crate::storage::Environment;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+const ERROR: &str = "error";
+const WARNING: &str = "warning";
+const INFO: &str = "info";
+
+const NETWORKS: &[&str] = &["aws_vpc", "azurerm_virtual_network", "google_compute_network"];
+const NAT_GATEWAYS: &[&str] = &["aws_nat_gateway", "azurerm_nat_gateway", "google_compute_router_nat"];
+const AWS_VPC_MODULE: &str = "terraform-aws-modules/vpc/aws";
+const WORKSPACES: &[&str] = &["databricks_mws_workspaces", "azurerm_databricks_workspace"];
+const OPEN_CIDRS: &[&str] = &["0.0.0.0/0", "::/0", "*", "internet", "any"];
+const SSH_PORT: u32 = 22;
+
+/// One best-practice violation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintFinding {
+    /// Rule ID (`open_ssh`, `nat_egress`, ...).
+    pub rule: String,
+    /// "error", "warning" or "info".
+    pub severity: String,
+    pub message: String,
+    /// File relative to the template or deployment directory.
+    pub file: String,
+    /// Resource or module address, e.g. `aws_security_group.databricks`.
+    pub resource: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LintReport {
+    /// Errors first, then warnings, then info.
+    pub findings: Vec<LintFinding>,
+    pub files_scanned: usize,
+    /// Files that could not be parsed and were skipped.
+    pub parse_errors: Vec<String>,
+}
+
+/// A `resource` or `module` block with where it was found.
+struct Declared {
+    kind: String,
+    name: String,
+    body: Body,
+    file: String,
+    /// Directory of the Terraform module the block belongs to.
+    module_dir: PathBuf,
+}
+
+impl Declared {
+    fn address(&self) -> String {
+        if self.kind == "module" {
+            format!("module.{}", self.name)
+        } else {
+            format!("{}.{}", self.kind, self.name)
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.body.attribute(name).map(|e| e.string_or_text().trim())
+    }
+
+    fn finding(&self, rule: &str, severity: &str, message: String) -> LintFinding {
+        LintFinding {
+            rule: rule.to_string(),
+            severity: severity.to_string(),
+            message,
+            file: self.file.clone(),
+            resource: Some(self.address()),
+        }
+    }
+}
+
+struct Config {
+    root: PathBuf,
+    blocks: Vec<Declared>,
+    /// Root module variable values: defaults, overridden by tfvars.
+    vars: HashMap<String, String>,
+}
+
+impl Config {
+    fn resources<'a>(&'a self, kinds: &'a [&str]) -> impl Iterator<Item = &'a Declared> + 'a {
+        self.blocks.iter().filter(move |b| kinds.contains(&b.kind.as_str()))
+    }
+
+    fn modules(&self) -> impl Iterator<Item = &Declared> {
+        self.blocks.iter().filter(|b| b.kind == "module")
+    }
+
+    /// Literal value of `expr`, resolving `var.name` in the root module.
+    fn resolve(&self, block: &Declared, expr: &Expression) -> Option<String> {
+        if let Some(value) = &expr.string {
+            return Some(value.clone());
+        }
+        let name = expr.text.trim().strip_prefix("var.")?;
+        if block.module_dir != self.root || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+        self.vars.get(name).cloned()
+    }
+}
+
+// ─── Loading ────────────────────────────────────────────────────────────────
+
+fn load(dir: &Path, tfvars: &str, report: &mut LintReport) -> Config {
+    let mut config = Config { root: dir.to_path_buf(), blocks: Vec::new(), vars: HashMap::new() };
+    for path in tf_files(dir) {
+        let file = path.strip_prefix(dir).unwrap_or(path.as_path()).to_string_lossy().replace('\\', "/");
+        let body = match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|c| hcl_reader::parse(&c)) {
+            Ok(body) => body,
+            Err(e) => {
+                report.parse_errors.push(format!("{}: {}", file, e));
+                continue;
+            }
+        };
+        report.files_scanned += 1;
+        let module_dir = path.parent().unwrap_or(dir).to_path_buf();
+        if module_dir == dir {
+            for block in body.blocks("variable") {
+                let default = block.body.attribute("default").and_then(|e| e.string.clone());
+                if let (Some(name), Some(default)) = (block.labels.first(), default) {
+                    config.vars.insert(name.clone(), default);
+                }
+            }
+        }
+        for kind in ["resource", "module"] {
+            for block in body.blocks(kind) {
+                let (kind, name) = match (kind, block.labels.as_slice()) {
+                    ("resource", [kind, name, ..]) => (kind.clone(), name.clone()),
+                    ("module", [name, ..]) => ("module".to_string(), name.clone()),
+                    _ => continue,
+                };
+                config.blocks.push(Declared { kind, name, body: block.body.clone(), file: file.clone(), module_dir: module_dir.clone() });
+            }
+        }
+    }
+    for (name, value) in parse_tfvars(tfvars) {
+        config.vars.insert(name, value.trim().trim_matches('"').to_string());
+    }
+    config
+}
+
+// ─── Expression helpers ─────────────────────────────────────────────────────
+
+/// Items of a list expression such as `["0.0.0.0/0", var.cidr]`, unquoted.
+fn list_items(expr: &Expression) -> Vec<String> {
+    let text = expr.text.trim();
+    let inner = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')).unwrap_or(text);
+    inner
+        .split(',')
+        .map(|item| item.trim().trim_matches('"').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn is_open_cidr(value: &str) -> bool {
+    OPEN_CIDRS.contains(&value.to_ascii_lowercase().as_str())
+}
+
+/// Whether a port spec (`22`, `0-1024`, `*`) includes `port`.
+fn port_spec_includes(spec: &str, port: u32) -> bool {
+    let spec = spec.trim().trim_matches('"');
+    if spec == "*" {
+        return true;
+    }
+    match spec.split_once('-') {
+        Some((from, to)) => matches!((from.trim().parse::<u32>(), to.trim().parse::<u32>()), (Ok(from), Ok(to)) if from <= port && port <= to),
+        None => spec.parse::<u32>() == Ok(port),
+    }
+}
+
+/// AWS `from_port`/`to_port`/`protocol` rule covering `port`. Unparseable
+/// ports count as not covering it.
+fn aws_rule_covers(body: &Body, port: u32, protocol_attr: &str) -> bool {
+    let text = |name: &str| body.attribute(name).map(|e| e.string_or_text().trim().to_string());
+    if matches!(text(protocol_attr).as_deref(), Some("-1" | "all")) {
+        return true;
+    }
+    match (text("from_port").and_then(|p| p.parse::<u32>().ok()), text("to_port").and_then(|p| p.parse::<u32>().ok())) {
+        (Some(from), Some(to)) => from <= port && port <= to,
+        _ => false,
+    }
+}
+
+fn any_open(body: &Body, attrs: &[&str]) -> bool {
+    attrs
+        .iter()
+        .filter_map(|name| body.attribute(name))
+        .any(|expr| list_items(expr).iter().any(|item| is_open_cidr(item)))
+}
+
+/// `name` blocks of a body, including the `content` of `dynamic "name"` blocks.
+fn nested<'a>(body: &'a Body, name: &'a str) -> Vec<&'a Body> {
+    let mut bodies: Vec<&Body> = body.blocks(name).map(|b| &b.body).collect();
+    bodies.extend(
+        body.blocks("dynamic")
+            .filter(|b| b.labels.first().is_some_and(|l| l == name))
+            .flat_map(|b| b.body.blocks("content").map(|c| &c.body)),
+    );
+    bodies
+}
+
+// ─── Rules ──────────────────────────────────────────────────────────────────
+
+fn check_nat_egress(config: &Config, findings: &mut Vec<LintFinding>) {
+    let vpc_modules = || config.modules().filter(|m| m.attr("source") == Some(AWS_VPC_MODULE));
+    let has_nat = config.resources(NAT_GATEWAYS).next().is_some()
+        || vpc_modules().any(|m| m.attr("enable_nat_gateway").is_some_and(|v| v != "false"));
+    if has_nat {
+        return;
+    }
+    if let Some(network) = config.resources(NETWORKS).chain(vpc_modules()).next() {
+        findings.push(network.finding(
+            "nat_egress",
+            WARNING,
+            "The network is created without a NAT gateway. Clusters need outbound access to the control plane and \
+             package repositories unless egress goes through a firewall or private endpoints."
+                .to_string(),
+        ));
+    }
+}
+
+fn check_open_ssh(config: &Config, findings: &mut Vec<LintFinding>) {
+    let message = "SSH (port 22) is open to the internet; restrict the source range".to_string();
+    for block in &config.blocks {
+        let open = match block.kind.as_str() {
+            "aws_security_group" => nested(&block.body, "ingress")
+                .into_iter()
+                .any(|rule| any_open(rule, &["cidr_blocks", "ipv6_cidr_blocks"]) && aws_rule_covers(rule, SSH_PORT, "protocol")),
+            "aws_security_group_rule" => {
+                block.attr("type") == Some("ingress")
+                    && any_open(&block.body, &["cidr_blocks", "ipv6_cidr_blocks"])
+                    && aws_rule_covers(&block.body, SSH_PORT, "protocol")
+            }
+            "aws_vpc_security_group_ingress_rule" => {
+                any_open(&block.body, &["cidr_ipv4", "cidr_ipv6"]) && aws_rule_covers(&block.body, SSH_PORT, "ip_protocol")
+            }
+            "azurerm_network_security_rule" => azure_rule_open(&block.body),
+            "azurerm_network_security_group" => nested(&block.body, "security_rule").into_iter().any(azure_rule_open),
+            "google_compute_firewall" => gcp_firewall_open(&block.body),
+            _ => false,
+        };
+        if open {
+            findings.push(block.finding("open_ssh", ERROR, message.clone()));
+        }
+    }
+}
+
+fn azure_rule_open(rule: &Body) -> bool {
+    let text = |name: &str| rule.attribute(name).map(|e| e.string_or_text().trim().to_ascii_lowercase());
+    let ports = ["destination_port_range", "destination_port_ranges"]
+        .iter()
+        .filter_map(|name| rule.attribute(name))
+        .flat_map(list_items)
+        .any(|spec| port_spec_includes(&spec, SSH_PORT));
+    text("direction").as_deref() == Some("inbound")
+        && text("access").as_deref() == Some("allow")
+        && any_open(rule, &["source_address_prefix", "source_address_prefixes"])
+        && ports
+}
+
+fn gcp_firewall_open(firewall: &Body) -> bool {
+    let direction = firewall.attribute("direction").map(|e| e.string_or_text().trim().to_ascii_uppercase());
+    if direction.is_some_and(|d| d != "INGRESS") || !any_open(firewall, &["source_ranges"]) {
+        return false;
+    }
+    nested(firewall, "allow").into_iter().any(|allow| {
+        let protocol = allow.attribute("protocol").map(|e| e.string_or_text().trim().to_ascii_lowercase());
+        let tcp = matches!(protocol.as_deref(), Some("tcp" | "all" | "6"));
+        match allow.attribute("ports") {
+            Some(ports) => tcp && list_items(ports).iter().any(|spec| port_spec_includes(spec, SSH_PORT)),
+            None => tcp,
+        }
+    })
+}
+
+fn check_storage_encryption(config: &Config, findings: &mut Vec<LintFinding>) {
+    let encryption_configs: Vec<&Declared> = config.resources(&["aws_s3_bucket_server_side_encryption_configuration"]).collect();
+    for bucket in config.resources(&["aws_s3_bucket"]) {
+        let reference = format!("aws_s3_bucket.{}.", bucket.name);
+        let encrypted = encryption_configs
+            .iter()
+            .any(|sse| sse.module_dir == bucket.module_dir && sse.attr("bucket").is_some_and(|b| b.contains(&reference)));
+        if !encrypted {
+            findings.push(bucket.finding(
+                "storage_encryption",
+                INFO,
+                "The bucket has no server-side encryption configuration and relies on S3's default SSE-S3 \
+                 encryption; add aws_s3_bucket_server_side_encryption_configuration to use a KMS key."
+                    .to_string(),
+            ));
+        }
+    }
+    for account in config.resources(&["azurerm_storage_account"]) {
+        let https_only = ["https_traffic_only_enabled", "enable_https_traffic_only"].iter().find_map(|a| account.attr(a));
+        if https_only == Some("false") {
+            findings.push(account.finding(
+                "storage_encryption",
+                ERROR,
+                "The storage account accepts unencrypted HTTP traffic; enable HTTPS-only traffic.".to_string(),
+            ));
+        }
+        if matches!(account.attr("min_tls_version"), Some("TLS1_0" | "TLS1_1")) {
+            findings.push(account.finding(
+                "storage_encryption",
+                WARNING,
+                "The storage account allows TLS versions older than 1.2.".to_string(),
+            ));
+        }
+    }
+}
+
+fn check_metastore_region(config: &Config, findings: &mut Vec<LintFinding>) {
+    for metastore in config.resources(&["databricks_metastore"]) {
+        let Some(metastore_region) = metastore.body.attribute("region") else { continue };
+        let workspace = config.resources(WORKSPACES).find(|w| w.module_dir == metastore.module_dir);
+        let Some((workspace, workspace_region)) =
+            workspace.and_then(|w| ["aws_region", "location"].iter().find_map(|a| w.body.attribute(a)).map(|r| (w, r)))
+        else {
+            continue;
+        };
+        if metastore_region.text == workspace_region.text {
+            continue;
+        }
+        let (Some(ours), Some(theirs)) = (config.resolve(metastore, metastore_region), config.resolve(workspace, workspace_region)) else {
+            continue;
+        };
+        // Azure accepts both `East US` and `eastus`; MWS workspaces name AWS regions in `aws_region`
+        let cloud = match (workspace.kind.as_str(), workspace.body.attribute("aws_region")) {
+            ("azurerm_databricks_workspace", _) => "azure",
+            (_, Some(_)) => "aws",
+            _ => "gcp",
+        };
+        if ours != theirs && !same_region(cloud, &ours, &theirs) {
+            findings.push(metastore.finding(
+                "metastore_region",
+                ERROR,
+                format!(
+                    "The metastore is in '{}' but {} is in '{}'; workspaces can only use a metastore in their own region.",
+                    ours,
+                    workspace.address(),
+                    theirs
+                ),
+            ));
+        }
+    }
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        ERROR => 0,
+        WARNING => 1,
+        _ => 2,
+    }
+}
+
+/// Lint the configuration in `dir`, with `tfvars` supplying variable values.
+pub(crate) fn lint_dir(dir: &Path, tfvars: &str) -> LintReport {
+    let mut report = LintReport::default();
+    let config = load(dir, tfvars, &mut report);
+    check_nat_egress(&config, &mut report.findings);
+    check_open_ssh(&config, &mut report.findings);
+    check_storage_encryption(&config, &mut report.findings);
+    check_metastore_region(&config, &mut report.findings);
+    report.findings.sort_by(|a, b| severity_rank(&a.severity).cmp(&severity_rank(&b.severity)).then(a.file.cmp(&b.file)));
+    report
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+pub(crate) fn lint_template_dir(env: &dyn Environment, template_id: &str) -> Result<LintReport, String> {
+    let dir = get_templates_dir(env)?.join(sanitize_template_id(template_id)?);
+    if !dir.is_dir() {
+        return Err(format!("Template not found: {}", template_id));
+    }
+    Ok(lint_dir(&dir, ""))
+}
+
+pub(crate) fn lint_deployment_dir(env: &dyn Environment, deployment_name: &str) -> Result<LintReport, String> {
+    let dir = get_deployments_dir(env)?.join(sanitize_deployment_name(deployment_name)?);
+    if !dir.is_dir() {
+        return Err("Deployment not found".to_string());
+    }
+    let tfvars = fs::read_to_string(dir.join("terraform.tfvars")).unwrap_or_default();
+    Ok(lint_dir(&dir, &tfvars))
+}
+
+/// Check a bundled or imported template against Databricks best practices.
+#[tauri::command]
+pub fn lint_template(app: AppHandle, template_id: String) -> Result<LintReport, String> {
+    lint_template_dir(&app, &template_id)
+}
+
+/// Check a deployment's configuration, with its variable values, against
+/// Databricks best practices.
+#[tauri::command]
+pub fn lint_deployment(app: AppHandle, deployment_name: String) -> Result<LintReport, String> {
+    lint_deployment_dir(&app, &deployment_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(files: &[(&str, &str)], tfvars: &str) -> LintReport {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        lint_dir(dir.path(), tfvars)
+    }
+
+    fn rules(report: &LintReport) -> Vec<(&str, &str)> {
+        report.findings.iter().map(|f| (f.rule.as_str(), f.severity.as_str())).collect()
+    }
+
+    #[test]
+    fn flags_ssh_open_to_the_internet() {
+        let report = lint(
+            &[(
+                "network.tf",
+                r#"
+resource "aws_security_group" "open" {
+  ingress {
+    from_port   = 0
+    to_port     = 65535
+    protocol    = "tcp"
+    cidr_blocks = ["0.0.0.0/0"]
+  }
+}
+
+resource "aws_security_group" "internal" {
+  ingress {
+    from_port   = 22
+    to_port     = 22
+    protocol    = "tcp"
+    cidr_blocks = ["10.0.0.0/16"]
+  }
+}
+
+resource "azurerm_network_security_rule" "ssh" {
+  direction                  = "Inbound"
+  access                     = "Allow"
+  destination_port_range     = "22"
+  source_address_prefix      = "Internet"
+}
+
+resource "google_compute_firewall" "ssh" {
+  source_ranges = ["0.0.0.0/0"]
+  allow {
+    protocol = "tcp"
+    ports    = ["22", "3389"]
+  }
+}
+"#,
+            )],
+            "",
+        );
+        let flagged: Vec<_> = report.findings.iter().filter_map(|f| f.resource.as_deref()).collect();
+        assert_eq!(
+            flagged,
+            vec!["aws_security_group.open", "azurerm_network_security_rule.ssh", "google_compute_firewall.ssh"]
+        );
+        assert!(report.findings.iter().all(|f| f.rule == "open_ssh" && f.severity == ERROR));
+    }
+
+    #[test]
+    fn nat_and_encryption_rules() {
+        let report = lint(
+            &[(
+                "main.tf",
+                r#"
+module "vpc" {
+  source             = "terraform-aws-modules/vpc/aws"
+  enable_nat_gateway = false
+}
+
+resource "aws_s3_bucket" "root" {}
+resource "aws_s3_bucket" "catalog" {}
+
+resource "aws_s3_bucket_server_side_encryption_configuration" "catalog" {
+  bucket = aws_s3_bucket.catalog.id
+}
+
+resource "azurerm_storage_account" "uc" {
+  https_traffic_only_enabled = false
+  min_tls_version            = "TLS1_1"
+}
+"#,
+            )],
+            "",
+        );
+        assert_eq!(
+            rules(&report),
+            vec![("storage_encryption", ERROR), ("nat_egress", WARNING), ("storage_encryption", WARNING), ("storage_encryption", INFO)]
+        );
+        assert_eq!(report.findings[3].resource.as_deref(), Some("aws_s3_bucket.root"));
+    }
+
+    #[test]
+    fn metastore_region_resolves_root_variables() {
+        let files = [(
+            "main.tf",
+            r#"
+variable "region" {
+  default = "us-east-1"
+}
+variable "metastore_region" {
+  default = "us-east-1"
+}
+
+resource "databricks_mws_workspaces" "this" {
+  aws_region = var.region
+}
+
+resource "databricks_metastore" "this" {
+  region = var.metastore_region
+}
+"#,
+        )];
+        assert!(lint(&files, "").findings.is_empty());
+
+        let report = lint(&files, "metastore_region = \"eu-west-1\"\n");
+        assert_eq!(rules(&report), vec![("metastore_region", ERROR)]);
+        assert!(report.findings[0].message.contains("eu-west-1"));
+    }
+
+    #[test]
+    fn reports_parse_errors_and_bundled_templates_have_no_errors() {
+        let report = lint(&[("broken.tf", "resource \"x\" \"y\" {\n"), ("ok.tf", "locals {}\n")], "");
+        assert_eq!(report.files_scanned, 1);
+        assert_eq!(report.parse_errors.len(), 1);
+
+        let templates = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("templates");
+        for template in ["aws-simple", "aws-sra", "azure-simple", "azure-sra", "gcp-simple", "gcp-sra"] {
+            let report = lint_dir(&templates.join(template), "");
+            assert!(report.parse_errors.is_empty(), "{}: {:?}", template, report.parse_errors);
+            assert!(report.findings.iter().all(|f| f.severity != ERROR), "{}: {:?}", template, report.findings);
+        }
+    }
+}
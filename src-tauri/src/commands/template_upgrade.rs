@@ -0,0 +1,340 @@
+//! Upgrade a deployment's Terraform files to the current template version.
+//!
+//! Deployments get a copy of their template when first saved, so a template
+//! refresh (a new [`TEMPLATES_VERSION`]) leaves them on the old sources.
+//! This module diffs the deployment against the current template and, on
+//! request, copies the changed files over. Values, state, and app bookkeeping
+//! are never touched, and every overwritten file is first saved under
+//! `.template-backups/<millis>/`.
+
+use super::bundle::collect_files;
+use super::{
+    get_deployments_dir, get_templates_dir, lock_or_recover, sanitize_deployment_name, sanitize_template_id,
+    INTERNAL_VARIABLES, TEMPLATES_VERSION,
+};
+use crate::terraform::{self, DEPLOYMENT_STATUS};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Directory inside a deployment holding files replaced by upgrades.
+pub(crate) const TEMPLATE_BACKUP_DIR: &str = ".template-backups";
+
+/// Lines of unchanged context around each change in a diff.
+const DIFF_CONTEXT: usize = 2;
+
+/// Largest `old_lines * new_lines` product diffed line by line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// How a file differs between the deployment and the current template.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TemplateFileChange {
+    pub path: String,
+    /// "added" (new in the template), "modified", or "not_in_template"
+    /// (a deployment `.tf` file the template no longer has; left in place).
+    pub status: String,
+    /// Line diff for modified files (`-` deployment, `+` template).
+    pub diff: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateUpgradePreview {
+    pub template_id: String,
+    /// Templates version the deployment was last saved with, if recorded.
+    pub current_version: Option<String>,
+    pub target_version: String,
+    pub changes: Vec<TemplateFileChange>,
+    /// Required variables added by the template that `terraform.tfvars` lacks.
+    pub new_required_variables: Vec<String>,
+    pub up_to_date: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateUpgradeResult {
+    pub templates_version: String,
+    pub updated_files: Vec<String>,
+    /// Where replaced files were saved, when any were replaced.
+    pub backup_dir: Option<String>,
+    pub new_required_variables: Vec<String>,
+}
+
+// ─── Diff ───────────────────────────────────────────────────────────────────
+
+/// Line diff of `old` → `new` with [`DIFF_CONTEXT`] lines of context; hunks
+/// are separated by `...`.
+fn line_diff(old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        return format!("(file too large to diff: {} → {} lines)", a.len(), b.len());
+    }
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = ops.iter().enumerate().filter(|(_, (op, _))| *op != ' ').map(|(k, _)| k).collect();
+    let keep = |k: usize| {
+        changed
+            .iter()
+            .any(|&c| k + DIFF_CONTEXT >= c && k <= c + DIFF_CONTEXT)
+    };
+    let mut out = String::new();
+    let mut skipped = false;
+    for (k, (op, line)) in ops.iter().enumerate() {
+        if keep(k) {
+            if skipped && !out.is_empty() {
+                out.push_str("...\n");
+            }
+            skipped = false;
+            out.push_str(&format!("{}{}\n", op, line));
+        } else {
+            skipped = true;
+        }
+    }
+    out
+}
+
+// ─── Upgrade ────────────────────────────────────────────────────────────────
+
+fn is_terraform_source(path: &str) -> bool {
+    !path.contains('/') && (path.ends_with(".tf") || path.ends_with(".tf.json"))
+}
+
+/// Files that differ between the deployment and the template.
+pub(crate) fn diff_template(deployment_dir: &Path, template_dir: &Path) -> Result<Vec<TemplateFileChange>, String> {
+    let template_files = collect_files(template_dir)?;
+    let mut changes = Vec::new();
+    for rel in &template_files {
+        let new = fs::read(template_dir.join(rel)).map_err(|e| format!("Failed to read template file {}: {}", rel, e))?;
+        match fs::read(deployment_dir.join(rel)) {
+            Err(_) => changes.push(TemplateFileChange {
+                path: rel.clone(),
+                status: "added".to_string(),
+                diff: None,
+            }),
+            Ok(old) if old != new => changes.push(TemplateFileChange {
+                path: rel.clone(),
+                status: "modified".to_string(),
+                diff: Some(line_diff(&String::from_utf8_lossy(&old), &String::from_utf8_lossy(&new))),
+            }),
+            Ok(_) => {}
+        }
+    }
+    for rel in collect_files(deployment_dir)? {
+        if is_terraform_source(&rel) && !template_files.contains(&rel) {
+            changes.push(TemplateFileChange {
+                path: rel,
+                status: "not_in_template".to_string(),
+                diff: None,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Required template variables with no value in the deployment's
+/// `terraform.tfvars` or its stored sensitive values.
+fn missing_required_variables(deployment_dir: &Path, template_dir: &Path) -> Result<Vec<String>, String> {
+    let Ok(content) = fs::read_to_string(template_dir.join("variables.tf")) else {
+        return Ok(Vec::new());
+    };
+    let tfvars = fs::read_to_string(deployment_dir.join("terraform.tfvars")).unwrap_or_default();
+    let mut set: Vec<String> = super::audit::parse_tfvars(&tfvars).into_iter().map(|(k, _)| k).collect();
+    set.extend(super::secret_vars::stored_names(deployment_dir));
+    Ok(terraform::parse_variables_tf(&content)?
+        .into_iter()
+        .filter(|v| v.required && !set.contains(&v.name) && !INTERNAL_VARIABLES.contains(&v.name.as_str()))
+        .map(|v| v.name)
+        .collect())
+}
+
+/// Copy added and modified template files into the deployment, saving the
+/// replaced versions under [`TEMPLATE_BACKUP_DIR`]. Returns the updated
+/// paths and the backup directory (if anything was replaced).
+pub(crate) fn apply_template_upgrade(
+    deployment_dir: &Path,
+    template_dir: &Path,
+) -> Result<(Vec<String>, Option<PathBuf>), String> {
+    let changes = diff_template(deployment_dir, template_dir)?;
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup_dir = deployment_dir.join(TEMPLATE_BACKUP_DIR).join(millis.to_string());
+    let mut backed_up = false;
+    let mut updated = Vec::new();
+
+    for change in changes.iter().filter(|c| c.status != "not_in_template") {
+        let target = deployment_dir.join(&change.path);
+        if change.status == "modified" {
+            let backup = backup_dir.join(&change.path);
+            if let Some(parent) = backup.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create template backup: {}", e))?;
+            }
+            fs::copy(&target, &backup).map_err(|e| format!("Failed to back up {}: {}", change.path, e))?;
+            backed_up = true;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(template_dir.join(&change.path), &target)
+            .map_err(|e| format!("Failed to update {}: {}", change.path, e))?;
+        updated.push(change.path.clone());
+    }
+    Ok((updated, backed_up.then_some(backup_dir)))
+}
+
+/// Deployment directory and its recorded template directory.
+fn resolve(app: &AppHandle, deployment_name: &str) -> Result<(PathBuf, PathBuf, super::audit::DeploymentMeta), String> {
+    let deployment_dir = get_deployments_dir(app)?.join(sanitize_deployment_name(deployment_name)?);
+    if !deployment_dir.exists() {
+        return Err("Deployment not found".to_string());
+    }
+    let meta = super::audit::read_meta(&deployment_dir).ok_or(
+        "This deployment has no recorded template. Save its configuration again to record one before upgrading.",
+    )?;
+    let template_dir = get_templates_dir(app)?.join(sanitize_template_id(&meta.template_id)?);
+    if !template_dir.join("variables.tf").exists() {
+        return Err(format!("Template '{}' is no longer available", meta.template_id));
+    }
+    Ok((deployment_dir, template_dir, meta))
+}
+
+// ─── Commands ───────────────────────────────────────────────────────────────
+
+/// Compare a deployment's Terraform files with the current template version.
+#[tauri::command]
+pub fn diff_deployment_template(app: AppHandle, deployment_name: String) -> Result<TemplateUpgradePreview, String> {
+    let (deployment_dir, template_dir, meta) = resolve(&app, &deployment_name)?;
+    let changes = diff_template(&deployment_dir, &template_dir)?;
+    Ok(TemplateUpgradePreview {
+        up_to_date: changes.iter().all(|c| c.status == "not_in_template"),
+        template_id: meta.template_id,
+        current_version: Some(meta.templates_version).filter(|v| !v.is_empty()),
+        target_version: TEMPLATES_VERSION.to_string(),
+        new_required_variables: missing_required_variables(&deployment_dir, &template_dir)?,
+        changes,
+    })
+}
+
+/// Bring a deployment's Terraform files up to the current template version,
+/// keeping `terraform.tfvars`, state, and files the template doesn't have.
+/// Run `init` afterwards in case provider requirements changed.
+#[tauri::command]
+pub fn upgrade_deployment_template(app: AppHandle, deployment_name: String) -> Result<TemplateUpgradeResult, String> {
+    if lock_or_recover(&DEPLOYMENT_STATUS).running {
+        return Err("Cannot upgrade the template while a deployment is running".to_string());
+    }
+    let (deployment_dir, template_dir, meta) = resolve(&app, &deployment_name)?;
+    let (updated_files, backup_dir) = apply_template_upgrade(&deployment_dir, &template_dir)?;
+    super::audit::record_template(&deployment_dir, &meta.template_id)?;
+    super::telemetry::record_feature(&app, super::telemetry::Feature::TemplateUpgrade);
+    Ok(TemplateUpgradeResult {
+        templates_version: TEMPLATES_VERSION.to_string(),
+        updated_files,
+        backup_dir: backup_dir.map(|d| d.to_string_lossy().to_string()),
+        new_required_variables: missing_required_variables(&deployment_dir, &template_dir)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> (tempfile::TempDir, tempfile::TempDir) {
+        let template = tempfile::tempdir().unwrap();
+        fs::write(
+            template.path().join("variables.tf"),
+            "variable \"region\" {\n  type = string\n}\nvariable \"vpc_cidr\" {\n  type = string\n}\n",
+        )
+        .unwrap();
+        fs::write(template.path().join("main.tf"), "a\nb\nc-new\nd\n").unwrap();
+        fs::create_dir_all(template.path().join("modules/net")).unwrap();
+        fs::write(template.path().join("modules/net/main.tf"), "# net\n").unwrap();
+
+        let deployment = tempfile::tempdir().unwrap();
+        fs::write(
+            deployment.path().join("variables.tf"),
+            "variable \"region\" {\n  type = string\n}\nvariable \"vpc_cidr\" {\n  type = string\n}\n",
+        )
+        .unwrap();
+        fs::write(deployment.path().join("main.tf"), "a\nb\nc\nd\n").unwrap();
+        fs::write(deployment.path().join("custom.tf"), "# mine\n").unwrap();
+        fs::write(deployment.path().join("terraform.tfvars"), "region = \"us-east-1\"\n").unwrap();
+        fs::write(deployment.path().join("terraform.tfstate"), "{}").unwrap();
+        (template, deployment)
+    }
+
+    #[test]
+    fn diffs_lines_with_context() {
+        assert_eq!(line_diff("a\nb\nc\nd\n", "a\nb\nc-new\nd\n"), " a\n b\n-c\n+c-new\n d\n");
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20)
+            .map(|n| match n {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                n => format!("{}\n", n),
+            })
+            .collect();
+        let diff = line_diff(&old, &new);
+        assert!(diff.contains("...\n"));
+        assert!(!diff.contains(" 10\n"));
+    }
+
+    #[test]
+    fn reports_added_modified_and_extra_files() {
+        let (template, deployment) = fixture();
+        let changes = diff_template(deployment.path(), template.path()).unwrap();
+        let summary: Vec<(&str, &str)> = changes.iter().map(|c| (c.path.as_str(), c.status.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![("main.tf", "modified"), ("modules/net/main.tf", "added"), ("custom.tf", "not_in_template")]
+        );
+        assert_eq!(missing_required_variables(deployment.path(), template.path()).unwrap(), vec!["vpc_cidr"]);
+    }
+
+    #[test]
+    fn upgrade_keeps_values_state_and_backs_up_replaced_files() {
+        let (template, deployment) = fixture();
+        let (updated, backup) = apply_template_upgrade(deployment.path(), template.path()).unwrap();
+        assert_eq!(updated, vec!["main.tf", "modules/net/main.tf"]);
+        assert_eq!(fs::read_to_string(deployment.path().join("main.tf")).unwrap(), "a\nb\nc-new\nd\n");
+        assert_eq!(fs::read_to_string(backup.unwrap().join("main.tf")).unwrap(), "a\nb\nc\nd\n");
+        assert_eq!(
+            fs::read_to_string(deployment.path().join("terraform.tfvars")).unwrap(),
+            "region = \"us-east-1\"\n"
+        );
+        assert!(deployment.path().join("terraform.tfstate").exists());
+        assert!(deployment.path().join("custom.tf").exists());
+
+        // A second pass has nothing left to change
+        let remaining = diff_template(deployment.path(), template.path()).unwrap();
+        assert!(remaining.iter().all(|c| c.status == "not_in_template"));
+    }
+}
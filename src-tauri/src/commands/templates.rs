@@ -1,123 +1,387 @@
 //! Template management commands — setup, listing, variable parsing.
 
+use super::bundle::collect_files_except;
 use super::{
-    copy_dir_all, get_templates_dir, sanitize_template_id, Template, INTERNAL_VARIABLES,
-    TEMPLATES_VERSION,
+    copy_dir_all, debug_log, get_templates_dir, lock_or_recover, sanitize_template_id, Template,
+    INTERNAL_VARIABLES, TEMPLATES_VERSION,
 };
+use crate::storage::Environment;
 use crate::terraform;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
 
 const GITHUB_TEMPLATES_BASE: &str =
     "https://github.com/OgnjenPantelic/workspace-creator/tree/main/src-tauri/templates";
 
+/// Hashes of the extracted template files, keyed by path relative to the templates dir.
+const TEMPLATES_MANIFEST_FILE: &str = ".templates_manifest.json";
+
+/// Extraction target inside the data dir; renamed over the templates dir once complete.
+const TEMPLATES_STAGING_DIR: &str = ".templates.staging";
+
+/// SHA-256 of every bundled template file, computed by `build.rs` at compile time.
+const EMBEDDED_TEMPLATES_MANIFEST: &str =
+    include_str!(concat!(env!("OUT_DIR"), "/templates_manifest.json"));
+
+lazy_static::lazy_static! {
+    static ref EMBEDDED_MANIFEST: BTreeMap<String, String> =
+        serde_json::from_str(EMBEDDED_TEMPLATES_MANIFEST).unwrap_or_default();
+
+    /// Outcome of the most recent template extraction, surfaced to the UI after startup.
+    static ref LAST_TEMPLATE_REFRESH: Mutex<Option<TemplateRefreshReport>> = Mutex::new(None);
+}
+
+/// What a template refresh did, including any user-modified files it set aside.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateRefreshReport {
+    pub refreshed: bool,
+    pub version: String,
+    pub preserved_files: Vec<String>,
+    pub backup_dir: Option<String>,
+}
+
+/// Extracted templates compared against the checksums embedded at build time.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateIntegrityReport {
+    pub intact: bool,
+    pub version: String,
+    pub checked: usize,
+    /// Files whose contents differ from the bundled copy (tampered or corrupted).
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    /// Files not shipped with the app.
+    pub unexpected: Vec<String>,
+    /// Set when `repair` re-extracted the templates.
+    pub repaired: Option<TemplateRefreshReport>,
+}
+
 /// Copy bundled templates into app-data on first run (or version change).
-pub fn setup_templates(app: &AppHandle) -> Result<(), String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+pub fn setup_templates(app: &AppHandle) -> Result<TemplateRefreshReport, String> {
+    let report = setup_templates_in(app)?;
+    if report.refreshed {
+        *lock_or_recover(&LAST_TEMPLATE_REFRESH) = Some(report.clone());
+    }
+    Ok(report)
+}
 
-    let templates_dir = app_data_dir.join("templates");
-    let version_file = app_data_dir.join(".templates_version");
+/// Report from the last template refresh in this session, if one happened.
+#[tauri::command]
+pub fn get_template_refresh_report() -> Option<TemplateRefreshReport> {
+    lock_or_recover(&LAST_TEMPLATE_REFRESH).clone()
+}
+
+/// Copy bundled templates into the data directory if missing or outdated.
+///
+/// Templates are extracted into a staging dir and renamed into place, and the
+/// version file is written last, so an interrupted run is re-done on next start.
+/// Files the user edited (or added) since the last extraction are copied to
+/// `templates.backup-<ts>` before the old templates are removed.
+pub(crate) fn setup_templates_in(env: &dyn Environment) -> Result<TemplateRefreshReport, String> {
+    let templates_dir = env.templates_dir()?;
+    let data_dir = env.data_dir()?;
+    let version_file = data_dir.join(".templates_version");
+
+    // Clear out a staging dir left behind by an interrupted extraction
+    let staging_dir = data_dir.join(TEMPLATES_STAGING_DIR);
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)
+            .map_err(|e| format!("Failed to remove stale template staging dir: {}", e))?;
+    }
 
     // Check if we need to update templates
     let needs_update = if templates_dir.exists() {
         match fs::read_to_string(&version_file) {
-            Ok(version) => version.trim() != TEMPLATES_VERSION,
-            Err(_) => true,
+            Ok(version) if version.trim() == TEMPLATES_VERSION => {
+                let complete = templates_complete(&data_dir, &templates_dir);
+                if !complete {
+                    debug_log!("[templates] Extracted templates are incomplete, repairing");
+                }
+                !complete
+            }
+            _ => true,
         }
     } else {
         true
     };
 
+    let mut report = TemplateRefreshReport {
+        version: TEMPLATES_VERSION.to_string(),
+        ..Default::default()
+    };
     if !needs_update {
-        return Ok(());
+        return Ok(report);
     }
 
-    // Remove old templates to copy fresh ones (version changed or first run)
+    // Extract into a staging dir so a crash never leaves a half-copied templates dir
+    let templates_source = bundled_templates_source(env)?;
+    copy_dir_all(&templates_source, &staging_dir).map_err(|e| {
+        let _ = fs::remove_dir_all(&staging_dir);
+        format!("Failed to extract templates: {}", e)
+    })?;
+    let hashes = hash_templates(&staging_dir)?;
+    let check = compare_to_manifest(&hashes, &EMBEDDED_MANIFEST);
+    if !check.intact {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(format!(
+            "Bundled templates failed checksum validation ({} modified, {} missing, {} unexpected)",
+            check.modified.len(),
+            check.missing.len(),
+            check.unexpected.len()
+        ));
+    }
+    let manifest = serde_json::to_string_pretty(&hashes)
+        .map_err(|e| format!("Failed to serialize template manifest: {}", e))?;
+
+    // Invalidate the version first: any crash from here on triggers a re-extract
+    let _ = fs::remove_file(&version_file);
+
+    // Remove old templates to swap in fresh ones (version changed, first run, or repair)
     if templates_dir.exists() {
+        let (preserved, backup_dir) = preserve_modified_templates(&data_dir, &templates_dir)?;
+        report.preserved_files = preserved;
+        report.backup_dir = backup_dir.map(|d| d.to_string_lossy().to_string());
         fs::remove_dir_all(&templates_dir)
             .map_err(|e| format!("Failed to remove old templates: {}", e))?;
     }
+    // The old manifest no longer describes what's on disk
+    let manifest_file = data_dir.join(TEMPLATES_MANIFEST_FILE);
+    let _ = fs::remove_file(&manifest_file);
 
-    fs::create_dir_all(&templates_dir).map_err(|e| e.to_string())?;
+    fs::rename(&staging_dir, &templates_dir)
+        .map_err(|e| format!("Failed to move extracted templates into place: {}", e))?;
+
+    fs::write(&manifest_file, manifest)
+        .map_err(|e| format!("Failed to write template manifest: {}", e))?;
+
+    // Write version file
+    fs::write(&version_file, TEMPLATES_VERSION)
+        .map_err(|e| format!("Failed to write version: {}", e))?;
+
+    report.refreshed = true;
+    Ok(report)
+}
+
+/// Diff actual file hashes against an expected manifest.
+fn compare_to_manifest(
+    actual: &BTreeMap<String, String>,
+    expected: &BTreeMap<String, String>,
+) -> TemplateIntegrityReport {
+    let mut report = TemplateIntegrityReport {
+        version: TEMPLATES_VERSION.to_string(),
+        checked: expected.len(),
+        ..Default::default()
+    };
+    for (rel, hash) in expected {
+        match actual.get(rel) {
+            Some(h) if h == hash => {}
+            Some(_) => report.modified.push(rel.clone()),
+            None => report.missing.push(rel.clone()),
+        }
+    }
+    report.unexpected = actual
+        .keys()
+        .filter(|rel| !expected.contains_key(*rel))
+        .cloned()
+        .collect();
+    report.intact =
+        report.modified.is_empty() && report.missing.is_empty() && report.unexpected.is_empty();
+    report
+}
+
+/// Verify extracted templates against the embedded checksums, optionally
+/// re-extracting them when they don't match. User edits are preserved to
+/// `templates.backup-<ts>` by the re-extraction.
+pub(crate) fn verify_templates_in(
+    env: &dyn Environment,
+    repair: bool,
+) -> Result<TemplateIntegrityReport, String> {
+    let templates_dir = env.templates_dir()?;
+    let actual = if templates_dir.exists() {
+        hash_templates(&templates_dir)?
+    } else {
+        BTreeMap::new()
+    };
+    let mut report = compare_to_manifest(&actual, &EMBEDDED_MANIFEST);
+
+    if repair && !report.intact {
+        // Dropping the version file forces a full re-extract
+        let version_file = env.data_dir()?.join(".templates_version");
+        if version_file.exists() {
+            fs::remove_file(&version_file)
+                .map_err(|e| format!("Failed to remove version file: {}", e))?;
+        }
+        report.repaired = Some(setup_templates_in(env)?);
+    }
+    Ok(report)
+}
+
+/// Check extracted templates for tampered or corrupted files; `repair` re-extracts them.
+#[tauri::command]
+pub fn verify_templates_integrity(
+    app: AppHandle,
+    repair: Option<bool>,
+) -> Result<TemplateIntegrityReport, String> {
+    verify_templates_in(&app, repair.unwrap_or(false))
+}
 
-    // Copy embedded templates
-    let source_templates = app
-        .path()
+/// Locate the bundled templates: resource dir (production), then dev locations.
+fn bundled_templates_source(env: &dyn Environment) -> Result<PathBuf, String> {
+    if let Some(path) = env
         .resource_dir()
-        .ok()
         .map(|d| d.join("templates"))
-        .filter(|p| p.exists());
+        .filter(|p| p.exists())
+    {
+        return Ok(path);
+    }
 
-    // Try resource dir first (production), then fall back to dev location
-    let templates_source = if let Some(path) = source_templates {
-        path
-    } else {
-        // In dev builds, CARGO_MANIFEST_DIR points to src-tauri/
-        let manifest_candidate =
-            std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("templates");
-        if manifest_candidate.exists() {
-            manifest_candidate
-        } else {
-            let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
-            let mut search_path = exe_path.parent();
-
-            let mut dev_templates = None;
-            while let Some(path) = search_path {
-                let candidate = path.join("src-tauri").join("templates");
-                if candidate.exists() {
-                    dev_templates = Some(candidate);
-                    break;
-                }
-                search_path = path.parent();
-            }
+    // In dev builds, CARGO_MANIFEST_DIR points to src-tauri/
+    let manifest_candidate = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("templates");
+    if manifest_candidate.exists() {
+        return Ok(manifest_candidate);
+    }
 
-            match dev_templates {
-                Some(path) => path,
-                None => {
-                    return Err(
-                        "Templates not found in resource dir or src-tauri directory".to_string(),
-                    )
-                }
-            }
+    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut search_path = exe_path.parent();
+    while let Some(path) = search_path {
+        let candidate = path.join("src-tauri").join("templates");
+        if candidate.exists() {
+            return Ok(candidate);
         }
+        search_path = path.parent();
+    }
+
+    Err("Templates not found in resource dir or src-tauri directory".to_string())
+}
+
+/// Extraction manifest, if present and readable.
+fn read_manifest(data_dir: &Path) -> Option<BTreeMap<String, String>> {
+    let raw = fs::read_to_string(data_dir.join(TEMPLATES_MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Whether every file recorded in the extraction manifest is present. Installs
+/// that predate the manifest have no way to tell, so they count as complete.
+fn templates_complete(data_dir: &Path, templates_dir: &Path) -> bool {
+    if !data_dir.join(TEMPLATES_MANIFEST_FILE).exists() {
+        return true;
+    }
+    match read_manifest(data_dir) {
+        Some(manifest) => {
+            !manifest.is_empty() && manifest.keys().all(|rel| templates_dir.join(rel).is_file())
+        }
+        None => false,
+    }
+}
+
+/// Relative paths (with `/` separators) of every file under `root`.
+fn list_files(root: &Path) -> Result<Vec<String>, String> {
+    collect_files_except(root, |_| false)
+}
+
+/// Hex-encoded SHA-256 of a file's contents.
+pub(super) fn sha256_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// SHA-256 of every file under `templates_dir`, keyed by relative path.
+fn hash_templates(templates_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    list_files(templates_dir)?
+        .into_iter()
+        .map(|rel| {
+            let hash = sha256_file(&templates_dir.join(&rel))?;
+            Ok((rel, hash))
+        })
+        .collect()
+}
+
+/// Files whose hash differs from the extraction manifest, or that the manifest
+/// doesn't know about. Without a readable manifest (installs predating it) nothing
+/// can be attributed to the user, so nothing is reported.
+fn modified_templates(data_dir: &Path, templates_dir: &Path) -> Result<Vec<String>, String> {
+    let Some(manifest) = read_manifest(data_dir) else {
+        return Ok(Vec::new());
     };
 
-    copy_dir_all(&templates_source, &templates_dir)?;
+    let mut modified = Vec::new();
+    for rel in list_files(templates_dir)? {
+        let hash = sha256_file(&templates_dir.join(&rel))?;
+        if manifest.get(&rel) != Some(&hash) {
+            modified.push(rel);
+        }
+    }
+    Ok(modified)
+}
 
-    // Write version file
-    fs::write(&version_file, TEMPLATES_VERSION)
-        .map_err(|e| format!("Failed to write version: {}", e))?;
+/// Copy user-modified template files into `templates.backup-<ts>` so a refresh
+/// doesn't destroy local edits. Returns the preserved paths and the backup dir.
+fn preserve_modified_templates(
+    data_dir: &Path,
+    templates_dir: &Path,
+) -> Result<(Vec<String>, Option<PathBuf>), String> {
+    let modified = modified_templates(data_dir, templates_dir)?;
+    if modified.is_empty() {
+        return Ok((modified, None));
+    }
 
-    Ok(())
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup_dir = data_dir.join(format!("templates.backup-{}", ts));
+    for rel in &modified {
+        let dest = backup_dir.join(rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create template backup: {}", e))?;
+        }
+        fs::copy(templates_dir.join(rel), &dest)
+            .map_err(|e| format!("Failed to back up {}: {}", rel, e))?;
+    }
+    Ok((modified, Some(backup_dir)))
 }
 
 /// Clear cached templates and force refresh.
 #[tauri::command]
 pub fn clear_templates_cache(app: AppHandle) -> Result<String, String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let app_data_dir = app.data_dir()?;
 
-    let templates_dir = app_data_dir.join("templates");
     let version_file = app_data_dir.join(".templates_version");
 
-    if templates_dir.exists() {
-        fs::remove_dir_all(&templates_dir)
-            .map_err(|e| format!("Failed to remove templates: {}", e))?;
-    }
-
     if version_file.exists() {
         fs::remove_file(&version_file)
             .map_err(|e| format!("Failed to remove version file: {}", e))?;
     }
 
-    setup_templates(&app)?;
+    // Without a version file the refresh below preserves local edits, then re-extracts.
+    let report = setup_templates(&app)?;
 
-    Ok("Templates cache cleared and refreshed".to_string())
+    match report.backup_dir {
+        Some(dir) => Ok(format!(
+            "Templates cache cleared and refreshed. {} modified file(s) preserved in {}",
+            report.preserved_files.len(),
+            dir
+        )),
+        None => Ok("Templates cache cleared and refreshed".to_string()),
+    }
 }
 
 /// List available deployment templates.
 #[tauri::command]
 pub fn get_templates(app: AppHandle) -> Result<Vec<Template>, String> {
-    let templates_dir = get_templates_dir(&app)?;
+    Ok(list_templates(&get_templates_dir(&app)?))
+}
+
+/// Templates present under `templates_dir`.
+pub(crate) fn list_templates(templates_dir: &std::path::Path) -> Vec<Template> {
     let mut templates = Vec::new();
 
     if templates_dir.join("aws-simple").exists() {
@@ -249,18 +513,33 @@ pub fn get_templates(app: AppHandle) -> Result<Vec<Template>, String> {
         });
     }
 
-    Ok(templates)
+    templates
 }
 
-/// Parse and return the Terraform variables for a given template.
+/// Parse and return the Terraform variables for a given template. With
+/// `profile`, defaults come from that saved organization profile where it
+/// sets a value.
 #[tauri::command]
 pub fn get_template_variables(
     app: AppHandle,
     template_id: String,
+    profile: Option<String>,
 ) -> Result<Vec<terraform::TerraformVariable>, String> {
-    let safe_template_id = sanitize_template_id(&template_id)?;
+    let mut variables = template_variables(&app, &template_id)?;
+    if let Some(name) = profile.filter(|p| !p.trim().is_empty()) {
+        super::org_profiles::apply_saved_profile(&app, &name, &template_id, &mut variables)?;
+    }
+    Ok(variables)
+}
 
-    let templates_dir = get_templates_dir(&app)?;
+/// Parsed, ordered, user-facing variables of a template.
+pub(crate) fn template_variables(
+    env: &dyn Environment,
+    template_id: &str,
+) -> Result<Vec<terraform::TerraformVariable>, String> {
+    let safe_template_id = sanitize_template_id(template_id)?;
+
+    let templates_dir = get_templates_dir(env)?;
     let variables_path = templates_dir.join(&safe_template_id).join("variables.tf");
 
     if !variables_path.exists() {
@@ -268,7 +547,19 @@ pub fn get_template_variables(
     }
 
     let content = fs::read_to_string(&variables_path).map_err(|e| e.to_string())?;
-    let variables = terraform::parse_variables_tf(&content);
+    let mut variables = terraform::parse_variables_tf(&content)?;
+
+    // Optional companion file with grouping/ordering metadata
+    let meta_path = templates_dir.join(&safe_template_id).join("variables.meta.json");
+    if let Ok(meta_json) = fs::read_to_string(&meta_path) {
+        terraform::apply_variables_meta(&mut variables, &meta_json)?;
+    } else {
+        terraform::sort_variables_by_order(&mut variables);
+    }
+
+    if let Some(cloud) = safe_template_id.split('-').next() {
+        super::variable_sources::annotate_data_sources(&mut variables, cloud);
+    }
 
     // Filter out internal variables that are automatically set by the app
     let filtered_variables: Vec<terraform::TerraformVariable> = variables
@@ -279,6 +570,60 @@ pub fn get_template_variables(
     Ok(filtered_variables)
 }
 
+/// What a template produces and needs, read from its `.tf` files.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateDetails {
+    pub outputs: Vec<terraform::TerraformOutput>,
+    #[serde(flatten)]
+    pub requirements: terraform::TerraformRequirements,
+}
+
+/// Outputs and Terraform/provider version requirements of a template, for
+/// display next to [`get_template_variables`]. Requirements of the template's
+/// local modules are included; the root module's declarations take precedence.
+#[tauri::command]
+pub fn get_template_details(app: AppHandle, template_id: String) -> Result<TemplateDetails, String> {
+    template_details(&app, &template_id)
+}
+
+/// `.tf` files under `dir`, root module first, each directory sorted by name.
+/// Hidden directories such as `.terraform` are skipped.
+pub(super) fn tf_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    let (dirs, files): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|p| p.is_dir());
+    let mut tf: Vec<PathBuf> = files.into_iter().filter(|p| p.extension().is_some_and(|ext| ext == "tf")).collect();
+    for sub in dirs {
+        tf.extend(tf_files(&sub));
+    }
+    tf
+}
+
+pub(crate) fn template_details(env: &dyn Environment, template_id: &str) -> Result<TemplateDetails, String> {
+    let safe_template_id = sanitize_template_id(template_id)?;
+    let template_dir = get_templates_dir(env)?.join(&safe_template_id);
+    if !template_dir.join("variables.tf").exists() {
+        return Err(format!("Template not found: {}", safe_template_id));
+    }
+
+    let mut details = TemplateDetails { outputs: Vec::new(), requirements: Default::default() };
+    for path in tf_files(&template_dir) {
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        if path.parent() == Some(template_dir.as_path()) {
+            details.outputs.extend(terraform::parse_outputs_tf(&content));
+        }
+        terraform::parse_terraform_requirements(&content, &mut details.requirements);
+    }
+    Ok(details)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,7 +641,7 @@ mod tests {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path)
             .expect("azure-simple/variables.tf should exist");
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         assert!(vars.len() >= 20, "azure-simple should have many variables, got {}", vars.len());
 
@@ -314,7 +659,7 @@ mod tests {
     fn azure_simple_required_variables() {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         let tenant_id = vars.iter().find(|v| v.name == "tenant_id").unwrap();
         assert!(tenant_id.required, "tenant_id should be required (no default)");
@@ -327,7 +672,7 @@ mod tests {
     fn azure_simple_optional_variables() {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         let create_rg = vars.iter().find(|v| v.name == "create_new_resource_group").unwrap();
         assert!(!create_rg.required, "create_new_resource_group has a default");
@@ -342,7 +687,7 @@ mod tests {
     fn azure_simple_sensitive_variables() {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         let account_id = vars.iter().find(|v| v.name == "databricks_account_id").unwrap();
         assert!(!account_id.sensitive, "databricks_account_id is an identifier, not a secret");
@@ -355,7 +700,7 @@ mod tests {
     fn azure_simple_map_and_bool_types() {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         let tags = vars.iter().find(|v| v.name == "tags").unwrap();
         assert!(tags.var_type.contains("map"), "tags should be map type");
@@ -371,7 +716,7 @@ mod tests {
         let variables_path = real_templates_dir().join("aws-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path)
             .expect("aws-simple/variables.tf should exist");
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         assert!(!vars.is_empty(), "aws-simple should have variables");
 
@@ -386,7 +731,7 @@ mod tests {
         let variables_path = real_templates_dir().join("gcp-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path)
             .expect("gcp-simple/variables.tf should exist");
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
 
         assert!(!vars.is_empty(), "gcp-simple should have variables");
 
@@ -396,13 +741,102 @@ mod tests {
         assert!(names.contains(&"google_credentials_json"));
     }
 
+    // ── Real template parsing: every bundled variables.tf ───────────────
+
+    fn bundled_variables() -> Vec<(String, String)> {
+        let mut files: Vec<(String, String)> = fs::read_dir(real_templates_dir())
+            .unwrap()
+            .flatten()
+            .filter_map(|e| {
+                let content = fs::read_to_string(e.path().join("variables.tf")).ok()?;
+                Some((e.file_name().to_string_lossy().to_string(), content))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn every_bundled_template_parses_completely() {
+        let files = bundled_variables();
+        assert!(files.len() >= 7, "expected all bundled templates, got {}", files.len());
+
+        for (template, content) in &files {
+            crate::hcl_reader::parse(content).unwrap_or_else(|e| panic!("{}: {}", template, e));
+            let vars = terraform::parse_variables_tf(content).unwrap();
+            let declared = content.lines().filter(|l| l.starts_with("variable \"")).count();
+            assert_eq!(vars.len(), declared, "{}: every variable block should be parsed", template);
+
+            for var in &vars {
+                let opens = var.var_type.matches(['(', '{']).count();
+                let closes = var.var_type.matches([')', '}']).count();
+                assert_eq!(opens, closes, "{}: {} type {:?} is truncated", template, var.name, var.var_type);
+                assert!(!var.var_type.contains('\n'), "{}: {} type spans lines", template, var.name);
+                assert!(!var.description.starts_with('"'), "{}: {} description is still quoted", template, var.name);
+                assert_eq!(var.required, var.default.is_none());
+                if let Some(condition) = &var.validation {
+                    assert!(condition.contains(&format!("var.{}", var.name)), "{}: {} validation {:?}", template, var.name, condition);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bundled_nested_types_and_defaults() {
+        let files: std::collections::HashMap<String, String> = bundled_variables().into_iter().collect();
+
+        let azure_sra = terraform::parse_variables_tf(&files["azure-sra"]).unwrap();
+        let vnet = azure_sra.iter().find(|v| v.name == "workspace_vnet").unwrap();
+        assert_eq!(vnet.var_type, "object({ cidr = string new_bits = optional(number, null) })");
+        assert_eq!(vnet.default.as_deref(), Some("null"));
+        assert!(vnet.validation.is_some());
+
+        let existing = azure_sra.iter().find(|v| v.name == "existing_workspace_vnet").unwrap();
+        assert!(existing.var_type.contains("network_configuration = object({"));
+        assert!(existing.var_type.contains("dns_zone_ids = object({"));
+
+        let aws_sra = terraform::parse_variables_tf(&files["aws-sra"]).unwrap();
+        let regions = aws_sra.iter().find(|v| v.name == "region_name_config").unwrap();
+        assert!(regions.var_type.starts_with("map(object({ primary_name = string"));
+        let default = regions.default.as_deref().unwrap();
+        assert!(default.starts_with("{ \"ap-northeast-1\" = { primary_name = \"tokyo\" }"));
+        assert!(default.ends_with('}') && !default.contains('\n'));
+        // The comment block above the variable is documentation, not metadata
+        assert_eq!(regions.meta, terraform::VariableMeta::default());
+    }
+
+    #[test]
+    fn bundled_template_details() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+
+        for (template, _) in bundled_variables() {
+            let details = template_details(&env, &template).unwrap();
+            assert!(!details.outputs.is_empty(), "{} should declare outputs", template);
+            assert!(
+                details.requirements.providers.iter().any(|p| p.name == "databricks" && p.source.as_deref() == Some("databricks/databricks")),
+                "{} should require the databricks provider",
+                template
+            );
+        }
+
+        let aws = template_details(&env, "aws-simple").unwrap();
+        assert_eq!(aws.requirements.required_version.as_deref(), Some("~> 1.3"));
+        let provider = aws.requirements.providers.iter().find(|p| p.name == "aws").unwrap();
+        assert_eq!(provider.version.as_deref(), Some(">= 5.76, < 7.0"));
+        let json = serde_json::to_value(&aws).unwrap();
+        assert!(json["required_version"].is_string() && json["providers"].is_array());
+        assert!(template_details(&env, "no-such-template").is_err());
+    }
+
     // ── INTERNAL_VARIABLES filtering ────────────────────────────────────
 
     #[test]
     fn internal_variables_filtered_from_gcp() {
         let variables_path = real_templates_dir().join("gcp-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let all_vars = terraform::parse_variables_tf(&content);
+        let all_vars = terraform::parse_variables_tf(&content).unwrap();
 
         let filtered: Vec<_> = all_vars
             .into_iter()
@@ -419,7 +853,7 @@ mod tests {
     fn internal_variables_no_effect_on_azure() {
         let variables_path = real_templates_dir().join("azure-simple").join("variables.tf");
         let content = fs::read_to_string(&variables_path).unwrap();
-        let all_vars = terraform::parse_variables_tf(&content);
+        let all_vars = terraform::parse_variables_tf(&content).unwrap();
         let all_count = all_vars.len();
 
         let internal_in_template = all_vars
@@ -453,10 +887,166 @@ mod tests {
         assert!(vars_path.exists(), "variables.tf should exist after copy");
 
         let content = fs::read_to_string(&vars_path).unwrap();
-        let vars = terraform::parse_variables_tf(&content);
+        let vars = terraform::parse_variables_tf(&content).unwrap();
         assert!(vars.len() >= 20, "parsed variables should match source");
     }
 
+    // ── Environment-backed setup (no Tauri app) ─────────────────────────
+
+    #[test]
+    fn setup_and_list_templates_in_storage_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+
+        setup_templates_in(&env).unwrap();
+        let ids: Vec<String> = list_templates(&env.templates_dir().unwrap())
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert!(ids.contains(&"aws-simple".to_string()));
+
+        let vars = template_variables(&env, "gcp-simple").unwrap();
+        assert!(vars.iter().all(|v| !INTERNAL_VARIABLES.contains(&v.name.as_str())));
+        assert!(template_variables(&env, "no-such-template").is_err());
+    }
+
+    #[test]
+    fn refresh_without_edits_preserves_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+
+        let first = setup_templates_in(&env).unwrap();
+        assert!(first.refreshed);
+        assert!(tmp.path().join(TEMPLATES_MANIFEST_FILE).exists());
+
+        // Same version: nothing to do.
+        assert!(!setup_templates_in(&env).unwrap().refreshed);
+
+        fs::write(tmp.path().join(".templates_version"), "0.0.0").unwrap();
+        let report = setup_templates_in(&env).unwrap();
+        assert!(report.refreshed);
+        assert!(report.preserved_files.is_empty());
+        assert!(report.backup_dir.is_none());
+    }
+
+    #[test]
+    fn refresh_preserves_user_modified_templates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+
+        let templates_dir = env.templates_dir().unwrap();
+        let edited = templates_dir.join("aws-simple").join("variables.tf");
+        let original = fs::read_to_string(&edited).unwrap();
+        fs::write(&edited, format!("{}\n# local edit\n", original)).unwrap();
+        fs::write(templates_dir.join("aws-simple").join("extra.tf"), "# mine\n").unwrap();
+
+        fs::write(tmp.path().join(".templates_version"), "0.0.0").unwrap();
+        let report = setup_templates_in(&env).unwrap();
+
+        assert_eq!(
+            report.preserved_files,
+            vec!["aws-simple/extra.tf".to_string(), "aws-simple/variables.tf".to_string()]
+        );
+        let backup = PathBuf::from(report.backup_dir.unwrap());
+        assert!(backup
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("templates.backup-"));
+        assert!(fs::read_to_string(backup.join("aws-simple/variables.tf"))
+            .unwrap()
+            .contains("# local edit"));
+        assert!(backup.join("aws-simple/extra.tf").exists());
+
+        // The fresh extraction replaces the edits.
+        assert_eq!(fs::read_to_string(&edited).unwrap(), original);
+        assert!(!templates_dir.join("aws-simple").join("extra.tf").exists());
+    }
+
+    #[test]
+    fn embedded_manifest_covers_bundled_templates() {
+        let actual = hash_templates(&real_templates_dir()).unwrap();
+        assert!(actual.contains_key("aws-simple/variables.tf"));
+        assert!(compare_to_manifest(&actual, &EMBEDDED_MANIFEST).intact);
+    }
+
+    #[test]
+    fn verify_templates_reports_and_repairs_tampering() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+        assert!(verify_templates_in(&env, false).unwrap().intact);
+
+        let templates_dir = env.templates_dir().unwrap();
+        fs::write(templates_dir.join("aws-simple").join("variables.tf"), "corrupt").unwrap();
+        fs::remove_file(templates_dir.join("gcp-simple").join("variables.tf")).unwrap();
+        fs::write(templates_dir.join("azure-simple").join("extra.tf"), "# injected").unwrap();
+
+        let report = verify_templates_in(&env, false).unwrap();
+        assert!(!report.intact);
+        assert_eq!(report.modified, vec!["aws-simple/variables.tf".to_string()]);
+        assert_eq!(report.missing, vec!["gcp-simple/variables.tf".to_string()]);
+        assert_eq!(report.unexpected, vec!["azure-simple/extra.tf".to_string()]);
+        assert!(report.repaired.is_none());
+
+        let repaired = verify_templates_in(&env, true).unwrap().repaired.unwrap();
+        assert!(repaired.refreshed);
+        assert_eq!(repaired.preserved_files.len(), 2);
+        assert!(verify_templates_in(&env, false).unwrap().intact);
+    }
+
+    #[test]
+    fn setup_repairs_partial_extraction() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+
+        // Simulate a run killed mid-copy: a file is missing and a staging dir remains.
+        let templates_dir = env.templates_dir().unwrap();
+        let missing = templates_dir.join("gcp-simple").join("variables.tf");
+        fs::remove_file(&missing).unwrap();
+        let staging = tmp.path().join(TEMPLATES_STAGING_DIR);
+        fs::create_dir_all(staging.join("aws-simple")).unwrap();
+        fs::write(staging.join("aws-simple").join("main.tf"), "# partial").unwrap();
+
+        let report = setup_templates_in(&env).unwrap();
+        assert!(report.refreshed);
+        assert!(report.preserved_files.is_empty());
+        assert!(missing.exists());
+        assert!(!staging.exists());
+        assert!(templates_complete(tmp.path(), &templates_dir));
+    }
+
+    #[test]
+    fn setup_reextracts_when_version_file_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+
+        fs::remove_file(tmp.path().join(".templates_version")).unwrap();
+        assert!(setup_templates_in(&env).unwrap().refreshed);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join(".templates_version")).unwrap(),
+            TEMPLATES_VERSION
+        );
+        assert!(!setup_templates_in(&env).unwrap().refreshed);
+    }
+
+    #[test]
+    fn refresh_without_manifest_skips_change_detection() {
+        let tmp = tempfile::tempdir().unwrap();
+        let env = crate::storage::StoragePaths::from_data_dir(tmp.path());
+        setup_templates_in(&env).unwrap();
+
+        fs::remove_file(tmp.path().join(TEMPLATES_MANIFEST_FILE)).unwrap();
+        fs::write(tmp.path().join(".templates_version"), "0.0.0").unwrap();
+        let report = setup_templates_in(&env).unwrap();
+        assert!(report.refreshed);
+        assert!(report.preserved_files.is_empty());
+        assert!(tmp.path().join(TEMPLATES_MANIFEST_FILE).exists());
+    }
+
     // ── Template copy + generate tfvars integration ─────────────────────
 
     #[test]
@@ -468,7 +1058,7 @@ mod tests {
         copy_dir_all(&template_src, &deployment_dir).unwrap();
 
         let content = fs::read_to_string(deployment_dir.join("variables.tf")).unwrap();
-        let variables = terraform::parse_variables_tf(&content);
+        let variables = terraform::parse_variables_tf(&content).unwrap();
 
         let mut values = std::collections::HashMap::new();
         values.insert("tenant_id".to_string(), serde_json::json!("my-tenant-id"));
@@ -503,7 +1093,7 @@ mod tests {
         copy_dir_all(&template_src, &deployment_dir).unwrap();
 
         let content = fs::read_to_string(deployment_dir.join("variables.tf")).unwrap();
-        let variables = terraform::parse_variables_tf(&content);
+        let variables = terraform::parse_variables_tf(&content).unwrap();
 
         let mut values = std::collections::HashMap::new();
         values.insert("tenant_id".to_string(), serde_json::json!("tid"));
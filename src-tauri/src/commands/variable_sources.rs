@@ -0,0 +1,519 @@
+//! Dynamic dropdown options for template variables.
+//!
+//! Variables annotated with `# @source: <name>` (or matched by well-known
+//! variable names) are backed by a data source that queries the cloud with the
+//! current credentials, so users pick real regions, VPCs, subnets and resource
+//! groups instead of typing IDs. Existing Databricks account configurations
+//! come from the account API so they can be reused instead of recreated.
+
+use super::account_configs::{list_account_configurations, AccountConfiguration};
+use super::aws::{apply_aws_credentials, get_aws_vpcs};
+use super::azure::{get_azure_resource_groups, get_azure_resource_groups_sp, get_azure_vnets, get_azure_vnets_sp};
+use super::gcp::get_gcp_projects;
+use super::encryption_keys::list_encryption_keys;
+use super::CloudCredentials;
+use crate::dependencies;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A selectable value for a variable.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariableOption {
+    pub value: String,
+    pub label: String,
+}
+
+/// Data sources understood by [`get_variable_options`].
+pub const DATA_SOURCES: &[&str] = &[
+    "aws_region",
+    "aws_vpc",
+    "aws_subnet",
+    "aws_security_group",
+    "azure_location",
+    "azure_resource_group",
+    "azure_vnet",
+    "gcp_region",
+    "gcp_project",
+    "databricks_network",
+    "databricks_storage_configuration",
+    "databricks_credentials",
+    "aws_kms_key",
+    "gcp_kms_key",
+];
+
+// ─── Inference ──────────────────────────────────────────────────────────────
+
+/// Data source for well-known variable names in the bundled templates, used
+/// when a variable has no explicit `@source` annotation.
+pub fn infer_data_source(cloud: &str, variable: &str) -> Option<&'static str> {
+    match (cloud, variable) {
+        ("aws", "region") => Some("aws_region"),
+        ("aws", "existing_vpc_id" | "custom_vpc_id") => Some("aws_vpc"),
+        ("aws", "existing_subnet_ids" | "custom_private_subnet_ids") => Some("aws_subnet"),
+        ("aws", "existing_security_group_id" | "custom_sg_id") => Some("aws_security_group"),
+        ("azure", "location") => Some("azure_location"),
+        (
+            "azure",
+            "resource_group_name" | "existing_resource_group_name" | "existing_data_plane_resource_group_name"
+            | "vnet_resource_group_name",
+        ) => Some("azure_resource_group"),
+        ("azure", "vnet_name") => Some("azure_vnet"),
+        ("gcp", "google_region") => Some("gcp_region"),
+        ("gcp", "google_project_name" | "google_project") => Some("gcp_project"),
+        ("aws" | "gcp", "existing_network_id") => Some("databricks_network"),
+        ("aws", "existing_storage_configuration_id") => Some("databricks_storage_configuration"),
+        ("aws", "existing_credentials_id") => Some("databricks_credentials"),
+        ("aws", "existing_workspace_storage_key_arn" | "existing_managed_services_key_arn") => Some("aws_kms_key"),
+        ("gcp", "kms_key_id") => Some("gcp_kms_key"),
+        _ => None,
+    }
+}
+
+/// Fill in `meta.source` for variables of a template on `cloud` that weren't annotated.
+pub fn annotate_data_sources(variables: &mut [crate::terraform::TerraformVariable], cloud: &str) {
+    for var in variables.iter_mut() {
+        if var.meta.source.is_none() {
+            var.meta.source = infer_data_source(cloud, &var.name).map(String::from);
+        }
+    }
+}
+
+// ─── Parsing ────────────────────────────────────────────────────────────────
+
+fn name_tag(tags: &serde_json::Value) -> Option<&str> {
+    tags.as_array()?
+        .iter()
+        .find(|t| t["Key"].as_str() == Some("Name"))
+        .and_then(|t| t["Value"].as_str())
+}
+
+fn with_detail(id: &str, detail: &str) -> String {
+    if detail.is_empty() {
+        id.to_string()
+    } else {
+        format!("{} ({})", id, detail)
+    }
+}
+
+fn account_config_options(configs: Vec<AccountConfiguration>) -> Vec<VariableOption> {
+    configs
+        .into_iter()
+        .map(|c| VariableOption { label: with_detail(&c.name, &c.detail), value: c.id })
+        .collect()
+}
+
+fn sorted(mut options: Vec<VariableOption>) -> Vec<VariableOption> {
+    options.sort_by_key(|o| o.label.to_lowercase());
+    options
+}
+
+/// `aws ec2 describe-regions` output.
+fn parse_aws_regions(json: &serde_json::Value) -> Vec<VariableOption> {
+    let options = json["Regions"]
+        .as_array()
+        .map(|regions| {
+            regions
+                .iter()
+                .filter_map(|r| r["RegionName"].as_str())
+                .map(|name| VariableOption { value: name.to_string(), label: name.to_string() })
+                .collect()
+        })
+        .unwrap_or_default();
+    sorted(options)
+}
+
+/// `aws ec2 describe-subnets` output.
+fn parse_aws_subnets(json: &serde_json::Value) -> Vec<VariableOption> {
+    let options = json["Subnets"]
+        .as_array()
+        .map(|subnets| {
+            subnets
+                .iter()
+                .filter_map(|s| {
+                    let id = s["SubnetId"].as_str()?;
+                    let detail = [
+                        name_tag(&s["Tags"]).unwrap_or(""),
+                        s["AvailabilityZone"].as_str().unwrap_or(""),
+                        s["CidrBlock"].as_str().unwrap_or(""),
+                    ]
+                    .iter()
+                    .filter(|p| !p.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                    Some(VariableOption { value: id.to_string(), label: with_detail(id, &detail) })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    sorted(options)
+}
+
+/// `aws ec2 describe-security-groups` output.
+fn parse_aws_security_groups(json: &serde_json::Value) -> Vec<VariableOption> {
+    let options = json["SecurityGroups"]
+        .as_array()
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|g| {
+                    let id = g["GroupId"].as_str()?;
+                    let name = g["GroupName"].as_str().unwrap_or("");
+                    Some(VariableOption { value: id.to_string(), label: with_detail(id, name) })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    sorted(options)
+}
+
+/// `az account list-locations` output; logical (non-physical) regions are skipped.
+fn parse_azure_locations(json: &serde_json::Value) -> Vec<VariableOption> {
+    let options = json
+        .as_array()
+        .map(|locations| {
+            locations
+                .iter()
+                .filter(|l| l["metadata"]["regionType"].as_str().unwrap_or("Physical") == "Physical")
+                .filter_map(|l| {
+                    let name = l["name"].as_str()?;
+                    let display = l["displayName"].as_str().unwrap_or("");
+                    Some(VariableOption { value: name.to_string(), label: with_detail(name, display) })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    sorted(options)
+}
+
+/// `gcloud compute regions list --format=json` output.
+fn parse_gcp_regions(json: &serde_json::Value) -> Vec<VariableOption> {
+    let options = json
+        .as_array()
+        .map(|regions| {
+            regions
+                .iter()
+                .filter(|r| r["status"].as_str().unwrap_or("UP") == "UP")
+                .filter_map(|r| r["name"].as_str())
+                .map(|name| VariableOption { value: name.to_string(), label: name.to_string() })
+                .collect()
+        })
+        .unwrap_or_default();
+    sorted(options)
+}
+
+// ─── Queries ────────────────────────────────────────────────────────────────
+
+fn aws_region(credentials: &CloudCredentials) -> String {
+    credentials
+        .aws_region
+        .as_ref()
+        .filter(|s| !s.is_empty())
+        .cloned()
+        .unwrap_or_else(|| "us-east-1".to_string())
+}
+
+/// Run an `aws ec2` query with the deployment credentials and parse the JSON output.
+fn aws_ec2_json(credentials: &CloudCredentials, args: &[&str]) -> Result<serde_json::Value, String> {
+    let aws_cli = dependencies::find_aws_cli_path()
+        .ok_or_else(|| crate::errors::cli_not_found("AWS CLI"))?;
+    let region = aws_region(credentials);
+
+    let mut cmd = super::silent_cmd(&aws_cli);
+    cmd.arg("ec2").args(args).args(["--region", &region, "--output", "json"]);
+    apply_aws_credentials(&mut cmd, credentials)?;
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run AWS CLI: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("AWS CLI error: {}", stderr.trim()));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse AWS response: {}", e))
+}
+
+pub(super) fn cli_json(program: std::path::PathBuf, args: &[&str], tool: &str) -> Result<serde_json::Value, String> {
+    let output = super::silent_cmd(&program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} error: {}", tool, stderr.trim()));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse {} response: {}", tool, e))
+}
+
+fn context_value(context: &HashMap<String, serde_json::Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| context.get(*k))
+        .find_map(|v| v.as_str().filter(|s| !s.is_empty()).map(String::from))
+}
+
+fn uses_azure_sp(credentials: &CloudCredentials) -> bool {
+    credentials
+        .azure_client_secret
+        .as_ref()
+        .is_some_and(|s| !s.is_empty())
+}
+
+fn azure_subscription(credentials: &CloudCredentials) -> Result<String, String> {
+    credentials
+        .azure_subscription_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Azure subscription is required".to_string())
+}
+
+/// Valid options for a variable data source, queried with the current credentials.
+///
+/// `context` holds the other form values, for sources that depend on them
+/// (subnets and security groups are filtered by the selected VPC).
+#[tauri::command]
+pub async fn get_variable_options(
+    source: String,
+    credentials: CloudCredentials,
+    context: Option<HashMap<String, serde_json::Value>>,
+) -> Result<Vec<VariableOption>, String> {
+    if !DATA_SOURCES.contains(&source.as_str()) {
+        return Err(format!("Unknown variable data source: {}", source));
+    }
+    let context = context.unwrap_or_default();
+
+    match source.as_str() {
+        "aws_vpc" => {
+            let vpcs = get_aws_vpcs(credentials).await?;
+            Ok(sorted(
+                vpcs.into_iter()
+                    .map(|v| {
+                        let detail = [v.name.as_str(), v.cidr_block.as_str()]
+                            .iter()
+                            .filter(|p| !p.is_empty())
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        VariableOption { label: with_detail(&v.vpc_id, &detail), value: v.vpc_id }
+                    })
+                    .collect(),
+            ))
+        }
+        "azure_resource_group" => {
+            let groups = if uses_azure_sp(&credentials) {
+                get_azure_resource_groups_sp(credentials, None).await?
+            } else {
+                let subscription = azure_subscription(&credentials)?;
+                tokio::task::spawn_blocking(move || get_azure_resource_groups(subscription, None))
+                    .await
+                    .map_err(|e| format!("Failed to list resource groups: {}", e))??
+            };
+            Ok(groups
+                .into_iter()
+                .map(|g| VariableOption { label: with_detail(&g.name, &g.location), value: g.name })
+                .collect())
+        }
+        "azure_vnet" => {
+            let vnets = if uses_azure_sp(&credentials) {
+                get_azure_vnets_sp(credentials).await?
+            } else {
+                let subscription = azure_subscription(&credentials)?;
+                tokio::task::spawn_blocking(move || get_azure_vnets(subscription))
+                    .await
+                    .map_err(|e| format!("Failed to list VNets: {}", e))??
+            };
+            Ok(sorted(
+                vnets
+                    .into_iter()
+                    .map(|v| VariableOption {
+                        label: with_detail(&v.name, &format!("{}, {}", v.resource_group, v.location)),
+                        value: v.name,
+                    })
+                    .collect(),
+            ))
+        }
+        "gcp_project" => {
+            let projects = tokio::task::spawn_blocking(get_gcp_projects)
+                .await
+                .map_err(|e| format!("Failed to list GCP projects: {}", e))??;
+            Ok(projects
+                .into_iter()
+                .map(|p| VariableOption { label: with_detail(&p.project_id, &p.name), value: p.project_id })
+                .collect())
+        }
+        "databricks_network" | "databricks_storage_configuration" | "databricks_credentials" => {
+            let configs = list_account_configurations(credentials).await?;
+            Ok(account_config_options(match source.as_str() {
+                "databricks_network" => configs.networks,
+                "databricks_storage_configuration" => configs.storage_configurations,
+                _ => configs.credentials,
+            }))
+        }
+        "aws_kms_key" | "gcp_kms_key" => {
+            let region = context_value(&context, &["region", "google_region"]);
+            let keys = list_encryption_keys(credentials, region).await?;
+            Ok(keys
+                .into_iter()
+                .map(|k| VariableOption { label: with_detail(&k.name, &k.detail), value: k.id })
+                .collect())
+        }
+        _ => tokio::task::spawn_blocking(move || query_cli_source(&source, &credentials, &context))
+            .await
+            .map_err(|e| format!("Failed to load options: {}", e))?,
+    }
+}
+
+/// Drop regions where Databricks isn't available.
+fn supported_only(cloud: &str, options: Vec<VariableOption>) -> Vec<VariableOption> {
+    options
+        .into_iter()
+        .filter(|o| super::regions::find_supported_region(cloud, &o.value).is_some())
+        .collect()
+}
+
+/// Sources backed by a single blocking CLI call.
+fn query_cli_source(
+    source: &str,
+    credentials: &CloudCredentials,
+    context: &HashMap<String, serde_json::Value>,
+) -> Result<Vec<VariableOption>, String> {
+    match source {
+        "aws_region" => Ok(supported_only(
+            "aws",
+            parse_aws_regions(&aws_ec2_json(credentials, &["describe-regions"])?),
+        )),
+        "aws_subnet" | "aws_security_group" => {
+            let vpc_id = context_value(context, &["existing_vpc_id", "custom_vpc_id", "vpc_id"])
+                .ok_or_else(|| "Select a VPC first".to_string())?;
+            let filter = format!("Name=vpc-id,Values={}", vpc_id);
+            if source == "aws_subnet" {
+                Ok(parse_aws_subnets(&aws_ec2_json(credentials, &["describe-subnets", "--filters", &filter])?))
+            } else {
+                Ok(parse_aws_security_groups(&aws_ec2_json(
+                    credentials,
+                    &["describe-security-groups", "--filters", &filter],
+                )?))
+            }
+        }
+        "azure_location" => {
+            let az = dependencies::find_azure_cli_path()
+                .ok_or_else(|| crate::errors::cli_not_found("Azure CLI"))?;
+            let mut args = vec!["account", "list-locations", "--output", "json"];
+            let subscription = credentials.azure_subscription_id.clone().filter(|s| !s.is_empty());
+            if let Some(sub) = subscription.as_deref() {
+                args.extend(["--subscription", sub]);
+            }
+            Ok(supported_only("azure", parse_azure_locations(&cli_json(az, &args, "Azure CLI")?)))
+        }
+        "gcp_region" => {
+            let gcloud = dependencies::find_gcloud_cli_path()
+                .ok_or_else(|| crate::errors::cli_not_found("Google Cloud CLI"))?;
+            let project = credentials
+                .gcp_project_id
+                .clone()
+                .filter(|s| !s.is_empty())
+                .or_else(|| context_value(context, &["google_project_name", "google_project"]))
+                .ok_or_else(|| "GCP project is required".to_string())?;
+            let project_arg = format!("--project={}", project);
+            Ok(supported_only(
+                "gcp",
+                parse_gcp_regions(&cli_json(
+                    gcloud,
+                    &["compute", "regions", "list", "--format=json", &project_arg],
+                    "gcloud",
+                )?),
+            ))
+        }
+        other => Err(format!("Unknown variable data source: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn infer_data_source_for_bundled_names() {
+        assert_eq!(infer_data_source("aws", "region"), Some("aws_region"));
+        assert_eq!(infer_data_source("azure", "existing_resource_group_name"), Some("azure_resource_group"));
+        assert_eq!(infer_data_source("gcp", "google_region"), Some("gcp_region"));
+        assert_eq!(infer_data_source("azure", "region"), None);
+        assert_eq!(infer_data_source("aws", "prefix"), None);
+        assert_eq!(infer_data_source("gcp", "existing_network_id"), Some("databricks_network"));
+        assert_eq!(infer_data_source("gcp", "existing_credentials_id"), None);
+    }
+
+    #[test]
+    fn inferred_sources_are_known() {
+        for (cloud, name) in [
+            ("aws", "custom_sg_id"),
+            ("azure", "vnet_name"),
+            ("gcp", "google_project"),
+            ("aws", "existing_credentials_id"),
+            ("gcp", "existing_network_id"),
+        ] {
+            assert!(DATA_SOURCES.contains(&infer_data_source(cloud, name).unwrap()));
+        }
+    }
+
+    #[test]
+    fn parse_aws_regions_sorted() {
+        let json = json!({"Regions": [{"RegionName": "us-west-2"}, {"RegionName": "eu-central-1"}]});
+        let values: Vec<String> = parse_aws_regions(&json).into_iter().map(|o| o.value).collect();
+        assert_eq!(values, vec!["eu-central-1", "us-west-2"]);
+    }
+
+    #[test]
+    fn parse_aws_subnets_labels() {
+        let json = json!({"Subnets": [{
+            "SubnetId": "subnet-1",
+            "AvailabilityZone": "us-east-1a",
+            "CidrBlock": "10.0.1.0/24",
+            "Tags": [{"Key": "Name", "Value": "private-a"}]
+        }]});
+        assert_eq!(
+            parse_aws_subnets(&json),
+            vec![VariableOption {
+                value: "subnet-1".to_string(),
+                label: "subnet-1 (private-a, us-east-1a, 10.0.1.0/24)".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_azure_locations_skips_logical() {
+        let json = json!([
+            {"name": "westeurope", "displayName": "West Europe", "metadata": {"regionType": "Physical"}},
+            {"name": "europe", "displayName": "Europe", "metadata": {"regionType": "Logical"}}
+        ]);
+        let options = parse_azure_locations(&json);
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].label, "westeurope (West Europe)");
+    }
+
+    #[test]
+    fn parse_gcp_regions_skips_down() {
+        let json = json!([{"name": "us-central1", "status": "UP"}, {"name": "old-region", "status": "DOWN"}]);
+        let values: Vec<String> = parse_gcp_regions(&json).into_iter().map(|o| o.value).collect();
+        assert_eq!(values, vec!["us-central1"]);
+    }
+
+    #[test]
+    fn query_requires_vpc_for_subnets() {
+        let err = query_cli_source("aws_subnet", &CloudCredentials::default(), &HashMap::new()).unwrap_err();
+        assert!(err.contains("VPC"));
+        assert!(query_cli_source("bogus", &CloudCredentials::default(), &HashMap::new())
+            .unwrap_err()
+            .contains("Unknown"));
+    }
+
+    #[test]
+    fn supported_only_filters_catalog() {
+        let options = vec![
+            VariableOption { value: "us-east-1".to_string(), label: "us-east-1".to_string() },
+            VariableOption { value: "me-south-1".to_string(), label: "me-south-1".to_string() },
+        ];
+        let kept: Vec<String> = supported_only("aws", options).into_iter().map(|o| o.value).collect();
+        assert_eq!(kept, vec!["us-east-1"]);
+    }
+}
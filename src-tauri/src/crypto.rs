@@ -6,6 +6,8 @@ use aes_gcm::{
 };
 use base64::Engine;
 use rand::RngCore;
+use std::fs;
+use std::path::Path;
 
 const ENC_PREFIX: &str = "enc:v1:";
 
@@ -58,6 +60,47 @@ pub fn decrypt(encrypted: &str, enc_key: &[u8; 32]) -> Result<String, String> {
     String::from_utf8(plaintext).map_err(|e| format!("Invalid UTF-8 in decrypted value: {}", e))
 }
 
+/// Read a 256-bit key from `path`, generating and saving a random one if the
+/// file doesn't exist. On Unix the key file is readable only by the owner.
+pub fn load_or_create_key(path: &Path) -> Result<[u8; 32], String> {
+    if path.exists() {
+        let key_bytes = fs::read(path).map_err(|e| e.to_string())?;
+        if key_bytes.len() != 32 {
+            return Err(format!("Corrupted encryption key file: {}", path.display()));
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&key_bytes);
+        return Ok(key);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    fs::write(path, key).map_err(|e| format!("Failed to save encryption key: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+
+    Ok(key)
+}
+
+/// Encrypt a stored value in place if it is still plaintext.
+/// Returns `true` when the value changed and should be saved.
+pub fn migrate_plaintext(value: &mut Option<String>, enc_key: &[u8; 32]) -> Result<bool, String> {
+    match value {
+        Some(v) if !v.is_empty() && !is_encrypted(v) => {
+            *v = encrypt(v, enc_key)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,4 +160,37 @@ mod tests {
         assert!(!is_encrypted(""));
         assert!(!is_encrypted("enc:v2:data"));
     }
+
+    #[test]
+    fn load_or_create_key_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("keyfile");
+        let first = load_or_create_key(&path).unwrap();
+        let second = load_or_create_key(&path).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(fs::read(&path).unwrap().len(), 32);
+    }
+
+    #[test]
+    fn load_or_create_key_rejects_corrupted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keyfile");
+        fs::write(&path, b"short").unwrap();
+        assert!(load_or_create_key(&path).is_err());
+    }
+
+    #[test]
+    fn migrate_plaintext_encrypts_once() {
+        let key = random_key();
+        let mut value = Some("ghp_plaintext".to_string());
+        assert!(migrate_plaintext(&mut value, &key).unwrap());
+        let encrypted = value.clone().unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt(&encrypted, &key).unwrap(), "ghp_plaintext");
+        assert!(!migrate_plaintext(&mut value, &key).unwrap());
+        assert_eq!(value.unwrap(), encrypted);
+
+        let mut none: Option<String> = None;
+        assert!(!migrate_plaintext(&mut none, &key).unwrap());
+    }
 }
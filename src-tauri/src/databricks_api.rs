@@ -0,0 +1,1165 @@
+//! Typed clients for the Databricks account and workspace APIs.
+//!
+//! Commands used to mint tokens and call SCIM, metastore, and workspace
+//! endpoints with inline `reqwest` code. [`AccountClient`] owns the auth
+//! strategy, retries throttled responses (and 5xx for idempotent requests),
+//! and maps failures to [`ApiError`], leaving commands to pick the
+//! user-facing wording.
+//! [`WorkspaceClient`] does the same for a deployed workspace's own API.
+
+use crate::commands::debug_log;
+use crate::commands::{databricks_accounts_host, http_client, lock_or_recover, silent_cmd};
+use crate::dependencies;
+use crate::endpoints::https_url;
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Audience of Google ID tokens accepted by the GCP account console.
+pub(crate) const GCP_ACCOUNTS_AUDIENCE: &str = "https://accounts.gcp.databricks.com";
+/// Azure AD application ID of Azure Databricks, the resource of its tokens.
+const AZURE_DATABRICKS_RESOURCE_ID: &str = "2ff814a6-3304-4ab8-85cb-cd0e6f879c1d";
+
+/// Attempts per request, including the first, for retryable failures.
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+
+// ─── Errors ─────────────────────────────────────────────────────────────────
+
+/// Failure of an account API call, classified so callers can word errors
+/// per context (e.g. a 403 means "not an account admin" for SCIM).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ApiError {
+    /// No response (DNS, TLS, timeout, connection refused).
+    Network(String),
+    /// 401 Unauthorized, with the response body.
+    Unauthorized(String),
+    /// 403 Forbidden, with the response body.
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    /// Any other non-success status, with the response body.
+    Status(StatusCode, String),
+    /// An HTML page (usually a login redirect) where JSON was expected.
+    UnexpectedHtml,
+    /// The body did not match the expected response type.
+    Parse(String),
+    /// The auth strategy could not produce a token.
+    Auth(String),
+}
+
+impl ApiError {
+    fn from_status(status: StatusCode, body: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized(body),
+            StatusCode::FORBIDDEN => ApiError::Forbidden(body),
+            StatusCode::NOT_FOUND => ApiError::NotFound(body),
+            StatusCode::CONFLICT => ApiError::Conflict(body),
+            _ => ApiError::Status(status, body),
+        }
+    }
+
+    /// HTTP status of the failed response, if there was one.
+    pub(crate) fn status(&self) -> Option<StatusCode> {
+        match self {
+            ApiError::Unauthorized(_) => Some(StatusCode::UNAUTHORIZED),
+            ApiError::Forbidden(_) => Some(StatusCode::FORBIDDEN),
+            ApiError::NotFound(_) => Some(StatusCode::NOT_FOUND),
+            ApiError::Conflict(_) => Some(StatusCode::CONFLICT),
+            ApiError::Status(status, _) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// 401 or 403: the caller is not allowed, as opposed to a transient failure.
+    pub(crate) fn is_auth_failure(&self) -> bool {
+        matches!(self, ApiError::Unauthorized(_) | ApiError::Forbidden(_))
+    }
+
+    /// Response body of a failed request (empty when there was no response).
+    pub(crate) fn body(&self) -> &str {
+        match self {
+            ApiError::Unauthorized(b)
+            | ApiError::Forbidden(b)
+            | ApiError::NotFound(b)
+            | ApiError::Conflict(b)
+            | ApiError::Status(_, b) => b,
+            _ => "",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "Failed to connect to Databricks: {}", e),
+            ApiError::UnexpectedHtml => write!(
+                f,
+                "Received unexpected HTML response from Databricks API. Please verify your Databricks Account ID and credentials."
+            ),
+            ApiError::Parse(e) => write!(f, "Failed to parse Databricks response: {}", e),
+            ApiError::Auth(e) => write!(f, "{}", e),
+            other => {
+                let status = other.status().unwrap_or_default();
+                match other.body().trim() {
+                    "" => write!(f, "Databricks API error ({})", status),
+                    body => write!(f, "Databricks API error ({}): {}", status, body),
+                }
+            }
+        }
+    }
+}
+
+impl From<ApiError> for String {
+    fn from(e: ApiError) -> Self {
+        e.to_string()
+    }
+}
+
+// ─── Auth strategies ────────────────────────────────────────────────────────
+
+/// How account API requests are authenticated.
+#[derive(Clone)]
+pub(crate) enum AccountAuth {
+    /// OAuth M2M client-credentials exchange for a service principal.
+    ServicePrincipal {
+        client_id: String,
+        client_secret: String,
+    },
+    /// Azure AD access token for the Databricks resource, used as-is.
+    AzureAd(String),
+    /// Google-issued token used as-is: an ID token for [`GCP_ACCOUNTS_AUDIENCE`]
+    /// or a user access token.
+    GcpToken(String),
+    /// Databricks CLI profile, authenticated the way its config says (see
+    /// [`ProfileAuth`]).
+    CliProfile(String),
+}
+
+/// How a Databricks CLI profile authenticates, read from its config entries.
+#[derive(Debug, Clone, PartialEq)]
+enum ProfileAuth {
+    /// `token`: personal access token, used as-is.
+    Pat(String),
+    /// `client_id` / `client_secret`: Databricks OAuth M2M.
+    ClientCredentials { client_id: String, client_secret: String },
+    /// `azure_client_id` / `azure_client_secret` / `azure_tenant_id`: Azure AD
+    /// service principal.
+    AzureClientSecret {
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+    },
+    /// `auth_type = azure-cli`: the Azure CLI login.
+    AzureCli,
+    /// OAuth (U2M) login cached by the Databricks CLI.
+    CliToken,
+}
+
+/// Pick the auth method of a profile: an explicit `auth_type` wins,
+/// otherwise the credentials present decide, as in the Databricks SDKs.
+fn profile_auth(profile: &str, config: &HashMap<String, String>) -> Result<ProfileAuth, ApiError> {
+    let get = |key: &str| config.get(key).map(|v| v.trim()).filter(|v| !v.is_empty()).map(String::from);
+    let pat = get("token").map(ProfileAuth::Pat);
+    let m2m = match (get("client_id"), get("client_secret")) {
+        (Some(client_id), Some(client_secret)) => Some(ProfileAuth::ClientCredentials { client_id, client_secret }),
+        _ => None,
+    };
+    let azure_sp = match (get("azure_tenant_id"), get("azure_client_id"), get("azure_client_secret")) {
+        (Some(tenant_id), Some(client_id), Some(client_secret)) => Some(ProfileAuth::AzureClientSecret {
+            tenant_id,
+            client_id,
+            client_secret,
+        }),
+        _ => None,
+    };
+    let missing = |what: &str| ApiError::Auth(format!("Profile '{}' is missing its {}", profile, what));
+    match get("auth_type").as_deref() {
+        Some("pat") => pat.ok_or_else(|| missing("token")),
+        Some("oauth-m2m") => m2m.ok_or_else(|| missing("client_id or client_secret")),
+        Some("azure-client-secret") => azure_sp.ok_or_else(|| missing("Azure service principal credentials")),
+        Some("azure-cli") => Ok(ProfileAuth::AzureCli),
+        Some("databricks-cli") => Ok(ProfileAuth::CliToken),
+        Some(other) => Err(ApiError::Auth(format!(
+            "Profile '{}' uses {} authentication, which isn't supported here",
+            profile, other
+        ))),
+        None => Ok(pat.or(m2m).or(azure_sp).unwrap_or(ProfileAuth::CliToken)),
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Azure AD token for the Databricks resource from `az account get-access-token`.
+fn azure_cli_token() -> Result<String, ApiError> {
+    let az = dependencies::find_azure_cli_path()
+        .ok_or_else(|| ApiError::Auth(crate::errors::cli_not_found("Azure CLI")))?;
+    let output = silent_cmd(&az)
+        .args(["account", "get-access-token", "--resource", AZURE_DATABRICKS_RESOURCE_ID])
+        .args(["--query", "accessToken", "-o", "tsv"])
+        .output()
+        .map_err(|e| ApiError::Auth(format!("Failed to run Azure CLI: {}", e)))?;
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || token.is_empty() {
+        return Err(ApiError::Auth(format!(
+            "Azure CLI has no token for Databricks: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(token)
+}
+
+/// Token of an OAuth profile from `databricks auth token --profile <name>`.
+fn cli_profile_token(profile: &str) -> Result<String, ApiError> {
+    let cli = dependencies::find_databricks_cli_path()
+        .ok_or_else(|| ApiError::Auth(crate::errors::cli_not_found("Databricks CLI")))?;
+    let output = silent_cmd(&cli)
+        .args(["auth", "token", "--profile", profile, "--output", "json"])
+        .output()
+        .map_err(|e| ApiError::Auth(format!("Failed to run Databricks CLI: {}", e)))?;
+    if !output.status.success() {
+        return Err(ApiError::Auth(format!(
+            "Profile '{}' has no valid token: {}",
+            profile,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    serde_json::from_slice::<TokenResponse>(&output.stdout)
+        .map(|t| t.access_token)
+        .map_err(|e| ApiError::Parse(format!("databricks auth token: {}", e)))
+}
+
+// ─── Response types ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Metastore {
+    #[serde(default)]
+    pub metastore_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub owner: String,
+}
+
+#[derive(Deserialize)]
+struct MetastoreList {
+    #[serde(default)]
+    metastores: Vec<Metastore>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct PrivilegeAssignment {
+    #[serde(default)]
+    pub principal: String,
+    #[serde(default)]
+    pub privileges: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PermissionsList {
+    #[serde(default)]
+    privilege_assignments: Vec<PrivilegeAssignment>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct ScimUser {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "userName", default)]
+    pub user_name: String,
+    #[serde(rename = "displayName", default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<ScimRole>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct ScimRole {
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct ScimServicePrincipal {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "applicationId", default)]
+    pub application_id: String,
+    #[serde(rename = "displayName", default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<ScimRole>,
+}
+
+/// One page of a SCIM listing.
+#[derive(Deserialize)]
+struct ScimPage<T> {
+    #[serde(rename = "totalResults", default)]
+    total_results: usize,
+    #[serde(rename = "Resources", default)]
+    resources: Vec<T>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct ScimUserList {
+    #[serde(rename = "totalResults")]
+    pub total_results: Option<u64>,
+    #[serde(rename = "Resources")]
+    pub resources: Option<Vec<ScimUser>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct ScimGroup {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "displayName", default)]
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct Workspace {
+    #[serde(default)]
+    pub workspace_name: String,
+    pub workspace_id: Option<u64>,
+    pub workspace_status: Option<String>,
+    pub deployment_name: Option<String>,
+    pub network_id: Option<String>,
+    pub storage_configuration_id: Option<String>,
+    pub credentials_id: Option<String>,
+}
+
+/// The workspaces endpoint returns a bare array; older responses wrap it.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorkspaceList {
+    Bare(Vec<Workspace>),
+    Wrapped {
+        #[serde(default)]
+        workspaces: Vec<Workspace>,
+    },
+}
+
+/// An account-level network configuration (`/networks`). AWS entries carry
+/// `vpc_id`; GCP entries carry `gcp_network_info`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct NetworkConfig {
+    #[serde(default)]
+    pub network_id: String,
+    #[serde(default)]
+    pub network_name: String,
+    pub vpc_id: Option<String>,
+    pub vpc_status: Option<String>,
+    #[serde(default)]
+    pub subnet_ids: Vec<String>,
+    pub gcp_network_info: Option<GcpNetworkInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct GcpNetworkInfo {
+    #[serde(default)]
+    pub network_project_id: String,
+    #[serde(default)]
+    pub vpc_id: String,
+    #[serde(default)]
+    pub subnet_id: String,
+    #[serde(default)]
+    pub subnet_region: String,
+}
+
+/// An account-level root storage configuration (AWS only).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct StorageConfig {
+    #[serde(default)]
+    pub storage_configuration_id: String,
+    #[serde(default)]
+    pub storage_configuration_name: String,
+    pub root_bucket_info: Option<RootBucketInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct RootBucketInfo {
+    #[serde(default)]
+    pub bucket_name: String,
+}
+
+/// An account-level cross-account credential configuration (AWS only).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct CredentialConfig {
+    #[serde(default)]
+    pub credentials_id: String,
+    #[serde(default)]
+    pub credentials_name: String,
+    pub aws_credentials: Option<AwsCredentials>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct AwsCredentials {
+    pub sts_role: Option<StsRole>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub(crate) struct StsRole {
+    #[serde(default)]
+    pub role_arn: String,
+}
+
+impl CredentialConfig {
+    pub(crate) fn role_arn(&self) -> Option<&str> {
+        self.aws_credentials.as_ref()?.sts_role.as_ref().map(|r| r.role_arn.as_str())
+    }
+}
+
+// ─── Client ─────────────────────────────────────────────────────────────────
+
+/// SCIM `attribute eq "value"` filter, with the value quoted as a JSON string.
+fn scim_eq_filter(attribute: &str, value: &str) -> String {
+    format!("{} eq {}", attribute, serde_json::Value::from(value))
+}
+
+/// Delay before retry number `attempt` (1-based): the server's `Retry-After`
+/// in seconds when given, otherwise exponential backoff; capped either way.
+fn retry_delay(attempt: u32, retry_after: Option<&str>) -> Duration {
+    let delay = retry_after
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1)));
+    delay.min(RETRY_MAX_DELAY)
+}
+
+/// Methods that can be sent again after the server may have acted on them.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE)
+}
+
+/// Throttled requests were not processed, so any method can be retried;
+/// a 5xx may come after a create went through, so only idempotent ones are.
+fn is_retryable(status: StatusCode, idempotent: bool) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+}
+
+/// Send a request, retrying throttled responses and connection failures,
+/// plus 5xx and other network errors for idempotent methods. `build` is
+/// called once per attempt since a `RequestBuilder` is consumed.
+async fn send_with_retry(build: impl Fn() -> RequestBuilder) -> Result<Response, ApiError> {
+    let mut attempt = 1;
+    loop {
+        let (http, request) = build().build_split();
+        let request = request.map_err(|e| ApiError::Network(e.to_string()))?;
+        let idempotent = is_idempotent(request.method());
+        match http.execute(request).await {
+            Ok(resp) if is_retryable(resp.status(), idempotent) && attempt < MAX_ATTEMPTS => {
+                let retry_after = resp.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok());
+                let delay = retry_delay(attempt, retry_after);
+                debug_log!("[databricks_api] {} from {}, retrying in {:?}", resp.status(), resp.url().path(), delay);
+                tokio::time::sleep(delay).await;
+            }
+            Ok(resp) => return Ok(resp),
+            // A request that failed after connecting may have been applied
+            Err(_e) if (idempotent || _e.is_connect()) && attempt < MAX_ATTEMPTS => {
+                debug_log!("[databricks_api] request failed, retrying: {}", _e);
+                tokio::time::sleep(retry_delay(attempt, None)).await;
+            }
+            Err(e) => return Err(ApiError::Network(e.to_string())),
+        }
+        attempt += 1;
+    }
+}
+
+/// Reject non-success and HTML responses.
+async fn check_response(resp: Response) -> Result<Response, ApiError> {
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(ApiError::from_status(status, resp.text().await.unwrap_or_default()));
+    }
+    let is_html = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/html"));
+    if is_html {
+        return Err(ApiError::UnexpectedHtml);
+    }
+    Ok(resp)
+}
+
+async fn parse_json<T: DeserializeOwned>(resp: Response) -> Result<T, ApiError> {
+    check_response(resp)
+        .await?
+        .json()
+        .await
+        .map_err(|e| ApiError::Parse(e.to_string()))
+}
+
+/// OAuth M2M `client_credentials` exchange at an OIDC token endpoint.
+async fn exchange_client_credentials(
+    http: &reqwest::Client,
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String, ApiError> {
+    let resp = send_with_retry(|| {
+        http.post(token_url)
+            .form(&[("grant_type", "client_credentials"), ("scope", "all-apis")])
+            .basic_auth(client_id, Some(client_secret))
+    })
+    .await?;
+    parse_json::<TokenResponse>(resp).await.map(|t| t.access_token)
+}
+
+/// Azure AD client-credentials token for the Databricks resource.
+async fn azure_client_secret_token(
+    http: &reqwest::Client,
+    tenant_id: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String, ApiError> {
+    let url = https_url("login.microsoftonline.com", &format!("/{}/oauth2/v2.0/token", tenant_id));
+    let scope = format!("{}/.default", AZURE_DATABRICKS_RESOURCE_ID);
+    let resp = send_with_retry(|| {
+        http.post(&url).form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("scope", scope.as_str()),
+        ])
+    })
+    .await?;
+    parse_json::<TokenResponse>(resp).await.map(|t| t.access_token)
+}
+
+/// Token for `auth`; service principals are exchanged at `token_url`.
+async fn mint_token(http: &reqwest::Client, token_url: &str, auth: &AccountAuth) -> Result<String, ApiError> {
+    match auth {
+        AccountAuth::ServicePrincipal {
+            client_id,
+            client_secret,
+        } => exchange_client_credentials(http, token_url, client_id, client_secret).await,
+        AccountAuth::AzureAd(token) | AccountAuth::GcpToken(token) => Ok(token.clone()),
+        AccountAuth::CliProfile(profile) => {
+            let config = crate::commands::get_databricks_profile_credentials(profile.clone())
+                .map_err(ApiError::Auth)?;
+            let token_task = match profile_auth(profile, &config)? {
+                ProfileAuth::Pat(token) => return Ok(token),
+                ProfileAuth::ClientCredentials { client_id, client_secret } => {
+                    return exchange_client_credentials(http, token_url, &client_id, &client_secret).await;
+                }
+                ProfileAuth::AzureClientSecret {
+                    tenant_id,
+                    client_id,
+                    client_secret,
+                } => return azure_client_secret_token(http, &tenant_id, &client_id, &client_secret).await,
+                ProfileAuth::AzureCli => tokio::task::spawn_blocking(azure_cli_token),
+                ProfileAuth::CliToken => {
+                    let profile = profile.clone();
+                    tokio::task::spawn_blocking(move || cli_profile_token(&profile))
+                }
+            };
+            token_task
+                .await
+                .map_err(|e| ApiError::Auth(format!("Token task panicked: {}", e)))?
+        }
+    }
+}
+
+/// Account-scoped client for one cloud's accounts host. The access token is
+/// minted on first use and reused for the client's lifetime.
+pub(crate) struct AccountClient {
+    http: reqwest::Client,
+    host: &'static str,
+    account_id: String,
+    auth: AccountAuth,
+    token: Mutex<Option<String>>,
+}
+
+impl AccountClient {
+    pub(crate) fn new(cloud: &str, account_id: &str, auth: AccountAuth) -> Result<Self, String> {
+        Ok(Self {
+            http: http_client()?,
+            host: databricks_accounts_host(cloud),
+            account_id: account_id.to_string(),
+            auth,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Bearer token for the configured auth strategy.
+    pub(crate) async fn access_token(&self) -> Result<String, ApiError> {
+        if let Some(token) = lock_or_recover(&self.token).clone() {
+            return Ok(token);
+        }
+        let token_url = https_url(self.host, &format!("/oidc/accounts/{}/v1/token", self.account_id));
+        let token = mint_token(&self.http, &token_url, &self.auth).await?;
+        *lock_or_recover(&self.token) = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Authenticated request to `/api/2.0/accounts/{account_id}{path}`.
+    async fn send<B: Serialize>(&self, method: Method, path: &str, body: Option<&B>) -> Result<Response, ApiError> {
+        self.send_with_query(method, path, &[], body).await
+    }
+
+    /// [`Self::send`] with URL-encoded query parameters.
+    async fn send_with_query<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&B>,
+    ) -> Result<Response, ApiError> {
+        let token = self.access_token().await?;
+        let url = https_url(self.host, &format!("/api/2.0/accounts/{}{}", self.account_id, path));
+        let resp = send_with_retry(|| {
+            let mut req = self.http.request(method.clone(), &url).query(query).bearer_auth(&token);
+            if let Some(body) = body {
+                if path.starts_with("/scim/") {
+                    req = req.header(CONTENT_TYPE, "application/scim+json");
+                }
+                req = req.json(body);
+            }
+            req
+        })
+        .await?;
+        check_response(resp).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        parse_json(self.send::<()>(Method::GET, path, None).await?).await
+    }
+
+    async fn get_with_query<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, ApiError> {
+        parse_json(self.send_with_query::<()>(Method::GET, path, query, None).await?).await
+    }
+
+    pub(crate) async fn list_metastores(&self) -> Result<Vec<Metastore>, ApiError> {
+        self.get::<MetastoreList>("/metastores").await.map(|l| l.metastores)
+    }
+
+    pub(crate) async fn metastore_permissions(&self, metastore_id: &str) -> Result<Vec<PrivilegeAssignment>, ApiError> {
+        self.get::<PermissionsList>(&format!("/metastores/{}/permissions", metastore_id))
+            .await
+            .map(|p| p.privilege_assignments)
+    }
+
+    /// Add `privileges` for `principal` on the metastore. Only a metastore
+    /// admin may change grants. Returns the updated assignments.
+    pub(crate) async fn grant_metastore_privileges(
+        &self,
+        metastore_id: &str,
+        principal: &str,
+        privileges: &[&str],
+    ) -> Result<Vec<PrivilegeAssignment>, ApiError> {
+        let body = serde_json::json!({ "changes": [{ "principal": principal, "add": privileges }] });
+        let path = format!("/metastores/{}/permissions", metastore_id);
+        parse_json::<PermissionsList>(self.send(Method::PATCH, &path, Some(&body)).await?)
+            .await
+            .map(|p| p.privilege_assignments)
+    }
+
+    /// First `count` account users. Only account admins may list users, so
+    /// this doubles as an admin check.
+    pub(crate) async fn list_users(&self, count: u32) -> Result<ScimUserList, ApiError> {
+        self.get(&format!("/scim/v2/Users?count={}", count)).await
+    }
+
+    /// Every item of a SCIM listing with only `attributes` returned, fetched
+    /// a page at a time.
+    async fn scim_list_all<T: DeserializeOwned>(&self, resource: &str, attributes: &str) -> Result<Vec<T>, ApiError> {
+        const PAGE_SIZE: usize = 100;
+        let mut items = Vec::new();
+        loop {
+            let page: ScimPage<T> = self
+                .get(&format!(
+                    "/scim/v2/{}?attributes={}&startIndex={}&count={}",
+                    resource,
+                    attributes,
+                    items.len() + 1,
+                    PAGE_SIZE
+                ))
+                .await?;
+            let fetched = page.resources.len();
+            items.extend(page.resources);
+            if fetched == 0 || items.len() >= page.total_results {
+                return Ok(items);
+            }
+        }
+    }
+
+    /// All account users with their directly assigned roles.
+    pub(crate) async fn list_users_with_roles(&self) -> Result<Vec<ScimUser>, ApiError> {
+        self.scim_list_all("Users", "id,userName,displayName,roles").await
+    }
+
+    /// All account service principals with their directly assigned roles.
+    pub(crate) async fn list_service_principals(&self) -> Result<Vec<ScimServicePrincipal>, ApiError> {
+        self.scim_list_all("ServicePrincipals", "id,applicationId,displayName,roles").await
+    }
+
+    /// First item of a SCIM `resource` whose `attribute` equals `value`.
+    async fn scim_find<T: DeserializeOwned>(&self, resource: &str, attribute: &str, value: &str) -> Result<Option<T>, ApiError> {
+        let filter = scim_eq_filter(attribute, value);
+        let page: ScimPage<T> = self
+            .get_with_query(&format!("/scim/v2/{}", resource), &[("filter", &filter)])
+            .await?;
+        Ok(page.resources.into_iter().next())
+    }
+
+    pub(crate) async fn find_user(&self, user_name: &str) -> Result<Option<ScimUser>, ApiError> {
+        self.scim_find("Users", "userName", user_name).await
+    }
+
+    /// Create an active account user. Fails with [`ApiError::Conflict`] if it exists.
+    pub(crate) async fn create_user(&self, user_name: &str, display_name: &str) -> Result<ScimUser, ApiError> {
+        let body = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": user_name,
+            "displayName": display_name,
+            "active": true
+        });
+        parse_json(self.send(Method::POST, "/scim/v2/Users", Some(&body)).await?).await
+    }
+
+    pub(crate) async fn grant_account_admin(&self, user_id: &str) -> Result<(), ApiError> {
+        let body = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {
+                    "op": "add",
+                    "path": "roles",
+                    "value": [{ "value": "account_admin" }]
+                }
+            ]
+        });
+        self.send(Method::PATCH, &format!("/scim/v2/Users/{}", user_id), Some(&body))
+            .await
+            .map(|_| ())
+    }
+
+    pub(crate) async fn find_group(&self, display_name: &str) -> Result<Option<ScimGroup>, ApiError> {
+        self.scim_find("Groups", "displayName", display_name).await
+    }
+
+    /// Create an account group. Fails with [`ApiError::Conflict`] if it exists.
+    pub(crate) async fn create_group(&self, display_name: &str) -> Result<ScimGroup, ApiError> {
+        let body = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "displayName": display_name
+        });
+        parse_json(self.send(Method::POST, "/scim/v2/Groups", Some(&body)).await?).await
+    }
+
+    /// Add principals (user, group, or service principal IDs) to a group.
+    /// Existing members are left as they are.
+    pub(crate) async fn add_group_members(&self, group_id: &str, member_ids: &[String]) -> Result<(), ApiError> {
+        let members: Vec<_> = member_ids.iter().map(|id| serde_json::json!({ "value": id })).collect();
+        let body = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{ "op": "add", "path": "members", "value": members }]
+        });
+        self.send(Method::PATCH, &format!("/scim/v2/Groups/{}", group_id), Some(&body))
+            .await
+            .map(|_| ())
+    }
+
+    /// Grant a principal access to a workspace (`USER` or `ADMIN`),
+    /// replacing any earlier assignment.
+    pub(crate) async fn assign_workspace_permission(
+        &self,
+        workspace_id: u64,
+        principal_id: &str,
+        permission: &str,
+    ) -> Result<(), ApiError> {
+        let body = serde_json::json!({ "permissions": [permission] });
+        let path = format!("/workspaces/{}/permissionassignments/principals/{}", workspace_id, principal_id);
+        self.send(Method::PUT, &path, Some(&body)).await.map(|_| ())
+    }
+
+    pub(crate) async fn list_workspaces(&self) -> Result<Vec<Workspace>, ApiError> {
+        Ok(match self.get::<WorkspaceList>("/workspaces").await? {
+            WorkspaceList::Bare(workspaces) | WorkspaceList::Wrapped { workspaces } => workspaces,
+        })
+    }
+
+    pub(crate) async fn list_networks(&self) -> Result<Vec<NetworkConfig>, ApiError> {
+        self.get("/networks").await
+    }
+
+    pub(crate) async fn list_storage_configurations(&self) -> Result<Vec<StorageConfig>, ApiError> {
+        self.get("/storage-configurations").await
+    }
+
+    pub(crate) async fn list_credentials(&self) -> Result<Vec<CredentialConfig>, ApiError> {
+        self.get("/credentials").await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), ApiError> {
+        self.send::<()>(Method::DELETE, path, None).await.map(|_| ())
+    }
+
+    pub(crate) async fn delete_network(&self, network_id: &str) -> Result<(), ApiError> {
+        self.delete(&format!("/networks/{}", network_id)).await
+    }
+
+    pub(crate) async fn delete_storage_configuration(&self, storage_configuration_id: &str) -> Result<(), ApiError> {
+        self.delete(&format!("/storage-configurations/{}", storage_configuration_id)).await
+    }
+
+    pub(crate) async fn delete_credentials(&self, credentials_id: &str) -> Result<(), ApiError> {
+        self.delete(&format!("/credentials/{}", credentials_id)).await
+    }
+}
+
+// ─── Workspace client ───────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct CreatedPolicy {
+    policy_id: String,
+}
+
+#[derive(Deserialize)]
+struct CreatedWarehouse {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ClusterPolicy {
+    policy_id: String,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ClusterPolicyList {
+    #[serde(default)]
+    policies: Vec<ClusterPolicy>,
+}
+
+#[derive(Deserialize)]
+struct SqlWarehouse {
+    id: String,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SqlWarehouseList {
+    #[serde(default)]
+    warehouses: Vec<SqlWarehouse>,
+}
+
+/// Workspace-scoped client for a deployed workspace's REST API. Uses the same
+/// auth strategies as [`AccountClient`]; service principals are exchanged at
+/// the workspace OIDC endpoint.
+pub(crate) struct WorkspaceClient {
+    http: reqwest::Client,
+    host: String,
+    auth: AccountAuth,
+    token: Mutex<Option<String>>,
+}
+
+impl WorkspaceClient {
+    /// `workspace_url` may be a bare host or a URL (`https://host/`).
+    pub(crate) fn new(workspace_url: &str, auth: AccountAuth) -> Result<Self, String> {
+        let host = workspace_host(workspace_url).ok_or_else(|| format!("Invalid workspace URL: {}", workspace_url))?;
+        Ok(Self {
+            http: http_client()?,
+            host,
+            auth,
+            token: Mutex::new(None),
+        })
+    }
+
+    pub(crate) async fn access_token(&self) -> Result<String, ApiError> {
+        if let Some(token) = lock_or_recover(&self.token).clone() {
+            return Ok(token);
+        }
+        let token = mint_token(&self.http, &https_url(&self.host, "/oidc/v1/token"), &self.auth).await?;
+        *lock_or_recover(&self.token) = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        let token = self.access_token().await?;
+        let url = https_url(&self.host, path);
+        parse_json(send_with_retry(|| self.http.get(&url).bearer_auth(&token)).await?).await
+    }
+
+    async fn post<T: DeserializeOwned, B: Serialize>(&self, path: &str, body: &B) -> Result<T, ApiError> {
+        let token = self.access_token().await?;
+        let url = https_url(&self.host, path);
+        let resp = send_with_retry(|| {
+            let req = self.http.post(&url).bearer_auth(&token);
+            if path.contains("/scim/") {
+                req.header(CONTENT_TYPE, "application/scim+json").json(body)
+            } else {
+                req.json(body)
+            }
+        })
+        .await?;
+        parse_json(resp).await
+    }
+
+    /// ID of the cluster policy named `name`, if there is one.
+    pub(crate) async fn find_cluster_policy(&self, name: &str) -> Result<Option<String>, ApiError> {
+        let list: ClusterPolicyList = self.get("/api/2.0/policies/clusters/list").await?;
+        Ok(list.policies.into_iter().find(|p| p.name == name).map(|p| p.policy_id))
+    }
+
+    /// ID of the SQL warehouse named `name`, if there is one.
+    pub(crate) async fn find_sql_warehouse(&self, name: &str) -> Result<Option<String>, ApiError> {
+        let list: SqlWarehouseList = self.get("/api/2.0/sql/warehouses").await?;
+        Ok(list.warehouses.into_iter().find(|w| w.name == name).map(|w| w.id))
+    }
+
+    /// Create a cluster policy from a policy definition. Returns the policy ID.
+    pub(crate) async fn create_cluster_policy(&self, name: &str, definition: &serde_json::Value) -> Result<String, ApiError> {
+        let body = serde_json::json!({
+            "name": name,
+            "definition": definition.to_string()
+        });
+        self.post::<CreatedPolicy, _>("/api/2.0/policies/clusters/create", &body)
+            .await
+            .map(|p| p.policy_id)
+    }
+
+    /// Create a single-cluster SQL warehouse that stops when idle. Returns the warehouse ID.
+    pub(crate) async fn create_sql_warehouse(&self, name: &str, cluster_size: &str, auto_stop_mins: u32) -> Result<String, ApiError> {
+        let body = serde_json::json!({
+            "name": name,
+            "cluster_size": cluster_size,
+            "min_num_clusters": 1,
+            "max_num_clusters": 1,
+            "auto_stop_mins": auto_stop_mins
+        });
+        self.post::<CreatedWarehouse, _>("/api/2.0/sql/warehouses", &body)
+            .await
+            .map(|w| w.id)
+    }
+
+    /// Add a user to the workspace. Fails with [`ApiError::Conflict`] if they're already a member.
+    pub(crate) async fn create_user(&self, user_name: &str) -> Result<ScimUser, ApiError> {
+        let body = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": user_name
+        });
+        self.post("/api/2.0/preview/scim/v2/Users", &body).await
+    }
+}
+
+/// Host of a workspace URL (`https://adb-1.azuredatabricks.net/` → `adb-1.azuredatabricks.net`).
+pub(crate) fn workspace_host(workspace_url: &str) -> Option<String> {
+    let trimmed = workspace_url.trim();
+    let without_scheme = trimmed
+        .strip_prefix("https://")
+        .or_else(|| trimmed.strip_prefix("http://"))
+        .unwrap_or(trimmed);
+    let host = without_scheme.split('/').next().unwrap_or("");
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == ':');
+    valid.then(|| host.to_ascii_lowercase())
+}
+
+// ─── Google ID tokens ───────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct GeneratedIdToken {
+    token: String,
+}
+
+/// Google ID token for the GCP account console, issued for `sa_email` via
+/// the IAM Credentials API. `oauth_token` must be allowed to act as the SA
+/// (`roles/iam.serviceAccountTokenCreator`).
+pub(crate) async fn gcp_id_token_via_iam(oauth_token: &str, sa_email: &str) -> Result<String, ApiError> {
+    let http = http_client().map_err(ApiError::Auth)?;
+    let url = https_url(
+        "iamcredentials.googleapis.com",
+        &format!("/v1/projects/-/serviceAccounts/{}:generateIdToken", sa_email),
+    );
+    let body = serde_json::json!({
+        "audience": GCP_ACCOUNTS_AUDIENCE,
+        "includeEmail": true
+    });
+    let resp = send_with_retry(|| http.post(&url).bearer_auth(oauth_token).json(&body)).await?;
+    parse_json::<GeneratedIdToken>(resp).await.map(|t| t.token)
+}
+
+/// Google ID token for the GCP account console, signed with a service
+/// account JSON key (JWT bearer grant, no CLI needed).
+pub(crate) async fn gcp_id_token_from_key(sa_json: &str) -> Result<String, ApiError> {
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+    #[derive(Serialize)]
+    struct IdTokenClaims<'a> {
+        iss: &'a str,
+        sub: &'a str,
+        aud: &'a str,
+        target_audience: &'a str,
+        iat: u64,
+        exp: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct IdTokenResponse {
+        id_token: String,
+    }
+
+    let sa: serde_json::Value = serde_json::from_str(sa_json)
+        .map_err(|e| ApiError::Auth(format!("Invalid service account JSON: {}", e)))?;
+    let (Some(email), Some(key)) = (sa["client_email"].as_str(), sa["private_key"].as_str()) else {
+        return Err(ApiError::Auth("Service account JSON is missing client_email or private_key".to_string()));
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let claims = IdTokenClaims {
+        iss: email,
+        sub: email,
+        aud: "https://oauth2.googleapis.com/token",
+        target_audience: GCP_ACCOUNTS_AUDIENCE,
+        iat: now,
+        exp: now + 3600,
+    };
+    let encoding_key = EncodingKey::from_rsa_pem(key.as_bytes())
+        .map_err(|e| ApiError::Auth(format!("Invalid private key: {}", e)))?;
+    let assertion = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)
+        .map_err(|e| ApiError::Auth(format!("Failed to sign JWT: {}", e)))?;
+
+    let http = http_client().map_err(ApiError::Auth)?;
+    let url = https_url("oauth2.googleapis.com", "/token");
+    let resp = send_with_retry(|| {
+        http.post(&url).form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+    })
+    .await?;
+    parse_json::<IdTokenResponse>(resp).await.map(|t| t.id_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_error_classifies_status() {
+        assert_eq!(
+            ApiError::from_status(StatusCode::FORBIDDEN, "no".to_string()),
+            ApiError::Forbidden("no".to_string())
+        );
+        let err = ApiError::from_status(StatusCode::BAD_GATEWAY, String::new());
+        assert_eq!(err.status(), Some(StatusCode::BAD_GATEWAY));
+        assert!(!err.is_auth_failure());
+        assert!(ApiError::Unauthorized(String::new()).is_auth_failure());
+        assert_eq!(ApiError::Network("x".to_string()).status(), None);
+    }
+
+    #[test]
+    fn api_error_messages() {
+        assert_eq!(
+            ApiError::Status(StatusCode::BAD_REQUEST, "bad filter".to_string()).to_string(),
+            "Databricks API error (400 Bad Request): bad filter"
+        );
+        assert_eq!(
+            ApiError::NotFound(String::new()).to_string(),
+            "Databricks API error (404 Not Found)"
+        );
+        assert!(String::from(ApiError::UnexpectedHtml).contains("HTML"));
+    }
+
+    #[test]
+    fn workspace_host_accepts_urls_and_bare_hosts() {
+        assert_eq!(
+            workspace_host("https://adb-123.4.azuredatabricks.net/").as_deref(),
+            Some("adb-123.4.azuredatabricks.net")
+        );
+        assert_eq!(workspace_host("dbc-1a2b.cloud.databricks.com").as_deref(), Some("dbc-1a2b.cloud.databricks.com"));
+        assert_eq!(workspace_host(""), None);
+        assert_eq!(workspace_host("https://evil.com@host"), None);
+    }
+
+    #[test]
+    fn retry_delay_backs_off_and_honors_retry_after() {
+        assert_eq!(retry_delay(1, None), Duration::from_millis(500));
+        assert_eq!(retry_delay(2, None), Duration::from_secs(1));
+        assert_eq!(retry_delay(1, Some("3")), Duration::from_secs(3));
+        assert_eq!(retry_delay(1, Some("3600")), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(2, Some("soon")), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS, true));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE, true));
+        assert!(!is_retryable(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(!is_retryable(StatusCode::FORBIDDEN, true));
+        assert!(!is_retryable(StatusCode::CONFLICT, true));
+        assert!(is_idempotent(&Method::GET) && !is_idempotent(&Method::POST) && !is_idempotent(&Method::PATCH));
+    }
+
+    #[test]
+    fn scim_filter_quotes_value() {
+        assert_eq!(scim_eq_filter("userName", "a@b.com"), r#"userName eq "a@b.com""#);
+        assert_eq!(scim_eq_filter("displayName", r#"a"b"#), r#"displayName eq "a\"b""#);
+    }
+
+    #[test]
+    fn profile_auth_follows_config() {
+        let config = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(profile_auth("p", &config(&[("host", "h")])), Ok(ProfileAuth::CliToken));
+        assert_eq!(profile_auth("p", &config(&[("token", "dapi1")])), Ok(ProfileAuth::Pat("dapi1".to_string())));
+        assert_eq!(
+            profile_auth("p", &config(&[("client_id", "id"), ("client_secret", "s")])),
+            Ok(ProfileAuth::ClientCredentials { client_id: "id".to_string(), client_secret: "s".to_string() })
+        );
+        assert!(matches!(
+            profile_auth("p", &config(&[("azure_tenant_id", "t"), ("azure_client_id", "id"), ("azure_client_secret", "s")])),
+            Ok(ProfileAuth::AzureClientSecret { .. })
+        ));
+        assert_eq!(profile_auth("p", &config(&[("auth_type", "azure-cli")])), Ok(ProfileAuth::AzureCli));
+        assert!(profile_auth("p", &config(&[("auth_type", "pat")])).is_err());
+        assert!(profile_auth("p", &config(&[("auth_type", "github-oidc")])).is_err());
+    }
+
+    #[test]
+    fn workspace_list_accepts_bare_and_wrapped() {
+        let bare: WorkspaceList = serde_json::from_value(serde_json::json!([
+            {"workspace_name": "prod", "workspace_id": 1}
+        ]))
+        .unwrap();
+        let wrapped: WorkspaceList = serde_json::from_value(serde_json::json!({
+            "workspaces": [{"workspace_name": "dev"}]
+        }))
+        .unwrap();
+        assert!(matches!(bare, WorkspaceList::Bare(ref w) if w[0].workspace_name == "prod"));
+        assert!(matches!(wrapped, WorkspaceList::Wrapped { ref workspaces } if workspaces[0].workspace_name == "dev"));
+    }
+
+    #[test]
+    fn metastore_fields_default_when_missing() {
+        let list: MetastoreList = serde_json::from_value(serde_json::json!({
+            "metastores": [{"metastore_id": "ms-1", "region": "us-east-1"}]
+        }))
+        .unwrap();
+        assert_eq!(list.metastores[0].metastore_id, "ms-1");
+        assert_eq!(list.metastores[0].owner, "");
+        let empty: MetastoreList = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(empty.metastores.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::Mutex;
 use which::which;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,6 +12,101 @@ pub struct DependencyStatus {
     pub version: Option<String>,
     pub required: bool,
     pub install_url: String,
+    /// Installed but older than `min_version`; the UI should prompt an upgrade.
+    #[serde(default)]
+    pub outdated: bool,
+    /// Minimum version the templates need, if any.
+    #[serde(default)]
+    pub min_version: Option<String>,
+}
+
+// ─── Version Requirements ───────────────────────────────────────────────────
+
+/// Oldest Terraform release the deployer's templates are tested with.
+pub const MIN_TERRAFORM_VERSION: &str = "1.5.0";
+/// `databricks auth login` with unified auth needs the Go CLI 0.218+.
+pub const MIN_DATABRICKS_CLI_VERSION: &str = "0.218.0";
+/// AWS SSO (`aws sso login`) is only available in CLI v2.
+pub const MIN_AWS_CLI_VERSION: &str = "2.0.0";
+
+/// Extract the first `major.minor[.patch]` version number from CLI output,
+/// e.g. `aws-cli/2.15.30 Python/3.11.8` → (2, 15, 30) or `azure-cli 2.61.0 *` → (2, 61, 0).
+pub fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+    text.split(|c: char| c.is_whitespace() || c == '/' || c == ',' || c == '(' || c == ')')
+        .map(|token| token.trim_start_matches('v'))
+        .find_map(|token| {
+            let mut parts = token.split('.');
+            let major = parts.next()?.parse().ok()?;
+            // Require at least major.minor so build numbers aren't mistaken for versions
+            let minor = parts.next()?.parse().ok()?;
+            let patch = parts
+                .next()
+                .map(|p| p.chars().take_while(|c| c.is_ascii_digit()).collect::<String>())
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(0);
+            Some((major, minor, patch))
+        })
+}
+
+/// Whether a reported version is older than `minimum`. Unparseable versions
+/// are not flagged, to avoid false upgrade prompts.
+pub fn is_outdated(version: &str, minimum: &str) -> bool {
+    match (parse_version(version), parse_version(minimum)) {
+        (Some(found), Some(min)) => found < min,
+        _ => false,
+    }
+}
+
+fn apply_min_version(status: &mut DependencyStatus, minimum: &str) {
+    status.min_version = Some(minimum.to_string());
+    if let Some(version) = &status.version {
+        status.outdated = status.installed && is_outdated(version, minimum);
+    }
+}
+
+/// Which Terraform releases the user accepts, on top of the templates' own
+/// minimum.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum TerraformVersionPolicy {
+    /// Anything at or above [`MIN_TERRAFORM_VERSION`].
+    #[default]
+    Recommended,
+    /// A stricter minimum (never looser than the templates').
+    Minimum { version: String },
+    /// Exactly this release, e.g. to match a CI pipeline.
+    Exact { version: String },
+}
+
+impl TerraformVersionPolicy {
+    /// Reject versions that aren't plain `X.Y.Z` releases.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Recommended => Ok(()),
+            Self::Minimum { version } | Self::Exact { version } => {
+                let valid = version.split('.').count() == 3 && version.split('.').all(|p| p.parse::<u64>().is_ok());
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid Terraform version '{}': expected X.Y.Z", version))
+                }
+            }
+        }
+    }
+}
+
+fn apply_terraform_policy(status: &mut DependencyStatus, policy: &TerraformVersionPolicy) {
+    match policy {
+        TerraformVersionPolicy::Recommended => apply_min_version(status, MIN_TERRAFORM_VERSION),
+        TerraformVersionPolicy::Minimum { version } => {
+            let minimum = if is_outdated(version, MIN_TERRAFORM_VERSION) { MIN_TERRAFORM_VERSION } else { version };
+            apply_min_version(status, minimum);
+        }
+        TerraformVersionPolicy::Exact { version } => {
+            status.min_version = Some(version.clone());
+            status.outdated = status.installed && status.version.as_deref().is_some_and(|v| v != version);
+        }
+    }
 }
 
 /// Configuration for finding a CLI binary
@@ -30,68 +126,228 @@ struct CliPathConfig {
     env_var_paths: &'static [(&'static str, &'static str)],
 }
 
-/// Generic function to find a CLI binary path
-fn find_cli_path(config: &CliPathConfig) -> Option<PathBuf> {
+/// Home-relative directories where version managers (asdf, mise, scoop) place shims.
+const VERSION_MANAGER_DIRS: &[&str] = &[".asdf/shims", ".local/share/mise/shims", "scoop/shims"];
+
+lazy_static::lazy_static! {
+    /// User-configured directories searched before any built-in location.
+    static ref EXTRA_SEARCH_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    /// Terraform version policy from the app settings.
+    static ref TERRAFORM_POLICY: Mutex<TerraformVersionPolicy> = Mutex::new(TerraformVersionPolicy::default());
+    /// PATH as seen by the user's login shell (macOS only; GUI apps get a minimal PATH).
+    static ref LOGIN_SHELL_PATH: Vec<PathBuf> = login_shell_path();
+}
+
+/// Replace the user-configured extra search directories.
+pub fn set_extra_search_paths(paths: Vec<PathBuf>) {
+    if let Ok(mut extra) = EXTRA_SEARCH_PATHS.lock() {
+        *extra = paths;
+    }
+}
+
+/// Replace the Terraform version policy used by [`check_terraform`].
+pub fn set_terraform_version_policy(policy: TerraformVersionPolicy) {
+    if let Ok(mut current) = TERRAFORM_POLICY.lock() {
+        *current = policy;
+    }
+}
+
+fn extra_search_paths() -> Vec<PathBuf> {
+    EXTRA_SEARCH_PATHS.lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+/// Extract the PATH printed between markers, ignoring anything shell rc files echo.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_marked_path(output: &str) -> Vec<PathBuf> {
+    let Some(start) = output.find("__PATH_START__") else {
+        return Vec::new();
+    };
+    let rest = &output[start + "__PATH_START__".len()..];
+    let Some(end) = rest.find("__PATH_END__") else {
+        return Vec::new();
+    };
+    rest[..end]
+        .split(':')
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn login_shell_path() -> Vec<PathBuf> {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let output = crate::commands::silent_cmd(&shell)
+            .args(["-l", "-c", "printf '__PATH_START__%s__PATH_END__' \"$PATH\""])
+            .stdin(std::process::Stdio::null())
+            .output();
+        let _ = tx.send(output);
+    });
+    // A broken shell profile must not hang dependency detection
+    match rx.recv_timeout(std::time::Duration::from_secs(5)) {
+        Ok(Ok(output)) => parse_marked_path(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn login_shell_path() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// Every location considered for a CLI binary, in priority order, labelled
+/// with where the candidate came from. Not all candidates exist. The user's
+/// `extra_paths` come first, then the app's own Terraform/OpenTofu install.
+fn candidate_paths(config: &CliPathConfig, extra_paths: &[PathBuf]) -> Vec<(PathBuf, &'static str)> {
     #[cfg(target_os = "windows")]
     let binary_name = config.windows_binary_name.unwrap_or(config.binary_name);
     #[cfg(not(target_os = "windows"))]
     let binary_name = config.binary_name;
 
-    // Check env var paths first
+    let mut candidates = Vec::new();
+
+    for dir in extra_paths {
+        candidates.push((dir.join(binary_name), "configured search path"));
+    }
+
+    if config.binary_name == "terraform" || config.binary_name == "tofu" {
+        candidates.push((get_terraform_install_path().join(binary_name), "app install"));
+    }
+
     for (env_var, relative_path) in config.env_var_paths {
         if let Ok(base_path) = std::env::var(env_var) {
-            let p = PathBuf::from(base_path).join(relative_path);
-            if p.exists() {
-                return Some(p);
-            }
+            candidates.push((PathBuf::from(base_path).join(relative_path), "environment"));
         }
     }
 
-    // Check home-relative paths
     if let Some(home) = dirs::home_dir() {
         for relative_path in config.home_relative_paths {
-            let p = home.join(relative_path);
-            if p.exists() {
-                return Some(p);
-            }
+            candidates.push((home.join(relative_path), "home directory"));
         }
     }
 
     #[cfg(target_os = "windows")]
-    {
-        for path in config.windows_paths {
-            let p = PathBuf::from(path);
-            if p.exists() {
-                return Some(p);
-            }
-        }
+    for path in config.windows_paths {
+        candidates.push((PathBuf::from(path), "standard location"));
     }
 
     #[cfg(not(target_os = "windows"))]
-    {
-        for path in config.unix_paths {
-            let p = PathBuf::from(path);
-            if p.exists() {
-                return Some(p);
-            }
+    for path in config.unix_paths {
+        candidates.push((PathBuf::from(path), "standard location"));
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        for dir in VERSION_MANAGER_DIRS {
+            candidates.push((home.join(dir).join(binary_name), "version manager"));
         }
     }
 
-    // Fall back to which
-    if let Ok(p) = which(binary_name) {
-        return Some(p);
+    if let Ok(found) = which::which_all(binary_name) {
+        candidates.extend(found.map(|p| (p, "PATH")));
     }
 
     // On Windows, also try .cmd extension
     #[cfg(target_os = "windows")]
-    {
-        let cmd_name = format!("{}.cmd", config.binary_name);
-        if let Ok(p) = which(&cmd_name) {
-            return Some(p);
+    if let Ok(found) = which::which_all(format!("{}.cmd", config.binary_name)) {
+        candidates.extend(found.map(|p| (p, "PATH")));
+    }
+
+    for dir in LOGIN_SHELL_PATH.iter() {
+        candidates.push((dir.join(binary_name), "login shell PATH"));
+    }
+
+    candidates
+}
+
+/// Generic function to find a CLI binary path
+fn find_cli_path(config: &CliPathConfig) -> Option<PathBuf> {
+    candidate_paths(config, &extra_search_paths())
+        .into_iter()
+        .map(|(p, _)| p)
+        .find(|p| p.exists())
+}
+
+// ─── Binary Location Diagnostics ────────────────────────────────────────────
+
+/// A binary found while locating a dependency.
+#[derive(Debug, Serialize, Clone)]
+pub struct BinaryCandidate {
+    pub path: String,
+    /// Where the candidate came from ("configured search path", "PATH", ...).
+    pub source: String,
+}
+
+/// Every copy of a dependency found on disk and the one the app will use.
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyLocation {
+    pub name: String,
+    pub candidates: Vec<BinaryCandidate>,
+    pub chosen: Option<String>,
+    pub extra_search_paths: Vec<String>,
+    pub login_shell_path: Vec<String>,
+}
+
+/// Report every existing candidate for a dependency ("terraform", "tofu", "git",
+/// "aws", "azure", "gcloud" or "databricks"). The first candidate is the one
+/// `find_*_path` returns.
+pub fn locate(name: &str) -> Result<DependencyLocation, String> {
+    locate_in(name, &extra_search_paths())
+}
+
+/// [`locate`] with the user's search directories passed in.
+fn locate_in(name: &str, extra_paths: &[PathBuf]) -> Result<DependencyLocation, String> {
+    let config = match name {
+        "terraform" => &TERRAFORM_CONFIG,
+        "tofu" => &TOFU_CONFIG,
+        "git" => &GIT_CONFIG,
+        "aws" => &AWS_CLI_CONFIG,
+        "azure" => &AZURE_CLI_CONFIG,
+        "gcloud" => &GCLOUD_CLI_CONFIG,
+        "databricks" => &DATABRICKS_CLI_CONFIG,
+        other => return Err(format!("Unknown dependency: {}", other)),
+    };
+
+    let mut candidates: Vec<BinaryCandidate> = Vec::new();
+    for (path, source) in candidate_paths(config, extra_paths) {
+        let path = path.display().to_string();
+        if Path::new(&path).exists() && !candidates.iter().any(|c| c.path == path) {
+            candidates.push(BinaryCandidate { path, source: source.to_string() });
         }
     }
 
-    None
+    Ok(DependencyLocation {
+        name: name.to_string(),
+        chosen: candidates.first().map(|c| c.path.clone()),
+        candidates,
+        extra_search_paths: extra_paths.iter().map(|p| p.display().to_string()).collect(),
+        login_shell_path: LOGIN_SHELL_PATH.iter().map(|p| p.display().to_string()).collect(),
+    })
+}
+
+/// Validate user-supplied search directories: trimmed, `~/` expanded,
+/// absolute, and de-duplicated.
+pub fn normalize_search_paths(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut normalized: Vec<PathBuf> = Vec::new();
+    for raw in paths {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let path = match trimmed.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .ok_or_else(|| "Could not determine home directory".to_string())?
+                .join(rest),
+            None => PathBuf::from(trimmed),
+        };
+        if !path.is_absolute() {
+            return Err(format!("Search path must be absolute: {}", trimmed));
+        }
+        if !normalized.contains(&path) {
+            normalized.push(path);
+        }
+    }
+    Ok(normalized)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -104,32 +360,35 @@ pub struct DatabricksProfile {
     pub cloud: String, // "aws", "azure", or "gcp"
 }
 
+static DATABRICKS_CLI_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "databricks",
+    windows_binary_name: Some("databricks.exe"),
+    windows_paths: &[
+        "C:\\Program Files\\Databricks\\databricks.exe",
+        "C:\\Program Files (x86)\\Databricks\\databricks.exe",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/databricks",
+        "/opt/homebrew/bin/databricks",
+        "/usr/bin/databricks",
+        "/bin/databricks",
+        "/opt/local/bin/databricks",
+        "/snap/bin/databricks",
+    ],
+    home_relative_paths: &[
+        ".local/bin/databricks",  // pip install location on Linux/macOS
+        ".databricks/bin/databricks", // Databricks installer location
+        ".databricks-deployer/bin/databricks", // installed by this app
+    ],
+    env_var_paths: &[
+        ("LOCALAPPDATA", "Programs/databricks/databricks.exe"),
+        ("USERPROFILE", ".databricks-deployer/bin/databricks.exe"),
+    ],
+};
+
 /// Find Databricks CLI binary
 pub fn find_databricks_cli_path() -> Option<PathBuf> {
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "databricks",
-        windows_binary_name: Some("databricks.exe"),
-        windows_paths: &[
-            "C:\\Program Files\\Databricks\\databricks.exe",
-            "C:\\Program Files (x86)\\Databricks\\databricks.exe",
-        ],
-        unix_paths: &[
-            "/usr/local/bin/databricks",
-            "/opt/homebrew/bin/databricks",
-            "/usr/bin/databricks",
-            "/bin/databricks",
-            "/opt/local/bin/databricks",
-            "/snap/bin/databricks",
-        ],
-        home_relative_paths: &[
-            ".local/bin/databricks",  // pip install location on Linux/macOS
-            ".databricks/bin/databricks", // Databricks installer location
-        ],
-        env_var_paths: &[
-            ("LOCALAPPDATA", "Programs/databricks/databricks.exe"),
-        ],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&DATABRICKS_CLI_CONFIG)
 }
 
 pub fn check_databricks_cli() -> DependencyStatus {
@@ -139,6 +398,8 @@ pub fn check_databricks_cli() -> DependencyStatus {
         version: None,
         required: false,
         install_url: "https://docs.databricks.com/en/dev-tools/cli/install.html".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(cli_path) = find_databricks_cli_path() {
@@ -152,6 +413,8 @@ pub fn check_databricks_cli() -> DependencyStatus {
         }
     }
 
+    apply_min_version(&mut status, MIN_DATABRICKS_CLI_VERSION);
+
     status
 }
 
@@ -176,20 +439,10 @@ pub fn get_databricks_config_path() -> Option<PathBuf> {
     None
 }
 
-/// Parse the Databricks config file and extract profiles
-pub fn read_databricks_profiles() -> Vec<DatabricksProfile> {
-    let mut profiles = Vec::new();
-    
-    let config_path = match get_databricks_config_path() {
-        Some(p) => p,
-        None => return profiles,
-    };
-    
-    let content = match fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return profiles,
-    };
-    
+/// Split `.databrickscfg` content into `(section, key/value)` pairs, in file
+/// order. Keys are lowercased; comments and blank lines are skipped.
+pub fn parse_databricks_config(content: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut sections = Vec::new();
     let mut current_profile: Option<String> = None;
     let mut current_data: HashMap<String, String> = HashMap::new();
     
@@ -205,14 +458,11 @@ pub fn read_databricks_profiles() -> Vec<DatabricksProfile> {
         if line.starts_with('[') && line.ends_with(']') {
             // Save previous profile if exists
             if let Some(profile_name) = current_profile.take() {
-                if let Some(profile) = create_profile(&profile_name, &current_data) {
-                    profiles.push(profile);
-                }
+                sections.push((profile_name, std::mem::take(&mut current_data)));
             }
             
             // Start new profile
             current_profile = Some(line[1..line.len()-1].to_string());
-            current_data.clear();
             continue;
         }
         
@@ -226,28 +476,47 @@ pub fn read_databricks_profiles() -> Vec<DatabricksProfile> {
     
     // Don't forget the last profile
     if let Some(profile_name) = current_profile {
-        if let Some(profile) = create_profile(&profile_name, &current_data) {
-            profiles.push(profile);
-        }
+        sections.push((profile_name, current_data));
     }
     
-    profiles
+    sections
 }
 
-fn create_profile(name: &str, data: &HashMap<String, String>) -> Option<DatabricksProfile> {
-    let host = data.get("host")?.clone();
-    
-    // Determine cloud from host
-    let cloud = if host.contains("azuredatabricks") {
-        "azure".to_string()
+/// All sections of the Databricks config file, including ones with custom
+/// hosts or no host at all.
+pub fn read_databricks_config_sections() -> Vec<(String, HashMap<String, String>)> {
+    get_databricks_config_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .map(|content| parse_databricks_config(&content))
+        .unwrap_or_default()
+}
+
+/// Parse the Databricks config file and extract profiles
+pub fn read_databricks_profiles() -> Vec<DatabricksProfile> {
+    read_databricks_config_sections()
+        .iter()
+        .filter_map(|(name, data)| create_profile(name, data))
+        .collect()
+}
+
+/// Cloud of a Databricks host, or `None` for custom/unknown hosts.
+pub fn cloud_for_host(host: &str) -> Option<&'static str> {
+    if host.contains("azuredatabricks") {
+        Some("azure")
     } else if host.contains("gcp.databricks.com") {
-        "gcp".to_string()
+        Some("gcp")
     } else if host.contains("cloud.databricks.com") || host.contains("accounts.cloud.databricks") {
-        "aws".to_string()
+        Some("aws")
     } else {
-        // Custom or unknown host, skip
-        return None;
-    };
+        None
+    }
+}
+
+fn create_profile(name: &str, data: &HashMap<String, String>) -> Option<DatabricksProfile> {
+    let host = data.get("host")?.clone();
+    
+    // Determine cloud from host; custom or unknown hosts are skipped
+    let cloud = cloud_for_host(&host)?.to_string();
     
     // Check for various auth types
     let has_client_credentials = data.contains_key("client_id") && data.contains_key("client_secret");
@@ -269,11 +538,7 @@ fn create_profile(name: &str, data: &HashMap<String, String>) -> Option<Databric
 /// Get Databricks profiles filtered by cloud and account-level only
 pub fn get_databricks_profiles_for_cloud(cloud: &str) -> Vec<DatabricksProfile> {
     let all_profiles = read_databricks_profiles();
-    
-    // Account-level hosts
-    let aws_account_host = "accounts.cloud.databricks.com";
-    let azure_account_host = "accounts.azuredatabricks.net";
-    let gcp_account_host = "accounts.gcp.databricks.com";
+    let account_host = crate::commands::cloud_provider::provider(cloud).map(|p| p.databricks_host());
     
     let mut filtered: Vec<DatabricksProfile> = all_profiles
         .into_iter()
@@ -284,12 +549,7 @@ pub fn get_databricks_profiles_for_cloud(cloud: &str) -> Vec<DatabricksProfile>
             }
             
             // Must be account-level (not workspace-level)
-            let is_account_level = match cloud {
-                "aws" => p.host.contains(aws_account_host),
-                "azure" => p.host.contains(azure_account_host),
-                "gcp" => p.host.contains(gcp_account_host),
-                _ => false,
-            };
+            let is_account_level = account_host.is_some_and(|host| p.host.contains(host));
             
             if !is_account_level {
                 return false;
@@ -326,26 +586,27 @@ pub fn get_databricks_profiles_for_cloud(cloud: &str) -> Vec<DatabricksProfile>
     filtered
 }
 
+static GIT_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "git",
+    windows_binary_name: Some("git.exe"),
+    windows_paths: &[
+        "C:\\Program Files\\Git\\cmd\\git.exe",
+        "C:\\Program Files (x86)\\Git\\cmd\\git.exe",
+        "C:\\Program Files\\Git\\bin\\git.exe",
+    ],
+    unix_paths: &[
+        "/usr/bin/git",
+        "/usr/local/bin/git",
+        "/opt/homebrew/bin/git",
+        "/opt/local/bin/git",
+    ],
+    home_relative_paths: &[],
+    env_var_paths: &[],
+};
+
 /// Find git binary
 pub fn find_git_path() -> Option<PathBuf> {
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "git",
-        windows_binary_name: Some("git.exe"),
-        windows_paths: &[
-            "C:\\Program Files\\Git\\cmd\\git.exe",
-            "C:\\Program Files (x86)\\Git\\cmd\\git.exe",
-            "C:\\Program Files\\Git\\bin\\git.exe",
-        ],
-        unix_paths: &[
-            "/usr/bin/git",
-            "/usr/local/bin/git",
-            "/opt/homebrew/bin/git",
-            "/opt/local/bin/git",
-        ],
-        home_relative_paths: &[],
-        env_var_paths: &[],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&GIT_CONFIG)
 }
 
 pub fn check_git() -> DependencyStatus {
@@ -355,6 +616,8 @@ pub fn check_git() -> DependencyStatus {
         version: None,
         required: true,
         install_url: "https://git-scm.com/downloads".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(git_path) = find_git_path() {
@@ -373,44 +636,34 @@ pub fn check_git() -> DependencyStatus {
     status
 }
 
+static TERRAFORM_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "terraform",
+    windows_binary_name: Some("terraform.exe"),
+    windows_paths: &[
+        "C:\\Program Files\\Terraform\\terraform.exe",
+        "C:\\Program Files (x86)\\Terraform\\terraform.exe",
+        "C:\\HashiCorp\\Terraform\\terraform.exe",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/terraform",
+        "/opt/homebrew/bin/terraform",
+        "/usr/bin/terraform",
+        "/bin/terraform",
+        "/opt/local/bin/terraform",
+        "/snap/bin/terraform",
+    ],
+    home_relative_paths: &[
+        ".local/bin/terraform",  // manual install to ~/.local/bin
+    ],
+    env_var_paths: &[
+        ("LOCALAPPDATA", "Programs/Terraform/terraform.exe"),
+    ],
+};
+
 /// Find terraform binary by checking common installation paths
 /// macOS GUI apps don't inherit shell PATH, so we check explicit locations
 pub fn find_terraform_path() -> Option<PathBuf> {
-    // Check our own install directory first
-    #[cfg(target_os = "windows")]
-    let binary_name = "terraform.exe";
-    #[cfg(not(target_os = "windows"))]
-    let binary_name = "terraform";
-
-    let app_install_path = get_terraform_install_path().join(binary_name);
-    if app_install_path.exists() {
-        return Some(app_install_path);
-    }
-
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "terraform",
-        windows_binary_name: Some("terraform.exe"),
-        windows_paths: &[
-            "C:\\Program Files\\Terraform\\terraform.exe",
-            "C:\\Program Files (x86)\\Terraform\\terraform.exe",
-            "C:\\HashiCorp\\Terraform\\terraform.exe",
-        ],
-        unix_paths: &[
-            "/usr/local/bin/terraform",
-            "/opt/homebrew/bin/terraform",
-            "/usr/bin/terraform",
-            "/bin/terraform",
-            "/opt/local/bin/terraform",
-            "/snap/bin/terraform",
-        ],
-        home_relative_paths: &[
-            ".local/bin/terraform",  // manual install to ~/.local/bin
-        ],
-        env_var_paths: &[
-            ("LOCALAPPDATA", "Programs/Terraform/terraform.exe"),
-        ],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&TERRAFORM_CONFIG)
 }
 
 pub fn check_terraform() -> DependencyStatus {
@@ -420,6 +673,8 @@ pub fn check_terraform() -> DependencyStatus {
         version: None,
         required: true,
         install_url: "https://developer.hashicorp.com/terraform/install".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(terraform_path) = find_terraform_path() {
@@ -437,33 +692,90 @@ pub fn check_terraform() -> DependencyStatus {
         }
     }
 
+    let policy = TERRAFORM_POLICY.lock().map(|p| p.clone()).unwrap_or_default();
+    apply_terraform_policy(&mut status, &policy);
+
+    status
+}
+
+static TOFU_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "tofu",
+    windows_binary_name: Some("tofu.exe"),
+    windows_paths: &[
+        "C:\\Program Files\\OpenTofu\\tofu.exe",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/tofu",
+        "/opt/homebrew/bin/tofu",
+        "/usr/bin/tofu",
+        "/opt/local/bin/tofu",
+        "/snap/bin/tofu",
+    ],
+    home_relative_paths: &[
+        ".local/bin/tofu",
+    ],
+    env_var_paths: &[
+        ("LOCALAPPDATA", "Programs/OpenTofu/tofu.exe"),
+    ],
+};
+
+/// Find the OpenTofu binary, preferring the copy installed by this app.
+pub fn find_tofu_path() -> Option<PathBuf> {
+    find_cli_path(&TOFU_CONFIG)
+}
+
+pub fn check_tofu() -> DependencyStatus {
+    let mut status = DependencyStatus {
+        name: "OpenTofu".to_string(),
+        installed: false,
+        version: None,
+        required: false,
+        install_url: "https://opentofu.org/docs/intro/install/".to_string(),
+        outdated: false,
+        min_version: None,
+    };
+
+    if let Some(tofu_path) = find_tofu_path() {
+        if let Ok(output) = crate::commands::silent_cmd(&tofu_path).arg("version").output() {
+            if output.status.success() {
+                status.installed = true;
+                if let Ok(stdout) = String::from_utf8(output.stdout) {
+                    if let Some(version) = stdout.lines().next().and_then(|l| l.strip_prefix("OpenTofu v")) {
+                        status.version = Some(version.split_whitespace().next().unwrap_or(version).to_string());
+                    }
+                }
+            }
+        }
+    }
+
     status
 }
 
+static AWS_CLI_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "aws",
+    windows_binary_name: Some("aws.exe"),
+    windows_paths: &[
+        "C:\\Program Files\\Amazon\\AWSCLIV2\\aws.exe",
+        "C:\\Program Files (x86)\\Amazon\\AWSCLIV2\\aws.exe",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/aws",
+        "/opt/homebrew/bin/aws",
+        "/usr/bin/aws",
+        "/bin/aws",
+        "/opt/local/bin/aws",
+        "/Library/Frameworks/Python.framework/Versions/Current/bin/aws",
+        "/snap/bin/aws",
+    ],
+    home_relative_paths: &[
+        ".local/bin/aws",  // pip install --user location / app-installed v2 bundle
+    ],
+    env_var_paths: &[],
+};
+
 /// Find AWS CLI binary by checking common installation paths
 pub fn find_aws_cli_path() -> Option<PathBuf> {
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "aws",
-        windows_binary_name: Some("aws.exe"),
-        windows_paths: &[
-            "C:\\Program Files\\Amazon\\AWSCLIV2\\aws.exe",
-            "C:\\Program Files (x86)\\Amazon\\AWSCLIV2\\aws.exe",
-        ],
-        unix_paths: &[
-            "/usr/local/bin/aws",
-            "/opt/homebrew/bin/aws",
-            "/usr/bin/aws",
-            "/bin/aws",
-            "/opt/local/bin/aws",
-            "/Library/Frameworks/Python.framework/Versions/Current/bin/aws",
-            "/snap/bin/aws",
-        ],
-        home_relative_paths: &[
-            ".local/bin/aws",  // pip install --user location
-        ],
-        env_var_paths: &[],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&AWS_CLI_CONFIG)
 }
 
 pub fn check_aws_cli() -> DependencyStatus {
@@ -473,6 +785,8 @@ pub fn check_aws_cli() -> DependencyStatus {
         version: None,
         required: false,
         install_url: "https://docs.aws.amazon.com/cli/latest/userguide/getting-started-install.html".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(aws_path) = find_aws_cli_path() {
@@ -486,33 +800,36 @@ pub fn check_aws_cli() -> DependencyStatus {
         }
     }
 
+    apply_min_version(&mut status, MIN_AWS_CLI_VERSION);
+
     status
 }
 
+static AZURE_CLI_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "az",
+    windows_binary_name: Some("az.cmd"),
+    windows_paths: &[
+        "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
+        "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/az",
+        "/opt/homebrew/bin/az",
+        "/usr/bin/az",
+        "/bin/az",
+        "/opt/local/bin/az",
+        "/opt/az/bin/az",  // Linux apt install location
+    ],
+    home_relative_paths: &[
+        ".local/bin/az",  // pip install --user location
+        "bin/az",         // some Linux installs
+    ],
+    env_var_paths: &[],
+};
+
 /// Find Azure CLI binary by checking common installation paths
 pub fn find_azure_cli_path() -> Option<PathBuf> {
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "az",
-        windows_binary_name: Some("az.cmd"),
-        windows_paths: &[
-            "C:\\Program Files\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
-            "C:\\Program Files (x86)\\Microsoft SDKs\\Azure\\CLI2\\wbin\\az.cmd",
-        ],
-        unix_paths: &[
-            "/usr/local/bin/az",
-            "/opt/homebrew/bin/az",
-            "/usr/bin/az",
-            "/bin/az",
-            "/opt/local/bin/az",
-            "/opt/az/bin/az",  // Linux apt install location
-        ],
-        home_relative_paths: &[
-            ".local/bin/az",  // pip install --user location
-            "bin/az",         // some Linux installs
-        ],
-        env_var_paths: &[],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&AZURE_CLI_CONFIG)
 }
 
 pub fn check_azure_cli() -> DependencyStatus {
@@ -522,6 +839,8 @@ pub fn check_azure_cli() -> DependencyStatus {
         version: None,
         required: false,
         install_url: "https://docs.microsoft.com/en-us/cli/azure/install-azure-cli".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(az_path) = find_azure_cli_path() {
@@ -540,35 +859,36 @@ pub fn check_azure_cli() -> DependencyStatus {
     status
 }
 
+static GCLOUD_CLI_CONFIG: CliPathConfig = CliPathConfig {
+    binary_name: "gcloud",
+    windows_binary_name: Some("gcloud.cmd"),
+    windows_paths: &[
+        "C:\\Program Files\\Google\\Cloud SDK\\google-cloud-sdk\\bin\\gcloud.cmd",
+        "C:\\Program Files (x86)\\Google\\Cloud SDK\\google-cloud-sdk\\bin\\gcloud.cmd",
+    ],
+    unix_paths: &[
+        "/usr/local/bin/gcloud",
+        "/opt/homebrew/bin/gcloud",
+        "/usr/bin/gcloud",
+        "/bin/gcloud",
+        "/opt/local/bin/gcloud",
+        "/Applications/google-cloud-sdk/bin/gcloud",  // macOS .pkg / extracted install
+        "/snap/bin/gcloud",                            // Linux snap
+        "/snap/google-cloud-sdk/current/bin/gcloud",   // Linux snap (direct)
+    ],
+    home_relative_paths: &[
+        "google-cloud-sdk/bin/gcloud",                 // Default interactive install (~/google-cloud-sdk)
+        ".local/google-cloud-sdk/bin/gcloud",          // Alternative user install
+        "AppData/Local/Google/Cloud SDK/google-cloud-sdk/bin/gcloud.cmd", // Windows per-user install
+    ],
+    env_var_paths: &[
+        ("LOCALAPPDATA", "Google/Cloud SDK/google-cloud-sdk/bin/gcloud.cmd"),
+    ],
+};
+
 /// Find gcloud CLI binary by checking common installation paths
 pub fn find_gcloud_cli_path() -> Option<PathBuf> {
-    static CONFIG: CliPathConfig = CliPathConfig {
-        binary_name: "gcloud",
-        windows_binary_name: Some("gcloud.cmd"),
-        windows_paths: &[
-            "C:\\Program Files\\Google\\Cloud SDK\\google-cloud-sdk\\bin\\gcloud.cmd",
-            "C:\\Program Files (x86)\\Google\\Cloud SDK\\google-cloud-sdk\\bin\\gcloud.cmd",
-        ],
-        unix_paths: &[
-            "/usr/local/bin/gcloud",
-            "/opt/homebrew/bin/gcloud",
-            "/usr/bin/gcloud",
-            "/bin/gcloud",
-            "/opt/local/bin/gcloud",
-            "/Applications/google-cloud-sdk/bin/gcloud",  // macOS .pkg / extracted install
-            "/snap/bin/gcloud",                            // Linux snap
-            "/snap/google-cloud-sdk/current/bin/gcloud",   // Linux snap (direct)
-        ],
-        home_relative_paths: &[
-            "google-cloud-sdk/bin/gcloud",                 // Default interactive install (~/google-cloud-sdk)
-            ".local/google-cloud-sdk/bin/gcloud",          // Alternative user install
-            "AppData/Local/Google/Cloud SDK/google-cloud-sdk/bin/gcloud.cmd", // Windows per-user install
-        ],
-        env_var_paths: &[
-            ("LOCALAPPDATA", "Google/Cloud SDK/google-cloud-sdk/bin/gcloud.cmd"),
-        ],
-    };
-    find_cli_path(&CONFIG)
+    find_cli_path(&GCLOUD_CLI_CONFIG)
 }
 
 pub fn check_gcloud_cli() -> DependencyStatus {
@@ -578,6 +898,8 @@ pub fn check_gcloud_cli() -> DependencyStatus {
         version: None,
         required: false,
         install_url: "https://cloud.google.com/sdk/docs/install".to_string(),
+        outdated: false,
+        min_version: None,
     };
 
     if let Some(gcloud_path) = find_gcloud_cli_path() {
@@ -629,6 +951,227 @@ pub fn get_terraform_install_path() -> std::path::PathBuf {
     }
 }
 
+// ─── Dependency Installation ────────────────────────────────────────────────
+
+/// Databricks CLI release installed when no package manager provides it.
+pub const DATABRICKS_CLI_VERSION: &str = "0.240.0";
+
+/// OpenTofu release installed when no package manager provides it.
+pub const OPENTOFU_VERSION: &str = "1.8.5";
+
+/// System package managers the installer can drive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Winget,
+    Brew,
+    Apt,
+}
+
+/// How to install a dependency on the current machine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallMethod {
+    /// Run the package manager with these arguments.
+    PackageManager { manager: PackageManager, args: Vec<String> },
+    /// Download a zip containing a single binary into the app's bin directory,
+    /// checked against the release's published SHA256SUMS file.
+    BinaryZip { url: String, checksums_url: String },
+    /// Download the AWS CLI v2 bundle and run its installer into the user's home.
+    AwsBundle { url: String },
+    /// Download the Google Cloud SDK archive and extract it into the user's home.
+    GcloudArchive { url: String },
+}
+
+/// Find an available package manager and its path.
+/// macOS GUI apps don't inherit the shell PATH, so Homebrew's prefixes are checked explicitly.
+pub fn detect_package_manager() -> Option<(PackageManager, PathBuf)> {
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(p) = which("winget") {
+            return Some((PackageManager::Winget, p));
+        }
+        if let Ok(local) = std::env::var("LOCALAPPDATA") {
+            let p = PathBuf::from(local).join("Microsoft/WindowsApps/winget.exe");
+            if p.exists() {
+                return Some((PackageManager::Winget, p));
+            }
+        }
+        None
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        for path in ["/opt/homebrew/bin/brew", "/usr/local/bin/brew", "/home/linuxbrew/.linuxbrew/bin/brew"] {
+            let p = PathBuf::from(path);
+            if p.exists() {
+                return Some((PackageManager::Brew, p));
+            }
+        }
+        if let Ok(p) = which("brew") {
+            return Some((PackageManager::Brew, p));
+        }
+        if cfg!(target_os = "linux") {
+            if let Ok(p) = which("apt-get") {
+                return Some((PackageManager::Apt, p));
+            }
+        }
+        None
+    }
+}
+
+fn pm_install(manager: PackageManager, package: &str) -> InstallMethod {
+    let args: Vec<&str> = match manager {
+        PackageManager::Winget => vec![
+            "install", "--id", package, "-e", "--silent",
+            "--accept-package-agreements", "--accept-source-agreements",
+        ],
+        PackageManager::Brew => vec!["install", package],
+        PackageManager::Apt => vec!["install", "-y", package],
+    };
+    InstallMethod::PackageManager {
+        manager,
+        args: args.into_iter().map(String::from).collect(),
+    }
+}
+
+/// Databricks CLI release zip for an OS/arch (`std::env::consts` values).
+fn databricks_cli_zip_url(os: &str, arch: &str) -> Option<String> {
+    let os = match os {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "windows",
+        _ => return None,
+    };
+    let arch = match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+    Some(format!(
+        "https://github.com/databricks/cli/releases/download/v{v}/databricks_cli_{v}_{os}_{arch}.zip",
+        v = DATABRICKS_CLI_VERSION,
+        os = os,
+        arch = arch
+    ))
+}
+
+/// SHA256SUMS file of the Databricks CLI release.
+fn databricks_cli_checksums_url() -> String {
+    format!(
+        "https://github.com/databricks/cli/releases/download/v{v}/databricks_cli_{v}_SHA256SUMS",
+        v = DATABRICKS_CLI_VERSION
+    )
+}
+
+/// OpenTofu release zip for an OS/arch (`std::env::consts` values).
+fn tofu_zip_url(os: &str, arch: &str) -> Option<String> {
+    let os = match os {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "windows",
+        _ => return None,
+    };
+    let arch = match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+    Some(format!(
+        "https://github.com/opentofu/opentofu/releases/download/v{v}/tofu_{v}_{os}_{arch}.zip",
+        v = OPENTOFU_VERSION,
+        os = os,
+        arch = arch
+    ))
+}
+
+/// SHA256SUMS file of the OpenTofu release.
+fn tofu_checksums_url() -> String {
+    format!(
+        "https://github.com/opentofu/opentofu/releases/download/v{v}/tofu_{v}_SHA256SUMS",
+        v = OPENTOFU_VERSION
+    )
+}
+
+/// Hash listed for `file_name` in a SHA256SUMS file (`<hex>  <name>` per line).
+pub fn expected_sha256(checksums: &str, file_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == file_name).then(|| hash.to_lowercase())
+    })
+}
+
+/// Decide how to install `dependency` given the available package manager.
+/// `os`/`arch` are `std::env::consts` values. Package managers are preferred;
+/// direct downloads are used only where the vendor publishes a redistributable
+/// archive that installs without admin rights.
+pub fn install_method(
+    dependency: &str,
+    manager: Option<PackageManager>,
+    os: &str,
+    arch: &str,
+) -> Result<InstallMethod, String> {
+    use PackageManager::*;
+
+    let method = match (dependency, manager) {
+        ("git", Some(Winget)) => Some(pm_install(Winget, "Git.Git")),
+        ("git", Some(m)) => Some(pm_install(m, "git")),
+
+        ("aws", Some(Winget)) => Some(pm_install(Winget, "Amazon.AWSCLI")),
+        ("aws", Some(Brew)) => Some(pm_install(Brew, "awscli")),
+        // apt only ships the outdated v1 CLI; use the official v2 bundle instead
+        ("aws", _) if os == "linux" => Some(InstallMethod::AwsBundle {
+            url: format!(
+                "https://awscli.amazonaws.com/awscli-exe-linux-{}.zip",
+                if arch == "aarch64" { "aarch64" } else { "x86_64" }
+            ),
+        }),
+
+        ("azure", Some(Winget)) => Some(pm_install(Winget, "Microsoft.AzureCLI")),
+        ("azure", Some(Brew)) => Some(pm_install(Brew, "azure-cli")),
+
+        ("gcloud", Some(Winget)) => Some(pm_install(Winget, "Google.CloudSDK")),
+        ("gcloud", Some(Brew)) => Some(InstallMethod::PackageManager {
+            manager: Brew,
+            args: vec!["install".into(), "--cask".into(), "google-cloud-sdk".into()],
+        }),
+        ("gcloud", _) if os == "linux" || os == "macos" => {
+            let platform = match (os, arch) {
+                ("linux", "aarch64") => "linux-arm",
+                ("linux", _) => "linux-x86_64",
+                ("macos", "aarch64") => "darwin-arm",
+                _ => "darwin-x86_64",
+            };
+            Some(InstallMethod::GcloudArchive {
+                url: format!(
+                    "https://dl.google.com/dl/cloudsdk/channels/rapid/downloads/google-cloud-cli-{}.tar.gz",
+                    platform
+                ),
+            })
+        }
+
+        ("databricks", Some(Winget)) => Some(pm_install(Winget, "Databricks.DatabricksCLI")),
+        ("databricks", Some(Brew)) => Some(pm_install(Brew, "databricks/tap/databricks")),
+        ("databricks", _) => databricks_cli_zip_url(os, arch)
+            .map(|url| InstallMethod::BinaryZip { url, checksums_url: databricks_cli_checksums_url() }),
+
+        ("tofu", Some(Winget)) => Some(pm_install(Winget, "OpenTofu.Tofu")),
+        ("tofu", Some(Brew)) => Some(pm_install(Brew, "opentofu")),
+        ("tofu", _) => {
+            tofu_zip_url(os, arch).map(|url| InstallMethod::BinaryZip { url, checksums_url: tofu_checksums_url() })
+        }
+
+        ("git" | "aws" | "azure" | "gcloud", _) => None,
+        (other, _) => return Err(format!("Unknown dependency: {}", other)),
+    };
+
+    method.ok_or_else(|| {
+        format!(
+            "Automatic installation of {} isn't available on this system. Install it manually and re-check dependencies.",
+            dependency
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -794,4 +1337,236 @@ token = dapi123
         let ws = profiles.iter().find(|p| p.name == "workspace-level").unwrap();
         assert!(ws.has_token);
     }
+
+    #[test]
+    fn parse_config_keeps_sections_without_known_hosts() {
+        let sections = parse_databricks_config(
+            "; comment\n[custom]\nhost = https://dbx.internal.example\n\n[empty]\n[DEFAULT]\nHost = https://accounts.gcp.databricks.com\n",
+        );
+        let names: Vec<&str> = sections.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["custom", "empty", "DEFAULT"]);
+        assert!(sections[1].1.is_empty());
+        assert_eq!(cloud_for_host(&sections[2].1["host"]), Some("gcp"));
+        assert_eq!(cloud_for_host(&sections[0].1["host"]), None);
+    }
+
+    // ── install_method ──────────────────────────────────────────────────
+
+    #[test]
+    fn install_method_prefers_package_manager() {
+        let m = install_method("git", Some(PackageManager::Winget), "windows", "x86_64").unwrap();
+        match m {
+            InstallMethod::PackageManager { manager, args } => {
+                assert_eq!(manager, PackageManager::Winget);
+                assert!(args.contains(&"Git.Git".to_string()));
+                assert!(args.contains(&"--silent".to_string()));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            install_method("azure", Some(PackageManager::Brew), "macos", "aarch64").unwrap(),
+            InstallMethod::PackageManager {
+                manager: PackageManager::Brew,
+                args: vec!["install".into(), "azure-cli".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn install_method_aws_linux_uses_v2_bundle() {
+        let m = install_method("aws", Some(PackageManager::Apt), "linux", "aarch64").unwrap();
+        assert_eq!(
+            m,
+            InstallMethod::AwsBundle { url: "https://awscli.amazonaws.com/awscli-exe-linux-aarch64.zip".into() }
+        );
+    }
+
+    #[test]
+    fn install_method_databricks_direct_download() {
+        let m = install_method("databricks", None, "linux", "x86_64").unwrap();
+        match m {
+            InstallMethod::BinaryZip { url, checksums_url } => {
+                assert!(url.contains(DATABRICKS_CLI_VERSION));
+                assert!(url.ends_with("_linux_amd64.zip"));
+                assert!(checksums_url.ends_with(&format!("databricks_cli_{}_SHA256SUMS", DATABRICKS_CLI_VERSION)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(databricks_cli_zip_url("macos", "aarch64").unwrap().ends_with("_darwin_arm64.zip"));
+        assert!(databricks_cli_zip_url("freebsd", "x86_64").is_none());
+    }
+
+    #[test]
+    fn expected_sha256_finds_the_named_file() {
+        let sums = "AB12  tool_1.0_linux_amd64.zip\ncd34 *tool_1.0_darwin_arm64.zip\n\nbroken\n";
+        assert_eq!(expected_sha256(sums, "tool_1.0_linux_amd64.zip").as_deref(), Some("ab12"));
+        assert_eq!(expected_sha256(sums, "tool_1.0_darwin_arm64.zip").as_deref(), Some("cd34"));
+        assert_eq!(expected_sha256(sums, "tool_1.0_windows_amd64.zip"), None);
+    }
+
+    #[test]
+    fn install_method_gcloud_archive_without_brew() {
+        let m = install_method("gcloud", Some(PackageManager::Apt), "linux", "x86_64").unwrap();
+        assert!(matches!(m, InstallMethod::GcloudArchive { ref url } if url.ends_with("linux-x86_64.tar.gz")));
+    }
+
+    #[test]
+    fn install_method_unavailable_or_unknown() {
+        assert!(install_method("azure", Some(PackageManager::Apt), "linux", "x86_64")
+            .unwrap_err()
+            .contains("isn't available"));
+        assert!(install_method("git", None, "macos", "aarch64").is_err());
+        assert!(install_method("kubectl", None, "linux", "x86_64")
+            .unwrap_err()
+            .contains("Unknown dependency"));
+    }
+
+    // ── version checks ──────────────────────────────────────────────────
+
+    #[test]
+    fn parse_version_formats() {
+        assert_eq!(parse_version("1.9.8"), Some((1, 9, 8)));
+        assert_eq!(parse_version("aws-cli/2.15.30 Python/3.11.8 Darwin/23.4.0"), Some((2, 15, 30)));
+        assert_eq!(parse_version("azure-cli                         2.61.0 *"), Some((2, 61, 0)));
+        assert_eq!(parse_version("Google Cloud SDK 470.0.0"), Some((470, 0, 0)));
+        assert_eq!(parse_version("Databricks CLI v0.221.1"), Some((0, 221, 1)));
+        assert_eq!(parse_version("2.39.3 (Apple Git-146)"), Some((2, 39, 3)));
+        assert_eq!(parse_version("2.45.1.windows.1"), Some((2, 45, 1)));
+        assert_eq!(parse_version("no version here"), None);
+    }
+
+    #[test]
+    fn outdated_comparison() {
+        assert!(is_outdated("Databricks CLI v0.200.0", MIN_DATABRICKS_CLI_VERSION));
+        assert!(!is_outdated("Databricks CLI v0.218.0", MIN_DATABRICKS_CLI_VERSION));
+        assert!(is_outdated("1.4.7", MIN_TERRAFORM_VERSION));
+        assert!(!is_outdated("1.10.0", MIN_TERRAFORM_VERSION));
+        assert!(is_outdated("aws-cli/1.32.0 Python/3.9", MIN_AWS_CLI_VERSION));
+        assert!(!is_outdated("garbage", MIN_TERRAFORM_VERSION));
+    }
+
+    #[test]
+    fn apply_min_version_sets_status() {
+        let mut status = DependencyStatus {
+            name: "Terraform".to_string(),
+            installed: true,
+            version: Some("1.3.0".to_string()),
+            required: true,
+            install_url: String::new(),
+            outdated: false,
+            min_version: None,
+        };
+        apply_min_version(&mut status, MIN_TERRAFORM_VERSION);
+        assert!(status.outdated);
+        assert_eq!(status.min_version.as_deref(), Some("1.5.0"));
+
+        status.installed = false;
+        apply_min_version(&mut status, MIN_TERRAFORM_VERSION);
+        assert!(!status.outdated);
+    }
+
+    #[test]
+    fn terraform_policy_adjusts_status() {
+        let mut status = DependencyStatus {
+            name: "Terraform".to_string(),
+            installed: true,
+            version: Some("1.6.2".to_string()),
+            required: true,
+            install_url: String::new(),
+            outdated: false,
+            min_version: None,
+        };
+        apply_terraform_policy(&mut status, &TerraformVersionPolicy::Minimum { version: "1.7.0".to_string() });
+        assert!(status.outdated);
+        // A looser minimum than the templates' is ignored
+        apply_terraform_policy(&mut status, &TerraformVersionPolicy::Minimum { version: "1.0.0".to_string() });
+        assert_eq!(status.min_version.as_deref(), Some(MIN_TERRAFORM_VERSION));
+        assert!(!status.outdated);
+        apply_terraform_policy(&mut status, &TerraformVersionPolicy::Exact { version: "1.6.2".to_string() });
+        assert!(!status.outdated);
+        apply_terraform_policy(&mut status, &TerraformVersionPolicy::Exact { version: "1.6.3".to_string() });
+        assert!(status.outdated);
+
+        assert!(TerraformVersionPolicy::Exact { version: "1.6".to_string() }.validate().is_err());
+        let parsed: TerraformVersionPolicy = serde_json::from_str(r#"{"mode":"minimum","version":"1.8.0"}"#).unwrap();
+        assert_eq!(parsed, TerraformVersionPolicy::Minimum { version: "1.8.0".to_string() });
+    }
+
+    // ── search paths ────────────────────────────────────────────────────
+
+    #[test]
+    fn parse_marked_path_ignores_profile_noise() {
+        let out = "Welcome!\n__PATH_START__/opt/homebrew/bin:/usr/bin::/Users/me/.asdf/shims__PATH_END__";
+        assert_eq!(
+            parse_marked_path(out),
+            vec![
+                PathBuf::from("/opt/homebrew/bin"),
+                PathBuf::from("/usr/bin"),
+                PathBuf::from("/Users/me/.asdf/shims"),
+            ]
+        );
+        assert!(parse_marked_path("no markers").is_empty());
+    }
+
+    #[test]
+    fn normalize_search_paths_dedupes_and_rejects_relative() {
+        let paths = vec!["/opt/tools/bin".to_string(), " ".to_string(), "/opt/tools/bin ".to_string()];
+        assert_eq!(normalize_search_paths(&paths).unwrap(), vec![PathBuf::from("/opt/tools/bin")]);
+        assert!(normalize_search_paths(&["relative/bin".to_string()]).is_err());
+    }
+
+    #[test]
+    fn configured_search_paths_come_before_the_app_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let candidates = candidate_paths(&TERRAFORM_CONFIG, &[dir.path().to_path_buf()]);
+        assert_eq!(candidates[0].1, "configured search path");
+        assert!(candidates[0].0.starts_with(dir.path()));
+        assert_eq!(candidates[1], (get_terraform_install_path().join(candidates[0].0.file_name().unwrap()), "app install"));
+        assert!(candidate_paths(&GIT_CONFIG, &[]).iter().all(|(_, source)| *source != "app install"));
+    }
+
+    #[test]
+    fn locate_rejects_unknown_dependency() {
+        assert!(locate("kubectl").is_err());
+    }
+
+    #[test]
+    fn locate_finds_binary_in_extra_search_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = if cfg!(target_os = "windows") { "databricks.exe" } else { "databricks" };
+        fs::write(dir.path().join(binary), "").unwrap();
+
+        let location = locate_in("databricks", &[dir.path().to_path_buf()]).unwrap();
+
+        let expected = dir.path().join(binary).display().to_string();
+        assert_eq!(location.chosen.as_deref(), Some(expected.as_str()));
+        assert_eq!(location.candidates[0].source, "configured search path");
+    }
+
+    // ── OpenTofu ────────────────────────────────────────────────────────
+
+    #[test]
+    fn tofu_install_methods() {
+        match install_method("tofu", None, "linux", "aarch64").unwrap() {
+            InstallMethod::BinaryZip { url, checksums_url } => {
+                assert!(url.starts_with("https://github.com/opentofu/opentofu/releases/download/"));
+                assert!(checksums_url.ends_with(&format!("tofu_{}_SHA256SUMS", OPENTOFU_VERSION)));
+                assert!(url.ends_with(&format!("tofu_{}_linux_arm64.zip", OPENTOFU_VERSION)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            install_method("tofu", Some(PackageManager::Brew), "macos", "aarch64").unwrap(),
+            InstallMethod::PackageManager {
+                manager: PackageManager::Brew,
+                args: vec!["install".into(), "opentofu".into()],
+            }
+        );
+        assert!(tofu_zip_url("windows", "x86_64").unwrap().ends_with("_windows_amd64.zip"));
+    }
+
+    #[test]
+    fn locate_accepts_tofu() {
+        assert_eq!(locate("tofu").unwrap().name, "tofu");
+    }
 }
@@ -0,0 +1,59 @@
+//! Base URLs of external services.
+//!
+//! Request code builds URLs through [`https_url`] instead of formatting
+//! `https://{host}` inline, so tests can point a host at a local mock server.
+
+/// `https://{host}{path}`, or the test override registered for `host`.
+pub(crate) fn https_url(host: &str, path: &str) -> String {
+    #[cfg(test)]
+    if let Some(base) = overrides::base_for(host) {
+        return format!("{}{}", base, path);
+    }
+    format!("https://{}{}", host, path)
+}
+
+/// Per-thread host overrides for tests. `#[tokio::test]` runs on a
+/// current-thread runtime, so overrides never leak between parallel tests.
+#[cfg(test)]
+pub(crate) mod overrides {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    thread_local! {
+        static OVERRIDES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+    }
+
+    pub(crate) fn base_for(host: &str) -> Option<String> {
+        OVERRIDES.with(|o| o.borrow().get(host).cloned())
+    }
+
+    /// Route requests for `host` to `base` (e.g. a mock server URI) until the
+    /// guard is dropped.
+    pub(crate) fn set(host: &str, base: &str) -> OverrideGuard {
+        OVERRIDES.with(|o| o.borrow_mut().insert(host.to_string(), base.trim_end_matches('/').to_string()));
+        OverrideGuard(host.to_string())
+    }
+
+    pub(crate) struct OverrideGuard(String);
+
+    impl Drop for OverrideGuard {
+        fn drop(&mut self) {
+            OVERRIDES.with(|o| o.borrow_mut().remove(&self.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn https_url_uses_override_until_dropped() {
+        assert_eq!(https_url("api.github.com", "/user"), "https://api.github.com/user");
+        {
+            let _guard = overrides::set("api.github.com", "http://127.0.0.1:9999/");
+            assert_eq!(https_url("api.github.com", "/user"), "http://127.0.0.1:9999/user");
+        }
+        assert_eq!(https_url("api.github.com", "/user"), "https://api.github.com/user");
+    }
+}
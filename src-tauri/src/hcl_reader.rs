@@ -0,0 +1,264 @@
+//! Structural view of HCL, the Terraform configuration syntax.
+//!
+//! Files are parsed with `hcl-rs` and turned into their attributes and
+//! blocks, nested to any depth, without evaluating expressions. Each
+//! expression keeps its formatted source text (whitespace collapsed), the
+//! decoded value when it is a string or heredoc without interpolation, and
+//! its entries when it is an object with literal keys. `hcl-rs` drops
+//! comments, so the line comments directly above each top-level block are
+//! read from the source, for `# @key: value` annotations.
+
+use ::hcl::template::{Element, Template};
+
+/// Attributes and blocks of a file or block, in source order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Body {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Item {
+    Attribute(Attribute),
+    Block(Block),
+}
+
+/// `name = expression`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    pub name: String,
+    pub expr: Expression,
+}
+
+/// `kind "label" ... { body }`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Block {
+    pub kind: String,
+    pub labels: Vec<String>,
+    pub body: Body,
+    /// Line comments directly above a top-level block, without the comment
+    /// marker. Empty for nested blocks.
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Expression {
+    /// Formatted source text, whitespace runs collapsed to one space.
+    pub text: String,
+    /// Decoded value when the expression is a single string or heredoc
+    /// without interpolation.
+    pub string: Option<String>,
+    /// Entries when the expression is an object constructor with literal
+    /// keys (`{ key = value, "other" = value }`), in source order.
+    pub object: Option<Vec<(String, Expression)>>,
+}
+
+impl Body {
+    pub fn attribute(&self, name: &str) -> Option<&Expression> {
+        self.items.iter().find_map(|item| match item {
+            Item::Attribute(a) if a.name == name => Some(&a.expr),
+            _ => None,
+        })
+    }
+
+    pub fn blocks<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a Block> + 'a {
+        self.items.iter().filter_map(move |item| match item {
+            Item::Block(b) if b.kind == kind => Some(b),
+            _ => None,
+        })
+    }
+}
+
+impl Expression {
+    /// The decoded string, or the source text for anything else.
+    pub fn string_or_text(&self) -> &str {
+        self.string.as_deref().unwrap_or(&self.text)
+    }
+
+    /// Value of an object entry.
+    pub fn entry(&self, key: &str) -> Option<&Expression> {
+        self.object.as_ref()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// Parse a whole file. Errors carry the line and column.
+pub(crate) fn parse(src: &str) -> Result<Body, String> {
+    let parsed = ::hcl::parse(src).map_err(|e| e.to_string())?;
+    let mut body = convert_body(&parsed);
+    attach_comments(src, &mut body);
+    Ok(body)
+}
+
+// ─── Conversion ─────────────────────────────────────────────────────────────
+
+fn convert_body(body: &::hcl::Body) -> Body {
+    let items = body
+        .iter()
+        .map(|structure| match structure {
+            ::hcl::Structure::Attribute(attr) => {
+                Item::Attribute(Attribute { name: attr.key().to_string(), expr: convert_expr(attr.expr()) })
+            }
+            ::hcl::Structure::Block(block) => Item::Block(Block {
+                kind: block.identifier().to_string(),
+                labels: block.labels().iter().map(|l| l.as_str().to_string()).collect(),
+                body: convert_body(block.body()),
+                comments: Vec::new(),
+            }),
+        })
+        .collect();
+    Body { items }
+}
+
+fn convert_expr(expr: &::hcl::Expression) -> Expression {
+    let text = expr.to_string().split_whitespace().collect::<Vec<_>>().join(" ");
+    let object = match expr {
+        ::hcl::Expression::Object(object) => object
+            .iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    ::hcl::ObjectKey::Identifier(ident) => ident.to_string(),
+                    ::hcl::ObjectKey::Expression(::hcl::Expression::String(s)) => s.clone(),
+                    // Computed keys: not a plain object
+                    _ => return None,
+                };
+                Some((key, convert_expr(value)))
+            })
+            .collect(),
+        _ => None,
+    };
+    Expression { text, string: literal_string(expr), object }
+}
+
+/// Value of a string or heredoc made only of literal text.
+fn literal_string(expr: &::hcl::Expression) -> Option<String> {
+    match expr {
+        ::hcl::Expression::String(s) => Some(s.clone()),
+        ::hcl::Expression::TemplateExpr(template) => Template::from_expr(template)
+            .ok()?
+            .elements()
+            .iter()
+            .map(|element| match element {
+                Element::Literal(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Give each top-level block the `#` / `//` comment lines directly above
+/// its header. Headers are matched in order, so repeated kinds line up.
+fn attach_comments(src: &str, body: &mut Body) {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut next = 0;
+    for item in &mut body.items {
+        let Item::Block(block) = item else {
+            continue;
+        };
+        let Some(offset) = lines[next..].iter().position(|line| is_header(line, block)) else {
+            continue;
+        };
+        let header = next + offset;
+        let above = lines[..header].iter().rev().map_while(|line| line_comment(line));
+        block.comments = above.collect::<Vec<_>>().into_iter().rev().collect();
+        next = header + 1;
+    }
+}
+
+fn is_header(line: &str, block: &Block) -> bool {
+    let Some(mut rest) = line.trim_start().strip_prefix(block.kind.as_str()) else {
+        return false;
+    };
+    if !rest.starts_with(|c: char| c.is_whitespace() || c == '{') {
+        return false;
+    }
+    for label in &block.labels {
+        rest = rest.trim_start();
+        rest = match rest.strip_prefix(&format!("\"{}\"", label)).or_else(|| rest.strip_prefix(label.as_str())) {
+            Some(after) => after,
+            None => return false,
+        };
+    }
+    rest.trim_start().starts_with('{')
+}
+
+fn line_comment(line: &str) -> Option<String> {
+    let line = line.trim();
+    let text = line.strip_prefix('#').or_else(|| line.strip_prefix("//"))?;
+    Some(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_templates_and_heredocs() {
+        let body = parse(
+            r#"
+a = "plain \"quoted\" é"
+b = "${var.x}-${join(",", ["}", "y"])}"
+c = "https://example.com" // not part of the value
+d = <<EOT
+line one
+EOT
+e = <<EOT
+${var.x}
+EOT
+"#,
+        )
+        .unwrap();
+        assert_eq!(body.attribute("a").unwrap().string.as_deref(), Some("plain \"quoted\" é"));
+        assert_eq!(body.attribute("b").unwrap().string, None);
+        assert!(body.attribute("b").unwrap().text.contains("var.x"));
+        assert_eq!(body.attribute("c").unwrap().text, "\"https://example.com\"");
+        assert_eq!(body.attribute("d").unwrap().string.as_deref(), Some("line one\n"));
+        assert_eq!(body.attribute("e").unwrap().string, None);
+        assert_eq!(body.items.len(), 5);
+    }
+
+    #[test]
+    fn nested_blocks_objects_and_comments() {
+        let body = parse(
+            r#"
+/* header
+   comment */
+terraform {
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws" # trailing
+      version = ">= 5.0"
+    }
+  }
+}
+
+# @group: Network
+// @order: 2
+variable "cidr" {
+  type = list(
+    string
+  )
+}
+computed = { (var.k) = 1 }
+"#,
+        )
+        .unwrap();
+        let terraform = body.blocks("terraform").next().unwrap();
+        let providers = terraform.body.blocks("required_providers").next().unwrap();
+        let aws = providers.body.attribute("aws").unwrap();
+        assert_eq!(aws.entry("source").and_then(|e| e.string.as_deref()), Some("hashicorp/aws"));
+        assert_eq!(aws.entry("version").unwrap().text, "\">= 5.0\"");
+        assert!(terraform.comments.is_empty());
+
+        let variable = body.blocks("variable").next().unwrap();
+        assert_eq!(variable.labels, vec!["cidr"]);
+        assert_eq!(variable.comments, vec!["@group: Network", "@order: 2"]);
+        assert_eq!(variable.body.attribute("type").unwrap().text, "list(string)");
+        assert_eq!(body.attribute("computed").unwrap().object, None);
+    }
+
+    #[test]
+    fn invalid_input_is_an_error() {
+        assert!(parse("a = 1\nb = \"open\n").is_err());
+        assert!(parse("x {\n  a = 1\n").is_err());
+    }
+}
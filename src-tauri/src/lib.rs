@@ -1,8 +1,17 @@
+mod aws_config;
+pub mod cli;
 mod commands;
 mod crypto;
+mod databricks_api;
 mod dependencies;
+mod endpoints;
 mod errors;
+mod hcl_reader;
+#[cfg(test)]
+mod mock_cloud;
 pub(crate) mod proxy;
+mod secret_scan;
+mod storage;
 mod terraform;
 
 use commands::debug_log;
@@ -12,10 +21,15 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_single_instance::init(|_app, _argv, _cwd| {
-            // Single-instance: second launch is ignored (focus logic could be added here)
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // Single-instance: focus this window and open any deployment the second launch named
+            commands::handle_second_launch(app, &argv);
         }))
         .setup(|app| {
+            commands::handle_initial_launch();
+            commands::apply_settings_at_startup(app.handle());
+
             // Extract templates to app data directory on first run or when template version changes
             let app_handle = app.handle().clone();
             std::thread::spawn(move || {
@@ -23,70 +37,194 @@ pub fn run() {
                     debug_log!("Failed to setup templates: {}", _e);
                 }
             });
+
+            // Reattach to or record Terraform runs cut off by the last exit
+            let app_handle = app.handle().clone();
+            std::thread::spawn(move || commands::recover_interrupted_runs(&app_handle));
+
+            // Destroy or report deployments whose TTL has passed
+            let app_handle = app.handle().clone();
+            std::thread::spawn(move || commands::run_expiry_scheduler(&app_handle));
+
+            // Upload opted-in usage telemetry in the background
+            let app_handle = app.handle().clone();
+            std::thread::spawn(move || commands::run_telemetry_uploader(&app_handle));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::check_dependencies,
             commands::check_terraform_connectivity,
             commands::install_terraform,
+            commands::install_dependency,
+            commands::locate_dependency,
+            commands::get_dependency_search_paths,
+            commands::set_dependency_search_paths,
             commands::validate_databricks_credentials,
             commands::get_templates,
             commands::get_template_variables,
+            commands::list_org_profiles,
+            commands::save_org_profile,
+            commands::delete_org_profile,
+            commands::import_org_profile,
+            commands::get_template_details,
+            commands::get_variable_options,
+            commands::list_account_configurations,
+            commands::deep_cleanup_workspace,
+            commands::get_account_admins,
+            commands::scan_orphaned_resources,
+            commands::lint_template,
+            commands::lint_deployment,
+            commands::list_encryption_keys,
+            commands::validate_encryption_key,
+            commands::encryption_key_variables,
             commands::save_configuration,
+            commands::preview_configuration_change,
             commands::run_terraform_command,
+            commands::check_credential_freshness,
+            commands::get_deployment_engine,
+            commands::set_deployment_engine,
             commands::get_deployment_status,
+            commands::get_deployment_lock,
+            commands::get_interrupted_runs,
+            commands::dismiss_interrupted_runs,
+            commands::get_notification_settings,
+            commands::get_settings,
+            commands::update_settings,
+            commands::export_app_config,
+            commands::import_app_config,
+            commands::validate_tags,
+            commands::get_telemetry_status,
+            commands::preview_telemetry_events,
+            commands::set_telemetry_opt_in,
+            commands::flush_telemetry,
+            commands::clear_telemetry_queue,
+            commands::set_notification_settings,
+            commands::send_test_notification,
+            commands::export_deployment_report,
+            commands::compare_deployments,
+            commands::get_run_settings,
+            commands::get_terraform_debug_log,
+            commands::save_run_settings,
+            commands::update_provider_locks,
+            commands::compare_environment,
+            commands::export_deployment_bundle,
+            commands::list_state_backups,
+            commands::restore_state_backup,
+            commands::get_state_encryption,
+            commands::set_state_encryption,
+            commands::get_post_deploy_settings,
+            commands::run_post_deploy,
+            commands::preview_identity_batch,
+            commands::provision_account_identities,
+            commands::list_directory_groups,
+            commands::sync_directory_groups,
             commands::reset_deployment_status,
             commands::cancel_deployment,
+            commands::enqueue_run,
+            commands::get_queue,
+            commands::reorder_queue,
+            commands::cancel_queued,
             commands::rollback_deployment,
+            commands::get_deployment_expiry,
+            commands::set_deployment_expiry,
+            commands::list_expired_deployments,
             commands::get_cloud_credentials,
+            commands::verify_credential_context,
+            commands::switch_credential_context,
             commands::get_aws_profiles,
             commands::get_aws_identity,
             commands::aws_sso_login,
+            commands::get_aws_sso_sessions,
             commands::get_aws_vpcs,
             commands::get_azure_account,
             commands::get_azure_subscriptions,
+            commands::get_azure_subscriptions_sp,
+            commands::get_azure_locations,
             commands::get_azure_resource_groups,
             commands::get_azure_resource_groups_sp,
             commands::get_azure_vnets,
             commands::get_azure_vnets_sp,
             commands::azure_login,
             commands::cancel_cli_login,
+            commands::cancel_request,
             commands::set_azure_subscription,
             commands::check_resource_names_available,
             commands::check_resource_names_available_sp,
+            commands::check_cloud_quotas,
+            commands::run_preflight_checks,
+            commands::validate_private_connectivity,
+            commands::validate_resource_names,
+            commands::list_supported_regions,
             commands::clear_templates_cache,
+            commands::clear_metadata_cache,
+            commands::get_template_refresh_report,
+            commands::verify_templates_integrity,
+            commands::diff_deployment_template,
+            commands::upgrade_deployment_template,
             commands::get_deployments_folder,
             commands::open_folder,
+            commands::list_deployment_files,
+            commands::read_deployment_file,
+            commands::open_in_editor,
             commands::open_url,
             commands::get_databricks_profiles,
             commands::databricks_cli_login,
             commands::get_databricks_profile_credentials,
             commands::create_databricks_sp_profile,
             commands::check_uc_permissions,
+            commands::grant_metastore_permissions,
+            commands::plan_metastore_strategy,
             commands::check_aws_permissions,
             commands::check_azure_permissions,
             commands::validate_gcp_credentials,
             commands::get_gcp_projects,
+            commands::list_gcp_projects,
             commands::gcp_login,
             commands::check_gcp_permissions,
+            commands::check_and_enable_gcp_apis,
             commands::validate_gcp_databricks_access,
             commands::validate_gcp_databricks_access_with_key,
             commands::validate_databricks_profile,
+            commands::validate_all_databricks_profiles,
+            commands::list_databricks_cached_tokens,
+            commands::invalidate_databricks_token,
             commands::validate_azure_databricks_identity,
             commands::create_gcp_service_account,
+            commands::use_existing_gcp_service_account,
+            commands::cleanup_bootstrap_artifacts,
             commands::add_service_account_to_databricks,
+            // Recent and pinned items (command palette)
+            commands::get_recent_items,
+            commands::pin_item,
             // Git / GitHub integration
             commands::git_get_status,
             commands::git_init_repo,
             commands::git_check_remote,
             commands::git_push_to_remote,
+            commands::git_commit_changes,
+            commands::git_pull,
+            commands::git_sync,
+            commands::git_scan_secrets,
+            commands::ssh_list_keys,
+            commands::ssh_generate_key,
+            commands::github_register_ssh_key,
             commands::preview_tfvars_example,
             commands::github_device_auth_start,
             commands::github_device_auth_poll,
             commands::github_get_auth,
             commands::github_logout,
+            commands::github_validate_token,
             commands::github_create_repo,
+            commands::github_list_orgs,
+            commands::git_hosting_connect,
+            commands::git_hosting_get_auth,
+            commands::git_hosting_logout,
+            commands::create_remote_repo,
+            commands::generate_ci_pipeline,
             commands::check_for_updates,
+            commands::take_launch_request,
+            commands::preview_rollback,
+            commands::get_preserved_resources,
             // AI Assistant
             commands::assistant_save_token,
             commands::assistant_chat,
@@ -97,8 +235,15 @@ pub fn run() {
             commands::assistant_delete_all_keys,
             commands::assistant_get_available_models,
             commands::assistant_update_model,
+            commands::assistant_configure_endpoint,
+            commands::assistant_get_local_models,
             commands::assistant_save_history,
             commands::assistant_clear_history,
+            commands::assistant_create_conversation,
+            commands::assistant_list_conversations,
+            commands::assistant_get_conversation,
+            commands::assistant_rename_conversation,
+            commands::assistant_delete_conversation,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,1218 @@
+//! Mock-cloud integration harness.
+//!
+//! Starts a local wiremock server, routes the Databricks account and
+//! workspace, GCP IAM credentials, and GitHub hosts to it (see
+//! [`crate::endpoints`]), and drives credential validation, Unity Catalog
+//! checks, the account and workspace API clients, and the GitHub device flow
+//! end-to-end. Mocks match on method, path, auth, and body, so a change in a
+//! request's format fails here without needing real accounts.
+
+use crate::commands::{self, CloudCredentials};
+use crate::databricks_api::{AccountAuth, AccountClient, ApiError};
+use crate::endpoints::overrides::{self, OverrideGuard};
+use crate::storage::StoragePaths;
+use wiremock::matchers::{basic_auth, bearer_token, body_json, body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ACCOUNT_ID: &str = "01234567-89ab-cdef-0123-456789abcdef";
+const CLIENT_ID: &str = "sp-client-id";
+const CLIENT_SECRET: &str = "sp-client-secret";
+const ACCESS_TOKEN: &str = "dbx-access-token";
+const WORKSPACE_HOST: &str = "dbc-1a2b3c4d.cloud.databricks.com";
+
+/// Hosts redirected to the mock server.
+const MOCKED_HOSTS: &[&str] = &[
+    "accounts.cloud.databricks.com",
+    "accounts.azuredatabricks.net",
+    "accounts.gcp.databricks.com",
+    WORKSPACE_HOST,
+    "iamcredentials.googleapis.com",
+    "cloudresourcemanager.googleapis.com",
+    "serviceusage.googleapis.com",
+    "graph.microsoft.com",
+    "login.microsoftonline.com",
+    "management.azure.com",
+    "github.com",
+    "api.github.com",
+    "raw.githubusercontent.com",
+];
+
+/// A running mock server with every [`MOCKED_HOSTS`] entry routed to it.
+struct MockCloud {
+    server: MockServer,
+    _guards: Vec<OverrideGuard>,
+}
+
+impl MockCloud {
+    async fn start() -> Self {
+        let server = MockServer::start().await;
+        let guards = MOCKED_HOSTS
+            .iter()
+            .map(|host| overrides::set(host, &server.uri()))
+            .collect();
+        Self { server, _guards: guards }
+    }
+
+    async fn mount(&self, mock: Mock) {
+        mock.mount(&self.server).await;
+    }
+
+    /// Databricks OAuth M2M token exchange for the test service principal.
+    async fn mount_databricks_token(&self) {
+        self.mount_databricks_token_times(1).await;
+    }
+
+    /// Token endpoint for `clients` separate account clients, each minting its own token.
+    async fn mount_databricks_token_times(&self, clients: u64) {
+        self.mount(
+            Mock::given(method("POST"))
+                .and(path(format!("/oidc/accounts/{}/v1/token", ACCOUNT_ID)))
+                .and(basic_auth(CLIENT_ID, CLIENT_SECRET))
+                .and(body_string_contains("grant_type=client_credentials"))
+                .and(body_string_contains("scope=all-apis"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": ACCESS_TOKEN,
+                    "token_type": "Bearer",
+                    "expires_in": 3600
+                })))
+                .expect(clients),
+        )
+        .await;
+    }
+}
+
+fn sp_credentials(cloud: &str) -> CloudCredentials {
+    CloudCredentials {
+        cloud: Some(cloud.to_string()),
+        databricks_account_id: Some(ACCOUNT_ID.to_string()),
+        databricks_client_id: Some(CLIENT_ID.to_string()),
+        databricks_client_secret: Some(CLIENT_SECRET.to_string()),
+        databricks_auth_type: Some("credentials".to_string()),
+        ..Default::default()
+    }
+}
+
+// ── validate_databricks_credentials ─────────────────────────────────────
+
+#[tokio::test]
+async fn validate_databricks_credentials_confirms_account_admin() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .and(query_param("count", "1"))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "totalResults": 1,
+                    "Resources": []
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let result = commands::validate_databricks_credentials(
+        ACCOUNT_ID.to_string(),
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+        "aws".to_string(),
+    )
+    .await
+    .unwrap();
+    assert!(result.contains("Account Admin"), "{}", result);
+}
+
+#[tokio::test]
+async fn validate_databricks_credentials_reports_missing_admin_role() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(403)),
+        )
+        .await;
+
+    let err = commands::validate_databricks_credentials(
+        ACCOUNT_ID.to_string(),
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+        "aws".to_string(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.contains("account admin privileges"), "{}", err);
+}
+
+#[tokio::test]
+async fn validate_databricks_credentials_rejects_bad_secret() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("/oidc/accounts/{}/v1/token", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(401).set_body_string("invalid_client")),
+        )
+        .await;
+
+    let err = commands::validate_databricks_credentials(
+        ACCOUNT_ID.to_string(),
+        CLIENT_ID.to_string(),
+        "wrong".to_string(),
+        "azure".to_string(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.starts_with("Authentication failed"), "{}", err);
+}
+
+// ── check_uc_permissions ────────────────────────────────────────────────
+
+#[tokio::test]
+async fn check_uc_permissions_reads_metastore_grants() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores", ACCOUNT_ID)))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "metastores": [
+                        { "metastore_id": "ms-eu", "name": "eu", "region": "eu-west-1", "owner": "admins" },
+                        { "metastore_id": "ms-us", "name": "us", "region": "us-east-1", "owner": "admins" }
+                    ]
+                }))),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores/ms-us/permissions", ACCOUNT_ID)))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "privilege_assignments": [
+                        { "principal": CLIENT_ID, "privileges": ["CREATE_CATALOG", "CREATE_STORAGE_CREDENTIAL"] },
+                        { "principal": "someone-else", "privileges": ["ALL_PRIVILEGES"] }
+                    ]
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let check = commands::check_uc_permissions(sp_credentials("aws"), "us-east-1".to_string(), Some(true), None)
+        .await
+        .unwrap();
+    assert!(check.metastore.exists);
+    assert_eq!(check.metastore.metastore_id.as_deref(), Some("ms-us"));
+    assert!(check.has_create_catalog);
+    assert!(check.has_create_storage_credential);
+    assert!(!check.has_create_external_location);
+    assert!(!check.can_create_catalog);
+    assert!(check.message.contains("CREATE_EXTERNAL_LOCATION"), "{}", check.message);
+}
+
+#[tokio::test]
+async fn check_uc_permissions_without_regional_metastore() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "metastores": [{ "metastore_id": "ms-eu", "name": "eu", "region": "westeurope" }]
+                }))),
+        )
+        .await;
+
+    let check = commands::check_uc_permissions(sp_credentials("azure"), "East US 2".to_string(), Some(true), None)
+        .await
+        .unwrap();
+    assert!(!check.metastore.exists);
+    assert!(check.can_create_catalog);
+}
+
+// ── validate_gcp_databricks_access ──────────────────────────────────────
+
+const GCP_SA: &str = "deployer@my-project.iam.gserviceaccount.com";
+
+/// GCP OAuth tokens must look real enough to pass the length check.
+fn gcp_oauth_token() -> String {
+    format!("ya29.{}", "a".repeat(60))
+}
+
+async fn mount_gcp_id_token(cloud: &MockCloud) {
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("/v1/projects/-/serviceAccounts/{}:generateIdToken", GCP_SA)))
+                .and(bearer_token(gcp_oauth_token()))
+                .and(body_json(serde_json::json!({
+                    "audience": "https://accounts.gcp.databricks.com",
+                    "includeEmail": true
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "token": "gcp-id-token" })))
+                .expect(1),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn validate_gcp_databricks_access_with_authorized_sa() {
+    let cloud = MockCloud::start().await;
+    mount_gcp_id_token(&cloud).await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores", ACCOUNT_ID)))
+                .and(bearer_token("gcp-id-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "metastores": [] })))
+                .expect(1),
+        )
+        .await;
+
+    let result = commands::validate_gcp_databricks_access(
+        ACCOUNT_ID.to_string(),
+        gcp_oauth_token(),
+        Some(GCP_SA.to_string()),
+    )
+    .await
+    .unwrap();
+    assert!(result.contains(GCP_SA), "{}", result);
+}
+
+#[tokio::test]
+async fn validate_gcp_databricks_access_with_unregistered_sa() {
+    let cloud = MockCloud::start().await;
+    mount_gcp_id_token(&cloud).await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(403)),
+        )
+        .await;
+
+    let err = commands::validate_gcp_databricks_access(
+        ACCOUNT_ID.to_string(),
+        gcp_oauth_token(),
+        Some(GCP_SA.to_string()),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.contains("not been added to the Databricks Account Console"), "{}", err);
+}
+
+// ── databricks_api ──────────────────────────────────────────────────────
+
+fn sp_client(cloud: &str) -> AccountClient {
+    let auth = AccountAuth::ServicePrincipal {
+        client_id: CLIENT_ID.to_string(),
+        client_secret: CLIENT_SECRET.to_string(),
+    };
+    AccountClient::new(cloud, ACCOUNT_ID, auth).unwrap()
+}
+
+#[tokio::test]
+async fn account_client_retries_throttled_requests() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/workspaces", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+                .up_to_n_times(1)
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/workspaces", ACCOUNT_ID)))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "workspace_name": "analytics", "workspace_id": 42 }
+                ])))
+                .expect(1),
+        )
+        .await;
+
+    let workspaces = sp_client("aws").list_workspaces().await.unwrap();
+    assert_eq!(workspaces.len(), 1);
+    assert_eq!(workspaces[0].workspace_name, "analytics");
+}
+
+#[tokio::test]
+async fn account_client_does_not_retry_failed_creates() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(1),
+        )
+        .await;
+
+    let err = sp_client("aws").create_user(GCP_SA, "deployer").await.unwrap_err();
+    assert_eq!(err.status(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+}
+
+#[tokio::test]
+async fn list_account_configurations_filters_by_cloud() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token_times(3).await;
+    let account_path = |suffix: &str| format!("/api/2.0/accounts/{}/{}", ACCOUNT_ID, suffix);
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(account_path("networks")))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "network_id": "net-aws", "network_name": "shared-vpc", "vpc_id": "vpc-1",
+                      "subnet_ids": ["subnet-a", "subnet-b"], "vpc_status": "VALID" },
+                    { "network_id": "net-gcp", "network_name": "gcp-net",
+                      "gcp_network_info": { "network_project_id": "p", "vpc_id": "v", "subnet_id": "s", "subnet_region": "us-east1" } }
+                ]))),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(account_path("storage-configurations")))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "storage_configuration_id": "sc-1", "storage_configuration_name": "root",
+                      "root_bucket_info": { "bucket_name": "root-bucket" } }
+                ])))
+                .expect(2),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(account_path("credentials")))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "credentials_id": "cr-1", "credentials_name": "cross-account",
+                      "aws_credentials": { "sts_role": { "role_arn": "arn:aws:iam::123:role/dbx" } } }
+                ])))
+                .expect(2),
+        )
+        .await;
+
+    let aws = commands::list_account_configurations(sp_credentials("aws")).await.unwrap();
+    assert_eq!(aws.networks.len(), 1);
+    assert_eq!(aws.networks[0].id, "net-aws");
+    assert_eq!(aws.networks[0].detail, "vpc-1, 2 subnets, VALID");
+    assert_eq!(aws.storage_configurations[0].detail, "root-bucket");
+    assert_eq!(aws.credentials[0].detail, "arn:aws:iam::123:role/dbx");
+
+    let options = commands::get_variable_options("databricks_credentials".to_string(), sp_credentials("aws"), None);
+    let options = options.await.unwrap();
+    assert_eq!(options[0].value, "cr-1");
+    assert_eq!(options[0].label, "cross-account (arn:aws:iam::123:role/dbx)");
+
+    let gcp = commands::list_account_configurations(sp_credentials("gcp")).await.unwrap();
+    assert_eq!(gcp.networks.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), vec!["net-gcp"]);
+    assert!(gcp.storage_configurations.is_empty() && gcp.credentials.is_empty());
+}
+
+#[tokio::test]
+async fn account_client_maps_html_and_errors() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(200).set_body_raw("<html>login</html>", "text/html")),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/metastores/ms-1/permissions", ACCOUNT_ID)))
+                .respond_with(ResponseTemplate::new(404).set_body_string("no such metastore")),
+        )
+        .await;
+
+    let client = sp_client("gcp");
+    assert_eq!(client.list_metastores().await.unwrap_err(), ApiError::UnexpectedHtml);
+    assert_eq!(
+        client.metastore_permissions("ms-1").await.unwrap_err(),
+        ApiError::NotFound("no such metastore".to_string())
+    );
+}
+
+#[tokio::test]
+async fn deep_cleanup_deletes_only_confirmed_orphans() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    let account_path = |suffix: &str| format!("/api/2.0/accounts/{}/{}", ACCOUNT_ID, suffix);
+    let list = |suffix: &str, body: serde_json::Value| {
+        Mock::given(method("GET"))
+            .and(path(account_path(suffix)))
+            .and(bearer_token(ACCESS_TOKEN))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    };
+    cloud
+        .mount(list("workspaces", serde_json::json!([
+            { "workspace_name": "other", "workspace_id": 7, "storage_configuration_id": "sc-1" }
+        ])))
+        .await;
+    cloud
+        .mount(list("networks", serde_json::json!([{ "network_id": "net-1", "network_name": "dev-network" }])).up_to_n_times(1))
+        .await;
+    cloud.mount(list("networks", serde_json::json!([]))).await;
+    cloud
+        .mount(list("storage-configurations", serde_json::json!([
+            { "storage_configuration_id": "sc-1", "storage_configuration_name": "dev-storage" }
+        ])))
+        .await;
+    cloud
+        .mount(list("credentials", serde_json::json!([
+            { "credentials_id": "cr-1", "credentials_name": "dev-creds" },
+            { "credentials_id": "cr-2", "credentials_name": "devops-creds" }
+        ])))
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("DELETE"))
+                .and(path(account_path("networks/net-1")))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .expect(1),
+        )
+        .await;
+
+    let ids = Some(vec!["net-1".to_string(), "sc-1".to_string()]);
+    let cleanup = commands::account_cleanup::deep_cleanup("dev".to_string(), Vec::new(), sp_credentials("aws"), ids).await.unwrap();
+    assert_eq!(cleanup.prefix, "dev");
+    assert_eq!(cleanup.removed, vec!["net-1"]);
+    assert!(cleanup.failed.is_empty());
+    assert_eq!(cleanup.orphans.iter().map(|o| o.id.as_str()).collect::<Vec<_>>(), vec!["cr-1"]);
+}
+
+#[tokio::test]
+async fn account_admins_pages_through_users_and_service_principals() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    let users_page = |start: &str, body: serde_json::Value| {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+            .and(query_param("startIndex", start))
+            .and(bearer_token(ACCESS_TOKEN))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+    };
+    cloud
+        .mount(users_page("1", serde_json::json!({
+            "totalResults": 2,
+            "Resources": [{ "id": "u-1", "userName": "ops@example.com", "roles": [{ "value": "account_admin" }] }]
+        })))
+        .await;
+    cloud
+        .mount(users_page("2", serde_json::json!({
+            "totalResults": 2,
+            "Resources": [{ "id": "u-2", "userName": "dev@example.com" }]
+        })))
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/ServicePrincipals", ACCOUNT_ID)))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "totalResults": 1,
+                    "Resources": [{ "id": "sp-1", "applicationId": CLIENT_ID, "roles": [{ "value": "account_admin" }] }]
+                }))),
+        )
+        .await;
+
+    let admins = commands::account_admins::account_admins(sp_credentials("aws")).await.unwrap();
+    let names: Vec<&str> = admins.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["ops@example.com", CLIENT_ID]);
+}
+
+#[tokio::test]
+async fn grant_metastore_permissions_adds_only_missing_privileges() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    let permissions_path = format!("/api/2.0/accounts/{}/metastores/ms-1/permissions", ACCOUNT_ID);
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(permissions_path.clone()))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "privilege_assignments": [{ "principal": "deployer", "privileges": ["CREATE_CATALOG"] }]
+                }))),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("PATCH"))
+                .and(path(permissions_path))
+                .and(body_json(serde_json::json!({
+                    "changes": [{
+                        "principal": "deployer",
+                        "add": ["CREATE_EXTERNAL_LOCATION", "CREATE_STORAGE_CREDENTIAL"]
+                    }]
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "privilege_assignments": [{
+                        "principal": "deployer",
+                        "privileges": ["CREATE_CATALOG", "CREATE_EXTERNAL_LOCATION", "CREATE_STORAGE_CREDENTIAL"]
+                    }]
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let grant = commands::grant_metastore_permissions(sp_credentials("aws"), "ms-1".to_string(), Some("deployer".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(grant.already_granted, vec!["CREATE_CATALOG"]);
+    assert_eq!(grant.granted, vec!["CREATE_EXTERNAL_LOCATION", "CREATE_STORAGE_CREDENTIAL"]);
+}
+
+#[tokio::test]
+async fn account_client_scim_create_falls_back_to_existing_user() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .and(header("content-type", "application/scim+json"))
+                .and(body_string_contains(GCP_SA))
+                .respond_with(ResponseTemplate::new(409).set_body_string("User already exists"))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .and(query_param("filter", format!("userName eq \"{}\"", GCP_SA)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "totalResults": 1,
+                    "Resources": [{ "id": "user-7", "userName": GCP_SA }]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("PATCH"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users/user-7", ACCOUNT_ID)))
+                .and(body_string_contains("account_admin"))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1),
+        )
+        .await;
+
+    let client = sp_client("gcp");
+    let err = client.create_user(GCP_SA, "deployer").await.unwrap_err();
+    assert!(matches!(err, ApiError::Conflict(_)), "{:?}", err);
+    let user = client.find_user(GCP_SA).await.unwrap().unwrap();
+    assert_eq!(user.id, "user-7");
+    client.grant_account_admin(&user.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn post_deploy_configures_workspace() {
+    use crate::commands::post_deploy::{run_steps, PostDeploySettings};
+    use crate::databricks_api::WorkspaceClient;
+
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/oidc/v1/token"))
+                .and(basic_auth(CLIENT_ID, CLIENT_SECRET))
+                .and(body_string_contains("scope=all-apis"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": ACCESS_TOKEN
+                })))
+                .expect(1),
+        )
+        .await;
+    // The policy exists from an earlier run; the warehouse doesn't
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/api/2.0/policies/clusters/list"))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "policies": [{ "policy_id": "P0", "name": "Starter - Small Clusters" }]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/api/2.0/policies/clusters/create"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "policy_id": "P1" })))
+                .expect(0),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/api/2.0/sql/warehouses"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "warehouses": [{ "id": "wh-0", "name": "Other warehouse" }]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/api/2.0/sql/warehouses"))
+                .and(body_string_contains("\"cluster_size\":\"2X-Small\""))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "wh-1" })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/api/2.0/preview/scim/v2/Users"))
+                .and(header("content-type", "application/scim+json"))
+                .and(body_string_contains("existing@example.com"))
+                .respond_with(ResponseTemplate::new(409).set_body_string("User already exists"))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/api/2.0/preview/scim/v2/Users"))
+                .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                    "id": "u-2", "userName": "new@example.com"
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let auth = AccountAuth::ServicePrincipal {
+        client_id: CLIENT_ID.to_string(),
+        client_secret: CLIENT_SECRET.to_string(),
+    };
+    let client = WorkspaceClient::new(&format!("https://{}/", WORKSPACE_HOST), auth).unwrap();
+    let settings = PostDeploySettings {
+        cluster_policy: true,
+        sql_warehouse: true,
+        invite_users: vec!["existing@example.com".to_string(), "new@example.com".to_string()],
+    };
+    let steps = run_steps(&client, &settings).await;
+    let messages: Vec<(&str, bool, &str)> = steps
+        .iter()
+        .map(|s| (s.step.as_str(), s.success, s.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            ("cluster_policy", true, "Cluster policy already exists (P0)"),
+            ("sql_warehouse", true, "Created SQL warehouse wh-1"),
+            ("invite_user", true, "Already a workspace user"),
+            ("invite_user", true, "Added to workspace"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn identity_batch_provisions_users_groups_and_workspace_access() {
+    use crate::commands::identity_batch::{provision, BatchGroup, BatchUser, IdentityBatch};
+
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    let scim = format!("/api/2.0/accounts/{}/scim/v2", ACCOUNT_ID);
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("{}/Groups", scim)))
+                .and(body_string_contains("data-eng"))
+                .respond_with(ResponseTemplate::new(409).set_body_string("Group already exists"))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("{}/Groups", scim)))
+                .and(query_param("filter", "displayName eq \"data-eng\""))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "Resources": [{ "id": "g-1", "displayName": "data-eng" }]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path(format!("{}/Users", scim)))
+                .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                    "id": "u-1", "userName": "a@example.com"
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("PATCH"))
+                .and(path(format!("{}/Groups/g-1", scim)))
+                .and(body_string_contains("\"value\":\"u-1\""))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("PUT"))
+                .and(path(format!(
+                    "/api/2.0/accounts/{}/workspaces/42/permissionassignments/principals/g-1",
+                    ACCOUNT_ID
+                )))
+                .and(body_json(serde_json::json!({ "permissions": ["USER"] })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .expect(1),
+        )
+        .await;
+
+    let batch = IdentityBatch {
+        users: vec![BatchUser {
+            email: "a@example.com".to_string(),
+            groups: vec!["data-eng".to_string()],
+            ..Default::default()
+        }],
+        groups: vec![BatchGroup {
+            name: "data-eng".to_string(),
+            workspace_permission: Some("USER".to_string()),
+        }],
+    };
+    let report = provision(&sp_client("aws"), &batch, Some(42)).await;
+    let steps: Vec<(&str, &str)> = report.steps.iter().map(|s| (s.action.as_str(), s.message.as_str())).collect();
+    assert_eq!(
+        steps,
+        vec![
+            ("create_group", "Already exists"),
+            ("create_user", "Created user"),
+            ("add_members", "Added 1 member(s)"),
+            ("assign_workspace", "Granted USER on workspace 42"),
+        ]
+    );
+    assert_eq!(report.failed, 0);
+}
+
+#[tokio::test]
+async fn directory_sync_lists_azure_ad_groups_across_pages() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/tenant-1/oauth2/v2.0/token"))
+                .and(body_string_contains("scope=https%3A%2F%2Fgraph.microsoft.com%2F.default"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": "graph-token"
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1.0/groups"))
+                .and(query_param("$skiptoken", "page2"))
+                .and(bearer_token("graph-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": [{ "id": "g-2", "displayName": "analysts" }]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1.0/groups"))
+                .and(query_param("$filter", "startswith(displayName,'a')"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": [{ "id": "g-1", "displayName": "Admins", "description": "Tenant admins" }],
+                    "@odata.nextLink": format!("{}/v1.0/groups?$skiptoken=page2", cloud.server.uri())
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let credentials = CloudCredentials {
+        cloud: Some("azure".to_string()),
+        azure_tenant_id: Some("tenant-1".to_string()),
+        azure_client_id: Some("app-id".to_string()),
+        azure_client_secret: Some("app-secret".to_string()),
+        ..Default::default()
+    };
+    let groups = commands::list_directory_groups(credentials, "azure_ad".to_string(), Some("a".to_string()))
+        .await
+        .unwrap();
+    let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, vec!["Admins", "analysts"]);
+    assert_eq!(groups[0].description.as_deref(), Some("Tenant admins"));
+}
+
+#[tokio::test]
+async fn directory_sync_rejects_truncated_listings() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/tenant-1/oauth2/v2.0/token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": "graph-token"
+                }))),
+        )
+        .await;
+    // Every page links to another one
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1.0/groups"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": [{ "id": "g-1", "displayName": "Admins" }],
+                    "@odata.nextLink": format!("{}/v1.0/groups?$skiptoken=next", cloud.server.uri())
+                })))
+                .expect(20),
+        )
+        .await;
+
+    let credentials = CloudCredentials {
+        cloud: Some("azure".to_string()),
+        azure_tenant_id: Some("tenant-1".to_string()),
+        azure_client_id: Some("app-id".to_string()),
+        azure_client_secret: Some("app-secret".to_string()),
+        ..Default::default()
+    };
+    let err = commands::list_directory_groups(credentials, "azure_ad".to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(err.contains("more than 20 items"), "{}", err);
+}
+
+#[tokio::test]
+async fn azure_subscriptions_listed_with_service_principal() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/tenant-1/oauth2/v2.0/token"))
+                .and(body_string_contains("client_secret=app-secret"))
+                .and(body_string_contains("scope=https%3A%2F%2Fmanagement.azure.com%2F.default"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": "arm-token"
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/subscriptions"))
+                .and(query_param("$skiptoken", "page2"))
+                .and(bearer_token("arm-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": [
+                        { "subscriptionId": "sub-b", "displayName": "analytics", "tenantId": "tenant-1", "state": "Enabled" },
+                        { "subscriptionId": "sub-c", "displayName": "Old", "tenantId": "tenant-1", "state": "Disabled" }
+                    ]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/subscriptions"))
+                .and(query_param("api-version", "2022-12-01"))
+                .and(bearer_token("arm-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "value": [{ "subscriptionId": "SUB-A", "displayName": "Production", "tenantId": "tenant-1", "state": "Enabled" }],
+                    "nextLink": format!("{}/subscriptions?api-version=2022-12-01&$skiptoken=page2", cloud.server.uri())
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let credentials = CloudCredentials {
+        cloud: Some("azure".to_string()),
+        azure_tenant_id: Some("tenant-1".to_string()),
+        azure_client_id: Some("app-id".to_string()),
+        azure_client_secret: Some("app-secret".to_string()),
+        azure_subscription_id: Some("sub-a".to_string()),
+        ..Default::default()
+    };
+    let subscriptions = commands::get_azure_subscriptions_sp(credentials, Some(true)).await.unwrap();
+    let ids: Vec<(&str, bool)> = subscriptions.iter().map(|s| (s.id.as_str(), s.is_default)).collect();
+    assert_eq!(ids, vec![("sub-b", false), ("SUB-A", true)]);
+}
+
+#[tokio::test]
+async fn gcp_projects_listed_across_pages() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1/projects"))
+                .and(query_param("pageToken", "page2"))
+                .and(bearer_token("gcp-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "projects": [
+                        { "projectId": "old-proj", "name": "Archive", "projectNumber": "222", "lifecycleState": "DELETE_REQUESTED" }
+                    ]
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1/projects"))
+                .and(bearer_token("gcp-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "projects": [
+                        { "projectId": "data-proj", "name": "Data", "projectNumber": "111", "lifecycleState": "ACTIVE" }
+                    ],
+                    "nextPageToken": "page2"
+                })))
+                .expect(1),
+        )
+        .await;
+
+    let credentials = CloudCredentials {
+        cloud: Some("gcp".to_string()),
+        gcp_oauth_token: Some("gcp-token".to_string()),
+        ..Default::default()
+    };
+    let projects = commands::list_gcp_projects(credentials).await.unwrap();
+    let ids: Vec<(&str, &str, &str)> = projects
+        .iter()
+        .map(|p| (p.project_id.as_str(), p.project_number.as_str(), p.state.as_str()))
+        .collect();
+    assert_eq!(ids, vec![("data-proj", "111", "ACTIVE"), ("old-proj", "222", "DELETE_REQUESTED")]);
+}
+
+#[tokio::test]
+async fn gcp_apis_enabled_after_confirmation() {
+    let cloud = MockCloud::start().await;
+    let service = |name: &str, state: &str| {
+        serde_json::json!({ "name": format!("projects/123/services/{}", name), "config": { "name": name }, "state": state })
+    };
+    let all_enabled: Vec<serde_json::Value> = [
+        "compute.googleapis.com",
+        "storage.googleapis.com",
+        "iam.googleapis.com",
+        "iamcredentials.googleapis.com",
+        "serviceusage.googleapis.com",
+    ]
+    .iter()
+    .map(|name| service(name, "ENABLED"))
+    .collect();
+    let mut before = all_enabled.clone();
+    before[0] = service("compute.googleapis.com", "DISABLED");
+    before[3] = service("iamcredentials.googleapis.com", "DISABLED");
+
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1/projects/data-proj/services:batchGet"))
+                .and(query_param("names", "projects/data-proj/services/compute.googleapis.com"))
+                .and(bearer_token("gcp-token"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "services": before })))
+                .up_to_n_times(1)
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/v1/projects/data-proj/services:batchEnable"))
+                .and(body_json(serde_json::json!({
+                    "serviceIds": ["compute.googleapis.com", "iamcredentials.googleapis.com"]
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "name": "operations/acf.123",
+                    "done": true
+                })))
+                .expect(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/v1/projects/data-proj/services:batchGet"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "services": all_enabled })))
+                .expect(1),
+        )
+        .await;
+
+    let credentials = CloudCredentials {
+        cloud: Some("gcp".to_string()),
+        gcp_project_id: Some("data-proj".to_string()),
+        gcp_oauth_token: Some("gcp-token".to_string()),
+        ..Default::default()
+    };
+    let report = commands::check_and_enable_gcp_apis(credentials, None, Some(true)).await.unwrap();
+    assert!(report.ready);
+    assert!(report.missing.is_empty());
+    assert_eq!(report.enabled, vec!["compute.googleapis.com", "iamcredentials.googleapis.com"]);
+}
+
+#[tokio::test]
+async fn profile_health_check_reports_missing_admin_role() {
+    let cloud = MockCloud::start().await;
+    cloud.mount_databricks_token().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.0/accounts/{}/scim/v2/Users", ACCOUNT_ID)))
+                .and(bearer_token(ACCESS_TOKEN))
+                .respond_with(ResponseTemplate::new(403)),
+        )
+        .await;
+
+    let auth = AccountAuth::ServicePrincipal {
+        client_id: CLIENT_ID.to_string(),
+        client_secret: CLIENT_SECRET.to_string(),
+    };
+    let err = commands::databricks_profiles::check_account_access("aws", ACCOUNT_ID, auth)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ApiError::Forbidden(_)), "{:?}", err);
+}
+
+// ── GitHub device flow ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn github_device_flow_persists_token() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/login/device/code"))
+                .and(body_string_contains("client_id="))
+                .and(body_string_contains("scope=repo"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "device_code": "dev-code",
+                    "user_code": "ABCD-1234",
+                    "verification_uri": "https://github.com/login/device",
+                    "expires_in": 900,
+                    "interval": 5
+                }))),
+        )
+        .await;
+    // First poll: user hasn't entered the code yet
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/login/oauth/access_token"))
+                .and(body_string_contains("device_code=dev-code"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "error": "authorization_pending"
+                })))
+                .up_to_n_times(1)
+                .with_priority(1),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("POST"))
+                .and(path("/login/oauth/access_token"))
+                .and(body_string_contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "access_token": "gho_test",
+                    "token_type": "bearer",
+                    "scope": "repo"
+                }))),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .and(bearer_token("gho_test"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "login": "octocat",
+                    "avatar_url": "https://avatars.example/octocat.png"
+                }))),
+        )
+        .await;
+
+    let start = commands::github_device_auth_start().await.unwrap();
+    assert_eq!(start.user_code, "ABCD-1234");
+
+    let data = tempfile::tempdir().unwrap();
+    let env = StoragePaths::from_data_dir(data.path());
+
+    let pending = commands::github::device_auth_poll(&env, &start.device_code).await.unwrap();
+    assert_eq!(pending.status, "pending");
+
+    let done = commands::github::device_auth_poll(&env, &start.device_code).await.unwrap();
+    assert_eq!(done.status, "success");
+    assert_eq!(done.username.as_deref(), Some("octocat"));
+    assert!(done.access_token.is_none(), "token must never reach the frontend");
+    assert_eq!(
+        commands::github::get_decrypted_token(&env).unwrap().as_deref(),
+        Some("gho_test")
+    );
+}
+
+#[tokio::test]
+async fn update_check_reports_release_and_template_bundle() {
+    let cloud = MockCloud::start().await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/OgnjenPantelic/workspace-creator/releases/latest"))
+                .and(header("User-Agent", "DatabricksDeployer/1.0"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "tag_name": "v9.0.0",
+                    "html_url": "https://github.com/OgnjenPantelic/workspace-creator/releases/tag/v9.0.0",
+                    "body": "Release notes",
+                    "published_at": "2026-01-01T00:00:00Z",
+                    "assets": [{
+                        "name": "Databricks Deployer_9.0.0_x64-setup.exe",
+                        "browser_download_url": "https://github.com/download/setup.exe",
+                        "size": 1024
+                    }]
+                }))),
+        )
+        .await;
+    cloud
+        .mount(
+            Mock::given(method("GET"))
+                .and(path("/OgnjenPantelic/workspace-creator/main/src-tauri/templates-catalog.json"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "templates_version": "99.0.0",
+                    "min_app_version": "9.0.0",
+                    "download_url": "https://github.com/OgnjenPantelic/workspace-creator/releases/latest"
+                }))),
+        )
+        .await;
+
+    let check = commands::check_for_updates("1.0.0".to_string()).await.unwrap();
+    assert!(check.update_available);
+    assert_eq!(check.latest_version.as_deref(), Some("9.0.0"));
+    assert_eq!(check.release_notes.as_deref(), Some("Release notes"));
+    assert_eq!(check.assets.len(), 1);
+    assert!(check.templates.update_available);
+    assert_eq!(check.templates.latest_version.as_deref(), Some("99.0.0"));
+    assert_eq!(check.templates.min_app_version.as_deref(), Some("9.0.0"));
+    assert!(check.templates.error.is_none());
+}
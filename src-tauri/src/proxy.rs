@@ -8,9 +8,12 @@
 //!
 //! This module detects the OS proxy configuration and returns the
 //! corresponding environment variables so they can be injected into
-//! Terraform (and other) child processes.
+//! Terraform (and other) child processes. A proxy chosen in the app settings
+//! takes precedence over both the environment and OS detection.
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// Networking-related environment variable names that should be forwarded
 /// from the user's environment (if present) into child processes.
@@ -29,17 +32,91 @@ const FORWARDED_ENV_VARS: &[&str] = &[
     "GIT_SSL_CAINFO",
 ];
 
+/// Proxy variable names, which a settings override replaces wholesale.
+const PROXY_ENV_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "NO_PROXY", "no_proxy"];
+
+/// How outbound connections pick a proxy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// Environment variables, then the OS configuration.
+    #[default]
+    System,
+    /// The URL from the settings.
+    Manual,
+    /// No proxy, even if the environment or OS configures one.
+    Direct,
+}
+
+/// Proxy configuration from the app settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// Proxy URL used in manual mode.
+    pub url: String,
+    /// Comma-separated hosts that bypass the manual proxy.
+    pub no_proxy: String,
+}
+
+impl ProxySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == ProxyMode::Manual {
+            let url = self.url.trim();
+            if url.is_empty() {
+                return Err("A proxy URL is required in manual mode".to_string());
+            }
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!("Proxy URL must start with http:// or https://: {}", url));
+            }
+        }
+        Ok(())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIGURED: Mutex<ProxySettings> = Mutex::new(ProxySettings::default());
+}
+
+/// Replace the proxy configuration from the app settings.
+pub fn set_proxy_settings(settings: ProxySettings) {
+    if let Ok(mut current) = CONFIGURED.lock() {
+        *current = settings;
+    }
+}
+
+fn configured() -> ProxySettings {
+    CONFIGURED.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Whether the settings ask for direct connections (no proxy at all).
+pub fn proxy_disabled() -> bool {
+    configured().mode == ProxyMode::Direct
+}
+
+/// Bypass list of the manual proxy from the settings, if one is configured.
+pub fn manual_no_proxy() -> Option<String> {
+    let settings = configured();
+    (settings.mode == ProxyMode::Manual && !settings.no_proxy.trim().is_empty())
+        .then(|| settings.no_proxy.trim().to_string())
+}
+
 /// Return proxy and networking environment variables to inject into child
 /// processes.
 ///
 /// Priority order:
-/// 1. Existing process env vars (user's shell may have set them)
-/// 2. OS-level proxy settings (Windows registry / macOS `scutil`)
+/// 1. Proxy configured in the app settings (manual or direct)
+/// 2. Existing process env vars (user's shell may have set them)
+/// 3. OS-level proxy settings (Windows registry / macOS `scutil`)
 pub fn get_proxy_env_vars() -> HashMap<String, String> {
     let mut vars = HashMap::new();
+    let settings = configured();
 
     // 1. Forward any networking env vars already present in the process environment.
     for &name in FORWARDED_ENV_VARS {
+        if settings.mode != ProxyMode::System && PROXY_ENV_VARS.contains(&name) {
+            continue;
+        }
         if let Ok(val) = std::env::var(name) {
             if !val.is_empty() {
                 vars.insert(name.to_string(), val);
@@ -47,6 +124,23 @@ pub fn get_proxy_env_vars() -> HashMap<String, String> {
         }
     }
 
+    match settings.mode {
+        ProxyMode::System => {}
+        // Empty values override anything the child would inherit
+        ProxyMode::Direct => {
+            vars.extend(PROXY_ENV_VARS.iter().map(|name| (name.to_string(), String::new())));
+            return vars;
+        }
+        ProxyMode::Manual => {
+            vars.extend(PROXY_ENV_VARS.iter().map(|name| (name.to_string(), String::new())));
+            let url = settings.url.trim().to_string();
+            vars.insert("HTTPS_PROXY".to_string(), url.clone());
+            vars.insert("HTTP_PROXY".to_string(), url);
+            vars.insert("NO_PROXY".to_string(), settings.no_proxy.trim().to_string());
+            return vars;
+        }
+    }
+
     // 2. If no proxy env vars were inherited, try OS-level detection.
     let has_proxy = vars.contains_key("HTTPS_PROXY")
         || vars.contains_key("https_proxy")
@@ -72,6 +166,13 @@ pub fn get_proxy_env_vars() -> HashMap<String, String> {
 
 /// Return the detected HTTPS proxy URL (if any), for configuring reqwest.
 pub fn get_https_proxy() -> Option<String> {
+    let settings = configured();
+    match settings.mode {
+        ProxyMode::Manual => return Some(settings.url.trim().to_string()),
+        ProxyMode::Direct => return None,
+        ProxyMode::System => {}
+    }
+
     // Check env vars first
     for name in &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"] {
         if let Ok(val) = std::env::var(name) {
@@ -298,4 +399,19 @@ mod tests {
         let vars = get_proxy_env_vars();
         assert!(vars.is_empty() || !vars.is_empty());
     }
+
+    #[test]
+    fn proxy_settings_validation() {
+        assert!(ProxySettings::default().validate().is_ok());
+        let mut manual = ProxySettings { mode: ProxyMode::Manual, ..Default::default() };
+        assert!(manual.validate().is_err());
+        manual.url = "proxy.corp:8080".to_string();
+        assert!(manual.validate().is_err());
+        manual.url = "http://proxy.corp:8080".to_string();
+        assert!(manual.validate().is_ok());
+
+        let parsed: ProxySettings = serde_json::from_str(r#"{"mode":"direct"}"#).unwrap();
+        assert_eq!(parsed.mode, ProxyMode::Direct);
+        assert!(parsed.url.is_empty());
+    }
 }
@@ -0,0 +1,121 @@
+//! On-disk storage locations, independent of a running Tauri app.
+//!
+//! The desktop app resolves these from its `AppHandle`; the headless CLI and
+//! tests use [`StoragePaths`], which defaults to the same per-user data
+//! directory so both see the same templates and deployments.
+
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Bundle identifier from `tauri.conf.json`; names the app-data directory.
+pub const APP_IDENTIFIER: &str = "com.databricks.deployer";
+
+/// Environment variable overriding the data directory for the CLI.
+pub const DATA_DIR_ENV: &str = "WORKSPACE_CREATOR_DATA_DIR";
+
+/// Where the app keeps its data. Business logic takes `&dyn Environment`
+/// instead of an `AppHandle`, so it runs unchanged in the desktop app, the
+/// headless CLI, and tests.
+pub trait Environment: Send + Sync {
+    /// Per-user data directory (settings, keys, templates, deployments).
+    fn data_dir(&self) -> Result<PathBuf, String>;
+
+    /// Bundled resource directory (packaged app only).
+    fn resource_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn templates_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.data_dir()?.join("templates"))
+    }
+
+    /// Deployments directory, created if missing.
+    fn deployments_dir(&self) -> Result<PathBuf, String> {
+        let deployments_dir = self.data_dir()?.join("deployments");
+        fs::create_dir_all(&deployments_dir).map_err(|e| e.to_string())?;
+        Ok(deployments_dir)
+    }
+
+    /// Per-deployment lock files, kept outside the deployment directories so
+    /// they never end up in bundles or repositories. Created if missing.
+    fn locks_dir(&self) -> Result<PathBuf, String> {
+        let locks_dir = self.data_dir()?.join("locks");
+        fs::create_dir_all(&locks_dir).map_err(|e| e.to_string())?;
+        Ok(locks_dir)
+    }
+
+    /// Path of a file directly in the data directory, creating the directory.
+    fn data_file(&self, name: &str) -> Result<PathBuf, String> {
+        let data_dir = self.data_dir()?;
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+        Ok(data_dir.join(name))
+    }
+}
+
+impl<R: Runtime> Environment for AppHandle<R> {
+    fn data_dir(&self) -> Result<PathBuf, String> {
+        self.path().app_data_dir().map_err(|e| e.to_string())
+    }
+
+    fn resource_dir(&self) -> Option<PathBuf> {
+        self.path().resource_dir().ok()
+    }
+}
+
+/// Storage rooted at an explicit directory, used by the CLI and tests.
+#[derive(Debug, Clone)]
+pub struct StoragePaths {
+    data_dir: PathBuf,
+}
+
+impl StoragePaths {
+    pub fn from_data_dir(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+        }
+    }
+
+    /// The data directory the desktop app would use for the current user,
+    /// unless overridden by `WORKSPACE_CREATOR_DATA_DIR`.
+    pub fn user_default() -> Result<Self, String> {
+        if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+            return Ok(Self::from_data_dir(dir));
+        }
+        dirs::data_dir()
+            .map(|d| Self::from_data_dir(d.join(APP_IDENTIFIER)))
+            .ok_or_else(|| "Could not determine the user data directory".to_string())
+    }
+}
+
+impl Environment for StoragePaths {
+    fn data_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.data_dir.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_rooted_at_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path());
+        assert_eq!(paths.templates_dir().unwrap(), dir.path().join("templates"));
+        let deployments = paths.deployments_dir().unwrap();
+        assert_eq!(deployments, dir.path().join("deployments"));
+        assert!(deployments.is_dir());
+        assert_eq!(paths.locks_dir().unwrap(), dir.path().join("locks"));
+        assert!(paths.resource_dir().is_none());
+    }
+
+    #[test]
+    fn data_file_creates_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = StoragePaths::from_data_dir(dir.path().join("nested"));
+        let file = paths.data_file("settings.json").unwrap();
+        assert_eq!(file, dir.path().join("nested").join("settings.json"));
+        assert!(dir.path().join("nested").is_dir());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::commands::debug_log;
+use crate::hcl_reader;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -15,6 +17,37 @@ pub struct TerraformVariable {
     pub required: bool,
     pub sensitive: bool,
     pub validation: Option<String>,
+    /// UI grouping/ordering metadata from `# @key: value` comments or `variables.meta.json`.
+    #[serde(default)]
+    pub meta: VariableMeta,
+}
+
+/// Presentation metadata for a template variable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariableMeta {
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub order: Option<i64>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub help_url: Option<String>,
+    #[serde(default)]
+    pub visible_when: Option<VisibilityRule>,
+    /// Dynamic dropdown data source (e.g. `aws_region`, `azure_resource_group`).
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Show a variable only when another variable has (or doesn't have) a value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisibilityRule {
+    pub variable: String,
+    #[serde(default)]
+    pub equals: Option<serde_json::Value>,
+    #[serde(default)]
+    pub not_equals: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +57,9 @@ pub struct DeploymentStatus {
     pub output: String,
     pub success: Option<bool>,
     pub can_rollback: bool,
+    /// Logins that may not last through the rest of the run.
+    #[serde(default)]
+    pub credential_warnings: Vec<String>,
 }
 
 impl Default for DeploymentStatus {
@@ -34,6 +70,7 @@ impl Default for DeploymentStatus {
             output: String::new(),
             success: None,
             can_rollback: false,
+            credential_warnings: Vec::new(),
         }
     }
 }
@@ -43,182 +80,201 @@ lazy_static::lazy_static! {
     pub static ref CURRENT_PROCESS: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
 }
 
-pub fn parse_variables_tf(content: &str) -> Vec<TerraformVariable> {
-    let mut variables = Vec::new();
-    let mut current_var: Option<TerraformVariable> = None;
-    let mut in_variable_block = false;
-    let mut brace_count = 0;
-    let mut current_description = String::new();
-    let mut current_type = String::from("string");
-    let mut current_default: Option<String> = None;
-    let mut is_sensitive = false;
-    let mut current_validation: Option<String> = None;
-    
-    // Track multiline default value parsing
-    let mut in_multiline_default = false;
-    let mut default_brace_count = 0;
-    let mut default_bracket_count = 0;
-    let mut multiline_default_buffer = String::new();
+/// Variables declared in a `variables.tf`. A file that is not valid HCL is
+/// an error, as Terraform would refuse it too.
+pub fn parse_variables_tf(content: &str) -> Result<Vec<TerraformVariable>, String> {
+    let body = hcl_reader::parse(content).map_err(|e| format!("Invalid variables.tf: {}", e))?;
+    Ok(body.blocks("variable").filter_map(variable_from_block).collect())
+}
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+fn variable_from_block(block: &hcl_reader::Block) -> Option<TerraformVariable> {
+    let [name] = block.labels.as_slice() else {
+        return None;
+    };
+    let attrs = &block.body;
+    let default = attrs.attribute("default").map(|e| e.string_or_text().to_string());
+
+    let mut meta = VariableMeta::default();
+    for comment in &block.comments {
+        apply_meta_comment(&mut meta, comment);
+    }
+
+    Some(TerraformVariable {
+        name: name.clone(),
+        description: attrs.attribute("description").map(|e| e.string_or_text().to_string()).unwrap_or_default(),
+        var_type: attrs.attribute("type").map_or_else(|| "string".to_string(), |e| e.text.clone()),
+        required: default.is_none(),
+        default,
+        sensitive: attrs.attribute("sensitive").is_some_and(|e| e.text == "true"),
+        validation: attrs
+            .blocks("validation")
+            .find_map(|v| v.body.attribute("condition"))
+            .map(|e| e.text.clone()),
+        meta,
+    })
+}
 
-        // Start of variable block
-        if !in_variable_block && trimmed.starts_with("variable ") && trimmed.contains('{') {
-            in_variable_block = true;
-            brace_count = 1;
-            
-            // Extract variable name
-            if let Some(name_start) = trimmed.find('"') {
-                if let Some(name_end) = trimmed[name_start + 1..].find('"') {
-                    let name = &trimmed[name_start + 1..name_start + 1 + name_end];
-                    current_var = Some(TerraformVariable {
-                        name: name.to_string(),
-                        description: String::new(),
-                        var_type: "string".to_string(),
-                        default: None,
-                        required: true,
-                        sensitive: false,
-                        validation: None,
-                    });
-                }
-            }
-            current_description.clear();
-            current_type = String::from("string");
-            current_default = None;
-            is_sensitive = false;
-            current_validation = None;
-            in_multiline_default = false;
-            default_brace_count = 0;
-            default_bracket_count = 0;
-            multiline_default_buffer.clear();
-            continue;
-        }
+/// An `output` block: something a deployment reports after apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerraformOutput {
+    pub name: String,
+    pub description: String,
+    pub sensitive: bool,
+}
 
-        if in_variable_block {
-            // Parse multiline default values (maps/lists) by tracking brace/bracket balance
-            if in_multiline_default {
-                multiline_default_buffer.push_str(trimmed);
-                multiline_default_buffer.push(' ');
-                
-                default_brace_count += trimmed.matches('{').count() as i32;
-                default_brace_count -= trimmed.matches('}').count() as i32;
-                default_bracket_count += trimmed.matches('[').count() as i32;
-                default_bracket_count -= trimmed.matches(']').count() as i32;
-                
-                // Check if multiline default is complete
-                if default_brace_count <= 0 && default_bracket_count <= 0 {
-                    in_multiline_default = false;
-                    // For complex defaults (maps/lists), just mark as having a default
-                    // We don't need to parse the actual value for the UI
-                    current_default = Some(multiline_default_buffer.trim().to_string());
-                }
-                
-                // Still count braces for the variable block
-                brace_count += trimmed.matches('{').count() as i32;
-                brace_count -= trimmed.matches('}').count() as i32;
-            } else {
-                // Count braces for variable block
-                brace_count += trimmed.matches('{').count() as i32;
-                brace_count -= trimmed.matches('}').count() as i32;
-
-                // Parse attributes (only at brace_count == 1, i.e., top level of variable)
-                if brace_count >= 1 {
-                    if trimmed.starts_with("description") {
-                        if let Some(val) = extract_string_value(trimmed) {
-                            current_description = val;
-                        }
-                    } else if trimmed.starts_with("type") {
-                        if let Some(val) = extract_type_value(trimmed) {
-                            current_type = val;
-                        }
-                    } else if trimmed.starts_with("default") {
-                        // Check if this is a multiline default
-                        let after_eq = trimmed.split_once('=').map(|(_, v)| v.trim()).unwrap_or("");
-                        
-                        if after_eq.starts_with('{') || after_eq.starts_with('[') {
-                            // Count opening/closing braces/brackets on this line
-                            let open_braces = after_eq.matches('{').count() as i32;
-                            let close_braces = after_eq.matches('}').count() as i32;
-                            let open_brackets = after_eq.matches('[').count() as i32;
-                            let close_brackets = after_eq.matches(']').count() as i32;
-                            
-                            if open_braces > close_braces || open_brackets > close_brackets {
-                                // Multiline default starts here
-                                in_multiline_default = true;
-                                default_brace_count = open_braces - close_braces;
-                                default_bracket_count = open_brackets - close_brackets;
-                                multiline_default_buffer = after_eq.to_string();
-                                multiline_default_buffer.push(' ');
-                            } else {
-                                // Single-line complex default
-                                current_default = Some(after_eq.to_string());
-                            }
-                        } else {
-                            // Simple default value
-                            current_default = extract_default_value(trimmed);
-                        }
-                    } else if trimmed.starts_with("sensitive") && trimmed.contains("true") {
-                        is_sensitive = true;
-                    } else if trimmed.starts_with("condition") {
-                        if let Some(val) = extract_string_value(line) {
-                            current_validation = Some(val);
-                        }
-                    }
-                }
-            }
+/// A `required_providers` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderRequirement {
+    /// Local name, e.g. `aws`.
+    pub name: String,
+    /// Registry address, e.g. `hashicorp/aws`.
+    pub source: Option<String>,
+    /// Version constraint, e.g. `>= 5.76, < 7.0`.
+    pub version: Option<String>,
+}
 
-            // End of variable block
-            if brace_count == 0 && !in_multiline_default {
-                if let Some(mut var) = current_var.take() {
-                    var.description = current_description.clone();
-                    var.var_type = current_type.clone();
-                    var.default = current_default.clone();
-                    var.required = current_default.is_none();
-                    var.sensitive = is_sensitive;
-                    var.validation = current_validation.clone();
-                    variables.push(var);
-                }
-                in_variable_block = false;
+/// Settings from `terraform { ... }` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerraformRequirements {
+    /// `required_version` constraint on the Terraform CLI.
+    pub required_version: Option<String>,
+    pub providers: Vec<ProviderRequirement>,
+}
+
+/// Outputs declared in a `.tf` file. Invalid HCL yields none.
+pub fn parse_outputs_tf(content: &str) -> Vec<TerraformOutput> {
+    let body = match hcl_reader::parse(content) {
+        Ok(body) => body,
+        Err(_e) => {
+            debug_log!("[terraform] Failed to parse outputs: {}", _e);
+            return Vec::new();
+        }
+    };
+    body.blocks("output")
+        .filter_map(|block| {
+            let [name] = block.labels.as_slice() else {
+                return None;
+            };
+            Some(TerraformOutput {
+                name: name.clone(),
+                description: block.body.attribute("description").map(|e| e.string_or_text().to_string()).unwrap_or_default(),
+                sensitive: block.body.attribute("sensitive").is_some_and(|e| e.text == "true"),
+            })
+        })
+        .collect()
+}
+
+/// Add the `terraform` block settings of a `.tf` file to `requirements`.
+/// Later files fill in what earlier ones left unset, and a provider declared
+/// twice keeps its first declaration.
+pub fn parse_terraform_requirements(content: &str, requirements: &mut TerraformRequirements) {
+    let body = match hcl_reader::parse(content) {
+        Ok(body) => body,
+        Err(_e) => {
+            debug_log!("[terraform] Failed to parse terraform block: {}", _e);
+            return;
+        }
+    };
+    for settings in body.blocks("terraform") {
+        if requirements.required_version.is_none() {
+            requirements.required_version = settings.body.attribute("required_version").map(|e| e.string_or_text().to_string());
+        }
+        for item in settings.body.blocks("required_providers").flat_map(|b| &b.body.items) {
+            let hcl_reader::Item::Attribute(attr) = item else {
+                continue;
+            };
+            if requirements.providers.iter().any(|p| p.name == attr.name) {
+                continue;
             }
+            // Pre-0.13 shorthand: `aws = "~> 5.0"`
+            let (source, version) = match &attr.expr.object {
+                Some(_) => (
+                    attr.expr.entry("source").map(|e| e.string_or_text().to_string()),
+                    attr.expr.entry("version").map(|e| e.string_or_text().to_string()),
+                ),
+                None => (None, Some(attr.expr.string_or_text().to_string())),
+            };
+            requirements.providers.push(ProviderRequirement { name: attr.name.clone(), source, version });
         }
     }
-
-    variables
 }
 
-fn extract_string_value(line: &str) -> Option<String> {
-    if let Some(start) = line.find('"') {
-        if let Some(end) = line[start + 1..].rfind('"') {
-            return Some(line[start + 1..start + 1 + end].to_string());
-        }
+// ─── Variable Metadata ──────────────────────────────────────────────────────
+
+/// Parse a `@visible_when` expression: `name == value` or `name != value`.
+/// Values are read as JSON (`true`, `3`, `"x"`) and fall back to a bare string.
+fn parse_visibility_rule(expr: &str) -> Option<VisibilityRule> {
+    let (variable, value, negated) = if let Some((l, r)) = expr.split_once("!=") {
+        (l, r, true)
+    } else {
+        let (l, r) = expr.split_once("==")?;
+        (l, r, false)
+    };
+    let variable = variable.trim();
+    if variable.is_empty() {
+        return None;
     }
-    None
+    let raw = value.trim();
+    let value = serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+    Some(VisibilityRule {
+        variable: variable.to_string(),
+        equals: if negated { None } else { Some(value.clone()) },
+        not_equals: if negated { Some(value) } else { None },
+    })
 }
 
-fn extract_type_value(line: &str) -> Option<String> {
-    let line = line.trim();
-    if let Some(idx) = line.find('=') {
-        let type_part = line[idx + 1..].trim();
-        return Some(type_part.to_string());
+/// Apply one `@key: value` comment (without the comment marker) to `meta`.
+fn apply_meta_comment(meta: &mut VariableMeta, comment: &str) {
+    let Some(rest) = comment.strip_prefix('@') else {
+        return;
+    };
+    let Some((key, value)) = rest.split_once(':') else {
+        return;
+    };
+    let value = value.trim();
+    match key.trim() {
+        "group" => meta.group = Some(value.to_string()),
+        "order" => meta.order = value.parse().ok(),
+        "label" => meta.label = Some(value.to_string()),
+        "help" | "help_url" => meta.help_url = Some(value.to_string()),
+        "visible_when" => meta.visible_when = parse_visibility_rule(value),
+        "source" => meta.source = Some(value.to_string()),
+        _ => {}
     }
-    None
 }
 
-fn extract_default_value(line: &str) -> Option<String> {
-    let line = line.trim();
-    if let Some(idx) = line.find('=') {
-        let value_part = line[idx + 1..].trim();
-        // Handle quoted strings
-        if value_part.starts_with('"') && value_part.ends_with('"') {
-            return Some(value_part[1..value_part.len() - 1].to_string());
-        }
-        // Handle other values
-        if !value_part.is_empty() && value_part != "{" && value_part != "[" {
-            return Some(value_part.to_string());
+/// Companion `variables.meta.json`: variable name → metadata.
+#[derive(Debug, Default, Deserialize)]
+struct VariablesMetaFile {
+    #[serde(default)]
+    variables: HashMap<String, VariableMeta>,
+}
+
+/// Merge a `variables.meta.json` document into parsed variables (JSON fields
+/// override comment annotations) and sort by `order`. Variables without an
+/// order keep their file order after the ordered ones.
+pub fn apply_variables_meta(variables: &mut [TerraformVariable], json: &str) -> Result<(), String> {
+    let file: VariablesMetaFile =
+        serde_json::from_str(json).map_err(|e| format!("Invalid variables.meta.json: {}", e))?;
+
+    for var in variables.iter_mut() {
+        if let Some(meta) = file.variables.get(&var.name) {
+            let meta = meta.clone();
+            var.meta.group = meta.group.or(var.meta.group.take());
+            var.meta.order = meta.order.or(var.meta.order);
+            var.meta.label = meta.label.or(var.meta.label.take());
+            var.meta.help_url = meta.help_url.or(var.meta.help_url.take());
+            var.meta.visible_when = meta.visible_when.or(var.meta.visible_when.take());
+            var.meta.source = meta.source.or(var.meta.source.take());
         }
     }
-    None
+    sort_variables_by_order(variables);
+    Ok(())
+}
+
+/// Stable sort by `meta.order`; unordered variables keep their relative position at the end.
+pub fn sort_variables_by_order(variables: &mut [TerraformVariable]) {
+    variables.sort_by_key(|v| v.meta.order.unwrap_or(i64::MAX));
 }
 
 pub fn generate_tfvars(values: &HashMap<String, serde_json::Value>, variables: &[TerraformVariable]) -> String {
@@ -396,37 +452,129 @@ fn format_object_fields(
     }
 }
 
-pub fn run_terraform(
+fn terraform_command(
     command: &str,
-    working_dir: &PathBuf,
-    env_vars: HashMap<String, String>,
-) -> Result<Child, String> {
-    let terraform_path = get_terraform_path();
+    working_dir: &Path,
+    env_vars: &HashMap<String, String>,
+    run_args: &[String],
+) -> Result<std::process::Command, String> {
+    let terraform_path = get_terraform_path(working_dir);
     
     let args: Vec<&str> = match command {
-        "init" => vec!["init", "-no-color"],
-        "plan" => vec!["plan", "-no-color"],
-        "apply" => vec!["apply", "-auto-approve", "-no-color"],
-        "destroy" => vec!["destroy", "-auto-approve", "-no-color"],
+        "init" => vec!["init", "-input=false", "-no-color"],
+        "plan" => vec!["plan", "-input=false", "-no-color"],
+        // Machine-readable preview of what destroy would remove
+        "plan-destroy" => vec!["plan", "-destroy", "-json", "-input=false", "-no-color"],
+        "apply" => vec!["apply", "-auto-approve", "-input=false", "-no-color"],
+        "destroy" => vec!["destroy", "-auto-approve", "-input=false", "-no-color"],
         _ => return Err(format!("Unknown command: {}", command)),
     };
 
+    // Per-deployment advanced settings; credentials and app-set variables win
+    let (extra_args, extra_env) = crate::commands::run_settings::run_overrides(working_dir, args[0]);
+    let extra_env: Vec<(String, String)> =
+        extra_env.into_iter().filter(|(key, _)| !env_vars.contains_key(key)).collect();
+
     let mut cmd = crate::commands::silent_cmd(&terraform_path);
-    cmd.args(&args)
-        .current_dir(working_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+    own_process_group(&mut cmd);
+    // Nothing can answer a prompt; a read from stdin fails instead of hanging
+    cmd.args(&args).args(&extra_args).args(run_args).current_dir(working_dir).stdin(Stdio::null());
+    let logging = env_vars.iter().chain(extra_env.iter().map(|(k, v)| (k, v)));
+    if crate::commands::debug_logs::log_requested(logging) && !env_vars.contains_key("TF_LOG_PATH") {
+        if let Some(path) = crate::commands::debug_logs::new_log_path(working_dir, args[0]) {
+            cmd.env("TF_LOG_PATH", path);
+        }
+    }
+    cmd.envs(extra_env);
+    apply_standard_env(&mut cmd, env_vars);
+    Ok(cmd)
+}
 
-    apply_standard_env(&mut cmd, &env_vars);
+pub fn run_terraform(
+    command: &str,
+    working_dir: &Path,
+    env_vars: HashMap<String, String>,
+) -> Result<Child, String> {
+    let mut cmd = terraform_command(command, working_dir, &env_vars, &[])?;
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    cmd.spawn().map_err(|e| e.to_string())
+}
 
+/// Like [`run_terraform`], but stdout and stderr go to `log_path` instead of
+/// pipes, so Terraform keeps running (and logging) if the app exits mid-run.
+/// `run_args` are one-shot arguments for this run, such as `-target=...`.
+pub fn run_terraform_logged(
+    command: &str,
+    working_dir: &Path,
+    env_vars: HashMap<String, String>,
+    run_args: &[String],
+    log_path: &Path,
+) -> Result<Child, String> {
+    let mut cmd = terraform_command(command, working_dir, &env_vars, run_args)?;
+    let log = fs::File::create(log_path).map_err(|e| format!("Failed to create run log: {}", e))?;
+    // Cloned handles share the file offset, so stdout and stderr interleave
+    let err_log = log.try_clone().map_err(|e| format!("Failed to create run log: {}", e))?;
+    cmd.stdout(Stdio::from(log)).stderr(Stdio::from(err_log));
     cmd.spawn().map_err(|e| e.to_string())
 }
 
-fn get_terraform_path() -> String {
-    // Reuse the path finding logic from dependencies module
-    crate::dependencies::find_terraform_path()
+// ─── Engine Selection ───────────────────────────────────────────────────────
+
+/// Infrastructure-as-code engine used to run a deployment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    #[default]
+    Terraform,
+    Tofu,
+}
+
+impl Engine {
+    pub fn binary_name(self) -> &'static str {
+        match self {
+            Engine::Terraform => "terraform",
+            Engine::Tofu => "tofu",
+        }
+    }
+
+    pub fn find_path(self) -> Option<PathBuf> {
+        match self {
+            Engine::Terraform => crate::dependencies::find_terraform_path(),
+            Engine::Tofu => crate::dependencies::find_tofu_path(),
+        }
+    }
+}
+
+/// Marker file in a deployment directory recording the selected engine.
+const ENGINE_FILE: &str = ".deployer-engine";
+
+/// Engine selected for a deployment. Deployments without a marker use Terraform.
+pub fn deployment_engine(working_dir: &Path) -> Engine {
+    match fs::read_to_string(working_dir.join(ENGINE_FILE)) {
+        Ok(content) if content.trim() == "tofu" => Engine::Tofu,
+        _ => Engine::Terraform,
+    }
+}
+
+/// Record the engine for a deployment.
+pub fn set_deployment_engine(working_dir: &Path, engine: Engine) -> Result<(), String> {
+    let marker = working_dir.join(ENGINE_FILE);
+    match engine {
+        Engine::Terraform if marker.exists() => {
+            fs::remove_file(&marker).map_err(|e| format!("Failed to update engine: {}", e))
+        }
+        Engine::Terraform => Ok(()),
+        Engine::Tofu => fs::write(&marker, "tofu\n").map_err(|e| format!("Failed to update engine: {}", e)),
+    }
+}
+
+/// Binary to run for the deployment in `working_dir`, honouring its engine.
+pub fn get_terraform_path(working_dir: &Path) -> String {
+    let engine = deployment_engine(working_dir);
+    engine
+        .find_path()
         .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_else(|| "terraform".to_string())
+        .unwrap_or_else(|| engine.binary_name().to_string())
 }
 
 // ─── Import-on-retry: detect "already exists" errors and auto-import ────────
@@ -561,6 +709,202 @@ pub fn parse_importable_errors(output: &str) -> Vec<ImportableResource> {
     results
 }
 
+// ─── Diagnostics ────────────────────────────────────────────────────────────
+
+/// A single `Error:` / `Warning:` block from Terraform output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerraformDiagnostic {
+    /// "error" or "warning".
+    pub severity: String,
+    pub summary: String,
+    /// Resource address from the `with <address>,` line, if present.
+    pub address: Option<String>,
+    /// Source location from the `on <file> line <n>` line, if present.
+    pub location: Option<String>,
+    pub detail: String,
+}
+
+/// Maximum detail length kept per diagnostic.
+const MAX_DIAGNOSTIC_DETAIL: usize = 1000;
+
+/// Parse Terraform output into diagnostics. Handles both `-no-color` output
+/// and the boxed (`│ `-prefixed) format.
+pub fn parse_diagnostics(output: &str) -> Vec<TerraformDiagnostic> {
+    lazy_static::lazy_static! {
+        static ref WITH_RE: Regex = Regex::new(r"^\s*with\s+([^,]+),").unwrap();
+        static ref ON_RE: Regex = Regex::new(r"^\s*on\s+(.+?\s+line\s+\d+)").unwrap();
+        static ref SOURCE_RE: Regex = Regex::new(r"^\s*\d+:\s").unwrap();
+    }
+
+    let lines: Vec<&str> = output
+        .lines()
+        .map(|l| l.trim_start_matches(['│', '╷', '╵']).trim_start_matches(' '))
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    let mut current: Option<TerraformDiagnostic> = None;
+
+    for line in lines {
+        let header = line
+            .strip_prefix("Error: ")
+            .map(|s| ("error", s))
+            .or_else(|| line.strip_prefix("Warning: ").map(|s| ("warning", s)));
+
+        if let Some((severity, summary)) = header {
+            if let Some(d) = current.take() {
+                diagnostics.push(d);
+            }
+            current = Some(TerraformDiagnostic {
+                severity: severity.to_string(),
+                summary: summary.trim().to_string(),
+                address: None,
+                location: None,
+                detail: String::new(),
+            });
+            continue;
+        }
+
+        let diag = match current.as_mut() {
+            Some(d) => d,
+            None => continue,
+        };
+
+        if diag.address.is_none() {
+            if let Some(caps) = WITH_RE.captures(line) {
+                diag.address = Some(caps[1].trim().to_string());
+                continue;
+            }
+        }
+        if diag.location.is_none() {
+            if let Some(caps) = ON_RE.captures(line) {
+                diag.location = Some(caps[1].trim().to_string());
+                continue;
+            }
+        }
+        if SOURCE_RE.is_match(line) || line.trim().is_empty() {
+            continue;
+        }
+        if diag.detail.len() < MAX_DIAGNOSTIC_DETAIL {
+            if !diag.detail.is_empty() {
+                diag.detail.push(' ');
+            }
+            diag.detail.push_str(line.trim());
+        }
+    }
+
+    if let Some(d) = current {
+        diagnostics.push(d);
+    }
+
+    for d in diagnostics.iter_mut() {
+        if d.detail.len() > MAX_DIAGNOSTIC_DETAIL {
+            let mut cut = MAX_DIAGNOSTIC_DETAIL;
+            while !d.detail.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            d.detail.truncate(cut);
+            d.detail.push('…');
+        }
+    }
+
+    diagnostics
+}
+
+// ─── Input Prompts ──────────────────────────────────────────────────────────
+
+/// Bytes at the end of a run log checked for a pending prompt.
+const PROMPT_TAIL_BYTES: u64 = 4096;
+
+/// Whether Terraform output ends at an unanswered prompt.
+fn awaiting_input(output: &str) -> bool {
+    output.trim_end().ends_with("Enter a value:")
+}
+
+/// Explain which input a run was missing, from a Terraform prompt or the
+/// error `-input=false` produces in its place. `None` when the output shows
+/// neither.
+pub fn input_diagnostic(output: &str) -> Option<String> {
+    lazy_static::lazy_static! {
+        static ref UNSET_RE: Regex = Regex::new(r#"The root module input variable "([^"]+)" is not set"#).unwrap();
+        static ref PROMPT_VAR_RE: Regex = Regex::new(r"^\s*var\.([A-Za-z0-9_-]+)\s*$").unwrap();
+    }
+
+    let mut missing: Vec<String> = Vec::new();
+    for caps in UNSET_RE.captures_iter(output) {
+        if !missing.contains(&caps[1].to_string()) {
+            missing.push(caps[1].to_string());
+        }
+    }
+    let prompt = awaiting_input(output);
+    if prompt && missing.is_empty() {
+        // `var.name` is printed above the variable's description and the prompt
+        let lines: Vec<&str> = output.lines().collect();
+        let start = lines.iter().rposition(|l| PROMPT_VAR_RE.is_match(l));
+        if let Some(caps) = start.and_then(|i| PROMPT_VAR_RE.captures(lines[i])) {
+            missing.push(caps[1].to_string());
+        }
+    }
+    if !missing.is_empty() {
+        let names = missing.iter().map(|n| format!("\"{}\"", n)).collect::<Vec<_>>().join(", ");
+        let (noun, verb) = if missing.len() == 1 { ("Variable", "has") } else { ("Variables", "have") };
+        return Some(format!(
+            "{} {} {} no value and no default. Set {} in the deployment's configuration and run again.",
+            noun,
+            names,
+            verb,
+            if missing.len() == 1 { "it" } else { "them" }
+        ));
+    }
+
+    if output.contains("Backend configuration changed")
+        || output.contains("Do you want to copy existing state")
+        || output.contains("Do you want to migrate all workspaces")
+    {
+        return Some(
+            "The backend configuration changed and Terraform asked whether to migrate the existing state. \
+             Run `terraform init -migrate-state` in the deployment folder to copy it, or `terraform init -reconfigure` to start from the new backend."
+                .to_string(),
+        );
+    }
+    if !prompt {
+        return None;
+    }
+    if output.contains("Do you want to perform these actions") || output.contains("Do you really want to destroy") {
+        return Some("Terraform asked to confirm the changes instead of applying them automatically.".to_string());
+    }
+    // Name the question: the last non-empty line above the prompt
+    let question = output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && *l != "Enter a value:")
+        .unwrap_or("");
+    Some(format!("Terraform stopped to ask for input that can't be given here: {}", question))
+}
+
+/// The end of a run log, if it stops at a prompt.
+fn pending_prompt(log_path: &Path) -> Option<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(log_path).ok()?;
+    let size = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(size.saturating_sub(PROMPT_TAIL_BYTES))).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    let tail = String::from_utf8_lossy(&bytes).to_string();
+    awaiting_input(&tail).then_some(tail)
+}
+
+/// Append the missing-input explanation for a failed run to its output.
+fn report_missing_input(status: &Arc<Mutex<DeploymentStatus>>, output: Option<&str>) {
+    let Ok(mut s) = status.lock() else {
+        return;
+    };
+    if let Some(diagnostic) = input_diagnostic(output.unwrap_or(&s.output)) {
+        s.output.push_str(&format!("\nError: Missing input\n\n{}\n", diagnostic));
+    }
+}
+
 /// Run `terraform import` for a single resource and wait for completion.
 pub fn run_terraform_import(
     address: &str,
@@ -568,7 +912,7 @@ pub fn run_terraform_import(
     working_dir: &Path,
     env_vars: &HashMap<String, String>,
 ) -> Result<String, String> {
-    let terraform_path = get_terraform_path();
+    let terraform_path = get_terraform_path(working_dir);
 
     let mut cmd = crate::commands::silent_cmd(&terraform_path);
     cmd.args(["import", "-no-color", "-input=false", address, id])
@@ -597,6 +941,89 @@ pub fn run_terraform_import(
     }
 }
 
+/// Run `terraform state rm` for `addresses`, so Terraform forgets them
+/// without touching the real resources.
+pub fn run_state_rm(
+    addresses: &[String],
+    working_dir: &Path,
+    env_vars: &HashMap<String, String>,
+) -> Result<String, String> {
+    let terraform_path = get_terraform_path(working_dir);
+
+    let mut cmd = crate::commands::silent_cmd(&terraform_path);
+    cmd.args(["state", "rm", "-no-color"])
+        .args(addresses)
+        .current_dir(working_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    apply_standard_env(&mut cmd, env_vars);
+
+    let output = cmd.output().map_err(|e| format!("Failed to run terraform state rm: {}", e))?;
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if output.status.success() {
+        Ok(combined)
+    } else {
+        Err(combined)
+    }
+}
+
+/// Run `terraform state pull`, returning the state from the configured
+/// backend (local or remote).
+pub fn run_state_pull(working_dir: &Path, env_vars: &HashMap<String, String>) -> Result<String, String> {
+    let terraform_path = get_terraform_path(working_dir);
+
+    let mut cmd = crate::commands::silent_cmd(&terraform_path);
+    cmd.args(["state", "pull"])
+        .current_dir(working_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    apply_standard_env(&mut cmd, env_vars);
+
+    let output = cmd.output().map_err(|e| format!("Failed to run terraform state pull: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+/// Run `terraform providers lock` for `platforms` (`os_arch`), recording
+/// provider checksums for each in `.terraform.lock.hcl`.
+pub fn run_providers_lock(
+    platforms: &[String],
+    working_dir: &Path,
+    env_vars: &HashMap<String, String>,
+) -> Result<String, String> {
+    let terraform_path = get_terraform_path(working_dir);
+
+    let mut cmd = crate::commands::silent_cmd(&terraform_path);
+    cmd.args(["providers", "lock", "-no-color"])
+        .args(platforms.iter().map(|p| format!("-platform={}", p)))
+        .current_dir(working_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    apply_standard_env(&mut cmd, env_vars);
+
+    let output = cmd.output().map_err(|e| format!("Failed to run terraform providers lock: {}", e))?;
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if output.status.success() {
+        Ok(combined)
+    } else {
+        Err(combined)
+    }
+}
+
 /// Look up the NCC ID from Terraform state (for `create_hub = true` case).
 ///
 /// Runs `terraform state list` to find the NCC resource, then
@@ -605,7 +1032,7 @@ pub fn get_ncc_id_from_state(
     working_dir: &Path,
     env_vars: &HashMap<String, String>,
 ) -> Option<String> {
-    let terraform_path = get_terraform_path();
+    let terraform_path = get_terraform_path(working_dir);
 
     // Step 1: list state entries and find the NCC resource
     let mut list_cmd = crate::commands::silent_cmd(&terraform_path);
@@ -685,7 +1112,7 @@ pub fn resolve_azure_role_assignment_id(
     working_dir: &Path,
     env_vars: &HashMap<String, String>,
 ) -> Option<String> {
-    let terraform_path = get_terraform_path();
+    let terraform_path = get_terraform_path(working_dir);
 
     // Step 1: get planned values from Terraform state/plan
     let mut show_cmd = crate::commands::silent_cmd(&terraform_path);
@@ -972,7 +1399,7 @@ pub fn apply_import_blocks(
         import_path.display()
     ));
 
-    let terraform_path = get_terraform_path();
+    let terraform_path = get_terraform_path(working_dir);
 
     let mut args = vec![
         "apply".to_string(),
@@ -1147,65 +1574,181 @@ pub fn run_import_batch(
     all_ok
 }
 
-/// Stream stdout + stderr from a Terraform child process into a shared output
-/// buffer, wait for the child to exit, and return whether it succeeded.
+// ─── Process Control ────────────────────────────────────────────────────────
+
+/// How long [`kill_process_tree`] waits for Terraform to stop before
+/// killing what is left of its process group.
+#[cfg(unix)]
+const GRACEFUL_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Start the command in a process group of its own (Unix), so the provider
+/// plugins Terraform starts can be found and stopped with it.
+fn own_process_group(cmd: &mut std::process::Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Stop a Terraform run and the provider plugins it started.
+///
+/// On Windows the whole process tree is ended with `taskkill /T`. On Unix
+/// Terraform gets SIGTERM so it can stop gracefully and release the state
+/// lock; once it has exited (or after [`GRACEFUL_STOP_TIMEOUT`]) anything
+/// left in its process group is killed.
+pub fn kill_process_tree(pid: u32) -> Result<(), String> {
+    let pid_str = pid.to_string();
+
+    #[cfg(unix)]
+    {
+        crate::commands::silent_cmd("kill")
+            .args(["-TERM", &pid_str])
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        std::thread::spawn(move || {
+            let alive = || {
+                crate::commands::silent_cmd("kill")
+                    .args(["-0", &pid_str])
+                    .output()
+                    .map(|o| o.status.success())
+                    .unwrap_or(false)
+            };
+            let started = std::time::Instant::now();
+            while alive() && started.elapsed() < GRACEFUL_STOP_TIMEOUT {
+                std::thread::sleep(LOG_POLL_INTERVAL);
+            }
+            // Terraform is the group leader (see `own_process_group`)
+            let _ = crate::commands::silent_cmd("kill")
+                .args(["-KILL", "--", &format!("-{}", pid_str)])
+                .output();
+        });
+    }
+
+    #[cfg(windows)]
+    {
+        crate::commands::silent_cmd("taskkill")
+            .args(["/F", "/T", "/PID", &pid_str])
+            .output()
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Copy complete lines appended to `log_path` into `status` until `finished()`
+/// returns true, then drain what's left. Used for runs started with
+/// [`run_terraform_logged`], including ones from a previous app session.
+pub fn follow_log(log_path: &Path, status: &Arc<Mutex<DeploymentStatus>>, finished: &mut dyn FnMut() -> bool) {
+    use std::io::Read;
+
+    let append = |bytes: &[u8]| {
+        if let Ok(mut s) = status.lock() {
+            s.output.push_str(&String::from_utf8_lossy(bytes));
+        }
+    };
+    let Ok(mut file) = fs::File::open(log_path) else {
+        while !finished() {
+            std::thread::sleep(LOG_POLL_INTERVAL);
+        }
+        return;
+    };
+    let mut pending: Vec<u8> = Vec::new();
+    let mut buf = [0u8; 8192];
+    let mut done = false;
+    loop {
+        let n = file.read(&mut buf).unwrap_or(0);
+        if n > 0 {
+            pending.extend_from_slice(&buf[..n]);
+            // Only whole lines, so multi-byte characters aren't split
+            if let Some(end) = pending.iter().rposition(|b| *b == b'\n') {
+                append(&pending.drain(..=end).collect::<Vec<_>>());
+            }
+            continue;
+        }
+        if done {
+            break;
+        }
+        done = finished();
+        if !done {
+            std::thread::sleep(LOG_POLL_INTERVAL);
+        }
+    }
+    if !pending.is_empty() {
+        pending.push(b'\n');
+        append(&pending);
+    }
+}
+
+/// How often [`follow_log`] checks for new output.
+const LOG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Follow a child started with [`run_terraform_logged`] until it exits,
+/// copying its output into `status`, and return whether it succeeded.
 ///
 /// `set_pid` is called with the child PID so the caller can track it for
-/// cancellation. `append_output` is called for each line of output.
-pub fn stream_and_wait(
+/// cancellation.
+pub fn follow_and_wait(
     child: &mut Child,
-    append_output: Arc<Mutex<DeploymentStatus>>,
+    log_path: &Path,
+    status: Arc<Mutex<DeploymentStatus>>,
     set_pid: &dyn Fn(u32),
 ) -> Result<bool, String> {
     set_pid(child.id());
 
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-
-    let out_status = append_output.clone();
-    let err_status = append_output.clone();
-
-    let h1 = stdout.map(|out| {
-        std::thread::spawn(move || {
-            let reader = std::io::BufReader::new(out);
-            for line in std::io::BufRead::lines(reader).flatten() {
-                if let Ok(mut s) = out_status.lock() {
-                    s.output.push_str(&line);
-                    s.output.push('\n');
-                }
-            }
-        })
-    });
-
-    let h2 = stderr.map(|err| {
-        std::thread::spawn(move || {
-            let reader = std::io::BufReader::new(err);
-            for line in std::io::BufRead::lines(reader).flatten() {
-                if let Ok(mut s) = err_status.lock() {
-                    s.output.push_str(&line);
-                    s.output.push('\n');
+    let mut exit = None;
+    let mut prompt = None;
+    follow_log(log_path, &status, &mut || match child.try_wait() {
+        Ok(None) => {
+            // A prompt nobody can answer: stop instead of waiting forever,
+            // along with any provider plugins Terraform started
+            if prompt.is_none() {
+                prompt = pending_prompt(log_path);
+                if prompt.is_some() {
+                    let _ = kill_process_tree(child.id());
                 }
             }
-        })
+            false
+        }
+        result => {
+            exit = Some(result);
+            true
+        }
     });
-
-    if let Some(h) = h1 { let _ = h.join(); }
-    if let Some(h) = h2 { let _ = h.join(); }
-
-    child.wait()
-        .map(|exit| exit.success())
-        .map_err(|e| format!("Error waiting for terraform: {}", e))
+    let success = match exit {
+        Some(Ok(Some(status))) => status.success(),
+        Some(Err(e)) => return Err(format!("Error waiting for terraform: {}", e)),
+        _ => child
+            .wait()
+            .map(|exit| exit.success())
+            .map_err(|e| format!("Error waiting for terraform: {}", e))?,
+    };
+    if prompt.is_some() || !success {
+        report_missing_input(&status, prompt.as_deref());
+        return Ok(false);
+    }
+    Ok(true)
 }
 
 /// After an `apply` failure, auto-import "already exists" resources and
 /// retry `apply` up to `MAX_RETRIES` times.
 ///
+/// Each retry runs like the first apply: with `run_args`, its output logged
+/// to `log_path` and followed into `status`, and its PID stored in `process`
+/// (for cancellation) and passed to `on_start`.
+///
 /// Returns `(success, can_rollback)`.
 pub fn import_and_retry_apply(
     working_dir: &Path,
     env_vars: &HashMap<String, String>,
+    run_args: &[String],
+    log_path: &Path,
     status: Arc<Mutex<DeploymentStatus>>,
     process: Arc<Mutex<Option<u32>>>,
+    on_start: &dyn Fn(u32),
 ) -> (bool, bool) {
     const MAX_RETRIES: usize = 3;
 
@@ -1249,7 +1792,7 @@ pub fn import_and_retry_apply(
             ));
         }
 
-        let mut retry_child = match run_terraform("apply", &working_dir.to_path_buf(), env_vars.clone()) {
+        let mut retry_child = match run_terraform_logged("apply", working_dir, env_vars.clone(), run_args, log_path) {
             Ok(child) => child,
             Err(e) => {
                 log_to_status(&format!("\nFailed to start retry: {}\n", e));
@@ -1265,9 +1808,10 @@ pub fn import_and_retry_apply(
             if let Ok(mut proc) = process.lock() {
                 *proc = Some(pid);
             }
+            on_start(pid);
         };
 
-        let success = match stream_and_wait(&mut retry_child, status.clone(), &set_pid) {
+        let success = match follow_and_wait(&mut retry_child, log_path, status.clone(), &set_pid) {
             Ok(s) => s,
             Err(e) => {
                 log_to_status(&format!("\nRetry error: {}\n", e));
@@ -1316,7 +1860,8 @@ pub fn check_state_exists(working_dir: &PathBuf) -> bool {
             return content.contains("\"resources\"") && content.contains("\"type\"");
         }
     }
-    false
+    // State encrypted at rest records its resource count in plaintext
+    crate::commands::state_encryption::encrypted_resource_count(working_dir).is_some_and(|n| n > 0)
 }
 
 #[cfg(test)]
@@ -1335,7 +1880,7 @@ variable "region" {
   default     = "us-east-1"
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert_eq!(vars[0].name, "region");
         assert_eq!(vars[0].description, "The AWS region");
@@ -1351,7 +1896,7 @@ variable "name" {
   type        = string
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert!(vars[0].required);
         assert!(vars[0].default.is_none());
@@ -1366,7 +1911,7 @@ variable "db_password" {
   sensitive   = true
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert!(vars[0].sensitive);
     }
@@ -1380,7 +1925,7 @@ variable "enable_logging" {
   default     = true
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert_eq!(vars[0].default.as_deref(), Some("true"));
     }
@@ -1405,7 +1950,7 @@ variable "count" {
   default     = 1
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 3);
         assert_eq!(vars[0].name, "region");
         assert_eq!(vars[1].name, "instance_type");
@@ -1424,7 +1969,7 @@ variable "tags" {
   }
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert_eq!(vars[0].name, "tags");
         assert!(!vars[0].required);
@@ -1443,15 +1988,125 @@ variable "subnets" {
   ]
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert_eq!(vars.len(), 1);
         assert!(!vars[0].required);
         assert!(vars[0].default.is_some());
     }
 
+    #[test]
+    fn parse_heredoc_and_nested_object_type() {
+        let tf = r#"
+variable "network" {
+  description = <<-EOT
+    Network settings.
+    Leave `cidr` empty to skip.
+  EOT
+  type = object({
+    cidr    = string # e.g. "10.0.0.0/16" }
+    subnets = optional(list(object({
+      name = string
+    })), [])
+  })
+  default = {
+    cidr = "10.0.0.0/16" // the default range
+    /* no subnets
+       by default */
+    subnets = []
+  }
+}
+"#;
+        let vars = parse_variables_tf(tf).unwrap();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[0].description, "Network settings.\nLeave `cidr` empty to skip.\n");
+        assert_eq!(
+            vars[0].var_type,
+            "object({ cidr = string subnets = optional(list(object({ name = string })), []) })"
+        );
+        assert_eq!(vars[0].default.as_deref(), Some("{ cidr = \"10.0.0.0/16\" subnets = [] }"));
+    }
+
+    #[test]
+    fn parse_comments_and_braces_inside_blocks() {
+        let tf = r#"
+variable "prefix" {
+  # default = "commented-out" {
+  description = "Prefix for {names}, e.g. \"dbx\""
+  // sensitive = true
+  type = string
+
+  validation {
+    condition     = can(regex("^[a-z]{1,8}$", var.prefix))
+    error_message = "Use 1-8 lowercase letters; see ${var.docs}."
+  }
+}
+
+variable "single_line" { type = number }
+"#;
+        let vars = parse_variables_tf(tf).unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars[0].description, "Prefix for {names}, e.g. \"dbx\"");
+        assert!(vars[0].required && !vars[0].sensitive);
+        assert_eq!(vars[0].validation.as_deref(), Some(r#"can(regex("^[a-z]{1,8}$", var.prefix))"#));
+        assert_eq!((vars[1].name.as_str(), vars[1].var_type.as_str()), ("single_line", "number"));
+    }
+
+    #[test]
+    fn parse_invalid_hcl_is_an_error() {
+        let err = parse_variables_tf("variable \"a\" {\n  type = string\n").unwrap_err();
+        assert!(err.starts_with("Invalid variables.tf"), "{}", err);
+    }
+
+    #[test]
+    fn parse_outputs_and_requirements() {
+        let outputs = parse_outputs_tf(
+            r#"
+output "workspace_url" {
+  description = "Workspace URL"
+  value       = databricks_mws_workspaces.this.workspace_url
+}
+output "token" {
+  value     = databricks_token.pat.token_value
+  sensitive = true
+}
+"#,
+        );
+        assert_eq!(outputs.len(), 2);
+        assert_eq!((outputs[0].name.as_str(), outputs[0].description.as_str()), ("workspace_url", "Workspace URL"));
+        assert!(!outputs[0].sensitive && outputs[1].sensitive);
+
+        let mut requirements = TerraformRequirements::default();
+        parse_terraform_requirements(
+            r#"
+terraform {
+  required_version = "~> 1.3"
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = ">= 5.76, < 7.0"
+    }
+    random = "~> 3.0"
+  }
+}
+"#,
+            &mut requirements,
+        );
+        parse_terraform_requirements(
+            "terraform {\n  required_version = \">= 1.9\"\n  required_providers {\n    aws = { source = \"other/aws\" }\n    time = { source = \"hashicorp/time\" }\n  }\n}\n",
+            &mut requirements,
+        );
+        assert_eq!(requirements.required_version.as_deref(), Some("~> 1.3"));
+        let names: Vec<&str> = requirements.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["aws", "random", "time"]);
+        assert_eq!(requirements.providers[0].source.as_deref(), Some("hashicorp/aws"));
+        assert_eq!(requirements.providers[0].version.as_deref(), Some(">= 5.76, < 7.0"));
+        assert_eq!((requirements.providers[1].source.as_deref(), requirements.providers[1].version.as_deref()), (None, Some("~> 3.0")));
+        assert_eq!(requirements.providers[2].version, None);
+    }
+
     #[test]
     fn parse_empty_content() {
-        let vars = parse_variables_tf("");
+        let vars = parse_variables_tf("").unwrap();
         assert!(vars.is_empty());
     }
 
@@ -1463,7 +2118,7 @@ resource "aws_instance" "web" {
   instance_type = "t2.micro"
 }
 "#;
-        let vars = parse_variables_tf(tf);
+        let vars = parse_variables_tf(tf).unwrap();
         assert!(vars.is_empty());
     }
 
@@ -1479,6 +2134,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("region".to_string(), serde_json::json!("us-east-1"));
@@ -1496,6 +2152,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("enabled".to_string(), serde_json::json!(true));
@@ -1513,6 +2170,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("count".to_string(), serde_json::json!(42));
@@ -1530,6 +2188,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("zones".to_string(), serde_json::json!(["us-east-1a", "us-east-1b"]));
@@ -1547,6 +2206,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         let mut map = serde_json::Map::new();
@@ -1567,6 +2227,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("tags".to_string(), serde_json::Value::Object(serde_json::Map::new()));
@@ -1584,6 +2245,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("flag".to_string(), serde_json::json!("true"));
@@ -1601,6 +2263,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("name".to_string(), serde_json::json!(""));
@@ -1618,6 +2281,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let values = HashMap::new();
         let result = generate_tfvars(&values, &vars);
@@ -1635,6 +2299,7 @@ resource "aws_instance" "web" {
                 required: true,
                 sensitive: false,
                 validation: None,
+                meta: VariableMeta::default(),
             },
             TerraformVariable {
                 name: "count".to_string(),
@@ -1644,6 +2309,7 @@ resource "aws_instance" "web" {
                 required: true,
                 sensitive: false,
                 validation: None,
+                meta: VariableMeta::default(),
             },
         ];
         let mut values = HashMap::new();
@@ -1664,6 +2330,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("tags".to_string(), serde_json::json!("{\"env\":\"prod\"}"));
@@ -1682,6 +2349,7 @@ resource "aws_instance" "web" {
             required: true,
             sensitive: false,
             validation: None,
+            meta: VariableMeta::default(),
         }];
         let mut values = HashMap::new();
         values.insert("zones".to_string(), serde_json::json!("[\"a\",\"b\"]"));
@@ -2050,6 +2718,44 @@ Error: an association between "/subscriptions/x/subnets/snet-container" and "/su
         assert_eq!(sibling_groups[0].len(), 2);
     }
 
+    // ── parse_diagnostics ───────────────────────────────────────────────
+
+    #[test]
+    fn parse_diagnostics_no_color_error() {
+        let output = r#"
+Error: creating Workspace: InvalidParameter: The subnet is in use
+
+  with azurerm_databricks_workspace.this,
+  on main.tf line 12, in resource "azurerm_databricks_workspace" "this":
+  12: resource "azurerm_databricks_workspace" "this" {
+
+The subnet must be delegated to Microsoft.Databricks/workspaces.
+"#;
+        let diags = parse_diagnostics(output);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, "error");
+        assert_eq!(diags[0].summary, "creating Workspace: InvalidParameter: The subnet is in use");
+        assert_eq!(diags[0].address.as_deref(), Some("azurerm_databricks_workspace.this"));
+        assert_eq!(diags[0].location.as_deref(), Some("main.tf line 12"));
+        assert_eq!(diags[0].detail, "The subnet must be delegated to Microsoft.Databricks/workspaces.");
+    }
+
+    #[test]
+    fn parse_diagnostics_boxed_format_and_warnings() {
+        let output = "╷\n│ Warning: Argument is deprecated\n│ \n│ Use foo instead.\n╵\n╷\n│ Error: Missing required argument\n╵\n";
+        let diags = parse_diagnostics(output);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, "warning");
+        assert_eq!(diags[0].detail, "Use foo instead.");
+        assert_eq!(diags[1].severity, "error");
+        assert_eq!(diags[1].summary, "Missing required argument");
+    }
+
+    #[test]
+    fn parse_diagnostics_ignores_plain_output() {
+        assert!(parse_diagnostics("Apply complete! Resources: 3 added, 0 changed, 0 destroyed.").is_empty());
+    }
+
     // ── resolve_import_pair: AzureRoleAssignment ────────────────────────
 
     #[test]
@@ -2370,5 +3076,214 @@ Error: an association between "/subscriptions/x/subnets/snet-container" and "/su
         let dir = tempfile::tempdir().unwrap();
         cleanup_import_file(dir.path());
     }
+
+    // ── engine selection ────────────────────────────────────────────────
+
+    #[test]
+    fn deployment_engine_defaults_to_terraform() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(deployment_engine(dir.path()), Engine::Terraform);
+    }
+
+    #[test]
+    fn set_deployment_engine_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        set_deployment_engine(dir.path(), Engine::Tofu).unwrap();
+        assert_eq!(deployment_engine(dir.path()), Engine::Tofu);
+        set_deployment_engine(dir.path(), Engine::Terraform).unwrap();
+        assert_eq!(deployment_engine(dir.path()), Engine::Terraform);
+        assert!(!dir.path().join(ENGINE_FILE).exists());
+    }
+
+    #[test]
+    fn engine_serializes_lowercase() {
+        assert_eq!(serde_json::to_string(&Engine::Tofu).unwrap(), "\"tofu\"");
+        assert_eq!(serde_json::from_str::<Engine>("\"terraform\"").unwrap(), Engine::Terraform);
+    }
+
+    // ── variable metadata ───────────────────────────────────────────────
+
+    #[test]
+    fn parse_variables_reads_meta_comments() {
+        let content = r#"
+# @group: Networking
+# @order: 2
+# @label: VPC CIDR
+# @help: https://docs.databricks.com/vpc
+# @visible_when: create_vpc == true
+# @source: aws_vpc
+variable "cidr_block" {
+  type = string
+}
+
+# Plain comment, no metadata
+variable "create_vpc" {
+  type    = bool
+  default = true
 }
+"#;
+        let vars = parse_variables_tf(content).unwrap();
+        assert_eq!(vars[0].meta.group.as_deref(), Some("Networking"));
+        assert_eq!(vars[0].meta.order, Some(2));
+        assert_eq!(vars[0].meta.label.as_deref(), Some("VPC CIDR"));
+        assert_eq!(vars[0].meta.help_url.as_deref(), Some("https://docs.databricks.com/vpc"));
+        assert_eq!(vars[0].meta.source.as_deref(), Some("aws_vpc"));
+        assert_eq!(
+            vars[0].meta.visible_when,
+            Some(VisibilityRule {
+                variable: "create_vpc".to_string(),
+                equals: Some(serde_json::json!(true)),
+                not_equals: None,
+            })
+        );
+        assert_eq!(vars[1].meta, VariableMeta::default());
+    }
 
+    #[test]
+    fn meta_comments_reset_by_other_blocks() {
+        let content = r#"
+# @group: Orphaned
+locals {
+  x = 1
+}
+variable "a" {
+  type = string
+}
+"#;
+        let vars = parse_variables_tf(content).unwrap();
+        assert_eq!(vars[0].meta.group, None);
+    }
+
+    #[test]
+    fn visibility_rule_not_equals_bare_string() {
+        let rule = parse_visibility_rule("network_mode != default").unwrap();
+        assert_eq!(rule.variable, "network_mode");
+        assert_eq!(rule.not_equals, Some(serde_json::json!("default")));
+        assert!(rule.equals.is_none());
+        assert!(parse_visibility_rule("no operator").is_none());
+    }
+
+    #[test]
+    fn apply_variables_meta_overrides_and_sorts() {
+        let content = r#"
+# @group: General
+variable "a" {
+  type = string
+}
+variable "b" {
+  type = string
+}
+variable "c" {
+  type = string
+}
+"#;
+        let mut vars = parse_variables_tf(content).unwrap();
+        let json = r#"{"variables": {"c": {"order": 1, "group": "Advanced"}, "a": {"order": 2, "label": "A"}}}"#;
+        apply_variables_meta(&mut vars, json).unwrap();
+
+        let names: Vec<&str> = vars.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["c", "a", "b"]);
+        assert_eq!(vars[0].meta.group.as_deref(), Some("Advanced"));
+        assert_eq!(vars[1].meta.group.as_deref(), Some("General"));
+        assert_eq!(vars[1].meta.label.as_deref(), Some("A"));
+        assert!(apply_variables_meta(&mut vars, "not json").is_err());
+    }
+
+    // ── input prompts ───────────────────────────────────────────────────
+
+    #[test]
+    fn input_diagnostic_names_missing_variables() {
+        let unset = "Error: No value for required variable\n\n  on variables.tf line 1:\n   1: variable \"prefix\" {\n\n\
+                     The root module input variable \"prefix\" is not set, and has no default value.\n";
+        let diag = input_diagnostic(unset).unwrap();
+        assert!(diag.starts_with("Variable \"prefix\" has no value"), "{}", diag);
+
+        let prompt = "var.admin_user\n  Account admin e-mail\n\n  Enter a value: ";
+        assert!(input_diagnostic(prompt).unwrap().contains("\"admin_user\""));
+
+        let backend = "Initializing the backend...\nDo you want to copy existing state to the new backend?\n  Enter a value: ";
+        assert!(input_diagnostic(backend).unwrap().contains("-migrate-state"));
+
+        let other = "Provider needs a token\n  Enter a value: ";
+        assert!(input_diagnostic(other).unwrap().ends_with("Provider needs a token"));
+
+        assert_eq!(input_diagnostic("Error: Invalid provider configuration\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_and_wait_stops_at_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("run.log");
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "printf 'var.region\\n  Enter a value: '; sleep 30"])
+            .stdout(Stdio::from(fs::File::create(&log).unwrap()))
+            .spawn()
+            .unwrap();
+        let status = Arc::new(Mutex::new(DeploymentStatus::default()));
+
+        let started = std::time::Instant::now();
+        assert!(!follow_and_wait(&mut child, &log, status.clone(), &|_| {}).unwrap());
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        let output = status.lock().unwrap().output.clone();
+        assert!(output.contains("Error: Missing input"), "{}", output);
+        assert!(output.contains("\"region\""), "{}", output);
+    }
+
+    // ── process control ─────────────────────────────────────────────────
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kill_process_tree_stops_child_processes() {
+        use std::time::{Duration, Instant};
+
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("child.pid");
+        // Stands in for Terraform with a provider plugin running under it
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", &format!("sleep 60 & echo $! > '{}'; wait", pid_file.display())]);
+        own_process_group(&mut cmd);
+        let mut parent = cmd.spawn().unwrap();
+
+        let started = Instant::now();
+        let child_pid = loop {
+            if let Some(pid) = fs::read_to_string(&pid_file).ok().filter(|s| s.ends_with('\n')) {
+                break pid.trim().to_string();
+            }
+            assert!(started.elapsed() < Duration::from_secs(10), "child never started");
+            std::thread::sleep(Duration::from_millis(50));
+        };
+
+        kill_process_tree(parent.id()).unwrap();
+        assert!(!parent.wait().unwrap().success());
+
+        // Zombies left for a non-reaping init count as stopped
+        let alive = || {
+            fs::read_to_string(format!("/proc/{}/stat", child_pid))
+                .map(|stat| !stat.contains(") Z"))
+                .unwrap_or(false)
+        };
+        let started = Instant::now();
+        while alive() {
+            assert!(started.elapsed() < Duration::from_secs(10), "child process survived");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    // ── follow_log ──────────────────────────────────────────────────────
+
+    #[test]
+    fn follow_log_drains_after_finish() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("run.log");
+        fs::write(&log, "Creating...\nApply complete!").unwrap();
+        let status = Arc::new(Mutex::new(DeploymentStatus::default()));
+
+        let mut polls = 0;
+        follow_log(&log, &status, &mut || {
+            polls += 1;
+            polls > 1
+        });
+        assert_eq!(status.lock().unwrap().output, "Creating...\nApply complete!\n");
+    }
+}
@@ -19,7 +19,12 @@ data "aws_iam_policy_document" "assume_role_policy" {
   }
 }
 
+locals {
+  reuse_credentials = var.existing_credentials_id != ""
+}
+
 resource "aws_iam_role" "cross_account_role" {
+  count              = local.reuse_credentials ? 0 : 1
   name               = "${var.prefix}-crossaccount"
   assume_role_policy = data.aws_iam_policy_document.assume_role_policy.json
   tags               = var.tags
@@ -31,13 +36,31 @@ data "databricks_aws_crossaccount_policy" "this" {
 }
 
 resource "aws_iam_role_policy" "this" {
+  count  = local.reuse_credentials ? 0 : 1
   name   = "${var.prefix}-policy"
-  role   = aws_iam_role.cross_account_role.id
+  role   = aws_iam_role.cross_account_role[0].id
   policy = data.databricks_aws_crossaccount_policy.this.json
 }
 
 # Wait for IAM role to propagate before Databricks validates it
 resource "time_sleep" "iam_propagation" {
+  count           = local.reuse_credentials ? 0 : 1
   depends_on      = [aws_iam_role_policy.this]
   create_duration = "60s"
 }
+
+# Keep state addresses from before these resources became conditional
+moved {
+  from = aws_iam_role.cross_account_role
+  to   = aws_iam_role.cross_account_role[0]
+}
+
+moved {
+  from = aws_iam_role_policy.this
+  to   = aws_iam_role_policy.this[0]
+}
+
+moved {
+  from = time_sleep.iam_propagation
+  to   = time_sleep.iam_propagation[0]
+}
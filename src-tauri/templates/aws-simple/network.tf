@@ -1,5 +1,7 @@
 locals {
-  create_vpc = var.create_new_vpc
+  # A reused Databricks network configuration already points at its own VPC
+  reuse_network = var.existing_network_id != ""
+  create_vpc    = var.create_new_vpc && !local.reuse_network
 }
 
 module "vpc" {
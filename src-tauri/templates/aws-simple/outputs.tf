@@ -8,19 +8,24 @@ output "workspace_id" {
   value       = databricks_mws_workspaces.this.workspace_id
 }
 
+output "network_id" {
+  description = "Databricks network configuration ID used by the workspace"
+  value       = local.network_id
+}
+
 output "vpc_id" {
   description = "VPC ID"
   value       = local.vpc_id
 }
 
 output "root_s3_bucket" {
-  description = "Root storage S3 bucket"
-  value       = aws_s3_bucket.root_storage_bucket.bucket
+  description = "Root storage S3 bucket (null when reusing a storage configuration)"
+  value       = local.reuse_storage ? null : aws_s3_bucket.root_storage_bucket[0].bucket
 }
 
 output "cross_account_role_arn" {
-  description = "Cross-account IAM role ARN"
-  value       = aws_iam_role.cross_account_role.arn
+  description = "Cross-account IAM role ARN (null when reusing a credential configuration)"
+  value       = local.reuse_credentials ? null : aws_iam_role.cross_account_role[0].arn
 }
 
 output "metastore_id" {
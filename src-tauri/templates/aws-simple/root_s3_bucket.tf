@@ -1,22 +1,46 @@
+locals {
+  reuse_storage = var.existing_storage_configuration_id != ""
+}
+
 resource "random_string" "bucket_suffix" {
+  count   = local.reuse_storage ? 0 : 1
   length  = 8
   special = false
   upper   = false
 }
 
 resource "aws_s3_bucket" "root_storage_bucket" {
-  bucket        = "${var.prefix}-root-${random_string.bucket_suffix.result}"
+  count         = local.reuse_storage ? 0 : 1
+  bucket        = "${var.prefix}-root-${random_string.bucket_suffix[0].result}"
   force_destroy = true
   tags          = merge(var.tags, { Name = "${var.prefix}-root-storage" })
 }
 
 data "databricks_aws_bucket_policy" "this" {
+  count                    = local.reuse_storage ? 0 : 1
   provider                 = databricks.mws
   databricks_e2_account_id = var.databricks_account_id
-  bucket                   = aws_s3_bucket.root_storage_bucket.bucket
+  bucket                   = aws_s3_bucket.root_storage_bucket[0].bucket
 }
 
 resource "aws_s3_bucket_policy" "root_bucket_policy" {
-  bucket = aws_s3_bucket.root_storage_bucket.id
-  policy = data.databricks_aws_bucket_policy.this.json
+  count  = local.reuse_storage ? 0 : 1
+  bucket = aws_s3_bucket.root_storage_bucket[0].id
+  policy = data.databricks_aws_bucket_policy.this[0].json
+}
+
+# Keep state addresses from before these resources became conditional
+moved {
+  from = random_string.bucket_suffix
+  to   = random_string.bucket_suffix[0]
+}
+
+moved {
+  from = aws_s3_bucket.root_storage_bucket
+  to   = aws_s3_bucket.root_storage_bucket[0]
+}
+
+moved {
+  from = aws_s3_bucket_policy.root_bucket_policy
+  to   = aws_s3_bucket_policy.root_bucket_policy[0]
 }
@@ -78,6 +78,25 @@ variable "existing_security_group_id" {
   default     = ""
 }
 
+# Existing Databricks account configurations (leave empty to create new ones)
+variable "existing_network_id" {
+  description = "ID of an existing Databricks network configuration to reuse. Skips VPC and network creation."
+  type        = string
+  default     = ""
+}
+
+variable "existing_storage_configuration_id" {
+  description = "ID of an existing Databricks storage configuration to reuse. Skips root bucket creation."
+  type        = string
+  default     = ""
+}
+
+variable "existing_credentials_id" {
+  description = "ID of an existing Databricks credential configuration to reuse. Skips cross-account role creation."
+  type        = string
+  default     = ""
+}
+
 # Unity Catalog (leave empty to auto-detect or create new metastore)
 variable "existing_metastore_id" {
   description = "The ID of an existing metastore to use. Leave empty to auto-detect or create a new one."
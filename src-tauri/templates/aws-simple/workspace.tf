@@ -1,18 +1,21 @@
 resource "databricks_mws_storage_configurations" "this" {
+  count                      = local.reuse_storage ? 0 : 1
   provider                   = databricks.mws
   account_id                 = var.databricks_account_id
   storage_configuration_name = "${var.prefix}-storage"
-  bucket_name                = aws_s3_bucket.root_storage_bucket.bucket
+  bucket_name                = aws_s3_bucket.root_storage_bucket[0].bucket
 }
 
 resource "databricks_mws_credentials" "this" {
+  count            = local.reuse_credentials ? 0 : 1
   provider         = databricks.mws
-  role_arn         = aws_iam_role.cross_account_role.arn
+  role_arn         = aws_iam_role.cross_account_role[0].arn
   credentials_name = "${var.prefix}-creds"
   depends_on       = [time_sleep.iam_propagation]
 }
 
 resource "databricks_mws_networks" "this" {
+  count              = local.reuse_network ? 0 : 1
   provider           = databricks.mws
   account_id         = var.databricks_account_id
   network_name       = "${var.prefix}-network"
@@ -21,14 +24,37 @@ resource "databricks_mws_networks" "this" {
   vpc_id             = local.vpc_id
 }
 
+# Reused account configurations (set via existing_*_id) take precedence
+locals {
+  credentials_id           = local.reuse_credentials ? var.existing_credentials_id : databricks_mws_credentials.this[0].credentials_id
+  storage_configuration_id = local.reuse_storage ? var.existing_storage_configuration_id : databricks_mws_storage_configurations.this[0].storage_configuration_id
+  network_id               = local.reuse_network ? var.existing_network_id : databricks_mws_networks.this[0].network_id
+}
+
 resource "databricks_mws_workspaces" "this" {
   provider                 = databricks.mws
   account_id               = var.databricks_account_id
   aws_region               = var.region
   workspace_name           = var.prefix
-  credentials_id           = databricks_mws_credentials.this.credentials_id
-  storage_configuration_id = databricks_mws_storage_configurations.this.storage_configuration_id
-  network_id               = databricks_mws_networks.this.network_id
+  credentials_id           = local.credentials_id
+  storage_configuration_id = local.storage_configuration_id
+  network_id               = local.network_id
+  custom_tags              = var.tags
+}
+
+moved {
+  from = databricks_mws_storage_configurations.this
+  to   = databricks_mws_storage_configurations.this[0]
+}
+
+moved {
+  from = databricks_mws_credentials.this
+  to   = databricks_mws_credentials.this[0]
+}
+
+moved {
+  from = databricks_mws_networks.this
+  to   = databricks_mws_networks.this[0]
 }
 
 # Assign admin access to the workspace
@@ -2,9 +2,19 @@
 
 locals {
   cmk_admin_value = var.cmk_admin_arn == null ? "arn:${local.computed_aws_partition}:iam::${local.aws_account_id}:root" : var.cmk_admin_arn
+
+  # Existing keys (existing_*_key_arn) are used as-is; their policies must already grant Databricks access
+  create_workspace_storage_key = var.existing_workspace_storage_key_arn == null
+  create_managed_services_key  = var.existing_managed_services_key_arn == null
+
+  workspace_storage_key_arn   = local.create_workspace_storage_key ? aws_kms_key.workspace_storage[0].arn : var.existing_workspace_storage_key_arn
+  managed_services_key_arn    = local.create_managed_services_key ? aws_kms_key.managed_services[0].arn : var.existing_managed_services_key_arn
+  workspace_storage_key_alias = local.create_workspace_storage_key ? aws_kms_alias.workspace_storage_key_alias[0].name : null
+  managed_services_key_alias  = local.create_managed_services_key ? aws_kms_alias.managed_services_key_alias[0].name : null
 }
 
 resource "aws_kms_key" "workspace_storage" {
+  count       = local.create_workspace_storage_key ? 1 : 0
   description = "KMS key for databricks workspace storage"
   policy = jsonencode({
     Version : "2012-10-17",
@@ -70,13 +80,15 @@ resource "aws_kms_key" "workspace_storage" {
 
 
 resource "aws_kms_alias" "workspace_storage_key_alias" {
+  count         = local.create_workspace_storage_key ? 1 : 0
   name          = "alias/${var.resource_prefix}-workspace-storage-key"
-  target_key_id = aws_kms_key.workspace_storage.id
+  target_key_id = aws_kms_key.workspace_storage[0].id
 }
 
 # CMK for Managed Services
 
 resource "aws_kms_key" "managed_services" {
+  count       = local.create_managed_services_key ? 1 : 0
   description = "KMS key for managed services"
   policy = jsonencode({ Version : "2012-10-17",
     "Id" : "key-policy-managed-services",
@@ -118,6 +130,28 @@ resource "aws_kms_key" "managed_services" {
 }
 
 resource "aws_kms_alias" "managed_services_key_alias" {
+  count         = local.create_managed_services_key ? 1 : 0
   name          = "alias/${var.resource_prefix}-managed-services-key"
-  target_key_id = aws_kms_key.managed_services.key_id
+  target_key_id = aws_kms_key.managed_services[0].key_id
+}
+
+# Keep state addresses from before these resources became conditional
+moved {
+  from = aws_kms_key.workspace_storage
+  to   = aws_kms_key.workspace_storage[0]
+}
+
+moved {
+  from = aws_kms_alias.workspace_storage_key_alias
+  to   = aws_kms_alias.workspace_storage_key_alias[0]
+}
+
+moved {
+  from = aws_kms_key.managed_services
+  to   = aws_kms_key.managed_services[0]
+}
+
+moved {
+  from = aws_kms_alias.managed_services_key_alias
+  to   = aws_kms_alias.managed_services_key_alias[0]
 }
\ No newline at end of file
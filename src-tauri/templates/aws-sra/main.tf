@@ -49,6 +49,7 @@ module "databricks_mws_workspace" {
   resource_prefix       = var.resource_prefix
   region                = var.region
   deployment_name       = var.deployment_name
+  tags                  = var.tags
 
   # Network Configuration
   vpc_id             = coalesce(var.custom_vpc_id, try(module.vpc[0].vpc_id, null))
@@ -57,6 +58,10 @@ module "databricks_mws_workspace" {
   backend_rest       = coalesce(var.custom_workspace_vpce_id, try(aws_vpc_endpoint.backend_rest[0].id, null))
   backend_relay      = coalesce(var.custom_relay_vpce_id, try(aws_vpc_endpoint.backend_relay[0].id, null))
 
+  # Front-end PrivateLink
+  frontend_vpce_id      = var.frontend_vpce_id
+  public_access_enabled = var.public_access_enabled
+
   # Cross-Account Role
   cross_account_role_arn = aws_iam_role.cross_account_role.arn
 
@@ -64,10 +69,10 @@ module "databricks_mws_workspace" {
   bucket_name = aws_s3_bucket.root_storage_bucket.id
 
   # KMS Keys
-  managed_services_key        = aws_kms_key.managed_services.arn
-  workspace_storage_key       = aws_kms_key.workspace_storage.arn
-  managed_services_key_alias  = aws_kms_alias.managed_services_key_alias.name
-  workspace_storage_key_alias = aws_kms_alias.workspace_storage_key_alias.name
+  managed_services_key        = local.managed_services_key_arn
+  workspace_storage_key       = local.workspace_storage_key_arn
+  managed_services_key_alias  = local.managed_services_key_alias
+  workspace_storage_key_alias = local.workspace_storage_key_alias
 
   # Network Connectivity Configuration and Network Policy
   network_connectivity_configuration_id = module.network_connectivity_configuration.ncc_id
@@ -74,12 +74,26 @@ resource "databricks_mws_customer_managed_keys" "workspace_storage" {
   use_cases = ["STORAGE"]
 }
 
+# Front-end REST VPC Endpoint Configuration (optional, usually in a transit VPC)
+resource "databricks_mws_vpc_endpoint" "frontend_rest" {
+  count               = var.frontend_vpce_id != null ? 1 : 0
+  account_id          = var.databricks_account_id
+  aws_vpc_endpoint_id = var.frontend_vpce_id
+  vpc_endpoint_name   = "${var.resource_prefix}-vpce-frontend"
+  region              = var.region
+}
+
 # Private Access Setting Configuration
+# With a front-end endpoint, only the registered endpoints may reach the workspace privately
 resource "databricks_mws_private_access_settings" "pas" {
   private_access_settings_name = "${var.resource_prefix}-PAS"
   region                       = var.region
-  public_access_enabled        = true
-  private_access_level         = "ACCOUNT"
+  public_access_enabled        = var.public_access_enabled
+  private_access_level         = var.frontend_vpce_id != null ? "ENDPOINT" : "ACCOUNT"
+  allowed_vpc_endpoint_ids = var.frontend_vpce_id != null ? [
+    databricks_mws_vpc_endpoint.frontend_rest[0].vpc_endpoint_id,
+    databricks_mws_vpc_endpoint.backend_rest.vpc_endpoint_id,
+  ] : null
 }
 
 # Workspace Configuration with Deployment Name
@@ -95,6 +109,7 @@ resource "databricks_mws_workspaces" "workspace" {
   managed_services_customer_managed_key_id = databricks_mws_customer_managed_keys.managed_services.customer_managed_key_id
   storage_customer_managed_key_id          = databricks_mws_customer_managed_keys.workspace_storage.customer_managed_key_id
   pricing_tier                             = "ENTERPRISE"
+  custom_tags                              = var.tags
 
   depends_on = [databricks_mws_networks.this]
 }
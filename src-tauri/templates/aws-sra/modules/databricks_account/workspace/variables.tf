@@ -30,6 +30,12 @@ variable "deployment_name" {
   nullable    = true
 }
 
+variable "frontend_vpce_id" {
+  description = "ID of the front-end PrivateLink interface endpoint, or null for back-end PrivateLink only."
+  type        = string
+  default     = null
+}
+
 variable "managed_services_key" {
   description = "CMK for managed services."
   type        = string
@@ -50,6 +56,12 @@ variable "network_connectivity_configuration_id" {
   type        = string
 }
 
+variable "public_access_enabled" {
+  description = "Whether the workspace accepts requests from the public internet."
+  type        = bool
+  default     = true
+}
+
 variable "region" {
   description = "AWS region code."
   type        = string
@@ -70,6 +82,12 @@ variable "subnet_ids" {
   type        = list(string)
 }
 
+variable "tags" {
+  description = "Custom tags applied to workspace cloud resources."
+  type        = map(string)
+  default     = {}
+}
+
 variable "vpc_id" {
   description = "VPC ID"
   type        = string
@@ -20,9 +20,9 @@ terraform {
 provider "aws" {
   region = var.region
   default_tags {
-    tags = {
+    tags = merge(var.tags, {
       Resource = var.resource_prefix
-    }
+    })
   }
 }
 
@@ -22,7 +22,7 @@ resource "aws_s3_bucket_server_side_encryption_configuration" "root_storage_buck
     bucket_key_enabled = true
     apply_server_side_encryption_by_default {
       sse_algorithm     = "aws:kms"
-      kms_master_key_id = aws_kms_key.workspace_storage.arn
+      kms_master_key_id = local.workspace_storage_key_arn
     }
   }
   depends_on = [aws_kms_alias.workspace_storage_key_alias]
@@ -64,30 +64,40 @@ variable "compliance_standards" {
   nullable    = true
 }
 
+# @group: Network
+# @visible_when: network_configuration == custom
 variable "custom_private_subnet_ids" {
   description = "List of custom private subnet IDs"
   type        = list(string)
   default     = null
 }
 
+# @group: Private connectivity
+# @visible_when: network_configuration == custom
 variable "custom_relay_vpce_id" {
   description = "Custom Relay VPC Endpoint ID"
   type        = string
   default     = null
 }
 
+# @group: Network
+# @visible_when: network_configuration == custom
 variable "custom_sg_id" {
   description = "Custom security group ID"
   type        = string
   default     = null
 }
 
+# @group: Network
+# @visible_when: network_configuration == custom
 variable "custom_vpc_id" {
   description = "Custom VPC ID"
   type        = string
   default     = null
 }
 
+# @group: Private connectivity
+# @visible_when: network_configuration == custom
 variable "custom_workspace_vpce_id" {
   description = "Custom Workspace VPC Endpoint ID"
   type        = string
@@ -148,6 +158,27 @@ variable "enable_security_analysis_tool" {
   default     = false
 }
 
+# @group: Encryption
+variable "existing_managed_services_key_arn" {
+  description = "ARN of an existing KMS key for managed services encryption. Leave empty to create one."
+  type        = string
+  default     = null
+}
+
+# @group: Encryption
+variable "existing_workspace_storage_key_arn" {
+  description = "ARN of an existing KMS key for workspace storage encryption. Leave empty to create one."
+  type        = string
+  default     = null
+}
+
+# @group: Private connectivity
+variable "frontend_vpce_id" {
+  description = "AWS VPC endpoint ID (usually in a transit VPC) for front-end PrivateLink to the workspace UI and REST API. Leave empty for back-end PrivateLink only."
+  type        = string
+  default     = null
+}
+
 variable "metastore_exists" {
   description = "If a metastore exists"
   type        = bool
@@ -192,6 +223,8 @@ variable "network_configuration" {
   }
 }
 
+# @group: Network
+# @visible_when: network_configuration == isolated
 variable "private_subnets_cidr" {
   description = "CIDR blocks for private subnets."
   type        = list(string)
@@ -199,6 +232,8 @@ variable "private_subnets_cidr" {
   default     = [null]
 }
 
+# @group: Private connectivity
+# @visible_when: network_configuration == isolated
 variable "privatelink_subnets_cidr" {
   description = "CIDR blocks for private link subnets."
   type        = list(string)
@@ -206,6 +241,13 @@ variable "privatelink_subnets_cidr" {
   default     = [null]
 }
 
+# @group: Private connectivity
+variable "public_access_enabled" {
+  description = "Allow workspace UI/API access over the public internet. Set to false with frontend_vpce_id for front-end PrivateLink only access."
+  type        = bool
+  default     = true
+}
+
 variable "region" {
   description = "AWS region code. (e.g. us-east-1)"
   type        = string
@@ -552,6 +594,12 @@ variable "log_storage_bucket_config" {
   }
 }
 
+variable "tags" {
+  description = "Tags to apply to all AWS resources and the workspace. Merged with Resource = resource_prefix."
+  type        = map(string)
+  default     = {}
+}
+
 variable "vpc_cidr_range" {
   description = "CIDR range for the VPC."
   type        = string
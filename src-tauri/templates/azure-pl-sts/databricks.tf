@@ -126,7 +126,7 @@ resource "azurerm_databricks_workspace" "dp_workspace" {
   location                       = local.dp_rg_location
   sku                            = "premium"
   tags                           = local.tags
-  public_network_access_enabled  = true
+  public_network_access_enabled  = var.public_network_access_enabled
   network_security_group_rules_required = "NoAzureDatabricksRules"
   customer_managed_key_enabled   = true
   # Named MRG (e.g. mrg-dbw-ts-privatelink-test-dp). Changing this forces workspace replacement.
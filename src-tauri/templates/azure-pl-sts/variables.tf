@@ -83,11 +83,19 @@ variable "subnet_workspace_cidrs" {
   }
 }
 
+# @group: Private connectivity
 variable "subnet_private_endpoint_cidr" {
   description = "CIDR for the Private Link subnet (control plane and DBFS private endpoints). Must be within the VNet (cidr_dp). Example: 10.0.2.0/26."
   type        = string
 }
 
+# @group: Private connectivity
+variable "public_network_access_enabled" {
+  description = "Allow workspace UI/API access over the public internet. Set to false for front-end Private Link only; Terraform must then run from a network that resolves the workspace through the private endpoint."
+  type        = bool
+  default     = true
+}
+
 variable "subnets_service_endpoints" {
   description = "List of Azure service endpoints to associate with the public and private subnets (e.g. [\"Microsoft.Storage\"])"
   type        = list(string)
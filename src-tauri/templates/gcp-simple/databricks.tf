@@ -2,18 +2,24 @@
 # Databricks BYO VPC Network Configuration
 ######################################################
 resource "databricks_mws_networks" "databricks_network" {
+  count        = local.reuse_network ? 0 : 1
   provider     = databricks.accounts
   account_id   = var.databricks_account_id
   network_name = "dbx-nwt-${random_string.databricks_suffix.result}"
 
   gcp_network_info {
     network_project_id = var.google_project_name
-    vpc_id             = google_compute_network.databricks_vpc.name
-    subnet_id          = google_compute_subnetwork.databricks_subnet.name
+    vpc_id             = google_compute_network.databricks_vpc[0].name
+    subnet_id          = google_compute_subnetwork.databricks_subnet[0].name
     subnet_region      = var.google_region
   }
 }
 
+moved {
+  from = databricks_mws_networks.databricks_network
+  to   = databricks_mws_networks.databricks_network[0]
+}
+
 ######################################################
 # Databricks Workspace
 ######################################################
@@ -29,7 +35,7 @@ resource "databricks_mws_workspaces" "databricks_workspace" {
     }
   }
 
-  network_id = databricks_mws_networks.databricks_network.network_id
+  network_id = local.reuse_network ? var.existing_network_id : databricks_mws_networks.databricks_network[0].network_id
 }
 
 ######################################################
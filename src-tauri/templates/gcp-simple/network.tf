@@ -10,8 +10,14 @@ resource "random_string" "databricks_suffix" {
 
 ######################################################
 # Google VPC, Subnet, Router, NAT
+# Skipped when reusing an existing Databricks network configuration
 ######################################################
+locals {
+  reuse_network = var.existing_network_id != ""
+}
+
 resource "google_compute_network" "databricks_vpc" {
+  count                   = local.reuse_network ? 0 : 1
   project                 = var.google_project_name
   name                    = "databricks-vpc-${random_string.databricks_suffix.result}"
   auto_create_subnetworks = false
@@ -79,24 +85,46 @@ resource "google_compute_network" "databricks_vpc" {
 }
 
 resource "google_compute_subnetwork" "databricks_subnet" {
+  count         = local.reuse_network ? 0 : 1
   name          = "databricks-subnet-${random_string.databricks_suffix.result}"
   ip_cidr_range = var.subnet_cidr
   region        = var.google_region
-  network       = google_compute_network.databricks_vpc.id
+  network       = google_compute_network.databricks_vpc[0].id
 }
 
 resource "google_compute_router" "databricks_router" {
+  count   = local.reuse_network ? 0 : 1
   name    = "databricks-router-${random_string.databricks_suffix.result}"
   region  = var.google_region
-  network = google_compute_network.databricks_vpc.id
+  network = google_compute_network.databricks_vpc[0].id
 }
 
 resource "google_compute_router_nat" "databricks_nat" {
+  count                              = local.reuse_network ? 0 : 1
   name                               = "databricks-nat-${random_string.databricks_suffix.result}"
-  router                             = google_compute_router.databricks_router.name
+  router                             = google_compute_router.databricks_router[0].name
   region                             = var.google_region
   nat_ip_allocate_option             = "AUTO_ONLY"
   source_subnetwork_ip_ranges_to_nat = "ALL_SUBNETWORKS_ALL_IP_RANGES"
 }
 
+# Keep state addresses from before these resources became conditional
+moved {
+  from = google_compute_network.databricks_vpc
+  to   = google_compute_network.databricks_vpc[0]
+}
+
+moved {
+  from = google_compute_subnetwork.databricks_subnet
+  to   = google_compute_subnetwork.databricks_subnet[0]
+}
+
+moved {
+  from = google_compute_router.databricks_router
+  to   = google_compute_router.databricks_router[0]
+}
 
+moved {
+  from = google_compute_router_nat.databricks_nat
+  to   = google_compute_router_nat.databricks_nat[0]
+}
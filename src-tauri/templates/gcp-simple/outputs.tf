@@ -23,13 +23,13 @@ output "gcp_region" {
 }
 
 output "vpc_name" {
-  description = "Name of the VPC created for Databricks"
-  value       = google_compute_network.databricks_vpc.name
+  description = "Name of the VPC created for Databricks (null when reusing a network configuration)"
+  value       = local.reuse_network ? null : google_compute_network.databricks_vpc[0].name
 }
 
 output "subnet_name" {
-  description = "Name of the subnet created for Databricks"
-  value       = google_compute_subnetwork.databricks_subnet.name
+  description = "Name of the subnet created for Databricks (null when reusing a network configuration)"
+  value       = local.reuse_network ? null : google_compute_subnetwork.databricks_subnet[0].name
 }
 
 # Metastore outputs
@@ -54,3 +54,7 @@ output "catalog_storage_bucket" {
   value       = local.create_uc ? google_storage_bucket.uc_catalog[0].name : null
 }
 
+output "network_id" {
+  description = "Databricks network configuration ID used by the workspace"
+  value       = databricks_mws_workspaces.databricks_workspace.network_id
+}
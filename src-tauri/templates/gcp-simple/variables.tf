@@ -97,6 +97,12 @@ variable "subnet_cidr" {
   default     = "10.0.0.0/20"
 }
 
+variable "existing_network_id" {
+  description = "ID of an existing Databricks network configuration to reuse. Leave empty to create a new VPC and network configuration."
+  type        = string
+  default     = ""
+}
+
 # =============================================================================
 # Unity Catalog Configuration
 # =============================================================================
@@ -7,6 +7,7 @@ module "customer_managed_vpc" {
   databricks_account_id            = var.databricks_account_id
   databricks_google_service_account = var.databricks_google_service_account
   workspace_name                   = var.workspace_name
+  tags                             = var.tags
 
   # Network
   nodes_ip_cidr_range              = var.nodes_ip_cidr_range
@@ -39,6 +40,7 @@ module "customer_managed_vpc" {
   key_name                         = var.key_name
   keyring_name                     = var.keyring_name
   use_existing_cmek                = var.use_existing_cmek
+  kms_key_id                       = var.kms_key_id
   cmek_resource_id                 = var.cmek_resource_id
 
   # Access Control
@@ -1,8 +1,12 @@
 
+locals {
+  create_kms_key = !var.use_existing_cmek && var.kms_key_id == ""
+}
+
 # create key ring
 resource "google_kms_key_ring" "databricks_key_ring" {
     provider = google
-    count = var.use_existing_cmek ? 0 : 1
+    count = local.create_kms_key ? 1 : 0
     name     = "${var.keyring_name}-${random_string.suffix.result}"
     location = var.google_region
 }
@@ -10,7 +14,7 @@ resource "google_kms_key_ring" "databricks_key_ring" {
 # create key used for encryption
 resource "google_kms_crypto_key" "databricks_key" {
   provider = google
-  count = var.use_existing_cmek ? 0 : 1
+  count = local.create_kms_key ? 1 : 0
   name       = "${var.key_name}-${random_string.suffix.result}"
   key_ring   = google_kms_key_ring.databricks_key_ring[0].id
   purpose    = "ENCRYPT_DECRYPT"
@@ -26,7 +30,7 @@ resource "databricks_mws_customer_managed_keys" "this" {
         account_id   = var.databricks_account_id
         gcp_key_info {
             # kms_key_id   = var.use_existing_cmek? "projects/${var.google_project}/locations/${var.google_region}/keyRings/${var.keyring_name}-${random_string.suffix.result}/cryptoKeys/${var.key_name}-${random_string.suffix.result}": google_kms_crypto_key.databricks_key[0].id
-            kms_key_id   = var.cmek_resource_id
+            kms_key_id   = var.kms_key_id != "" ? var.kms_key_id : google_kms_crypto_key.databricks_key[0].id
         }
         use_cases = ["STORAGE","MANAGED","MANAGED_SERVICES"]
         lifecycle {
@@ -23,6 +23,9 @@ provider "google" {
   
   # Impersonate the service account created by the service_account module
   impersonate_service_account = var.databricks_google_service_account != "" ? var.databricks_google_service_account : null
+
+  # Labels applied to every resource that supports them
+  default_labels = var.tags
   
   # # Add explicit scopes
   # scopes = [
@@ -24,6 +24,11 @@ variable "workspace_name" {
     # Name you want to give to the Databricks workspace you are creating
     default = "sra-deployed-ws"
 }
+variable "tags" {
+    # Labels applied to all GCP resources (provider default_labels)
+    type    = map(string)
+    default = {}
+}
 resource "random_string" "suffix" { 
     # Random string generator for suffix used in resource names.
     special = false
@@ -136,6 +141,10 @@ variable "cmek_resource_id" {
     # Resource ID for CMEK. only needed if use_existing_cmek is true
     default = ""
 }
+variable "kms_key_id" {
+    # Existing Cloud KMS key to register. only used if use_existing_cmek is false; empty creates a new key
+    default = ""
+}
 
 variable "use_psc" {
     # Flag to use Private Service Connect (PSC) for the workspace
@@ -41,6 +41,12 @@ variable "workspace_name" {
   default     = "my-databricks-workspace"
 }
 
+variable "tags" {
+  description = "A map of labels to assign to GCP resources"
+  type        = map(string)
+  default     = {}
+}
+
 # =============================================================================
 # Network Configuration
 # =============================================================================
@@ -85,78 +91,103 @@ variable "control_plane_ips" {
 # Private Service Connect (PSC) Configuration
 # =============================================================================
 
+# @group: Private connectivity
 variable "use_psc" {
   description = "Use Private Service Connect (PSC) for the workspace"
   type        = bool
   default     = false
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "google_pe_subnet" {
   description = "Name of the subnet for PSC endpoints"
   type        = string
   default     = "databricks-pe-subnet"
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "google_pe_subnet_ip_cidr_range" {
   description = "CIDR range for the PSC endpoint subnet"
   type        = string
   default     = "10.3.0.0/24"
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "workspace_pe" {
   description = "Name of the workspace PSC endpoint"
   type        = string
   default     = "workspace-pe"
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "relay_pe" {
   description = "Name of the relay PSC endpoint"
   type        = string
   default     = "relay-pe"
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "relay_pe_ip_name" {
   description = "Private IP address name for the relay PSC endpoint"
   type        = string
   default     = ""
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "workspace_pe_ip_name" {
   description = "Private IP address name for the workspace PSC endpoint"
   type        = string
   default     = ""
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "relay_service_attachment" {
   description = "Relay service attachment URI. Regional values: https://docs.gcp.databricks.com/resources/supported-regions.html#psc"
   type        = string
   default     = ""
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "workspace_service_attachment" {
   description = "Workspace service attachment URI. Regional values: https://docs.gcp.databricks.com/resources/supported-regions.html#psc"
   type        = string
   default     = ""
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "use_existing_PSC_EP" {
   description = "Use existing PSC endpoints instead of creating new ones"
   type        = bool
   default     = false
 }
 
+# @group: Private connectivity
+# @visible_when: use_psc == true
 variable "use_existing_databricks_vpc_eps" {
   description = "Use existing Databricks VPC Endpoints for PSC"
   type        = bool
   default     = false
 }
 
+# @group: Private connectivity
+# @visible_when: use_existing_databricks_vpc_eps == true
 variable "existing_databricks_vpc_ep_workspace" {
   description = "ID of the existing Databricks workspace VPC endpoint"
   type        = string
   default     = ""
 }
 
+# @group: Private connectivity
+# @visible_when: use_existing_databricks_vpc_eps == true
 variable "existing_databricks_vpc_ep_relay" {
   description = "ID of the existing Databricks relay VPC endpoint"
   type        = string
@@ -183,24 +214,37 @@ variable "existing_pas_id" {
 # Customer-Managed Encryption Keys (CMEK)
 # =============================================================================
 
+# @group: Encryption
 variable "use_existing_cmek" {
   description = "Use an existing CMEK resource instead of creating new key and keyring"
   type        = bool
   default     = false
 }
 
+# @group: Encryption
+# @visible_when: use_existing_cmek == false
+variable "kms_key_id" {
+  description = "Resource name of an existing Cloud KMS key (projects/.../cryptoKeys/...) to register as the workspace CMEK. Leave empty to create a new key and keyring."
+  type        = string
+  default     = ""
+}
+
+# @group: Encryption
 variable "key_name" {
   description = "Cloud KMS key name for CMEK (used when creating new key)"
   type        = string
   default     = "sra-key"
 }
 
+# @group: Encryption
 variable "keyring_name" {
   description = "Cloud KMS keyring name for CMEK (used when creating new keyring)"
   type        = string
   default     = "sra-keyring"
 }
 
+# @group: Encryption
+# @visible_when: use_existing_cmek == true
 variable "cmek_resource_id" {
   description = "Resource ID of an existing CMEK (required if use_existing_cmek is true)"
   type        = string
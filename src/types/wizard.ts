@@ -31,6 +31,7 @@ export interface DeploymentStatus {
   output: string;
   success: boolean | null;
   can_rollback: boolean;
+  credential_warnings?: string[];
 }
 
 export type AppScreen =